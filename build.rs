@@ -6,7 +6,14 @@ fn main() {
 
     // Make empty file for documentation and tests
     std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/rhai-sci-docs.md").unwrap();
-    std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/rhai-sci-tests.rs").unwrap();
+    std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/doc-examples.rs").unwrap();
+
+    // Without metadata there is no documentation for help() to show
+    std::fs::write(
+        std::env::var("OUT_DIR").unwrap() + "/function-docs.rs",
+        "&[]",
+    )
+    .unwrap();
 }
 
 #[cfg(feature = "metadata")]
@@ -44,25 +51,82 @@ fn main() {
     let mut doc_file =
         std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/rhai-sci-docs.md").unwrap();
 
-    // Make a file for tests
+    // Make a file for tests, which is included by tests/doc_examples.rs
     let mut test_file =
-        std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/rhai-sci-tests.rs").unwrap();
+        std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/doc-examples.rs").unwrap();
+
+    // Make a file for the documentation shown by help(), which is included by src/lib.rs
+    let mut help_file =
+        std::fs::File::create(std::env::var("OUT_DIR").unwrap() + "/function-docs.rs").unwrap();
 
     // Build an engine for doctests
     let mut engine = Engine::new();
 
-    // Add custom functions from Rust
+    // Add custom functions from Rust, along with the source file each module comes from
+    let modules: Vec<(&str, Module)> = vec![
+        ("matrices_and_arrays", exported_module!(matrix_functions)),
+        ("misc", exported_module!(misc_functions)),
+        ("statistics", exported_module!(stats)),
+        ("metrics", exported_module!(metrics_functions)),
+        ("decision", exported_module!(decision_functions)),
+        ("graph", exported_module!(graph_functions)),
+        ("cumulative", exported_module!(cum_functions)),
+        (
+            "integration_and_differentiation",
+            exported_module!(int_and_diff),
+        ),
+        ("assertions", exported_module!(assert_functions)),
+        ("constants", exported_module!(constant_definitions)),
+        ("sets", exported_module!(set_functions)),
+        ("moving", exported_module!(moving_functions)),
+        ("validate", exported_module!(validation_functions)),
+        #[cfg(feature = "timing")]
+        ("profiling", exported_module!(profiling_functions)),
+        ("random", exported_module!(random_functions)),
+        ("logical", exported_module!(logical_functions)),
+        #[cfg(feature = "sampling")]
+        ("sampling", exported_module!(sampling_functions)),
+        #[cfg(feature = "interpolation")]
+        ("interpolation", exported_module!(interpolation_functions)),
+        ("matrix_type", exported_module!(matrix_type_functions)),
+        #[cfg(feature = "geometry")]
+        ("geometry", exported_module!(geometry_functions)),
+        #[cfg(feature = "signal")]
+        ("signal", exported_module!(signal_functions)),
+        #[cfg(feature = "signal")]
+        ("control", exported_module!(control_functions)),
+        #[cfg(feature = "geometry")]
+        ("structures", exported_module!(structures_functions)),
+        #[cfg(feature = "physics")]
+        ("thermo", exported_module!(thermo_functions)),
+        ("elementwise", exported_module!(elementwise_functions)),
+        #[cfg(feature = "sparse")]
+        ("sparse", exported_module!(sparse_functions)),
+        #[cfg(feature = "tensor")]
+        ("tensor", exported_module!(tensor_functions)),
+        ("bignum", exported_module!(bignum_functions)),
+        #[cfg(feature = "quaternion")]
+        ("quaternion", exported_module!(quaternion_functions)),
+        #[cfg(feature = "datetime")]
+        ("datetime", exported_module!(datetime_functions)),
+        #[cfg(feature = "physics")]
+        ("units", exported_module!(units_functions)),
+        #[cfg(feature = "data")]
+        ("table", exported_module!(table_functions)),
+        #[cfg(feature = "data")]
+        ("cleaning", exported_module!(cleaning_functions)),
+        #[cfg(feature = "display")]
+        ("printing", exported_module!(printing_functions)),
+        #[cfg(feature = "display")]
+        ("plotting", exported_module!(plotting_functions)),
+        #[cfg(feature = "serde")]
+        ("serialization", exported_module!(serialization_functions)),
+        ("help", exported_module!(help_functions)),
+    ];
     let mut lib = Module::new();
-    combine_with_exported_module!(&mut lib, "rhai_sci_matrix_function", matrix_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_miscellaneous_functions", misc_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_basic_stats", stats);
-    combine_with_exported_module!(&mut lib, "rhai_sci_cumulative", cum_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_int_and_diff", int_and_diff);
-    combine_with_exported_module!(&mut lib, "rhai_sci_assertions", assert_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_constants", constant_definitions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_sets", set_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_moving", moving_functions);
-    combine_with_exported_module!(&mut lib, "rhai_sci_validate", validation_functions);
+    for (_, module) in &modules {
+        lib.combine_flatten(module.clone());
+    }
     engine.register_global_module(rhai::Shared::new(lib));
 
     // Extract metadata
@@ -77,7 +141,7 @@ fn main() {
 
     // Write functions
     write!(doc_file, "\n# API\n This package provides a large variety of functions to help with scientific computing:\n").expect("Cannot write to {doc_file}");
-    write!(test_file, "#[cfg(test)]\nmod rhai_tests {{\n").expect("Cannot write to {test_file}");
+    let mut test_names = std::collections::HashSet::new();
 
     let mut indented = false;
     for (idx, function) in function_list.iter().enumerate() {
@@ -133,6 +197,61 @@ fn main() {
             }
         }
     }
+    // Find the source file of each function from the metadata of its module alone
+    let mut function_files = HashMap::new();
+    for (file, module) in &modules {
+        let mut flat = Module::new();
+        flat.combine_flatten(module.clone());
+        let mut module_engine = Engine::new_raw();
+        module_engine.register_global_module(rhai::Shared::new(flat));
+        let module_json = module_engine.gen_fn_metadata_to_json(false).unwrap();
+        let module_metadata: Metadata = serde_json::from_str(&module_json).unwrap();
+        for function in module_metadata.functions {
+            function_files.insert(function.fullHash, *file);
+        }
+    }
+
+    // Write the name, signature, source file, and doc comments of each function as a Rust array
+    // for help() and lab_functions()
+    write!(help_file, "&[\n").expect("Cannot write to {help_file}");
+    for function in &function_list {
+        if function.name.starts_with("anon") {
+            continue;
+        }
+        let (name, signature) = if function.name == "$CONSTANTS$" {
+            ("physics".to_string(), "physics".to_string())
+        } else {
+            (
+                function.name.clone(),
+                function
+                    .signature
+                    .replace("Result<", "")
+                    .replace(", Box<EvalAltResult>>", "")
+                    .replace("&mut ", "")
+                    .replace("ImmutableString", "String"),
+            )
+        };
+        let docs = function
+            .docComments
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|comment| comment.lines())
+            .map(|line| {
+                let line = line.trim_start().trim_start_matches("///");
+                line.strip_prefix(' ').unwrap_or(line).to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let file = function_files.get(&function.fullHash).unwrap_or(&"");
+        write!(
+            help_file,
+            "    ({name:?}, {signature:?}, {file:?}, {docs:?}),\n"
+        )
+        .expect("Cannot write to {help_file}");
+    }
+    write!(help_file, "]\n").expect("Cannot write to {help_file}");
+
     let mut indented = false;
     for (idx, function) in function_list.iter().enumerate() {
         let function = function.clone();
@@ -179,30 +298,44 @@ fn main() {
                 }
             }
 
-            // Run doc tests
+            // Write doc examples as tests. Names are built from the signature, with anything that
+            // isn't valid in an identifier (operators, getter prefixes, etc.) replaced
+            let mut test_name = String::from("example_");
+            for c in signature.to_lowercase().chars() {
+                if c.is_ascii_alphanumeric() {
+                    test_name.push(c);
+                } else if !test_name.ends_with('_') {
+                    test_name.push('_');
+                }
+            }
             let code = comments.split("```").collect::<Vec<&str>>();
             for i in (1..code.len()).step_by(2) {
-                let clean_code = code[i]
-                    .replace("javascript", "")
-                    .replace("typescript", "")
-                    .replace("rhai", "");
+                // Drop the info string (the language) on the first line of the code block, and
+                // skip examples marked `no_run`, such as those that need the network. Examples
+                // marked `only_i64` or `only_f64` rely on 64-bit INTs or FLOATs (large literals,
+                // tight tolerances), so they are skipped when the engine uses narrower types
+                let (info, clean_code) = code[i].split_once('\n').unwrap_or((code[i], ""));
+                let skip = info.split(',').map(str::trim).any(|attr| {
+                    attr == "no_run"
+                        || (attr == "only_i64" && cfg!(feature = "only_i32"))
+                        || (attr == "only_f64" && cfg!(feature = "f32_float"))
+                });
+                if skip {
+                    continue;
+                }
+                let mut unique_name = format!("{}_{i}", test_name.trim_end_matches('_'));
+                while !test_names.insert(unique_name.clone()) {
+                    unique_name.push('_');
+                }
                 write!(
                     test_file,
-                    "#[test]\nfn {}_{i}() {{ \n assert!(rhai_sci::eval::<bool>(\"{}\").unwrap()); }}\n",
-                    signature
-                        .replace("(", "_")
-                        .replace(")", "_")
-                        .replace(" ", "_")
-                        .replace(":", "_")
-                        .replace("->", "_")
-                        .replace(",", "_").replace("____", "_").replace("___", "_").replace("__", "_").to_lowercase(),
-                    clean_code.replace("\"", "\\\"")
+                    "#[test]\nfn {unique_name}() {{\n    run_example({:?}, {:?});\n}}\n\n",
+                    signature, clean_code
                 )
                 .expect("Cannot write to {test_file}");
             }
         }
     }
-    write!(test_file, "\n}}").expect("Cannot write to {test_file}");
 }
 
 #[cfg(feature = "metadata")]
@@ -210,6 +343,9 @@ fn main() {
 mod functions {
     include!("src/matrices_and_arrays.rs");
     include!("src/statistics.rs");
+    include!("src/metrics.rs");
+    include!("src/decision.rs");
+    include!("src/graph.rs");
     include!("src/misc.rs");
     include!("src/cumulative.rs");
     include!("src/integration_and_differentiation.rs");
@@ -219,7 +355,82 @@ mod functions {
     include!("src/moving.rs");
     include!("src/validate.rs");
     include!("src/patterns.rs");
+    #[cfg(feature = "timing")]
+    include!("src/profiling.rs");
+    include!("src/random.rs");
+    include!("src/logical.rs");
+    #[cfg(feature = "sampling")]
+    include!("src/sampling.rs");
+    #[cfg(feature = "interpolation")]
+    include!("src/interpolation.rs");
+    include!("src/matrix_type.rs");
+    #[cfg(feature = "geometry")]
+    include!("src/geometry.rs");
+    #[cfg(feature = "signal")]
+    include!("src/signal.rs");
+    #[cfg(feature = "signal")]
+    include!("src/control.rs");
+    #[cfg(feature = "geometry")]
+    include!("src/structures.rs");
+    #[cfg(feature = "physics")]
+    include!("src/thermo.rs");
+    include!("src/elementwise.rs");
+    #[cfg(feature = "sparse")]
+    include!("src/sparse.rs");
+    #[cfg(feature = "tensor")]
+    include!("src/tensor.rs");
+    include!("src/bignum.rs");
+    #[cfg(feature = "quaternion")]
+    include!("src/quaternion.rs");
+    #[cfg(feature = "datetime")]
+    include!("src/datetime.rs");
+    #[cfg(feature = "physics")]
+    include!("src/units.rs");
+    #[cfg(feature = "data")]
+    include!("src/table.rs");
+    #[cfg(feature = "data")]
+    include!("src/cleaning.rs");
+    #[cfg(feature = "display")]
+    include!("src/printing.rs");
+    #[cfg(feature = "display")]
+    include!("src/plotting.rs");
+    #[cfg(feature = "serde")]
+    include!("src/serialization.rs");
+    include!("src/help.rs");
 }
 
 #[cfg(feature = "metadata")]
 pub use functions::*;
+
+/// The error types that the function modules raise
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+mod errors {
+    include!("src/errors.rs");
+}
+#[cfg(feature = "metadata")]
+use errors::{LabError, LabErrorKind};
+
+/// The helpers that split work across threads
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+mod parallel {
+    include!("src/parallel.rs");
+}
+#[cfg(feature = "metadata")]
+use parallel::{parallel_map_chunks, parallel_map_indices, use_parallel, CHUNK_SIZE};
+
+/// The element-wise and reduction kernels
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+mod kernels {
+    include!("src/kernels.rs");
+}
+#[cfg(all(feature = "metadata", feature = "tensor"))]
+use kernels::{fma_floats, reduce_floats, Reduction};
+#[cfg(feature = "metadata")]
+use kernels::{zip_floats, FloatOp, Operand};
+
+/// Stands in for the documentation shown by help(), which this script generates
+#[cfg(feature = "metadata")]
+static FUNCTION_DOCS: &[(&str, &str, &str, &str)] = &[];
@@ -1,8 +1,100 @@
 use rhai::plugin::*;
 
+/// Finds the first place where `lhs` and `rhs` are not equal, allowing numbers to differ by the
+/// larger of `atol` and `rtol` times the larger of their magnitudes. Arrays and maps are compared
+/// element by element and other values exactly. Returns where the difference is, such as `[1][0]`
+/// or `.x[2]`, and a description of it, or `None` if the values are approximately equal.
+fn approx_difference(
+    lhs: &rhai::Dynamic,
+    rhs: &rhai::Dynamic,
+    rtol: rhai::FLOAT,
+    atol: rhai::FLOAT,
+) -> Option<(String, String)> {
+    let number = |x: &rhai::Dynamic| {
+        x.as_float()
+            .ok()
+            .or_else(|| x.as_int().ok().map(|i| i as rhai::FLOAT))
+    };
+    let inside =
+        |location: String, (rest, detail): (String, String)| (format!("{location}{rest}"), detail);
+    if let (Some(a), Some(b)) = (number(lhs), number(rhs)) {
+        let close = a == b || (a - b).abs() <= (rtol * a.abs().max(b.abs())).max(atol);
+        return (!close).then(|| {
+            (
+                String::new(),
+                format!("{a} and {b} differ by {}", (a - b).abs()),
+            )
+        });
+    }
+    if let (Some(a), Some(b)) = (
+        lhs.read_lock::<rhai::Array>(),
+        rhs.read_lock::<rhai::Array>(),
+    ) {
+        if a.len() != b.len() {
+            return Some((
+                String::new(),
+                format!("the lengths {} and {} differ", a.len(), b.len()),
+            ));
+        }
+        return a.iter().zip(b.iter()).enumerate().find_map(|(i, (x, y))| {
+            approx_difference(x, y, rtol, atol).map(|d| inside(format!("[{i}]"), d))
+        });
+    }
+    if let (Some(a), Some(b)) = (lhs.read_lock::<rhai::Map>(), rhs.read_lock::<rhai::Map>()) {
+        if let Some(key) = a
+            .keys()
+            .chain(b.keys())
+            .find(|k| !a.contains_key(*k) || !b.contains_key(*k))
+        {
+            return Some((
+                format!(".{key}"),
+                "the key is missing from one side".to_string(),
+            ));
+        }
+        return a.iter().find_map(|(key, x)| {
+            approx_difference(x, &b[key], rtol, atol).map(|d| inside(format!(".{key}"), d))
+        });
+    }
+    if lhs.type_name() != rhs.type_name() {
+        return Some((
+            String::new(),
+            format!(
+                "{} and {} have different types",
+                lhs.type_name(),
+                rhs.type_name()
+            ),
+        ));
+    }
+    (format!("{lhs:?}") != format!("{rhs:?}"))
+        .then(|| (String::new(), format!("{lhs:?} and {rhs:?} are not equal")))
+}
+
+/// Checks that the tolerances of an approximate comparison are non-negative.
+fn check_tolerances(
+    rtol: rhai::FLOAT,
+    atol: rhai::FLOAT,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    for (name, tol) in [("rtol", rtol), ("atol", atol)] {
+        if tol.is_nan() || tol < 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The tolerance must be non-negative, not {tol}"),
+            )
+            .in_function(function)
+            .with_argument(name)
+            .into());
+        }
+    }
+    Ok(())
+}
+
 #[export_module]
 pub mod assert_functions {
-    use rhai::{Dynamic, EvalAltResult, Position};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
+
+    /// The relative tolerance of `approx_eq` and `assert_approx_eq` when none is given.
+    const DEFAULT_RTOL: FLOAT = 1e-9;
 
     /// Assert that a statement is true and throw an error if it is not.
     /// ```typescript
@@ -13,10 +105,12 @@ pub mod assert_functions {
         if comparison {
             Ok(comparison)
         } else {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The comparison is not true".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                "The comparison is not true",
             )
+            .in_function("assert")
+            .with_argument("comparison")
             .into())
         }
     }
@@ -30,13 +124,14 @@ pub mod assert_functions {
         let lhs_type = lhs.type_name();
         let rhs_type = rhs.type_name();
         if lhs_type != rhs_type {
-            return Err(EvalAltResult::ErrorArithmetic(
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
                 format!(
                     "The left-hand side ({}) and right-hand side ({}) do not have the same type",
                     lhs_type, rhs_type
                 ),
-                Position::NONE,
             )
+            .in_function("assert_eq")
             .into());
         }
 
@@ -47,10 +142,11 @@ pub mod assert_functions {
         } else {
             println!("LHS: {:?}", lhs);
             println!("RHS: {:?}", rhs);
-            Err(EvalAltResult::ErrorArithmetic(
-                "The left-hand side and right-hand side are not equal".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                "The left-hand side and right-hand side are not equal",
             )
+            .in_function("assert_eq")
             .into())
         }
     }
@@ -64,13 +160,14 @@ pub mod assert_functions {
         let lhs_type = lhs.type_name();
         let rhs_type = rhs.type_name();
         if lhs_type != rhs_type {
-            return Err(EvalAltResult::ErrorArithmetic(
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
                 format!(
                     "The left-hand side ({}) and right-hand side ({}) do not have the same type",
                     lhs_type, rhs_type
                 ),
-                Position::NONE,
             )
+            .in_function("assert_ne")
             .into());
         }
 
@@ -81,10 +178,213 @@ pub mod assert_functions {
         } else {
             println!("LHS: {:?}", lhs);
             println!("RHS: {:?}", rhs);
-            Err(EvalAltResult::ErrorArithmetic(
-                "The left-hand side and right-hand side are equal".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                "The left-hand side and right-hand side are equal",
+            )
+            .in_function("assert_ne")
+            .into())
+        }
+    }
+
+    /// Returns whether two values are equal to within a relative tolerance of `1e-9`. Numbers are
+    /// compared as floats, so `1` and `1.0` are approximately equal, and arrays (including
+    /// matrices) and maps are compared element by element. Other values must be exactly equal.
+    /// Use `approx_eq(a, b, rtol, atol)` to compare with zero, which no relative tolerance allows.
+    /// ```typescript
+    /// assert(approx_eq(0.1 + 0.2, 0.3));
+    /// assert(!approx_eq(1.0, 1.001));
+    /// ```
+    /// ```typescript
+    /// assert(approx_eq([[1, 2], [3, 4]], [[1.0, 2.0], [3.0, 4.0 + 1e-12]]));
+    /// assert(!approx_eq([1.0, 2.0], [1.0, 2.0, 3.0]));
+    /// ```
+    #[rhai_fn(name = "approx_eq")]
+    pub fn approx_eq(a: Dynamic, b: Dynamic) -> bool {
+        super::approx_difference(&a, &b, DEFAULT_RTOL, 0.0).is_none()
+    }
+
+    /// Returns whether two values are equal to within a tolerance: numbers `x` and `y` are close
+    /// enough if `abs(x - y) <= max(rtol * max(abs(x), abs(y)), atol)`. Arrays (including
+    /// matrices) and maps are compared element by element, and other values must be exactly
+    /// equal. NaN is not approximately equal to anything.
+    /// ```typescript
+    /// assert(approx_eq(1.0, 1.001, 1e-2, 0.0));
+    /// assert(approx_eq(1e-17, 0.0, 0.0, 1e-12));
+    /// assert(!approx_eq(1e-17, 0.0, 1e-9, 0.0));
+    /// ```
+    /// ```typescript
+    /// let r = sum([0.1, 0.2, 0.3]);
+    /// assert(approx_eq(#{total: r, parts: [0.1, 0.2]}, #{total: 0.6, parts: [0.1, 0.2]}, 1e-12, 0.0));
+    /// ```
+    #[rhai_fn(name = "approx_eq", return_raw)]
+    pub fn approx_eq_with_tolerances(
+        a: Dynamic,
+        b: Dynamic,
+        rtol: FLOAT,
+        atol: FLOAT,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        super::check_tolerances(rtol, atol, "approx_eq")?;
+        Ok(super::approx_difference(&a, &b, rtol, atol).is_none())
+    }
+
+    /// Assert that two arguments are equal to within a relative tolerance of `1e-9`, as checked
+    /// by `approx_eq`, and throw an error saying where they differ if they are not.
+    /// ```typescript
+    /// assert_approx_eq(0.1 + 0.2, 0.3);
+    /// assert_approx_eq(inv([[2.0, 0.0], [0.0, 4.0]]), [[0.5, 0.0], [0.0, 0.25]]);
+    /// ```
+    /// ```typescript
+    /// let result = "";
+    /// try {
+    ///     assert_approx_eq([[1.0, 2.0], [3.0, 4.0]], [[1.0, 2.0], [3.0, 4.5]]);
+    /// } catch (err) {
+    ///     result = err.message;
+    /// }
+    /// assert_eq(result, "The left-hand side and right-hand side are not approximately equal at [1][1]: 4 and 4.5 differ by 0.5");
+    /// ```
+    #[rhai_fn(name = "assert_approx_eq", return_raw)]
+    pub fn assert_approx_eq(lhs: Dynamic, rhs: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        assert_approx_eq_with_tolerances(lhs, rhs, DEFAULT_RTOL, 0.0)
+    }
+
+    /// Assert that two arguments are equal to within the relative tolerance `rtol` or the
+    /// absolute tolerance `atol`, as checked by `approx_eq`, and throw an error saying where they
+    /// differ if they are not.
+    /// ```typescript
+    /// let x = linspace(0.0, 1.0, 11);
+    /// assert_approx_eq(sum(x), 5.5, 0.0, 1e-12);
+    /// ```
+    #[rhai_fn(name = "assert_approx_eq", return_raw)]
+    pub fn assert_approx_eq_with_tolerances(
+        lhs: Dynamic,
+        rhs: Dynamic,
+        rtol: FLOAT,
+        atol: FLOAT,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        super::check_tolerances(rtol, atol, "assert_approx_eq")?;
+        match super::approx_difference(&lhs, &rhs, rtol, atol) {
+            None => Ok(true),
+            Some((location, detail)) => {
+                let location = if location.is_empty() {
+                    String::new()
+                } else {
+                    format!(" at {location}")
+                };
+                Err(crate::LabError::new(
+                    crate::LabErrorKind::AssertionFailed,
+                    format!(
+                        "The left-hand side and right-hand side are not approximately equal{location}: {detail}"
+                    ),
+                )
+                .in_function("assert_approx_eq")
+                .into())
+            }
+        }
+    }
+
+    /// Assert that a matrix has `rows` rows and `cols` columns, and throw an error giving its
+    /// actual size if it does not.
+    /// ```typescript
+    /// assert_size(ones(2, 3), 2, 3);
+    /// ```
+    /// ```typescript
+    /// let result = "";
+    /// try {
+    ///     assert_size(transpose(ones(2, 3)), 2, 3);
+    /// } catch (err) {
+    ///     result = err.message;
+    /// }
+    /// assert_eq(result, "Expected a matrix of size [2, 3], but its size is [3, 2]");
+    /// ```
+    #[rhai_fn(name = "assert_size", return_raw, pure)]
+    pub fn assert_size(m: &mut Array, rows: INT, cols: INT) -> Result<bool, Box<EvalAltResult>> {
+        assert_size_of_shape(m, vec![Dynamic::from_int(rows), Dynamic::from_int(cols)])
+    }
+
+    /// Assert that an array has the given size along each dimension, as returned by `size`, and
+    /// throw an error giving its actual size if it does not.
+    /// ```typescript
+    /// assert_size(zeros([2, 3, 4]), [2, 3, 4]);
+    /// assert_size([1, 2, 3], [3]);
+    /// ```
+    #[rhai_fn(name = "assert_size", return_raw, pure)]
+    pub fn assert_size_of_shape(m: &mut Array, shape: Array) -> Result<bool, Box<EvalAltResult>> {
+        let size = crate::matrix_functions::matrix_size_by_reference(m);
+        if format!("{size:?}") == format!("{shape:?}") {
+            Ok(true)
+        } else {
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                format!("Expected a matrix of size {shape:?}, but its size is {size:?}"),
+            )
+            .in_function("assert_size")
+            .with_argument("m")
+            .into())
+        }
+    }
+
+    /// Assert that two matrices (or arrays) have the same size, and throw an error giving both
+    /// sizes if they do not.
+    /// ```typescript
+    /// assert_same_size(ones(2, 3), zeros(2, 3));
+    /// ```
+    /// ```typescript
+    /// let result = "";
+    /// try {
+    ///     assert_same_size([[1, 2], [3, 4]], [1, 2, 3, 4]);
+    /// } catch (err) {
+    ///     result = err.message;
+    /// }
+    /// assert_eq(result, "The left-hand side has size [2, 2] but the right-hand side has size [4]");
+    /// ```
+    #[rhai_fn(name = "assert_same_size", return_raw, pure)]
+    pub fn assert_same_size(a: &mut Array, mut b: Array) -> Result<bool, Box<EvalAltResult>> {
+        let lhs = crate::matrix_functions::matrix_size_by_reference(a);
+        let rhs = crate::matrix_functions::matrix_size_by_reference(&mut b);
+        if format!("{lhs:?}") == format!("{rhs:?}") {
+            Ok(true)
+        } else {
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                format!(
+                    "The left-hand side has size {lhs:?} but the right-hand side has size {rhs:?}"
+                ),
+            )
+            .in_function("assert_same_size")
+            .into())
+        }
+    }
+
+    /// Assert that an array has `n` elements, and throw an error giving its actual length if it
+    /// does not. Only the outermost level counts, so a matrix has one element per row.
+    /// ```typescript
+    /// assert_len([1, 2, 3], 3);
+    /// assert_len(ones(4, 2), 4);
+    /// ```
+    /// ```typescript
+    /// let result = "";
+    /// try {
+    ///     assert_len([1, 2], 3);
+    /// } catch (err) {
+    ///     result = err.message;
+    /// }
+    /// assert_eq(result, "Expected an array of length 3, but its length is 2");
+    /// ```
+    #[rhai_fn(name = "assert_len", return_raw, pure)]
+    pub fn assert_len(arr: &mut Array, n: INT) -> Result<bool, Box<EvalAltResult>> {
+        if arr.len() as INT == n {
+            Ok(true)
+        } else {
+            Err(crate::LabError::new(
+                crate::LabErrorKind::AssertionFailed,
+                format!(
+                    "Expected an array of length {n}, but its length is {}",
+                    arr.len()
+                ),
             )
+            .in_function("assert_len")
+            .with_argument("arr")
             .into())
         }
     }
@@ -0,0 +1,729 @@
+use rhai::plugin::*;
+
+/// Builds the error for a failed big number operation.
+#[cfg(feature = "bignum")]
+fn bignum_error(msg: impl Into<String>) -> Box<rhai::EvalAltResult> {
+    crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg.into()).into()
+}
+
+/// Fails if `b` is zero, so that division doesn't panic.
+#[cfg(feature = "bignum")]
+fn check_divisor<T: num_traits::Zero>(b: &T) -> Result<(), Box<rhai::EvalAltResult>> {
+    if b.is_zero() {
+        Err(bignum_error("Division by zero"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Builds a rational number, failing if the denominator is zero.
+#[cfg(feature = "bignum")]
+fn make_rational(
+    numer: num_bigint::BigInt,
+    denom: num_bigint::BigInt,
+) -> Result<num_rational::BigRational, Box<rhai::EvalAltResult>> {
+    check_divisor(&denom)?;
+    Ok(num_rational::BigRational::new(numer, denom))
+}
+
+#[export_module]
+pub mod bignum_functions {
+    #[cfg(feature = "bignum")]
+    use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+    #[cfg(feature = "bignum")]
+    use rhai::{EvalAltResult, ImmutableString, FLOAT, INT};
+
+    /// An integer of arbitrary size, created with `bigint`.
+    #[cfg(feature = "bignum")]
+    pub type BigInt = num_bigint::BigInt;
+
+    /// An exact fraction of two arbitrary-size integers, created with `rational`.
+    #[cfg(feature = "bignum")]
+    pub type Rational = num_rational::BigRational;
+
+    /// Converts an integer into a `BigInt`.
+    /// ```typescript
+    /// let x = bigint(42);
+    /// assert_eq(to_string(x * x), "1764");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "bigint")]
+    pub fn bigint(x: INT) -> BigInt {
+        BigInt::from(x)
+    }
+
+    /// Parses a string of decimal digits, with an optional sign, into a `BigInt`.
+    /// ```typescript
+    /// let x = bigint("-123456789012345678901234567890");
+    /// assert_eq(to_string(x), "-123456789012345678901234567890");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "bigint", return_raw)]
+    pub fn bigint_from_string(s: ImmutableString) -> Result<BigInt, Box<EvalAltResult>> {
+        s.trim()
+            .parse::<BigInt>()
+            .map_err(|_| super::bignum_error(format!("Cannot parse '{s}' as an integer")))
+    }
+
+    /// Converts a FLOAT into a `BigInt`, rounding towards zero.
+    /// ```typescript
+    /// let x = bigint(1e20);
+    /// assert_eq(to_string(x), "100000000000000000000");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "bigint", return_raw)]
+    pub fn bigint_from_float(x: FLOAT) -> Result<BigInt, Box<EvalAltResult>> {
+        num_traits::FromPrimitive::from_f64(x.trunc() as f64)
+            .ok_or_else(|| super::bignum_error(format!("Cannot convert {x} to an integer")))
+    }
+
+    /// Creates a `Rational` from a numerator and denominator, reduced to lowest terms.
+    /// ```typescript
+    /// let r = rational(6, -8);
+    /// assert_eq(to_string(r), "-3/4");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "rational", return_raw)]
+    pub fn rational(numer: INT, denom: INT) -> Result<Rational, Box<EvalAltResult>> {
+        super::make_rational(BigInt::from(numer), BigInt::from(denom))
+    }
+
+    /// Creates a `Rational` from a `BigInt` numerator and denominator, reduced to lowest terms.
+    /// ```typescript
+    /// let r = rational(bigint(10).pow(30), bigint(10).pow(28));
+    /// assert_eq(to_string(r), "100");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "rational", return_raw)]
+    pub fn rational_big(numer: BigInt, denom: BigInt) -> Result<Rational, Box<EvalAltResult>> {
+        super::make_rational(numer, denom)
+    }
+
+    /// Converts an integer into a `Rational`.
+    /// ```typescript
+    /// assert_eq(to_string(rational(5)), "5");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "rational")]
+    pub fn rational_from_int(x: INT) -> Rational {
+        Rational::from_integer(BigInt::from(x))
+    }
+
+    /// Converts a FLOAT into the `Rational` that it represents exactly.
+    /// ```typescript
+    /// assert_eq(to_string(rational(0.375)), "3/8");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "rational", return_raw)]
+    pub fn rational_from_float(x: FLOAT) -> Result<Rational, Box<EvalAltResult>> {
+        Rational::from_float(x)
+            .ok_or_else(|| super::bignum_error(format!("Cannot convert {x} to a rational")))
+    }
+
+    /// Parses a string such as `"3/4"` or `"-12"` into a `Rational`.
+    /// ```typescript
+    /// let r = rational("10/4");
+    /// assert_eq(to_string(r), "5/2");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "rational", return_raw)]
+    pub fn rational_from_string(s: ImmutableString) -> Result<Rational, Box<EvalAltResult>> {
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<BigInt>()
+                .map_err(|_| super::bignum_error(format!("Cannot parse '{s}' as a rational")))
+        };
+        match s.split_once('/') {
+            Some((numer, denom)) => super::make_rational(parse(numer)?, parse(denom)?),
+            None => Ok(Rational::from_integer(parse(&s)?)),
+        }
+    }
+
+    /// Returns the numerator of a `Rational` in lowest terms.
+    /// ```typescript
+    /// assert_eq(to_string(rational(6, 4).numer), "3");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(get = "numer", pure)]
+    pub fn numer(r: &mut Rational) -> BigInt {
+        r.numer().clone()
+    }
+
+    /// Returns the denominator of a `Rational` in lowest terms.
+    /// ```typescript
+    /// assert_eq(to_string(rational(6, 4).denom), "2");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(get = "denom", pure)]
+    pub fn denom(r: &mut Rational) -> BigInt {
+        r.denom().clone()
+    }
+
+    /// Converts a `BigInt` back into an integer, failing if it is too large to fit.
+    /// ```typescript
+    /// assert_eq(to_int(bigint(2).pow(40)), 1099511627776);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "to_int", return_raw, pure)]
+    pub fn to_int(x: &mut BigInt) -> Result<INT, Box<EvalAltResult>> {
+        INT::try_from(&*x).map_err(|_| super::bignum_error(format!("{x} is too large for an INT")))
+    }
+
+    /// Converts a `BigInt` into the nearest FLOAT.
+    /// ```typescript
+    /// assert_eq(to_float(bigint(3)), 3.0);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "to_float", pure)]
+    pub fn to_float(x: &mut BigInt) -> FLOAT {
+        x.to_f64().unwrap_or(f64::NAN) as FLOAT
+    }
+
+    /// Converts a `Rational` into the nearest FLOAT.
+    /// ```typescript
+    /// assert_eq(to_float(rational(1, 4)), 0.25);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "to_float", pure)]
+    pub fn rational_to_float(r: &mut Rational) -> FLOAT {
+        r.to_f64().unwrap_or(f64::NAN) as FLOAT
+    }
+
+    /// Returns `n!` exactly.
+    /// ```typescript
+    /// let f = factorial(bigint(25));
+    /// assert_eq(to_string(f), "15511210043330985984000000");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "factorial", return_raw)]
+    pub fn factorial(n: BigInt) -> Result<BigInt, Box<EvalAltResult>> {
+        let n = n
+            .to_u64()
+            .ok_or_else(|| super::bignum_error(format!("Cannot take the factorial of {n}")))?;
+        Ok((1..=n).fold(BigInt::one(), |acc, k| acc * k))
+    }
+
+    /// Returns the binomial coefficient "`n` choose `k`" exactly.
+    /// ```typescript
+    /// let c = nchoosek(bigint(100), 50);
+    /// assert_eq(to_string(c), "100891344545564193334812497256");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "nchoosek", return_raw)]
+    pub fn nchoosek(n: BigInt, k: INT) -> Result<BigInt, Box<EvalAltResult>> {
+        if n.is_negative() || k < 0 {
+            return Err(super::bignum_error(format!(
+                "Cannot choose {k} from {n}, both must be non-negative"
+            )));
+        }
+        let k = BigInt::from(k);
+        if k > n {
+            return Ok(BigInt::zero());
+        }
+        let k = std::cmp::min(k.clone(), &n - &k)
+            .to_u64()
+            .unwrap_or(u64::MAX);
+        // Each partial product is itself a binomial coefficient, so the division is exact
+        let mut c = BigInt::one();
+        for i in 0..k {
+            c = c * (&n - i) / (i + 1);
+        }
+        Ok(c)
+    }
+
+    /// Returns the greatest common divisor of two `BigInt`s.
+    /// ```typescript
+    /// assert_eq(to_string(gcd(bigint(84), bigint(-36))), "12");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "gcd")]
+    pub fn gcd(a: BigInt, b: BigInt) -> BigInt {
+        num_integer::Integer::gcd(&a, &b)
+    }
+
+    /// Returns the least common multiple of two `BigInt`s.
+    /// ```typescript
+    /// assert_eq(to_string(lcm(bigint(4), bigint(6))), "12");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "lcm")]
+    pub fn lcm(a: BigInt, b: BigInt) -> BigInt {
+        num_integer::Integer::lcm(&a, &b)
+    }
+
+    /// Returns the absolute value of a `BigInt`.
+    /// ```typescript
+    /// assert_eq(to_string(abs(bigint(-7))), "7");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "abs")]
+    pub fn abs(x: BigInt) -> BigInt {
+        x.abs()
+    }
+
+    /// Returns the absolute value of a `Rational`.
+    /// ```typescript
+    /// assert_eq(to_string(abs(rational(-1, 3))), "1/3");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "abs")]
+    pub fn rational_abs(x: Rational) -> Rational {
+        x.abs()
+    }
+
+    /// Raises a `BigInt` to a non-negative integer power.
+    /// ```typescript
+    /// let x = bigint(2).pow(100);
+    /// assert_eq(to_string(x), "1267650600228229401496703205376");
+    /// assert_eq(to_string(bigint(3) ** 4), "81");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "pow", name = "**", return_raw)]
+    pub fn pow(x: BigInt, n: INT) -> Result<BigInt, Box<EvalAltResult>> {
+        let n = u32::try_from(n)
+            .map_err(|_| super::bignum_error(format!("Cannot raise a BigInt to the power {n}")))?;
+        Ok(Pow::pow(x, n))
+    }
+
+    /// Raises a `Rational` to an integer power, which can be negative.
+    /// ```typescript
+    /// assert_eq(to_string(rational(2, 3) ** -2), "9/4");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "pow", name = "**", return_raw)]
+    pub fn rational_pow(x: Rational, n: INT) -> Result<Rational, Box<EvalAltResult>> {
+        if n < 0 {
+            super::check_divisor(&x)?;
+        }
+        let n = i32::try_from(n).map_err(|_| {
+            super::bignum_error(format!("Cannot raise a Rational to the power {n}"))
+        })?;
+        Ok(Pow::pow(x, n))
+    }
+
+    /// Adds two `BigInt`s.
+    /// ```typescript
+    /// let x = bigint(9223372036854775807) + 1;
+    /// assert_eq(to_string(x), "9223372036854775808");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn add(a: BigInt, b: BigInt) -> BigInt {
+        a + b
+    }
+
+    /// Adds an integer to a `BigInt`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn add_int(a: BigInt, b: INT) -> BigInt {
+        a + b
+    }
+
+    /// Adds a `BigInt` to an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn int_add(a: INT, b: BigInt) -> BigInt {
+        b + a
+    }
+
+    /// Subtracts one `BigInt` from another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn subtract(a: BigInt, b: BigInt) -> BigInt {
+        a - b
+    }
+
+    /// Subtracts an integer from a `BigInt`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn subtract_int(a: BigInt, b: INT) -> BigInt {
+        a - b
+    }
+
+    /// Subtracts a `BigInt` from an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn int_subtract(a: INT, b: BigInt) -> BigInt {
+        BigInt::from(a) - b
+    }
+
+    /// Negates a `BigInt`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn negate(a: BigInt) -> BigInt {
+        -a
+    }
+
+    /// Multiplies two `BigInt`s.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn multiply(a: BigInt, b: BigInt) -> BigInt {
+        a * b
+    }
+
+    /// Multiplies a `BigInt` by an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn multiply_int(a: BigInt, b: INT) -> BigInt {
+        a * b
+    }
+
+    /// Multiplies an integer by a `BigInt`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn int_multiply(a: INT, b: BigInt) -> BigInt {
+        b * a
+    }
+
+    /// Divides one `BigInt` by another, rounding towards zero like integer division.
+    /// ```typescript
+    /// assert_eq(to_string(bigint(-7) / bigint(2)), "-3");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide(a: BigInt, b: BigInt) -> Result<BigInt, Box<EvalAltResult>> {
+        super::check_divisor(&b)?;
+        Ok(a / b)
+    }
+
+    /// Divides a `BigInt` by an integer, rounding towards zero.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_int(a: BigInt, b: INT) -> Result<BigInt, Box<EvalAltResult>> {
+        divide(a, BigInt::from(b))
+    }
+
+    /// Divides an integer by a `BigInt`, rounding towards zero.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn int_divide(a: INT, b: BigInt) -> Result<BigInt, Box<EvalAltResult>> {
+        divide(BigInt::from(a), b)
+    }
+
+    /// Returns the remainder of dividing one `BigInt` by another, with the sign of the dividend.
+    /// ```typescript
+    /// assert_eq(to_string(bigint(2).pow(70) % 1000), "424");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "%", return_raw)]
+    pub fn remainder(a: BigInt, b: BigInt) -> Result<BigInt, Box<EvalAltResult>> {
+        super::check_divisor(&b)?;
+        Ok(a % b)
+    }
+
+    /// Returns the remainder of dividing a `BigInt` by an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "%", return_raw)]
+    pub fn remainder_int(a: BigInt, b: INT) -> Result<BigInt, Box<EvalAltResult>> {
+        remainder(a, BigInt::from(b))
+    }
+
+    /// Tests whether two `BigInt`s are equal.
+    /// ```typescript
+    /// assert(bigint("100") == bigint(100));
+    /// assert(bigint(100) == 100);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "==")]
+    pub fn eq(a: BigInt, b: BigInt) -> bool {
+        a == b
+    }
+
+    /// Tests whether two `BigInt`s differ.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "!=")]
+    pub fn ne(a: BigInt, b: BigInt) -> bool {
+        a != b
+    }
+
+    /// Tests whether one `BigInt` is less than another.
+    /// ```typescript
+    /// assert(bigint(2).pow(64) > bigint(2).pow(63));
+    /// assert(bigint(5) < 6);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<")]
+    pub fn lt(a: BigInt, b: BigInt) -> bool {
+        a < b
+    }
+
+    /// Tests whether one `BigInt` is at most another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<=")]
+    pub fn le(a: BigInt, b: BigInt) -> bool {
+        a <= b
+    }
+
+    /// Tests whether one `BigInt` is greater than another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">")]
+    pub fn gt(a: BigInt, b: BigInt) -> bool {
+        a > b
+    }
+
+    /// Tests whether one `BigInt` is at least another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">=")]
+    pub fn ge(a: BigInt, b: BigInt) -> bool {
+        a >= b
+    }
+
+    /// Tests whether a `BigInt` equals an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "==")]
+    pub fn eq_int(a: BigInt, b: INT) -> bool {
+        a == BigInt::from(b)
+    }
+
+    /// Tests whether a `BigInt` differs from an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "!=")]
+    pub fn ne_int(a: BigInt, b: INT) -> bool {
+        a != BigInt::from(b)
+    }
+
+    /// Tests whether a `BigInt` is less than an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<")]
+    pub fn lt_int(a: BigInt, b: INT) -> bool {
+        a < BigInt::from(b)
+    }
+
+    /// Tests whether a `BigInt` is at most an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<=")]
+    pub fn le_int(a: BigInt, b: INT) -> bool {
+        a <= BigInt::from(b)
+    }
+
+    /// Tests whether a `BigInt` is greater than an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">")]
+    pub fn gt_int(a: BigInt, b: INT) -> bool {
+        a > BigInt::from(b)
+    }
+
+    /// Tests whether a `BigInt` is at least an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">=")]
+    pub fn ge_int(a: BigInt, b: INT) -> bool {
+        a >= BigInt::from(b)
+    }
+
+    /// Adds two `Rational`s.
+    /// ```typescript
+    /// let r = rational(1, 3) + rational(1, 6);
+    /// assert_eq(to_string(r), "1/2");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn rational_add(a: Rational, b: Rational) -> Rational {
+        a + b
+    }
+
+    /// Adds an integer to a `Rational`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn rational_add_int(a: Rational, b: INT) -> Rational {
+        a + BigInt::from(b)
+    }
+
+    /// Adds a `Rational` to an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "+")]
+    pub fn int_add_rational(a: INT, b: Rational) -> Rational {
+        b + BigInt::from(a)
+    }
+
+    /// Subtracts one `Rational` from another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn rational_subtract(a: Rational, b: Rational) -> Rational {
+        a - b
+    }
+
+    /// Subtracts an integer from a `Rational`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn rational_subtract_int(a: Rational, b: INT) -> Rational {
+        a - BigInt::from(b)
+    }
+
+    /// Subtracts a `Rational` from an integer.
+    /// ```typescript
+    /// assert_eq(to_string(1 - rational(1, 4)), "3/4");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn int_subtract_rational(a: INT, b: Rational) -> Rational {
+        Rational::from_integer(BigInt::from(a)) - b
+    }
+
+    /// Negates a `Rational`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "-")]
+    pub fn rational_negate(a: Rational) -> Rational {
+        -a
+    }
+
+    /// Multiplies two `Rational`s.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn rational_multiply(a: Rational, b: Rational) -> Rational {
+        a * b
+    }
+
+    /// Multiplies a `Rational` by an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn rational_multiply_int(a: Rational, b: INT) -> Rational {
+        a * BigInt::from(b)
+    }
+
+    /// Multiplies an integer by a `Rational`.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "*")]
+    pub fn int_multiply_rational(a: INT, b: Rational) -> Rational {
+        b * BigInt::from(a)
+    }
+
+    /// Divides one `Rational` by another exactly.
+    /// ```typescript
+    /// // The chance of being dealt a royal flush
+    /// let p = rational(4, 1) / rational(nchoosek(bigint(52), 5), bigint(1));
+    /// assert_eq(to_string(p), "1/649740");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn rational_divide(a: Rational, b: Rational) -> Result<Rational, Box<EvalAltResult>> {
+        super::check_divisor(&b)?;
+        Ok(a / b)
+    }
+
+    /// Divides a `Rational` by an integer exactly.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn rational_divide_int(a: Rational, b: INT) -> Result<Rational, Box<EvalAltResult>> {
+        rational_divide(a, Rational::from_integer(BigInt::from(b)))
+    }
+
+    /// Divides an integer by a `Rational` exactly.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn int_divide_rational(a: INT, b: Rational) -> Result<Rational, Box<EvalAltResult>> {
+        rational_divide(Rational::from_integer(BigInt::from(a)), b)
+    }
+
+    /// Tests whether two `Rational`s are equal.
+    /// ```typescript
+    /// assert(rational(2, 4) == rational(1, 2));
+    /// assert(rational(4, 2) == 2);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "==")]
+    pub fn rational_eq(a: Rational, b: Rational) -> bool {
+        a == b
+    }
+
+    /// Tests whether two `Rational`s differ.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "!=")]
+    pub fn rational_ne(a: Rational, b: Rational) -> bool {
+        a != b
+    }
+
+    /// Tests whether one `Rational` is less than another.
+    /// ```typescript
+    /// assert(rational(1, 3) < rational(1, 2));
+    /// assert(rational(7, 2) > 3);
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<")]
+    pub fn rational_lt(a: Rational, b: Rational) -> bool {
+        a < b
+    }
+
+    /// Tests whether one `Rational` is at most another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<=")]
+    pub fn rational_le(a: Rational, b: Rational) -> bool {
+        a <= b
+    }
+
+    /// Tests whether one `Rational` is greater than another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">")]
+    pub fn rational_gt(a: Rational, b: Rational) -> bool {
+        a > b
+    }
+
+    /// Tests whether one `Rational` is at least another.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">=")]
+    pub fn rational_ge(a: Rational, b: Rational) -> bool {
+        a >= b
+    }
+
+    /// Tests whether a `Rational` equals an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "==")]
+    pub fn rational_eq_int(a: Rational, b: INT) -> bool {
+        a == Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Tests whether a `Rational` differs from an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "!=")]
+    pub fn rational_ne_int(a: Rational, b: INT) -> bool {
+        a != Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Tests whether a `Rational` is less than an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<")]
+    pub fn rational_lt_int(a: Rational, b: INT) -> bool {
+        a < Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Tests whether a `Rational` is at most an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "<=")]
+    pub fn rational_le_int(a: Rational, b: INT) -> bool {
+        a <= Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Tests whether a `Rational` is greater than an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">")]
+    pub fn rational_gt_int(a: Rational, b: INT) -> bool {
+        a > Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Tests whether a `Rational` is at least an integer.
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = ">=")]
+    pub fn rational_ge_int(a: Rational, b: INT) -> bool {
+        a >= Rational::from_integer(BigInt::from(b))
+    }
+
+    /// Formats a `BigInt` as decimal digits.
+    /// ```typescript
+    /// assert_eq(to_string(bigint(-12)), "-12");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(x: &mut BigInt) -> ImmutableString {
+        x.to_string().into()
+    }
+
+    /// Formats a `Rational` as `numerator/denominator`, or just the numerator if it is a whole
+    /// number.
+    /// ```typescript
+    /// assert_eq(to_string(rational(3, 9)), "1/3");
+    /// ```
+    #[cfg(feature = "bignum")]
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn rational_to_string(x: &mut Rational) -> ImmutableString {
+        x.to_string().into()
+    }
+}
@@ -0,0 +1,67 @@
+//! Runs Rhai scripts with rhai-sci from the command line, so that they can be used in shell
+//! pipelines:
+//!
+//! ```text
+//! rhai-lab run script.rhai < data.csv > result.csv
+//! ```
+//!
+//! Delimited text piped to standard input is available to the script as `input` (a `Table` if it
+//! has a header row, and a matrix otherwise), and the value of the script is written to standard
+//! output as delimited text. See [`rhai_sci::run_with_csv`].
+
+use std::io::{IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+/// How to call the tool.
+const USAGE: &str = "Usage: rhai-lab run <script.rhai>";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let path = match args.as_slice() {
+        [mode, path] if mode == "run" => path,
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let script = match std::fs::read_to_string(path) {
+        Ok(script) => script,
+        Err(err) => {
+            eprintln!("Cannot read script {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Only read standard input if something is piped to it
+    let mut input = String::new();
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        if let Err(err) = stdin.lock().read_to_string(&mut input) {
+            eprintln!("Cannot read standard input: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let input = Some(input.as_str()).filter(|text| !text.trim().is_empty());
+
+    match rhai_sci::run_with_csv(&script, input) {
+        Ok(output) => {
+            if let Err(err) = std::io::stdout().lock().write_all(output.as_bytes()) {
+                eprintln!("Cannot write standard output: {err}");
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            match rhai_sci::LabError::from_eval(&err) {
+                Some(err) => eprintln!("Error in {path}: {err}"),
+                None => eprintln!("Error in {path}: {err}"),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
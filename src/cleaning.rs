@@ -0,0 +1,748 @@
+use rhai::plugin::*;
+
+/// Whether a value is missing: `NaN` or `()`.
+fn is_missing_value(x: &rhai::Dynamic) -> bool {
+    x.is_unit() || x.as_float().is_ok_and(|f| f.is_nan())
+}
+
+/// Reads a vector whose elements are numbers or missing values, giving `None` for missing ones.
+fn missing_or_numbers(
+    v: &rhai::Array,
+) -> Result<Vec<Option<rhai::FLOAT>>, Box<rhai::EvalAltResult>> {
+    v.iter()
+        .map(|x| match (x.as_float(), x.as_int()) {
+            (Ok(f), _) if f.is_nan() => Ok(None),
+            (Ok(f), _) => Ok(Some(f)),
+            (_, Ok(i)) => Ok(Some(i as rhai::FLOAT)),
+            _ if x.is_unit() => Ok(None),
+            _ => Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!(
+                    "The elements of the input array must be numbers or missing values, not {}",
+                    x.type_name()
+                ),
+            )
+            .into()),
+        })
+        .collect()
+}
+
+/// Splits a vector or matrix into the 1-D lines that an operation along dimension `dim` works
+/// on: the vector itself, the columns of a matrix for `dim` = 1, or its rows for `dim` = 2.
+/// Returns the lines and whether they are columns.
+fn lines_along(
+    arr: &mut rhai::Array,
+    dim: rhai::INT,
+) -> Result<(Vec<rhai::Array>, bool), Box<rhai::EvalAltResult>> {
+    let is_matrix = arr.first().is_some_and(|row| row.is_array());
+    match (is_matrix, dim) {
+        (false, 1 | 2) => Ok((vec![arr.clone()], false)),
+        (true, 1) => Ok((
+            crate::matrix_functions::transpose(arr)?
+                .into_iter()
+                .map(|col| col.into_array().unwrap())
+                .collect(),
+            true,
+        )),
+        (true, 2) => Ok((
+            arr.iter()
+                .map(|row| row.clone().into_array().unwrap_or_default())
+                .collect(),
+            false,
+        )),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The dimension must be 1 (down the columns) or 2 (along the rows), not {dim}"),
+        )
+        .into()),
+    }
+}
+
+/// Reassembles lines made by `lines_along` into the shape of the original array.
+fn unlines(
+    arr: &rhai::Array,
+    mut lines: Vec<rhai::Array>,
+    columns: bool,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let is_matrix = arr.first().is_some_and(|row| row.is_array());
+    if !is_matrix {
+        return Ok(lines.pop().unwrap_or_default());
+    }
+    let mut matrix = lines.into_iter().map(rhai::Dynamic::from_array).collect();
+    if columns {
+        matrix = crate::matrix_functions::transpose(&mut matrix)?;
+    }
+    Ok(matrix)
+}
+
+/// Fills the missing values of a vector with the method described for `fillmissing`.
+fn fill_missing_line(
+    line: &rhai::Array,
+    method: &rhai::Dynamic,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let values = missing_or_numbers(line)?;
+    let present = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|_| i))
+        .collect::<Vec<_>>();
+    if method.is_int() || method.is_float() {
+        return Ok(line
+            .iter()
+            .map(|x| {
+                if is_missing_value(x) {
+                    method.clone()
+                } else {
+                    x.clone()
+                }
+            })
+            .collect());
+    }
+    let method = method.clone().into_string().map_err(|_| {
+        rhai::EvalAltResult::from(crate::LabError::new(crate::LabErrorKind::InvalidArgument, "The fill method must be a number or one of \"previous\", \"next\", \"nearest\", or \"linear\""))
+    })?;
+    let mut filled = line.clone();
+    for (i, v) in values.iter().enumerate() {
+        if v.is_some() {
+            continue;
+        }
+        // The positions of the nearest values before and after the gap
+        let after = present.partition_point(|p| *p < i);
+        let prev = after.checked_sub(1).map(|k| present[k]);
+        let next = present.get(after).copied();
+        filled[i] = match method.as_str() {
+            "previous" => prev.map_or(line[i].clone(), |p| line[p].clone()),
+            "next" => next.map_or(line[i].clone(), |n| line[n].clone()),
+            "nearest" => match (prev, next) {
+                (Some(p), Some(n)) if n - i < i - p => line[n].clone(),
+                (Some(p), _) => line[p].clone(),
+                (None, Some(n)) => line[n].clone(),
+                (None, None) => line[i].clone(),
+            },
+            "linear" => {
+                // Interpolate between the neighbors, or extrapolate from the two nearest values
+                let pair = match (prev, next) {
+                    (Some(p), Some(n)) => Some((p, n)),
+                    (None, Some(_)) if present.len() > 1 => Some((present[0], present[1])),
+                    (Some(_), None) if present.len() > 1 => {
+                        Some((present[present.len() - 2], present[present.len() - 1]))
+                    }
+                    _ => None,
+                };
+                match pair {
+                    Some((a, b)) => {
+                        let (ya, yb) = (values[a].unwrap(), values[b].unwrap());
+                        let t = (i as rhai::FLOAT - a as rhai::FLOAT) / (b - a) as rhai::FLOAT;
+                        rhai::Dynamic::from_float(ya + t * (yb - ya))
+                    }
+                    None => line[i].clone(),
+                }
+            }
+            _ => {
+                return Err(crate::LabError::new(crate::LabErrorKind::InvalidArgument, format!(
+                        "Unknown fill method '{method}', expected a number or one of \"previous\", \"next\", \"nearest\", or \"linear\""
+                    ))
+                .into())
+            }
+        };
+    }
+    Ok(filled)
+}
+
+/// Scale factor that makes the median absolute deviation of normally distributed data an
+/// estimate of its standard deviation, `-1 / (sqrt(2) * erfcinv(3/2))`.
+const MAD_SCALE: rhai::FLOAT = 1.482602218505602;
+
+/// The `p`-th percentile of sorted values, interpolating linearly as `prctile` does.
+fn sorted_percentile(sorted: &[rhai::FLOAT], p: rhai::FLOAT) -> rhai::FLOAT {
+    let position = p / 100.0 * (sorted.len() - 1) as rhai::FLOAT;
+    let below = position.floor() as usize;
+    let above = (below + 1).min(sorted.len() - 1);
+    sorted[below] + (position - below as rhai::FLOAT) * (sorted[above] - sorted[below])
+}
+
+/// The lower and upper limits beyond which values are outliers, and the center of the data, for
+/// the methods described for `isoutlier`. Missing values are ignored.
+fn outlier_limits(
+    values: &[Option<rhai::FLOAT>],
+    method: &str,
+    threshold: Option<rhai::FLOAT>,
+) -> Result<(rhai::FLOAT, rhai::FLOAT, rhai::FLOAT), Box<rhai::EvalAltResult>> {
+    let mut sorted = values.iter().flatten().copied().collect::<Vec<_>>();
+    if sorted.is_empty() {
+        return Ok((rhai::FLOAT::NAN, rhai::FLOAT::NAN, rhai::FLOAT::NAN));
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted_percentile(&sorted, 50.0);
+    Ok(match method {
+        "median" | "mad" => {
+            let mut deviations = sorted.iter().map(|x| (x - median).abs()).collect::<Vec<_>>();
+            deviations.sort_by(|a, b| a.total_cmp(b));
+            let spread = threshold.unwrap_or(3.0) * MAD_SCALE * sorted_percentile(&deviations, 50.0);
+            (median - spread, median + spread, median)
+        }
+        "mean" | "zscore" => {
+            let n = sorted.len() as rhai::FLOAT;
+            let mean = sorted.iter().sum::<rhai::FLOAT>() / n;
+            let std = (sorted.iter().map(|x| (x - mean).powi(2)).sum::<rhai::FLOAT>()
+                / (n - 1.0).max(1.0))
+            .sqrt();
+            let spread = threshold.unwrap_or(3.0) * std;
+            (mean - spread, mean + spread, mean)
+        }
+        "quartiles" | "iqr" => {
+            let (q1, q3) = (sorted_percentile(&sorted, 25.0), sorted_percentile(&sorted, 75.0));
+            let spread = threshold.unwrap_or(1.5) * (q3 - q1);
+            (q1 - spread, q3 + spread, median)
+        }
+        _ => {
+            return Err(crate::LabError::new(crate::LabErrorKind::InvalidArgument, format!(
+                    "Unknown outlier method '{method}', expected \"median\", \"mean\", or \"quartiles\""
+                ))
+            .into())
+        }
+    })
+}
+
+/// Marks the outliers of a vector with the methods described for `isoutlier`.
+fn outliers_of_line(
+    line: &rhai::Array,
+    method: &str,
+    threshold: Option<rhai::FLOAT>,
+) -> Result<Vec<bool>, Box<rhai::EvalAltResult>> {
+    let values = missing_or_numbers(line)?;
+    let (lower, upper, _) = outlier_limits(&values, method, threshold)?;
+    Ok(values
+        .iter()
+        .map(|x| x.is_some_and(|x| x < lower || x > upper))
+        .collect())
+}
+
+/// Replaces the outliers of a vector with the fill methods described for `filloutliers`.
+fn fill_outliers_line(
+    line: &rhai::Array,
+    fill: &rhai::Dynamic,
+    method: &str,
+    threshold: Option<rhai::FLOAT>,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let values = missing_or_numbers(line)?;
+    let (lower, upper, center) = outlier_limits(&values, method, threshold)?;
+    let outlier = |x: &Option<rhai::FLOAT>| x.is_some_and(|x| x < lower || x > upper);
+    match fill.clone().into_string().as_deref() {
+        Ok("center") => Ok(line
+            .iter()
+            .zip(&values)
+            .map(|(el, x)| {
+                if outlier(x) {
+                    rhai::Dynamic::from_float(center)
+                } else {
+                    el.clone()
+                }
+            })
+            .collect()),
+        Ok("clip") => Ok(line
+            .iter()
+            .zip(&values)
+            .map(|(el, x)| match x {
+                Some(x) if *x < lower => rhai::Dynamic::from_float(lower),
+                Some(x) if *x > upper => rhai::Dynamic::from_float(upper),
+                _ => el.clone(),
+            })
+            .collect()),
+        _ => {
+            // Treat the outliers as gaps, fill them, and then restore the values that were
+            // missing to begin with
+            let gaps = line
+                .iter()
+                .zip(&values)
+                .map(|(el, x)| {
+                    if outlier(x) {
+                        rhai::Dynamic::UNIT
+                    } else {
+                        el.clone()
+                    }
+                })
+                .collect();
+            let filled = fill_missing_line(&gaps, fill)?;
+            Ok(filled
+                .into_iter()
+                .zip(line)
+                .zip(&values)
+                .map(|((new, old), x)| if x.is_none() { old.clone() } else { new })
+                .collect())
+        }
+    }
+}
+
+/// Characters that may separate groups of thousands in a number, besides `,` and `.`.
+const THOUSANDS_SEPARATORS: [char; 5] = [' ', '\'', '_', '\u{a0}', '\u{202f}'];
+
+/// Whether the only comma in `mantissa`, at byte `comma`, more likely separates thousands than
+/// decimals: it has one to three digits before it, not starting with zero, and exactly three
+/// after, as in `1,234`.
+fn is_thousands_comma(mantissa: &str, comma: usize) -> bool {
+    let (before, after) = (&mantissa[..comma], &mantissa[comma + 1..]);
+    (1..=3).contains(&before.len())
+        && !before.starts_with('0')
+        && before.chars().all(|c| c.is_ascii_digit())
+        && after.len() == 3
+        && after.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Reads a number as people write it: with surrounding whitespace, scientific notation, groups
+/// of thousands separated by commas, periods, spaces, or apostrophes, and either a period or a
+/// comma as the decimal separator. Whichever of `,` and `.` comes last is the decimal separator
+/// when both appear; a lone comma is taken as a decimal comma unless it is followed by exactly
+/// three digits, as in `1,234`. Returns `None` if the text is not a number.
+fn parse_number_text(text: &str) -> Option<rhai::FLOAT> {
+    let text = text.trim();
+    if let Ok(x) = text.parse::<rhai::FLOAT>() {
+        return Some(x);
+    }
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (mantissa, exponent) = rest.split_at(rest.find(['e', 'E']).unwrap_or(rest.len()));
+
+    let decimal = match (mantissa.rfind(','), mantissa.rfind('.')) {
+        (Some(c), Some(d)) => Some(c.max(d)),
+        (Some(c), None) if mantissa.matches(',').count() == 1 => {
+            (!is_thousands_comma(mantissa, c)).then_some(c)
+        }
+        (None, Some(d)) if mantissa.matches('.').count() == 1 => Some(d),
+        _ => None,
+    };
+    let (whole, fraction) = match decimal {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+
+    // Every group of thousands after the first must have exactly three digits
+    let groups = whole
+        .split(|c: char| c == ',' || c == '.' || THOUSANDS_SEPARATORS.contains(&c))
+        .collect::<Vec<_>>();
+    if groups.len() > 1
+        && (!(1..=3).contains(&groups[0].len()) || groups[1..].iter().any(|g| g.len() != 3))
+    {
+        return None;
+    }
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if !groups.iter().all(|g| digits(g)) || !digits(fraction) {
+        return None;
+    }
+    format!("{sign}{}.{fraction}{exponent}", groups.concat())
+        .parse()
+        .ok()
+}
+
+/// Converts a string to a number with `parse_number_text`, and an integer to a float, giving
+/// `NaN` for text that is not a number and for values of any other type.
+fn number_or_nan(x: &rhai::Dynamic) -> rhai::FLOAT {
+    if let Some(text) = x.read_lock::<rhai::ImmutableString>() {
+        return parse_number_text(&text).unwrap_or(rhai::FLOAT::NAN);
+    }
+    match (x.as_float(), x.as_int()) {
+        (Ok(f), _) => f,
+        (_, Ok(i)) => i as rhai::FLOAT,
+        _ => rhai::FLOAT::NAN,
+    }
+}
+
+#[export_module]
+pub mod cleaning_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT, INT};
+
+    /// Returns an array of the same shape as the input, which is `true` where a value is missing
+    /// (`NaN` or `()`).
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// assert_eq(ismissing([1.0, nan, 3.0, ()]), [false, true, false, true]);
+    /// ```
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// assert_eq(ismissing([[1, nan], [3, 4]]), [[false, true], [false, false]]);
+    /// ```
+    #[rhai_fn(name = "ismissing", pure)]
+    pub fn ismissing(arr: &mut Array) -> Array {
+        arr.iter()
+            .map(|x| match x.clone().into_array() {
+                Ok(mut inner) => Dynamic::from_array(ismissing(&mut inner)),
+                Err(_) => Dynamic::from_bool(super::is_missing_value(x)),
+            })
+            .collect()
+    }
+
+    /// Removes missing values (`NaN` or `()`) from a vector. For a matrix, removes every row that
+    /// has a missing value.
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// assert_eq(rmmissing([1.0, nan, 3.0, ()]), [1.0, 3.0]);
+    /// ```
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// let m = [[1.0, 2.0],
+    ///          [nan, 4.0],
+    ///          [5.0, 6.0]];
+    /// assert_eq(rmmissing(m), [[1.0, 2.0], [5.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "rmmissing", return_raw, pure)]
+    pub fn rmmissing(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        rmmissing_along(arr, 1)
+    }
+
+    /// Removes the rows (`dim` = 1) or columns (`dim` = 2) of a matrix that have a missing value
+    /// (`NaN` or `()`). For a vector, removes the missing values.
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// let m = [[1.0, nan, 3.0],
+    ///          [4.0, 5.0, 6.0]];
+    /// assert_eq(rmmissing(m, 2), [[1.0, 3.0], [4.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "rmmissing", return_raw, pure)]
+    pub fn rmmissing_along(arr: &mut Array, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        let is_matrix = arr.first().is_some_and(|row| row.is_array());
+        if !is_matrix {
+            super::lines_along(arr, dim)?;
+            return Ok(arr
+                .iter()
+                .filter(|x| !super::is_missing_value(x))
+                .cloned()
+                .collect());
+        }
+        // Lines across the dimension being removed from: rows for dim 1, columns for dim 2
+        let (lines, columns) = super::lines_along(arr, 3 - dim)?;
+        let kept = lines
+            .into_iter()
+            .filter(|line| !line.iter().any(super::is_missing_value))
+            .collect();
+        super::unlines(arr, kept, columns)
+    }
+
+    /// Fills the missing values (`NaN` or `()`) of a vector, or of each column of a matrix, using
+    /// `method`:
+    /// * a number: replaces every missing value with it.
+    /// * `"previous"`: the previous value that is not missing.
+    /// * `"next"`: the next value that is not missing.
+    /// * `"nearest"`: the nearest value that is not missing, preferring the previous one on ties.
+    /// * `"linear"`: linear interpolation between the neighboring values that are not missing,
+    ///   extrapolating at the ends from the two nearest values.
+    ///
+    /// Values that cannot be filled, such as leading gaps with `"previous"`, stay missing.
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// let x = [1.0, nan, nan, 4.0, nan];
+    /// assert_eq(fillmissing(x, "previous"), [1.0, 1.0, 1.0, 4.0, 4.0]);
+    /// assert_eq(fillmissing(x, "linear"), [1.0, 2.0, 3.0, 4.0, 5.0]);
+    /// assert_eq(fillmissing(x, 0.0), [1.0, 0.0, 0.0, 4.0, 0.0]);
+    /// ```
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// let m = [[1.0, nan],
+    ///          [nan, 4.0],
+    ///          [3.0, 6.0]];
+    /// assert_eq(fillmissing(m, "next"), [[1.0, 4.0], [3.0, 4.0], [3.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "fillmissing", return_raw, pure)]
+    pub fn fillmissing(arr: &mut Array, method: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        fillmissing_along(arr, method, 1)
+    }
+
+    /// Fills the missing values (`NaN` or `()`) of each column (`dim` = 1) or each row (`dim` = 2)
+    /// of a matrix, using one of the methods described for `fillmissing(arr, method)`.
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// let m = [[1.0, nan, 3.0],
+    ///          [nan, 5.0, 7.0]];
+    /// assert_eq(fillmissing(m, "linear", 2), [[1.0, 2.0, 3.0], [3.0, 5.0, 7.0]]);
+    /// ```
+    #[rhai_fn(name = "fillmissing", return_raw, pure)]
+    pub fn fillmissing_along(
+        arr: &mut Array,
+        method: Dynamic,
+        dim: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let (lines, columns) = super::lines_along(arr, dim)?;
+        let filled = lines
+            .iter()
+            .map(|line| super::fill_missing_line(line, &method))
+            .collect::<Result<Vec<_>, _>>()?;
+        super::unlines(arr, filled, columns)
+    }
+
+    /// Returns an array of the same shape as the input, which is `true` where a value is an
+    /// outlier: more than three scaled median absolute deviations from the median. For a matrix,
+    /// each column is checked separately. Missing values (`NaN` or `()`) are never outliers.
+    /// ```typescript
+    /// let x = [2.1, 2.3, 1.9, 2.0, 8.5, 2.2];
+    /// assert_eq(isoutlier(x), [false, false, false, false, true, false]);
+    /// ```
+    #[rhai_fn(name = "isoutlier", return_raw, pure)]
+    pub fn isoutlier(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        isoutlier_with_method(arr, "median".into())
+    }
+
+    /// Returns an array of the same shape as the input, which is `true` where a value is an
+    /// outlier by one of these methods:
+    /// * `"median"` (or `"mad"`): more than three scaled median absolute deviations from the
+    ///   median. The scaling makes the deviation estimate the standard deviation of normal data.
+    /// * `"mean"` (or `"zscore"`): more than three standard deviations from the mean.
+    /// * `"quartiles"` (or `"iqr"`): more than 1.5 interquartile ranges below the lower quartile
+    ///   or above the upper quartile.
+    ///
+    /// For a matrix, each column is checked separately. Missing values (`NaN` or `()`) are never
+    /// outliers.
+    /// ```typescript
+    /// let x = [10, 12, 11, 13, 12, 40];
+    /// assert_eq(isoutlier(x, "quartiles"), [false, false, false, false, false, true]);
+    /// ```
+    #[rhai_fn(name = "isoutlier", return_raw, pure)]
+    pub fn isoutlier_with_method(
+        arr: &mut Array,
+        method: ImmutableString,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        isoutlier_with_threshold(arr, method, Dynamic::UNIT)
+    }
+
+    /// Returns an array of the same shape as the input, which is `true` where a value is an
+    /// outlier by one of the methods described for `isoutlier(arr, method)`, with `threshold`
+    /// replacing the default number of deviations (3 for `"median"` and `"mean"`, and 1.5 for
+    /// `"quartiles"`).
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0, 10.0];
+    /// assert_eq(isoutlier(x, "mean", 1.5), [false, false, false, false, true]);
+    /// assert_eq(isoutlier(x, "mean", 2), [false, false, false, false, false]);
+    /// ```
+    #[rhai_fn(name = "isoutlier", return_raw, pure)]
+    pub fn isoutlier_with_threshold(
+        arr: &mut Array,
+        method: ImmutableString,
+        threshold: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let threshold = if threshold.is_unit() {
+            None
+        } else {
+            Some(crate::if_int_convert_to_float_and_do(threshold, Ok)?)
+        };
+        let (lines, columns) = super::lines_along(arr, 1)?;
+        let masks = lines
+            .iter()
+            .map(|line| {
+                super::outliers_of_line(line, &method, threshold)
+                    .map(|mask| mask.into_iter().map(Dynamic::from_bool).collect())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        super::unlines(arr, masks, columns)
+    }
+
+    /// Replaces the outliers of a vector, or of each column of a matrix, found with the `"median"`
+    /// method of `isoutlier`. `fill` is one of:
+    /// * a number: replaces every outlier with it.
+    /// * `"center"`: the median.
+    /// * `"clip"`: the nearest limit beyond which values are outliers.
+    /// * `"previous"`, `"next"`, `"nearest"`, or `"linear"`: fills the outliers as
+    ///   `fillmissing` fills missing values.
+    /// ```typescript,only_f64
+    /// let x = [2.0, 2.2, 9.0, 2.4, 2.6];
+    /// assert_eq(filloutliers(x, "linear"), [2.0, 2.2, 2.3, 2.4, 2.6]);
+    /// assert_eq(filloutliers(x, "previous"), [2.0, 2.2, 2.2, 2.4, 2.6]);
+    /// assert_eq(filloutliers(x, "center"), [2.0, 2.2, 2.4, 2.4, 2.6]);
+    /// ```
+    #[rhai_fn(name = "filloutliers", return_raw, pure)]
+    pub fn filloutliers(arr: &mut Array, fill: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        filloutliers_with_method(arr, fill, "median".into())
+    }
+
+    /// Replaces the outliers of a vector, or of each column of a matrix, found with one of the
+    /// methods described for `isoutlier(arr, method)`, using one of the fills described for
+    /// `filloutliers(arr, fill)`. With `"center"`, outliers found with `"mean"` are replaced with
+    /// the mean, and otherwise with the median.
+    /// ```typescript
+    /// let x = [10, 12, 11, 13, 12, 40];
+    /// assert_eq(filloutliers(x, 0, "quartiles"), [10, 12, 11, 13, 12, 0]);
+    /// ```
+    #[rhai_fn(name = "filloutliers", return_raw, pure)]
+    pub fn filloutliers_with_method(
+        arr: &mut Array,
+        fill: Dynamic,
+        method: ImmutableString,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        filloutliers_with_threshold(arr, fill, method, Dynamic::UNIT)
+    }
+
+    /// Replaces the outliers of a vector, or of each column of a matrix, found with one of the
+    /// methods and a threshold as described for `isoutlier(arr, method, threshold)`, using one of
+    /// the fills described for `filloutliers(arr, fill)`.
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0, 10.0];
+    /// let clipped = filloutliers(x, "clip", "mean", 1.5);
+    /// assert(clipped[4] < 10.0 && clipped[3] == 4.0);
+    /// ```
+    #[rhai_fn(name = "filloutliers", return_raw, pure)]
+    pub fn filloutliers_with_threshold(
+        arr: &mut Array,
+        fill: Dynamic,
+        method: ImmutableString,
+        threshold: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let threshold = if threshold.is_unit() {
+            None
+        } else {
+            Some(crate::if_int_convert_to_float_and_do(threshold, Ok)?)
+        };
+        let (lines, columns) = super::lines_along(arr, 1)?;
+        let filled = lines
+            .iter()
+            .map(|line| super::fill_outliers_line(line, &fill, &method, threshold))
+            .collect::<Result<Vec<_>, _>>()?;
+        super::unlines(arr, filled, columns)
+    }
+
+    /// Encodes the categories in an array as integer codes, returning a map with the `codes` and
+    /// the sorted, distinct `labels`, so that `labels[codes[i]]` is `arr[i]`. Categories can be
+    /// numbers, strings, characters, or booleans, but not a mix of them.
+    /// ```typescript
+    /// let encoded = label_encode(["red", "green", "red", "blue"]);
+    /// assert_eq(encoded.labels, ["blue", "green", "red"]);
+    /// assert_eq(encoded.codes, [2, 1, 2, 0]);
+    /// ```
+    #[rhai_fn(name = "label_encode", return_raw, pure)]
+    pub fn label_encode(arr: &mut Array) -> Result<Map, Box<EvalAltResult>> {
+        let labels = crate::sorted_categories(arr)?;
+        label_encode_with_labels(arr, labels)
+    }
+
+    /// Encodes the categories in an array as integer codes given by their position in `labels`,
+    /// returning a map with the `codes` and the `labels`. Use this to encode new data the same
+    /// way as earlier data. `labels` must be sorted and distinct, as returned by
+    /// `label_encode(arr)`, and every value must be one of them.
+    /// ```typescript
+    /// let train = label_encode(["low", "high", "mid"]);
+    /// let test = label_encode(["mid", "mid", "low"], train.labels);
+    /// assert_eq(test.codes, [2, 2, 1]);
+    /// ```
+    #[rhai_fn(name = "label_encode", return_raw, pure)]
+    pub fn label_encode_with_labels(
+        arr: &mut Array,
+        labels: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let codes = crate::category_codes(arr, &labels)?
+            .into_iter()
+            .map(|code| Dynamic::from_int(code as INT))
+            .collect::<Array>();
+        let mut result = Map::new();
+        result.insert("codes".into(), Dynamic::from_array(codes));
+        result.insert("labels".into(), Dynamic::from_array(labels));
+        Ok(result)
+    }
+
+    /// One-hot encodes the categories in an array, returning a map with the sorted, distinct
+    /// `labels` and a `data` matrix with one row per value and one column per label, which is
+    /// `1.0` in the column of the value's label and `0.0` elsewhere. Categories can be numbers,
+    /// strings, characters, or booleans, but not a mix of them.
+    /// ```typescript
+    /// let encoded = onehot(["cat", "dog", "cat"]);
+    /// assert_eq(encoded.labels, ["cat", "dog"]);
+    /// assert_eq(encoded.data, [[1.0, 0.0], [0.0, 1.0], [1.0, 0.0]]);
+    /// ```
+    #[rhai_fn(name = "onehot", return_raw, pure)]
+    pub fn onehot(arr: &mut Array) -> Result<Map, Box<EvalAltResult>> {
+        let labels = crate::sorted_categories(arr)?;
+        onehot_with_labels(arr, labels)
+    }
+
+    /// One-hot encodes the categories in an array with a column for each of `labels`, returning a
+    /// map with the `data` matrix and the `labels`. Use this to encode new data the same way as
+    /// earlier data. `labels` must be sorted and distinct, as returned by `onehot(arr)`, and every
+    /// value must be one of them.
+    /// ```typescript
+    /// let encoded = onehot([3, 1], [1, 2, 3]);
+    /// assert_eq(encoded.data, [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0]]);
+    /// ```
+    #[rhai_fn(name = "onehot", return_raw, pure)]
+    pub fn onehot_with_labels(arr: &mut Array, labels: Array) -> Result<Map, Box<EvalAltResult>> {
+        let data = crate::category_codes(arr, &labels)?
+            .into_iter()
+            .map(|code| {
+                Dynamic::from_array(
+                    (0..labels.len())
+                        .map(|j| Dynamic::from_float(if j == code { 1.0 } else { 0.0 }))
+                        .collect(),
+                )
+            })
+            .collect::<Array>();
+        let mut result = Map::new();
+        result.insert("data".into(), Dynamic::from_array(data));
+        result.insert("labels".into(), Dynamic::from_array(labels));
+        Ok(result)
+    }
+
+    /// Converts text to a number, tolerating the ways people write numbers: surrounding
+    /// whitespace, scientific notation, thousands separators, and decimal commas. Returns `NaN`
+    /// rather than an error if the text is not a number. When both `,` and `.` appear, whichever
+    /// comes last is the decimal separator; a lone comma is a decimal comma unless it is followed
+    /// by exactly three digits, so `"1,234"` is read as `1234.0`.
+    /// ```typescript
+    /// assert_eq(str2double(" 1.5e3 "), 1500.0);
+    /// assert_eq(str2double("1,234,567.89"), 1234567.89);
+    /// assert_eq(str2double("1.234.567,89"), 1234567.89);
+    /// assert_eq(str2double("3,14"), 3.14);
+    /// assert_eq(str2double("-1 000"), -1000.0);
+    /// ```
+    /// ```typescript
+    /// assert(str2double("twelve").is_nan());
+    /// assert(str2double("1,2,3").is_nan());
+    /// ```
+    #[rhai_fn(name = "str2double")]
+    pub fn str2double(text: ImmutableString) -> FLOAT {
+        super::parse_number_text(&text).unwrap_or(FLOAT::NAN)
+    }
+
+    /// Converts every string in an array or matrix to a number as `str2double(text)` does,
+    /// keeping its shape. Numbers are converted to floats, and anything else becomes `NaN`.
+    /// ```typescript
+    /// let x = str2double([["1,5", "2"], ["n/a", 4]]);
+    /// assert_eq(x[0], [1.5, 2.0]);
+    /// assert(x[1][0].is_nan());
+    /// assert_eq(x[1][1], 4.0);
+    /// ```
+    #[rhai_fn(name = "str2double", pure)]
+    pub fn str2double_array(arr: &mut Array) -> Array {
+        arr.iter()
+            .map(|x| match x.clone().into_array() {
+                Ok(mut inner) => Dynamic::from_array(str2double_array(&mut inner)),
+                Err(_) => Dynamic::from_float(super::number_or_nan(x)),
+            })
+            .collect()
+    }
+
+    /// Converts a list of strings, such as a column of text read from a file, to an array of
+    /// floats as `str2double` does, with `NaN` for entries that are not numbers. Numbers in the
+    /// list are converted to floats.
+    /// ```typescript
+    /// let x = parse_float_array(["1 234,5", "  7 ", "", "2.5E-1"]);
+    /// assert_eq(x[0], 1234.5);
+    /// assert_eq(x[1], 7.0);
+    /// assert(x[2].is_nan());
+    /// assert_eq(x[3], 0.25);
+    /// ```
+    #[rhai_fn(name = "parse_float_array", return_raw, pure)]
+    pub fn parse_float_array(strings: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        strings
+            .iter()
+            .map(|x| {
+                if x.is_array() {
+                    Err(crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "The input must be a list of strings, not a matrix",
+                    )
+                    .in_function("parse_float_array")
+                    .with_argument("strings")
+                    .into())
+                } else {
+                    Ok(Dynamic::from_float(super::number_or_nan(x)))
+                }
+            })
+            .collect()
+    }
+}
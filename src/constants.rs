@@ -1,9 +1,99 @@
 use rhai::plugin::*;
 
+/// CODATA 2018 recommended values of physical constants, as `(name, value, unit, description)`.
+const CODATA: [(&str, rhai::FLOAT, &str, &str); 25] = [
+    ("c", 299792458.0, "m/s", "Speed of light in vacuum"),
+    ("h", 6.62607015e-34, "J*s", "Planck constant"),
+    ("hbar", 1.054571817e-34, "J*s", "Reduced Planck constant"),
+    ("e", 1.602176634e-19, "C", "Elementary charge"),
+    ("k_B", 1.380649e-23, "J/K", "Boltzmann constant"),
+    ("N_A", 6.02214076e23, "1/mol", "Avogadro constant"),
+    ("R", 8.314462618, "J/mol/K", "Molar gas constant"),
+    ("F", 96485.33212, "C/mol", "Faraday constant"),
+    (
+        "sigma",
+        5.670374419e-8,
+        "W/m^2/K^4",
+        "Stefan-Boltzmann constant",
+    ),
+    (
+        "b",
+        2.897771955e-3,
+        "m*K",
+        "Wien wavelength displacement law constant",
+    ),
+    (
+        "epsilon_0",
+        8.8541878128e-12,
+        "F/m",
+        "Vacuum electric permittivity",
+    ),
+    (
+        "mu_0",
+        1.25663706212e-6,
+        "N/A^2",
+        "Vacuum magnetic permeability",
+    ),
+    (
+        "Z_0",
+        376.730313668,
+        "ohm",
+        "Characteristic impedance of vacuum",
+    ),
+    ("k_e", 8.9875517923e9, "N*m^2/C^2", "Coulomb constant"),
+    (
+        "G",
+        6.67430e-11,
+        "m^3/kg/s^2",
+        "Newtonian constant of gravitation",
+    ),
+    ("g_n", 9.80665, "m/s^2", "Standard acceleration of gravity"),
+    ("m_e", 9.1093837015e-31, "kg", "Electron mass"),
+    ("m_p", 1.67262192369e-27, "kg", "Proton mass"),
+    ("m_n", 1.67492749804e-27, "kg", "Neutron mass"),
+    ("m_u", 1.66053906660e-27, "kg", "Atomic mass constant"),
+    ("alpha", 7.2973525693e-3, "", "Fine-structure constant"),
+    ("R_inf", 10973731.568160, "1/m", "Rydberg constant"),
+    ("a_0", 5.29177210903e-11, "m", "Bohr radius"),
+    ("mu_B", 9.2740100783e-24, "J/T", "Bohr magneton"),
+    ("atm", 101325.0, "Pa", "Standard atmosphere"),
+];
+
+/// Returns the CODATA 2018 physical constants as a map from name to value, which the package
+/// provides to scripts as the `physics` variable.
+pub fn physical_constants() -> rhai::Map {
+    CODATA
+        .iter()
+        .map(|(name, value, ..)| ((*name).into(), rhai::Dynamic::from_float(*value)))
+        .collect()
+}
+
+/// Registers the CODATA 2018 physical constants as the `physics` module of an engine, so that
+/// scripts can refer to them as `physics::k_B`. Scripts run with [`eval`](crate::eval) can
+/// already do so.
+/// ```
+/// use rhai::{Engine, packages::Package, FLOAT};
+/// use rhai_sci::{register_physics_constants, SciPackage};
+///
+/// let mut engine = Engine::new();
+/// engine.register_global_module(SciPackage::new().as_shared_module());
+/// register_physics_constants(&mut engine);
+///
+/// let k_b = engine.eval::<FLOAT>("physics::k_B").unwrap();
+/// assert_eq!(k_b, 1.380649e-23);
+/// ```
+pub fn register_physics_constants(engine: &mut rhai::Engine) {
+    let mut module = rhai::Module::new();
+    for (name, value, ..) in CODATA {
+        module.set_var(name, value);
+    }
+    engine.register_static_module("physics", module.into());
+}
+
 #[export_module]
 #[allow(non_upper_case_globals)]
 pub mod constant_definitions {
-    use rhai::FLOAT;
+    use rhai::{Dynamic, EvalAltResult, ImmutableString, FLOAT};
 
     // The ratio of a circle's circumference to its diameter.
     #[allow(non_upper_case_globals)]
@@ -29,8 +119,8 @@ pub mod constant_definitions {
     #[allow(non_upper_case_globals)]
     pub const phi: FLOAT = 1.61803398874989484820;
 
-    // Newtonian gravitational constant
-    pub const G: FLOAT = 6.6743015e-11;
+    // Newtonian constant of gravitation in cubic meters per kilogram per second squared (m^3/(kg s^2))
+    pub const G: FLOAT = 6.67430e-11;
 
     /// Physical constants useful for science.
     ///  ### `pi: FLOAT`
@@ -64,10 +154,82 @@ pub mod constant_definitions {
     /// assert_eq(phi, 1.61803398874989484820);
     /// ```
     /// ### `G: FLOAT`
-    /// The Newtonian gravitational constant (non-dimensional).
+    /// The Newtonian constant of gravitation in cubic meters per kilogram per second squared
+    /// (m^3/(kg s^2)).
     /// ```typescript
-    /// assert_eq(G, 6.6743015e-11);
+    /// assert_eq(G, 6.67430e-11);
+    /// ```
+    /// ### `physics: Map`
+    /// CODATA 2018 recommended values of physical constants, in SI units. Scripts run with `eval`
+    /// can also write them as `physics::k_B`, and `codata` gives the unit of each.
+    ///
+    /// | Name | Constant | Unit |
+    /// | ---- | -------- | ---- |
+    /// | `c` | Speed of light in vacuum | m/s |
+    /// | `h` | Planck constant | J s |
+    /// | `hbar` | Reduced Planck constant | J s |
+    /// | `e` | Elementary charge | C |
+    /// | `k_B` | Boltzmann constant | J/K |
+    /// | `N_A` | Avogadro constant | 1/mol |
+    /// | `R` | Molar gas constant | J/(mol K) |
+    /// | `F` | Faraday constant | C/mol |
+    /// | `sigma` | Stefan-Boltzmann constant | W/(m^2 K^4) |
+    /// | `b` | Wien wavelength displacement law constant | m K |
+    /// | `epsilon_0` | Vacuum electric permittivity | F/m |
+    /// | `mu_0` | Vacuum magnetic permeability | N/A^2 |
+    /// | `Z_0` | Characteristic impedance of vacuum | ohm |
+    /// | `k_e` | Coulomb constant | N m^2/C^2 |
+    /// | `G` | Newtonian constant of gravitation | m^3/(kg s^2) |
+    /// | `g_n` | Standard acceleration of gravity | m/s^2 |
+    /// | `m_e` | Electron mass | kg |
+    /// | `m_p` | Proton mass | kg |
+    /// | `m_n` | Neutron mass | kg |
+    /// | `m_u` | Atomic mass constant | kg |
+    /// | `alpha` | Fine-structure constant | (non-dimensional) |
+    /// | `R_inf` | Rydberg constant | 1/m |
+    /// | `a_0` | Bohr radius | m |
+    /// | `mu_B` | Bohr magneton | J/T |
+    /// | `atm` | Standard atmosphere | Pa |
+    /// ```typescript,only_f64
+    /// assert_eq(physics.k_B, 1.380649e-23);
+    /// assert_eq(physics::N_A, 6.02214076e23);
+    /// assert(abs(physics.k_B * physics.N_A - physics.R) < 1e-9);
     /// ```
     #[rhai_fn(name = "$CONSTANTS$")]
     pub fn constants() {}
+
+    /// Looks up a CODATA 2018 physical constant by name (see `physics` for the list), returning
+    /// a map with its `value`, its SI `unit`, and a `description`.
+    /// ```typescript
+    /// let k = codata("k_B");
+    /// assert_eq(k.value, 1.380649e-23);
+    /// assert_eq(k.unit, "J/K");
+    /// assert_eq(k.description, "Boltzmann constant");
+    /// ```
+    /// ```typescript
+    /// let g = codata("g_n");
+    /// let weight = quantity(70 * g.value, "kg*" + g.unit);
+    /// assert_eq(weight.to("N").unit, "N");
+    /// ```
+    #[rhai_fn(name = "codata", return_raw)]
+    pub fn codata(name: ImmutableString) -> Result<rhai::Map, Box<EvalAltResult>> {
+        let (_, value, unit, description) = super::CODATA
+            .iter()
+            .find(|(n, ..)| *n == name.as_str())
+            .ok_or_else(|| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::NotFound,
+                        format!("'{name}' is not a known physical constant"),
+                    )
+                    .in_function("codata")
+                    .with_argument("name"),
+                )
+            })?;
+        let mut result = rhai::Map::new();
+        result.insert("value".into(), Dynamic::from_float(*value));
+        result.insert("unit".into(), Dynamic::from(unit.to_string()));
+        result.insert("description".into(), Dynamic::from(description.to_string()));
+        Ok(result)
+    }
 }
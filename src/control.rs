@@ -0,0 +1,217 @@
+use rhai::plugin::*;
+
+/// A single-input single-output linear system in controllable canonical state-space form.
+struct StateSpace {
+    /// Last row of the state matrix (the other rows shift the state)
+    a: Vec<rhai::FLOAT>,
+    /// Output matrix
+    c: Vec<rhai::FLOAT>,
+    /// Feedthrough
+    d: rhai::FLOAT,
+}
+
+impl StateSpace {
+    /// Realizes a continuous transfer function given by numerator and denominator coefficients in
+    /// descending powers of s.
+    fn from_tf(num: &[rhai::FLOAT], den: &[rhai::FLOAT]) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let first = den.iter().position(|x| *x != 0.0).ok_or_else(|| {
+            rhai::EvalAltResult::from(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The denominator of the plant must not be zero",
+            ))
+        })?;
+        let lead = den[first];
+        let den = den[first..].iter().map(|x| x / lead).collect::<Vec<_>>();
+        let n = den.len() - 1;
+        let num_first = num.iter().position(|x| *x != 0.0).unwrap_or(num.len());
+        let num = &num[num_first..];
+        if num.len() > n + 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The plant must be proper: the numerator order cannot exceed the denominator order"
+                    .to_string(),
+            )
+            .into());
+        }
+        let mut b = vec![0.0; n + 1 - num.len()];
+        b.extend(num.iter().map(|x| x / lead));
+        let d = b[0];
+        Ok(StateSpace {
+            a: (0..n).map(|j| -den[n - j]).collect(),
+            c: (0..n).map(|j| b[n - j] - den[n - j] * d).collect(),
+            d,
+        })
+    }
+
+    /// Computes the state derivative for input `u`.
+    fn derivative(&self, x: &[rhai::FLOAT], u: rhai::FLOAT) -> Vec<rhai::FLOAT> {
+        if x.is_empty() {
+            return vec![];
+        }
+        let mut dx = x[1..].to_vec();
+        dx.push(
+            self.a
+                .iter()
+                .zip(x)
+                .map(|(a, x)| a * x)
+                .sum::<rhai::FLOAT>()
+                + u,
+        );
+        dx
+    }
+
+    /// Advances the state by `dt` with the input held constant, using a classical Runge-Kutta step.
+    fn step(&self, x: &[rhai::FLOAT], u: rhai::FLOAT, dt: rhai::FLOAT) -> Vec<rhai::FLOAT> {
+        let add = |x: &[rhai::FLOAT], k: &[rhai::FLOAT], h: rhai::FLOAT| {
+            x.iter().zip(k).map(|(x, k)| x + h * k).collect::<Vec<_>>()
+        };
+        let k1 = self.derivative(x, u);
+        let k2 = self.derivative(&add(x, &k1, dt / 2.0), u);
+        let k3 = self.derivative(&add(x, &k2, dt / 2.0), u);
+        let k4 = self.derivative(&add(x, &k3, dt), u);
+        (0..x.len())
+            .map(|i| x[i] + dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]))
+            .collect()
+    }
+
+    /// Computes the output for state `x` and input `u`.
+    fn output(&self, x: &[rhai::FLOAT], u: rhai::FLOAT) -> rhai::FLOAT {
+        self.c
+            .iter()
+            .zip(x)
+            .map(|(c, x)| c * x)
+            .sum::<rhai::FLOAT>()
+            + self.d * u
+    }
+}
+
+#[export_module]
+pub mod control_functions {
+    use crate::{if_int_convert_to_float_and_do, if_list_convert_to_vec_float_and_do};
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT};
+
+    /// Computes PID gains from the ultimate gain `ku` (the proportional gain at which the closed
+    /// loop oscillates steadily) and the period `tu` of those oscillations, using the classic
+    /// Ziegler-Nichols rules. Returns an object map with `kp`, `ki`, and `kd` for a controller of
+    /// the form `u = kp*e + ki*integral(e) + kd*de/dt`.
+    /// ```typescript
+    /// let gains = pid_ziegler_nichols(10.0, 2.0);
+    /// assert_eq(gains, #{"kd": 1.5, "ki": 6.0, "kp": 6.0});
+    /// ```
+    #[rhai_fn(name = "pid_ziegler_nichols", return_raw)]
+    pub fn pid_ziegler_nichols(ku: Dynamic, tu: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        let ku = if_int_convert_to_float_and_do(ku, Ok)?;
+        let tu = if_int_convert_to_float_and_do(tu, Ok)?;
+        if ku <= 0.0 || tu <= 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The ultimate gain and period must be positive",
+            )
+            .in_function("pid_ziegler_nichols")
+            .into());
+        }
+        let kp = 0.6 * ku;
+        let mut gains = Map::new();
+        gains.insert("kp".into(), Dynamic::from_float(kp));
+        gains.insert("ki".into(), Dynamic::from_float(2.0 * kp / tu));
+        gains.insert("kd".into(), Dynamic::from_float(kp * tu / 8.0));
+        Ok(gains)
+    }
+
+    /// Simulates a PID controller in closed loop with a continuous-time plant. The plant is an
+    /// object map with `num` and `den` coefficient arrays in descending powers of `s` (for
+    /// example, `#{num: [1], den: [1, 1]}` for `1/(s + 1)`), `t` is an increasing array of time
+    /// points, and `setpoint` is either a number or an array with one value per time point. The
+    /// controller output is held constant between time points, and the plant is integrated with
+    /// a fourth-order Runge-Kutta method. Returns an object map with the time `t`, plant output
+    /// `y`, control signal `u`, and error `e`.
+    /// ```typescript
+    /// let plant = #{num: [1.0], den: [1.0, 1.0]};
+    /// let t = linspace(0, 5, 501);
+    /// let result = pid_simulate(10.0, 10.0, 0.0, plant, t, 1.0);
+    /// assert(abs(result.y[-1] - 1.0) < 1e-3);
+    /// ```
+    /// ```typescript
+    /// let plant = #{num: [1], den: [1, 2, 1]};
+    /// let gains = pid_ziegler_nichols(8, 3);
+    /// let t = linspace(0, 20, 2001);
+    /// let result = pid_simulate(gains.kp, gains.ki, gains.kd, plant, t, 1.0);
+    /// assert(abs(result.e[-1]) < 1e-6);
+    /// ```
+    #[rhai_fn(name = "pid_simulate", return_raw)]
+    pub fn pid_simulate(
+        kp: FLOAT,
+        ki: FLOAT,
+        kd: FLOAT,
+        plant_tf: Map,
+        t: Array,
+        setpoint: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let coefficients = |name: &str| {
+            let mut arr = plant_tf
+                .get(name)
+                .and_then(|x| x.clone().into_array().ok())
+                .ok_or_else(|| -> Box<EvalAltResult> {
+                    crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        format!("The plant must be an object map with a '{name}' array"),
+                    )
+                    .in_function("pid_simulate")
+                    .into()
+                })?;
+            if_list_convert_to_vec_float_and_do(&mut arr, Ok)
+        };
+        let plant = super::StateSpace::from_tf(&coefficients("num")?, &coefficients("den")?)?;
+
+        let mut t = t;
+        let times = if_list_convert_to_vec_float_and_do(&mut t, Ok)?;
+        let setpoints = if setpoint.is_array() {
+            let mut arr = setpoint.into_array().unwrap();
+            if_list_convert_to_vec_float_and_do(&mut arr, Ok)?
+        } else {
+            vec![if_int_convert_to_float_and_do(setpoint, Ok)?; times.len()]
+        };
+        if setpoints.len() != times.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "There must be one setpoint for each time point",
+            )
+            .in_function("pid_simulate")
+            .with_argument("setpoint")
+            .into());
+        }
+
+        let mut x = vec![0.0; plant.a.len()];
+        let (mut integral, mut previous_error, mut u) = (0.0, None, 0.0);
+        let (mut ys, mut us, mut es) = (vec![], vec![], vec![]);
+        for (k, tk) in times.iter().enumerate() {
+            let dt = if k > 0 { tk - times[k - 1] } else { 0.0 };
+            if k > 0 {
+                x = plant.step(&x, u, dt);
+            }
+
+            // The output uses the previous control signal, which avoids an algebraic loop for
+            // plants with direct feedthrough
+            let y = plant.output(&x, u);
+            let e = setpoints[k] - y;
+            integral += e * dt;
+            let derivative = match previous_error {
+                Some(pe) if dt > 0.0 => (e - pe) / dt,
+                _ => 0.0,
+            };
+            previous_error = Some(e);
+            u = kp * e + ki * integral + kd * derivative;
+
+            ys.push(Dynamic::from_float(y));
+            us.push(Dynamic::from_float(u));
+            es.push(Dynamic::from_float(e));
+        }
+
+        let mut result = Map::new();
+        result.insert("t".into(), Dynamic::from_array(t));
+        result.insert("y".into(), Dynamic::from_array(ys));
+        result.insert("u".into(), Dynamic::from_array(us));
+        result.insert("e".into(), Dynamic::from_array(es));
+        Ok(result)
+    }
+}
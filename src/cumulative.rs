@@ -1,9 +1,105 @@
 use rhai::plugin::*;
 
+/// Replaces the elements of an array with its running totals, starting from `init`. INTs are
+/// combined exactly with `int_op`, which returns `None` once a total is too large for an i128,
+/// alongside a FLOAT approximation from `float_op`. If any total is too large for an INT, either
+/// this fails, leaving the array as it was, or every total is stored as a FLOAT, depending on
+/// `overflow`. An array that mixes INTs and FLOATs gets FLOAT totals.
+fn running_totals(
+    arr: &mut rhai::Array,
+    init: rhai::INT,
+    int_op: fn(Option<i128>, i128) -> Option<i128>,
+    float_op: fn(rhai::FLOAT, rhai::FLOAT) -> rhai::FLOAT,
+    overflow: crate::IntOverflow,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    if !crate::validation_functions::is_numeric_list(arr) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The elements of the input array must either be INT or FLOAT",
+        )
+        .in_function(function)
+        .into());
+    }
+
+    if arr.iter().all(|el| el.is_int()) {
+        let step = |(exact, approx): &mut (Option<i128>, rhai::FLOAT), x: rhai::INT| {
+            *exact = int_op(*exact, x as i128);
+            *approx = float_op(*approx, x as rhai::FLOAT);
+            exact.ok_or(*approx)
+        };
+        let start = (Some(init as i128), init as rhai::FLOAT);
+
+        // Check every total before changing anything, so that an error leaves the array as it was
+        let mut state = start;
+        let fits = arr.iter().all(|el| {
+            matches!(step(&mut state, el.as_int().unwrap()), Ok(x) if rhai::INT::try_from(x).is_ok())
+        });
+        if !fits && overflow == crate::IntOverflow::Error {
+            return Err(crate::int_overflow_error(function));
+        }
+
+        // Promote every total if any overflows, so that the result does not mix INTs and FLOATs
+        let mut state = start;
+        for el in arr.iter_mut() {
+            *el = match step(&mut state, el.as_int().unwrap()) {
+                Ok(x) if fits => rhai::Dynamic::from_int(x as rhai::INT),
+                total => rhai::Dynamic::from_float(
+                    total.map_or_else(|approx| approx, |x| x as rhai::FLOAT),
+                ),
+            };
+        }
+    } else {
+        let mut total = init as rhai::FLOAT;
+        for el in arr.iter_mut() {
+            total = float_op(
+                total,
+                crate::if_int_convert_to_float_and_do(el.clone(), Ok)?,
+            );
+            *el = rhai::Dynamic::from_float(total);
+        }
+    }
+    Ok(())
+}
+
+/// The running sum used by `cumsum` and `cumsum_in_place`.
+fn running_sum(
+    arr: &mut rhai::Array,
+    options: &rhai::Map,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let overflow = crate::IntOverflow::from_options(options, function)?;
+    running_totals(
+        arr,
+        0,
+        |s, x| s?.checked_add(x),
+        |s, x| s + x,
+        overflow,
+        function,
+    )
+}
+
+/// The running product used by `cumprod` and `cumprod_in_place`.
+fn running_product(
+    arr: &mut rhai::Array,
+    options: &rhai::Map,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let overflow = crate::IntOverflow::from_options(options, function)?;
+    running_totals(
+        arr,
+        1,
+        |p, x| if x == 0 { Some(0) } else { p?.checked_mul(x) },
+        |p, x| p * x,
+        overflow,
+        function,
+    )
+}
+
 #[export_module]
 pub mod cum_functions {
-    use crate::{if_list_convert_to_vec_float_and_do, if_list_do};
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT, INT};
+    use crate::{if_int_convert_to_float_and_do, if_list_convert_to_vec_float_and_do, if_list_do};
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
 
     fn accumulate<G>(arr: &mut Array, mut f: G) -> Result<Array, Box<EvalAltResult>>
     where
@@ -19,7 +115,9 @@ pub mod cum_functions {
         })
     }
 
-    /// Returns an array representing the cumulative product of a 1-D array.
+    /// Returns an array representing the cumulative product of a 1-D array. Fails if a product of
+    /// INTs is too large for an INT, unless the `overflow` option is used as in
+    /// `cumprod(arr, options)`.
     /// ```typescript
     /// let arr = [1, 2, 3, 4, 5];
     /// let c = cumprod(arr);
@@ -27,7 +125,59 @@ pub mod cum_functions {
     /// ```
     #[rhai_fn(name = "cumprod", return_raw, pure)]
     pub fn cumprod(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
-        accumulate(arr, |x| crate::stats::prod(x).unwrap())
+        cumprod_with_options(arr, Map::new())
+    }
+
+    /// Returns an array representing the cumulative product of a 1-D array, with options given
+    /// as a map:
+    /// - `overflow`: what to do if a product of INTs is too large for an INT, either `"error"`
+    ///   (the default) or `"float"` to return every product as a FLOAT
+    /// ```typescript
+    /// let arr = [4294967296, 4294967296, 2];
+    /// let c = cumprod(arr, #{overflow: "float"});
+    /// assert_eq(c, [4294967296.0, 18446744073709551616.0, 36893488147419103232.0]);
+    /// ```
+    #[rhai_fn(name = "cumprod", return_raw, pure)]
+    pub fn cumprod_with_options(
+        arr: &mut Array,
+        options: Map,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let mut result = arr.clone();
+        super::running_product(&mut result, &options, "cumprod")?;
+        Ok(result)
+    }
+
+    /// Replaces the elements of an array with its cumulative product, like `cumprod` but without
+    /// allocating a new array. The INTs in an array that mixes INTs and FLOATs become FLOATs.
+    /// ```typescript
+    /// let arr = [1, 2, 3, 4, 5];
+    /// arr.cumprod_in_place();
+    /// assert_eq(arr, [1, 2, 6, 24, 120]);
+    /// ```
+    #[rhai_fn(name = "cumprod_in_place", return_raw)]
+    pub fn cumprod_in_place(arr: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        super::running_product(arr, &Map::new(), "cumprod_in_place")
+    }
+
+    /// Replaces the elements of an array with its cumulative product, with options as for
+    /// `cumprod(arr, options)`. If the product overflows and the `overflow` option is `"error"`,
+    /// the array is left as it was.
+    /// ```typescript
+    /// let arr = [4294967296, 4294967296];
+    /// arr.cumprod_in_place(#{overflow: "float"});
+    /// assert_eq(arr, [4294967296.0, 18446744073709551616.0]);
+    /// ```
+    /// ```typescript,only_i64
+    /// let arr = [4294967296, 4294967296];
+    /// try { arr.cumprod_in_place(#{overflow: "error"}); } catch { }
+    /// assert_eq(arr, [4294967296, 4294967296]);
+    /// ```
+    #[rhai_fn(name = "cumprod_in_place", return_raw)]
+    pub fn cumprod_in_place_with_options(
+        arr: &mut Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        super::running_product(arr, &options, "cumprod_in_place")
     }
 
     /// Returns an array representing the cumulative maximum of a 1-D array.
@@ -52,15 +202,106 @@ pub mod cum_functions {
         accumulate(arr, |x| crate::stats::array_min(x).unwrap())
     }
 
-    /// Returns an array representing the cumulative product of a 1-D array.
+    /// Returns an array representing the cumulative sum of a 1-D array. Fails if a sum of INTs is
+    /// too large for an INT, unless the `overflow` option is used as in `cumsum(arr, options)`.
     /// ```typescript
     /// let arr = [1.1, 2.5, 3.4];
     /// let c = cumsum(arr);
     /// assert_eq(c, [1.1, 3.6, 7.0]);
     /// ```
+    /// ```typescript
+    /// let arr = [1, 2.5, 3];
+    /// let c = cumsum(arr);
+    /// assert_eq(c, [1.0, 3.5, 6.5]);
+    /// ```
     #[rhai_fn(name = "cumsum", return_raw, pure)]
     pub fn cumsum(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
-        accumulate(arr, |x| crate::stats::sum(x).unwrap())
+        cumsum_with_options(arr, Map::new())
+    }
+
+    /// Returns an array representing the cumulative sum of a 1-D array, with options given as a
+    /// map:
+    /// - `overflow`: what to do if a sum of INTs is too large for an INT, either `"error"` (the
+    ///   default) or `"float"` to return every sum as a FLOAT
+    /// ```typescript
+    /// let arr = [4611686018427387904, 4611686018427387904];
+    /// let c = cumsum(arr, #{overflow: "float"});
+    /// assert_eq(c, [4611686018427387904.0, 9223372036854775808.0]);
+    /// ```
+    /// ```typescript,only_i64
+    /// let result = "no error";
+    /// try { cumsum([9223372036854775807, 1]); } catch (err) { result = err.kind; }
+    /// assert_eq(result, "numerical");
+    /// ```
+    #[rhai_fn(name = "cumsum", return_raw, pure)]
+    pub fn cumsum_with_options(arr: &mut Array, options: Map) -> Result<Array, Box<EvalAltResult>> {
+        let mut result = arr.clone();
+        super::running_sum(&mut result, &options, "cumsum")?;
+        Ok(result)
+    }
+
+    /// Replaces the elements of an array with its cumulative sum, like `cumsum` but without
+    /// allocating a new array. The INTs in an array that mixes INTs and FLOATs become FLOATs.
+    /// ```typescript
+    /// let arr = [1, 2, 3];
+    /// arr.cumsum_in_place();
+    /// assert_eq(arr, [1, 3, 6]);
+    /// ```
+    /// ```typescript
+    /// let arr = [1, 2.5, 3];
+    /// cumsum_in_place(arr);
+    /// assert_eq(arr, [1.0, 3.5, 6.5]);
+    /// ```
+    #[rhai_fn(name = "cumsum_in_place", return_raw)]
+    pub fn cumsum_in_place(arr: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        super::running_sum(arr, &Map::new(), "cumsum_in_place")
+    }
+
+    /// Replaces the elements of an array with its cumulative sum, with options as for
+    /// `cumsum(arr, options)`.
+    /// ```typescript
+    /// let arr = [9223372036854775807, 1];
+    /// arr.cumsum_in_place(#{overflow: "float"});
+    /// assert_eq(arr, [9223372036854775807.0, 9223372036854775808.0]);
+    /// ```
+    #[rhai_fn(name = "cumsum_in_place", return_raw)]
+    pub fn cumsum_in_place_with_options(
+        arr: &mut Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        super::running_sum(arr, &options, "cumsum_in_place")
+    }
+
+    /// Returns the cumulative sum of a 1-D array normalized by the total sum, so that the last
+    /// element is always 1.0. This is equivalent to `cumsum(arr) / sum(arr)`, but is computed in a
+    /// single pass that only allocates the output array.
+    /// ```typescript
+    /// let arr = [1, 1, 2];
+    /// let c = normalized_cumsum(arr);
+    /// assert_eq(c, [0.25, 0.5, 1.0]);
+    /// ```
+    #[rhai_fn(name = "normalized_cumsum", return_raw, pure)]
+    pub fn normalized_cumsum(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut total = 0.0 as FLOAT;
+        let mut new_arr = Array::with_capacity(arr.len());
+        for el in arr.iter() {
+            total += if_int_convert_to_float_and_do(el.clone(), Ok)?;
+            new_arr.push(Dynamic::from_float(total));
+        }
+
+        if total == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The array must not sum to zero",
+            )
+            .in_function("normalized_cumsum")
+            .into());
+        }
+
+        for el in new_arr.iter_mut() {
+            *el = Dynamic::from_float(el.as_float().unwrap() / total);
+        }
+        Ok(new_arr)
     }
 
     /// Returns the cumulative approximate integral of the curve defined by Y and x using the trapezoidal method.
@@ -73,10 +314,11 @@ pub mod cum_functions {
     #[rhai_fn(name = "cumtrapz", return_raw)]
     pub fn cumtrapz(x: Array, y: Array) -> Result<Array, Box<EvalAltResult>> {
         if x.len() != y.len() {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have the same length".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "The arrays must have the same length",
             )
+            .in_function("cumtrapz")
             .into())
         } else {
             if_list_convert_to_vec_float_and_do(&mut y.clone(), |yf| {
@@ -111,5 +353,6 @@ pub mod cum_functions {
             }
             Ok(cumtrapsum)
         })
+        .map_err(crate::in_function("cumtrapz", Some("y")))
     }
 }
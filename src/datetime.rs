@@ -0,0 +1,836 @@
+use rhai::plugin::*;
+
+/// A date and time of day without a time zone. Timestamps parsed with a UTC offset are converted
+/// to UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime(pub chrono::NaiveDateTime);
+
+/// A length of time, positive or negative, with nanosecond precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub chrono::TimeDelta);
+
+impl std::fmt::Display for DateTime {
+    /// Formats the date and time in ISO 8601 form, such as `2024-01-02T03:04:05`, with as many
+    /// digits of fractional seconds as needed.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m-%dT%H:%M:%S%.f"))
+    }
+}
+
+impl std::fmt::Display for Duration {
+    /// Formats the duration as hours, minutes, and seconds, such as `26:03:04.5`, preceded by the
+    /// number of days if there are any, as in `1d 02:03:04.5`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < chrono::TimeDelta::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let total = self.0.abs();
+        let secs = total.num_seconds();
+        let (days, h, m, s) = (
+            secs / 86400,
+            secs % 86400 / 3600,
+            secs % 3600 / 60,
+            secs % 60,
+        );
+        write!(f, "{sign}")?;
+        if days > 0 {
+            write!(f, "{days}d ")?;
+        }
+        write!(f, "{h:02}:{m:02}:{s:02}")?;
+        match total.subsec_nanos() {
+            0 => Ok(()),
+            nanos => write!(f, ".{}", format!("{nanos:09}").trim_end_matches('0')),
+        }
+    }
+}
+
+impl Duration {
+    /// Creates a duration from a number of seconds, or returns `None` if it is not finite or is
+    /// too long to represent (more than about 292 years).
+    #[allow(clippy::unnecessary_cast)] // FLOAT may be f32
+    pub fn from_seconds(seconds: rhai::FLOAT) -> Option<Self> {
+        let nanos = (seconds as f64 * 1e9).round();
+        (nanos.is_finite() && nanos.abs() < i64::MAX as f64)
+            .then(|| Duration(chrono::TimeDelta::nanoseconds(nanos as i64)))
+    }
+
+    /// Returns the length of the duration in seconds.
+    pub fn as_seconds(&self) -> rhai::FLOAT {
+        (self.0.num_seconds() as f64 + self.0.subsec_nanos() as f64 / 1e9) as rhai::FLOAT
+    }
+}
+
+/// Days between the MATLAB `datenum` origin (day 1 is January 1 of year 0) and the start of
+/// chrono's day count (day 1 is January 1 of year 1).
+const DATENUM_OFFSET: i64 = 366;
+
+/// Reads a timestamp in ISO 8601 form: a date such as `2024-01-02`, optionally followed by `T` or
+/// a space and a time such as `03:04` or `03:04:05.25`, and optionally a UTC offset such as `Z` or
+/// `+02:00`, in which case the result is converted to UTC.
+fn parse_timestamp(text: &str) -> Option<chrono::NaiveDateTime> {
+    let text = text.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(dt.naive_utc());
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| chrono::NaiveDateTime::parse_from_str(text, format).ok())
+    .or_else(|| {
+        chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .map(|d| d.and_time(chrono::NaiveTime::MIN))
+    })
+}
+
+/// Fails unless `format` is a valid `strftime`-style format, which chrono would otherwise only
+/// report by panicking when formatting.
+fn check_time_format(format: &str, function: &str) -> Result<(), Box<rhai::EvalAltResult>> {
+    use chrono::format::{Item, StrftimeItems};
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Invalid date and time format '{format}'"),
+        )
+        .in_function(function)
+        .with_argument("format")
+        .into());
+    }
+    Ok(())
+}
+
+/// Reads a `DateTime`, or a string in one of the forms `datetime` accepts.
+fn as_datetime(
+    value: &rhai::Dynamic,
+    function: &str,
+) -> Result<DateTime, Box<rhai::EvalAltResult>> {
+    if let Some(dt) = value.read_lock::<DateTime>() {
+        return Ok(*dt);
+    }
+    let parsed = value
+        .read_lock::<rhai::ImmutableString>()
+        .and_then(|text| parse_timestamp(&text));
+    parsed.map(DateTime).ok_or_else(|| {
+        crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            format!("Cannot read {value:?} as a date and time"),
+        )
+        .in_function(function)
+        .into()
+    })
+}
+
+/// Converts a MATLAB-style serial date number to a date and time, to the nearest millisecond.
+#[allow(clippy::unnecessary_cast)] // FLOAT may be f32
+fn datetime_from_datenum(x: rhai::FLOAT) -> Option<DateTime> {
+    let days = (x as f64).floor();
+    let millis = ((x as f64 - days) * 86_400_000.0).round() as i64;
+    let date = chrono::NaiveDate::from_num_days_from_ce_opt(
+        i32::try_from(days as i64 - DATENUM_OFFSET).ok()?,
+    )?;
+    date.and_time(chrono::NaiveTime::MIN)
+        .checked_add_signed(chrono::TimeDelta::milliseconds(millis))
+        .map(DateTime)
+}
+
+/// The ways `resample_time` can combine the values in each interval.
+const RESAMPLE_AGGREGATIONS: [&str; 7] = ["mean", "sum", "min", "max", "count", "first", "last"];
+
+/// Combines the values that fall in one interval of `resample_time`. Empty intervals have a count
+/// and sum of zero, and `NaN` for everything else.
+fn aggregate_bucket(values: &[rhai::FLOAT], agg: &str) -> rhai::Dynamic {
+    let nan = rhai::FLOAT::NAN;
+    let x = match agg {
+        "count" => return rhai::Dynamic::from_int(values.len() as rhai::INT),
+        "sum" => crate::stable_sum_slice(values),
+        _ if values.is_empty() => nan,
+        "mean" => crate::stable_sum_slice(values) / values.len() as rhai::FLOAT,
+        "min" => values
+            .iter()
+            .copied()
+            .fold(rhai::FLOAT::INFINITY, rhai::FLOAT::min),
+        "max" => values
+            .iter()
+            .copied()
+            .fold(rhai::FLOAT::NEG_INFINITY, rhai::FLOAT::max),
+        "first" => values[0],
+        _ => values[values.len() - 1],
+    };
+    rhai::Dynamic::from_float(x)
+}
+
+#[export_module]
+pub mod datetime_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, ImmutableString, Map, NativeCallContext, FLOAT, INT,
+    };
+
+    /// A date and time of day, created with `datetime`.
+    pub type DateTime = crate::DateTime;
+
+    /// A length of time, created with `seconds`, `minutes`, `hours`, or `days`, or by subtracting
+    /// one `DateTime` from another.
+    pub type Duration = crate::Duration;
+
+    /// Reads a timestamp in ISO 8601 form into a `DateTime`: a date such as `"2024-01-02"`,
+    /// optionally followed by `T` or a space and a time such as `"03:04"` or `"03:04:05.25"`.
+    /// Timestamps ending in a UTC offset, such as `Z` or `+02:00`, are converted to UTC.
+    /// ```typescript
+    /// let t = datetime("2024-01-02T03:04:05");
+    /// assert_eq([t.year, t.month, t.day, t.hour, t.minute, t.second], [2024, 1, 2, 3, 4, 5.0]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(datetime("2024-01-02 05:04:05+02:00"), datetime("2024-01-02T03:04:05"));
+    /// assert_eq(datetime("2024-01-02").hour, 0);
+    /// ```
+    #[rhai_fn(name = "datetime", return_raw)]
+    pub fn datetime(text: ImmutableString) -> Result<DateTime, Box<EvalAltResult>> {
+        super::parse_timestamp(&text).map(DateTime).ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot read '{text}' as a date and time"),
+            )
+            .in_function("datetime")
+            .with_argument("text")
+            .into()
+        })
+    }
+
+    /// Reads a timestamp into a `DateTime` using a `strftime`-style format, in which `%Y` is the
+    /// year, `%m` the month, `%d` the day, `%H` the hour, `%M` the minute, and `%S` the second.
+    /// The time of day is midnight if the format has none.
+    /// ```typescript
+    /// let t = datetime("02/01/2024 15:30", "%d/%m/%Y %H:%M");
+    /// assert_eq(t, datetime("2024-01-02T15:30:00"));
+    /// ```
+    /// ```typescript
+    /// assert_eq(datetime("2024.002", "%Y.%j"), datetime("2024-01-02"));
+    /// ```
+    #[rhai_fn(name = "datetime", return_raw)]
+    pub fn datetime_with_format(
+        text: ImmutableString,
+        format: ImmutableString,
+    ) -> Result<DateTime, Box<EvalAltResult>> {
+        super::check_time_format(&format, "datetime")?;
+        chrono::NaiveDateTime::parse_from_str(&text, &format)
+            .or_else(|_| {
+                chrono::NaiveDate::parse_from_str(&text, &format)
+                    .map(|d| d.and_time(chrono::NaiveTime::MIN))
+            })
+            .map(DateTime)
+            .map_err(|err| {
+                crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("Cannot read '{text}' with the format '{format}': {err}"),
+                )
+                .in_function("datetime")
+                .with_argument("text")
+                .into()
+            })
+    }
+
+    /// Creates a `DateTime` at midnight on the given year, month, and day.
+    /// ```typescript
+    /// assert_eq(datetime(2024, 2, 29), datetime("2024-02-29"));
+    /// ```
+    #[rhai_fn(name = "datetime", return_raw)]
+    pub fn datetime_from_date(
+        year: INT,
+        month: INT,
+        day: INT,
+    ) -> Result<DateTime, Box<EvalAltResult>> {
+        datetime_from_parts(year, month, day, 0, 0, Dynamic::from_int(0))
+    }
+
+    /// Creates a `DateTime` from its year, month, day, hour, minute, and second, which may have a
+    /// fractional part.
+    /// ```typescript
+    /// let t = datetime(2024, 1, 2, 3, 4, 5.5);
+    /// assert_eq(datestr(t), "2024-01-02T03:04:05.500");
+    /// ```
+    #[rhai_fn(name = "datetime", return_raw)]
+    pub fn datetime_from_parts(
+        year: INT,
+        month: INT,
+        day: INT,
+        hour: INT,
+        minute: INT,
+        second: Dynamic,
+    ) -> Result<DateTime, Box<EvalAltResult>> {
+        let error = |msg: String| -> Box<EvalAltResult> {
+            crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+                .in_function("datetime")
+                .into()
+        };
+        let second = crate::if_int_convert_to_float_and_do(second, Ok)?;
+        let date = i32::try_from(year)
+            .ok()
+            .and_then(|y| chrono::NaiveDate::from_ymd_opt(y, month as u32, day as u32))
+            .filter(|_| (1..=12).contains(&month) && (1..=31).contains(&day))
+            .ok_or_else(|| error(format!("There is no date {year}-{month}-{day}")))?;
+        let time = chrono::NaiveTime::from_hms_opt(hour as u32, minute as u32, 0)
+            .filter(|_| (0..24).contains(&hour) && (0..60).contains(&minute))
+            .ok_or_else(|| error(format!("There is no time {hour}:{minute}")))?;
+        let offset = crate::Duration::from_seconds(second)
+            .filter(|_| (0.0..60.0).contains(&second))
+            .ok_or_else(|| error(format!("The second must be from 0 up to 60, not {second}")))?;
+        Ok(DateTime(date.and_time(time) + offset.0))
+    }
+
+    /// Returns the year of a `DateTime`.
+    #[rhai_fn(get = "year", pure)]
+    pub fn get_year(t: &mut DateTime) -> INT {
+        chrono::Datelike::year(&t.0) as INT
+    }
+
+    /// Returns the month of a `DateTime`, from 1 for January to 12 for December.
+    #[rhai_fn(get = "month", pure)]
+    pub fn get_month(t: &mut DateTime) -> INT {
+        chrono::Datelike::month(&t.0) as INT
+    }
+
+    /// Returns the day of the month of a `DateTime`, starting from 1.
+    #[rhai_fn(get = "day", pure)]
+    pub fn get_day(t: &mut DateTime) -> INT {
+        chrono::Datelike::day(&t.0) as INT
+    }
+
+    /// Returns the day of the week of a `DateTime`, from 1 for Monday to 7 for Sunday.
+    /// ```typescript
+    /// assert_eq(datetime("2024-01-07").weekday, 7);
+    /// ```
+    #[rhai_fn(get = "weekday", pure)]
+    pub fn get_weekday(t: &mut DateTime) -> INT {
+        chrono::Datelike::weekday(&t.0).number_from_monday() as INT
+    }
+
+    /// Returns the hour of a `DateTime`, from 0 to 23.
+    #[rhai_fn(get = "hour", pure)]
+    pub fn get_hour(t: &mut DateTime) -> INT {
+        chrono::Timelike::hour(&t.0) as INT
+    }
+
+    /// Returns the minute of a `DateTime`, from 0 to 59.
+    #[rhai_fn(get = "minute", pure)]
+    pub fn get_minute(t: &mut DateTime) -> INT {
+        chrono::Timelike::minute(&t.0) as INT
+    }
+
+    /// Returns the second of a `DateTime`, including any fraction of a second.
+    /// ```typescript
+    /// assert_eq(datetime("2024-01-02T03:04:05.25").second, 5.25);
+    /// ```
+    #[rhai_fn(get = "second", pure)]
+    pub fn get_second(t: &mut DateTime) -> FLOAT {
+        let nanos = chrono::Timelike::nanosecond(&t.0) as FLOAT;
+        chrono::Timelike::second(&t.0) as FLOAT + nanos / 1e9
+    }
+
+    /// Creates a `Duration` of a number of seconds.
+    /// ```typescript
+    /// let t = datetime("2024-01-02T03:04:05") + seconds(55);
+    /// assert_eq(t, datetime("2024-01-02T03:05:00"));
+    /// ```
+    #[rhai_fn(name = "seconds", return_raw)]
+    pub fn seconds(x: Dynamic) -> Result<Duration, Box<EvalAltResult>> {
+        duration_from(x, 1.0, "seconds")
+    }
+
+    /// Creates a `Duration` of a number of minutes.
+    /// ```typescript
+    /// assert_eq(minutes(1.5), seconds(90));
+    /// ```
+    #[rhai_fn(name = "minutes", return_raw)]
+    pub fn minutes(x: Dynamic) -> Result<Duration, Box<EvalAltResult>> {
+        duration_from(x, 60.0, "minutes")
+    }
+
+    /// Creates a `Duration` of a number of hours.
+    /// ```typescript
+    /// assert_eq(hours(36).days, 1.5);
+    /// ```
+    #[rhai_fn(name = "hours", return_raw)]
+    pub fn hours(x: Dynamic) -> Result<Duration, Box<EvalAltResult>> {
+        duration_from(x, 3600.0, "hours")
+    }
+
+    /// Creates a `Duration` of a number of days, each of which is 24 hours long.
+    /// ```typescript
+    /// assert_eq(datetime("2024-02-28") + days(2), datetime("2024-03-01"));
+    /// ```
+    #[rhai_fn(name = "days", return_raw)]
+    pub fn days(x: Dynamic) -> Result<Duration, Box<EvalAltResult>> {
+        duration_from(x, 86400.0, "days")
+    }
+
+    /// Creates a `Duration` of `x` units of `unit` seconds each.
+    fn duration_from(
+        x: Dynamic,
+        unit: FLOAT,
+        function: &str,
+    ) -> Result<Duration, Box<EvalAltResult>> {
+        let x = crate::if_int_convert_to_float_and_do(x, Ok)?;
+        crate::Duration::from_seconds(x * unit).ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot make a duration of {x} {function}"),
+            )
+            .in_function(function)
+            .with_argument("x")
+            .into()
+        })
+    }
+
+    /// Returns the length of a `Duration` in seconds.
+    /// ```typescript
+    /// let d = datetime("2024-01-02T03:04:05") - datetime("2024-01-02T03:00:00");
+    /// assert_eq(d.seconds, 245.0);
+    /// ```
+    #[rhai_fn(get = "seconds", pure)]
+    pub fn get_seconds(d: &mut Duration) -> FLOAT {
+        d.as_seconds()
+    }
+
+    /// Returns the length of a `Duration` in minutes.
+    #[rhai_fn(get = "minutes", pure)]
+    pub fn get_minutes(d: &mut Duration) -> FLOAT {
+        d.as_seconds() / 60.0
+    }
+
+    /// Returns the length of a `Duration` in hours.
+    #[rhai_fn(get = "hours", pure)]
+    pub fn get_hours(d: &mut Duration) -> FLOAT {
+        d.as_seconds() / 3600.0
+    }
+
+    /// Returns the length of a `Duration` in days of 24 hours.
+    #[rhai_fn(get = "days", pure)]
+    pub fn get_days(d: &mut Duration) -> FLOAT {
+        d.as_seconds() / 86400.0
+    }
+
+    /// The error for a `DateTime` outside the range that can be represented.
+    fn out_of_range(function: &str) -> Box<EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::Numerical,
+            "The result is outside the range of dates that can be represented",
+        )
+        .in_function(function)
+        .into()
+    }
+
+    /// Moves a `DateTime` later by a `Duration`.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_duration(t: DateTime, d: Duration) -> Result<DateTime, Box<EvalAltResult>> {
+        t.0.checked_add_signed(d.0)
+            .map(DateTime)
+            .ok_or_else(|| out_of_range("+"))
+    }
+
+    /// Moves a `DateTime` later by a `Duration`.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_to_datetime(d: Duration, t: DateTime) -> Result<DateTime, Box<EvalAltResult>> {
+        add_duration(t, d)
+    }
+
+    /// Moves a `DateTime` earlier by a `Duration`.
+    /// ```typescript
+    /// assert_eq(datetime("2024-03-01") - hours(12), datetime("2024-02-29T12:00"));
+    /// ```
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_duration(t: DateTime, d: Duration) -> Result<DateTime, Box<EvalAltResult>> {
+        t.0.checked_sub_signed(d.0)
+            .map(DateTime)
+            .ok_or_else(|| out_of_range("-"))
+    }
+
+    /// Returns the `Duration` from one `DateTime` to another.
+    /// ```typescript
+    /// let d = datetime("2024-03-01") - datetime("2024-02-28T12:00");
+    /// assert_eq(d, hours(36));
+    /// assert_eq(to_string(d), "1d 12:00:00");
+    /// ```
+    #[rhai_fn(name = "-")]
+    pub fn difference(a: DateTime, b: DateTime) -> Duration {
+        Duration(a.0 - b.0)
+    }
+
+    /// Adds two durations.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_durations(a: Duration, b: Duration) -> Result<Duration, Box<EvalAltResult>> {
+        a.0.checked_add(&b.0)
+            .map(Duration)
+            .ok_or_else(|| out_of_range("+"))
+    }
+
+    /// Subtracts one duration from another.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_durations(a: Duration, b: Duration) -> Result<Duration, Box<EvalAltResult>> {
+        a.0.checked_sub(&b.0)
+            .map(Duration)
+            .ok_or_else(|| out_of_range("-"))
+    }
+
+    /// Negates a duration.
+    #[rhai_fn(name = "-")]
+    pub fn negate_duration(d: Duration) -> Duration {
+        Duration(-d.0)
+    }
+
+    /// Scales a duration by a number.
+    /// ```typescript
+    /// assert_eq(minutes(10) * 1.5, minutes(15));
+    /// ```
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale_duration(d: Duration, x: FLOAT) -> Result<Duration, Box<EvalAltResult>> {
+        crate::Duration::from_seconds(d.as_seconds() * x).ok_or_else(|| out_of_range("*"))
+    }
+
+    /// Scales a duration by an integer.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale_duration_int(d: Duration, x: INT) -> Result<Duration, Box<EvalAltResult>> {
+        d.0.checked_mul(x as i32)
+            .filter(|_| i32::try_from(x).is_ok())
+            .map(Duration)
+            .ok_or_else(|| out_of_range("*"))
+    }
+
+    /// Scales a duration by a number.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale_float_duration(x: FLOAT, d: Duration) -> Result<Duration, Box<EvalAltResult>> {
+        scale_duration(d, x)
+    }
+
+    /// Scales a duration by an integer.
+    /// ```typescript
+    /// assert_eq(3 * hours(8), days(1));
+    /// ```
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale_int_duration(x: INT, d: Duration) -> Result<Duration, Box<EvalAltResult>> {
+        scale_duration_int(d, x)
+    }
+
+    /// Divides a duration by a number.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_duration(d: Duration, x: FLOAT) -> Result<Duration, Box<EvalAltResult>> {
+        crate::Duration::from_seconds(d.as_seconds() / x).ok_or_else(|| out_of_range("/"))
+    }
+
+    /// Divides a duration by an integer.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_duration_int(d: Duration, x: INT) -> Result<Duration, Box<EvalAltResult>> {
+        divide_duration(d, x as FLOAT)
+    }
+
+    /// Returns the ratio of two durations.
+    /// ```typescript
+    /// assert_eq(days(1) / hours(1), 24.0);
+    /// ```
+    #[rhai_fn(name = "/")]
+    pub fn duration_ratio(a: Duration, b: Duration) -> FLOAT {
+        a.as_seconds() / b.as_seconds()
+    }
+
+    /// Tests whether two times are the same.
+    #[rhai_fn(name = "==")]
+    pub fn eq(a: DateTime, b: DateTime) -> bool {
+        a == b
+    }
+
+    /// Tests whether two times differ.
+    #[rhai_fn(name = "!=")]
+    pub fn ne(a: DateTime, b: DateTime) -> bool {
+        a != b
+    }
+
+    /// Tests whether one time is earlier than another.
+    /// ```typescript
+    /// assert(datetime("2024-01-02") < datetime("2024-01-02T00:00:01"));
+    /// ```
+    #[rhai_fn(name = "<")]
+    pub fn lt(a: DateTime, b: DateTime) -> bool {
+        a < b
+    }
+
+    /// Tests whether one time is earlier than or the same as another.
+    #[rhai_fn(name = "<=")]
+    pub fn le(a: DateTime, b: DateTime) -> bool {
+        a <= b
+    }
+
+    /// Tests whether one time is later than another.
+    #[rhai_fn(name = ">")]
+    pub fn gt(a: DateTime, b: DateTime) -> bool {
+        a > b
+    }
+
+    /// Tests whether one time is later than or the same as another.
+    #[rhai_fn(name = ">=")]
+    pub fn ge(a: DateTime, b: DateTime) -> bool {
+        a >= b
+    }
+
+    /// Tests whether two durations are the same length.
+    #[rhai_fn(name = "==")]
+    pub fn duration_eq(a: Duration, b: Duration) -> bool {
+        a == b
+    }
+
+    /// Tests whether two durations differ in length.
+    #[rhai_fn(name = "!=")]
+    pub fn duration_ne(a: Duration, b: Duration) -> bool {
+        a != b
+    }
+
+    /// Tests whether one duration is shorter than another.
+    /// ```typescript
+    /// assert(minutes(59) < hours(1));
+    /// ```
+    #[rhai_fn(name = "<")]
+    pub fn duration_lt(a: Duration, b: Duration) -> bool {
+        a < b
+    }
+
+    /// Tests whether one duration is shorter than or the same length as another.
+    #[rhai_fn(name = "<=")]
+    pub fn duration_le(a: Duration, b: Duration) -> bool {
+        a <= b
+    }
+
+    /// Tests whether one duration is longer than another.
+    #[rhai_fn(name = ">")]
+    pub fn duration_gt(a: Duration, b: Duration) -> bool {
+        a > b
+    }
+
+    /// Tests whether one duration is longer than or the same length as another.
+    #[rhai_fn(name = ">=")]
+    pub fn duration_ge(a: Duration, b: Duration) -> bool {
+        a >= b
+    }
+
+    /// Converts a `DateTime`, or a timestamp that `datetime` can read, to a serial date number as
+    /// used by MATLAB: the number of days since January 0 of year 0, with the time of day as the
+    /// fractional part.
+    /// ```typescript
+    /// assert_eq(datenum(datetime("2000-01-01")), 730486.0);
+    /// assert_eq(datenum("2000-01-01T18:00"), 730486.75);
+    /// ```
+    #[rhai_fn(name = "datenum", return_raw)]
+    pub fn datenum(t: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        let t = super::as_datetime(&t, "datenum")?.0;
+        let days = chrono::Datelike::num_days_from_ce(&t) as i64 + super::DATENUM_OFFSET;
+        let time = t - t.date().and_time(chrono::NaiveTime::MIN);
+        Ok(days as FLOAT + Duration(time).as_seconds() / 86400.0)
+    }
+
+    /// Formats a `DateTime`, or a serial date number from `datenum`, as an ISO 8601 timestamp
+    /// such as `"2024-01-02T03:04:05"`, which `datetime` can read back. Serial date numbers are
+    /// rounded to the nearest millisecond.
+    /// ```typescript
+    /// assert_eq(datestr(datetime("2024-01-02T03:04:05")), "2024-01-02T03:04:05");
+    /// assert_eq(datestr(730486.75), "2000-01-01T18:00:00");
+    /// ```
+    #[rhai_fn(name = "datestr", return_raw)]
+    pub fn datestr(t: Dynamic) -> Result<ImmutableString, Box<EvalAltResult>> {
+        datestr_with_format(t, "%Y-%m-%dT%H:%M:%S%.f".into())
+    }
+
+    /// Formats a `DateTime`, or a serial date number from `datenum`, with a `strftime`-style
+    /// format, in which `%Y` is the year, `%m` the month, `%d` the day, `%H` the hour, `%M` the
+    /// minute, `%S` the second, `%b` the abbreviated month name, and `%a` the abbreviated day
+    /// name.
+    /// ```typescript
+    /// let t = datetime("2024-01-02T15:04:05");
+    /// assert_eq(datestr(t, "%d-%b-%Y %H:%M"), "02-Jan-2024 15:04");
+    /// assert_eq(datestr(t, "%a %Y/%m/%d"), "Tue 2024/01/02");
+    /// ```
+    #[rhai_fn(name = "datestr", return_raw)]
+    pub fn datestr_with_format(
+        t: Dynamic,
+        format: ImmutableString,
+    ) -> Result<ImmutableString, Box<EvalAltResult>> {
+        super::check_time_format(&format, "datestr")?;
+        let t = if t.is_int() || t.is_float() {
+            let x = crate::if_int_convert_to_float_and_do(t, Ok)?;
+            super::datetime_from_datenum(x).ok_or_else(|| {
+                EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        format!("{x} is not a date number that can be represented"),
+                    )
+                    .in_function("datestr")
+                    .with_argument("t"),
+                )
+            })?
+        } else {
+            super::as_datetime(&t, "datestr")?
+        };
+        let mut text = String::new();
+        std::fmt::Write::write_fmt(&mut text, format_args!("{}", t.0.format(&format))).map_err(
+            |_| {
+                EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        format!("Cannot format {t} with '{format}'"),
+                    )
+                    .in_function("datestr")
+                    .with_argument("format"),
+                )
+            },
+        )?;
+        Ok(text.into())
+    }
+
+    /// Buckets time-stamped data into regular intervals, such as hours or days, and combines the
+    /// values in each with `agg`: one of `"mean"`, `"sum"`, `"min"`, `"max"`, `"count"`,
+    /// `"first"`, or `"last"` (the earliest and latest in time). The times can be `DateTime`s or
+    /// timestamps that `datetime` can read, in any order. Intervals are aligned to multiples of
+    /// `interval` since 1970-01-01, so that daily intervals start at midnight and hourly ones on
+    /// the hour. Returns a map of the start `times` of every interval from the first to the last
+    /// and the combined `values`, which are `NaN` for empty intervals (or zero for `"sum"` and
+    /// `"count"`).
+    /// ```typescript
+    /// let times = ["2024-01-02T00:10", "2024-01-02T00:50", "2024-01-02T02:30"];
+    /// let r = resample_time(times, [1.0, 3.0, 10.0], hours(1), "mean");
+    /// assert_eq(r.times.map(|t| t.hour), [0, 1, 2]);
+    /// assert_eq(r.values[0], 2.0);
+    /// assert(r.values[1].is_nan());
+    /// assert_eq(r.values[2], 10.0);
+    /// ```
+    /// ```typescript
+    /// let times = [datetime("2024-01-03T09:00"), datetime("2024-01-01T12:00"), datetime("2024-01-01T08:00")];
+    /// let r = resample_time(times, [5, 2, 1], days(1), "count");
+    /// assert_eq(r.values, [2, 0, 1]);
+    /// assert_eq(r.times[0], datetime("2024-01-01"));
+    /// ```
+    #[rhai_fn(name = "resample_time", return_raw)]
+    pub fn resample_time(
+        ctx: NativeCallContext,
+        times: Array,
+        values: Array,
+        interval: Duration,
+        agg: ImmutableString,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let error =
+            |kind: crate::LabErrorKind, msg: String, argument: &str| -> Box<EvalAltResult> {
+                crate::LabError::new(kind, msg)
+                    .in_function("resample_time")
+                    .with_argument(argument)
+                    .into()
+            };
+        if times.len() != values.len() {
+            return Err(error(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "There are {} times but {} values",
+                    times.len(),
+                    values.len()
+                ),
+                "values",
+            ));
+        }
+        if interval.0 <= chrono::TimeDelta::zero() {
+            return Err(error(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The interval must be positive, not {interval}"),
+                "interval",
+            ));
+        }
+        if !super::RESAMPLE_AGGREGATIONS.contains(&agg.as_str()) {
+            return Err(error(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Unknown aggregation '{agg}', expected one of {:?}",
+                    super::RESAMPLE_AGGREGATIONS
+                ),
+                "agg",
+            ));
+        }
+
+        // Find the interval of each sample as a count of intervals since the epoch
+        let nanos = |d: chrono::TimeDelta| {
+            d.num_seconds() as i128 * 1_000_000_000 + d.subsec_nanos() as i128
+        };
+        let step = nanos(interval.0);
+        let epoch = chrono::DateTime::UNIX_EPOCH.naive_utc();
+        let mut samples = times
+            .iter()
+            .zip(&values)
+            .map(|(t, v)| {
+                let t = super::as_datetime(t, "resample_time")?.0;
+                let v = v
+                    .as_float()
+                    .or_else(|_| v.as_int().map(|i| i as FLOAT))
+                    .map_err(|_| {
+                        error(
+                            crate::LabErrorKind::TypeMismatch,
+                            format!("The values must be numbers, not {}", v.type_name()),
+                            "values",
+                        )
+                    })?;
+                Ok((t, nanos(t - epoch).div_euclid(step), v))
+            })
+            .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?;
+        samples.sort_by_key(|(t, _, _)| *t);
+
+        let (first, last) = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) => (first.1, last.1),
+            _ => (0, -1),
+        };
+        let n = usize::try_from(last - first + 1).unwrap_or(usize::MAX);
+        crate::check_data_size(&ctx, n.saturating_mul(2), 2)?;
+        let mut buckets = vec![vec![]; n];
+        for (_, k, v) in samples {
+            buckets[(k - first) as usize].push(v);
+        }
+
+        let mut out_times = Array::with_capacity(n);
+        for k in first..=last {
+            let offset = k * step;
+            let start = epoch
+                .checked_add_signed(chrono::TimeDelta::seconds(
+                    offset.div_euclid(1_000_000_000) as i64
+                ))
+                .map(|t| {
+                    t + chrono::TimeDelta::nanoseconds(offset.rem_euclid(1_000_000_000) as i64)
+                })
+                .ok_or_else(|| out_of_range("resample_time"))?;
+            out_times.push(Dynamic::from(DateTime(start)));
+        }
+        let out_values = buckets
+            .iter()
+            .map(|bucket| super::aggregate_bucket(bucket, &agg))
+            .collect::<Array>();
+
+        let mut result = Map::new();
+        result.insert("times".into(), Dynamic::from_array(out_times));
+        result.insert("values".into(), Dynamic::from_array(out_values));
+        Ok(result)
+    }
+
+    /// Formats a `DateTime` as an ISO 8601 timestamp.
+    /// ```typescript
+    /// assert_eq(to_string(datetime("2024-01-02T03:04")), "2024-01-02T03:04:00");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(t: &mut DateTime) -> ImmutableString {
+        t.to_string().into()
+    }
+
+    /// Formats a `Duration` as hours, minutes, and seconds, preceded by the number of days if
+    /// there are any.
+    /// ```typescript
+    /// assert_eq(to_string(seconds(3725.5)), "01:02:05.5");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn duration_to_string(d: &mut Duration) -> ImmutableString {
+        d.to_string().into()
+    }
+}
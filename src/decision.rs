@@ -0,0 +1,441 @@
+use rhai::plugin::*;
+
+/// Reads a matrix of numbers with a row for each alternative (or point) and a column for each
+/// criterion (or objective), checking that it is not empty and that every value is a number
+/// other than `NaN`.
+fn criteria_rows(
+    matrix: &rhai::Array,
+    function: &str,
+    argument: &str,
+) -> Result<Vec<Vec<rhai::FLOAT>>, Box<rhai::EvalAltResult>> {
+    let error = |message: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, message)
+            .in_function(function)
+            .with_argument(argument)
+            .into()
+    };
+    if matrix.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "There must be at least one row",
+        )
+        .in_function(function)
+        .with_argument(argument)
+        .into());
+    }
+    let mut rows = vec![];
+    for row in matrix {
+        if !row.is_array() {
+            return Err(error("Each row must be an array of numbers".to_string()));
+        }
+        let values = crate::if_list_convert_to_vec_float_and_do(&mut row.clone().cast(), Ok)?;
+        if values.iter().any(|v| v.is_nan()) {
+            return Err(error("The values cannot be NaN".to_string()));
+        }
+        rows.push(values);
+    }
+    let m = rows[0].len();
+    if m == 0 || rows.iter().any(|row| row.len() != m) {
+        return Err(error(
+            "Every row must have the same, non-zero number of columns".to_string(),
+        ));
+    }
+    Ok(rows)
+}
+
+/// Reads the weights of `m` criteria, which must be non-negative and not all zero, and scales
+/// them to add up to one.
+fn criteria_weights(
+    weights: &mut rhai::Array,
+    m: usize,
+    function: &str,
+) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    let w = crate::if_list_convert_to_vec_float_and_do(weights, Ok)?;
+    if w.len() != m {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("There are {m} criteria but {} weights", w.len()),
+        )
+        .in_function(function)
+        .with_argument("weights")
+        .into());
+    }
+    let total = crate::stable_sum_slice(&w);
+    if w.iter().any(|x| !(*x >= 0.0 && x.is_finite())) || total <= 0.0 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "The weights must be finite, non-negative, and not all zero",
+        )
+        .in_function(function)
+        .with_argument("weights")
+        .into());
+    }
+    Ok(w.into_iter().map(|x| x / total).collect())
+}
+
+/// Reads whether each of `m` criteria or objectives is to be maximized (`"max"`) or minimized
+/// (`"min"`).
+fn maximize_flags(
+    directions: &rhai::Array,
+    m: usize,
+    function: &str,
+) -> Result<Vec<bool>, Box<rhai::EvalAltResult>> {
+    if directions.len() != m {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("There are {m} columns but {} directions", directions.len()),
+        )
+        .in_function(function)
+        .with_argument("directions")
+        .into());
+    }
+    directions
+        .iter()
+        .map(|d| match d.clone().into_string().as_deref() {
+            Ok("max") => Ok(true),
+            Ok("min") => Ok(false),
+            _ => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Unknown direction '{d}', expected 'max' or 'min'"),
+            )
+            .in_function(function)
+            .with_argument("directions")
+            .into()),
+        })
+        .collect()
+}
+
+/// Returns a map of the `scores` of the alternatives and their `ranks`, where the alternative
+/// with the highest score is ranked 1 and tied alternatives share the best of their ranks.
+fn scores_and_ranks(scores: Vec<rhai::FLOAT>) -> rhai::Map {
+    let ranks = scores
+        .iter()
+        .map(|s| {
+            let better = scores.iter().filter(|other| *other > s).count();
+            rhai::Dynamic::from_int(better as rhai::INT + 1)
+        })
+        .collect::<rhai::Array>();
+    let mut result = rhai::Map::new();
+    result.insert(
+        "scores".into(),
+        rhai::Dynamic::from_array(scores.into_iter().map(rhai::Dynamic::from_float).collect()),
+    );
+    result.insert("ranks".into(), rhai::Dynamic::from_array(ranks));
+    result
+}
+
+/// Returns the indices of the rows that no other row dominates, in order, where every column is
+/// to be minimized. One row dominates another if it is no worse in every column and better in at
+/// least one, so rows that are exactly the same are either all kept or all dropped.
+fn non_dominated(rows: &[Vec<rhai::FLOAT>]) -> Vec<usize> {
+    if rows[0].len() == 2 {
+        // Sweep the points from left to right, keeping those lower than every point before them
+        let mut order = (0..rows.len()).collect::<Vec<usize>>();
+        order.sort_by(|&a, &b| {
+            rows[a][0]
+                .total_cmp(&rows[b][0])
+                .then(rows[a][1].total_cmp(&rows[b][1]))
+        });
+        let mut kept: Vec<usize> = vec![];
+        for i in order {
+            let keep = match kept.last() {
+                None => true,
+                Some(&last) => rows[i][1] < rows[last][1] || rows[i] == rows[last],
+            };
+            if keep {
+                kept.push(i);
+            }
+        }
+        kept.sort_unstable();
+        kept
+    } else {
+        let dominates = |a: &[rhai::FLOAT], b: &[rhai::FLOAT]| {
+            a.iter().zip(b).all(|(x, y)| x <= y) && a.iter().zip(b).any(|(x, y)| x < y)
+        };
+        (0..rows.len())
+            .filter(|&i| !rows.iter().any(|other| dominates(other, &rows[i])))
+            .collect()
+    }
+}
+
+/// Returns the area dominated by a set of points in two dimensions and bounded by a reference
+/// point, where both coordinates are minimized and every point is below the reference.
+fn dominated_area(
+    points: &[(rhai::FLOAT, rhai::FLOAT)],
+    reference: (rhai::FLOAT, rhai::FLOAT),
+) -> rhai::FLOAT {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    // Add a horizontal strip for each point that is lower than every point to its left
+    let mut lowest = reference.1;
+    let mut strips = vec![];
+    for (x, y) in sorted {
+        if y < lowest {
+            strips.push((reference.0 - x) * (lowest - y));
+            lowest = y;
+        }
+    }
+    crate::stable_sum_slice(&strips)
+}
+
+/// Returns the hypervolume dominated by the rows of a matrix of two or three objectives and
+/// bounded by a reference point, converting maximized objectives so every one is minimized.
+fn dominated_hypervolume(
+    front: &rhai::Array,
+    reference_point: &mut rhai::Array,
+    directions: &rhai::Array,
+) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>> {
+    let rows = criteria_rows(front, "hypervolume", "front")?;
+    let m = rows[0].len();
+    if m != 2 && m != 3 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("There must be 2 or 3 objectives, not {m}"),
+        )
+        .in_function("hypervolume")
+        .with_argument("front")
+        .into());
+    }
+    let reference = crate::if_list_convert_to_vec_float_and_do(reference_point, Ok)?;
+    if reference.len() != m || reference.iter().any(|r| r.is_nan()) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("The reference point must be {m} numbers, one for each objective"),
+        )
+        .in_function("hypervolume")
+        .with_argument("reference_point")
+        .into());
+    }
+    let maximize = maximize_flags(directions, m, "hypervolume")?;
+    let sign = |j: usize| if maximize[j] { -1.0 } else { 1.0 };
+    let reference = (0..m).map(|j| sign(j) * reference[j]).collect::<Vec<_>>();
+
+    // Only the points that are better than the reference in every objective add any volume
+    let mut points = rows
+        .into_iter()
+        .map(|row| (0..m).map(|j| sign(j) * row[j]).collect::<Vec<_>>())
+        .filter(|row| row.iter().zip(&reference).all(|(x, r)| x < r))
+        .collect::<Vec<_>>();
+
+    if m == 2 {
+        let points = points.iter().map(|p| (p[0], p[1])).collect::<Vec<_>>();
+        return Ok(dominated_area(&points, (reference[0], reference[1])));
+    }
+
+    // Sweep up through the third objective, adding the area dominated below each slab
+    points.sort_by(|a, b| a[2].total_cmp(&b[2]));
+    let mut slice = vec![];
+    let mut slabs = vec![];
+    for (i, point) in points.iter().enumerate() {
+        slice.push((point[0], point[1]));
+        let top = points.get(i + 1).map_or(reference[2], |next| next[2]);
+        slabs.push(dominated_area(&slice, (reference[0], reference[1])) * (top - point[2]));
+    }
+    Ok(crate::stable_sum_slice(&slabs))
+}
+
+#[export_module]
+pub mod decision_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
+
+    /// Scores alternatives with the weighted sum model, given a decision matrix with a row for
+    /// each alternative and a column for each criterion, and a weight for each criterion. The
+    /// weights are scaled to add up to one, and each score is the weighted sum of the row, so
+    /// every criterion should be on a comparable scale where larger is better. Returns a map of
+    /// the `scores` and the `ranks` of the alternatives, where the best is ranked 1.
+    /// ```typescript,only_f64
+    /// let matrix = [[0.8, 0.4],
+    ///               [0.5, 0.9],
+    ///               [0.6, 0.6]];
+    /// let result = wsm(matrix, [3, 1]);
+    /// assert_approx_eq(result.scores, [0.7, 0.6, 0.6]);
+    /// assert_eq(result.ranks, [1, 2, 2]);
+    /// ```
+    #[rhai_fn(name = "wsm", return_raw)]
+    pub fn wsm(decision_matrix: Array, mut weights: Array) -> Result<Map, Box<EvalAltResult>> {
+        let rows = super::criteria_rows(&decision_matrix, "wsm", "decision_matrix")?;
+        let w = super::criteria_weights(&mut weights, rows[0].len(), "wsm")?;
+        let scores = rows
+            .iter()
+            .map(|row| crate::stable_sum(row.iter().zip(&w).map(|(x, w)| x * w)))
+            .collect();
+        Ok(super::scores_and_ranks(scores))
+    }
+
+    /// Scores alternatives with TOPSIS (the technique for order of preference by similarity to
+    /// ideal solution), given a decision matrix with a row for each alternative and a column for
+    /// each criterion, a weight for each criterion, and whether each criterion is to be
+    /// maximized (`"max"`) or minimized (`"min"`). The columns are divided by their Euclidean
+    /// norms, so criteria can be in any units, and each score is the relative closeness of an
+    /// alternative to the ideal one, from 0.0 (the worst on every criterion) to 1.0 (the best).
+    /// Returns a map of the `scores` and the `ranks` of the alternatives, where the best is
+    /// ranked 1.
+    /// ```typescript
+    /// // Price, performance, and weight of three designs
+    /// let matrix = [[250, 16, 12],
+    ///               [200, 16, 8],
+    ///               [300, 32, 16]];
+    /// let result = topsis(matrix, [0.4, 0.4, 0.2], ["min", "max", "min"]);
+    /// assert_eq(result.ranks, [3, 2, 1]);
+    /// ```
+    /// ```typescript
+    /// let result = topsis([[1, 1], [2, 2]], [1, 1], ["max", "max"]);
+    /// assert_eq(result.scores, [0.0, 1.0]);
+    /// ```
+    #[rhai_fn(name = "topsis", return_raw)]
+    pub fn topsis(
+        decision_matrix: Array,
+        mut weights: Array,
+        criteria_directions: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let rows = super::criteria_rows(&decision_matrix, "topsis", "decision_matrix")?;
+        let m = rows[0].len();
+        let w = super::criteria_weights(&mut weights, m, "topsis")?;
+        let maximize = super::maximize_flags(&criteria_directions, m, "topsis")?;
+
+        // Normalize and weight each column, leaving columns of zeros as they are
+        let mut weighted = rows;
+        for j in 0..m {
+            let norm = crate::stable_sum(weighted.iter().map(|row| row[j] * row[j])).sqrt();
+            let scale = if norm > 0.0 { w[j] / norm } else { 0.0 };
+            for row in weighted.iter_mut() {
+                row[j] *= scale;
+            }
+        }
+
+        // Find the ideal and the anti-ideal alternatives
+        let (mut best, mut worst) = (vec![0.0; m], vec![0.0; m]);
+        for j in 0..m {
+            let column = weighted.iter().map(|row| row[j]);
+            let (lo, hi) = (
+                column.clone().fold(FLOAT::INFINITY, FLOAT::min),
+                column.fold(FLOAT::NEG_INFINITY, FLOAT::max),
+            );
+            (best[j], worst[j]) = if maximize[j] { (hi, lo) } else { (lo, hi) };
+        }
+
+        let distance = |row: &[FLOAT], target: &[FLOAT]| {
+            crate::stable_sum(row.iter().zip(target).map(|(x, t)| (x - t).powi(2))).sqrt()
+        };
+        let scores = weighted
+            .iter()
+            .map(|row| {
+                let (to_best, to_worst) = (distance(row, &best), distance(row, &worst));
+                // Every alternative is both the best and the worst if they are all the same
+                if to_best + to_worst == 0.0 {
+                    1.0
+                } else {
+                    to_worst / (to_best + to_worst)
+                }
+            })
+            .collect();
+        Ok(super::scores_and_ranks(scores))
+    }
+
+    /// Finds the Pareto front of a matrix of objective values with a row for each point and a
+    /// column for each objective, where every objective is minimized. A point is on the front if
+    /// no other point is at least as good in every objective and better in one. Returns a map of
+    /// the `points` on the front and their `indices` in the matrix, in their original order.
+    /// ```typescript
+    /// let points = [[1, 5], [2, 3], [3, 4], [4, 1]];
+    /// let front = pareto_front(points);
+    /// assert_eq(front.indices, [0, 1, 3]);
+    /// assert_eq(front.points, [[1, 5], [2, 3], [4, 1]]);
+    /// ```
+    #[rhai_fn(name = "pareto_front", return_raw)]
+    pub fn pareto_front(points: Array) -> Result<Map, Box<EvalAltResult>> {
+        let m = super::criteria_rows(&points, "pareto_front", "points")?[0].len();
+        let directions = vec![Dynamic::from("min"); m];
+        pareto_front_with_directions(points, directions)
+    }
+
+    /// Finds the Pareto front of a matrix of objective values with a row for each point and a
+    /// column for each objective, given whether each objective is to be maximized (`"max"`) or
+    /// minimized (`"min"`). A point is on the front if no other point is at least as good in
+    /// every objective and better in one, and points that are exactly the same are kept together.
+    /// Two objectives take O(n log n) time, and more take O(n²). Returns a map of the `points`
+    /// on the front and their `indices` in the matrix, in their original order.
+    /// ```typescript
+    /// // Cost and strength of four designs
+    /// let designs = [[10, 50], [12, 70], [15, 65], [20, 90]];
+    /// let front = pareto_front(designs, ["min", "max"]);
+    /// assert_eq(front.indices, [0, 1, 3]);
+    /// ```
+    /// ```typescript
+    /// let points = [[1, 2, 3], [3, 2, 1], [2, 2, 2], [3, 3, 3]];
+    /// let front = pareto_front(points, ["min", "min", "min"]);
+    /// assert_eq(front.indices, [0, 1, 2]);
+    /// ```
+    #[rhai_fn(name = "pareto_front", return_raw)]
+    pub fn pareto_front_with_directions(
+        points: Array,
+        directions: Array,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut rows = super::criteria_rows(&points, "pareto_front", "points")?;
+        let maximize = super::maximize_flags(&directions, rows[0].len(), "pareto_front")?;
+        for row in rows.iter_mut() {
+            for (x, &max) in row.iter_mut().zip(&maximize) {
+                if max {
+                    *x = -*x;
+                }
+            }
+        }
+        let indices = super::non_dominated(&rows);
+        let mut result = Map::new();
+        result.insert(
+            "points".into(),
+            Dynamic::from_array(indices.iter().map(|&i| points[i].clone()).collect()),
+        );
+        result.insert(
+            "indices".into(),
+            Dynamic::from_array(
+                indices
+                    .into_iter()
+                    .map(|i| Dynamic::from_int(i as INT))
+                    .collect(),
+            ),
+        );
+        Ok(result)
+    }
+
+    /// Computes the hypervolume indicator of a set of points in a space of two or three
+    /// objectives, all of which are minimized: the area or volume that the points dominate,
+    /// bounded by a reference point that is worse than every point in every objective. Points
+    /// that are not better than the reference point in every objective add nothing, and larger
+    /// values mean a better front.
+    /// ```typescript
+    /// let front = [[1, 3], [2, 2], [3, 1]];
+    /// assert_eq(hypervolume(front, [4, 4]), 6.0);
+    /// ```
+    /// ```typescript
+    /// let front = [[0, 0, 1], [1, 1, 0]];
+    /// assert_eq(hypervolume(front, [2, 2, 2]), 5.0);
+    /// ```
+    #[rhai_fn(name = "hypervolume", return_raw)]
+    pub fn hypervolume(
+        front: Array,
+        mut reference_point: Array,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let m = reference_point.len();
+        let directions = vec![Dynamic::from("min"); m];
+        super::dominated_hypervolume(&front, &mut reference_point, &directions)
+    }
+
+    /// Computes the hypervolume indicator of a set of points in a space of two or three
+    /// objectives, given whether each objective is to be maximized (`"max"`) or minimized
+    /// (`"min"`): the area or volume that the points dominate, bounded by a reference point that
+    /// is worse than every point in every objective.
+    /// ```typescript
+    /// // Cost and strength of the designs on a Pareto front
+    /// let front = [[10, 50], [12, 70], [20, 90]];
+    /// assert_eq(hypervolume(front, [25, 0], ["min", "max"]), 1110.0);
+    /// ```
+    #[rhai_fn(name = "hypervolume", return_raw)]
+    pub fn hypervolume_with_directions(
+        front: Array,
+        mut reference_point: Array,
+        directions: Array,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        super::dominated_hypervolume(&front, &mut reference_point, &directions)
+    }
+}
@@ -0,0 +1,699 @@
+use rhai::plugin::*;
+
+/// The element-wise operators, the custom operators they are rewritten to, and their precedence.
+/// Rhai reads `.*` as two tokens, so the operators cannot be registered directly.
+const ELEMENTWISE_OPERATORS: [(&str, &str, u8); 3] = [
+    (".*", "__dot_times__", 180),
+    ("./", "__dot_divide__", 180),
+    (".^", "__dot_power__", 190),
+];
+
+/// A function of two values, as registered for a custom operator.
+type BinaryFunction =
+    fn(rhai::Dynamic, rhai::Dynamic) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>>;
+
+/// An operation on two numbers, applied by `elementwise`.
+type ScalarOp =
+    dyn Fn(&rhai::Dynamic, &rhai::Dynamic) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>>;
+
+/// Registers the custom operators that `translate_elementwise_operators` rewrites `.*`, `./`, and
+/// `.^` into, so that scripts can use MATLAB-style element-wise arithmetic. The functions behind
+/// the operators are also available as `times`, `rdivide`, and `power`.
+/// ```
+/// use rhai::{packages::Package, Engine, FLOAT};
+/// use rhai_sci::{register_elementwise_operators, translate_elementwise_operators, SciPackage};
+///
+/// let mut engine = Engine::new();
+/// engine.register_global_module(SciPackage::new().as_shared_module());
+/// register_elementwise_operators(&mut engine);
+///
+/// let script = translate_elementwise_operators("let x = [1.0, 2.0, 3.0]; sum(x .* x ./ 2)");
+/// assert_eq!(engine.eval::<FLOAT>(&script).unwrap(), 7.0);
+/// ```
+pub fn register_elementwise_operators(engine: &mut rhai::Engine) {
+    let ops: [BinaryFunction; 3] = [
+        elementwise_functions::times,
+        elementwise_functions::rdivide,
+        elementwise_functions::power,
+    ];
+    for ((_, keyword, precedence), op) in ELEMENTWISE_OPERATORS.iter().zip(ops) {
+        engine
+            .register_custom_operator(*keyword, *precedence)
+            .expect("element-wise operators are valid custom operators");
+        engine.register_fn(*keyword, op);
+    }
+}
+
+/// Rewrites the element-wise operators `.*`, `./`, and `.^` in a script into the custom operators
+/// registered by `register_elementwise_operators`. String literals and comments are left as they
+/// are.
+/// ```
+/// use rhai_sci::translate_elementwise_operators;
+///
+/// let script = translate_elementwise_operators("a .^ 2 // squares \".*\"");
+/// assert_eq!(script, "a  __dot_power__  2 // squares \".*\"");
+/// ```
+pub fn translate_elementwise_operators(script: &str) -> String {
+    let chars = script.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(script.len());
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let next = chars.get(idx + 1).copied();
+
+        // Copy literals and comments through to their end
+        if let Some(end) = literal_end(&chars, idx) {
+            result.extend(&chars[idx..end]);
+            idx = end;
+            continue;
+        }
+
+        let operator = ELEMENTWISE_OPERATORS.iter().find(|(symbol, _, _)| {
+            let mut symbol = symbol.chars();
+            symbol.next() == Some(c) && symbol.next() == next
+        });
+        if let Some((_, keyword, _)) = operator {
+            result.push(' ');
+            result.push_str(keyword);
+            result.push(' ');
+            idx += 2;
+        } else {
+            result.push(c);
+            idx += 1;
+        }
+    }
+    result
+}
+
+/// Finds the end of the string or character literal, or comment, that starts at `idx` in a
+/// script, if there is one there.
+pub(crate) fn literal_end(chars: &[char], idx: usize) -> Option<usize> {
+    let c = chars[idx];
+    let end = match (c, chars.get(idx + 1)) {
+        ('"' | '\'' | '`', _) => {
+            let mut end = idx + 1;
+            while end < chars.len() && chars[end] != c {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            end + 1
+        }
+        ('/', Some('/')) => (idx..chars.len())
+            .find(|k| chars[*k] == '\n')
+            .unwrap_or(chars.len()),
+        ('/', Some('*')) => (idx + 2..chars.len())
+            .find(|k| chars[*k - 1] == '*' && chars[*k] == '/')
+            .map_or(chars.len(), |k| k + 1),
+        _ => return None,
+    };
+    Some(end.min(chars.len()))
+}
+
+/// Finds the shape of a number (`[]`) or nested array, following the first element of each level.
+fn broadcast_shape(x: &rhai::Dynamic) -> Vec<usize> {
+    let mut shape = vec![];
+    let mut current = x.clone();
+    while let Some(arr) = current.clone().try_cast::<rhai::Array>() {
+        shape.push(arr.len());
+        match arr.into_iter().next() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+    shape
+}
+
+/// Applies a binary operation element by element to two numbers or nested arrays, broadcasting
+/// them to a common shape as NumPy does: shapes are aligned at their last dimension, and each pair
+/// of dimensions must either match or include a 1, which is repeated to fit. A missing leading
+/// dimension acts as a 1, so a number combines with every element of an array, a row vector with
+/// every row of a matrix, and a column vector (`[[1], [2]]`) with every column. An array that mixes
+/// integers and floats is promoted to floats first.
+///
+/// Tensors, and large arrays of floats when the `parallel` feature is enabled, are handled with
+/// the kernels for `float_op` instead, which must agree with `op` on floats.
+fn elementwise(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+    op: &ScalarOp,
+    float_op: crate::FloatOp,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    #[cfg(feature = "tensor")]
+    if a.is::<crate::Tensor>() || b.is::<crate::Tensor>() {
+        return tensor_elementwise(a, b, float_op);
+    }
+    if a.is::<crate::Matrix>() || b.is::<crate::Matrix>() {
+        return matrix_elementwise(a, b, float_op);
+    }
+    let promote = |x: &rhai::Dynamic| match x.clone().try_cast::<rhai::Array>() {
+        Some(arr) => rhai::Dynamic::from_array(crate::promote_numbers(arr)),
+        None => x.clone(),
+    };
+    let (a, b) = (&promote(a), &promote(b));
+    let (shape_a, shape_b) = (broadcast_shape(a), broadcast_shape(b));
+    let compatible = shape_a
+        .iter()
+        .rev()
+        .zip(shape_b.iter().rev())
+        .all(|(x, y)| x == y || *x == 1 || *y == 1);
+    if !compatible {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("Cannot broadcast arrays with shapes {shape_a:?} and {shape_b:?} together"),
+        )
+        .into());
+    }
+    let size = |shape: &[usize]| shape.iter().product::<usize>();
+    if crate::use_parallel(size(&shape_a).max(size(&shape_b))) {
+        if let Some(result) = parallel_elementwise(a, b, float_op) {
+            return Ok(result);
+        }
+    }
+    broadcast(a, shape_a.len(), b, shape_b.len(), op)
+}
+
+/// Applies `float_op` element by element across threads if `a` and `b` are floats or rectangular
+/// arrays of floats with the same shape, or one of them is a single float. Returns `None` for
+/// anything else, which is left to `broadcast`.
+fn parallel_elementwise(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+    float_op: crate::FloatOp,
+) -> Option<rhai::Dynamic> {
+    use crate::Operand::{Scalar, Slice};
+    let ((shape_a, x), (shape_b, y)) = (float_block(a)?, float_block(b)?);
+    let result = if shape_a == shape_b {
+        crate::zip_floats(float_op, Slice(&x), Slice(&y), x.len())
+    } else if shape_b.is_empty() {
+        crate::zip_floats(float_op, Slice(&x), Scalar(y[0]), x.len())
+    } else if shape_a.is_empty() {
+        crate::zip_floats(float_op, Scalar(x[0]), Slice(&y), y.len())
+    } else {
+        return None;
+    };
+    let shape = if shape_a.is_empty() { shape_b } else { shape_a };
+    Some(unflatten_floats(&shape, &result))
+}
+
+/// Applies `float_op` element by element to two tensors of the same shape, or to a tensor and a
+/// number or nested array, with the kernels that the `simd` feature vectorizes.
+#[cfg(feature = "tensor")]
+fn tensor_elementwise(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+    float_op: crate::FloatOp,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    use crate::Operand::{Scalar, Slice};
+    let as_tensor = |x: &rhai::Dynamic| match x.clone().try_cast::<crate::Tensor>() {
+        Some(t) => Ok(Some(t)),
+        None => match x.read_lock::<rhai::Array>() {
+            Some(arr) => crate::Tensor::from_array(&arr).map(Some),
+            None => Ok(None),
+        },
+    };
+    let scalar = |x: &rhai::Dynamic| crate::if_int_convert_to_float_and_do(x.clone(), Ok);
+    let (ta, tb) = (as_tensor(a)?, as_tensor(b)?);
+    let (shape, data) = match (&ta, &tb) {
+        (Some(x), Some(y)) => {
+            if x.shape() != y.shape() {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "Tensors of shapes {:?} and {:?} cannot be combined element by element",
+                        x.shape(),
+                        y.shape()
+                    ),
+                )
+                .into());
+            }
+            let n = x.data().len();
+            let data = crate::zip_floats(float_op, Slice(x.data()), Slice(y.data()), n);
+            (x.shape(), data)
+        }
+        (Some(x), None) => {
+            let n = x.data().len();
+            let data = crate::zip_floats(float_op, Slice(x.data()), Scalar(scalar(b)?), n);
+            (x.shape(), data)
+        }
+        (None, Some(y)) => {
+            let n = y.data().len();
+            let data = crate::zip_floats(float_op, Scalar(scalar(a)?), Slice(y.data()), n);
+            (y.shape(), data)
+        }
+        (None, None) => unreachable!("one of the arguments is a tensor"),
+    };
+    crate::Tensor::new(shape.to_vec(), data).map(rhai::Dynamic::from)
+}
+
+/// Applies `float_op` element by element to a `Matrix` and another matrix, nested array, or
+/// number, giving a `Matrix`. Matrices are broadcast together as `elementwise` does for arrays, so
+/// a row vector is combined with every row and a column vector with every column.
+fn matrix_elementwise(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+    float_op: crate::FloatOp,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    use crate::Operand::{Scalar, Slice};
+    let as_matrix = |x: &rhai::Dynamic| match x.clone().try_cast::<crate::Matrix>() {
+        Some(m) => Ok(Some(m)),
+        None => match x.read_lock::<rhai::Array>() {
+            Some(arr) => crate::Matrix::from_array(&arr).map(Some),
+            None => Ok(None),
+        },
+    };
+    let scalar = |x: &rhai::Dynamic| crate::if_int_convert_to_float_and_do(x.clone(), Ok);
+    let (ma, mb) = (as_matrix(a)?, as_matrix(b)?);
+    let (rows, cols, data) = match (&ma, &mb) {
+        (Some(x), Some(y)) => {
+            let fits = |m: usize, n: usize| m == n || m == 1 || n == 1;
+            if !fits(x.rows(), y.rows()) || !fits(x.cols(), y.cols()) {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "Cannot broadcast matrices with shapes [{}, {}] and [{}, {}] together",
+                        x.rows(),
+                        x.cols(),
+                        y.rows(),
+                        y.cols()
+                    ),
+                )
+                .into());
+            }
+            // A dimension of 1 is repeated to fit the other matrix
+            let fit = |m: usize, n: usize| if m == 1 { n } else { m };
+            let (rows, cols) = (fit(x.rows(), y.rows()), fit(x.cols(), y.cols()));
+            fn row(m: &crate::Matrix, i: usize, cols: usize) -> crate::Operand<'_> {
+                let start = if m.rows() == 1 { 0 } else { i * m.cols() };
+                let row = &m.data()[start..start + m.cols()];
+                if m.cols() == 1 && cols != 1 {
+                    Scalar(row[0])
+                } else {
+                    Slice(row)
+                }
+            }
+            let mut data = Vec::with_capacity(rows * cols);
+            for i in 0..rows {
+                let (a, b) = (row(x, i, cols), row(y, i, cols));
+                data.extend(crate::zip_floats(float_op, a, b, cols));
+            }
+            (rows, cols, data)
+        }
+        (Some(x), None) => {
+            let n = x.data().len();
+            let data = crate::zip_floats(float_op, Slice(x.data()), Scalar(scalar(b)?), n);
+            (x.rows(), x.cols(), data)
+        }
+        (None, Some(y)) => {
+            let n = y.data().len();
+            let data = crate::zip_floats(float_op, Scalar(scalar(a)?), Slice(y.data()), n);
+            (y.rows(), y.cols(), data)
+        }
+        (None, None) => unreachable!("one of the arguments is a matrix"),
+    };
+    crate::Matrix::new(rows, cols, data).map(rhai::Dynamic::from)
+}
+
+/// Returns the shape and the elements, in row-major order, of a float or a non-empty rectangular
+/// nested array of floats, or `None` for anything else.
+fn float_block(x: &rhai::Dynamic) -> Option<(Vec<usize>, Vec<rhai::FLOAT>)> {
+    if let Ok(x) = x.as_float() {
+        return Some((vec![], vec![x]));
+    }
+    let arr = x.read_lock::<rhai::Array>()?;
+    let mut shape = None;
+    let mut data = vec![];
+    for el in arr.iter() {
+        let (el_shape, el_data) = float_block(el)?;
+        if shape.get_or_insert_with(|| el_shape.clone()) != &el_shape {
+            return None;
+        }
+        data.extend(el_data);
+    }
+    let mut shape = shape?;
+    shape.insert(0, arr.len());
+    Some((shape, data))
+}
+
+/// Builds a float or nested array of floats with the given shape from its elements in row-major
+/// order, undoing `float_block`.
+fn unflatten_floats(shape: &[usize], data: &[rhai::FLOAT]) -> rhai::Dynamic {
+    match shape.split_first() {
+        None => rhai::Dynamic::from_float(data[0]),
+        Some((n, rest)) => rhai::Dynamic::from_array(
+            data.chunks(data.len() / n)
+                .map(|chunk| unflatten_floats(rest, chunk))
+                .collect(),
+        ),
+    }
+}
+
+/// Applies `op` to `a` and `b`, which have `rank_a` and `rank_b` dimensions and compatible shapes.
+fn broadcast(
+    a: &rhai::Dynamic,
+    rank_a: usize,
+    b: &rhai::Dynamic,
+    rank_b: usize,
+    op: &ScalarOp,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    if rank_a == 0 && rank_b == 0 {
+        return op(a, b);
+    }
+    let ragged = || -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            "Arrays must be rectangular to be broadcast together",
+        )
+        .into()
+    };
+
+    // The array with more dimensions is iterated on its own
+    let elements = |x: &rhai::Dynamic| x.clone().try_cast::<rhai::Array>().ok_or_else(ragged);
+    let result = if rank_a > rank_b {
+        elements(a)?
+            .iter()
+            .map(|x| broadcast(x, rank_a - 1, b, rank_b, op))
+            .collect::<Result<rhai::Array, _>>()?
+    } else if rank_b > rank_a {
+        elements(b)?
+            .iter()
+            .map(|y| broadcast(a, rank_a, y, rank_b - 1, op))
+            .collect::<Result<rhai::Array, _>>()?
+    } else {
+        let (a, b) = (elements(a)?, elements(b)?);
+        if a.len() != b.len() && a.len() != 1 && b.len() != 1 {
+            return Err(ragged());
+        }
+        let n = if a.len() == 1 { b.len() } else { a.len() };
+        (0..n)
+            .map(|i| {
+                let x = &a[if a.len() == 1 { 0 } else { i }];
+                let y = &b[if b.len() == 1 { 0 } else { i }];
+                broadcast(x, rank_a - 1, y, rank_b - 1, op)
+            })
+            .collect::<Result<rhai::Array, _>>()?
+    };
+    Ok(rhai::Dynamic::from_array(result))
+}
+
+/// Applies an arithmetic operation to two numbers, using `int_op` if both are integers (which
+/// fails with an overflow error if it returns `None`), and `float_op` otherwise.
+fn arithmetic(
+    x: &rhai::Dynamic,
+    y: &rhai::Dynamic,
+    int_op: fn(rhai::INT, rhai::INT) -> Option<rhai::INT>,
+    float_op: fn(rhai::FLOAT, rhai::FLOAT) -> rhai::FLOAT,
+    name: &str,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    match (x.as_int(), y.as_int()) {
+        (Ok(x), Ok(y)) => int_op(x, y).map(rhai::Dynamic::from_int).ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::Numerical,
+                format!("{name} overflow: {x} and {y}"),
+            )
+            .into()
+        }),
+        _ => float_pair(x, y).map(|(x, y)| rhai::Dynamic::from_float(float_op(x, y))),
+    }
+}
+
+/// Converts a pair of numbers to floats.
+fn float_pair(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+) -> Result<(rhai::FLOAT, rhai::FLOAT), Box<rhai::EvalAltResult>> {
+    Ok((
+        crate::if_int_convert_to_float_and_do(a.clone(), Ok)?,
+        crate::if_int_convert_to_float_and_do(b.clone(), Ok)?,
+    ))
+}
+
+#[export_module]
+pub mod elementwise_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
+
+    /// Adds two numbers or arrays element by element. Arrays can be nested (for example,
+    /// matrices), and are broadcast to a common shape as in NumPy: shapes are aligned at their
+    /// last dimension, and dimensions must either match or be 1. This means a number is added to
+    /// every element, a row vector to every row of a matrix, and a column vector (such as
+    /// `[[1], [2]]`) to every column. Integers stay integers, but an array that mixes integers and
+    /// floats is treated as all floats.
+    ///
+    /// `+` is not element-wise for arrays: Rhai itself makes `+` between two arrays concatenate
+    /// them and `+=` on an array push onto it, and that cannot be changed, so `+` is left to mean
+    /// the same thing everywhere and adding an array and a number with `+` is an error. Use `plus`
+    /// to add element by element. `-`, `*`, and `/` have no such meaning in Rhai, so they are
+    /// element-wise between an array and a number, and `-` is also element-wise between two arrays.
+    ///
+    /// A `Tensor` can be combined with a tensor of the same shape, a number, or a nested array,
+    /// giving a tensor. This works on the contiguous elements directly, several at a time with the
+    /// `simd` feature, so it is much faster than nested arrays for large data. The same goes for
+    /// `minus`, `times`, `rdivide`, and `power`. A `Matrix` can be combined with another matrix, a
+    /// number, or a nested array, which are broadcast together in the same way, giving a `Matrix`.
+    /// ```typescript
+    /// let m = [[1, 2, 3], [4, 5, 6]];
+    /// assert_eq(plus(m, [10, 20, 30]), [[11, 22, 33], [14, 25, 36]]);
+    /// ```
+    /// ```typescript
+    /// let v = [1, 2.5];
+    /// assert_eq(plus(v, 1), [2.0, 3.5]);
+    /// ```
+    /// ```typescript
+    /// let m = [[1.0, 2.0], [3.0, 4.0]];
+    /// assert_eq(plus(m, 1.0), [[2.0, 3.0], [4.0, 5.0]]);
+    /// assert_eq(m - 1.0, [[0.0, 1.0], [2.0, 3.0]]);
+    /// ```
+    /// ```typescript
+    /// let x = [1, 2];
+    /// assert_eq(plus(x, [10, 20]), [11, 22]);
+    /// assert_eq(x + [10, 20], [1, 2, 10, 20]);
+    /// assert_eq(x - [1, 1], [0, 1]);
+    /// x += 1;
+    /// assert_eq(x, [1, 2, 1]);
+    /// let y = [1, 2];
+    /// y -= 1;
+    /// assert_eq(y, [0, 1]);
+    /// ```
+    /// ```typescript
+    /// let result = "no error";
+    /// try {
+    ///     [1, 2] + 1;
+    /// } catch {
+    ///     result = "error";
+    /// }
+    /// assert_eq(result, "error");
+    /// ```
+    /// ```typescript
+    /// let m = matrix([[1, 2, 3], [4, 5, 6]]);
+    /// assert(plus(m, [10, 20, 30]) == matrix([[11, 22, 33], [14, 25, 36]]));
+    /// assert(m - matrix([[1], [4]]) == matrix([[0, 1, 2], [0, 1, 2]]));
+    /// assert(m .* [[2], [3]] == matrix([[2, 4, 6], [12, 15, 18]]));
+    /// ```
+    /// ```typescript
+    /// let t = tensor([[1, 2], [3, 4]]);
+    /// assert_eq(plus(t, t), tensor([[2, 4], [6, 8]]));
+    /// assert_eq(to_array(plus(t, 0.5)), [[1.5, 2.5], [3.5, 4.5]]);
+    /// ```
+    #[rhai_fn(name = "plus", return_raw)]
+    pub fn plus(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::elementwise(
+            &a,
+            &b,
+            &|x, y| super::arithmetic(x, y, INT::checked_add, |x, y| x + y, "Addition"),
+            crate::FloatOp::Add,
+        )
+        .map_err(crate::in_function("plus", None))
+    }
+
+    /// Subtracts two numbers or arrays element by element, broadcasting them to a common shape as
+    /// `plus` does. The `-` operator does the same for arrays and numbers.
+    /// ```typescript
+    /// let m = [[1, 2], [3, 4]];
+    /// assert_eq(m - [[1], [3]], [[0, 1], [0, 1]]);
+    /// ```
+    /// ```typescript
+    /// let m = [[1, 2], [3, 4]];
+    /// assert_eq(minus(10, m), [[9, 8], [7, 6]]);
+    /// ```
+    #[rhai_fn(name = "minus", return_raw)]
+    pub fn minus(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::elementwise(
+            &a,
+            &b,
+            &|x, y| super::arithmetic(x, y, INT::checked_sub, |x, y| x - y, "Subtraction"),
+            crate::FloatOp::Sub,
+        )
+        .map_err(crate::in_function("minus", None))
+    }
+
+    /// Multiplies two numbers or arrays element by element, broadcasting them to a common shape as
+    /// `plus` does. Integers stay integers. Scripts run with `eval` can also write this as
+    /// `a .* b`, and the `*` operator does the same for an array and a number.
+    /// ```typescript
+    /// let c = times([[1, 2], [3, 4]], [[5, 6], [7, 8]]);
+    /// assert_eq(c, [[5, 12], [21, 32]]);
+    /// ```
+    /// ```typescript
+    /// let c = [1.0, 2.0] .* 3;
+    /// assert_eq(c, [3.0, 6.0]);
+    /// ```
+    /// ```typescript
+    /// let c = [[1, 2], [3, 4]] .* [[10], [100]];
+    /// assert_eq(c, [[10, 20], [300, 400]]);
+    /// ```
+    /// ```typescript
+    /// let err = #{};
+    /// try {
+    ///     ["a", "b"] .* 2;
+    /// } catch (error) {
+    ///     err = error;
+    /// }
+    /// assert_eq(err.kind, "type_mismatch");
+    /// assert_eq(err.function, "times");
+    /// ```
+    #[rhai_fn(name = "times", return_raw)]
+    pub fn times(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::elementwise(
+            &a,
+            &b,
+            &|x, y| super::arithmetic(x, y, INT::checked_mul, |x, y| x * y, "Multiplication"),
+            crate::FloatOp::Mul,
+        )
+        .map_err(crate::in_function("times", None))
+    }
+
+    /// Divides two numbers or arrays element by element, broadcasting them to a common shape as
+    /// `plus` does. The result is always a float. Scripts run with `eval` can also write this as
+    /// `a ./ b`, and the `/` operator does the same for an array and a number.
+    /// ```typescript
+    /// let c = rdivide([1, 2, 3], [2, 4, 6]);
+    /// assert_eq(c, [0.5, 0.5, 0.5]);
+    /// ```
+    /// ```typescript
+    /// let c = 1 ./ [[1, 2], [4, 8]];
+    /// assert_eq(c, [[1.0, 0.5], [0.25, 0.125]]);
+    /// ```
+    #[rhai_fn(name = "rdivide", return_raw)]
+    pub fn rdivide(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::elementwise(
+            &a,
+            &b,
+            &|x, y| super::float_pair(x, y).map(|(x, y)| Dynamic::from_float(x / y)),
+            crate::FloatOp::Div,
+        )
+        .map_err(crate::in_function("rdivide", None))
+    }
+
+    /// Raises numbers or arrays to a power element by element, broadcasting them to a common shape
+    /// as `plus` does. Integers raised to non-negative integer powers stay integers. Scripts run
+    /// with `eval` can also write this as `a .^ b`.
+    /// ```typescript
+    /// let c = power([1, 2, 3], 2);
+    /// assert_eq(c, [1, 4, 9]);
+    /// ```
+    /// ```typescript
+    /// let c = [4.0, 9.0] .^ 0.5;
+    /// assert_eq(c, [2.0, 3.0]);
+    /// ```
+    /// ```typescript
+    /// let c = 2 .^ [1, -1];
+    /// assert_eq(c, [2, 0.5]);
+    /// ```
+    #[rhai_fn(name = "power", return_raw)]
+    pub fn power(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::elementwise(
+            &a,
+            &b,
+            &|x, y| match y.as_int() {
+                Ok(p) if p < 0 => {
+                    super::float_pair(x, y).map(|(x, y)| Dynamic::from_float(x.powf(y)))
+                }
+                _ => super::arithmetic(
+                    x,
+                    y,
+                    |x, p| x.checked_pow(u32::try_from(p).ok()?),
+                    FLOAT::powf,
+                    "Exponentiation",
+                ),
+            },
+            crate::FloatOp::Pow,
+        )
+        .map_err(crate::in_function("power", None))
+    }
+
+    /// Subtracts an array and a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_array_int(a: Array, b: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+        minus(Dynamic::from_array(a), Dynamic::from_int(b))
+    }
+
+    /// Subtracts an array and a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_array_float(a: Array, b: FLOAT) -> Result<Dynamic, Box<EvalAltResult>> {
+        minus(Dynamic::from_array(a), Dynamic::from_float(b))
+    }
+
+    /// Subtracts an array and a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_int_array(a: INT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        minus(Dynamic::from_int(a), Dynamic::from_array(b))
+    }
+
+    /// Subtracts an array and a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_float_array(a: FLOAT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        minus(Dynamic::from_float(a), Dynamic::from_array(b))
+    }
+
+    /// Subtracts two arrays element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_array_array(a: Array, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        minus(Dynamic::from_array(a), Dynamic::from_array(b))
+    }
+
+    /// Multiplies an array and a number element by element, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_array_int(a: Array, b: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+        times(Dynamic::from_array(a), Dynamic::from_int(b))
+    }
+
+    /// Multiplies an array and a number element by element, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_array_float(a: Array, b: FLOAT) -> Result<Dynamic, Box<EvalAltResult>> {
+        times(Dynamic::from_array(a), Dynamic::from_float(b))
+    }
+
+    /// Multiplies an array and a number element by element, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_int_array(a: INT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        times(Dynamic::from_int(a), Dynamic::from_array(b))
+    }
+
+    /// Multiplies an array and a number element by element, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_float_array(a: FLOAT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        times(Dynamic::from_float(a), Dynamic::from_array(b))
+    }
+
+    /// Divides an array and a number element by element, as `rdivide` does.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_array_int(a: Array, b: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+        rdivide(Dynamic::from_array(a), Dynamic::from_int(b))
+    }
+
+    /// Divides an array and a number element by element, as `rdivide` does.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_array_float(a: Array, b: FLOAT) -> Result<Dynamic, Box<EvalAltResult>> {
+        rdivide(Dynamic::from_array(a), Dynamic::from_float(b))
+    }
+
+    /// Divides an array and a number element by element, as `rdivide` does.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_int_array(a: INT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        rdivide(Dynamic::from_int(a), Dynamic::from_array(b))
+    }
+
+    /// Divides an array and a number element by element, as `rdivide` does.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_float_array(a: FLOAT, b: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        rdivide(Dynamic::from_float(a), Dynamic::from_array(b))
+    }
+}
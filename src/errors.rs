@@ -0,0 +1,190 @@
+/// The kinds of errors raised by the functions in this package.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LabErrorKind {
+    /// An array or matrix that needs elements has none, as in `max([])`.
+    EmptyInput,
+    /// A value has the wrong type, such as a string in an array of numbers.
+    TypeMismatch,
+    /// Arrays or matrices have sizes or shapes that do not fit together.
+    DimensionMismatch,
+    /// A value has the right type but is not allowed, such as a negative size or an unknown
+    /// option.
+    InvalidArgument,
+    /// Something that was looked up by name, such as a column or a physical constant, does not
+    /// exist.
+    NotFound,
+    /// A calculation failed, such as solving a singular system or a fit that did not converge.
+    Numerical,
+    /// An assertion such as `assert_eq` did not hold.
+    AssertionFailed,
+}
+
+impl LabErrorKind {
+    /// The name of the kind, as scripts see it in the `kind` field of a caught error.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::EmptyInput => "empty_input",
+            Self::TypeMismatch => "type_mismatch",
+            Self::DimensionMismatch => "dimension_mismatch",
+            Self::InvalidArgument => "invalid_argument",
+            Self::NotFound => "not_found",
+            Self::Numerical => "numerical",
+            Self::AssertionFailed => "assertion_failed",
+        }
+    }
+
+    /// The kind with the given name, the reverse of [`as_str`](Self::as_str).
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::EmptyInput,
+            Self::TypeMismatch,
+            Self::DimensionMismatch,
+            Self::InvalidArgument,
+            Self::NotFound,
+            Self::Numerical,
+            Self::AssertionFailed,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == name)
+    }
+}
+
+impl std::fmt::Display for LabErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error raised by one of the functions in this package, with the function and argument it
+/// concerns and, once it reaches the host, where in the script it happened.
+///
+/// It is raised as a runtime error whose value is a map with the keys `kind` (see
+/// [`LabErrorKind::as_str`]), `message`, `function`, and `argument`, where the last two are `()`
+/// when they are not known. Scripts can inspect it with `try`/`catch`:
+/// ```typescript
+/// let kind = "";
+/// try {
+///     max([]);
+/// } catch (err) {
+///     kind = err.kind;
+/// }
+/// assert_eq(kind, "empty_input");
+/// ```
+/// On the Rust side, [`LabError::from_eval`] recovers it from the [`rhai::EvalAltResult`]:
+/// ```
+/// use rhai_sci::{eval, LabError, LabErrorKind};
+///
+/// let err = eval::<rhai::FLOAT>("let x = 1.0;\nx + max([])").unwrap_err();
+/// let err = LabError::from_eval(&err).unwrap();
+/// assert_eq!(err.kind, LabErrorKind::EmptyInput);
+/// assert_eq!(err.function.as_deref(), Some("max"));
+/// assert_eq!(err.position.line(), Some(2));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabError {
+    /// What went wrong.
+    pub kind: LabErrorKind,
+    /// The name of the function that raised the error, if known.
+    pub function: Option<String>,
+    /// The name of the argument that caused the error, if known.
+    pub argument: Option<String>,
+    /// A description of the error.
+    pub message: String,
+    /// Where in the script the error happened. This is filled in by the engine, so it is only
+    /// known for errors recovered with [`LabError::from_eval`].
+    pub position: rhai::Position,
+}
+
+impl LabError {
+    /// Creates an error of the given kind.
+    pub fn new(kind: LabErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            function: None,
+            argument: None,
+            message: message.into(),
+            position: rhai::Position::NONE,
+        }
+    }
+
+    /// Names the function that raised the error.
+    pub fn in_function(mut self, function: impl Into<String>) -> Self {
+        self.function = Some(function.into());
+        self
+    }
+
+    /// Names the argument that caused the error.
+    pub fn with_argument(mut self, argument: impl Into<String>) -> Self {
+        self.argument = Some(argument.into());
+        self
+    }
+
+    /// The error as the map that scripts see when they catch it.
+    pub fn to_map(&self) -> rhai::Map {
+        let optional = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map_or(rhai::Dynamic::UNIT, |value| value.as_str().into())
+        };
+        let mut map = rhai::Map::new();
+        map.insert("kind".into(), self.kind.as_str().into());
+        map.insert("message".into(), self.message.as_str().into());
+        map.insert("function".into(), optional(&self.function));
+        map.insert("argument".into(), optional(&self.argument));
+        map
+    }
+
+    /// Recovers the error raised by a function in this package from the result of evaluating a
+    /// script, looking through errors in script-defined functions and modules. Returns `None` for
+    /// any other error.
+    pub fn from_eval(err: &rhai::EvalAltResult) -> Option<Self> {
+        match err {
+            rhai::EvalAltResult::ErrorRuntime(value, position) => {
+                let map = value.read_lock::<rhai::Map>()?;
+                let text = |key: &str| {
+                    map.get(key)
+                        .and_then(|value| value.read_lock::<rhai::ImmutableString>())
+                        .map(|value| value.to_string())
+                };
+                Some(Self {
+                    kind: LabErrorKind::from_name(&text("kind")?)?,
+                    function: text("function"),
+                    argument: text("argument"),
+                    message: text("message")?,
+                    position: *position,
+                })
+            }
+            rhai::EvalAltResult::ErrorInFunctionCall(_, _, err, _)
+            | rhai::EvalAltResult::ErrorInModule(_, err, _) => Self::from_eval(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for LabError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(function) = &self.function {
+            write!(f, "{function}: ")?;
+        }
+        f.write_str(&self.message)?;
+        if !self.position.is_none() {
+            write!(f, " ({})", self.position)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LabError {}
+
+impl From<LabError> for rhai::EvalAltResult {
+    fn from(err: LabError) -> Self {
+        rhai::EvalAltResult::ErrorRuntime(rhai::Dynamic::from_map(err.to_map()), err.position)
+    }
+}
+
+impl From<LabError> for Box<rhai::EvalAltResult> {
+    fn from(err: LabError) -> Self {
+        Box::new(err.into())
+    }
+}
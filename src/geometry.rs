@@ -0,0 +1,537 @@
+use rhai::plugin::*;
+
+/// Clips a convex polygon to the half-plane of points closer to `p` than to `q`. Vertices within
+/// `tol` of the one before them, as when the bisector passes through a vertex, are dropped.
+fn clip_to_bisector(
+    polygon: Vec<[rhai::FLOAT; 2]>,
+    p: [rhai::FLOAT; 2],
+    q: [rhai::FLOAT; 2],
+    tol: rhai::FLOAT,
+) -> Vec<[rhai::FLOAT; 2]> {
+    // Signed distance (scaled) from the bisector, positive on the side of `p`
+    let (nx, ny) = (p[0] - q[0], p[1] - q[1]);
+    let (mx, my) = ((p[0] + q[0]) / 2.0, (p[1] + q[1]) / 2.0);
+    let side = |v: &[rhai::FLOAT; 2]| (v[0] - mx) * nx + (v[1] - my) * ny;
+
+    let mut clipped = vec![];
+    for (idx, current) in polygon.iter().enumerate() {
+        let next = &polygon[(idx + 1) % polygon.len()];
+        let (sc, sn) = (side(current), side(next));
+        if sc >= 0.0 {
+            clipped.push(*current);
+        }
+        if (sc >= 0.0) != (sn >= 0.0) {
+            let t = sc / (sc - sn);
+            clipped.push([
+                current[0] + t * (next[0] - current[0]),
+                current[1] + t * (next[1] - current[1]),
+            ]);
+        }
+    }
+    let coincident =
+        |a: &[rhai::FLOAT; 2], b: &[rhai::FLOAT; 2]| (a[0] - b[0]).hypot(a[1] - b[1]) <= tol;
+    clipped.dedup_by(|b, a| coincident(a, b));
+    while clipped.len() > 1 && coincident(&clipped[0], &clipped[clipped.len() - 1]) {
+        clipped.pop();
+    }
+    clipped
+}
+
+/// Wraps an angle into the interval from `-half_turn` to `half_turn`, keeping positive odd
+/// multiples of a half turn at `half_turn` rather than `-half_turn`.
+fn wrap_angle(x: rhai::FLOAT, half_turn: rhai::FLOAT) -> rhai::FLOAT {
+    let wrapped = (x + half_turn).rem_euclid(2.0 * half_turn) - half_turn;
+    if wrapped == -half_turn && x > 0.0 {
+        half_turn
+    } else {
+        wrapped
+    }
+}
+
+/// Reads two vectors of numbers with the same length.
+fn vector_pair(
+    a: rhai::Array,
+    b: rhai::Array,
+) -> Result<(Vec<rhai::FLOAT>, Vec<rhai::FLOAT>), Box<rhai::EvalAltResult>> {
+    let (mut a, mut b) = (a, b);
+    let a = crate::if_list_convert_to_vec_float_and_do(&mut a, Ok)?;
+    let b = crate::if_list_convert_to_vec_float_and_do(&mut b, Ok)?;
+    if a.len() != b.len() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!(
+                "The vectors must have the same length, not {} and {}",
+                a.len(),
+                b.len()
+            ),
+        )
+        .into());
+    }
+    Ok((a, b))
+}
+
+/// Returns the p-norm of a vector, where `p` is a positive number or `"inf"`.
+fn vector_p_norm(
+    v: &[rhai::FLOAT],
+    p: &rhai::Dynamic,
+) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>> {
+    let abs = v.iter().map(|x| x.abs());
+    if p.clone().into_string().is_ok_and(|p| p == "inf") {
+        return Ok(abs.fold(0.0, rhai::FLOAT::max));
+    }
+    match crate::if_int_convert_to_float_and_do(p.clone(), Ok) {
+        Ok(p) if p > 0.0 => Ok(abs.map(|x| x.powf(p)).sum::<rhai::FLOAT>().powf(1.0 / p)),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The norm type must be a positive number or \"inf\", not {p}"),
+        )
+        .into()),
+    }
+}
+
+/// Converts a vector of floats into an array.
+fn float_vector_to_array(v: Vec<rhai::FLOAT>) -> rhai::Array {
+    v.into_iter().map(rhai::Dynamic::from_float).collect()
+}
+
+/// Scales a vector to unit length in place, converting its elements to floats. Fails for a vector
+/// of zeros, leaving it as it was.
+fn scale_to_unit_length(
+    a: &mut rhai::Array,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let mut squares = Vec::with_capacity(a.len());
+    for x in a.iter() {
+        squares.push(crate::if_int_convert_to_float_and_do(x.clone(), |x| {
+            Ok(x * x)
+        })?);
+    }
+    let length = crate::stable_sum(squares).sqrt();
+    if length == 0.0 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "Cannot normalize a vector of zeros",
+        )
+        .in_function(function)
+        .into());
+    }
+    for x in a.iter_mut() {
+        *x = rhai::Dynamic::from_float(
+            crate::if_int_convert_to_float_and_do(x.clone(), Ok)? / length,
+        );
+    }
+    Ok(())
+}
+
+/// Tests whether a point is inside a polygon, counting points on its edges as inside.
+fn point_in_polygon(x: rhai::FLOAT, y: rhai::FLOAT, polygon: &[[rhai::FLOAT; 2]]) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        // On an edge if collinear with it and within its bounding box
+        let cross = (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]);
+        if cross == 0.0
+            && x >= a[0].min(b[0])
+            && x <= a[0].max(b[0])
+            && y >= a[1].min(b[1])
+            && y <= a[1].max(b[1])
+        {
+            return true;
+        }
+        // Count crossings of a ray from the point towards positive x
+        if (a[1] > y) != (b[1] > y) && x < a[0] + (y - a[1]) * (b[0] - a[0]) / (b[1] - a[1]) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[export_module]
+pub mod geometry_functions {
+    use crate::{array_to_points_2d, delaunay_triangles};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
+
+    /// Computes the Delaunay triangulation of a set of 2-D points, given as an array of `[x, y]`
+    /// pairs. Returns an array of triangles, each an array of three point indices in
+    /// counter-clockwise order.
+    /// ```typescript
+    /// let points = [[0, 0], [1, 0], [0, 1], [1, 1]];
+    /// let triangles = delaunay(points);
+    /// assert_eq(len(triangles), 2);
+    /// ```
+    /// ```typescript
+    /// let triangles = delaunay([[0, 0], [2, 0], [1, 3]]);
+    /// assert_eq(triangles, [[0, 1, 2]]);
+    /// ```
+    #[rhai_fn(name = "delaunay", return_raw)]
+    pub fn delaunay(points: Array) -> Result<Array, Box<EvalAltResult>> {
+        let points = array_to_points_2d(points)?;
+        let mut triangles = delaunay_triangles(&points);
+
+        // Present each triangle starting from its lowest index, in a predictable order
+        for t in triangles.iter_mut() {
+            let first = (0..3).min_by_key(|i| t[*i]).unwrap();
+            t.rotate_left(first);
+        }
+        triangles.sort_unstable();
+        Ok(triangles
+            .into_iter()
+            .map(|t| Dynamic::from_array(t.iter().map(|i| Dynamic::from_int(*i as INT)).collect()))
+            .collect())
+    }
+
+    /// Computes the Voronoi cells of a set of 2-D points, given as an array of `[x, y]` pairs,
+    /// clipped to the bounding box `[xmin, ymin, xmax, ymax]`. Returns one polygon per point, as an
+    /// array of `[x, y]` vertices in counter-clockwise order. Every location in a point's cell is
+    /// closer to that point than to any other.
+    /// ```typescript
+    /// let cells = voronoi([[0.25, 0.5], [0.75, 0.5]], [0, 0, 1, 1]);
+    /// assert_eq(cells[0], [[0.0, 0.0], [0.5, 0.0], [0.5, 1.0], [0.0, 1.0]]);
+    /// assert_eq(cells[1], [[0.5, 0.0], [1.0, 0.0], [1.0, 1.0], [0.5, 1.0]]);
+    /// ```
+    /// A bisector that passes through a corner of the box leaves that corner in the cell once.
+    /// ```typescript
+    /// let cells = voronoi([[0, 0], [1, 0], [0, 1]], [-1, -1, 2, 2]);
+    /// assert_eq(cells[1], [[0.5, -1.0], [2.0, -1.0], [2.0, 2.0], [0.5, 0.5]]);
+    /// assert_eq(cells[2], [[2.0, 2.0], [-1.0, 2.0], [-1.0, 0.5], [0.5, 0.5]]);
+    /// ```
+    #[rhai_fn(name = "voronoi", return_raw)]
+    pub fn voronoi(points: Array, bbox: Array) -> Result<Array, Box<EvalAltResult>> {
+        let points = array_to_points_2d(points)?;
+        let mut bbox = bbox;
+        let bbox = crate::if_list_convert_to_vec_float_and_do(&mut bbox, Ok)?;
+        if bbox.len() != 4 || bbox[0] >= bbox[2] || bbox[1] >= bbox[3] {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The bounding box must be of the form [xmin, ymin, xmax, ymax]",
+            )
+            .in_function("voronoi")
+            .into());
+        }
+        let (xmin, ymin, xmax, ymax) = (bbox[0], bbox[1], bbox[2], bbox[3]);
+        let tol = FLOAT::EPSILON.sqrt() * (xmax - xmin).max(ymax - ymin);
+
+        Ok(points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let mut cell = vec![[xmin, ymin], [xmax, ymin], [xmax, ymax], [xmin, ymax]];
+                for (j, q) in points.iter().enumerate() {
+                    if i != j && !cell.is_empty() {
+                        cell = super::clip_to_bisector(cell, *p, *q, tol);
+                    }
+                }
+                Dynamic::from_array(
+                    cell.into_iter()
+                        .map(|[x, y]| {
+                            Dynamic::from_array(vec![
+                                Dynamic::from_float(x),
+                                Dynamic::from_float(y),
+                            ])
+                        })
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+
+    /// Converts an angle, or each angle in an array or matrix, from degrees to radians.
+    /// ```typescript
+    /// assert_eq(deg2rad(180), pi);
+    /// assert_eq(deg2rad([0, 90, -360]), [0.0, pi / 2, -2 * pi]);
+    /// ```
+    #[rhai_fn(name = "deg2rad", return_raw)]
+    pub fn deg2rad(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &FLOAT::to_radians)
+    }
+
+    /// Converts an angle, or each angle in an array or matrix, from radians to degrees.
+    /// ```typescript
+    /// assert_eq(rad2deg(pi), 180.0);
+    /// assert_eq(rad2deg([pi / 2, -pi]), [90.0, -180.0]);
+    /// ```
+    #[rhai_fn(name = "rad2deg", return_raw)]
+    pub fn rad2deg(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &FLOAT::to_degrees)
+    }
+
+    /// Wraps an angle in radians, or each angle in an array or matrix, into the interval from `-pi`
+    /// to `pi`. Positive odd multiples of `pi` wrap to `pi`, and negative ones to `-pi`.
+    /// ```typescript,only_f64
+    /// assert_eq(wrap_to_pi(3 * pi), pi);
+    /// assert_eq(wrap_to_pi([-pi, 0.5, 2 * pi + 0.5]), [-pi, 0.5, 0.5]);
+    /// ```
+    #[rhai_fn(name = "wrap_to_pi", return_raw)]
+    pub fn wrap_to_pi(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        let half_turn = std::f64::consts::PI as FLOAT;
+        crate::map_numbers(x, &|x| super::wrap_angle(x, half_turn))
+    }
+
+    /// Wraps an angle in degrees, or each angle in an array or matrix, into the interval from
+    /// `-180` to `180`. Positive odd multiples of 180 wrap to 180, and negative ones to -180.
+    /// ```typescript
+    /// assert_eq(wrap_to_180(190), -170.0);
+    /// assert_eq(wrap_to_180([540, -190, -180]), [180.0, 170.0, -180.0]);
+    /// ```
+    #[rhai_fn(name = "wrap_to_180", return_raw)]
+    pub fn wrap_to_180(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &|x| super::wrap_angle(x, 180.0))
+    }
+
+    /// Returns the four-quadrant inverse tangent of `y / x` in radians, between `-pi` and `pi`.
+    /// Either argument can be an array or matrix, which is paired element by element with an array
+    /// of the same shape, or with every element if the other argument is a number.
+    /// ```typescript
+    /// assert_eq(atan2(1, -1), 3 * pi / 4);
+    /// assert_eq(atan2([1, -1, 0], [0, 0, -1]), [pi / 2, -pi / 2, pi]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(atan2([1, 2], 0), [pi / 2, pi / 2]);
+    /// ```
+    #[rhai_fn(name = "atan2", return_raw)]
+    pub fn atan2(y: Dynamic, x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::zip_numbers(y, x, &FLOAT::atan2)
+    }
+
+    /// Returns the dot product of two vectors of the same length.
+    /// ```typescript
+    /// assert_eq(dot([1, 2, 3], [4, 5, 6]), 32.0);
+    /// assert_eq(dot([1, 0], [0, 1]), 0.0);
+    /// ```
+    #[rhai_fn(name = "dot", return_raw)]
+    pub fn dot(a: Array, b: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (a, b) = super::vector_pair(a, b)?;
+        Ok(a.iter().zip(&b).map(|(x, y)| x * y).sum())
+    }
+
+    /// Returns the cross product of two 3-vectors, which is perpendicular to both.
+    /// ```typescript
+    /// assert_eq(cross([1, 0, 0], [0, 1, 0]), [0.0, 0.0, 1.0]);
+    /// assert_eq(cross([1, 2, 3], [4, 5, 6]), [-3.0, 6.0, -3.0]);
+    /// ```
+    #[rhai_fn(name = "cross", return_raw)]
+    pub fn cross(a: Array, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        let (a, b) = super::vector_pair(a, b)?;
+        if a.len() != 3 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The cross product needs 3-vectors, not {}-vectors", a.len()),
+            )
+            .in_function("cross")
+            .into());
+        }
+        Ok(super::float_vector_to_array(vec![
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]))
+    }
+
+    /// Returns the Euclidean length of a vector, or of each vector in an array of vectors.
+    /// ```typescript
+    /// assert_eq(vecnorm([3, 4]), 5.0);
+    /// assert_eq(vecnorm([[3, 4], [0, -2]]), [5.0, 2.0]);
+    /// ```
+    #[rhai_fn(name = "vecnorm", return_raw)]
+    pub fn vecnorm(a: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        vecnorm_with_p(a, Dynamic::from_int(2))
+    }
+
+    /// Returns the p-norm `sum(abs(x)^p)^(1/p)` of a vector, or of each vector in an array of
+    /// vectors. `p` can be any positive number, or `"inf"` for the largest absolute value.
+    /// ```typescript
+    /// assert_eq(vecnorm([1, -2, 2], 1), 5.0);
+    /// assert_eq(vecnorm([1, -2, 2], "inf"), 2.0);
+    /// assert_eq(vecnorm([[1, 1], [-3, 1]], 1), [2.0, 4.0]);
+    /// ```
+    #[rhai_fn(name = "vecnorm", return_raw)]
+    pub fn vecnorm_with_p(a: Array, p: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        if a.iter().any(|x| x.is_array()) {
+            a.into_iter()
+                .map(|mut v| {
+                    let v =
+                        crate::if_list_convert_to_vec_float_and_do(v.as_array_mut()?.as_mut(), Ok)?;
+                    super::vector_p_norm(&v, &p).map(Dynamic::from_float)
+                })
+                .collect::<Result<Array, _>>()
+                .map(Dynamic::from_array)
+        } else {
+            let mut a = a;
+            let v = crate::if_list_convert_to_vec_float_and_do(&mut a, Ok)?;
+            super::vector_p_norm(&v, &p).map(Dynamic::from_float)
+        }
+    }
+
+    /// Scales a vector to unit length. Fails for a vector of zeros.
+    /// ```typescript
+    /// assert_eq(normalize([3, 0, -4]), [0.6, 0.0, -0.8]);
+    /// ```
+    #[rhai_fn(name = "normalize", return_raw)]
+    pub fn normalize(a: Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut a = a;
+        super::scale_to_unit_length(&mut a, "normalize")?;
+        Ok(a)
+    }
+
+    /// Scales a vector to unit length in place, like `normalize` but without allocating a new
+    /// array. Fails for a vector of zeros, leaving it as it was.
+    /// ```typescript
+    /// let v = [3, 0, -4];
+    /// v.normalize_in_place();
+    /// assert_eq(v, [0.6, 0.0, -0.8]);
+    /// ```
+    #[rhai_fn(name = "normalize_in_place", return_raw)]
+    pub fn normalize_in_place(a: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        super::scale_to_unit_length(a, "normalize_in_place")
+    }
+
+    /// Returns the projection of the vector `a` onto the direction of the vector `b`. Fails if `b`
+    /// is a vector of zeros.
+    /// ```typescript
+    /// assert_eq(proj([2, 3], [1, 0]), [2.0, 0.0]);
+    /// assert_eq(proj([1, 2, 3], [2, 2, 0]), [1.5, 1.5, 0.0]);
+    /// ```
+    /// ```typescript
+    /// let a = [3, 1];
+    /// let b = [1, 1];
+    /// let perpendicular = a - proj(a, b);
+    /// assert_eq(dot(perpendicular, b), 0.0);
+    /// ```
+    #[rhai_fn(name = "proj", return_raw)]
+    pub fn proj(a: Array, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        let (a, b) = super::vector_pair(a, b)?;
+        let bb = b.iter().map(|x| x * x).sum::<FLOAT>();
+        if bb == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "Cannot project onto a vector of zeros",
+            )
+            .in_function("proj")
+            .into());
+        }
+        let scale = a.iter().zip(&b).map(|(x, y)| x * y).sum::<FLOAT>() / bb;
+        Ok(super::float_vector_to_array(
+            b.into_iter().map(|x| scale * x).collect(),
+        ))
+    }
+
+    /// Returns the area enclosed by a polygon with vertices at `x` and `y`, given in order around
+    /// it in either direction.
+    /// ```typescript
+    /// assert_eq(polyarea([0, 4, 4, 0], [0, 0, 3, 3]), 12.0);
+    /// assert_eq(polyarea([0, 0, 2], [0, 2, 0]), 2.0);
+    /// ```
+    #[rhai_fn(name = "polyarea", return_raw)]
+    pub fn polyarea(x: Array, y: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (x, y) = super::vector_pair(x, y)?;
+        let n = x.len();
+        let twice_area = (0..n)
+            .map(|i| x[i] * y[(i + 1) % n] - x[(i + 1) % n] * y[i])
+            .sum::<FLOAT>();
+        Ok(twice_area.abs() / 2.0)
+    }
+
+    /// Computes the convex hull of a set of 2-D points, given as an array of `[x, y]` pairs.
+    /// Returns the indices of the points on the hull in counter-clockwise order, starting from the
+    /// leftmost point and repeating it at the end to close the loop. Points along the edges of
+    /// the hull are left out.
+    /// ```typescript
+    /// let points = [[0, 0], [2, 0], [1, 1], [2, 2], [0, 2], [1, 0]];
+    /// assert_eq(convhull(points), [0, 1, 3, 4, 0]);
+    /// ```
+    #[rhai_fn(name = "convhull", return_raw)]
+    pub fn convhull(points: Array) -> Result<Array, Box<EvalAltResult>> {
+        let points = array_to_points_2d(points)?;
+        if points.len() < 3 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "A convex hull needs at least 3 points",
+            )
+            .in_function("convhull")
+            .with_argument("points")
+            .into());
+        }
+
+        // Andrew's monotone chain, building the lower and then the upper hull
+        let mut order = (0..points.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| {
+            let (a, b) = (points[*a], points[*b]);
+            a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1]))
+        });
+        let turn = |o: usize, a: usize, b: usize| {
+            let (o, a, b) = (points[o], points[a], points[b]);
+            (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+        };
+        let mut hull: Vec<usize> = vec![];
+        for pass in [order.clone(), order.into_iter().rev().collect()] {
+            let start = hull.len();
+            for idx in pass {
+                while hull.len() >= start + 2
+                    && turn(hull[hull.len() - 2], hull[hull.len() - 1], idx) <= 0.0
+                {
+                    hull.pop();
+                }
+                hull.push(idx);
+            }
+            hull.pop();
+        }
+        if hull.len() < 3 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The points are collinear, so they have no convex hull",
+            )
+            .in_function("convhull")
+            .with_argument("points")
+            .into());
+        }
+        hull.push(hull[0]);
+        Ok(hull
+            .into_iter()
+            .map(|i| Dynamic::from_int(i as INT))
+            .collect())
+    }
+
+    /// Tests whether points with coordinates `px` and `py` are inside the polygon with vertices at
+    /// `poly_x` and `poly_y`. Points on an edge count as inside. The coordinates can be numbers,
+    /// giving a boolean, or arrays, giving an array of booleans. A number is paired with every
+    /// element of an array.
+    /// ```typescript
+    /// let square_x = [0, 2, 2, 0];
+    /// let square_y = [0, 0, 2, 2];
+    /// assert(inpolygon(1, 1, square_x, square_y));
+    /// assert_eq(inpolygon([1, 3, 2], 1, square_x, square_y), [true, false, true]);
+    /// ```
+    #[rhai_fn(name = "inpolygon", return_raw)]
+    pub fn inpolygon(
+        px: Dynamic,
+        py: Dynamic,
+        poly_x: Array,
+        poly_y: Array,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let (poly_x, poly_y) = super::vector_pair(poly_x, poly_y)?;
+        let polygon = poly_x
+            .into_iter()
+            .zip(poly_y)
+            .map(|(x, y)| [x, y])
+            .collect::<Vec<_>>();
+        if !px.is_array() && !py.is_array() {
+            let x = crate::if_int_convert_to_float_and_do(px, Ok)?;
+            let y = crate::if_int_convert_to_float_and_do(py, Ok)?;
+            return Ok(Dynamic::from_bool(super::point_in_polygon(x, y, &polygon)));
+        }
+
+        // A number is paired with every element of the other array
+        let as_array = |d: Dynamic, other: &Dynamic| match d.clone().into_array() {
+            Ok(arr) => arr,
+            Err(_) => vec![d; other.clone().into_array().map_or(1, |o| o.len())],
+        };
+        let (xs, ys) = (as_array(px.clone(), &py), as_array(py, &px));
+        let (xs, ys) = super::vector_pair(xs, ys)?;
+        Ok(Dynamic::from_array(
+            xs.into_iter()
+                .zip(ys)
+                .map(|(x, y)| Dynamic::from_bool(super::point_in_polygon(x, y, &polygon)))
+                .collect(),
+        ))
+    }
+}
@@ -0,0 +1,397 @@
+use rhai::plugin::*;
+
+/// The edges leaving each node of a graph, as `(node, weight)` pairs.
+type Adjacency = Vec<Vec<(usize, rhai::FLOAT)>>;
+
+/// Builds the edges of a graph from the size of its adjacency matrix and its non-zero elements,
+/// checking that the matrix is square and that no weight is `NaN`.
+fn adjacency_from_triplets(
+    rows: usize,
+    cols: usize,
+    triplets: impl Iterator<Item = (usize, usize, rhai::FLOAT)>,
+    function: &str,
+) -> Result<Adjacency, Box<rhai::EvalAltResult>> {
+    if rows != cols {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("An adjacency matrix must be square, not {rows} x {cols}"),
+        )
+        .in_function(function)
+        .with_argument("adj")
+        .into());
+    }
+    let mut edges = vec![vec![]; rows];
+    for (i, j, w) in triplets {
+        if w.is_nan() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "Edge weights cannot be NaN",
+            )
+            .in_function(function)
+            .with_argument("adj")
+            .into());
+        }
+        edges[i].push((j, w));
+    }
+    Ok(edges)
+}
+
+/// Reads an adjacency matrix given as a nested array, where a non-zero element at row `i` and
+/// column `j` is an edge from node `i` to node `j` with that weight.
+fn dense_adjacency(
+    adj: &rhai::Array,
+    function: &str,
+) -> Result<Adjacency, Box<rhai::EvalAltResult>> {
+    let m = crate::Matrix::from_array(adj)?;
+    let mut triplets = vec![];
+    for i in 0..m.rows() {
+        for j in 0..m.cols() {
+            if m.get(i, j) != 0.0 {
+                triplets.push((i, j, m.get(i, j)));
+            }
+        }
+    }
+    adjacency_from_triplets(m.rows(), m.cols(), triplets.into_iter(), function)
+}
+
+/// Reads a sparse adjacency matrix, where each stored element is an edge.
+#[cfg(feature = "sparse")]
+fn sparse_adjacency(
+    adj: &crate::SparseMatrix,
+    function: &str,
+) -> Result<Adjacency, Box<rhai::EvalAltResult>> {
+    adjacency_from_triplets(adj.rows(), adj.cols(), adj.triplets(), function)
+}
+
+/// Reads the index of a node in a graph of `n` nodes.
+fn node_index(
+    node: rhai::INT,
+    n: usize,
+    function: &str,
+    argument: &str,
+) -> Result<usize, Box<rhai::EvalAltResult>> {
+    if node < 0 || node as usize >= n {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Node {node} is not in a graph of {n} nodes"),
+        )
+        .in_function(function)
+        .with_argument(argument)
+        .into());
+    }
+    Ok(node as usize)
+}
+
+/// A node waiting to be visited by Dijkstra's algorithm, ordered so that the closest node comes
+/// out of a `BinaryHeap` first.
+#[derive(PartialEq)]
+struct PathCandidate(rhai::FLOAT, usize);
+
+impl Eq for PathCandidate {}
+
+impl Ord for PathCandidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+impl PartialOrd for PathCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Finds the shortest path from `src` to `dst` with Dijkstra's algorithm, returning a map of the
+/// nodes along the `path` and its total `distance`.
+fn dijkstra(
+    edges: &Adjacency,
+    src: rhai::INT,
+    dst: rhai::INT,
+) -> Result<rhai::Map, Box<rhai::EvalAltResult>> {
+    let n = edges.len();
+    let src = node_index(src, n, "shortest_path", "src")?;
+    let dst = node_index(dst, n, "shortest_path", "dst")?;
+    if edges.iter().flatten().any(|(_, w)| *w < 0.0) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "Edge weights cannot be negative",
+        )
+        .in_function("shortest_path")
+        .with_argument("adj")
+        .into());
+    }
+
+    let mut distance = vec![rhai::FLOAT::INFINITY; n];
+    let mut previous = vec![None; n];
+    let mut heap = std::collections::BinaryHeap::new();
+    distance[src] = 0.0;
+    heap.push(PathCandidate(0.0, src));
+    while let Some(PathCandidate(d, i)) = heap.pop() {
+        if i == dst {
+            break;
+        }
+        // Skip nodes that were already reached by a shorter path
+        if d > distance[i] {
+            continue;
+        }
+        for &(j, w) in &edges[i] {
+            if d + w < distance[j] {
+                distance[j] = d + w;
+                previous[j] = Some(i);
+                heap.push(PathCandidate(d + w, j));
+            }
+        }
+    }
+
+    let mut path = vec![];
+    if distance[dst].is_finite() {
+        let mut node = Some(dst);
+        while let Some(i) = node {
+            path.push(rhai::Dynamic::from_int(i as rhai::INT));
+            node = previous[i];
+        }
+        path.reverse();
+    }
+    let mut result = rhai::Map::new();
+    result.insert("path".into(), rhai::Dynamic::from_array(path));
+    result.insert("distance".into(), rhai::Dynamic::from_float(distance[dst]));
+    Ok(result)
+}
+
+/// Disjoint sets of nodes, merged as edges join them.
+struct NodeSets(Vec<usize>);
+
+impl NodeSets {
+    /// Puts each of `n` nodes in a set of its own.
+    fn new(n: usize) -> Self {
+        NodeSets((0..n).collect())
+    }
+
+    /// Returns the node that represents the set holding node `i`.
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.0[i] != i {
+            self.0[i] = self.0[self.0[i]];
+            i = self.0[i];
+        }
+        i
+    }
+
+    /// Merges the sets holding nodes `i` and `j`, returning `false` if they were already one.
+    fn merge(&mut self, i: usize, j: usize) -> bool {
+        let (a, b) = (self.find(i), self.find(j));
+        if a == b {
+            return false;
+        }
+        self.0[a.max(b)] = a.min(b);
+        true
+    }
+}
+
+/// Labels each node with the number of its connected component, ignoring the direction of edges.
+/// Components are numbered from 0 in the order of the first node in each.
+fn component_labels(edges: &Adjacency) -> rhai::Array {
+    let n = edges.len();
+    let mut sets = NodeSets::new(n);
+    for (i, row) in edges.iter().enumerate() {
+        for &(j, _) in row {
+            sets.merge(i, j);
+        }
+    }
+    let mut labels = vec![usize::MAX; n];
+    let mut count = 0;
+    (0..n)
+        .map(|i| {
+            let root = sets.find(i);
+            if labels[root] == usize::MAX {
+                labels[root] = count;
+                count += 1;
+            }
+            rhai::Dynamic::from_int(labels[root] as rhai::INT)
+        })
+        .collect()
+}
+
+/// Counts the edges leaving each node.
+fn node_degrees(edges: &Adjacency) -> rhai::Array {
+    edges
+        .iter()
+        .map(|row| rhai::Dynamic::from_int(row.len() as rhai::INT))
+        .collect()
+}
+
+/// Finds a minimum spanning tree (or forest) with Kruskal's algorithm, ignoring the direction of
+/// edges, and returns a map of its `edges` as `[i, j, weight]` with `i < j` and its total
+/// `weight`.
+fn kruskal(edges: &Adjacency) -> rhai::Map {
+    let mut candidates = edges
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().map(move |&(j, w)| (i.min(j), i.max(j), w)))
+        .filter(|(i, j, _)| i != j)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.2.total_cmp(&b.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+
+    let mut sets = NodeSets::new(edges.len());
+    let mut tree = vec![];
+    let mut weights = vec![];
+    for (i, j, w) in candidates {
+        if sets.merge(i, j) {
+            tree.push(rhai::Dynamic::from_array(vec![
+                rhai::Dynamic::from_int(i as rhai::INT),
+                rhai::Dynamic::from_int(j as rhai::INT),
+                rhai::Dynamic::from_float(w),
+            ]));
+            weights.push(w);
+        }
+    }
+    let mut result = rhai::Map::new();
+    result.insert("edges".into(), rhai::Dynamic::from_array(tree));
+    result.insert(
+        "weight".into(),
+        rhai::Dynamic::from_float(crate::stable_sum_slice(&weights)),
+    );
+    result
+}
+
+#[export_module]
+pub mod graph_functions {
+    use rhai::{Array, EvalAltResult, Map, INT};
+
+    /// Finds the shortest path between two nodes of a graph with Dijkstra's algorithm, given an
+    /// adjacency matrix where a non-zero element at row `i` and column `j` is an edge from node
+    /// `i` to node `j` with that (non-negative) weight. Returns a map of the nodes along the
+    /// `path`, from `src` to `dst`, and its total `distance`. If there is no path, `path` is empty
+    /// and `distance` is infinite.
+    /// ```typescript
+    /// let adj = [[0, 4, 1, 0],
+    ///            [4, 0, 2, 5],
+    ///            [1, 2, 0, 8],
+    ///            [0, 5, 8, 0]];
+    /// let route = shortest_path(adj, 0, 3);
+    /// assert_eq(route.path, [0, 2, 1, 3]);
+    /// assert_eq(route.distance, 8.0);
+    /// ```
+    /// ```typescript
+    /// let route = shortest_path([[0, 1], [0, 0]], 1, 0);
+    /// assert_eq(route.path, []);
+    /// ```
+    #[rhai_fn(name = "shortest_path", return_raw)]
+    pub fn shortest_path(adj: Array, src: INT, dst: INT) -> Result<Map, Box<EvalAltResult>> {
+        super::dijkstra(&super::dense_adjacency(&adj, "shortest_path")?, src, dst)
+    }
+
+    /// Finds the shortest path between two nodes of a graph with a `SparseMatrix` for its
+    /// adjacency matrix, where each stored element is an edge with that (non-negative) weight.
+    /// Returns a map of the nodes along the `path` and its total `distance`.
+    /// ```typescript
+    /// let adj = sparse([0, 1, 2], [1, 2, 3], [1.5, 2.0, 0.5], 4, 4);
+    /// let route = shortest_path(adj, 0, 3);
+    /// assert_eq(route.path, [0, 1, 2, 3]);
+    /// assert_eq(route.distance, 4.0);
+    /// ```
+    #[cfg(feature = "sparse")]
+    #[rhai_fn(name = "shortest_path", return_raw)]
+    pub fn shortest_path_sparse(
+        adj: crate::SparseMatrix,
+        src: INT,
+        dst: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        super::dijkstra(&super::sparse_adjacency(&adj, "shortest_path")?, src, dst)
+    }
+
+    /// Labels each node of a graph with the number of the connected component it belongs to,
+    /// given its adjacency matrix. The direction of edges is ignored, so the components of a
+    /// directed graph are its weakly connected components. Components are numbered from 0 in
+    /// the order of their first node.
+    /// ```typescript
+    /// let adj = [[0, 1, 0, 0],
+    ///            [1, 0, 0, 0],
+    ///            [0, 0, 0, 1],
+    ///            [0, 0, 1, 0]];
+    /// assert_eq(connected_components(adj), [0, 0, 1, 1]);
+    /// ```
+    #[rhai_fn(name = "connected_components", return_raw)]
+    pub fn connected_components(adj: Array) -> Result<Array, Box<EvalAltResult>> {
+        let edges = super::dense_adjacency(&adj, "connected_components")?;
+        Ok(super::component_labels(&edges))
+    }
+
+    /// Labels each node of a graph with the number of the connected component it belongs to,
+    /// given a `SparseMatrix` for its adjacency matrix.
+    /// ```typescript
+    /// let adj = sparse([0, 3], [2, 4], [1, 1], 5, 5);
+    /// assert_eq(connected_components(adj), [0, 1, 0, 2, 2]);
+    /// ```
+    #[cfg(feature = "sparse")]
+    #[rhai_fn(name = "connected_components", return_raw)]
+    pub fn connected_components_sparse(
+        adj: crate::SparseMatrix,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let edges = super::sparse_adjacency(&adj, "connected_components")?;
+        Ok(super::component_labels(&edges))
+    }
+
+    /// Counts the edges leaving each node of a graph, which is the number of non-zero elements
+    /// in each row of its adjacency matrix. For an undirected graph with a symmetric adjacency
+    /// matrix, this is the degree of each node.
+    /// ```typescript
+    /// let adj = [[0, 1, 1],
+    ///            [1, 0, 0],
+    ///            [1, 0, 0]];
+    /// assert_eq(degree(adj), [2, 1, 1]);
+    /// ```
+    #[rhai_fn(name = "degree", return_raw)]
+    pub fn degree(adj: Array) -> Result<Array, Box<EvalAltResult>> {
+        let edges = super::dense_adjacency(&adj, "degree")?;
+        Ok(super::node_degrees(&edges))
+    }
+
+    /// Counts the edges leaving each node of a graph, given a `SparseMatrix` for its adjacency
+    /// matrix.
+    /// ```typescript
+    /// let adj = sparse([0, 0, 1], [1, 2, 2], [1, 1, 1], 3, 3);
+    /// assert_eq(degree(adj), [2, 1, 0]);
+    /// ```
+    #[cfg(feature = "sparse")]
+    #[rhai_fn(name = "degree", return_raw)]
+    pub fn degree_sparse(adj: crate::SparseMatrix) -> Result<Array, Box<EvalAltResult>> {
+        let edges = super::sparse_adjacency(&adj, "degree")?;
+        Ok(super::node_degrees(&edges))
+    }
+
+    /// Finds a minimum spanning tree of a graph with Kruskal's algorithm, given its adjacency
+    /// matrix. The direction of edges is ignored, and if the graph is not connected the result is
+    /// a minimum spanning forest with a tree for each component. Returns a map of the `edges` of
+    /// the tree as `[i, j, weight]` with `i < j`, in the order they were added, and its total
+    /// `weight`.
+    /// ```typescript
+    /// let adj = [[0, 2, 0, 6],
+    ///            [2, 0, 3, 8],
+    ///            [0, 3, 0, 0],
+    ///            [6, 8, 0, 0]];
+    /// let tree = minimum_spanning_tree(adj);
+    /// assert_eq(tree.edges, [[0, 1, 2.0], [1, 2, 3.0], [0, 3, 6.0]]);
+    /// assert_eq(tree.weight, 11.0);
+    /// ```
+    #[rhai_fn(name = "minimum_spanning_tree", return_raw)]
+    pub fn minimum_spanning_tree(adj: Array) -> Result<Map, Box<EvalAltResult>> {
+        let edges = super::dense_adjacency(&adj, "minimum_spanning_tree")?;
+        Ok(super::kruskal(&edges))
+    }
+
+    /// Finds a minimum spanning tree of a graph with Kruskal's algorithm, given a `SparseMatrix`
+    /// for its adjacency matrix. Returns a map of the `edges` of the tree as `[i, j, weight]` and
+    /// its total `weight`.
+    /// ```typescript
+    /// let adj = sparse([0, 1, 0], [1, 2, 2], [1.0, 1.0, 5.0], 3, 3);
+    /// assert_eq(minimum_spanning_tree(adj).weight, 2.0);
+    /// ```
+    #[cfg(feature = "sparse")]
+    #[rhai_fn(name = "minimum_spanning_tree", return_raw)]
+    pub fn minimum_spanning_tree_sparse(
+        adj: crate::SparseMatrix,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let edges = super::sparse_adjacency(&adj, "minimum_spanning_tree")?;
+        Ok(super::kruskal(&edges))
+    }
+}
@@ -0,0 +1,140 @@
+use rhai::plugin::*;
+
+/// The name, signature, source module, and documentation of a function.
+type FunctionDoc = (&'static str, &'static str, &'static str, &'static str);
+
+/// Returns the embedded documentation of every function, or an error if it was not generated.
+fn function_docs() -> Result<&'static [FunctionDoc], Box<rhai::EvalAltResult>> {
+    if crate::FUNCTION_DOCS.is_empty() {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::NotFound,
+            "Documentation is only available when rhai-sci is built with the `metadata` feature",
+        )
+        .into())
+    } else {
+        Ok(crate::FUNCTION_DOCS)
+    }
+}
+
+/// Looks up the documentation of every function registered under `name`, one entry per overload,
+/// each as its signature followed by its indented doc comments.
+fn help_text(name: &str) -> Result<String, Box<rhai::EvalAltResult>> {
+    let entries = function_docs()?
+        .iter()
+        .filter(|(fn_name, _, _, _)| *fn_name == name)
+        .map(|(_, signature, _, docs)| {
+            let mut entry = signature.to_string();
+            for line in docs.lines() {
+                entry.push('\n');
+                if !line.is_empty() {
+                    entry.push_str("    ");
+                    entry.push_str(line);
+                }
+            }
+            entry
+        })
+        .collect::<Vec<String>>();
+    if entries.is_empty() {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::NotFound,
+            format!("No documentation found for '{name}'"),
+        )
+        .into())
+    } else {
+        Ok(entries.join("\n\n"))
+    }
+}
+
+/// Describes a function as a map of its name, signature, and source module.
+fn function_entry(name: &str, signature: &str, module: &str) -> rhai::Dynamic {
+    let mut entry = rhai::Map::new();
+    entry.insert("name".into(), name.into());
+    entry.insert("signature".into(), signature.into());
+    entry.insert("module".into(), module.into());
+    rhai::Dynamic::from_map(entry)
+}
+
+/// Ranks how well a function name matches a search, ignoring case: 0 for an exact match, then 1
+/// for a prefix, 2 for a substring, and 3 if the characters of the search appear in order.
+fn search_rank(name: &str, query: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name == query {
+        Some(0)
+    } else if name.starts_with(&query) {
+        Some(1)
+    } else if name.contains(&query) {
+        Some(2)
+    } else {
+        let mut chars = name.chars();
+        query.chars().all(|q| chars.any(|c| c == q)).then_some(3)
+    }
+}
+
+#[export_module]
+pub mod help_functions {
+    use rhai::{Array, EvalAltResult, ImmutableString};
+
+    /// Returns the signature and documentation of a function, as text. A function with several
+    /// overloads has an entry for each of them, separated by blank lines. The documentation is
+    /// embedded at build time, so this requires the `metadata` feature.
+    /// ```typescript
+    /// let text = help("maxk");
+    /// assert(text.contains("maxk(arr: Array, k: "));
+    /// assert(text.contains("Returns the `k` highest values from an array."));
+    /// ```
+    /// ```typescript
+    /// let result = "no error";
+    /// try { help("not_a_function"); } catch { result = "error"; }
+    /// assert_eq(result, "error");
+    /// ```
+    #[rhai_fn(name = "help", return_raw)]
+    pub fn help(name: ImmutableString) -> Result<String, Box<EvalAltResult>> {
+        super::help_text(&name)
+    }
+
+    /// Lists every function in the package as an array of maps with the keys `name`,
+    /// `signature`, and `module` (the source file that defines it), with one entry per overload.
+    /// Since `module` is a keyword in Rhai, read that one as `f["module"]`. Like `help`, this
+    /// requires the `metadata` feature.
+    /// ```typescript
+    /// let functions = lab_functions();
+    /// let argmax = functions.filter(|f| f.name == "argmax");
+    /// assert_eq(argmax.len(), 1);
+    /// assert_eq(argmax[0].signature, "argmax(arr: Array) -> Dynamic");
+    /// assert_eq(argmax[0]["module"], "statistics");
+    /// ```
+    #[rhai_fn(name = "lab_functions", return_raw)]
+    pub fn lab_functions() -> Result<Array, Box<EvalAltResult>> {
+        Ok(super::function_docs()?
+            .iter()
+            .map(|(name, signature, module, _)| super::function_entry(name, signature, module))
+            .collect())
+    }
+
+    /// Finds the functions whose names match a search, ignoring case, in the same form as
+    /// `lab_functions`. Exact matches come first, then names that start with the search, names
+    /// that contain it, and finally names that contain its characters in order, so that
+    /// `"lnsp"` finds `linspace`. Within each group, shorter names come first.
+    /// ```typescript
+    /// let found = lab_search("interp");
+    /// assert_eq(found[0].name, "interp1");
+    /// assert_eq(found[0]["module"], "misc");
+    /// ```
+    /// ```typescript
+    /// let found = lab_search("lnsp");
+    /// assert_eq(found[0].name, "linspace");
+    /// ```
+    #[rhai_fn(name = "lab_search", return_raw)]
+    pub fn lab_search(query: ImmutableString) -> Result<Array, Box<EvalAltResult>> {
+        let mut found = super::function_docs()?
+            .iter()
+            .filter_map(|entry| super::search_rank(entry.0, &query).map(|rank| (rank, entry)))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(rank, (name, _, _, _))| (*rank, name.len(), *name));
+        Ok(found
+            .into_iter()
+            .map(|(_, (name, signature, module, _))| super::function_entry(name, signature, module))
+            .collect())
+    }
+}
@@ -1,9 +1,197 @@
 use rhai::plugin::*;
 
+/// Returns the derivative of `y` with respect to `x` the way MATLAB's `gradient` does: central
+/// differences between the neighbors of each interior point and one-sided differences at the
+/// ends.
+fn central_differences(y: &[rhai::FLOAT], x: &[rhai::FLOAT]) -> Vec<rhai::FLOAT> {
+    let n = y.len();
+    if n < 2 {
+        return vec![0.0; n];
+    }
+    let mut dy = Vec::with_capacity(n);
+    dy.push((y[1] - y[0]) / (x[1] - x[0]));
+    for i in 1..n - 1 {
+        dy.push((y[i + 1] - y[i - 1]) / (x[i + 1] - x[i - 1]));
+    }
+    dy.push((y[n - 1] - y[n - 2]) / (x[n - 1] - x[n - 2]));
+    dy
+}
+
+/// Returns half the second differences of evenly spaced values, extrapolated linearly to the
+/// ends, or zeros if there are fewer than three values. With exactly three values, the single
+/// interior value is copied to both ends.
+fn half_second_differences(f: &[rhai::FLOAT]) -> Vec<rhai::FLOAT> {
+    let n = f.len();
+    if n < 3 {
+        return vec![0.0; n];
+    }
+    let mut g = vec![0.0; n];
+    for i in 1..n - 1 {
+        g[i] = (f[i + 1] - 2.0 * f[i] + f[i - 1]) / 2.0;
+    }
+    if n == 3 {
+        g[0] = g[1];
+        g[2] = g[1];
+    } else {
+        g[0] = 2.0 * g[1] - g[2];
+        g[n - 1] = 2.0 * g[n - 2] - g[n - 3];
+    }
+    g
+}
+
+/// A boundary condition at one end of a 1-D heat equation.
+#[derive(Clone, Copy)]
+enum HeatBoundary {
+    /// The temperature at the end is held at this value.
+    Dirichlet(rhai::FLOAT),
+    /// The derivative of the temperature along x at the end is held at this value.
+    Neumann(rhai::FLOAT),
+}
+
+/// Reads the boundary conditions and method for `solve_heat_1d` from a map, returning the left
+/// and right boundaries and whether to use the Crank-Nicolson method.
+fn heat_options(
+    bc: &rhai::Map,
+    initial: &[rhai::FLOAT],
+) -> Result<(HeatBoundary, HeatBoundary, bool), Box<rhai::EvalAltResult>> {
+    let error = |message: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, message)
+            .in_function("solve_heat_1d")
+            .with_argument("bc")
+            .into()
+    };
+    if let Some(key) = bc
+        .keys()
+        .find(|k| !["type", "left", "right", "method"].contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected \"type\", \"left\", \"right\", or \"method\""
+        )));
+    }
+    let value = |key: &str, default: rhai::FLOAT| match bc.get(key) {
+        None => Ok(default),
+        Some(v) => crate::if_int_convert_to_float_and_do(v.clone(), Ok)
+            .map_err(|_| error(format!("The {key} option must be a number, not {v}"))),
+    };
+    let (left, right) = match bc.get("type").map(|x| x.to_string()).as_deref() {
+        None | Some("dirichlet") => (
+            HeatBoundary::Dirichlet(value("left", initial[0])?),
+            HeatBoundary::Dirichlet(value("right", initial[initial.len() - 1])?),
+        ),
+        Some("neumann") => (
+            HeatBoundary::Neumann(value("left", 0.0)?),
+            HeatBoundary::Neumann(value("right", 0.0)?),
+        ),
+        Some(other) => {
+            return Err(error(format!(
+                "The type option must be \"dirichlet\" or \"neumann\", not {other}"
+            )))
+        }
+    };
+    let crank_nicolson = match bc.get("method").map(|x| x.to_string()).as_deref() {
+        None | Some("explicit") => false,
+        Some("crank_nicolson") => true,
+        Some(other) => {
+            return Err(error(format!(
+                "The method option must be \"explicit\" or \"crank_nicolson\", not {other}"
+            )))
+        }
+    };
+    Ok((left, right, crank_nicolson))
+}
+
+/// Solves a tridiagonal system with the Thomas algorithm, given the coefficients below, on, and
+/// above the diagonal of each row and the right-hand side.
+fn solve_tridiagonal(
+    lower: &[rhai::FLOAT],
+    diagonal: &[rhai::FLOAT],
+    upper: &[rhai::FLOAT],
+    rhs: &[rhai::FLOAT],
+) -> Vec<rhai::FLOAT> {
+    let n = diagonal.len();
+    let mut c = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    c[0] = upper[0] / diagonal[0];
+    d[0] = rhs[0] / diagonal[0];
+    for i in 1..n {
+        let m = diagonal[i] - lower[i] * c[i - 1];
+        c[i] = upper[i] / m;
+        d[i] = (rhs[i] - lower[i] * d[i - 1]) / m;
+    }
+    let mut x = d;
+    for i in (0..n - 1).rev() {
+        x[i] -= c[i] * x[i + 1];
+    }
+    x
+}
+
+/// Steps the 1-D heat equation forward `n_steps` times from `initial`, returning the temperature
+/// at every step. The second difference at each point is `a * u[i-1] + b * u[i] + c * u[i+1] + k`,
+/// where a Neumann boundary replaces the missing neighbor with a ghost point that gives the
+/// right slope.
+fn heat_steps(
+    initial: Vec<rhai::FLOAT>,
+    r: rhai::FLOAT,
+    dx: rhai::FLOAT,
+    n_steps: usize,
+    boundaries: (HeatBoundary, HeatBoundary),
+    crank_nicolson: bool,
+) -> Vec<Vec<rhai::FLOAT>> {
+    let n = initial.len();
+    let (mut a, b, mut c, mut k) = (vec![1.0; n], vec![-2.0; n], vec![1.0; n], vec![0.0; n]);
+    let mut fixed = vec![None; n];
+    match boundaries.0 {
+        HeatBoundary::Dirichlet(value) => fixed[0] = Some(value),
+        HeatBoundary::Neumann(slope) => (c[0], k[0]) = (2.0, -2.0 * dx * slope),
+    }
+    match boundaries.1 {
+        HeatBoundary::Dirichlet(value) => fixed[n - 1] = Some(value),
+        HeatBoundary::Neumann(slope) => (a[n - 1], k[n - 1]) = (2.0, 2.0 * dx * slope),
+    }
+    let second_difference = |u: &[rhai::FLOAT], i: usize| {
+        let before = if i > 0 { a[i] * u[i - 1] } else { 0.0 };
+        let after = if i < n - 1 { c[i] * u[i + 1] } else { 0.0 };
+        before + b[i] * u[i] + after + k[i]
+    };
+
+    let mut u = initial;
+    for (i, value) in fixed.iter().enumerate() {
+        if let Some(value) = value {
+            u[i] = *value;
+        }
+    }
+    let mut history = Vec::with_capacity(n_steps + 1);
+    history.push(u.clone());
+    for _ in 0..n_steps {
+        u = if crank_nicolson {
+            // (I - r/2 D) u' = (I + r/2 D) u + r k, with the fixed ends as rows of the identity
+            let (mut lower, mut diagonal, mut upper, mut rhs) =
+                (vec![0.0; n], vec![1.0; n], vec![0.0; n], vec![0.0; n]);
+            for i in 0..n {
+                if let Some(value) = fixed[i] {
+                    rhs[i] = value;
+                } else {
+                    lower[i] = -r / 2.0 * a[i];
+                    diagonal[i] = 1.0 - r / 2.0 * b[i];
+                    upper[i] = -r / 2.0 * c[i];
+                    rhs[i] = u[i] + r / 2.0 * (second_difference(&u, i) - k[i]) + r * k[i];
+                }
+            }
+            solve_tridiagonal(&lower, &diagonal, &upper, &rhs)
+        } else {
+            (0..n)
+                .map(|i| fixed[i].unwrap_or_else(|| u[i] + r * second_difference(&u, i)))
+                .collect()
+        };
+        history.push(u.clone());
+    }
+    history
+}
+
 #[export_module]
 pub mod int_and_diff {
     use crate::if_list_convert_to_vec_float_and_do;
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT};
+    use rhai::{Array, Dynamic, EvalAltResult, Map, NativeCallContext, FLOAT, INT};
 
     /// Returns the approximate integral of the curve defined by `y` and `x` using the trapezoidal method.
     /// ```typescript
@@ -21,10 +209,11 @@ pub mod int_and_diff {
     #[rhai_fn(name = "trapz", return_raw)]
     pub fn trapz(x: Array, y: Array) -> Result<Dynamic, Box<EvalAltResult>> {
         if x.len() != y.len() {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have the same length".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "The arrays must have the same length",
             )
+            .in_function("trapz")
             .into())
         } else {
             if_list_convert_to_vec_float_and_do(&mut y.clone(), |yf| {
@@ -60,6 +249,7 @@ pub mod int_and_diff {
             }
             Ok(Dynamic::from_float(trapsum))
         })
+        .map_err(crate::in_function("trapz", Some("arr")))
     }
 
     /// Returns the difference between successive elements of a 1-D array.
@@ -91,5 +281,220 @@ pub mod int_and_diff {
                 Ok(new_arr)
             },
         )
+        .map_err(crate::in_function("diff", Some("arr")))
+    }
+
+    /// Returns the numerical gradient of evenly spaced samples `y`, assuming unit spacing. Like
+    /// MATLAB's `gradient`, interior points use central differences, which are second-order
+    /// accurate, and the two ends use one-sided differences.
+    /// ```typescript
+    /// let y = [1, 4, 9, 16, 25];
+    /// assert_eq(gradient(y), [3.0, 4.0, 6.0, 8.0, 9.0]);
+    /// ```
+    #[rhai_fn(name = "gradient", return_raw, pure)]
+    pub fn gradient(y: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        if_list_convert_to_vec_float_and_do(y, |yf| {
+            let x = (0..yf.len()).map(|i| i as FLOAT).collect::<Vec<FLOAT>>();
+            Ok(super::central_differences(&yf, &x)
+                .into_iter()
+                .map(Dynamic::from_float)
+                .collect())
+        })
+        .map_err(crate::in_function("gradient", Some("y")))
+    }
+
+    /// Returns the numerical gradient of samples `y` taken at the points `x`, which may be
+    /// unevenly spaced. Interior points use central differences and the two ends use one-sided
+    /// differences, as in MATLAB's `gradient`.
+    /// ```typescript
+    /// let x = [0.0, 1.0, 3.0, 4.0];
+    /// let y = [1.0, 3.0, 7.0, 9.0];
+    /// assert_eq(gradient(y, x), [2.0, 2.0, 2.0, 2.0]);
+    /// ```
+    #[rhai_fn(name = "gradient", return_raw)]
+    pub fn gradient_with_x(mut y: Array, mut x: Array) -> Result<Array, Box<EvalAltResult>> {
+        if x.len() != y.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!("There are {} samples but {} points", y.len(), x.len()),
+            )
+            .in_function("gradient")
+            .with_argument("x")
+            .into());
+        }
+        if_list_convert_to_vec_float_and_do(&mut y, |yf| {
+            if_list_convert_to_vec_float_and_do(&mut x, |xf| {
+                Ok(super::central_differences(&yf, &xf)
+                    .into_iter()
+                    .map(Dynamic::from_float)
+                    .collect())
+            })
+        })
+    }
+
+    /// Returns the discrete Laplacian of a matrix of evenly spaced samples, scaled by 1/4 like
+    /// MATLAB's `del2`, so that each interior element is the average of its four neighbors minus
+    /// itself. The edges are extrapolated linearly from the interior, and a dimension with fewer
+    /// than three samples adds nothing. A 1-D array gives 1/4 of its second derivative.
+    /// ```typescript
+    /// // x^2 + y^2 sampled on a grid with unit spacing
+    /// let z = [[0, 1, 4],
+    ///          [1, 2, 5],
+    ///          [4, 5, 8]];
+    /// assert_eq(del2(z), [[1.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 1.0]]);
+    /// ```
+    /// ```typescript
+    /// let z = [[0, 1, 4, 9],
+    ///          [1, 2, 5, 10],
+    ///          [4, 5, 8, 13],
+    ///          [9, 10, 13, 18]];
+    /// assert_eq(del2(z), [[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0],
+    ///                     [1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(del2([1, 4, 9, 16]), [0.5, 0.5, 0.5, 0.5]);
+    /// assert_eq(del2([0, 1, 8, 27]), [0.0, 1.5, 3.0, 4.5]);
+    /// assert_eq(del2([0, 1, 8]), [1.5, 1.5, 1.5]);
+    /// ```
+    #[rhai_fn(name = "del2", return_raw)]
+    pub fn del2(z: Array) -> Result<Array, Box<EvalAltResult>> {
+        let m = crate::Matrix::from_array(&z)?;
+        let (rows, cols) = (m.rows(), m.cols());
+        let mut laplacian = crate::Matrix::filled(rows, cols, 0.0);
+        for i in 0..rows {
+            let row = (0..cols).map(|j| m.get(i, j)).collect::<Vec<FLOAT>>();
+            for (j, g) in super::half_second_differences(&row).into_iter().enumerate() {
+                laplacian.set(i, j, laplacian.get(i, j) + g / 2.0);
+            }
+        }
+        for j in 0..cols {
+            let column = (0..rows).map(|i| m.get(i, j)).collect::<Vec<FLOAT>>();
+            for (i, g) in super::half_second_differences(&column)
+                .into_iter()
+                .enumerate()
+            {
+                laplacian.set(i, j, laplacian.get(i, j) + g / 2.0);
+            }
+        }
+        if z.iter().any(|row| row.is_array()) {
+            Ok(laplacian.to_array())
+        } else {
+            Ok(laplacian.row(0))
+        }
+    }
+
+    /// Solves the 1-D heat (diffusion) equation, du/dt = alpha d²u/dx², with finite differences,
+    /// starting from the `initial` temperatures at points spaced `dx` apart and taking `n_steps`
+    /// steps of `dt`. The ends are held at their initial temperatures and the explicit method is
+    /// used; see `solve_heat_1d(initial, alpha, dx, dt, n_steps, bc)` for other boundary
+    /// conditions and the Crank-Nicolson method. Returns a matrix with a row for the temperatures
+    /// at each time, starting with the initial ones.
+    /// ```typescript
+    /// let u = solve_heat_1d([0.0, 0.0, 1.0, 0.0, 0.0], 1.0, 1.0, 0.25, 2);
+    /// assert_eq(u[1], [0.0, 0.25, 0.5, 0.25, 0.0]);
+    /// assert_eq(u[2], [0.0, 0.25, 0.375, 0.25, 0.0]);
+    /// ```
+    #[rhai_fn(name = "solve_heat_1d", return_raw)]
+    pub fn solve_heat_1d(
+        ctx: NativeCallContext,
+        initial: Array,
+        alpha: FLOAT,
+        dx: FLOAT,
+        dt: FLOAT,
+        n_steps: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        solve_heat_1d_with_bc(ctx, initial, alpha, dx, dt, n_steps, Map::new())
+    }
+
+    /// Solves the 1-D heat (diffusion) equation, du/dt = alpha d²u/dx², with finite differences,
+    /// starting from the `initial` temperatures at points spaced `dx` apart and taking `n_steps`
+    /// steps of `dt`, with boundary conditions and options given as a map:
+    /// - `type`: `"dirichlet"` (the default) to hold the temperature at each end, or `"neumann"`
+    ///   to hold its slope du/dx
+    /// - `left`, `right`: the temperature or slope at each end, which default to the initial
+    ///   temperatures at the ends or to zero slope (an insulated end)
+    /// - `method`: `"explicit"` (the default), which is stable only if alpha dt / dx² is at most
+    ///   0.5, or `"crank_nicolson"`, which is stable for any step and second-order accurate in
+    ///   time
+    ///
+    /// Returns a matrix with a row for the temperatures at each time, starting with the initial
+    /// ones.
+    /// ```typescript
+    /// let initial = [0.0, 0.0, 0.0, 0.0, 0.0];
+    /// let u = solve_heat_1d(initial, 1.0, 0.25, 0.5, 200, #{left: 1.0, right: 0.0, method: "crank_nicolson"});
+    /// assert_approx_eq(u[200], [1.0, 0.75, 0.5, 0.25, 0.0], 0.0, 1e-6);
+    /// ```
+    /// ```typescript
+    /// // An insulated rod evens out
+    /// let u = solve_heat_1d([1.0, 0.0, 0.0, 0.0], 0.1, 1.0, 1.0, 500, #{type: "neumann"});
+    /// let even = 1.0 / 6.0;
+    /// assert_approx_eq(u[500], [even, even, even, even], 0.0, 1e-6);
+    /// ```
+    #[rhai_fn(name = "solve_heat_1d", return_raw)]
+    pub fn solve_heat_1d_with_bc(
+        ctx: NativeCallContext,
+        mut initial: Array,
+        alpha: FLOAT,
+        dx: FLOAT,
+        dt: FLOAT,
+        n_steps: INT,
+        bc: Map,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let error = |message: String, argument: &str| -> Box<EvalAltResult> {
+            crate::LabError::new(crate::LabErrorKind::InvalidArgument, message)
+                .in_function("solve_heat_1d")
+                .with_argument(argument)
+                .into()
+        };
+        let u0 = if_list_convert_to_vec_float_and_do(&mut initial, Ok)?;
+        if u0.len() < 3 {
+            return Err(error(
+                format!("There must be at least 3 points, not {}", u0.len()),
+                "initial",
+            ));
+        }
+        if !(alpha >= 0.0 && alpha.is_finite()) {
+            return Err(error(
+                format!("The diffusivity must be finite and non-negative, not {alpha}"),
+                "alpha",
+            ));
+        }
+        if !(dx > 0.0 && dx.is_finite()) {
+            return Err(error(
+                format!("The spacing must be positive, not {dx}"),
+                "dx",
+            ));
+        }
+        if !(dt > 0.0 && dt.is_finite()) {
+            return Err(error(
+                format!("The time step must be positive, not {dt}"),
+                "dt",
+            ));
+        }
+        if n_steps < 0 {
+            return Err(error(
+                format!("The number of steps must be non-negative, not {n_steps}"),
+                "n_steps",
+            ));
+        }
+        let (left, right, crank_nicolson) = super::heat_options(&bc, &u0)?;
+        let r = alpha * dt / (dx * dx);
+        if !crank_nicolson && r > 0.5 {
+            return Err(error(
+                format!(
+                    "The explicit method is unstable because alpha dt / dx² is {r}, more than 0.5; \
+                     use a smaller dt or method: \"crank_nicolson\""
+                ),
+                "dt",
+            ));
+        }
+        let n_steps = n_steps as usize;
+        crate::check_array_shape(&ctx, &[n_steps.saturating_add(1), u0.len()])?;
+        Ok(
+            super::heat_steps(u0, r, dx, n_steps, (left, right), crank_nicolson)
+                .into_iter()
+                .map(|row| Dynamic::from_array(row.into_iter().map(Dynamic::from_float).collect()))
+                .collect(),
+        )
     }
 }
@@ -0,0 +1,298 @@
+#[cfg(any(feature = "ndarray", feature = "nalgebra"))]
+use crate::Matrix;
+#[cfg(any(
+    feature = "ndarray",
+    feature = "nalgebra",
+    all(feature = "polars", feature = "data")
+))]
+use rhai::FLOAT;
+
+/// Takes the elements of an `ndarray` array in logical (row-major) order, reusing its storage
+/// when it is already laid out that way.
+#[cfg(feature = "ndarray")]
+fn ndarray_into_vec<D: ndarraylib::Dimension>(a: ndarraylib::Array<FLOAT, D>) -> Vec<FLOAT> {
+    let n = a.len();
+    let a = if a.is_standard_layout() {
+        a
+    } else {
+        a.as_standard_layout().into_owned()
+    };
+    let (mut data, offset) = a.into_raw_vec_and_offset();
+    let offset = offset.unwrap_or(0);
+    data.truncate(offset + n);
+    data.drain(..offset);
+    data
+}
+
+#[cfg(feature = "ndarray")]
+impl From<ndarraylib::Array2<FLOAT>> for Matrix {
+    /// Converts an `ndarray` matrix into a `Matrix`, without copying if it is in row-major order.
+    /// ```
+    /// use ndarraylib::array;
+    /// use rhai_sci::Matrix;
+    ///
+    /// let m = Matrix::from(array![[1.0, 2.0], [3.0, 4.0]]);
+    /// assert_eq!((m.rows(), m.cols()), (2, 2));
+    /// assert_eq!(m.get(1, 0), 3.0);
+    /// ```
+    fn from(a: ndarraylib::Array2<FLOAT>) -> Self {
+        let (rows, cols) = a.dim();
+        Matrix::new(rows, cols, ndarray_into_vec(a)).unwrap()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl From<ndarraylib::ArrayView2<'_, FLOAT>> for Matrix {
+    /// Copies a view of an `ndarray` matrix into a `Matrix`.
+    fn from(a: ndarraylib::ArrayView2<'_, FLOAT>) -> Self {
+        let (rows, cols) = a.dim();
+        Matrix::new(rows, cols, a.iter().copied().collect()).unwrap()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl From<ndarraylib::Array1<FLOAT>> for Matrix {
+    /// Converts an `ndarray` vector into a `Matrix` with one row, without copying if it is
+    /// contiguous.
+    fn from(a: ndarraylib::Array1<FLOAT>) -> Self {
+        let n = a.len();
+        Matrix::new(1, n, ndarray_into_vec(a)).unwrap()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl From<Matrix> for ndarraylib::Array2<FLOAT> {
+    /// Converts a `Matrix` into an `ndarray` matrix without copying.
+    /// ```
+    /// use ndarraylib::{array, Array2};
+    /// use rhai_sci::{eval, Matrix};
+    ///
+    /// let m = eval::<Matrix>("matrix([[1, 2], [3, 4]])").unwrap();
+    /// assert_eq!(Array2::from(m), array![[1.0, 2.0], [3.0, 4.0]]);
+    /// ```
+    fn from(m: Matrix) -> Self {
+        let shape = (m.rows(), m.cols());
+        ndarraylib::Array2::from_shape_vec(shape, m.into_data()).unwrap()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl TryFrom<Matrix> for ndarraylib::Array1<FLOAT> {
+    type Error = crate::LabError;
+
+    /// Converts a `Matrix` with one row or one column into an `ndarray` vector without copying.
+    fn try_from(m: Matrix) -> Result<Self, Self::Error> {
+        if m.rows() != 1 && m.cols() != 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "Only a row or a column vector can become an Array1, not a {} x {} matrix",
+                    m.rows(),
+                    m.cols()
+                ),
+            ));
+        }
+        Ok(ndarraylib::Array1::from_vec(m.into_data()))
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl Matrix {
+    /// Borrows the matrix as an `ndarray` view, without copying.
+    /// ```
+    /// use rhai_sci::Matrix;
+    ///
+    /// let m = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    /// assert_eq!(m.as_ndarray().column(2).sum(), 9.0);
+    /// ```
+    pub fn as_ndarray(&self) -> ndarraylib::ArrayView2<'_, FLOAT> {
+        ndarraylib::ArrayView2::from_shape((self.rows(), self.cols()), self.data()).unwrap()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebralib::DMatrix<FLOAT>> for Matrix {
+    /// Converts a `nalgebra` matrix into a `Matrix`, reordering its elements from column-major to
+    /// row-major order.
+    /// ```
+    /// use nalgebralib::DMatrix;
+    /// use rhai_sci::Matrix;
+    ///
+    /// let m = Matrix::from(DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 3.0, 4.0]));
+    /// assert_eq!(m.data(), &[1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    fn from(a: nalgebralib::DMatrix<FLOAT>) -> Self {
+        let (rows, cols) = a.shape();
+        let mut data = Vec::with_capacity(rows * cols);
+        for i in 0..rows {
+            data.extend(a.row(i).iter());
+        }
+        Matrix::new(rows, cols, data).unwrap()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebralib::DVector<FLOAT>> for Matrix {
+    /// Converts a `nalgebra` vector into a `Matrix` with one column.
+    fn from(v: nalgebralib::DVector<FLOAT>) -> Self {
+        let n = v.len();
+        Matrix::new(n, 1, v.iter().copied().collect()).unwrap()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Matrix> for nalgebralib::DMatrix<FLOAT> {
+    /// Converts a `Matrix` into a `nalgebra` matrix.
+    /// ```
+    /// use nalgebralib::DMatrix;
+    /// use rhai_sci::{eval, Matrix};
+    ///
+    /// let m = eval::<Matrix>("matrix([[1, 2], [3, 4]])").unwrap();
+    /// let a = DMatrix::from(m);
+    /// assert_eq!(&a * &a, DMatrix::from_row_slice(2, 2, &[7.0, 10.0, 15.0, 22.0]));
+    /// ```
+    fn from(m: Matrix) -> Self {
+        nalgebralib::DMatrix::from_row_slice(m.rows(), m.cols(), m.data())
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl TryFrom<Matrix> for nalgebralib::DVector<FLOAT> {
+    type Error = crate::LabError;
+
+    /// Converts a `Matrix` with one row or one column into a `nalgebra` vector without copying.
+    fn try_from(m: Matrix) -> Result<Self, Self::Error> {
+        if m.rows() != 1 && m.cols() != 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "Only a row or a column vector can become a DVector, not a {} x {} matrix",
+                    m.rows(),
+                    m.cols()
+                ),
+            ));
+        }
+        Ok(nalgebralib::DVector::from_vec(m.into_data()))
+    }
+}
+
+#[cfg(all(feature = "polars", feature = "data"))]
+impl TryFrom<&polars::prelude::DataFrame> for crate::Table {
+    type Error = crate::LabError;
+
+    /// Converts a `polars` data frame into a `Table`. Integer, boolean, and string columns keep
+    /// their types, and other columns become floats. Nulls become `NaN`, so an integer column with
+    /// nulls becomes a column of floats, and a boolean or string column with nulls is an error.
+    fn try_from(df: &polars::prelude::DataFrame) -> Result<Self, Self::Error> {
+        use polars::prelude::DataType;
+        let error =
+            |message: String| crate::LabError::new(crate::LabErrorKind::InvalidArgument, message);
+        let read = |err: polars::prelude::PolarsError| error(format!("Cannot read column: {err}"));
+        let mut names = Vec::with_capacity(df.width());
+        let mut columns = Vec::with_capacity(df.width());
+        for series in df.get_columns() {
+            let complete = series.null_count() == 0;
+            let column = match series.dtype() {
+                DataType::Boolean if complete => {
+                    crate::Column::Bool(series.bool().map_err(read)?.into_no_null_iter().collect())
+                }
+                DataType::Utf8 if complete => crate::Column::Text(
+                    series
+                        .utf8()
+                        .map_err(read)?
+                        .into_no_null_iter()
+                        .map(Into::into)
+                        .collect(),
+                ),
+                DataType::Boolean | DataType::Utf8 => {
+                    return Err(error(format!(
+                        "Column '{}' has missing values, which only a column of numbers can hold",
+                        series.name()
+                    )))
+                }
+                DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                    if complete =>
+                {
+                    crate::Column::Int(
+                        series
+                            .cast(&DataType::Int64)
+                            .map_err(read)?
+                            .i64()
+                            .map_err(read)?
+                            .into_no_null_iter()
+                            .map(|x| x as rhai::INT)
+                            .collect(),
+                    )
+                }
+                _ => crate::Column::Float(
+                    series
+                        .cast(&DataType::Float64)
+                        .map_err(read)?
+                        .f64()
+                        .map_err(read)?
+                        .into_iter()
+                        .map(|x| x.map_or(FLOAT::NAN, |x| x as FLOAT))
+                        .collect(),
+                ),
+            };
+            names.push(series.name().to_string());
+            columns.push(column);
+        }
+        crate::Table::new(names, columns).map_err(|err| {
+            crate::LabError::from_eval(&err).unwrap_or_else(|| error(err.to_string()))
+        })
+    }
+}
+
+#[cfg(all(feature = "polars", feature = "data"))]
+impl TryFrom<&crate::Table> for polars::prelude::DataFrame {
+    type Error = crate::LabError;
+
+    /// Converts a `Table` into a `polars` data frame, writing `NaN` in columns of floats as nulls.
+    /// ```
+    /// use polars::prelude::DataFrame;
+    /// use rhai_sci::{engine, Table};
+    ///
+    /// let t = engine()
+    ///     .eval::<Table>(r#"table(["id", "score"], [[1, 0.5], [2, ()]])"#)
+    ///     .unwrap();
+    /// let df = DataFrame::try_from(&t).unwrap();
+    /// assert_eq!(df.shape(), (2, 2));
+    /// assert_eq!(df.column("score").unwrap().null_count(), 1);
+    /// assert_eq!(Table::try_from(&df).unwrap().nrows(), 2);
+    /// ```
+    fn try_from(table: &crate::Table) -> Result<Self, Self::Error> {
+        use polars::prelude::{NamedFrom, Series};
+        let columns = table
+            .names()
+            .iter()
+            .zip(table.columns())
+            .map(|(name, column)| match column {
+                crate::Column::Int(v) => Series::new(name, v.as_slice()),
+                crate::Column::Float(v) => Series::new(
+                    name,
+                    v.iter()
+                        .map(|x| (!x.is_nan()).then_some(*x))
+                        .collect::<Vec<_>>(),
+                ),
+                crate::Column::Bool(v) => Series::new(name, v.as_slice()),
+                crate::Column::Text(v) => {
+                    Series::new(name, v.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+                }
+            })
+            .collect();
+        polars::prelude::DataFrame::new(columns).map_err(|err| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot build a data frame: {err}"),
+            )
+        })
+    }
+}
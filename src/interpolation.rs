@@ -0,0 +1,523 @@
+use rhai::plugin::*;
+
+/// A piecewise cubic Hermite interpolating polynomial whose slopes are chosen (with the
+/// Fritsch-Carlson method) so that the interpolant is monotone wherever the data are.
+#[derive(Clone, Debug)]
+pub struct Pchip {
+    /// Breakpoints, strictly increasing
+    x: Vec<rhai::FLOAT>,
+    /// Values at the breakpoints
+    y: Vec<rhai::FLOAT>,
+    /// Slopes at the breakpoints
+    d: Vec<rhai::FLOAT>,
+}
+
+impl Pchip {
+    /// Builds the interpolant, checking that the breakpoints are strictly increasing.
+    pub fn new(x: Vec<rhai::FLOAT>, y: Vec<rhai::FLOAT>) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if x.len() != y.len() || x.len() < 2 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "x and y must have the same length, of at least 2",
+            )
+            .into());
+        }
+        if x.windows(2)
+            .any(|w| w[1].partial_cmp(&w[0]) != Some(std::cmp::Ordering::Greater))
+        {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "x must be strictly increasing",
+            )
+            .into());
+        }
+
+        let n = x.len();
+        let h = x
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .collect::<Vec<rhai::FLOAT>>();
+        let delta = (0..n - 1)
+            .map(|k| (y[k + 1] - y[k]) / h[k])
+            .collect::<Vec<rhai::FLOAT>>();
+
+        let mut d = vec![0.0; n];
+        if n == 2 {
+            d[0] = delta[0];
+            d[1] = delta[0];
+        } else {
+            // Interior slopes are weighted harmonic means of the neighbouring secants, or zero at
+            // local extrema
+            for k in 1..n - 1 {
+                if delta[k - 1] * delta[k] > 0.0 {
+                    let w1 = 2.0 * h[k] + h[k - 1];
+                    let w2 = h[k] + 2.0 * h[k - 1];
+                    d[k] = (w1 + w2) / (w1 / delta[k - 1] + w2 / delta[k]);
+                }
+            }
+            d[0] = Self::end_slope(h[0], h[1], delta[0], delta[1]);
+            d[n - 1] = Self::end_slope(h[n - 2], h[n - 3], delta[n - 2], delta[n - 3]);
+        }
+        Ok(Pchip { x, y, d })
+    }
+
+    /// Computes a shape-preserving slope at an end point from a three-point formula.
+    fn end_slope(
+        h0: rhai::FLOAT,
+        h1: rhai::FLOAT,
+        delta0: rhai::FLOAT,
+        delta1: rhai::FLOAT,
+    ) -> rhai::FLOAT {
+        let sign = |v: rhai::FLOAT| (v > 0.0) as i8 - (v < 0.0) as i8;
+        let d = ((2.0 * h0 + h1) * delta0 - h0 * delta1) / (h0 + h1);
+        if sign(d) != sign(delta0) {
+            0.0
+        } else if sign(delta0) != sign(delta1) && d.abs() > (3.0 * delta0).abs() {
+            3.0 * delta0
+        } else {
+            d
+        }
+    }
+
+    /// The breakpoints, in increasing order.
+    pub fn breakpoints(&self) -> &[rhai::FLOAT] {
+        &self.x
+    }
+
+    /// The values at the breakpoints.
+    pub fn values(&self) -> &[rhai::FLOAT] {
+        &self.y
+    }
+
+    /// Finds the interval containing `xq`, using the end intervals for extrapolation.
+    fn interval(&self, xq: rhai::FLOAT) -> usize {
+        self.x
+            .partition_point(|v| *v <= xq)
+            .clamp(1, self.x.len() - 1)
+            - 1
+    }
+
+    /// Evaluates the interpolant at `xq`.
+    pub fn evaluate(&self, xq: rhai::FLOAT) -> rhai::FLOAT {
+        let k = self.interval(xq);
+        let h = self.x[k + 1] - self.x[k];
+        let t = (xq - self.x[k]) / h;
+        let h00 = (1.0 + 2.0 * t) * (1.0 - t) * (1.0 - t);
+        let h10 = t * (1.0 - t) * (1.0 - t);
+        let h01 = t * t * (3.0 - 2.0 * t);
+        let h11 = t * t * (t - 1.0);
+        h00 * self.y[k] + h10 * h * self.d[k] + h01 * self.y[k + 1] + h11 * h * self.d[k + 1]
+    }
+
+    /// Evaluates the first derivative of the interpolant at `xq`.
+    pub fn deriv(&self, xq: rhai::FLOAT) -> rhai::FLOAT {
+        let k = self.interval(xq);
+        let h = self.x[k + 1] - self.x[k];
+        let t = (xq - self.x[k]) / h;
+        let dh00 = 6.0 * t * (t - 1.0);
+        let dh10 = (1.0 - t) * (1.0 - 3.0 * t);
+        let dh01 = -dh00;
+        let dh11 = t * (3.0 * t - 2.0);
+        (dh00 * self.y[k] + dh01 * self.y[k + 1]) / h + dh10 * self.d[k] + dh11 * self.d[k + 1]
+    }
+}
+
+/// Evaluates a radial basis function at distance `r`.
+fn rbf_kernel(kernel: &str, epsilon: rhai::FLOAT, r: rhai::FLOAT) -> rhai::FLOAT {
+    let er = epsilon * r;
+    match kernel {
+        "gaussian" => (-er * er).exp(),
+        _ if er > 0.0 => er * er * er.ln(),
+        _ => 0.0,
+    }
+}
+
+/// Returns the Euclidean distance between two points.
+fn distance(a: &[rhai::FLOAT], b: &[rhai::FLOAT]) -> rhai::FLOAT {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<rhai::FLOAT>()
+        .sqrt()
+}
+
+#[export_module]
+pub mod interpolation_functions {
+    use crate::{
+        array_to_points_2d, delaunay_triangles, if_int_convert_to_float_and_do,
+        if_list_convert_to_vec_float_and_do,
+    };
+    #[cfg(feature = "nalgebra")]
+    use nalgebralib::{DMatrix, DVector};
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT};
+
+    /// A monotone piecewise cubic Hermite interpolant, created with `pchip`.
+    pub type Pchip = crate::Pchip;
+
+    /// Creates a piecewise cubic Hermite interpolating polynomial (PCHIP) through the points
+    /// `(x, y)`. The interpolant preserves the shape of the data: it is monotone wherever the data
+    /// are, and does not overshoot at local extrema. Evaluate it with `.evaluate` (`eval` is a
+    /// reserved word in Rhai) and its derivative with `.deriv`. Outside the range of `x`, the end
+    /// polynomials are extrapolated.
+    /// ```typescript
+    /// let p = pchip([0, 1, 2, 3], [0, 1, 1, 2]);
+    /// assert_eq(p.evaluate(0), 0.0);
+    /// assert_eq(p.evaluate(1.5), 1.0);
+    /// assert_eq(p.deriv(1.5), 0.0);
+    /// ```
+    #[rhai_fn(name = "pchip", return_raw)]
+    pub fn pchip(x: Array, y: Array) -> Result<Pchip, Box<EvalAltResult>> {
+        let mut x = x;
+        let mut y = y;
+        let x = if_list_convert_to_vec_float_and_do(&mut x, Ok)?;
+        let y = if_list_convert_to_vec_float_and_do(&mut y, Ok)?;
+        Pchip::new(x, y)
+    }
+
+    /// Evaluates a PCHIP interpolant at a point.
+    /// ```typescript
+    /// let p = pchip([0, 1, 2], [0, 2, 4]);
+    /// assert_eq(p.evaluate(0.5), 1.0);
+    /// ```
+    #[rhai_fn(name = "evaluate", return_raw, pure)]
+    pub fn evaluate(p: &mut Pchip, xq: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        if_int_convert_to_float_and_do(xq, |xq| Ok(p.evaluate(xq)))
+            .map_err(crate::in_function("evaluate", Some("xq")))
+    }
+
+    /// Evaluates a PCHIP interpolant at each point of an array.
+    /// ```typescript
+    /// let p = pchip([0, 1, 2], [0, 2, 4]);
+    /// assert_eq(p.evaluate([0.5, 1.5]), [1.0, 3.0]);
+    /// ```
+    #[rhai_fn(name = "evaluate", return_raw, pure)]
+    pub fn evaluate_array(p: &mut Pchip, xq: Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut xq = xq;
+        if_list_convert_to_vec_float_and_do(&mut xq, |xq| {
+            Ok(xq
+                .iter()
+                .map(|x| Dynamic::from_float(p.evaluate(*x)))
+                .collect())
+        })
+    }
+
+    /// Evaluates the first derivative of a PCHIP interpolant at a point.
+    /// ```typescript
+    /// let p = pchip([0, 1, 2], [0, 2, 4]);
+    /// assert_eq(p.deriv(1), 2.0);
+    /// ```
+    #[rhai_fn(name = "deriv", return_raw, pure)]
+    pub fn deriv(p: &mut Pchip, xq: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        if_int_convert_to_float_and_do(xq, |xq| Ok(p.deriv(xq)))
+            .map_err(crate::in_function("deriv", Some("xq")))
+    }
+
+    /// Evaluates the first derivative of a PCHIP interpolant at each point of an array.
+    /// ```typescript
+    /// let p = pchip([0, 1, 2, 3], [0, 1, 1, 2]);
+    /// assert_eq(p.deriv([1, 2]), [0.0, 0.0]);
+    /// ```
+    #[rhai_fn(name = "deriv", return_raw, pure)]
+    pub fn deriv_array(p: &mut Pchip, xq: Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut xq = xq;
+        if_list_convert_to_vec_float_and_do(&mut xq, |xq| {
+            Ok(xq
+                .iter()
+                .map(|x| Dynamic::from_float(p.deriv(*x)))
+                .collect())
+        })
+    }
+
+    /// Interpolates scattered 2-D data at a set of query points. `points` is an array of `[x, y]`
+    /// pairs with one `value` each, and `query_points` is an array of `[x, y]` pairs at which to
+    /// interpolate. The `method` is either `"linear"`, which interpolates linearly within the
+    /// triangles of the Delaunay triangulation of the points and returns NaN outside of their
+    /// convex hull, or `"nearest"`, which takes the value of the nearest point.
+    /// ```typescript
+    /// let points = [[0, 0], [1, 0], [0, 1], [1, 1]];
+    /// let values = [0, 1, 1, 2];
+    /// let v = griddata(points, values, [[0.5, 0.5], [0.25, 0.5]], "linear");
+    /// assert_eq(v, [1.0, 0.75]);
+    /// ```
+    /// ```typescript
+    /// let points = [[0, 0], [1, 0], [0, 1]];
+    /// let v = griddata(points, [10, 20, 30], [[0.9, 0.1], [5, 5]], "nearest");
+    /// assert_eq(v, [20.0, 20.0]);
+    /// ```
+    /// ```typescript
+    /// let points = [[0, 0], [1, 0], [0, 1]];
+    /// let v = griddata(points, [10, 20, 30], [[2, 2]], "linear");
+    /// assert(v[0].is_nan());
+    /// ```
+    #[rhai_fn(name = "griddata", return_raw)]
+    pub fn griddata(
+        points: Array,
+        values: Array,
+        query_points: Array,
+        method: ImmutableString,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let points = array_to_points_2d(points)?;
+        let queries = array_to_points_2d(query_points)?;
+        let mut values = values;
+        let values = if_list_convert_to_vec_float_and_do(&mut values, Ok)?;
+        if values.len() != points.len() || points.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "There must be one value for each point, and at least one point",
+            )
+            .in_function("griddata")
+            .into());
+        }
+
+        let interpolated = match method.as_str() {
+            "nearest" => queries
+                .iter()
+                .map(|[qx, qy]| {
+                    let distance = |p: &[FLOAT; 2]| (p[0] - qx).powi(2) + (p[1] - qy).powi(2);
+                    let nearest = (0..points.len())
+                        .min_by(|a, b| distance(&points[*a]).total_cmp(&distance(&points[*b])))
+                        .unwrap();
+                    values[nearest]
+                })
+                .collect::<Vec<FLOAT>>(),
+            "linear" => {
+                let triangles = delaunay_triangles(&points);
+                queries
+                    .iter()
+                    .map(|[qx, qy]| {
+                        for [a, b, c] in &triangles {
+                            let ([ax, ay], [bx, by], [cx, cy]) =
+                                (points[*a], points[*b], points[*c]);
+                            let area = (bx - ax) * (cy - ay) - (by - ay) * (cx - ax);
+                            let wa = ((bx - qx) * (cy - qy) - (by - qy) * (cx - qx)) / area;
+                            let wb = ((cx - qx) * (ay - qy) - (cy - qy) * (ax - qx)) / area;
+                            let wc = 1.0 - wa - wb;
+                            let tol = -1e-12;
+                            if wa >= tol && wb >= tol && wc >= tol {
+                                return wa * values[*a] + wb * values[*b] + wc * values[*c];
+                            }
+                        }
+                        FLOAT::NAN
+                    })
+                    .collect::<Vec<FLOAT>>()
+            }
+            _ => {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!(
+                        "Unknown interpolation method '{method}', expected 'linear' or 'nearest'"
+                    ),
+                )
+                .in_function("griddata")
+                .with_argument("method")
+                .into())
+            }
+        };
+        Ok(interpolated.into_iter().map(Dynamic::from_float).collect())
+    }
+
+    /// Converts an array of sample locations into a matrix with one row per sample. A flat array
+    /// is treated as one-dimensional samples.
+    fn to_samples(arr: Array) -> Result<crate::Matrix, Box<EvalAltResult>> {
+        let m = crate::Matrix::from_array(&arr)?;
+        if arr.iter().all(|x| !x.is_array()) {
+            crate::Matrix::new(m.cols(), 1, m.data().to_vec())
+        } else {
+            Ok(m)
+        }
+    }
+
+    /// Fits a radial basis function (RBF) interpolant to scattered data in any number of
+    /// dimensions, for use as a smooth surrogate of an expensive function. `x` holds one sample
+    /// location per row (or one number per sample for 1-D data), `y` the corresponding values,
+    /// `kernel` is `"gaussian"` (`exp(-(epsilon*r)^2)`) or `"thin_plate"` (`(epsilon*r)^2 *
+    /// ln(epsilon*r)`, augmented with a linear polynomial), and `epsilon` is the shape parameter.
+    /// Returns a model to be evaluated with `rbf_eval`.
+    /// ```typescript,only_f64
+    /// let x = [[0, 0], [1, 0], [0, 1], [1, 1], [0.5, 0.5]];
+    /// let y = [0, 1, 1, 2, 1.5];
+    /// let model = rbf_fit(x, y, "gaussian", 1.0);
+    /// let err = rbf_eval(model, x).map(|v, i| abs(v - y[i]));
+    /// assert(max(err) < 1e-9);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "rbf_fit", return_raw)]
+    pub fn rbf_fit(
+        x: Array,
+        y: Array,
+        kernel: ImmutableString,
+        epsilon: FLOAT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if kernel != "gaussian" && kernel != "thin_plate" {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Unknown kernel '{kernel}', expected 'gaussian' or 'thin_plate'"),
+            )
+            .in_function("rbf_fit")
+            .with_argument("kernel")
+            .into());
+        }
+        if epsilon.is_nan() || epsilon <= 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The shape parameter epsilon must be positive",
+            )
+            .in_function("rbf_fit")
+            .with_argument("epsilon")
+            .into());
+        }
+        let centers = to_samples(x.clone())?;
+        let mut y = y;
+        let y = if_list_convert_to_vec_float_and_do(&mut y, Ok)?;
+        let (n, d) = (centers.rows(), centers.cols());
+        if y.len() != n || n == 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "There must be one value for each sample, and at least one sample",
+            )
+            .in_function("rbf_fit")
+            .into());
+        }
+
+        // The thin plate spline is only conditionally positive definite, so a linear polynomial
+        // tail is added to make the system solvable
+        let n_poly = if kernel == "thin_plate" { d + 1 } else { 0 };
+        let row = |i: usize| &centers.data()[i * d..(i + 1) * d];
+        let kernel_name = kernel.as_str();
+        let kernel_matrix = crate::parallel_map_indices(n * n, n * n * d, |k| {
+            super::rbf_kernel(
+                kernel_name,
+                epsilon,
+                super::distance(row(k / n), row(k % n)),
+            )
+        });
+        let a = DMatrix::from_fn(n + n_poly, n + n_poly, |i, j| {
+            if i < n && j < n {
+                kernel_matrix[i * n + j]
+            } else if i < n {
+                if j == n {
+                    1.0
+                } else {
+                    row(i)[j - n - 1]
+                }
+            } else if j < n {
+                if i == n {
+                    1.0
+                } else {
+                    row(j)[i - n - 1]
+                }
+            } else {
+                0.0
+            }
+        });
+        let b = DVector::from_fn(n + n_poly, |i, _| if i < n { y[i] } else { 0.0 });
+        let coefficients = a.lu().solve(&b).ok_or_else(|| {
+            rhai::EvalAltResult::from(
+                crate::LabError::new(
+                    crate::LabErrorKind::Numerical,
+                    "The interpolation system is singular; check for duplicate samples",
+                )
+                .in_function("rbf_fit"),
+            )
+        })?;
+
+        let mut model = Map::new();
+        model.insert("kernel".into(), Dynamic::from(kernel));
+        model.insert("epsilon".into(), Dynamic::from_float(epsilon));
+        model.insert("centers".into(), Dynamic::from_array(centers.to_array()));
+        model.insert(
+            "weights".into(),
+            Dynamic::from_array(
+                coefficients
+                    .iter()
+                    .take(n)
+                    .map(|w| Dynamic::from_float(*w))
+                    .collect(),
+            ),
+        );
+        model.insert(
+            "polynomial".into(),
+            Dynamic::from_array(
+                coefficients
+                    .iter()
+                    .skip(n)
+                    .map(|w| Dynamic::from_float(*w))
+                    .collect(),
+            ),
+        );
+        Ok(model)
+    }
+
+    /// Evaluates a radial basis function model created by `rbf_fit` at query locations, given
+    /// with one location per row (or one number per location for 1-D data).
+    /// ```typescript,only_f64
+    /// let x = [0, 1, 2, 3];
+    /// let y = [1, 3, 5, 7];
+    /// let model = rbf_fit(x, y, "thin_plate", 1.0);
+    /// let yq = rbf_eval(model, [1.5, 4.0]);
+    /// assert(abs(yq[0] - 4.0) < 1e-9 && abs(yq[1] - 9.0) < 1e-9);
+    /// ```
+    #[rhai_fn(name = "rbf_eval", return_raw)]
+    pub fn rbf_eval(model: Map, xq: Array) -> Result<Array, Box<EvalAltResult>> {
+        let field = |name: &str| {
+            model.get(name).cloned().ok_or_else(|| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::NotFound,
+                        format!("The model has no '{name}' field; create it with rbf_fit"),
+                    )
+                    .in_function("rbf_eval")
+                    .with_argument("model"),
+                )
+            })
+        };
+        let kernel = field("kernel")?.into_immutable_string()?;
+        let kernel = kernel.as_str();
+        let epsilon = field("epsilon")?.as_float()?;
+        let centers = to_samples(field("centers")?.into_array()?)?;
+        let mut weights = field("weights")?.into_array()?;
+        let weights = if_list_convert_to_vec_float_and_do(&mut weights, Ok)?;
+        let polynomial = field("polynomial")?.into_array()?;
+        let polynomial = polynomial
+            .iter()
+            .map(|p| p.as_float())
+            .collect::<Result<Vec<FLOAT>, _>>()?;
+        let queries = to_samples(xq)?;
+
+        let d = centers.cols();
+        if queries.cols() != d {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The query locations have {} dimensions, but the model has {d}",
+                    queries.cols()
+                ),
+            )
+            .in_function("rbf_eval")
+            .with_argument("model")
+            .into());
+        }
+        let work = queries.rows() * weights.len() * d;
+        Ok(crate::parallel_map_indices(queries.rows(), work, |i| {
+            let q = &queries.data()[i * d..(i + 1) * d];
+            let mut value = weights
+                .iter()
+                .enumerate()
+                .map(|(j, w)| {
+                    w * super::rbf_kernel(
+                        kernel,
+                        epsilon,
+                        super::distance(q, &centers.data()[j * d..(j + 1) * d]),
+                    )
+                })
+                .sum::<FLOAT>();
+            if let Some((constant, linear)) = polynomial.split_first() {
+                value += constant + linear.iter().zip(q).map(|(c, x)| c * x).sum::<FLOAT>();
+            }
+            value
+        })
+        .into_iter()
+        .map(Dynamic::from_float)
+        .collect())
+    }
+}
@@ -0,0 +1,245 @@
+use rhai::FLOAT;
+
+/// The vector of floats that the kernels work on with the `simd` feature.
+#[cfg(all(feature = "simd", not(feature = "f32_float")))]
+type Lanes = wide::f64x4;
+
+/// The vector of floats that the kernels work on with the `simd` feature.
+#[cfg(all(feature = "simd", feature = "f32_float"))]
+type Lanes = wide::f32x8;
+
+/// The number of floats in `Lanes`.
+#[cfg(feature = "simd")]
+const WIDTH: usize = std::mem::size_of::<Lanes>() / std::mem::size_of::<FLOAT>();
+
+/// An element-wise arithmetic operation on floats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatOp {
+    /// `x + y`
+    Add,
+    /// `x - y`
+    Sub,
+    /// `x * y`
+    Mul,
+    /// `x / y`
+    Div,
+    /// `x` raised to the power `y`, which has no vectorized kernel
+    Pow,
+}
+
+impl FloatOp {
+    /// Applies the operation to two floats.
+    pub fn apply(self, x: FLOAT, y: FLOAT) -> FLOAT {
+        match self {
+            FloatOp::Add => x + y,
+            FloatOp::Sub => x - y,
+            FloatOp::Mul => x * y,
+            FloatOp::Div => x / y,
+            FloatOp::Pow => x.powf(y),
+        }
+    }
+}
+
+/// A way of combining all of the elements of an array of floats into one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg(feature = "tensor")]
+pub enum Reduction {
+    /// The sum of the elements
+    Sum,
+    /// The smallest element, ignoring NaNs
+    Min,
+    /// The largest element, ignoring NaNs
+    Max,
+}
+
+#[cfg(feature = "tensor")]
+impl Reduction {
+    /// The result for an empty array.
+    fn identity(self) -> FLOAT {
+        match self {
+            Reduction::Sum => 0.0,
+            Reduction::Min => FLOAT::INFINITY,
+            Reduction::Max => FLOAT::NEG_INFINITY,
+        }
+    }
+
+    /// Combines two partial results.
+    fn apply(self, x: FLOAT, y: FLOAT) -> FLOAT {
+        match self {
+            Reduction::Sum => x + y,
+            Reduction::Min => x.min(y),
+            Reduction::Max => x.max(y),
+        }
+    }
+}
+
+/// An argument of an element-wise operation: either the elements of an array or a single float
+/// that is combined with every element.
+#[derive(Clone, Copy, Debug)]
+pub enum Operand<'a> {
+    /// The elements of an array
+    Slice(&'a [FLOAT]),
+    /// A single float
+    Scalar(FLOAT),
+}
+
+impl Operand<'_> {
+    /// Returns element `i`.
+    fn get(&self, i: usize) -> FLOAT {
+        match self {
+            Operand::Slice(x) => x[i],
+            Operand::Scalar(x) => *x,
+        }
+    }
+
+    /// Returns the elements in `range`.
+    fn range(&self, range: std::ops::Range<usize>) -> Self {
+        match self {
+            Operand::Slice(x) => Operand::Slice(&x[range]),
+            Operand::Scalar(x) => Operand::Scalar(*x),
+        }
+    }
+
+    /// Returns the elements starting at `i` as a vector.
+    #[cfg(feature = "simd")]
+    fn lanes(&self, i: usize) -> Lanes {
+        match self {
+            Operand::Slice(x) => Lanes::new(x[i..i + WIDTH].try_into().unwrap()),
+            Operand::Scalar(x) => Lanes::splat(*x),
+        }
+    }
+}
+
+/// Applies `f` to consecutive ranges of `0..n` and concatenates the results, across threads if
+/// `n` is above the parallel threshold.
+fn in_blocks<F>(n: usize, f: F) -> Vec<FLOAT>
+where
+    F: Fn(std::ops::Range<usize>) -> Vec<FLOAT> + Sync + Send,
+{
+    if !crate::use_parallel(n) {
+        return f(0..n);
+    }
+    let blocks = n.div_ceil(crate::CHUNK_SIZE);
+    crate::parallel_map_indices(blocks, n, |k| {
+        f(k * crate::CHUNK_SIZE..n.min((k + 1) * crate::CHUNK_SIZE))
+    })
+    .concat()
+}
+
+/// Applies `op` to the first `n` pairs of elements of `a` and `b`. With the `simd` feature,
+/// every operation but `Pow` handles several elements per instruction.
+pub fn zip_floats(op: FloatOp, a: Operand, b: Operand, n: usize) -> Vec<FLOAT> {
+    in_blocks(n, |range| {
+        let (a, b, n) = (a.range(range.clone()), b.range(range.clone()), range.len());
+        let mut out = Vec::with_capacity(n);
+        let done = zip_lanes(op, a, b, n, &mut out);
+        out.extend((done..n).map(|i| op.apply(a.get(i), b.get(i))));
+        out
+    })
+}
+
+/// Applies `op` to as many whole vectors of elements as fit in `n`, appending the results to
+/// `out`, and returns the number of elements handled.
+#[cfg(feature = "simd")]
+fn zip_lanes(op: FloatOp, a: Operand, b: Operand, n: usize, out: &mut Vec<FLOAT>) -> usize {
+    let mut i = 0;
+    while i + WIDTH <= n {
+        let (x, y) = (a.lanes(i), b.lanes(i));
+        let z = match op {
+            FloatOp::Add => x + y,
+            FloatOp::Sub => x - y,
+            FloatOp::Mul => x * y,
+            FloatOp::Div => x / y,
+            FloatOp::Pow => break,
+        };
+        out.extend_from_slice(&z.to_array());
+        i += WIDTH;
+    }
+    i
+}
+
+/// Without the `simd` feature, every element is left to the scalar loop.
+#[cfg(not(feature = "simd"))]
+fn zip_lanes(_: FloatOp, _: Operand, _: Operand, _: usize, _: &mut Vec<FLOAT>) -> usize {
+    0
+}
+
+/// Computes `a * b + c` for the first `n` elements of each, in a single rounding where the
+/// hardware has a fused multiply-add instruction.
+#[cfg(feature = "tensor")]
+pub fn fma_floats(a: Operand, b: Operand, c: Operand, n: usize) -> Vec<FLOAT> {
+    in_blocks(n, |range| {
+        let (a, b, c) = (
+            a.range(range.clone()),
+            b.range(range.clone()),
+            c.range(range.clone()),
+        );
+        let n = range.len();
+        let mut out = Vec::with_capacity(n);
+        let done = fma_lanes(a, b, c, n, &mut out);
+        out.extend((done..n).map(|i| a.get(i).mul_add(b.get(i), c.get(i))));
+        out
+    })
+}
+
+/// Computes `a * b + c` for as many whole vectors of elements as fit in `n`, appending the
+/// results to `out`, and returns the number of elements handled.
+#[cfg(feature = "simd")]
+#[cfg(feature = "tensor")]
+fn fma_lanes(a: Operand, b: Operand, c: Operand, n: usize, out: &mut Vec<FLOAT>) -> usize {
+    let mut i = 0;
+    while i + WIDTH <= n {
+        out.extend_from_slice(&a.lanes(i).mul_add(b.lanes(i), c.lanes(i)).to_array());
+        i += WIDTH;
+    }
+    i
+}
+
+/// Without the `simd` feature, every element is left to the scalar loop.
+#[cfg(not(feature = "simd"))]
+#[cfg(feature = "tensor")]
+fn fma_lanes(_: Operand, _: Operand, _: Operand, _: usize, _: &mut Vec<FLOAT>) -> usize {
+    0
+}
+
+/// Combines the elements of `x` with `reduction`. With the `simd` feature, several running
+/// results are kept side by side in a vector, so sums may differ from adding the elements in
+/// order in the last few bits.
+#[cfg(feature = "tensor")]
+pub fn reduce_floats(x: &[FLOAT], reduction: Reduction) -> FLOAT {
+    crate::parallel_map_chunks(x, |chunk| {
+        let (result, rest) = reduce_lanes(chunk, reduction);
+        rest.iter().fold(result, |acc, v| reduction.apply(acc, *v))
+    })
+    .into_iter()
+    .fold(reduction.identity(), |acc, v| reduction.apply(acc, v))
+}
+
+/// Combines as many whole vectors of elements of `x` as fit, returning the result and the
+/// elements that are left over.
+#[cfg(feature = "simd")]
+#[cfg(feature = "tensor")]
+fn reduce_lanes(x: &[FLOAT], reduction: Reduction) -> (FLOAT, &[FLOAT]) {
+    let mut chunks = x.chunks_exact(WIDTH);
+    let mut acc = Lanes::splat(reduction.identity());
+    for chunk in &mut chunks {
+        let v = Lanes::new(chunk.try_into().unwrap());
+        acc = match reduction {
+            Reduction::Sum => acc + v,
+            Reduction::Min => acc.min(v),
+            Reduction::Max => acc.max(v),
+        };
+    }
+    let result = acc
+        .to_array()
+        .into_iter()
+        .fold(reduction.identity(), |a, v| reduction.apply(a, v));
+    (result, chunks.remainder())
+}
+
+/// Without the `simd` feature, every element is left to the scalar loop.
+#[cfg(not(feature = "simd"))]
+#[cfg(feature = "tensor")]
+fn reduce_lanes(x: &[FLOAT], reduction: Reduction) -> (FLOAT, &[FLOAT]) {
+    (reduction.identity(), x)
+}
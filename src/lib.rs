@@ -5,29 +5,201 @@
 #![doc = include_str!(concat!(env!("OUT_DIR"), "/rhai-sci-docs.md"))]
 #![doc = include_str!("../docs/highlight.html")]
 
+/// The error type raised by the functions in this package, and its kinds
+mod errors;
+pub use errors::{LabError, LabErrorKind};
+/// Splitting work across threads with the `parallel` feature
+mod parallel;
+#[cfg(feature = "parallel")]
+pub use parallel::set_max_threads;
+use parallel::{parallel_map_chunks, parallel_map_indices, use_parallel, CHUNK_SIZE};
+pub use parallel::{set_parallel_threshold, DEFAULT_PARALLEL_THRESHOLD};
+/// Element-wise and reduction kernels on contiguous floats, vectorized with the `simd` feature
+mod kernels;
+#[cfg(feature = "tensor")]
+use kernels::{fma_floats, reduce_floats, Reduction};
+use kernels::{zip_floats, FloatOp, Operand};
 mod patterns;
 use patterns::*;
 use rhai::{def_package, packages::Package, plugin::*, Engine, EvalAltResult};
+
 mod matrices_and_arrays;
 use matrices_and_arrays::matrix_functions;
+/// The `Matrix` type, its arithmetic, and matrix literals
+mod matrix_type;
+use matrix_type::matrix_type_functions;
+pub use matrix_type::{translate_matrix_literals, Matrix};
+/// Element-wise arithmetic on arrays and the `.*`, `./`, and `.^` operators
+mod elementwise;
+/// Conversions between `Matrix` and the array types of other crates
+mod interop;
+use elementwise::elementwise_functions;
+use elementwise::literal_end;
+pub use elementwise::{register_elementwise_operators, translate_elementwise_operators};
+/// Sparse matrices in compressed sparse row form
+#[cfg(feature = "sparse")]
+mod sparse;
+#[cfg(feature = "sparse")]
+use sparse::sparse_functions;
+#[cfg(feature = "sparse")]
+pub use sparse::SparseMatrix;
+/// N-dimensional arrays of floats
+#[cfg(feature = "tensor")]
+mod tensor;
+#[cfg(feature = "tensor")]
+use tensor::tensor_functions;
+#[cfg(feature = "tensor")]
+pub use tensor::Tensor;
+/// Integers of arbitrary size
+mod bignum;
+use bignum::bignum_functions;
+/// Searching and testing arrays of booleans
+mod logical;
+use logical::logical_functions;
+mod sets;
+use sets::set_functions;
+
 mod statistics;
 use statistics::stats;
-mod misc;
-use misc::misc_functions;
+mod moving;
+use moving::moving_functions;
 mod cumulative;
 use cumulative::cum_functions;
+/// Seedable random number generation and random sampling
+mod random;
+use random::random_functions;
+#[cfg(feature = "rand")]
+use random::{random_float, seed_rng_with_algorithm, with_rng};
+#[cfg(feature = "rand")]
+pub use random::{register_rng, LabRng};
+/// Quasi-random sequences and quasi-Monte Carlo integration
+#[cfg(feature = "sampling")]
+mod sampling;
+#[cfg(feature = "sampling")]
+use sampling::sampling_functions;
+/// Regression and classification metrics and cross-validation
+mod metrics;
+use metrics::metrics_functions;
+/// Multi-criteria decision making and Pareto fronts
+mod decision;
+use decision::decision_functions;
+
+mod misc;
+use misc::{complementary_error_function, misc_functions};
 mod integration_and_differentiation;
 use integration_and_differentiation::int_and_diff;
-mod assertions;
-use assertions::assert_functions;
+/// Interpolation of scattered and gridded data
+#[cfg(feature = "interpolation")]
+mod interpolation;
+#[cfg(feature = "interpolation")]
+use interpolation::interpolation_functions;
+#[cfg(feature = "interpolation")]
+pub use interpolation::Pchip;
+/// Filter responses and transfer functions
+#[cfg(feature = "signal")]
+mod signal;
+#[cfg(feature = "signal")]
+use signal::signal_functions;
+/// Simulation and tuning of PID controllers
+#[cfg(feature = "signal")]
+mod control;
+#[cfg(feature = "signal")]
+use control::control_functions;
+/// Shortest paths, spanning trees, and other algorithms on graphs
+mod graph;
+use graph::graph_functions;
+
+/// Vectors, polygons, and triangulations in the plane and in space
+#[cfg(feature = "geometry")]
+mod geometry;
+#[cfg(feature = "geometry")]
+use geometry::geometry_functions;
+/// Quaternions and rotations
+#[cfg(feature = "quaternion")]
+mod quaternion;
+#[cfg(feature = "quaternion")]
+use quaternion::quaternion_functions;
+#[cfg(feature = "quaternion")]
+pub use quaternion::Quaternion;
+/// Beam deflections and cross-section properties
+#[cfg(feature = "geometry")]
+mod structures;
+#[cfg(feature = "geometry")]
+use structures::structures_functions;
 mod constants;
 use constants::constant_definitions;
-mod moving;
-use moving::moving_functions;
-mod sets;
-use sets::set_functions;
+pub use constants::register_physics_constants;
+/// Physical quantities with units
+#[cfg(feature = "physics")]
+mod units;
+#[cfg(feature = "physics")]
+use units::units_functions;
+#[cfg(feature = "physics")]
+pub use units::Quantity;
+/// Ideal gases, the standard atmosphere, psychrometrics, and tables of fluid properties
+#[cfg(feature = "physics")]
+mod thermo;
+#[cfg(feature = "physics")]
+use thermo::thermo_functions;
+
+/// Dates, times, and durations
+#[cfg(feature = "datetime")]
+mod datetime;
+#[cfg(feature = "datetime")]
+use datetime::datetime_functions;
+#[cfg(feature = "datetime")]
+pub use datetime::{DateTime, Duration};
+/// Tables of named columns
+#[cfg(feature = "data")]
+mod table;
+#[cfg(feature = "data")]
+use table::table_functions;
+#[cfg(feature = "data")]
+pub use table::{Column, GroupedTable, Table};
+/// Finding and filling missing values and outliers, and encoding labels
+#[cfg(feature = "data")]
+mod cleaning;
+#[cfg(feature = "data")]
+use cleaning::cleaning_functions;
+/// Conversion of values to and from JSON
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "serde")]
+use serialization::serialization_functions;
+
+/// Formatting numbers, arrays, and matrices as text
+#[cfg(feature = "display")]
+mod printing;
+#[cfg(feature = "display")]
+use printing::printing_functions;
+/// Plots drawn as text or saved to files
+#[cfg(feature = "display")]
+mod plotting;
+#[cfg(feature = "display")]
+use plotting::plotting_functions;
+/// Searching the documentation of the functions in this package
+mod help;
+use help::help_functions;
+
+mod assertions;
+use assertions::assert_functions;
 mod validate;
 use validate::validation_functions;
+/// Timing and profiling scripts
+#[cfg(feature = "timing")]
+mod profiling;
+#[cfg(feature = "profiling")]
+pub use profiling::register_profiler;
+#[cfg(feature = "timing")]
+use profiling::{profiling_functions, start_profile, stop_profile};
+/// The module resolver for the script libraries bundled with this package
+mod scripts;
+pub use scripts::LabModuleResolver;
+
+/// The name, signature, source module, and documentation of every function, generated by the
+/// build script when the `metadata` feature is enabled and empty otherwise.
+static FUNCTION_DOCS: &[(&str, &str, &str, &str)] =
+    include!(concat!(env!("OUT_DIR"), "/function-docs.rs"));
 
 def_package! {
     /// Package for scientific computing
@@ -36,6 +208,9 @@ def_package! {
         combine_with_exported_module!(lib, "rhai_sci_matrix_function", matrix_functions);
         combine_with_exported_module!(lib, "rhai_sci_miscellaneous_functions", misc_functions);
         combine_with_exported_module!(lib, "rhai_sci_basic_stats", stats);
+        combine_with_exported_module!(lib, "rhai_sci_metrics", metrics_functions);
+        combine_with_exported_module!(lib, "rhai_sci_decision", decision_functions);
+        combine_with_exported_module!(lib, "rhai_sci_graph", graph_functions);
         combine_with_exported_module!(lib, "rhai_sci_cumulative", cum_functions);
         combine_with_exported_module!(lib, "rhai_sci_int_and_diff", int_and_diff);
         combine_with_exported_module!(lib, "rhai_sci_assertions", assert_functions);
@@ -43,11 +218,107 @@ def_package! {
         combine_with_exported_module!(lib, "rhai_sci_sets", set_functions);
         combine_with_exported_module!(lib, "rhai_sci_moving", moving_functions);
         combine_with_exported_module!(lib, "rhai_sci_validation", validation_functions);
+        #[cfg(feature = "timing")]
+        combine_with_exported_module!(lib, "rhai_sci_profiling", profiling_functions);
+        combine_with_exported_module!(lib, "rhai_sci_random", random_functions);
+        combine_with_exported_module!(lib, "rhai_sci_logical", logical_functions);
+        #[cfg(feature = "sampling")]
+        combine_with_exported_module!(lib, "rhai_sci_sampling", sampling_functions);
+        #[cfg(feature = "interpolation")]
+        combine_with_exported_module!(lib, "rhai_sci_interpolation", interpolation_functions);
+        combine_with_exported_module!(lib, "rhai_sci_matrix_type", matrix_type_functions);
+        #[cfg(feature = "geometry")]
+        combine_with_exported_module!(lib, "rhai_sci_geometry", geometry_functions);
+        #[cfg(feature = "signal")]
+        combine_with_exported_module!(lib, "rhai_sci_signal", signal_functions);
+        #[cfg(feature = "signal")]
+        combine_with_exported_module!(lib, "rhai_sci_control", control_functions);
+        #[cfg(feature = "geometry")]
+        combine_with_exported_module!(lib, "rhai_sci_structures", structures_functions);
+        #[cfg(feature = "physics")]
+        combine_with_exported_module!(lib, "rhai_sci_thermo", thermo_functions);
+        combine_with_exported_module!(lib, "rhai_sci_elementwise", elementwise_functions);
+        #[cfg(feature = "sparse")]
+        combine_with_exported_module!(lib, "rhai_sci_sparse", sparse_functions);
+        #[cfg(feature = "tensor")]
+        combine_with_exported_module!(lib, "rhai_sci_tensor", tensor_functions);
+        combine_with_exported_module!(lib, "rhai_sci_bignum", bignum_functions);
+        #[cfg(feature = "quaternion")]
+        combine_with_exported_module!(lib, "rhai_sci_quaternion", quaternion_functions);
+        #[cfg(feature = "datetime")]
+        combine_with_exported_module!(lib, "rhai_sci_datetime", datetime_functions);
+        #[cfg(feature = "physics")]
+        combine_with_exported_module!(lib, "rhai_sci_units", units_functions);
+        #[cfg(feature = "data")]
+        combine_with_exported_module!(lib, "rhai_sci_table", table_functions);
+        #[cfg(feature = "data")]
+        combine_with_exported_module!(lib, "rhai_sci_cleaning", cleaning_functions);
+        #[cfg(feature = "display")]
+        combine_with_exported_module!(lib, "rhai_sci_printing", printing_functions);
+        #[cfg(feature = "display")]
+        combine_with_exported_module!(lib, "rhai_sci_plotting", plotting_functions);
+        #[cfg(feature = "serde")]
+        combine_with_exported_module!(lib, "rhai_sci_serialization", serialization_functions);
+        combine_with_exported_module!(lib, "rhai_sci_help", help_functions);
+        lib.set_var("physics", constants::physical_constants());
     }
 }
 
+def_package! {
+    /// Package for scientific computing that also sets up the engine it is registered with: on
+    /// top of everything in [`SciPackage`], registering it with
+    /// [`register_into_engine`](Package::register_into_engine) adds the element-wise operators
+    /// (see [`register_elementwise_operators`]), the `physics` module of constants (see
+    /// [`register_physics_constants`]), a random number generator of the engine's own (see
+    /// `register_rng`), and a module resolver that finds the bundled script libraries (see
+    /// [`LabModuleResolver`]) before script files. Like any Rhai package, it can be combined with
+    /// others.
+    /// ```
+    /// use rhai::{packages::Package, Engine, FLOAT};
+    /// use rhai_sci::LabPackage;
+    ///
+    /// let mut engine = Engine::new();
+    /// LabPackage::new().register_into_engine(&mut engine);
+    ///
+    /// let value = engine.eval::<FLOAT>("max([1.0, 2.0]) * physics::c").unwrap();
+    /// assert_eq!(value, 599584916.0);
+    /// ```
+    pub LabPackage(lib): SciPackage {} |> |engine| {
+        register_elementwise_operators(engine);
+        register_physics_constants(engine);
+        #[cfg(feature = "rand")]
+        register_rng(engine, LabRng::new());
+        let mut resolvers = rhai::module_resolvers::ModuleResolversCollection::new();
+        resolvers.push(LabModuleResolver::new());
+        #[cfg(any(not(target_family = "wasm"), not(target_os = "unknown")))]
+        resolvers.push(rhai::module_resolvers::FileModuleResolver::new());
+        engine.set_module_resolver(resolvers);
+    }
+}
+
+/// Creates a script engine with everything set up, as registering [`LabPackage`] does: the
+/// functions of [`SciPackage`], the element-wise operators, the `physics` module of constants, a
+/// random number generator of its own, and the module resolver for the bundled script libraries.
+/// Scripts that use `.*`, `./`, `.^`, or matrix literals such as `matrix![1, 2; 3, 4]` still need
+/// to be rewritten with [`translate_elementwise_operators`] and [`translate_matrix_literals`]
+/// before they are evaluated, which [`eval`] and [`eval_file`] do.
+/// ```
+/// use rhai::FLOAT;
+///
+/// let engine = rhai_sci::engine();
+/// let value = engine.eval::<FLOAT>("mean([1, 2, 3]) * physics::c").unwrap();
+/// assert_eq!(value, 599584916.0);
+/// ```
+pub fn engine() -> Engine {
+    let mut engine = Engine::new();
+    LabPackage::new().register_into_engine(&mut engine);
+    engine
+}
+
 /// This provides the ability to easily evaluate a line (or lines) of code without explicitly
-/// setting up a script engine
+/// setting up a script engine. The element-wise operators `.*`, `./`, and `.^` and matrix literals
+/// such as `matrix![1, 2; 3, 4]` are available, and physical constants can be written as
+/// `physics::k_B`.
 /// ```
 /// use rhai_sci::eval;
 /// use rhai::FLOAT;
@@ -56,7 +327,70 @@ def_package! {
 pub fn eval<T: Clone + std::marker::Send + std::marker::Sync + 'static>(
     script: &str,
 ) -> Result<T, Box<EvalAltResult>> {
-    let mut engine = Engine::new();
-    engine.register_global_module(SciPackage::new().as_shared_module());
-    engine.eval::<T>(script)
+    engine().eval::<T>(&translate_elementwise_operators(
+        &translate_matrix_literals(script),
+    ))
+}
+
+/// Evaluates a script file the way [`eval`] evaluates a script, naming the file as the source of
+/// any error. A `#!` line at the start of the file is skipped.
+/// ```
+/// use rhai::FLOAT;
+/// use rhai_sci::eval_file;
+///
+/// let path = std::env::temp_dir().join("sum_of_squares.rhai");
+/// std::fs::write(&path, "let x = [1.0, 2.0, 3.0];\nsum(x .* x)").unwrap();
+/// assert_eq!(eval_file::<FLOAT>(&path).unwrap(), 14.0);
+/// ```
+#[cfg(any(not(target_family = "wasm"), not(target_os = "unknown")))]
+pub fn eval_file<T: Clone + std::marker::Send + std::marker::Sync + 'static>(
+    path: impl AsRef<std::path::Path>,
+) -> Result<T, Box<EvalAltResult>> {
+    let path = path.as_ref();
+    let mut script = std::fs::read_to_string(path).map_err(|err| {
+        EvalAltResult::ErrorSystem(
+            format!("Cannot read script file: {}", path.display()),
+            err.into(),
+        )
+    })?;
+    // Blank out the shebang line, keeping the line numbers of the rest of the script
+    if script.starts_with("#!") {
+        script.replace_range(..script.find('\n').unwrap_or(script.len()), "");
+    }
+    let engine = engine();
+    let mut ast = engine.compile(translate_elementwise_operators(&translate_matrix_literals(
+        &script,
+    )))?;
+    ast.set_source(path.to_string_lossy().as_ref());
+    engine.eval_ast::<T>(&ast)
+}
+
+/// Runs a script the way the `rhai-lab run` command line tool does. Delimited text (such as CSV)
+/// is available to the script as `input`: a [`Table`] if the text has a header row, and otherwise
+/// a matrix of floats. Without text, `input` is `()`. The value of the script is returned as
+/// delimited text (see `write_matrix`), and anything the script prints goes to standard error so
+/// that it stays out of the result.
+/// ```
+/// use rhai_sci::run_with_csv;
+///
+/// let doubled = run_with_csv("input .* 2", Some("1,2\n3,4\n")).unwrap();
+/// assert_eq!(doubled, "2,4\n6,8\n");
+///
+/// let csv = "name,age\nAda,36\nAlan,41\nGrace,85\n";
+/// let older = run_with_csv("input.filter(|row| row.age > 40)", Some(csv)).unwrap();
+/// assert_eq!(older, "name,age\nAlan,41\nGrace,85\n");
+/// ```
+#[cfg(all(feature = "io", feature = "data"))]
+pub fn run_with_csv(script: &str, input: Option<&str>) -> Result<String, Box<EvalAltResult>> {
+    let mut engine = engine();
+    engine.on_print(|text| eprintln!("{text}"));
+    let mut scope = rhai::Scope::new();
+    let input = match input {
+        Some(text) => matrices_and_arrays::delimited_input(text)?,
+        None => rhai::Dynamic::UNIT,
+    };
+    scope.push("input", input);
+    let script = translate_elementwise_operators(&translate_matrix_literals(script));
+    let result = engine.eval_with_scope::<rhai::Dynamic>(&mut scope, &script)?;
+    matrices_and_arrays::delimited_output(result)
 }
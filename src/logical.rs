@@ -0,0 +1,163 @@
+use rhai::plugin::*;
+
+#[export_module]
+pub mod logical_functions {
+    use crate::if_matrix_convert_to_vec_array_and_do;
+    use rhai::{Array, Dynamic, EvalAltResult, INT};
+
+    /// Interprets a value as a logical: booleans as themselves, and INT or FLOAT as true if nonzero.
+    fn truthy(x: Dynamic) -> Result<bool, Box<EvalAltResult>> {
+        if let Ok(b) = x.as_bool() {
+            Ok(b)
+        } else if let Ok(i) = x.as_int() {
+            Ok(i != 0)
+        } else if let Ok(f) = x.as_float() {
+            Ok(f != 0.0)
+        } else {
+            Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!(
+                    "The elements of the input array must be BOOL, INT, or FLOAT, not {}",
+                    x.type_name()
+                ),
+            )
+            .into())
+        }
+    }
+
+    /// Applies a reduction to each column (`dim` = 0) or each row (`dim` = 1) of a matrix.
+    fn reduce_along<F>(matrix: &mut Array, dim: INT, f: F) -> Result<Array, Box<EvalAltResult>>
+    where
+        F: FnMut(&mut Array) -> Result<Dynamic, Box<EvalAltResult>>,
+    {
+        let mut lines = match dim {
+            0 => if_matrix_convert_to_vec_array_and_do(
+                &mut crate::matrix_functions::transpose(matrix)?,
+                Ok,
+            )?,
+            1 => if_matrix_convert_to_vec_array_and_do(matrix, Ok)?,
+            _ => {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!(
+                    "The dimension must be 0 (down the rows) or 1 (across the columns), not {dim}"
+                ),
+                )
+                .into())
+            }
+        };
+        lines.iter_mut().map(f).collect()
+    }
+
+    /// Returns true if any element of the array is true or nonzero. Matrices are reduced over all
+    /// of their elements.
+    /// ```typescript
+    /// assert_eq(any([0, 0, 3]), true);
+    /// ```
+    /// ```typescript
+    /// assert_eq(any([false, false]), false);
+    /// ```
+    /// ```typescript
+    /// assert_eq(any([[0.0, 0.0], [0.0, 1.0]]), true);
+    /// ```
+    #[rhai_fn(name = "any", return_raw, pure)]
+    pub fn any(arr: &mut Array) -> Result<bool, Box<EvalAltResult>> {
+        for el in crate::matrix_functions::flatten(arr) {
+            if truthy(el)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Tests whether any element is true or nonzero along a dimension of a matrix. With `dim` = 0,
+    /// returns one result per column; with `dim` = 1, one result per row.
+    /// ```typescript
+    /// let m = [[0, 1],
+    ///          [0, 0]];
+    /// assert_eq(any(m, 0), [false, true]);
+    /// assert_eq(any(m, 1), [true, false]);
+    /// ```
+    #[rhai_fn(name = "any", return_raw, pure)]
+    pub fn any_along(matrix: &mut Array, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        reduce_along(matrix, dim, |line| any(line).map(Dynamic::from_bool))
+    }
+
+    /// Returns true if every element of the array is true or nonzero. Matrices are reduced over all
+    /// of their elements.
+    /// ```typescript
+    /// assert_eq(all([1, 2, 3]), true);
+    /// ```
+    /// ```typescript
+    /// assert_eq(all([true, false]), false);
+    /// ```
+    #[rhai_fn(name = "all", return_raw, pure)]
+    pub fn all(arr: &mut Array) -> Result<bool, Box<EvalAltResult>> {
+        for el in crate::matrix_functions::flatten(arr) {
+            if !truthy(el)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Tests whether all elements are true or nonzero along a dimension of a matrix. With `dim` = 0,
+    /// returns one result per column; with `dim` = 1, one result per row.
+    /// ```typescript
+    /// let m = [[1, 1],
+    ///          [0, 1]];
+    /// assert_eq(all(m, 0), [false, true]);
+    /// assert_eq(all(m, 1), [true, false]);
+    /// ```
+    #[rhai_fn(name = "all", return_raw, pure)]
+    pub fn all_along(matrix: &mut Array, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        reduce_along(matrix, dim, |line| all(line).map(Dynamic::from_bool))
+    }
+
+    /// Returns the indices of the elements that are true or nonzero. For matrices, the indices
+    /// refer to the flattened (row-major) matrix.
+    /// ```typescript
+    /// assert_eq(find([0, 5, 0, 2]), [1, 3]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(find([[true, false], [false, true]]), [0, 3]);
+    /// ```
+    #[rhai_fn(name = "find", return_raw, pure)]
+    pub fn find(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut indices = vec![];
+        for (idx, el) in crate::matrix_functions::flatten(arr)
+            .into_iter()
+            .enumerate()
+        {
+            if truthy(el)? {
+                indices.push(Dynamic::from_int(idx as INT));
+            }
+        }
+        Ok(indices)
+    }
+
+    /// Returns the number of elements that are true or nonzero.
+    /// ```typescript
+    /// assert_eq(nnz([0, 5, 0, 2]), 2);
+    /// ```
+    /// ```typescript
+    /// assert_eq(nnz(eye(3)), 3);
+    /// ```
+    #[rhai_fn(name = "nnz", return_raw, pure)]
+    pub fn nnz(arr: &mut Array) -> Result<INT, Box<EvalAltResult>> {
+        find(arr).map(|indices| indices.len() as INT)
+    }
+
+    /// Counts the elements that are true or nonzero along a dimension of a matrix. With `dim` = 0,
+    /// returns one count per column; with `dim` = 1, one count per row.
+    /// ```typescript
+    /// let m = [[1, 0, 2],
+    ///          [3, 0, 0]];
+    /// assert_eq(nnz(m, 0), [2, 0, 1]);
+    /// assert_eq(nnz(m, 1), [2, 1]);
+    /// ```
+    #[rhai_fn(name = "nnz", return_raw, pure)]
+    pub fn nnz_along(matrix: &mut Array, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        reduce_along(matrix, dim, |line| nnz(line).map(Dynamic::from_int))
+    }
+}
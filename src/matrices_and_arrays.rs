@@ -1,65 +1,2312 @@
-use nalgebralib::{Dyn, OMatrix};
 use rhai::plugin::*;
 
+/// Counts the elements of a (possibly nested) array without copying it.
+fn count_elements(arr: &[rhai::Dynamic]) -> usize {
+    arr.iter()
+        .map(|el| match el.read_lock::<rhai::Array>() {
+            Some(inner) => count_elements(&inner),
+            None => 1,
+        })
+        .sum()
+}
+
+/// Appends the elements of a (possibly nested) array to `flat`, without copying the nested arrays.
+fn flatten_into(arr: &[rhai::Dynamic], flat: &mut rhai::Array) {
+    for el in arr {
+        match el.read_lock::<rhai::Array>() {
+            Some(inner) => flatten_into(&inner, flat),
+            None => flat.push(el.clone()),
+        }
+    }
+}
+
+/// Resolves a (possibly negative, counted from the end) index into a dimension of length `n`.
+fn resolve_index(i: rhai::INT, n: usize) -> Result<usize, Box<rhai::EvalAltResult>> {
+    let idx = if i < 0 { i + n as rhai::INT } else { i };
+    if idx < 0 || idx >= n as rhai::INT {
+        Err(rhai::EvalAltResult::ErrorIndexNotFound(
+            rhai::Dynamic::from_int(i),
+            rhai::Position::NONE,
+        )
+        .into())
+    } else {
+        Ok(idx as usize)
+    }
+}
+
+/// Counts the dimensions of a number (0) or nested array, following the first element of each
+/// level.
+fn block_rank(block: &rhai::Dynamic) -> usize {
+    match block.read_lock::<rhai::Array>() {
+        Some(arr) => 1 + arr.first().map_or(0, block_rank),
+        None => 0,
+    }
+}
+
+/// Promotes a block to `rank` dimensions so it can be concatenated with others. Numbers and
+/// vectors become row matrices, and any further dimensions are added as trailing singletons.
+fn promote_block(block: rhai::Dynamic, rank: usize) -> rhai::Dynamic {
+    /// Wraps every element found `depth` levels down in `extra` singleton arrays.
+    fn wrap_leaves(x: rhai::Dynamic, depth: usize, extra: usize) -> rhai::Dynamic {
+        if depth == 0 {
+            (0..extra).fold(x, |x, _| rhai::Dynamic::from_array(vec![x]))
+        } else {
+            rhai::Dynamic::from_array(
+                x.into_array()
+                    .unwrap()
+                    .into_iter()
+                    .map(|el| wrap_leaves(el, depth - 1, extra))
+                    .collect(),
+            )
+        }
+    }
+
+    let mut block = block;
+    let mut block_rank = block_rank(&block);
+    while block_rank < rank.min(2) {
+        block = rhai::Dynamic::from_array(vec![block]);
+        block_rank += 1;
+    }
+    wrap_leaves(block, block_rank, rank.saturating_sub(block_rank))
+}
+
+/// Joins blocks of the same rank along dimension `dim` (counting from 1).
+fn join_blocks(
+    dim: usize,
+    blocks: Vec<rhai::Array>,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    if dim == 1 {
+        return Ok(blocks.into_iter().flatten().collect());
+    }
+    let ragged = || -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            "Arrays must be rectangular to be concatenated",
+        )
+        .into()
+    };
+    let n = blocks[0].len();
+    if blocks.iter().any(|b| b.len() != n) {
+        return Err(ragged());
+    }
+    (0..n)
+        .map(|i| {
+            let inner = blocks
+                .iter()
+                .map(|b| b[i].clone().into_array().map_err(|_| ragged()))
+                .collect::<Result<Vec<_>, _>>()?;
+            join_blocks(dim - 1, inner).map(rhai::Dynamic::from_array)
+        })
+        .collect()
+}
+
+/// Concatenates blocks along dimension `dim` (counting from 1, so 1 stacks rows and 2 joins
+/// columns). Empty arrays are skipped, numbers and vectors are treated as row matrices, and row
+/// vectors joined along dimension 2 stay vectors. Every other dimension must match.
+fn concatenate(
+    dim: rhai::INT,
+    blocks: rhai::Array,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    if dim < 1 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The dimension to concatenate along must be at least 1, not {dim}"),
+        )
+        .into());
+    }
+    let dim = dim as usize;
+    let blocks = blocks
+        .into_iter()
+        .filter(|b| {
+            !b.read_lock::<rhai::Array>()
+                .is_some_and(|arr| arr.is_empty())
+        })
+        .collect::<Vec<_>>();
+    if blocks.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ranks = blocks.iter().map(block_rank).collect::<Vec<_>>();
+    let row_vectors = dim == 2 && ranks.iter().all(|r| *r <= 1);
+    let rank = ranks.into_iter().max().unwrap().max(2).max(dim);
+    let blocks = blocks
+        .into_iter()
+        .map(|b| promote_block(b, rank).into_array().unwrap())
+        .collect::<Vec<_>>();
+
+    // Every dimension but the one being joined must match
+    let shapes = blocks
+        .iter()
+        .map(|b| {
+            let mut b = b.clone();
+            matrix_functions::matrix_size_by_reference(&mut b)
+                .into_iter()
+                .map(|x| x.as_int().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for shape in &shapes[1..] {
+        let mismatch = (0..rank).any(|k| k != dim - 1 && shape.get(k) != shapes[0].get(k));
+        if mismatch {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "Cannot concatenate along dimension {dim} blocks of size {:?} and {:?}",
+                    shapes[0], shape
+                ),
+            )
+            .into());
+        }
+    }
+
+    let joined = join_blocks(dim, blocks)?;
+    if row_vectors {
+        Ok(joined.into_iter().next().unwrap().into_array().unwrap())
+    } else {
+        Ok(joined)
+    }
+}
+
+/// Builds a nested array of the given shape from elements in row-major order.
+fn nest_elements(
+    elements: &mut impl Iterator<Item = rhai::Dynamic>,
+    shape: &[usize],
+) -> rhai::Array {
+    match shape {
+        [] => vec![],
+        [n] => elements.take(*n).collect(),
+        [n, rest @ ..] => (0..*n)
+            .map(|_| rhai::Dynamic::from_array(nest_elements(elements, rest)))
+            .collect(),
+    }
+}
+
+/// Builds an identity matrix with `nx` rows and `ny` columns, failing before allocating it if it
+/// is larger than the engine allows.
+fn identity_matrix(
+    context: &rhai::NativeCallContext,
+    nx: rhai::INT,
+    ny: rhai::INT,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    crate::check_array_shape(context, &[nx.max(0) as usize, ny.max(0) as usize])?;
+    let mut output = vec![];
+    for i in 0..nx {
+        let mut row = vec![];
+        for j in 0..ny {
+            if i == j {
+                row.push(rhai::Dynamic::FLOAT_ONE);
+            } else {
+                row.push(rhai::Dynamic::FLOAT_ZERO);
+            }
+        }
+        output.push(rhai::Dynamic::from_array(row))
+    }
+    Ok(output)
+}
+
+/// Builds the array returned by `linspace`, failing before allocating it if it is larger than the
+/// engine allows.
+fn evenly_spaced_array(
+    context: &rhai::NativeCallContext,
+    x1: rhai::Dynamic,
+    x2: rhai::Dynamic,
+    n: rhai::INT,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    crate::if_int_convert_to_float_and_do(x1, |new_x1| {
+        crate::if_int_convert_to_float_and_do(x2.clone(), |new_x2| {
+            if n < 0 {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("The number of elements must be non-negative, not {n}"),
+                )
+                .in_function("linspace")
+                .with_argument("n")
+                .into());
+            }
+            crate::check_array_shape(context, &[n as usize])?;
+            Ok(crate::evenly_spaced(new_x1, new_x2, n as usize)
+                .into_iter()
+                .map(rhai::Dynamic::from_float)
+                .collect())
+        })
+    })
+}
+
+/// Builds a nested array with one level per entry in `dims`, calling `fill` for each element.
+/// Fails before allocating anything if the array is larger than the engine allows.
+fn filled_array(
+    context: &rhai::NativeCallContext,
+    dims: &[rhai::Dynamic],
+    mut fill: impl FnMut() -> rhai::Dynamic,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let shape = dims
+        .iter()
+        .map(|d| match d.as_int() {
+            Ok(d) if d >= 0 => Ok(d as usize),
+            _ => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Dimensions must be non-negative integers, not {dims:?}"),
+            )
+            .into()),
+        })
+        .collect::<Result<Vec<usize>, Box<rhai::EvalAltResult>>>()?;
+    if shape.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "At least one dimension must be given",
+        )
+        .into());
+    }
+    crate::check_array_shape(context, &shape)?;
+    Ok(nest_elements(
+        &mut std::iter::repeat_with(&mut fill),
+        &shape,
+    ))
+}
+
+/// Applies `f` to every 1-D slice of a nested array along dimension `dim` (counting from 1).
+/// Dimensions beyond the depth of the array have size 1, so those slices are left unchanged.
+fn map_along_dim(arr: rhai::Array, dim: usize, f: &dyn Fn(&mut rhai::Array)) -> rhai::Array {
+    if dim <= 1 {
+        let mut arr = arr;
+        f(&mut arr);
+        return arr;
+    }
+    arr.into_iter()
+        .map(|el| {
+            if el.is_array() {
+                let inner = el.into_array().unwrap();
+                rhai::Dynamic::from_array(map_along_dim(inner, dim - 1, f))
+            } else {
+                el
+            }
+        })
+        .collect()
+}
+
+/// Checks that a dimension to operate along counts from 1.
+fn check_dim(dim: rhai::INT) -> Result<usize, Box<rhai::EvalAltResult>> {
+    if dim < 1 {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Dimensions count from 1, so {dim} is not a valid dimension"),
+        )
+        .into())
+    } else {
+        Ok(dim as usize)
+    }
+}
+
+/// Builds the grid of coordinates along `axis` for the grid spanned by `axes`, so that the element
+/// at `[i1][i2]...` is `axes[axis][i_axis]`.
+fn grid_along(
+    axes: &[rhai::Array],
+    axis: usize,
+    depth: usize,
+    value: Option<&rhai::Dynamic>,
+) -> rhai::Dynamic {
+    if depth == axes.len() {
+        return value.cloned().unwrap_or(rhai::Dynamic::UNIT);
+    }
+    rhai::Dynamic::from_array(
+        axes[depth]
+            .iter()
+            .map(|x| {
+                grid_along(
+                    axes,
+                    axis,
+                    depth + 1,
+                    if depth == axis { Some(x) } else { value },
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Splits delimited text into rows of fields. Quoted fields can contain delimiters, newlines, and
+/// doubled quote characters, and blank lines and lines starting with `comment` are skipped.
+#[cfg(feature = "io")]
+fn split_delimited(
+    text: &str,
+    delimiter: char,
+    quote: Option<char>,
+    comment: Option<char>,
+) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut at_line_start = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if at_line_start && !quoted {
+            at_line_start = false;
+            if Some(c) == comment || c == '\n' || c == '\r' {
+                // Skip the rest of a comment line, or a blank line
+                let mut c = c;
+                while c != '\n' {
+                    match chars.next() {
+                        Some(next) => c = next,
+                        None => break,
+                    }
+                }
+                at_line_start = true;
+                continue;
+            }
+        }
+        if quoted {
+            if Some(c) == quote {
+                if chars.peek() == quote.as_ref() {
+                    field.push(c);
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if Some(c) == quote && field.trim().is_empty() {
+            field.clear();
+            quoted = true;
+        } else if c == delimiter {
+            row.push(std::mem::take(&mut field));
+        } else if c == '\n' {
+            row.push(std::mem::take(&mut field));
+            rows.push(std::mem::take(&mut row));
+            at_line_start = true;
+        } else if c != '\r' {
+            field.push(c);
+        }
+    }
+    if !at_line_start {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Settings for caching downloaded URLs on disk, selected with `enable_url_cache`.
+#[cfg(feature = "io")]
+struct UrlCache {
+    /// The directory that holds cached downloads.
+    dir: std::path::PathBuf,
+    /// How long a cached download stays fresh, or `None` if it never expires.
+    ttl: Option<std::time::Duration>,
+    /// Whether to only use cached downloads, never the network.
+    offline: bool,
+}
+
+#[cfg(feature = "io")]
+thread_local! {
+    /// The URL cache, if one has been enabled with `enable_url_cache`.
+    static URL_CACHE: std::cell::RefCell<Option<UrlCache>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Options recognized by `enable_url_cache`.
+#[cfg(feature = "io")]
+const CACHE_OPTIONS: [&str; 3] = ["dir", "ttl", "offline"];
+
+/// Enables the URL cache with the options described in `enable_url_cache`.
+#[cfg(feature = "io")]
+#[allow(clippy::unnecessary_cast)] // FLOAT may be f32
+fn set_url_cache(options: &rhai::Map) -> Result<(), Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options
+        .keys()
+        .find(|k| !CACHE_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {CACHE_OPTIONS:?}"
+        )));
+    }
+    let dir = match options.get("dir") {
+        None => std::env::temp_dir().join("rhai-sci-cache"),
+        Some(dir) => dir
+            .clone()
+            .into_string()
+            .map(std::path::PathBuf::from)
+            .map_err(|_| error("The dir option must be a string".to_string()))?,
+    };
+    let ttl = match options.get("ttl") {
+        None => None,
+        Some(ttl) => {
+            let seconds = match (ttl.as_float(), ttl.as_int()) {
+                (Ok(f), _) => f as f64,
+                (_, Ok(i)) => i as f64,
+                _ => -1.0,
+            };
+            if !(seconds >= 0.0 && seconds.is_finite()) {
+                return Err(error(
+                    "The ttl option must be a non-negative number of seconds".to_string(),
+                ));
+            }
+            Some(std::time::Duration::from_secs_f64(seconds))
+        }
+    };
+    let offline = match options.get("offline") {
+        None => false,
+        Some(offline) => offline
+            .as_bool()
+            .map_err(|_| error("The offline option must be true or false".to_string()))?,
+    };
+    std::fs::create_dir_all(&dir).map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(
+            format!("Cannot create cache directory: {}", dir.display()),
+            err.into(),
+        )
+    })?;
+    URL_CACHE.with(|cache| *cache.borrow_mut() = Some(UrlCache { dir, ttl, offline }));
+    Ok(())
+}
+
+/// Disables the URL cache, leaving any cached downloads on disk.
+#[cfg(feature = "io")]
+fn clear_url_cache_settings() {
+    URL_CACHE.with(|cache| *cache.borrow_mut() = None);
+}
+
+/// Names the cache file for a URL with a stable (FNV-1a) hash of the URL, followed by the last
+/// segment of its path so the cache directory stays readable.
+#[cfg(feature = "io")]
+fn url_cache_file_name(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    let segment = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .take(64)
+        .collect::<String>();
+    format!("{hash:016x}-{segment}")
+}
+
+/// Downloads the contents of a URL from the network.
+#[cfg(feature = "io")]
+fn download_url(url: &str) -> Result<Vec<u8>, Box<rhai::EvalAltResult>> {
+    let response = minreq::get(url).send().map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(format!("Error getting url: {url}"), err.into())
+    })?;
+    Ok(response.into_bytes())
+}
+
+/// Gets the contents of a URL, going through the URL cache if one is enabled: fresh cached copies
+/// are used instead of the network, and new downloads are saved to the cache. In offline mode,
+/// cached copies are used however old they are, and missing ones are an error.
+#[cfg(feature = "io")]
+fn fetch_url(url: &str) -> Result<Vec<u8>, Box<rhai::EvalAltResult>> {
+    let settings = URL_CACHE.with(|cache| {
+        cache.borrow().as_ref().map(|cache| {
+            (
+                cache.dir.join(url_cache_file_name(url)),
+                cache.ttl,
+                cache.offline,
+            )
+        })
+    });
+    let Some((path, ttl, offline)) = settings else {
+        return download_url(url);
+    };
+    let fresh = std::fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .map(|modified| match (ttl, modified.elapsed()) {
+            (None, _) => true,
+            (Some(ttl), Ok(age)) => age <= ttl,
+            (Some(_), Err(_)) => true,
+        })
+        .unwrap_or(false);
+    if fresh || (offline && path.exists()) {
+        return std::fs::read(&path).map_err(|err| {
+            rhai::EvalAltResult::ErrorSystem(
+                format!("Cannot read cached copy of url: {url}"),
+                err.into(),
+            )
+            .into()
+        });
+    }
+    if offline {
+        return Err(rhai::EvalAltResult::ErrorRuntime(
+            format!("The url {url} is not in the cache, and the cache is in offline mode").into(),
+            rhai::Position::NONE,
+        )
+        .into());
+    }
+    let bytes = download_url(url)?;
+    std::fs::write(&path, &bytes).map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(
+            format!("Cannot write cached copy of url: {url}"),
+            err.into(),
+        )
+    })?;
+    Ok(bytes)
+}
+
+/// Reads the bytes of a local file, or downloads them if the path is a URL.
+#[cfg(feature = "io")]
+fn read_bytes(file_path: &str) -> Result<Vec<u8>, Box<rhai::EvalAltResult>> {
+    if std::path::Path::new(file_path).exists() {
+        std::fs::read(file_path).map_err(|err| {
+            rhai::EvalAltResult::ErrorSystem(format!("Cannot read file: {file_path}"), err.into())
+                .into()
+        })
+    } else if url::Url::parse(file_path).is_ok() {
+        fetch_url(file_path)
+    } else {
+        Err(rhai::EvalAltResult::ErrorRuntime(
+            format!("The string {file_path} is not a valid URL or file path").into(),
+            rhai::Position::NONE,
+        )
+        .into())
+    }
+}
+
+/// Reads the contents of a local file, or downloads them if the path is a URL.
+#[cfg(feature = "io")]
+fn read_text(file_path: &str) -> Result<String, Box<rhai::EvalAltResult>> {
+    String::from_utf8(read_bytes(file_path)?).map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(
+            format!("Cannot read file as text: {file_path}"),
+            err.into(),
+        )
+        .into()
+    })
+}
+
+/// Options recognized by `read_table`.
+#[cfg(all(feature = "io", feature = "data"))]
+const TABLE_OPTIONS: [&str; 3] = ["delimiter", "quote", "comment"];
+
+/// Parses delimited text with a header row into a `Table`, following the options described for
+/// `read_table`.
+#[cfg(all(feature = "io", feature = "data"))]
+fn parse_table(text: &str, options: &rhai::Map) -> Result<crate::Table, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options
+        .keys()
+        .find(|k| !TABLE_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {TABLE_OPTIONS:?}"
+        )));
+    }
+    let quote = char_option(options, "quote", Some('"'))?;
+    let comment = char_option(options, "comment", None)?;
+    let delimiter = match char_option(options, "delimiter", None)? {
+        Some(d) => d,
+        None => guess_delimiter(text, comment),
+    };
+    let mut rows = split_delimited(text, delimiter, quote, comment).into_iter();
+    let names = rows
+        .next()
+        .ok_or_else(|| error("Cannot read a table without a header row".to_string()))?
+        .into_iter()
+        .map(|n| n.trim().to_string())
+        .collect::<Vec<_>>();
+    let rows = rows.collect::<Vec<_>>();
+    if let Some((i, row)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, r)| r.len() != names.len())
+    {
+        return Err(error(format!(
+            "Row {} has {} fields, but the header has {}",
+            i + 1,
+            row.len(),
+            names.len()
+        )));
+    }
+    let columns = (0..names.len())
+        .map(|j| {
+            let fields = rows.iter().map(|row| row[j].clone()).collect::<Vec<_>>();
+            crate::Column::from_text(&fields)
+        })
+        .collect();
+    crate::Table::new(names, columns)
+}
+
+/// Options recognized by `read_matrix`.
+#[cfg(feature = "io")]
+const CSV_OPTIONS: [&str; 7] = [
+    "delimiter",
+    "header",
+    "columns",
+    "quote",
+    "comment",
+    "missing",
+    "names",
+];
+
+/// Reads a single-character option, where an empty string means none.
+#[cfg(feature = "io")]
+fn char_option(
+    options: &rhai::Map,
+    key: &str,
+    default: Option<char>,
+) -> Result<Option<char>, Box<rhai::EvalAltResult>> {
+    let Some(value) = options.get(key) else {
+        return Ok(default);
+    };
+    let value = value.clone().into_string().unwrap_or_default();
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (c, None) => Ok(c),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The {key} option must be a single character, not '{value}'"),
+        )
+        .into()),
+    }
+}
+
+/// Guesses the delimiter of delimited text as whichever common one appears most in the first line
+/// of data.
+#[cfg(feature = "io")]
+fn guess_delimiter(text: &str, comment: Option<char>) -> char {
+    let first = text
+        .lines()
+        .find(|l| !l.trim().is_empty() && l.chars().next() != comment)
+        .unwrap_or_default();
+    [',', '\t', ';', '|']
+        .into_iter()
+        .max_by_key(|d| (first.matches(*d).count(), *d == ','))
+        .unwrap()
+}
+
+/// Column names and rows of numbers read from delimited text.
+#[cfg(feature = "io")]
+type DelimitedData = (Vec<String>, Vec<Vec<rhai::FLOAT>>);
+
+/// Parses delimited text into column names and rows of numbers, following the options described
+/// for `read_matrix`.
+#[cfg(feature = "io")]
+fn parse_delimited(
+    text: &str,
+    options: &rhai::Map,
+) -> Result<DelimitedData, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options.keys().find(|k| !CSV_OPTIONS.contains(&k.as_str())) {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {CSV_OPTIONS:?}"
+        )));
+    }
+    let quote = char_option(options, "quote", Some('"'))?;
+    let comment = char_option(options, "comment", None)?;
+
+    let delimiter = match char_option(options, "delimiter", None)? {
+        Some(d) => d,
+        None => guess_delimiter(text, comment),
+    };
+    let mut rows = split_delimited(text, delimiter, quote, comment);
+
+    let is_missing =
+        |field: &str| matches!(field.trim(), "" | "NA" | "N/A" | "null" | "NULL" | "None");
+    let has_header = match options.get("header") {
+        Some(h) => h
+            .as_bool()
+            .map_err(|_| error("The header option must be true or false".to_string()))?,
+        None => rows.first().is_some_and(|row| {
+            row.iter()
+                .any(|f| !is_missing(f) && f.trim().parse::<rhai::FLOAT>().is_err())
+        }),
+    };
+    let width = rows.first().map_or(0, |row| row.len());
+    let names = if has_header {
+        rows.remove(0)
+            .into_iter()
+            .map(|n| n.trim().to_string())
+            .collect()
+    } else {
+        (1..=width).map(|i| format!("Var{i}")).collect::<Vec<_>>()
+    };
+
+    // Resolve the selected columns to indices
+    let selected = match options.get("columns") {
+        None => (0..width).collect::<Vec<_>>(),
+        Some(columns) => columns
+            .clone()
+            .into_array()
+            .map_err(|_| error("The columns option must be an array".to_string()))?
+            .into_iter()
+            .map(|c| {
+                if let Ok(i) = c.as_int() {
+                    usize::try_from(i)
+                        .ok()
+                        .filter(|i| *i < width)
+                        .ok_or_else(|| error(format!("Column {i} is out of range")))
+                } else {
+                    let name = c.into_string().unwrap_or_default();
+                    names
+                        .iter()
+                        .position(|n| *n == name)
+                        .ok_or_else(|| error(format!("There is no column named '{name}'")))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    let missing = match options.get("missing") {
+        None => "drop".to_string(),
+        Some(m) => m.clone().into_string().unwrap_or_default(),
+    };
+    if !["drop", "nan", "error"].contains(&missing.as_str()) {
+        return Err(error(format!(
+            "The missing option must be \"drop\", \"nan\", or \"error\", not \"{missing}\""
+        )));
+    }
+
+    let line_offset = if has_header { 2 } else { 1 };
+    let mut data = vec![];
+    'rows: for (r, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            return Err(error(format!(
+                "Row {} has {} fields instead of {width}",
+                r + line_offset,
+                row.len()
+            )));
+        }
+        let mut values = Vec::with_capacity(selected.len());
+        for c in &selected {
+            let field = row[*c].trim();
+            if is_missing(field) {
+                match missing.as_str() {
+                    "drop" => continue 'rows,
+                    "nan" => values.push(rhai::FLOAT::NAN),
+                    _ => {
+                        return Err(error(format!(
+                            "Missing value in row {}, column '{}'",
+                            r + line_offset,
+                            names[*c]
+                        )))
+                    }
+                }
+            } else {
+                values.push(field.parse::<rhai::FLOAT>().map_err(|_| {
+                    error(format!(
+                        "Cannot read '{field}' in row {}, column '{}' as a number",
+                        r + line_offset,
+                        names[*c]
+                    ))
+                })?);
+            }
+        }
+        data.push(values);
+    }
+    let names = selected.iter().map(|c| names[*c].clone()).collect();
+    Ok((names, data))
+}
+
+/// Options recognized by `write_matrix`.
+#[cfg(feature = "io")]
+const WRITE_OPTIONS: [&str; 4] = ["delimiter", "header", "precision", "scientific"];
+
+/// Quotes a field for delimited text if it contains the delimiter, a quote, or a line break.
+#[cfg(feature = "io")]
+fn quote_field(field: String, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Formats a matrix (or a single row) as delimited text, following the options described for
+/// `write_matrix`.
+#[cfg(feature = "io")]
+fn format_delimited(
+    matrix: rhai::Array,
+    options: &rhai::Map,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options
+        .keys()
+        .find(|k| !WRITE_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {WRITE_OPTIONS:?}"
+        )));
+    }
+    let delimiter = char_option(options, "delimiter", Some(','))?
+        .ok_or_else(|| error("The delimiter cannot be empty".to_string()))?;
+    let precision = match options.get("precision") {
+        None => None,
+        Some(p) => Some(
+            p.as_int()
+                .ok()
+                .and_then(|p| usize::try_from(p).ok())
+                .ok_or_else(|| error("The precision must be a non-negative integer".to_string()))?,
+        ),
+    };
+    let scientific = match options.get("scientific") {
+        None => false,
+        Some(s) => s
+            .as_bool()
+            .map_err(|_| error("The scientific option must be true or false".to_string()))?,
+    };
+    let format_number = |x: &rhai::Dynamic| -> Result<String, Box<rhai::EvalAltResult>> {
+        if let Ok(i) = x.as_int() {
+            Ok(i.to_string())
+        } else if let Ok(f) = x.as_float() {
+            Ok(match (precision, scientific) {
+                (Some(p), true) => format!("{f:.p$e}"),
+                (Some(p), false) => format!("{f:.p$}"),
+                (None, true) => format!("{f:e}"),
+                (None, false) => f.to_string(),
+            })
+        } else if x.is_string() {
+            Ok(quote_field(x.to_string(), delimiter))
+        } else {
+            Err(error(format!(
+                "Cannot write a value of type {} to a file",
+                x.type_name()
+            )))
+        }
+    };
+
+    let rows = if matrix.iter().all(|x| !x.is_array()) {
+        vec![matrix]
+    } else {
+        matrix
+            .into_iter()
+            .map(|row| {
+                row.into_array()
+                    .map_err(|_| error("Each row of the matrix must be an array".to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut text = String::new();
+    if let Some(header) = options.get("header") {
+        let header = header
+            .clone()
+            .into_array()
+            .map_err(|_| error("The header option must be an array of names".to_string()))?;
+        if rows.first().is_some_and(|row| row.len() != header.len()) {
+            return Err(error(format!(
+                "The header has {} names but the matrix has {} columns",
+                header.len(),
+                rows[0].len()
+            )));
+        }
+        let names = header
+            .into_iter()
+            .map(|name| quote_field(name.to_string(), delimiter))
+            .collect::<Vec<_>>();
+        text.push_str(&names.join(&delimiter.to_string()));
+        text.push('\n');
+    }
+    for row in rows {
+        let fields = row
+            .iter()
+            .map(format_number)
+            .collect::<Result<Vec<_>, _>>()?;
+        text.push_str(&fields.join(&delimiter.to_string()));
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// Reads delimited text given to a script as its `input`: text with a header row becomes a
+/// `Table`, and text without one becomes a matrix of floats with `NaN` for missing values.
+#[cfg(all(feature = "io", feature = "data"))]
+pub fn delimited_input(text: &str) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    let delimiter = guess_delimiter(text, None);
+    let has_header = split_delimited(text, delimiter, Some('"'), None)
+        .first()
+        .is_some_and(|row| {
+            row.iter().any(|f| {
+                !matches!(f.trim(), "" | "NA" | "N/A" | "null" | "NULL" | "None")
+                    && f.trim().parse::<rhai::FLOAT>().is_err()
+            })
+        });
+    if has_header {
+        Ok(rhai::Dynamic::from(parse_table(text, &rhai::Map::new())?))
+    } else {
+        let mut options = rhai::Map::new();
+        options.insert("header".into(), false.into());
+        options.insert("missing".into(), "nan".into());
+        let (_, data) = parse_delimited(text, &options)?;
+        Ok(rhai::Dynamic::from_array(
+            data.into_iter()
+                .map(|row| {
+                    rhai::Dynamic::from_array(
+                        row.into_iter().map(rhai::Dynamic::from_float).collect(),
+                    )
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Formats the result of a script as delimited text: a `Table` with its header row, a matrix (as
+/// an array or a `Matrix`) or a single row as it would be written by `write_matrix`, nothing for
+/// `()`, and anything else as a line of text.
+#[cfg(all(feature = "io", feature = "data"))]
+pub fn delimited_output(value: rhai::Dynamic) -> Result<String, Box<rhai::EvalAltResult>> {
+    if value.is_unit() {
+        Ok(String::new())
+    } else if value.is::<crate::Table>() {
+        let table = value.cast::<crate::Table>();
+        let rows = (0..table.nrows())
+            .map(|i| {
+                rhai::Dynamic::from_array(
+                    table
+                        .columns()
+                        .iter()
+                        .map(|column| match column.get(i) {
+                            x if x.is_bool() => x.to_string().into(),
+                            x => x,
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        let mut options = rhai::Map::new();
+        options.insert(
+            "header".into(),
+            rhai::Dynamic::from_array(table.names().iter().map(|n| n.clone().into()).collect()),
+        );
+        format_delimited(rows, &options)
+    } else if value.is::<crate::Matrix>() {
+        format_delimited(value.cast::<crate::Matrix>().to_array(), &rhai::Map::new())
+    } else if value.is_array() {
+        format_delimited(value.cast::<rhai::Array>(), &rhai::Map::new())
+    } else {
+        Ok(format!("{value}\n"))
+    }
+}
+
+/// Converts a JSON value into a Rhai value: arrays become arrays, objects become maps, `null`
+/// becomes `()`, and whole numbers become integers if they fit.
+#[cfg(feature = "io")]
+fn json_to_dynamic(value: serde_json::Value) -> rhai::Dynamic {
+    match value {
+        serde_json::Value::Null => rhai::Dynamic::UNIT,
+        serde_json::Value::Bool(b) => rhai::Dynamic::from_bool(b),
+        serde_json::Value::Number(n) => {
+            match n.as_i64().and_then(|i| rhai::INT::try_from(i).ok()) {
+                Some(i) => rhai::Dynamic::from_int(i),
+                None => rhai::Dynamic::from_float(n.as_f64().unwrap_or(f64::NAN) as rhai::FLOAT),
+            }
+        }
+        serde_json::Value::String(s) => s.into(),
+        serde_json::Value::Array(arr) => {
+            rhai::Dynamic::from_array(arr.into_iter().map(json_to_dynamic).collect())
+        }
+        serde_json::Value::Object(map) => rhai::Dynamic::from_map(
+            map.into_iter()
+                .map(|(k, v)| (k.into(), json_to_dynamic(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Counts the array elements and map entries a JSON value would have as a Rhai value, the way
+/// Rhai does for `max_array_size` and `max_map_size`.
+#[cfg(feature = "io")]
+fn json_data_size(value: &serde_json::Value) -> (usize, usize) {
+    let add = |(arrays, maps): (usize, usize), value| {
+        let (a, m) = json_data_size(value);
+        (arrays.saturating_add(a), maps.saturating_add(m))
+    };
+    match value {
+        serde_json::Value::Array(arr) => arr.iter().fold((arr.len(), 0), add),
+        serde_json::Value::Object(map) => map.values().fold((0, map.len()), add),
+        _ => (0, 0),
+    }
+}
+
+/// Converts a Rhai value into a JSON value, failing for values JSON cannot hold, such as `NaN`.
+#[cfg(feature = "io")]
+#[allow(clippy::unnecessary_cast)] // FLOAT may be f32
+fn dynamic_to_json(value: &rhai::Dynamic) -> Result<serde_json::Value, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if value.is_unit() {
+        Ok(serde_json::Value::Null)
+    } else if let Ok(b) = value.as_bool() {
+        Ok(serde_json::Value::Bool(b))
+    } else if let Ok(i) = value.as_int() {
+        Ok(serde_json::Value::from(i))
+    } else if let Ok(f) = value.as_float() {
+        serde_json::Number::from_f64(f as f64)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| error(format!("JSON cannot represent the number {f}")))
+    } else if value.is_string() || value.is_char() {
+        Ok(serde_json::Value::String(value.to_string()))
+    } else if let Some(arr) = value.read_lock::<rhai::Array>() {
+        arr.iter()
+            .map(dynamic_to_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(serde_json::Value::Array)
+    } else if let Some(map) = value.read_lock::<rhai::Map>() {
+        map.iter()
+            .map(|(k, v)| dynamic_to_json(v).map(|v| (k.to_string(), v)))
+            .collect::<Result<serde_json::Map<_, _>, _>>()
+            .map(serde_json::Value::Object)
+    } else {
+        Err(error(format!(
+            "Cannot write a value of type {} as JSON",
+            value.type_name()
+        )))
+    }
+}
+
+/// Encodes a rectangular (possibly nested) array in the NumPy `.npy` format. Arrays of integers
+/// are stored as `int64`, arrays of booleans as `bool`, and anything else as `float64`.
+#[cfg(feature = "io")]
+#[allow(clippy::unnecessary_cast)] // INT may be i32 and FLOAT may be f32
+fn encode_npy(arr: &rhai::Array) -> Result<Vec<u8>, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    let mut shape = vec![arr.len()];
+    let mut first = arr.first().cloned();
+    while let Some(inner) = first.and_then(|el| el.into_array().ok()) {
+        shape.push(inner.len());
+        first = inner.first().cloned();
+    }
+    let mut flat = rhai::Array::new();
+    flatten_into(arr, &mut flat);
+    if flat.len() != shape.iter().product::<usize>() {
+        return Err(error(
+            "Only rectangular arrays can be written to .npy files".to_string(),
+        ));
+    }
+    let (descr, mut data) = if !flat.is_empty() && flat.iter().all(|el| el.is_bool()) {
+        (
+            "|b1",
+            flat.iter().map(|el| el.as_bool().unwrap() as u8).collect(),
+        )
+    } else if !flat.is_empty() && flat.iter().all(|el| el.is_int()) {
+        let data = flat
+            .iter()
+            .flat_map(|el| (el.as_int().unwrap() as i64).to_le_bytes())
+            .collect();
+        ("<i8", data)
+    } else {
+        let mut data = Vec::with_capacity(8 * flat.len());
+        for el in &flat {
+            let x = match (el.as_float(), el.as_int()) {
+                (Ok(f), _) => f as f64,
+                (_, Ok(i)) => i as f64,
+                _ => {
+                    return Err(error(format!(
+                        "Cannot write a value of type {} to a .npy file",
+                        el.type_name()
+                    )))
+                }
+            };
+            data.extend(x.to_le_bytes());
+        }
+        ("<f8", data)
+    };
+    let dims = match shape.as_slice() {
+        [n] => format!("{n},"),
+        _ => shape
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': ({dims}), }}");
+    // The magic string, version and header length take 10 bytes, and the header is padded with
+    // spaces so that the data starts on a 64-byte boundary.
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.into_bytes());
+    bytes.append(&mut data);
+    Ok(bytes)
+}
+
+/// Finds the value of `key` in the Python dictionary literal of a `.npy` header.
+#[cfg(feature = "io")]
+fn npy_header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{key}'"))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = match rest.chars().next()? {
+        '(' => rest.find(')')? + 1,
+        '\'' => rest[1..].find('\'')? + 2,
+        _ => rest.find([',', '}'])?,
+    };
+    Some(rest[..end].trim())
+}
+
+/// Decodes the contents of a NumPy `.npy` file into a (possibly nested) array, or a single value
+/// for zero-dimensional arrays. Supports boolean, integer and floating point data in either byte
+/// order and in either C or Fortran order.
+#[cfg(feature = "io")]
+fn decode_npy(
+    context: &rhai::NativeCallContext,
+    bytes: &[u8],
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if bytes.len() < 10 || !bytes.starts_with(b"\x93NUMPY") {
+        return Err(error("Not a .npy file".to_string()));
+    }
+    let (header_len, offset) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            12,
+        ),
+        version => return Err(error(format!("Unsupported .npy version {version}"))),
+    };
+    let header = bytes
+        .get(offset..offset + header_len)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| error("Invalid .npy header".to_string()))?;
+    let descr = npy_header_value(header, "descr")
+        .map(|d| d.trim_matches('\''))
+        .ok_or_else(|| error("Missing dtype in .npy header".to_string()))?;
+    let fortran = npy_header_value(header, "fortran_order") == Some("True");
+    let shape = npy_header_value(header, "shape")
+        .ok_or_else(|| error("Missing shape in .npy header".to_string()))?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.trim_end_matches('L').parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| error("Invalid shape in .npy header".to_string()))?;
+    crate::check_array_shape(context, &shape)?;
+
+    let mut chars = descr.chars();
+    let big_endian = match chars.next() {
+        Some('>') => true,
+        Some('<') | Some('|') | Some('=') => false,
+        _ => return Err(error(format!("Unsupported .npy dtype '{descr}'"))),
+    };
+    let kind = chars.next().unwrap_or(' ');
+    let size = chars.as_str().parse::<usize>().unwrap_or(0);
+    let decode: fn([u8; 8]) -> rhai::Dynamic = match (kind, size) {
+        ('b', 1) => |b| rhai::Dynamic::from_bool(b[0] != 0),
+        ('i', 1) => |b| rhai::Dynamic::from_int(b[0] as i8 as rhai::INT),
+        ('i', 2) => |b| rhai::Dynamic::from_int(i16::from_le_bytes([b[0], b[1]]) as rhai::INT),
+        ('i', 4) => {
+            |b| rhai::Dynamic::from_int(i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as rhai::INT)
+        }
+        ('i', 8) => |b| rhai::Dynamic::from_int(i64::from_le_bytes(b) as rhai::INT),
+        ('u', 1) => |b| rhai::Dynamic::from_int(b[0] as rhai::INT),
+        ('u', 2) => |b| rhai::Dynamic::from_int(u16::from_le_bytes([b[0], b[1]]) as rhai::INT),
+        ('u', 4) => {
+            |b| rhai::Dynamic::from_int(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as rhai::INT)
+        }
+        ('u', 8) => |b| rhai::Dynamic::from_int(u64::from_le_bytes(b) as rhai::INT),
+        ('f', 4) => |b| {
+            rhai::Dynamic::from_float(f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as rhai::FLOAT)
+        },
+        ('f', 8) => |b| rhai::Dynamic::from_float(f64::from_le_bytes(b) as rhai::FLOAT),
+        _ => return Err(error(format!("Unsupported .npy dtype '{descr}'"))),
+    };
+
+    let count = shape.iter().product::<usize>();
+    let data = &bytes[offset + header_len..];
+    if data.len() < count * size {
+        return Err(error("The .npy file is truncated".to_string()));
+    }
+    let mut elements = data
+        .chunks_exact(size)
+        .take(count)
+        .map(|chunk| {
+            let mut b = [0u8; 8];
+            b[..size].copy_from_slice(chunk);
+            if big_endian {
+                b[..size].reverse();
+            }
+            decode(b)
+        })
+        .collect::<Vec<_>>();
+    if fortran && shape.len() > 1 {
+        // Fortran order varies the first index fastest, so reorder into row-major order.
+        let mut reordered = Vec::with_capacity(count);
+        for i in 0..count {
+            let mut index = vec![0; shape.len()];
+            let mut rest = i;
+            for (k, d) in shape.iter().enumerate().rev() {
+                index[k] = rest % d;
+                rest /= d;
+            }
+            let position = index
+                .iter()
+                .zip(&shape)
+                .rev()
+                .fold(0, |acc, (idx, d)| acc * d + idx);
+            reordered.push(elements[position].clone());
+        }
+        elements = reordered;
+    }
+    Ok(match shape.as_slice() {
+        [] => elements.into_iter().next().unwrap_or(rhai::Dynamic::UNIT),
+        _ => rhai::Dynamic::from_array(nest_elements(&mut elements.into_iter(), &shape)),
+    })
+}
+
+/// Computes the CRC-32 checksum used by ZIP archives.
+#[cfg(feature = "io")]
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Packs files into an uncompressed ZIP archive, the same layout `numpy.savez` writes.
+#[cfg(feature = "io")]
+fn write_zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let mut fields = Vec::new();
+        fields.extend(20u16.to_le_bytes()); // version needed to extract
+        fields.extend(0u16.to_le_bytes()); // flags
+        fields.extend(0u16.to_le_bytes()); // stored, without compression
+        fields.extend(0u32.to_le_bytes()); // modification time and date
+        fields.extend(crc32(data).to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes()); // extra field length
+
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(&fields);
+        archive.extend(name.as_bytes());
+        archive.extend(data);
+
+        directory.extend(0x0201_4b50u32.to_le_bytes());
+        directory.extend(20u16.to_le_bytes()); // version made by
+        directory.extend(&fields);
+        directory.extend([0u8; 6]); // comment length, disk, internal attributes
+        directory.extend(0u32.to_le_bytes()); // external attributes
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend(&directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0u8; 4]); // disk numbers
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((directory.len() as u32).to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes()); // comment length
+    archive
+}
+
+/// Unpacks the files of an uncompressed ZIP archive, such as one written by `numpy.savez`.
+#[cfg(feature = "io")]
+fn read_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Box<rhai::EvalAltResult>> {
+    let error = |msg: &str| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg.to_string()).into()
+    };
+    let u16_at = |i: usize| {
+        bytes
+            .get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+    };
+    let u32_at = |i: usize| {
+        bytes
+            .get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|i| u32_at(*i) == Some(0x0605_4b50))
+        .ok_or_else(|| error("Not a ZIP archive"))?;
+    let count = u16_at(end + 10).unwrap_or(0);
+    let mut entry = u32_at(end + 16).unwrap_or(0);
+    let mut files = Vec::with_capacity(count);
+    for _ in 0..count {
+        let invalid = || error("Invalid ZIP archive");
+        if u32_at(entry) != Some(0x0201_4b50) {
+            return Err(invalid());
+        }
+        let method = u16_at(entry + 10).ok_or_else(invalid)?;
+        let size = u32_at(entry + 20).ok_or_else(invalid)?;
+        let name_len = u16_at(entry + 28).ok_or_else(invalid)?;
+        let extra_len = u16_at(entry + 30).ok_or_else(invalid)?;
+        let comment_len = u16_at(entry + 32).ok_or_else(invalid)?;
+        let local = u32_at(entry + 42).ok_or_else(invalid)?;
+        let name = bytes
+            .get(entry + 46..entry + 46 + name_len)
+            .map(|n| String::from_utf8_lossy(n).to_string())
+            .ok_or_else(invalid)?;
+        if method != 0 {
+            return Err(error(
+                "Compressed archives (such as from numpy.savez_compressed) are not supported",
+            ));
+        }
+        let start = local
+            + 30
+            + u16_at(local + 26).ok_or_else(invalid)?
+            + u16_at(local + 28).ok_or_else(invalid)?;
+        let data = bytes.get(start..start + size).ok_or_else(invalid)?;
+        if crc32(data) as usize != u32_at(entry + 16).ok_or_else(invalid)? {
+            return Err(error("ZIP archive checksum mismatch"));
+        }
+        files.push((name, data.to_vec()));
+        entry += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(files)
+}
+
+/// Reads an Apache Parquet file into a data frame.
+#[cfg(feature = "parquet")]
+fn read_parquet_frame(
+    file_path: &str,
+) -> Result<polars::prelude::DataFrame, Box<rhai::EvalAltResult>> {
+    use polars::prelude::{ParquetReader, SerReader};
+    let file = std::fs::File::open(file_path).map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(format!("Cannot read file: {file_path}"), err.into())
+    })?;
+    ParquetReader::new(file).finish().map_err(|err| {
+        rhai::EvalAltResult::ErrorSystem(
+            format!("Cannot read file as Parquet: {file_path}"),
+            err.into(),
+        )
+        .into()
+    })
+}
+
+/// Converts a data frame into a map with the column `names` and the `data` as an array of rows.
+/// Integer, float, boolean and string columns keep their types, and nulls become `()`.
+#[cfg(feature = "parquet")]
+fn dataframe_to_table(
+    df: &polars::prelude::DataFrame,
+) -> Result<rhai::Map, Box<rhai::EvalAltResult>> {
+    use polars::prelude::DataType;
+    let error = |err: polars::prelude::PolarsError| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Cannot read column: {err}"),
+        )
+        .into()
+    };
+    let mut columns = Vec::with_capacity(df.width());
+    for series in df.get_columns() {
+        let column: Vec<rhai::Dynamic> = match series.dtype() {
+            DataType::Boolean => series
+                .bool()
+                .map_err(error)?
+                .into_iter()
+                .map(|x| x.map_or(rhai::Dynamic::UNIT, rhai::Dynamic::from_bool))
+                .collect(),
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64 => series
+                .cast(&DataType::Int64)
+                .map_err(error)?
+                .i64()
+                .map_err(error)?
+                .into_iter()
+                .map(|x| {
+                    x.map_or(rhai::Dynamic::UNIT, |x| {
+                        rhai::Dynamic::from_int(x as rhai::INT)
+                    })
+                })
+                .collect(),
+            DataType::Utf8 => series
+                .utf8()
+                .map_err(error)?
+                .into_iter()
+                .map(|x| x.map_or(rhai::Dynamic::UNIT, |x| x.to_string().into()))
+                .collect(),
+            _ => series
+                .cast(&DataType::Float64)
+                .map_err(error)?
+                .f64()
+                .map_err(error)?
+                .into_iter()
+                .map(|x| {
+                    x.map_or(rhai::Dynamic::UNIT, |x| {
+                        rhai::Dynamic::from_float(x as rhai::FLOAT)
+                    })
+                })
+                .collect(),
+        };
+        columns.push(column);
+    }
+    let rows = (0..df.height())
+        .map(|i| {
+            rhai::Dynamic::from_array(columns.iter().map(|column| column[i].clone()).collect())
+        })
+        .collect::<rhai::Array>();
+    let mut table = rhai::Map::new();
+    table.insert(
+        "names".into(),
+        rhai::Dynamic::from_array(
+            df.get_column_names()
+                .into_iter()
+                .map(|name| name.to_string().into())
+                .collect(),
+        ),
+    );
+    table.insert("data".into(), rhai::Dynamic::from_array(rows));
+    Ok(table)
+}
+
+/// Converts a `Table`, a matrix, or a map with column `names` and `data` rows, into a data frame.
+/// Each column must hold a single type: integers, floats (integers mixed with floats become
+/// floats), booleans, or strings. `()` is written as a null.
+#[cfg(feature = "parquet")]
+fn table_to_dataframe(
+    table: rhai::Dynamic,
+) -> Result<polars::prelude::DataFrame, Box<rhai::EvalAltResult>> {
+    use polars::prelude::{DataFrame, NamedFrom, Series};
+    if let Some(table) = table.read_lock::<crate::Table>() {
+        return DataFrame::try_from(&*table).map_err(Into::into);
+    }
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    let (names, rows) = match table.clone().try_cast::<rhai::Map>() {
+        Some(map) => {
+            let names = map
+                .get("names")
+                .and_then(|names| {
+                    names
+                        .clone()
+                        .into_typed_array::<rhai::ImmutableString>()
+                        .ok()
+                })
+                .ok_or_else(|| error("A table must have an array of column names".to_string()))?;
+            let rows = map
+                .get("data")
+                .and_then(|data| data.clone().into_array().ok())
+                .ok_or_else(|| error("A table must have an array of data rows".to_string()))?;
+            (Some(names), rows)
+        }
+        None => (
+            None,
+            table
+                .into_array()
+                .map_err(|_| error("Expected a matrix or a table of names and data".to_string()))?,
+        ),
+    };
+    let rows = rows
+        .into_iter()
+        .map(|row| row.into_array())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| error("Each row of the data must be an array".to_string()))?;
+    let width = match (&names, rows.first()) {
+        (Some(names), _) => names.len(),
+        (None, Some(row)) => row.len(),
+        (None, None) => 0,
+    };
+    if rows.iter().any(|row| row.len() != width) {
+        return Err(error(format!("Every row must have {width} values")));
+    }
+    let names = names.unwrap_or_else(|| (1..=width).map(|j| format!("Var{j}").into()).collect());
+
+    let mut columns = Vec::with_capacity(width);
+    for (j, name) in names.iter().enumerate() {
+        let column = rows.iter().map(|row| &row[j]).collect::<Vec<_>>();
+        let values = column.iter().filter(|x| !x.is_unit());
+        let series = if values.clone().all(|x| x.is_int()) {
+            Series::new(
+                name,
+                column
+                    .iter()
+                    .map(|x| x.as_int().ok().map(|x| x as i64))
+                    .collect::<Vec<_>>(),
+            )
+        } else if values.clone().all(|x| x.is_int() || x.is_float()) {
+            let as_float = |x: &rhai::Dynamic| match (x.as_float(), x.as_int()) {
+                (Ok(f), _) => Some(f as f64),
+                (_, Ok(i)) => Some(i as f64),
+                _ => None,
+            };
+            Series::new(name, column.iter().map(|x| as_float(x)).collect::<Vec<_>>())
+        } else if values.clone().all(|x| x.is_bool()) {
+            Series::new(
+                name,
+                column.iter().map(|x| x.as_bool().ok()).collect::<Vec<_>>(),
+            )
+        } else if values.clone().all(|x| x.is_string()) {
+            Series::new(
+                name,
+                column
+                    .iter()
+                    .map(|x| x.clone().into_string().ok())
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            return Err(error(format!(
+                "Column '{name}' must hold only numbers, only booleans, or only strings"
+            )));
+        };
+        columns.push(series);
+    }
+    DataFrame::new(columns).map_err(|err| error(format!("Cannot build table: {err}")))
+}
+
+/// Reads a dataset from an HDF5 file into a (possibly nested) array, or a single value for scalar
+/// datasets. Integer and boolean datasets keep their types, and other numeric datasets become
+/// floats.
+#[cfg(feature = "hdf5")]
+fn read_hdf5_dataset(
+    context: &rhai::NativeCallContext,
+    file_path: &str,
+    dataset: &str,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    use hdf5lib::types::TypeDescriptor;
+    let error = |msg: String, err: hdf5lib::Error| -> Box<rhai::EvalAltResult> {
+        rhai::EvalAltResult::ErrorSystem(msg, err.into()).into()
+    };
+    let file = hdf5lib::File::open(file_path)
+        .map_err(|err| error(format!("Cannot read file as HDF5: {file_path}"), err))?;
+    let ds = file.dataset(dataset).map_err(|err| {
+        error(
+            format!("Cannot find dataset '{dataset}' in {file_path}"),
+            err,
+        )
+    })?;
+    let read_error = |err| {
+        error(
+            format!("Cannot read dataset '{dataset}' in {file_path}"),
+            err,
+        )
+    };
+    crate::check_array_shape(context, &ds.shape())?;
+    let descriptor = ds
+        .dtype()
+        .and_then(|dtype| dtype.to_descriptor())
+        .map_err(read_error)?;
+    let elements: Vec<rhai::Dynamic> = match descriptor {
+        TypeDescriptor::Boolean => ds
+            .read_raw::<bool>()
+            .map_err(read_error)?
+            .into_iter()
+            .map(rhai::Dynamic::from_bool)
+            .collect(),
+        TypeDescriptor::Integer(_) | TypeDescriptor::Unsigned(_) => ds
+            .read_raw::<i64>()
+            .map_err(read_error)?
+            .into_iter()
+            .map(|x| rhai::Dynamic::from_int(x as rhai::INT))
+            .collect(),
+        TypeDescriptor::Float(_) => ds
+            .read_raw::<f64>()
+            .map_err(read_error)?
+            .into_iter()
+            .map(|x| rhai::Dynamic::from_float(x as rhai::FLOAT))
+            .collect(),
+        other => {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!("Cannot read dataset '{dataset}' of type {other} as numbers"),
+            )
+            .into())
+        }
+    };
+    let shape = ds.shape();
+    Ok(match shape.as_slice() {
+        [] => elements.into_iter().next().unwrap_or(rhai::Dynamic::UNIT),
+        _ => rhai::Dynamic::from_array(nest_elements(&mut elements.into_iter(), &shape)),
+    })
+}
+
+/// Writes a rectangular (possibly nested) array to a dataset in an HDF5 file, creating the file
+/// if needed. Arrays of integers are stored as 64-bit integers, arrays of booleans as booleans,
+/// and anything else as 64-bit floats.
+#[cfg(feature = "hdf5")]
+fn write_hdf5_dataset(
+    file_path: &str,
+    dataset: &str,
+    arr: &rhai::Array,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    let mut shape = vec![arr.len()];
+    let mut first = arr.first().cloned();
+    while let Some(inner) = first.and_then(|el| el.into_array().ok()) {
+        shape.push(inner.len());
+        first = inner.first().cloned();
+    }
+    let mut flat = rhai::Array::new();
+    flatten_into(arr, &mut flat);
+    if flat.len() != shape.iter().product::<usize>() {
+        return Err(error(
+            "Only rectangular arrays can be written to HDF5 files".to_string(),
+        ));
+    }
+
+    let write_error = |err: hdf5lib::Error| -> Box<rhai::EvalAltResult> {
+        rhai::EvalAltResult::ErrorSystem(
+            format!("Cannot write dataset '{dataset}' to {file_path}"),
+            err.into(),
+        )
+        .into()
+    };
+    let file = hdf5lib::File::append(file_path).map_err(write_error)?;
+    if !flat.is_empty() && flat.iter().all(|el| el.is_bool()) {
+        let data = flat
+            .iter()
+            .map(|el| el.as_bool().unwrap())
+            .collect::<Vec<_>>();
+        file.new_dataset::<bool>()
+            .shape(shape)
+            .create(dataset)
+            .and_then(|ds| ds.write_raw(data.as_slice()))
+            .map_err(write_error)
+    } else if !flat.is_empty() && flat.iter().all(|el| el.is_int()) {
+        let data = flat
+            .iter()
+            .map(|el| el.as_int().unwrap() as i64)
+            .collect::<Vec<_>>();
+        file.new_dataset::<i64>()
+            .shape(shape)
+            .create(dataset)
+            .and_then(|ds| ds.write_raw(data.as_slice()))
+            .map_err(write_error)
+    } else {
+        let data = flat
+            .iter()
+            .map(|el| match (el.as_float(), el.as_int()) {
+                (Ok(f), _) => Ok(f as f64),
+                (_, Ok(i)) => Ok(i as f64),
+                _ => Err(error(format!(
+                    "Cannot write a value of type {} to an HDF5 file",
+                    el.type_name()
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        file.new_dataset::<f64>()
+            .shape(shape)
+            .create(dataset)
+            .and_then(|ds| ds.write_raw(data.as_slice()))
+            .map_err(write_error)
+    }
+}
+
 #[export_module]
 pub mod matrix_functions {
     use crate::{
         if_int_convert_to_float_and_do, if_int_do_else_if_array_do, if_list_do,
-        if_matrix_convert_to_vec_array_and_do,
+        if_matrix_convert_to_vec_array_and_do, if_matrix_do,
+    };
+    #[cfg(feature = "nalgebra")]
+    use crate::{
+        if_matrices_and_compatible_convert_to_vec_array_and_do,
+        if_matrix_convert_to_dmatrix_and_do, omatrix_to_vec_dynamic, ovector_to_vec_dynamic, FOIL,
     };
     #[cfg(feature = "nalgebra")]
-    use crate::{
-        if_matrices_and_compatible_convert_to_vec_array_and_do, if_matrix_do,
-        omatrix_to_vec_dynamic, ovector_to_vec_dynamic, FOIL,
-    };
+    use nalgebralib::DMatrix;
+    use rhai::{Array, Dynamic, EvalAltResult, Map, NativeCallContext, FLOAT, INT};
+    #[cfg(feature = "nalgebra")]
+    use std::collections::BTreeMap;
+
+    /// Calculates the inverse of a matrix. Fails if the matrix is not square, if it is singular
+    /// (or so close to singular that the inverse would be meaningless), or if the elements of the
+    /// matrix aren't FLOAT or INT.
+    /// ```typescript
+    /// let x = [[ 1.0,  0.0,  2.0],
+    ///          [-1.0,  5.0,  0.0],
+    ///          [ 0.0,  3.0, -9.0]];
+    /// let x_inverted = inv(x);
+    /// assert_eq(x_inverted, [[0.8823529411764706,  -0.11764705882352941,   0.19607843137254902],
+    ///                        [0.17647058823529413,  0.17647058823529413,   0.0392156862745098 ],
+    ///                        [0.058823529411764705, 0.058823529411764705, -0.09803921568627451]]
+    /// );
+    /// ```
+    /// ```typescript
+    /// let x = [[1, 2],
+    ///          [3, 4]];
+    /// let x_inverted = inv(x);
+    /// assert_eq(x_inverted, [[-2.0, 1.0],
+    ///                        [1.5, -0.5]]
+    /// );
+    /// ```
+    /// ```typescript
+    /// let x = [[1, 2],
+    ///          [2, 4]];
+    /// let result = "inverted";
+    /// try { inv(x); } catch { result = "singular"; }
+    /// assert_eq(result, "singular");
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "inv", return_raw, pure)]
+    pub fn invert_matrix(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            if !dm.is_square() {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "Only square matrices can be inverted, but this matrix is {} x {}",
+                        dm.nrows(),
+                        dm.ncols()
+                    ),
+                )
+                .in_function("inv")
+                .with_argument("matrix")
+                .into());
+            }
+
+            // Detect singular and nearly singular matrices from the reciprocal condition number
+            let singular_values = dm.singular_values();
+            let rcond = singular_values.min() / singular_values.max();
+            if rcond.is_nan() || rcond <= FLOAT::EPSILON {
+                return Err(crate::LabError::new(crate::LabErrorKind::Numerical, format!("Matrix is singular or nearly singular (reciprocal condition number {rcond:e}) and cannot be inverted")).in_function("inv")
+                .into());
+            }
+
+            // Try to invert
+            let dm = crate::dense_inverse(dm);
+
+            dm.map(omatrix_to_vec_dynamic).ok_or_else(|| {
+                crate::LabError::new(crate::LabErrorKind::Numerical, "Matrix cannot be inverted")
+                    .in_function("inv")
+                    .into()
+            })
+        })
+        .map_err(crate::in_function("inv", Some("matrix")))
+    }
+
+    /// Calculates the determinant of a square matrix.
+    /// ```typescript
+    /// let x = [[1, 2],
+    ///          [3, 4]];
+    /// assert_eq(det(x), -2.0);
+    /// ```
+    /// ```typescript
+    /// assert_eq(det(eye(4)), 1.0);
+    /// ```
+    /// ```typescript
+    /// let err = #{};
+    /// try {
+    ///     det([]);
+    /// } catch (error) {
+    ///     err = error;
+    /// }
+    /// assert_eq(err.kind, "type_mismatch");
+    /// assert_eq(err.function, "det");
+    /// assert_eq(err.argument, "matrix");
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "det", return_raw, pure)]
+    pub fn determinant(matrix: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            if dm.is_square() {
+                Ok(dm.determinant())
+            } else {
+                Err(crate::LabError::new(crate::LabErrorKind::DimensionMismatch, format!(
+                        "The determinant is only defined for square matrices, but this matrix is {} x {}",
+                        dm.nrows(),
+                        dm.ncols()
+                    )).in_function("det").with_argument("matrix")
+                .into())
+            }
+        })
+        .map_err(crate::in_function("det", Some("matrix")))
+    }
+
+    /// Calculates the Moore-Penrose pseudo-inverse of a matrix using its singular value
+    /// decomposition. Singular values smaller than `max(rows, columns) * epsilon` times the largest
+    /// singular value are treated as zero. Unlike `inv`, this works for singular and non-square
+    /// matrices.
+    /// ```typescript
+    /// let x = [[2, 0],
+    ///          [0, 0]];
+    /// let y = pinv(x);
+    /// assert_eq(y, [[0.5, 0.0],
+    ///               [0.0, 0.0]]);
+    /// ```
+    /// ```typescript
+    /// let x = [[1, 0, 0],
+    ///          [0, 2, 0]];
+    /// assert_eq(pinv(x), [[1.0, 0.0], [0.0, 0.5], [0.0, 0.0]]);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "pinv", return_raw, pure)]
+    pub fn pseudo_inverse(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        pseudo_inverse_with_tolerance(matrix, -1.0)
+    }
+
+    /// Calculates the Moore-Penrose pseudo-inverse of a matrix, treating singular values smaller
+    /// than `tol` as zero.
+    /// ```typescript
+    /// let x = [[1.0, 0.0],
+    ///          [0.0, 1e-8]];
+    /// assert_eq(pinv(x, 1e-6), [[1.0, 0.0], [0.0, 0.0]]);
+    /// ```
     #[cfg(feature = "nalgebra")]
-    use nalgebralib::DMatrix;
-    use rhai::{Array, Dynamic, EvalAltResult, Map, Position, FLOAT, INT};
-    use std::collections::BTreeMap;
+    #[rhai_fn(name = "pinv", return_raw, pure)]
+    pub fn pseudo_inverse_with_tolerance(
+        matrix: &mut Array,
+        tol: FLOAT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            // A negative tolerance selects the default
+            let tol = if tol < 0.0 {
+                dm.nrows().max(dm.ncols()) as FLOAT * FLOAT::EPSILON * dm.singular_values().max()
+            } else {
+                tol
+            };
+
+            dm.pseudo_inverse(tol)
+                .map(omatrix_to_vec_dynamic)
+                .map_err(|e| {
+                    crate::LabError::new(
+                        crate::LabErrorKind::Numerical,
+                        format!("The pseudo-inverse cannot be computed: {e}"),
+                    )
+                    .in_function("pinv")
+                    .into()
+                })
+        })
+        .map_err(crate::in_function("pinv", None))
+    }
 
-    /// Calculates the inverse of a matrix. Fails if the matrix if not invertible, or if the
-    /// elements of the matrix aren't FLOAT or INT.
+    /// Calculates the 2-norm condition number of a matrix: the ratio of its largest to smallest
+    /// singular value. Large values mean that solving a system with this matrix will amplify
+    /// errors, and singular matrices give infinity.
     /// ```typescript
-    /// let x = [[ 1.0,  0.0,  2.0],
-    ///          [-1.0,  5.0,  0.0],
-    ///          [ 0.0,  3.0, -9.0]];
-    /// let x_inverted = inv(x);
-    /// assert_eq(x_inverted, [[0.8823529411764706,  -0.11764705882352941,   0.19607843137254902],
-    ///                        [0.17647058823529413,  0.17647058823529413,   0.0392156862745098 ],
-    ///                        [0.058823529411764705, 0.058823529411764705, -0.09803921568627451]]
-    /// );
+    /// let x = [[2.0, 0.0],
+    ///          [0.0, 0.5]];
+    /// assert_eq(cond(x), 4.0);
     /// ```
     /// ```typescript
-    /// let x = [[1, 2],
+    /// assert_eq(cond([[1, 2], [2, 4]]), 1.0 / 0.0);
+    /// ```
+    /// ```typescript
+    /// assert_eq(cond([[2, 0.0], [0, 0.5]]), 4.0);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "cond", return_raw, pure)]
+    pub fn cond(matrix: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            let singular_values = dm.singular_values();
+            let (largest, smallest) = (singular_values.max(), singular_values.min());
+            let tol = dm.nrows().max(dm.ncols()) as FLOAT * FLOAT::EPSILON * largest;
+            if smallest <= tol {
+                Ok(FLOAT::INFINITY)
+            } else {
+                Ok(largest / smallest)
+            }
+        })
+        .map_err(crate::in_function("cond", Some("matrix")))
+    }
+
+    /// Calculates the 2-norm of a vector (its Euclidean length) or of a matrix (its largest
+    /// singular value).
+    /// ```typescript
+    /// assert_eq(norm([3, 4]), 5.0);
+    /// ```
+    /// ```typescript
+    /// assert_eq(norm([[2, 0], [0, -3]]), 3.0);
+    /// ```
+    /// ```typescript
+    /// assert_eq(norm([[2, 0.0], [0, -3]]), 3.0);
+    /// assert_eq(norm([[1, 2.0], [3, 4]], "1"), 6.0);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "norm", return_raw, pure)]
+    pub fn norm(arr: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        norm_with_type(arr, Dynamic::from_int(2))
+    }
+
+    /// Calculates a norm of a vector or matrix. For vectors, `p` can be any positive number for
+    /// the p-norm `sum(abs(x)^p)^(1/p)`, or `"inf"` / `"-inf"` for the largest / smallest
+    /// absolute value. For matrices, `p` can be `1` (the largest absolute column sum), `2` (the
+    /// largest singular value), `"inf"` (the largest absolute row sum), or `"fro"` (the Frobenius
+    /// norm). Numbers may also be given as strings, such as `"1"`.
+    /// ```typescript
+    /// let v = [1, -2, 2];
+    /// assert_eq(norm(v, 1), 5.0);
+    /// assert_eq(norm(v, "inf"), 2.0);
+    /// assert_eq(norm(v, "-inf"), 1.0);
+    /// ```
+    /// ```typescript
+    /// let m = [[1, -2],
     ///          [3, 4]];
-    /// let x_inverted = inv(x);
-    /// assert_eq(x_inverted, [[-2.0, 1.0],
-    ///                        [1.5, -0.5]]
-    /// );
+    /// assert_eq(norm(m, "1"), 6.0);
+    /// assert_eq(norm(m, "inf"), 7.0);
+    /// assert_eq(norm(m, "fro"), sqrt(30.0));
     /// ```
     #[cfg(feature = "nalgebra")]
-    #[rhai_fn(name = "inv", return_raw, pure)]
-    pub fn invert_matrix(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            let dm = DMatrix::from_fn(matrix_as_vec.len(), matrix_as_vec[0].len(), |i, j| {
-                if matrix_as_vec[0][0].is_float() {
-                    matrix_as_vec[i][j].as_float().unwrap()
-                } else {
-                    matrix_as_vec[i][j].as_int().unwrap() as FLOAT
-                }
-            });
+    #[rhai_fn(name = "norm", return_raw, pure)]
+    pub fn norm_with_type(arr: &mut Array, p: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        // Accept numbers and strings alike, normalizing both to a string
+        let kind = if p.is_string() {
+            p.into_string().unwrap()
+        } else {
+            if_int_convert_to_float_and_do(p, |p| Ok(p.to_string()))?
+        };
+        let unknown = |kind: &str| -> Box<EvalAltResult> {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Unknown norm type '{kind}'"),
+            )
+            .in_function("norm")
+            .into()
+        };
 
-            // Try to invert
-            let dm = dm.try_inverse();
+        if arr.iter().all(|x| !x.is_array()) {
+            let x = crate::if_list_convert_to_vec_float_and_do(arr, Ok)?;
+            let abs = x.iter().map(|xi| xi.abs());
+            return match kind.as_str() {
+                "inf" => Ok(abs.fold(0.0, FLOAT::max)),
+                "-inf" => Ok(abs.fold(FLOAT::INFINITY, FLOAT::min)),
+                "fro" => Ok(abs.map(|xi| xi * xi).sum::<FLOAT>().sqrt()),
+                _ => match kind.parse::<FLOAT>() {
+                    Ok(p) if p > 0.0 => Ok(abs.map(|xi| xi.powf(p)).sum::<FLOAT>().powf(1.0 / p)),
+                    _ => Err(unknown(&kind)),
+                },
+            };
+        }
 
-            dm.map(omatrix_to_vec_dynamic).ok_or_else(|| {
-                EvalAltResult::ErrorArithmetic(
-                    "Matrix cannot be inverted".to_string(),
-                    Position::NONE,
+        if_matrix_convert_to_dmatrix_and_do(arr, |dm| match kind.as_str() {
+            "1" => Ok(dm
+                .column_iter()
+                .map(|c| c.iter().map(|x| x.abs()).sum::<FLOAT>())
+                .fold(0.0, FLOAT::max)),
+            "2" => Ok(dm.singular_values().max()),
+            "inf" => Ok(dm
+                .row_iter()
+                .map(|r| r.iter().map(|x| x.abs()).sum::<FLOAT>())
+                .fold(0.0, FLOAT::max)),
+            "fro" => Ok(dm.norm()),
+            _ => Err(unknown(&kind)),
+        })
+    }
+
+    /// Solves the linear system `A x = b` for `x`. Square systems are solved with an LU
+    /// decomposition, over-determined systems (more rows than columns) in the least-squares sense
+    /// with a QR decomposition, and under-determined systems with the minimum-norm solution. `b`
+    /// can be a list, in which case `x` is returned as a list, or a matrix with one right-hand side
+    /// per column. This is faster and more accurate than computing `mtimes(inv(A), b)`.
+    /// ```typescript
+    /// let a = [[2, 1],
+    ///          [1, 3]];
+    /// let x = solve(a, [3, 5]);
+    /// assert_eq(x, [0.8, 1.4]);
+    /// ```
+    /// ```typescript,only_f64
+    /// let a = [[1, 0],
+    ///          [1, 1],
+    ///          [1, 2]];
+    /// let x = solve(a, [[1], [2], [3]]);
+    /// assert(abs(x[0][0] - 1.0) < 1e-12 && abs(x[1][0] - 1.0) < 1e-12);
+    /// ```
+    /// ```typescript
+    /// let a = [[1, 2],
+    ///          [2, 4]];
+    /// let result = "solved";
+    /// try { solve(a, [1, 2]); } catch { result = "singular"; }
+    /// assert_eq(result, "singular");
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve(matrix: &mut Array, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        let b_is_list = b.iter().all(|x| !x.is_array());
+        let rhs = crate::Matrix::from_array(&b)?;
+        let rhs = if b_is_list {
+            DMatrix::from_column_slice(rhs.cols(), 1, rhs.data())
+        } else {
+            DMatrix::from_row_slice(rhs.rows(), rhs.cols(), rhs.data())
+        };
+
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            let (rows, cols) = dm.shape();
+            if rhs.nrows() != rows {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "The right-hand side has {} rows, but the matrix has {rows}",
+                        rhs.nrows()
+                    ),
+                )
+                .in_function("solve")
+                .with_argument("matrix")
+                .into());
+            }
+
+            let singular = || -> Box<EvalAltResult> {
+                crate::LabError::new(
+                    crate::LabErrorKind::Numerical,
+                    "Matrix is singular or rank deficient, so the system has no unique solution"
+                        .to_string(),
                 )
+                .in_function("solve")
                 .into()
-            })
+            };
+            let x = if rows == cols {
+                crate::dense_lu_solve(dm, &rhs).ok_or_else(singular)?
+            } else if rows > cols {
+                let qr = dm.qr();
+                let r = qr.r();
+                if r.diagonal()
+                    .iter()
+                    .any(|d| d.abs() <= FLOAT::EPSILON * r.amax())
+                {
+                    return Err(singular());
+                }
+                let qtb = qr.q().transpose() * &rhs;
+                r.solve_upper_triangular(&qtb).ok_or_else(singular)?
+            } else {
+                dm.svd(true, true).solve(&rhs, FLOAT::EPSILON).map_err(
+                    |e| -> Box<EvalAltResult> {
+                        crate::LabError::new(crate::LabErrorKind::Numerical, e.to_string())
+                            .in_function("solve")
+                            .into()
+                    },
+                )?
+            };
+
+            if b_is_list {
+                Ok(x.iter().map(|v| Dynamic::from_float(*v)).collect())
+            } else {
+                Ok(omatrix_to_vec_dynamic(x))
+            }
+        })
+    }
+
+    /// Calculates the eigenvalues of a square matrix. For symmetric matrices, the eigenvalues are
+    /// real and returned as FLOATs in ascending order. For general matrices, real eigenvalues are
+    /// returned as FLOATs and complex eigenvalues as `[real, imaginary]` pairs.
+    /// ```typescript,only_f64
+    /// let m = [[2, 1],
+    ///          [1, 2]];
+    /// let values = eig(m);
+    /// assert(abs(values[0] - 1.0) < 1e-12 && abs(values[1] - 3.0) < 1e-12);
+    /// ```
+    /// ```typescript,only_f64
+    /// let rotation = [[0, -1],
+    ///                 [1,  0]];
+    /// let values = eig(rotation);
+    /// assert(abs(values[0][0]) < 1e-12 && abs(abs(values[0][1]) - 1.0) < 1e-12);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "eig", return_raw, pure)]
+    pub fn eig(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        eig_with_vectors(matrix, false).map(|result| result["values"].clone().into_array().unwrap())
+    }
+
+    /// Calculates the eigenvalues of a square matrix and, if `with_vectors` is true, its
+    /// eigenvectors. Returns an object map with the `values` (as for `eig(m)`) and, if requested,
+    /// the `vectors` as the columns of a matrix, normalized to unit length. The elements of
+    /// eigenvectors for complex eigenvalues are `[real, imaginary]` pairs. A repeated eigenvalue
+    /// gets an independent eigenvector for each copy, and defective matrices, which do not have
+    /// enough independent eigenvectors, raise a `numerical` error.
+    /// ```typescript
+    /// let m = [[2, 0],
+    ///          [0, 3]];
+    /// let result = eig(m, true);
+    /// assert_eq(result.values, [2.0, 3.0]);
+    /// assert_eq(result.vectors, [[1.0, 0.0], [0.0, 1.0]]);
+    /// ```
+    /// ```typescript,only_f64
+    /// let m = [[1, 2],
+    ///          [0, 3]];
+    /// let result = eig(m, true);
+    /// let v = result.vectors;
+    /// for k in 0..2 {
+    ///     let lambda = result.values[k];
+    ///     let av = [m[0][0] * v[0][k] + m[0][1] * v[1][k], m[1][0] * v[0][k] + m[1][1] * v[1][k]];
+    ///     assert(abs(av[0] - lambda * v[0][k]) < 1e-12 && abs(av[1] - lambda * v[1][k]) < 1e-12);
+    /// }
+    /// true
+    /// ```
+    /// ```typescript
+    /// let m = [[2, 0, 1],
+    ///          [0, 2, 0],
+    ///          [0, 0, 3]];
+    /// let result = eig(m, true);
+    /// assert_eq(result.values, [2.0, 2.0, 3.0]);
+    /// let v = result.vectors;
+    /// let dot = v[0][0] * v[0][1] + v[1][0] * v[1][1] + v[2][0] * v[2][1];
+    /// assert(abs(dot) < 1e-6);
+    /// ```
+    /// ```typescript
+    /// let kind = "";
+    /// try { eig([[1, 1], [0, 1]], true); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "numerical");
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "eig", return_raw, pure)]
+    pub fn eig_with_vectors(
+        matrix: &mut Array,
+        with_vectors: bool,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            if !dm.is_square() {
+                return Err(crate::LabError::new(crate::LabErrorKind::DimensionMismatch, format!(
+                        "Eigenvalues are only defined for square matrices, but this matrix is {} x {}",
+                        dm.nrows(),
+                        dm.ncols()
+                    )).in_function("eig").with_argument("matrix")
+                .into());
+            }
+            let n = dm.nrows();
+            let mut result = Map::new();
+
+            // Symmetric matrices have real eigenvalues and orthonormal eigenvectors
+            if dm.relative_eq(&dm.transpose(), FLOAT::EPSILON, FLOAT::EPSILON) {
+                let (eigenvalues, eigenvectors) = crate::dense_symmetric_eigen(dm);
+                let order = crate::argsort_floats(eigenvalues.as_slice());
+                result.insert(
+                    "values".into(),
+                    Dynamic::from_array(
+                        order
+                            .iter()
+                            .map(|k| Dynamic::from_float(eigenvalues[*k]))
+                            .collect(),
+                    ),
+                );
+                if with_vectors {
+                    let vectors = DMatrix::from_fn(n, n, |i, j| eigenvectors[(i, order[j])]);
+                    result.insert(
+                        "vectors".into(),
+                        Dynamic::from_array(omatrix_to_vec_dynamic(vectors)),
+                    );
+                }
+                return Ok(result);
+            }
+
+            let eigenvalues = dm.complex_eigenvalues();
+            let is_real = |im: FLOAT, re: FLOAT| im.abs() <= FLOAT::EPSILON * re.abs().max(1.0);
+            result.insert(
+                "values".into(),
+                Dynamic::from_array(
+                    eigenvalues
+                        .iter()
+                        .map(|ev| {
+                            if is_real(ev.im, ev.re) {
+                                Dynamic::from_float(ev.re)
+                            } else {
+                                Dynamic::from_array(vec![
+                                    Dynamic::from_float(ev.re),
+                                    Dynamic::from_float(ev.im),
+                                ])
+                            }
+                        })
+                        .collect(),
+                ),
+            );
+
+            if with_vectors {
+                // Each eigenvector lies in the null space of A - lambda*I, spanned by the right
+                // singular vectors with (numerically) zero singular values. Complex eigenvectors
+                // u + iv are found from the equivalent real system of twice the size. A repeated
+                // eigenvalue shares its null space with its earlier copies, so the vector chosen
+                // is the one left longest after projecting out those already found for it.
+                use nalgebralib::{Complex, DVector};
+                let tol = FLOAT::EPSILON.sqrt();
+                let mut columns: Vec<DVector<Complex<FLOAT>>> = vec![];
+                for (idx, ev) in eigenvalues.iter().enumerate() {
+                    let shifted = &dm - DMatrix::from_diagonal_element(n, n, ev.re);
+                    let (matrix, to_complex): (_, &dyn Fn(DVector<FLOAT>) -> DVector<_>) =
+                        if is_real(ev.im, ev.re) {
+                            (shifted, &|v| v.map(|x| Complex::new(x, 0.0)))
+                        } else {
+                            let mut big = DMatrix::zeros(2 * n, 2 * n);
+                            big.view_mut((0, 0), (n, n)).copy_from(&shifted);
+                            big.view_mut((n, n), (n, n)).copy_from(&shifted);
+                            big.view_mut((0, n), (n, n)).fill_diagonal(ev.im);
+                            big.view_mut((n, 0), (n, n)).fill_diagonal(-ev.im);
+                            (big, &|v| DVector::from_fn(n, |i, _| Complex::new(v[i], v[n + i])))
+                        };
+                    let svd = matrix.svd(false, true);
+                    let v_t = svd.v_t.unwrap();
+                    let smallest = svd.singular_values.imin();
+                    let zero = tol * svd.singular_values.max().max(1.0);
+                    let previous = eigenvalues
+                        .iter()
+                        .zip(&columns)
+                        .take(idx)
+                        .filter(|(other, _)| (*other - ev).norm() <= tol * ev.norm().max(1.0))
+                        .map(|(_, column)| column)
+                        .collect::<Vec<_>>();
+                    let best = (0..svd.singular_values.len())
+                        .filter(|k| *k == smallest || svd.singular_values[*k] <= zero)
+                        .map(|k| {
+                            let mut v = to_complex(v_t.row(k).transpose());
+                            for p in &previous {
+                                v -= *p * p.dotc(&v);
+                            }
+                            v
+                        })
+                        .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+                        .unwrap();
+                    if best.norm() <= tol {
+                        return Err(crate::LabError::new(
+                            crate::LabErrorKind::Numerical,
+                            format!(
+                                "The matrix is defective: the repeated eigenvalue {} has fewer \
+                                 independent eigenvectors than its multiplicity",
+                                ev.re
+                            ),
+                        )
+                        .in_function("eig")
+                        .with_argument("matrix")
+                        .into());
+                    }
+                    let mut v = best.normalize();
+                    if is_real(ev.im, ev.re) {
+                        let largest = v.map(|x| x.re).iamax();
+                        if v[largest].re < 0.0 {
+                            v = -v;
+                        }
+                    }
+                    columns.push(v);
+                }
+                let vectors = (0..n)
+                    .map(|i| {
+                        Dynamic::from_array(
+                            eigenvalues
+                                .iter()
+                                .zip(&columns)
+                                .map(|(ev, c)| {
+                                    if is_real(ev.im, ev.re) {
+                                        Dynamic::from_float(c[i].re)
+                                    } else {
+                                        Dynamic::from_array(vec![
+                                            Dynamic::from_float(c[i].re),
+                                            Dynamic::from_float(c[i].im),
+                                        ])
+                                    }
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect::<Array>();
+                result.insert("vectors".into(), Dynamic::from_array(vectors));
+            }
+            Ok(result)
         })
+        .map_err(crate::in_function("eig", None))
     }
 
     /// Calculate the eigenvalues and eigenvectors for a matrix. Specifically, the output is an
@@ -79,16 +2326,7 @@ pub mod matrix_functions {
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "eigs", return_raw, pure)]
     pub fn matrix_eigs_alt(matrix: &mut Array) -> Result<Map, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            // Convert vec_array to omatrix
-            let mut dm = DMatrix::from_fn(matrix_as_vec.len(), matrix_as_vec[0].len(), |i, j| {
-                if matrix_as_vec[0][0].is_float() {
-                    matrix_as_vec[i][j].as_float().unwrap()
-                } else {
-                    matrix_as_vec[i][j].as_int().unwrap() as FLOAT
-                }
-            });
-
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
             // Grab shape for later
             let dms = dm.shape().1;
 
@@ -146,6 +2384,7 @@ pub mod matrix_functions {
 
             Ok(result)
         })
+        .map_err(crate::in_function("eigs", Some("matrix")))
     }
 
     /// Calculates the singular value decomposition of a matrix
@@ -157,54 +2396,38 @@ pub mod matrix_functions {
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "svd", return_raw, pure)]
     pub fn svd_decomp(matrix: &mut Array) -> Result<Map, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            let dm = DMatrix::from_fn(matrix_as_vec.len(), matrix_as_vec[0].len(), |i, j| {
-                if matrix_as_vec[0][0].is::<FLOAT>() {
-                    matrix_as_vec[i][j].as_float().unwrap()
-                } else {
-                    matrix_as_vec[i][j].as_int().unwrap() as FLOAT
-                }
-            });
-
-            // Try ot invert
-            let svd = nalgebralib::linalg::SVD::new(dm, true, true);
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            // Try to decompose
+            let (u, singular_values, v_t) = crate::dense_svd(dm).ok_or_else(|| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::Numerical,
+                        "SVD decomposition cannot be computed for this matrix.",
+                    )
+                    .in_function("svd")
+                    .with_argument("matrix"),
+                )
+            })?;
 
             let mut result = BTreeMap::new();
             let mut uid = smartstring::SmartString::new();
             uid.push_str("u");
-            match svd.u {
-                Some(u) => result.insert(uid, Dynamic::from_array(omatrix_to_vec_dynamic(u))),
-                None => {
-                    return Err(EvalAltResult::ErrorArithmetic(
-                        format!("SVD decomposition cannot be computed for this matrix."),
-                        Position::NONE,
-                    )
-                    .into());
-                }
-            };
+            result.insert(uid, Dynamic::from_array(omatrix_to_vec_dynamic(u)));
 
             let mut vid = smartstring::SmartString::new();
             vid.push_str("v");
-            match svd.v_t {
-                Some(v) => result.insert(vid, Dynamic::from_array(omatrix_to_vec_dynamic(v))),
-                None => {
-                    return Err(EvalAltResult::ErrorArithmetic(
-                        format!("SVD decomposition cannot be computed for this matrix."),
-                        Position::NONE,
-                    )
-                    .into());
-                }
-            };
+            result.insert(vid, Dynamic::from_array(omatrix_to_vec_dynamic(v_t)));
 
             let mut sid = smartstring::SmartString::new();
             sid.push_str("s");
             result.insert(
                 sid,
-                Dynamic::from_array(ovector_to_vec_dynamic(svd.singular_values)),
+                Dynamic::from_array(ovector_to_vec_dynamic(singular_values)),
             );
 
             Ok(result)
         })
+        .map_err(crate::in_function("svd", Some("matrix")))
     }
 
     /// Calculates the QR decomposition of a matrix
@@ -216,15 +2439,7 @@ pub mod matrix_functions {
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "qr", return_raw, pure)]
     pub fn qr_decomp(matrix: &mut Array) -> Result<Map, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            let dm = DMatrix::from_fn(matrix_as_vec.len(), matrix_as_vec[0].len(), |i, j| {
-                if matrix_as_vec[0][0].is::<FLOAT>() {
-                    matrix_as_vec[i][j].as_float().unwrap()
-                } else {
-                    matrix_as_vec[i][j].as_int().unwrap() as FLOAT
-                }
-            });
-
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
             // Try ot invert
             let qr = nalgebralib::linalg::QR::new(dm);
 
@@ -239,6 +2454,115 @@ pub mod matrix_functions {
 
             Ok(result)
         })
+        .map_err(crate::in_function("qr", Some("matrix")))
+    }
+
+    /// Calculates the QR decomposition of an m x n matrix, in economy size (the default, with `q`
+    /// of size m x min(m, n) and `r` of size min(m, n) x n) or in full size (with `q` an m x m
+    /// orthogonal matrix and `r` of size m x n).
+    /// ```typescript
+    /// let matrix = [[1, 2], [3, 4], [5, 6]];
+    /// let full = qr(matrix, false);
+    /// assert_eq(size(full.q), [3, 3]);
+    /// assert_eq(size(full.r), [3, 2]);
+    /// assert_eq(full.r[2], [0.0, 0.0]);
+    /// ```
+    /// ```typescript,only_f64
+    /// let matrix = [[1, 2], [3, 4], [5, 6]];
+    /// let economy = qr(matrix, true);
+    /// assert_eq(size(economy.q), [3, 2]);
+    /// assert_eq(size(economy.r), [2, 2]);
+    /// let a = mtimes(economy.q, economy.r);
+    /// assert(abs(a[2][1] - 6.0) < 1e-12);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "qr", return_raw, pure)]
+    pub fn qr_decomp_with_size(
+        matrix: &mut Array,
+        economy: bool,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if economy {
+            return qr_decomp(matrix);
+        }
+        if_matrix_convert_to_dmatrix_and_do(matrix, |a| {
+            let (m, n) = a.shape();
+
+            // Householder QR of [A | I] applies the same reflections to A, then completes Q with
+            // an orthonormal basis for the rest of the space
+            let dm = DMatrix::from_fn(m, n + m, |i, j| {
+                if j >= n {
+                    if i == j - n {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                } else {
+                    a[(i, j)]
+                }
+            });
+            let qr = nalgebralib::linalg::QR::new(dm);
+            let mut r = qr.r().columns(0, n).into_owned();
+            for j in 0..n {
+                for i in (j + 1)..m {
+                    r[(i, j)] = 0.0;
+                }
+            }
+
+            let mut result = BTreeMap::new();
+            result.insert(
+                "q".into(),
+                Dynamic::from_array(omatrix_to_vec_dynamic(qr.q())),
+            );
+            result.insert("r".into(), Dynamic::from_array(omatrix_to_vec_dynamic(r)));
+            Ok(result)
+        })
+    }
+
+    /// Calculates the LU decomposition of a matrix with partial pivoting, such that
+    /// `P * A = L * U`. Returns an object map with the unit lower triangular `l`, the upper
+    /// triangular `u`, the permutation matrix `p`, and the pivot indices `perm`, where row `i` of
+    /// `L * U` is row `perm[i]` of `A`.
+    /// ```typescript
+    /// let result = lu([[0, 1], [1, 0]]);
+    /// assert_eq(result.perm, [1, 0]);
+    /// assert_eq(result.p, [[0.0, 1.0], [1.0, 0.0]]);
+    /// assert_eq(result.l, eye(2));
+    /// assert_eq(result.u, eye(2));
+    /// ```
+    /// ```typescript
+    /// let result = lu([[1, 2], [3, 4]]);
+    /// assert_eq(result.perm, [1, 0]);
+    /// assert_eq(result.l, [[1.0, 0.0], [1.0 / 3.0, 1.0]]);
+    /// assert_eq(result.u[0], [3.0, 4.0]);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "lu", return_raw, pure)]
+    pub fn lu_decomp(matrix: &mut Array) -> Result<Map, Box<EvalAltResult>> {
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+            let m = dm.nrows();
+
+            let lu = nalgebralib::linalg::LU::new(dm);
+            let mut p = DMatrix::<FLOAT>::identity(m, m);
+            lu.p().permute_rows(&mut p);
+            let perm = p
+                .row_iter()
+                .map(|row| Dynamic::from_int(row.iter().position(|x| *x == 1.0).unwrap() as INT))
+                .collect::<Array>();
+
+            let mut result = BTreeMap::new();
+            result.insert(
+                "l".into(),
+                Dynamic::from_array(omatrix_to_vec_dynamic(lu.l())),
+            );
+            result.insert(
+                "u".into(),
+                Dynamic::from_array(omatrix_to_vec_dynamic(lu.u())),
+            );
+            result.insert("p".into(), Dynamic::from_array(omatrix_to_vec_dynamic(p)));
+            result.insert("perm".into(), Dynamic::from_array(perm));
+            Ok(result)
+        })
+        .map_err(crate::in_function("lu", Some("matrix")))
     }
 
     /// Calculates the QR decomposition of a matrix
@@ -250,58 +2574,361 @@ pub mod matrix_functions {
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "hessenberg", return_raw, pure)]
     pub fn hessenberg(matrix: &mut Array) -> Result<Map, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            let dm = DMatrix::from_fn(matrix_as_vec.len(), matrix_as_vec[0].len(), |i, j| {
-                if matrix_as_vec[0][0].is::<FLOAT>() {
-                    matrix_as_vec[i][j].as_float().unwrap()
-                } else {
-                    matrix_as_vec[i][j].as_int().unwrap() as FLOAT
-                }
-            });
-
+        if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
             // Try ot invert
             let h = nalgebralib::linalg::Hessenberg::new(dm);
 
-            let mut result = BTreeMap::new();
-            let mut hid = smartstring::SmartString::new();
-            hid.push_str("h");
-            result.insert(hid, Dynamic::from_array(omatrix_to_vec_dynamic(h.h())));
+            let mut result = BTreeMap::new();
+            let mut hid = smartstring::SmartString::new();
+            hid.push_str("h");
+            result.insert(hid, Dynamic::from_array(omatrix_to_vec_dynamic(h.h())));
+
+            let mut qid = smartstring::SmartString::new();
+            qid.push_str("q");
+            result.insert(qid, Dynamic::from_array(omatrix_to_vec_dynamic(h.q())));
+
+            Ok(result)
+        })
+        .map_err(crate::in_function("hessenberg", Some("matrix")))
+    }
+
+    /// Transposes a matrix. It can also be written as the method `matrix.t()`, so that
+    /// `transpose(mtimes(transpose(a), b))` becomes `mtimes(a.t(), b).t()`.
+    /// ```typescript
+    /// let row = [[1, 2, 3, 4]];
+    /// let column = transpose(row);
+    /// assert_eq(column, [[1],
+    ///                    [2],
+    ///                    [3],
+    ///                    [4]]);
+    /// ```
+    /// ```typescript
+    /// let matrix = transpose(eye(3));
+    /// assert_eq(matrix, eye(3));
+    /// ```
+    /// ```typescript
+    /// let a = [[1, 2], [3, 4]];
+    /// assert_eq(a.t(), [[1, 3], [2, 4]]);
+    /// ```
+    #[rhai_fn(name = "transpose", name = "t", pure, return_raw)]
+    pub fn transpose(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        if !crate::validation_functions::is_matrix(matrix) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The input must be a matrix",
+            )
+            .in_function("transpose")
+            .with_argument("matrix")
+            .into());
+        }
+
+        // Read the rows in place, copying each element only once
+        let rows = matrix
+            .iter()
+            .map(|row| row.read_lock::<Array>().unwrap())
+            .collect::<Vec<_>>();
+        Ok((0..rows[0].len())
+            .map(|idx| Dynamic::from_array(rows.iter().map(|row| row[idx].clone()).collect()))
+            .collect())
+    }
+
+    /// Transposes a matrix in place, moving its elements rather than copying them. This is the
+    /// cheapest way to transpose a large matrix that is no longer needed in its original form.
+    /// ```typescript
+    /// let matrix = [[1, 2, 3],
+    ///               [4, 5, 6]];
+    /// matrix.transpose_in_place();
+    /// assert_eq(matrix, [[1, 4],
+    ///                    [2, 5],
+    ///                    [3, 6]]);
+    /// ```
+    #[rhai_fn(name = "transpose_in_place", return_raw)]
+    pub fn transpose_in_place(matrix: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        if !crate::validation_functions::is_matrix(matrix) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The input must be a matrix",
+            )
+            .in_function("transpose_in_place")
+            .with_argument("matrix")
+            .into());
+        }
+
+        let rows = std::mem::take(matrix)
+            .into_iter()
+            .map(|row| row.into_array().unwrap())
+            .collect::<Vec<Array>>();
+        let mut columns = vec![Array::with_capacity(rows.len()); rows[0].len()];
+        for row in rows {
+            for (column, x) in columns.iter_mut().zip(row) {
+                column.push(x);
+            }
+        }
+        *matrix = columns.into_iter().map(Dynamic::from_array).collect();
+        Ok(())
+    }
+
+    /// Sorts an array of numbers in ascending order in place, without allocating a new array.
+    /// NaNs go to the end. If the array mixes INTs and FLOATs, its INTs become FLOATs.
+    /// ```typescript
+    /// let x = [3, 1, 2];
+    /// x.sort_in_place();
+    /// assert_eq(x, [1, 2, 3]);
+    /// ```
+    /// ```typescript
+    /// let x = [2.5, 0.0/0.0, 1];
+    /// sort_in_place(x);
+    /// assert_eq(x.extract(0, 2), [1.0, 2.5]);
+    /// assert(x[2].is_nan());
+    /// ```
+    #[rhai_fn(name = "sort_in_place", return_raw)]
+    pub fn sort_in_place(arr: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        if !crate::validation_functions::is_numeric_list(arr) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The elements of the input array must either be INT or FLOAT",
+            )
+            .in_function("sort_in_place")
+            .with_argument("arr")
+            .into());
+        }
+
+        if arr.iter().all(|x| x.is_int()) {
+            arr.sort_by_key(|x| x.as_int().unwrap());
+        } else {
+            for x in arr.iter_mut().filter(|x| x.is_int()) {
+                *x = Dynamic::from_float(x.as_int().unwrap() as FLOAT);
+            }
+            arr.sort_by(|a, b| {
+                let (a, b) = (a.as_float().unwrap(), b.as_float().unwrap());
+                a.is_nan().cmp(&b.is_nan()).then(a.total_cmp(&b))
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns row `i` of a matrix as a 1-D array, reading it without copying the rest of the
+    /// matrix. Negative indices count from the end.
+    /// ```typescript
+    /// let matrix = [[1, 2],
+    ///               [3, 4]];
+    /// assert_eq(row(matrix, 1), [3, 4]);
+    /// assert_eq(matrix.row(-2), [1, 2]);
+    /// ```
+    #[rhai_fn(name = "row", return_raw, pure)]
+    pub fn row(matrix: &mut Array, i: INT) -> Result<Array, Box<EvalAltResult>> {
+        if !crate::validation_functions::is_matrix(matrix) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The input must be a matrix",
+            )
+            .in_function("row")
+            .with_argument("matrix")
+            .into());
+        }
+        let i = super::resolve_index(i, matrix.len())?;
+        Ok(matrix[i].read_lock::<Array>().unwrap().clone())
+    }
+
+    /// Returns column `j` of a matrix as a 1-D array, reading it without copying the rest of the
+    /// matrix. Negative indices count from the end.
+    /// ```typescript
+    /// let matrix = [[1, 2],
+    ///               [3, 4]];
+    /// assert_eq(col(matrix, 0), [1, 3]);
+    /// assert_eq(matrix.col(-1), [2, 4]);
+    /// ```
+    #[rhai_fn(name = "col", return_raw, pure)]
+    pub fn col(matrix: &mut Array, j: INT) -> Result<Array, Box<EvalAltResult>> {
+        if !crate::validation_functions::is_matrix(matrix) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The input must be a matrix",
+            )
+            .in_function("col")
+            .with_argument("matrix")
+            .into());
+        }
+        let columns = matrix[0].read_lock::<Array>().unwrap().len();
+        let j = super::resolve_index(j, columns)?;
+        Ok(matrix
+            .iter()
+            .map(|row| row.read_lock::<Array>().unwrap()[j].clone())
+            .collect())
+    }
 
-            let mut qid = smartstring::SmartString::new();
-            qid.push_str("q");
-            result.insert(qid, Dynamic::from_array(omatrix_to_vec_dynamic(h.q())));
+    /// Returns the elements of an array (or rows of a matrix) picked out by `rows`, which can be
+    /// an integer, a range such as `1..3` or `1..=2`, or an array of indices. Negative indices
+    /// count from the end, and ranges are clamped to the array.
+    /// ```typescript
+    /// let x = [10, 20, 30, 40, 50];
+    /// assert_eq(slice(x, 1..3), [20, 30]);
+    /// assert_eq(slice(x, [-1, 0]), [50, 10]);
+    /// ```
+    #[rhai_fn(name = "slice", return_raw, pure)]
+    pub fn slice_rows(matrix: &mut Array, rows: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        Ok(crate::selection_indices(&rows, matrix.len())?
+            .into_iter()
+            .map(|i| matrix[i].clone())
+            .collect())
+    }
 
-            Ok(result)
+    /// Returns the rectangular block of a matrix picked out by `rows` and `cols`, each of which
+    /// can be an integer, a range such as `1..3` or `1..=2`, or an array of indices. The result is
+    /// always a matrix, even if it has a single row or column.
+    /// ```typescript
+    /// let m = [[1, 2, 3],
+    ///          [4, 5, 6],
+    ///          [7, 8, 9]];
+    /// assert_eq(slice(m, 1..3, 0..2), [[4, 5],
+    ///                                  [7, 8]]);
+    /// assert_eq(slice(m, 0, [2, 0]), [[3, 1]]);
+    /// ```
+    #[rhai_fn(name = "slice", return_raw, pure)]
+    pub fn slice_block(
+        matrix: &mut Array,
+        rows: Dynamic,
+        cols: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if_matrix_do(matrix, |matrix| {
+            let n_cols = matrix[0].read_lock::<Array>().unwrap().len();
+            let rows = crate::selection_indices(&rows, matrix.len())?;
+            let cols = crate::selection_indices(&cols, n_cols)?;
+            Ok(rows
+                .iter()
+                .map(|i| {
+                    let row = matrix[*i].read_lock::<Array>().unwrap();
+                    Dynamic::from_array(cols.iter().map(|j| row[*j].clone()).collect())
+                })
+                .collect())
         })
+        .map_err(crate::in_function("slice", None))
     }
 
-    /// Transposes a matrix.
+    /// Replaces the elements of an array (or rows of a matrix) picked out by `rows`, in place,
+    /// with the elements of `block`, which must have one element per selected index, or with a
+    /// single value repeated.
     /// ```typescript
-    /// let row = [[1, 2, 3, 4]];
-    /// let column = transpose(row);
-    /// assert_eq(column, [[1],
-    ///                    [2],
-    ///                    [3],
-    ///                    [4]]);
+    /// let x = [1, 2, 3, 4, 5];
+    /// set_slice(x, 1..3, [20, 30]);
+    /// set_slice(x, -1, 0);
+    /// assert_eq(x, [1, 20, 30, 4, 0]);
     /// ```
+    #[rhai_fn(name = "set_slice", return_raw)]
+    pub fn set_slice_rows(
+        matrix: &mut Array,
+        rows: Dynamic,
+        block: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let rows = crate::selection_indices(&rows, matrix.len())?;
+        let values = match block.read_lock::<Array>() {
+            Some(block) if block.len() == rows.len() => block.clone(),
+            Some(block) => {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "{} indices are selected, but {} values were given",
+                        rows.len(),
+                        block.len()
+                    ),
+                )
+                .in_function("set_slice")
+                .into())
+            }
+            None => vec![block.clone(); rows.len()],
+        };
+        for (i, value) in rows.into_iter().zip(values) {
+            matrix[i] = value;
+        }
+        Ok(())
+    }
+
+    /// Replaces the rectangular block of a matrix picked out by `rows` and `cols`, in place, with
+    /// `block`. The block must be a matrix with one row per selected row and one column per
+    /// selected column, or a single value to fill the block with. A vector can stand in for a
+    /// block with a single row or column.
     /// ```typescript
-    /// let matrix = transpose(eye(3));
-    /// assert_eq(matrix, eye(3));
+    /// let m = zeros(3, 3);
+    /// set_slice(m, 0..2, 1..3, [[1.0, 2.0], [3.0, 4.0]]);
+    /// set_slice(m, 2, 0..3, 9.0);
+    /// assert_eq(m, [[0.0, 1.0, 2.0],
+    ///               [0.0, 3.0, 4.0],
+    ///               [9.0, 9.0, 9.0]]);
     /// ```
-    #[rhai_fn(name = "transpose", pure, return_raw)]
-    pub fn transpose(matrix: &mut Array) -> Result<Array, Box<EvalAltResult>> {
-        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
-            // Turn into Array
-            let mut out = vec![];
-            for idx in 0..matrix_as_vec[0].len() {
-                let mut new_row = vec![];
-                for jdx in 0..matrix_as_vec.len() {
-                    new_row.push(matrix_as_vec[jdx][idx].clone());
-                }
-                out.push(Dynamic::from_array(new_row));
+    /// ```typescript
+    /// let m = [[1, 2], [3, 4]];
+    /// set_slice(m, 0..2, 0, [10, 30]);
+    /// assert_eq(m, [[10, 2], [30, 4]]);
+    /// ```
+    #[rhai_fn(name = "set_slice", return_raw)]
+    pub fn set_slice_block(
+        matrix: &mut Array,
+        rows: Dynamic,
+        cols: Dynamic,
+        block: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        if !crate::validation_functions::is_matrix(matrix) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The input must be a matrix",
+            )
+            .in_function("set_slice")
+            .with_argument("matrix")
+            .into());
+        }
+        let n_cols = matrix[0].read_lock::<Array>().unwrap().len();
+        let rows = crate::selection_indices(&rows, matrix.len())?;
+        let cols = crate::selection_indices(&cols, n_cols)?;
+
+        // Arrange the values as one array per selected row
+        let values = if block.is_array() {
+            let block = block.into_array().unwrap();
+            let block = if super::block_rank(&Dynamic::from_array(block.clone())) == 1
+                && cols.len() == 1
+                && block.len() == rows.len()
+            {
+                block
+                    .into_iter()
+                    .map(|x| Dynamic::from_array(vec![x]))
+                    .collect()
+            } else {
+                super::promote_block(Dynamic::from_array(block), 2)
+                    .into_array()
+                    .unwrap()
+            };
+            let shape = matrix_size_by_reference(&mut block.clone());
+            if block.len() != rows.len()
+                || block
+                    .iter()
+                    .any(|r| r.read_lock::<Array>().is_none_or(|r| r.len() != cols.len()))
+            {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "The block has size {:?}, but {} rows and {} columns are selected",
+                        shape,
+                        rows.len(),
+                        cols.len()
+                    ),
+                )
+                .in_function("set_slice")
+                .into());
             }
-            Ok(out)
-        })
+            block
+                .into_iter()
+                .map(|r| r.into_array().unwrap())
+                .collect::<Vec<_>>()
+        } else {
+            vec![vec![block; cols.len()]; rows.len()]
+        };
+
+        for (i, row_values) in rows.into_iter().zip(values) {
+            let mut row = matrix[i].write_lock::<Array>().unwrap();
+            for (j, value) in cols.iter().zip(row_values) {
+                row[*j] = value;
+            }
+        }
+        Ok(())
     }
 
     /// Returns an array indicating the size of the matrix along each dimension, passed by reference.
@@ -315,16 +2942,12 @@ pub mod matrix_functions {
     /// ```
     #[rhai_fn(name = "size", pure)]
     pub fn matrix_size_by_reference(matrix: &mut Array) -> Array {
-        let mut new_matrix = matrix.clone();
-
-        let mut shape = vec![Dynamic::from_int(new_matrix.len() as INT)];
-        loop {
-            if new_matrix[0].is_array() {
-                new_matrix = new_matrix[0].clone().into_array().unwrap();
-                shape.push(Dynamic::from_int(new_matrix.len() as INT));
-            } else {
-                break;
-            }
+        // Walk down the first element of each level, copying only those
+        let mut shape = vec![Dynamic::from_int(matrix.len() as INT)];
+        let mut first = matrix.first().cloned();
+        while let Some(Ok(inner)) = first.map(|x| x.into_array()) {
+            shape.push(Dynamic::from_int(inner.len() as INT));
+            first = inner.into_iter().next();
         }
 
         shape
@@ -354,13 +2977,13 @@ pub mod matrix_functions {
     /// ```
     #[rhai_fn(name = "numel", pure)]
     pub fn numel_by_reference(matrix: &mut Array) -> INT {
-        flatten(matrix).len() as INT
+        super::count_elements(matrix) as INT
     }
 
     #[cfg(all(feature = "io"))]
     pub mod read_write {
         use polars::prelude::{CsvReader, DataType, SerReader};
-        use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, FLOAT};
+        use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, NativeCallContext, FLOAT};
 
         /// Reads a numeric csv file from a url
         /// ```typescript
@@ -369,7 +2992,10 @@ pub mod matrix_functions {
         /// assert_eq(size(x), [768, 9]);
         /// ```
         #[rhai_fn(name = "read_matrix", return_raw)]
-        pub fn read_matrix(file_path: ImmutableString) -> Result<Array, Box<EvalAltResult>> {
+        pub fn read_matrix(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<Array, Box<EvalAltResult>> {
             // We will use this function later
             fn transpose_internal<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
                 assert!(!v.is_empty());
@@ -419,6 +3045,8 @@ pub mod matrix_functions {
                             )
                         })?;
 
+                    crate::check_array_shape(&ctx, &[x.height(), x.width()])?;
+
                     // Convert into vec of vec
 
                     let mut final_output = vec![];
@@ -426,10 +3054,10 @@ pub mod matrix_functions {
                         let col: Vec<FLOAT> = series
                             .cast(&DataType::Float64)
                             .map_err(|err| {
-                                EvalAltResult::ErrorArithmetic(
+                                rhai::EvalAltResult::from(crate::LabError::new(
+                                    crate::LabErrorKind::TypeMismatch,
                                     format!("Data cannot be cast to FLOAT: {err}"),
-                                    rhai::Position::NONE,
-                                )
+                                ))
                             })?
                             .f64()
                             .unwrap()
@@ -456,18 +3084,12 @@ pub mod matrix_functions {
                 }
                 Err(_) => {
                     if let Ok(_) = url::Url::parse(file_path_as_str) {
-                        let file_contents =
-                            minreq::get(file_path_as_str).send().map_err(|err| {
-                                EvalAltResult::ErrorSystem(
-                                    format!("Error getting url: {file_path_as_str}"),
-                                    err.into(),
-                                )
-                            })?;
-                        let temp = temp_file::with_contents(file_contents.as_bytes());
+                        let file_contents = super::super::fetch_url(file_path_as_str)?;
+                        let temp = temp_file::with_contents(&file_contents);
 
                         let temp_file_name: ImmutableString = temp.path().to_str().unwrap().into();
 
-                        read_matrix(temp_file_name)
+                        read_matrix(ctx, temp_file_name)
                     } else {
                         EvalAltResult::ErrorRuntime(
                             format!(
@@ -481,10 +3103,504 @@ pub mod matrix_functions {
                 }
             }
         }
+
+        /// Reads a numeric CSV (or other delimited) file from a path or url, configured by a map
+        /// of options:
+        /// * `delimiter`: the character between fields, guessed from `,`, tab, `;`, and `|` by
+        ///   default.
+        /// * `header`: whether the first row holds column names, guessed by default from whether
+        ///   it contains text.
+        /// * `columns`: an array of column names or (zero-based) indices to read, in order.
+        /// * `quote`: the character around fields that contain delimiters or line breaks, `"` by
+        ///   default, or `""` for none. Quotes inside a quoted field are doubled.
+        /// * `comment`: a character that marks lines to skip when it starts them.
+        /// * `missing`: how to treat blank, `NA`, and `null` values: `"drop"` (the default) skips
+        ///   their rows, `"nan"` reads them as `NaN`, and `"error"` fails.
+        /// * `names`: if `true`, returns a map with the column `names` and the `data` matrix
+        ///   instead of just the matrix. Columns without a header are named `Var1`, `Var2`, and
+        ///   so on.
+        /// ```typescript
+        /// let path = temp_path("diabetes-columns.csv");
+        /// let header = ["Pregnancies", "Glucose", "BMI", "Outcome"];
+        /// write_matrix([[6, 148, 33.5, 1], [1, 85, 26.5, 0]], path, #{header: header});
+        /// let x = read_matrix(path, #{columns: ["Glucose", "BMI"]});
+        /// assert_eq(x, [[148.0, 33.5], [85.0, 26.5]]);
+        /// ```
+        /// ```typescript
+        /// let path = temp_path("diabetes-names.csv");
+        /// let header = ["Pregnancies", "Glucose", "BMI", "Outcome"];
+        /// write_matrix([[6, 148, 33.5, 1], [1, 85, 26.5, 0]], path, #{header: header});
+        /// let table = read_matrix(path, #{names: true, columns: [0, 3]});
+        /// assert_eq(table.names, ["Pregnancies", "Outcome"]);
+        /// assert_eq(table.data, [[6.0, 1.0], [1.0, 0.0]]);
+        /// ```
+        #[rhai_fn(name = "read_matrix", return_raw)]
+        pub fn read_matrix_with_options(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+            options: rhai::Map,
+        ) -> Result<Dynamic, Box<EvalAltResult>> {
+            let text = super::super::read_text(&file_path)?;
+            let (names, data) = super::super::parse_delimited(&text, &options)?;
+            crate::check_array_shape(&ctx, &[data.len(), names.len()])?;
+            let data = data
+                .into_iter()
+                .map(|row| Dynamic::from_array(row.into_iter().map(Dynamic::from_float).collect()))
+                .collect::<Array>();
+            let with_names = match options.get("names") {
+                Some(n) => n.as_bool().map_err(|_| {
+                    rhai::EvalAltResult::from(crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "The names option must be true or false",
+                    ))
+                })?,
+                None => false,
+            };
+            if with_names {
+                let mut result = rhai::Map::new();
+                result.insert(
+                    "names".into(),
+                    Dynamic::from_array(names.into_iter().map(Dynamic::from).collect()),
+                );
+                result.insert("data".into(), Dynamic::from_array(data));
+                Ok(Dynamic::from_map(result))
+            } else {
+                Ok(Dynamic::from_array(data))
+            }
+        }
+
+        /// Reads a CSV (or other delimited) file with a header row from a path or url into a
+        /// `Table`. Each column becomes integers, floats, booleans, or strings, depending on what
+        /// all of its values can be read as, and missing values in columns of numbers become
+        /// `NaN`. With the `parquet` feature, a local file ending in `.parquet` is read as an
+        /// Apache Parquet file instead.
+        /// ```typescript
+        /// let path = temp_path("scores.csv");
+        /// write_matrix([[1, 2.5], [2, 3.5]], path, #{header: ["id", "score"]});
+        /// let t = read_table(path);
+        /// assert_eq(t.names, ["id", "score"]);
+        /// assert_eq(t["id"], [1, 2]);
+        /// ```
+        #[cfg(feature = "data")]
+        #[rhai_fn(name = "read_table", return_raw)]
+        pub fn read_table(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<crate::Table, Box<EvalAltResult>> {
+            read_table_with_options(ctx, file_path, rhai::Map::new())
+        }
+
+        /// Reads a CSV (or other delimited) file with a header row from a path or url into a
+        /// `Table`, configured by a map of options:
+        /// * `delimiter`: the character between fields, guessed from `,`, tab, `;`, and `|` by
+        ///   default.
+        /// * `quote`: the character that quotes fields, `"` by default. An empty string turns
+        ///   quoting off.
+        /// * `comment`: lines starting with this character are skipped. None by default.
+        /// ```typescript
+        /// let path = temp_path("pairs.txt");
+        /// write_matrix([[1, 2], [3, 4]], path, #{delimiter: ";", header: ["a", "b"]});
+        /// let t = read_table(path, #{delimiter: ";"});
+        /// assert_eq(t["b"], [2, 4]);
+        /// ```
+        #[cfg(feature = "data")]
+        #[rhai_fn(name = "read_table", return_raw)]
+        pub fn read_table_with_options(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+            options: rhai::Map,
+        ) -> Result<crate::Table, Box<EvalAltResult>> {
+            #[cfg(feature = "parquet")]
+            if file_path.to_lowercase().ends_with(".parquet") {
+                let df = super::super::read_parquet_frame(&file_path)?;
+                crate::check_array_shape(&ctx, &[df.width(), df.height()])?;
+                return crate::Table::try_from(&df)
+                    .map_err(|err| err.in_function("read_table").into());
+            }
+            let text = super::super::read_text(&file_path)?;
+            let table = super::super::parse_table(&text, &options)?;
+            crate::check_array_shape(&ctx, &[table.ncols(), table.nrows()])?;
+            Ok(table)
+        }
+
+        /// Returns the path of a file with the given name in the system's temporary directory,
+        /// for scratch files that should not clutter the working directory.
+        /// ```typescript
+        /// let path = temp_path("scratch.csv");
+        /// assert(path.ends_with("scratch.csv"));
+        /// ```
+        #[rhai_fn(name = "temp_path")]
+        pub fn temp_path(name: ImmutableString) -> ImmutableString {
+            std::env::temp_dir()
+                .join(name.as_str())
+                .to_string_lossy()
+                .into_owned()
+                .into()
+        }
+
+        /// Writes a matrix (or a single row) to a CSV file. Numbers are written with as many
+        /// digits as needed to read them back exactly.
+        /// ```typescript
+        /// let m = [[1, 2.5], [3, -4]];
+        /// let path = temp_path("matrix.csv");
+        /// write_matrix(m, path);
+        /// assert_eq(read_matrix(path), [[1.0, 2.5], [3.0, -4.0]]);
+        /// ```
+        #[rhai_fn(name = "write_matrix", return_raw)]
+        pub fn write_matrix(
+            matrix: Array,
+            file_path: ImmutableString,
+        ) -> Result<(), Box<EvalAltResult>> {
+            write_matrix_with_options(matrix, file_path, rhai::Map::new())
+        }
+
+        /// Writes a matrix (or a single row) to a CSV (or other delimited) file, configured by a
+        /// map of options:
+        /// * `delimiter`: the character between fields, `,` by default.
+        /// * `header`: an array of column names to write as the first row.
+        /// * `precision`: the number of digits to write after the decimal point of floats.
+        /// * `scientific`: if `true`, writes floats in scientific notation, such as `1.5e3`.
+        ///
+        /// Fields that contain the delimiter, quotes, or line breaks are quoted.
+        /// ```typescript
+        /// let m = [[1.0, 2.25], [3.0, 4.5]];
+        /// let path = temp_path("matrix.tsv");
+        /// write_matrix(m, path, #{delimiter: "\t", header: ["x", "y"], precision: 1});
+        /// let table = read_matrix(path, #{names: true});
+        /// assert_eq(table.names, ["x", "y"]);
+        /// assert_eq(table.data, [[1.0, 2.2], [3.0, 4.5]]);
+        /// ```
+        #[rhai_fn(name = "write_matrix", return_raw)]
+        pub fn write_matrix_with_options(
+            matrix: Array,
+            file_path: ImmutableString,
+            options: rhai::Map,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let text = super::super::format_delimited(matrix, &options)?;
+            std::fs::write(file_path.as_str(), text).map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+                    .into()
+            })
+        }
+
+        /// Reads a JSON file from a path or url. Arrays (including arrays of arrays, such as
+        /// matrices) become arrays, objects become maps, and `null` becomes `()`. Whole numbers
+        /// become integers and other numbers become floats.
+        /// ```typescript
+        /// let path = temp_path("data.json");
+        /// write_json(path, #{name: "run 1", x: [[1, 2], [3, 4]], scale: 0.5});
+        /// let data = read_json(path);
+        /// assert_eq(data.name, "run 1");
+        /// assert_eq(data.x, [[1, 2], [3, 4]]);
+        /// assert_eq(data.scale, 0.5);
+        /// ```
+        #[rhai_fn(name = "read_json", return_raw)]
+        pub fn read_json(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<Dynamic, Box<EvalAltResult>> {
+            let text = super::super::read_text(&file_path)?;
+            let value = serde_json::from_str(&text).map_err(|err| {
+                EvalAltResult::ErrorSystem(
+                    format!("Cannot read file as JSON: {file_path}"),
+                    err.into(),
+                )
+            })?;
+            let (arrays, maps) = super::super::json_data_size(&value);
+            crate::check_data_size(&ctx, arrays, maps)?;
+            Ok(super::super::json_to_dynamic(value))
+        }
+
+        /// Writes a value to a JSON file. Arrays (including matrices) become JSON arrays, maps
+        /// become objects, and `()` becomes `null`. Fails for values that JSON cannot hold, such
+        /// as `NaN` and custom types.
+        /// ```typescript
+        /// let m = [[1.0, 2.0], [3.0, 4.0]];
+        /// let path = temp_path("matrix.json");
+        /// write_json(path, m);
+        /// assert_eq(read_json(path), m);
+        /// ```
+        #[rhai_fn(name = "write_json", return_raw)]
+        pub fn write_json(
+            file_path: ImmutableString,
+            value: Dynamic,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let json = super::super::dynamic_to_json(&value)?;
+            let text = serde_json::to_string_pretty(&json).map_err(|err| {
+                EvalAltResult::ErrorSystem("Cannot convert value to JSON".to_string(), err.into())
+            })?;
+            std::fs::write(file_path.as_str(), text + "\n").map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+                    .into()
+            })
+        }
+
+        /// Reads a NumPy `.npy` file from a path or url. Integer and boolean arrays keep their
+        /// type, floating point arrays become floats, and zero-dimensional arrays become a single
+        /// value.
+        /// ```typescript
+        /// let m = [[1.0, 2.5, 3.0], [4.0, 5.0, -6.5]];
+        /// let path = temp_path("matrix.npy");
+        /// write_npy(path, m);
+        /// assert_eq(read_npy(path), m);
+        /// ```
+        #[rhai_fn(name = "read_npy", return_raw)]
+        pub fn read_npy(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<Dynamic, Box<EvalAltResult>> {
+            super::super::decode_npy(&ctx, &super::super::read_bytes(&file_path)?)
+        }
+
+        /// Writes a rectangular array (such as a matrix) to a NumPy `.npy` file, which can be
+        /// loaded in Python with `numpy.load`. Arrays of integers are written as `int64`, arrays
+        /// of booleans as `bool`, and anything else as `float64`.
+        /// ```typescript
+        /// let path = temp_path("counts.npy");
+        /// write_npy(path, [[1, 2], [3, 4], [5, 6]]);
+        /// let counts = read_npy(path);
+        /// assert_eq(counts, [[1, 2], [3, 4], [5, 6]]);
+        /// ```
+        #[rhai_fn(name = "write_npy", return_raw)]
+        pub fn write_npy(
+            file_path: ImmutableString,
+            matrix: Array,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let bytes = super::super::encode_npy(&matrix)?;
+            std::fs::write(file_path.as_str(), bytes).map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+                    .into()
+            })
+        }
+
+        /// Reads a NumPy `.npz` archive from a path or url into a map from array names to arrays.
+        /// Archives written by `numpy.savez` can be read, but compressed archives written by
+        /// `numpy.savez_compressed` cannot.
+        /// ```typescript
+        /// let path = temp_path("arrays.npz");
+        /// write_npz(path, #{x: [1.0, 2.0, 3.0], y: [[1, 0], [0, 1]]});
+        /// let arrays = read_npz(path);
+        /// assert_eq(arrays.x, [1.0, 2.0, 3.0]);
+        /// assert_eq(arrays.y, [[1, 0], [0, 1]]);
+        /// ```
+        #[rhai_fn(name = "read_npz", return_raw)]
+        pub fn read_npz(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<rhai::Map, Box<EvalAltResult>> {
+            let files = super::super::read_zip(&super::super::read_bytes(&file_path)?)?;
+            crate::check_data_size(&ctx, 0, files.len())?;
+            files
+                .into_iter()
+                .map(|(name, bytes)| {
+                    let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+                    super::super::decode_npy(&ctx, &bytes).map(|value| (name.into(), value))
+                })
+                .collect()
+        }
+
+        /// Writes a map of arrays to a NumPy `.npz` archive, which can be loaded in Python with
+        /// `numpy.load`. Each array is stored under its key, as with `numpy.savez`.
+        /// ```typescript
+        /// let path = temp_path("fit.npz");
+        /// write_npz(path, #{coefficients: [0.5, -1.25], residuals: [0.0, 0.25, -0.25]});
+        /// assert_eq(read_npz(path).coefficients, [0.5, -1.25]);
+        /// ```
+        #[rhai_fn(name = "write_npz", return_raw)]
+        pub fn write_npz(
+            file_path: ImmutableString,
+            arrays: rhai::Map,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let files = arrays
+                .into_iter()
+                .map(|(name, value)| match value.into_array() {
+                    Ok(arr) => super::super::encode_npy(&arr).map(|bytes| (format!("{name}.npy"), bytes)),
+                    Err(type_name) => Err(crate::LabError::new(crate::LabErrorKind::TypeMismatch, format!("Only arrays can be written to .npz files, but '{name}' is a {type_name}"))
+                    .into()),
+                })
+                .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?;
+            std::fs::write(file_path.as_str(), super::super::write_zip(&files)).map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+                    .into()
+            })
+        }
+
+        /// Reads an Apache Parquet file into a map with the column `names` and the `data` as an
+        /// array of rows. Integer, float, boolean and string columns keep their types, and missing
+        /// values become `()`. Requires the `parquet` feature.
+        /// ```typescript
+        /// let path = temp_path("table.parquet");
+        /// write_parquet(path, #{names: ["id", "score"], data: [[1, 0.5], [2, 0.75]]});
+        /// let table = read_parquet(path);
+        /// assert_eq(table.names, ["id", "score"]);
+        /// assert_eq(table.data, [[1, 0.5], [2, 0.75]]);
+        /// ```
+        #[cfg(feature = "parquet")]
+        #[rhai_fn(name = "read_parquet", return_raw)]
+        pub fn read_parquet(
+            ctx: NativeCallContext,
+            file_path: ImmutableString,
+        ) -> Result<rhai::Map, Box<EvalAltResult>> {
+            let df = super::super::read_parquet_frame(&file_path)?;
+            // The column names count towards the limit along with the rows of data
+            let arrays = crate::nested_array_size(&[df.height(), df.width()]);
+            crate::check_data_size(&ctx, arrays.saturating_add(df.width()), 2)?;
+            super::super::dataframe_to_table(&df)
+        }
+
+        /// Writes a table to an Apache Parquet file. The table can be a `Table`, a map with an
+        /// array of column `names` and the `data` as an array of rows, or a matrix, whose columns
+        /// are named `Var1`, `Var2`, and so on. Each column must hold only numbers, only booleans,
+        /// or only strings, and `()` (or `NaN` in a `Table`) is written as a missing value.
+        /// Requires the `parquet` feature.
+        /// ```typescript
+        /// let path = temp_path("matrix.parquet");
+        /// write_parquet(path, [[1.0, 2.0], [3.0, 4.0]]);
+        /// let table = read_parquet(path);
+        /// assert_eq(table.names, ["Var1", "Var2"]);
+        /// assert_eq(table.data, [[1.0, 2.0], [3.0, 4.0]]);
+        /// ```
+        /// ```typescript
+        /// let path = temp_path("cities.parquet");
+        /// write_parquet(path, table(["city", "temp"], [["Oslo", 4.5], ["Lima", 19]]));
+        /// let t = read_table(path);
+        /// assert_eq(t.names, ["city", "temp"]);
+        /// assert_eq(t["temp"], [4.5, 19.0]);
+        /// ```
+        #[cfg(feature = "parquet")]
+        #[rhai_fn(name = "write_parquet", return_raw)]
+        pub fn write_parquet(
+            file_path: ImmutableString,
+            table: Dynamic,
+        ) -> Result<(), Box<EvalAltResult>> {
+            use polars::prelude::ParquetWriter;
+            let mut df = super::super::table_to_dataframe(table)?;
+            let file = std::fs::File::create(file_path.as_str()).map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+            })?;
+            ParquetWriter::new(file).finish(&mut df).map_err(|err| {
+                EvalAltResult::ErrorSystem(
+                    format!("Cannot write file as Parquet: {file_path}"),
+                    err.into(),
+                )
+            })?;
+            Ok(())
+        }
+
+        /// Caches the contents of URLs read by `read_matrix`, `read_json`, `read_npy`, `read_npz`,
+        /// and `download` in a directory on disk, so repeated runs of a script don't fetch the
+        /// same data again. Cached copies never expire, and the cache lives in a `rhai-sci-cache`
+        /// folder in the system's temporary directory. Use `enable_url_cache(options)` to change
+        /// these settings, and `disable_url_cache` to turn caching off.
+        /// ```typescript
+        /// enable_url_cache();
+        /// disable_url_cache();
+        /// true
+        /// ```
+        #[rhai_fn(name = "enable_url_cache", return_raw)]
+        pub fn enable_url_cache() -> Result<(), Box<EvalAltResult>> {
+            super::super::set_url_cache(&rhai::Map::new())
+        }
+
+        /// Caches the contents of URLs read by `read_matrix`, `read_json`, `read_npy`, `read_npz`,
+        /// and `download` in a directory on disk, configured by a map of options:
+        /// * `dir`: the cache directory, created if it does not exist. Defaults to a
+        ///   `rhai-sci-cache` folder in the system's temporary directory.
+        /// * `ttl`: how many seconds a cached copy stays fresh before it is fetched again. Cached
+        ///   copies never expire by default.
+        /// * `offline`: if `true`, never uses the network. Cached copies are used however old they
+        ///   are, and reading a URL that is not in the cache is an error. Useful for CI runs.
+        /// ```typescript
+        /// enable_url_cache(#{dir: temp_path("url-cache"), offline: true});
+        /// let result = "no error";
+        /// try {
+        ///     read_json("https://example.com/not-cached.json");
+        /// } catch {
+        ///     result = "error";
+        /// }
+        /// disable_url_cache();
+        /// assert_eq(result, "error");
+        /// ```
+        #[rhai_fn(name = "enable_url_cache", return_raw)]
+        pub fn enable_url_cache_with_options(options: rhai::Map) -> Result<(), Box<EvalAltResult>> {
+            super::super::set_url_cache(&options)
+        }
+
+        /// Turns off the URL cache enabled by `enable_url_cache`, so URLs are always fetched from
+        /// the network. Cached copies are left on disk.
+        /// ```typescript
+        /// enable_url_cache(#{ttl: 3600});
+        /// disable_url_cache();
+        /// true
+        /// ```
+        #[rhai_fn(name = "disable_url_cache")]
+        pub fn disable_url_cache() {
+            super::super::clear_url_cache_settings();
+        }
+
+        /// Downloads a URL to a file, going through the URL cache if one is enabled with
+        /// `enable_url_cache`. This example needs the network, so it is not run as a test.
+        /// ```typescript,no_run
+        /// let url = "https://raw.githubusercontent.com/plotly/datasets/master/diabetes.csv";
+        /// download(url, "diabetes.csv");
+        /// assert_eq(size(read_matrix("diabetes.csv")), [768, 9]);
+        /// ```
+        #[rhai_fn(name = "download", return_raw)]
+        pub fn download(
+            url: ImmutableString,
+            file_path: ImmutableString,
+        ) -> Result<(), Box<EvalAltResult>> {
+            let bytes = super::super::fetch_url(&url)?;
+            std::fs::write(file_path.as_str(), bytes).map_err(|err| {
+                EvalAltResult::ErrorSystem(format!("Cannot write file: {file_path}"), err.into())
+                    .into()
+            })
+        }
+    }
+
+    /// Reads a dataset from an HDF5 file, such as a simulation output or an instrument log, into
+    /// a matrix (or an array with one level per dimension). Integer and boolean datasets keep
+    /// their types, other numeric datasets become floats, and scalar datasets become a single
+    /// value. Datasets inside groups are named by their path, such as `"results/pressure"`.
+    /// Requires the `hdf5` feature.
+    /// ```typescript
+    /// let path = temp_path("run.h5");
+    /// write_hdf5(path, "pressure", [[101.3, 101.1], [100.9, 100.8]]);
+    /// let p = read_hdf5(path, "pressure");
+    /// assert_eq(p, [[101.3, 101.1], [100.9, 100.8]]);
+    /// ```
+    #[cfg(feature = "hdf5")]
+    #[rhai_fn(name = "read_hdf5", return_raw)]
+    pub fn read_hdf5(
+        ctx: NativeCallContext,
+        file_path: rhai::ImmutableString,
+        dataset: rhai::ImmutableString,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::read_hdf5_dataset(&ctx, &file_path, &dataset)
+    }
+
+    /// Writes a rectangular array (such as a matrix) to a new dataset in an HDF5 file, creating
+    /// the file if it does not exist. Arrays of integers are stored as 64-bit integers, arrays of
+    /// booleans as booleans, and anything else as 64-bit floats. Requires the `hdf5` feature.
+    /// ```typescript
+    /// let path = temp_path("counts.h5");
+    /// write_hdf5(path, "counts", [1, 2, 3]);
+    /// assert_eq(read_hdf5(path, "counts"), [1, 2, 3]);
+    /// ```
+    #[cfg(feature = "hdf5")]
+    #[rhai_fn(name = "write_hdf5", return_raw)]
+    pub fn write_hdf5(
+        file_path: rhai::ImmutableString,
+        dataset: rhai::ImmutableString,
+        matrix: Array,
+    ) -> Result<(), Box<EvalAltResult>> {
+        super::write_hdf5_dataset(&file_path, &dataset, &matrix)
     }
 
     /// Return a matrix of zeros. Can be called with a single integer argument (indicating the
     /// square matrix of that size) or with an array argument (indicating the size for each dimension).
+    /// Fails, rather than allocating, if the result would exceed the engine's `max_array_size`, as do
+    /// the other functions that build arrays, such as `ones`, `rand`, `linspace`, and `repmat`.
     /// ```typescript
     /// let matrix = zeros(3);
     /// assert_eq(matrix, [[0.0, 0.0, 0.0],
@@ -513,30 +3629,21 @@ pub mod matrix_functions {
     ///                     [0.0, 0.0, 0.0],
     ///                    [0.0, 0.0, 0.0]]]);
     /// ```
+    /// ```typescript
+    /// let grid = zeros([2, 3, 4, 5]);
+    /// assert_eq(size(grid), [2, 3, 4, 5]);
+    /// ```
     #[rhai_fn(name = "zeros", return_raw)]
-    pub fn zeros_single_input(n: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+    pub fn zeros_single_input(
+        ctx: NativeCallContext,
+        n: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
         if_int_do_else_if_array_do(
             n,
-            |n| Ok(zeros_double_input(n, n)),
-            |m| {
-                if m.len() == 2 {
-                    Ok(zeros_double_input(
-                        m[0].as_int().unwrap(),
-                        m[1].as_int().unwrap(),
-                    ))
-                } else if m.len() > 2 {
-                    let l = m.remove(0);
-                    Ok(vec![
-                        Dynamic::from_array(
-                            zeros_single_input(Dynamic::from_array(m.to_vec())).unwrap()
-                        );
-                        l.as_int().unwrap() as usize
-                    ])
-                } else {
-                    Ok(vec![Dynamic::FLOAT_ZERO; m[0].as_int().unwrap() as usize])
-                }
-            },
+            |n| super::filled_array(&ctx, &[n.into(), n.into()], || Dynamic::FLOAT_ZERO),
+            |m| super::filled_array(&ctx, m, || Dynamic::FLOAT_ZERO),
         )
+        .map_err(crate::in_function("zeros", Some("n")))
     }
 
     /// Return a matrix of zeros. Arguments indicate the number of rows and columns in the matrix.
@@ -546,13 +3653,35 @@ pub mod matrix_functions {
     ///                    [0.0, 0.0, 0.0],
     ///                    [0.0, 0.0, 0.0]]);
     /// ```
-    #[rhai_fn(name = "zeros")]
-    pub fn zeros_double_input(nx: INT, ny: INT) -> Array {
-        let mut output = vec![];
-        for _ in 0..nx {
-            output.push(Dynamic::from_array(vec![Dynamic::FLOAT_ZERO; ny as usize]))
-        }
-        output
+    #[rhai_fn(name = "zeros", return_raw)]
+    pub fn zeros_double_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::filled_array(&ctx, &[nx.into(), ny.into()], || Dynamic::FLOAT_ZERO)
+    }
+
+    /// Return a three-dimensional array of zeros. Arguments indicate the size of each dimension.
+    /// ```typescript
+    /// let volume = zeros(2, 3, 4);
+    /// assert_eq(size(volume), [2, 3, 4]);
+    /// ```
+    /// ```typescript
+    /// let volume = zeros(2, 1, 2);
+    /// assert_eq(volume, [[[0.0, 0.0]], [[0.0, 0.0]]]);
+    /// ```
+    #[rhai_fn(name = "zeros", return_raw)]
+    pub fn zeros_triple_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+        nz: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        zeros_single_input(
+            ctx,
+            Dynamic::from_array(vec![nx.into(), ny.into(), nz.into()]),
+        )
     }
 
     /// Return a matrix of ones. Can be called with a single integer argument (indicating the
@@ -586,29 +3715,16 @@ pub mod matrix_functions {
     ///                     [1.0, 1.0, 1.0]]]);
     /// ```
     #[rhai_fn(name = "ones", return_raw)]
-    pub fn ones_single_input(n: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+    pub fn ones_single_input(
+        ctx: NativeCallContext,
+        n: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
         crate::if_int_do_else_if_array_do(
             n,
-            |n| Ok(ones_double_input(n, n)),
-            |m| {
-                if m.len() == 2 {
-                    Ok(ones_double_input(
-                        m[0].as_int().unwrap(),
-                        m[1].as_int().unwrap(),
-                    ))
-                } else if m.len() > 2 {
-                    let l = m.remove(0);
-                    Ok(vec![
-                        Dynamic::from_array(
-                            ones_single_input(Dynamic::from_array(m.to_vec())).unwrap()
-                        );
-                        l.as_int().unwrap() as usize
-                    ])
-                } else {
-                    Ok(vec![Dynamic::FLOAT_ONE; m[0].as_int().unwrap() as usize])
-                }
-            },
+            |n| super::filled_array(&ctx, &[n.into(), n.into()], || Dynamic::FLOAT_ONE),
+            |m| super::filled_array(&ctx, m, || Dynamic::FLOAT_ONE),
         )
+        .map_err(crate::in_function("ones", Some("n")))
     }
 
     /// Return a matrix of ones. Arguments indicate the number of rows and columns in the matrix.
@@ -618,13 +3734,35 @@ pub mod matrix_functions {
     ///                    [1.0, 1.0, 1.0],
     ///                    [1.0, 1.0, 1.0]]);
     /// ```
-    #[rhai_fn(name = "ones")]
-    pub fn ones_double_input(nx: INT, ny: INT) -> Array {
-        let mut output = vec![];
-        for _ in 0..nx {
-            output.push(Dynamic::from_array(vec![Dynamic::FLOAT_ONE; ny as usize]))
-        }
-        output
+    #[rhai_fn(name = "ones", return_raw)]
+    pub fn ones_double_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::filled_array(&ctx, &[nx.into(), ny.into()], || Dynamic::FLOAT_ONE)
+    }
+
+    /// Return a three-dimensional array of ones. Arguments indicate the size of each dimension.
+    /// ```typescript
+    /// let volume = ones(2, 3, 4);
+    /// assert_eq(size(volume), [2, 3, 4]);
+    /// ```
+    /// ```typescript
+    /// let volume = ones(2, 1, 2);
+    /// assert_eq(volume, [[[1.0, 1.0]], [[1.0, 1.0]]]);
+    /// ```
+    #[rhai_fn(name = "ones", return_raw)]
+    pub fn ones_triple_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+        nz: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        ones_single_input(
+            ctx,
+            Dynamic::from_array(vec![nx.into(), ny.into(), nz.into()]),
+        )
     }
 
     /// Returns a matrix of random values, each between zero and one. Can be called with a single integer argument (indicating the
@@ -637,33 +3775,21 @@ pub mod matrix_functions {
     /// let matrix = rand([3, 3]);
     /// assert_eq(size(matrix), [3, 3]);
     /// ```
+    /// ```typescript
+    /// let grid = rand([2, 3, 4, 5]);
+    /// assert_eq(size(grid), [2, 3, 4, 5]);
+    /// ```
     #[cfg(feature = "rand")]
     #[rhai_fn(name = "rand", return_raw)]
-    pub fn rand_single_input(n: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+    pub fn rand_single_input(
+        ctx: NativeCallContext,
+        n: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let random = || Dynamic::from_float(crate::random_float(&ctx));
         crate::if_int_do_else_if_array_do(
             n,
-            |n| Ok(rand_double_input(n, n)),
-            |m| {
-                if m.len() == 2 {
-                    Ok(rand_double_input(
-                        m[0].as_int().unwrap(),
-                        m[1].as_int().unwrap(),
-                    ))
-                } else if m.len() > 2 {
-                    let l = m.remove(0);
-                    Ok(vec![
-                        Dynamic::from_array(
-                            rand_single_input(Dynamic::from_array(m.to_vec())).unwrap()
-                        );
-                        l.as_int().unwrap() as usize
-                    ])
-                } else {
-                    Ok(rand_double_input(1, m[0].as_int().unwrap())[0]
-                        .clone()
-                        .into_array()
-                        .unwrap())
-                }
-            },
+            |n| super::filled_array(&ctx, &[n.into(), n.into()], random),
+            |m| super::filled_array(&ctx, m, random),
         )
     }
 
@@ -674,17 +3800,39 @@ pub mod matrix_functions {
     /// assert_eq(size(matrix), [3, 3]);
     /// ```
     #[cfg(feature = "rand")]
-    #[rhai_fn(name = "rand")]
-    pub fn rand_double_input(nx: INT, ny: INT) -> Array {
-        let mut output = vec![];
-        for _ in 0..nx {
-            let mut row = vec![];
-            for _ in 0..ny {
-                row.push(Dynamic::from_float(crate::misc_functions::rand_float()));
-            }
-            output.push(Dynamic::from_array(row))
-        }
-        output
+    #[rhai_fn(name = "rand", return_raw)]
+    pub fn rand_double_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::filled_array(&ctx, &[nx.into(), ny.into()], || {
+            Dynamic::from_float(crate::random_float(&ctx))
+        })
+    }
+
+    /// Return a three-dimensional array of random values, each between zero and one. Arguments
+    /// indicate the size of each dimension.
+    /// ```typescript
+    /// let volume = rand(2, 3, 4);
+    /// assert_eq(size(volume), [2, 3, 4]);
+    /// ```
+    /// ```typescript
+    /// let volume = rand(2, 2, 2);
+    /// volume[0] != volume[1]
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "rand", return_raw)]
+    pub fn rand_triple_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+        nz: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        rand_single_input(
+            ctx,
+            Dynamic::from_array(vec![nx.into(), ny.into(), nz.into()]),
+        )
     }
 
     /// Returns an identity matrix. If argument is a single number, then the output is
@@ -702,31 +3850,43 @@ pub mod matrix_functions {
     ///                    [0.0, 0.0, 1.0, 0.0]]);
     /// ```
     #[rhai_fn(name = "eye", return_raw)]
-    pub fn eye_single_input(n: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+    pub fn eye_single_input(
+        ctx: NativeCallContext,
+        n: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
         if_int_do_else_if_array_do(
             n,
-            |n| Ok(eye_double_input(n, n)),
+            |n| super::identity_matrix(&ctx, n, n),
             |m| {
+                let size = |d: &Dynamic| {
+                    d.as_int().map_err(|_| {
+                        EvalAltResult::from(
+                            crate::LabError::new(
+                                crate::LabErrorKind::TypeMismatch,
+                                format!("The size must be given as integers, not {m:?}"),
+                            )
+                            .in_function("eye"),
+                        )
+                    })
+                };
                 if m.len() == 1 {
-                    Ok(eye_double_input(1, m[0].as_int().unwrap())[0]
+                    Ok(super::identity_matrix(&ctx, 1, size(&m[0])?)?[0]
                         .clone()
                         .into_array()
                         .unwrap())
                 } else if m.len() == 2 {
-                    Ok(eye_double_input(
-                        m[0].as_int().unwrap(),
-                        m[1].as_int().unwrap(),
-                    ))
+                    super::identity_matrix(&ctx, size(&m[0])?, size(&m[1])?)
                 } else {
-                    Err(EvalAltResult::ErrorMismatchDataType(
-                        format!("Cannot create an identity matrix with more than 2 dimensions"),
-                        format!(""),
-                        Position::NONE,
+                    Err(crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        "Cannot create an identity matrix with more than 2 dimensions",
                     )
+                    .in_function("eye")
                     .into())
                 }
             },
         )
+        .map_err(crate::in_function("eye", Some("n")))
     }
 
     /// Returns the identity matrix, specifying the number of rows and columns separately.
@@ -736,45 +3896,117 @@ pub mod matrix_functions {
     ///                    [0.0, 1.0, 0.0, 0.0],
     ///                    [0.0, 0.0, 1.0, 0.0]]);
     /// ```
-    #[rhai_fn(name = "eye")]
-    pub fn eye_double_input(nx: INT, ny: INT) -> Array {
-        let mut output = vec![];
-        for i in 0..nx {
-            let mut row = vec![];
-            for j in 0..ny {
-                if i == j {
-                    row.push(Dynamic::FLOAT_ONE);
-                } else {
-                    row.push(Dynamic::FLOAT_ZERO);
-                }
-            }
-            output.push(Dynamic::from_array(row))
-        }
-        output
+    #[rhai_fn(name = "eye", return_raw)]
+    pub fn eye_double_input(
+        ctx: NativeCallContext,
+        nx: INT,
+        ny: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::identity_matrix(&ctx, nx, ny)
+    }
+
+    /// Returns the contents of an multidimensional array as a 1-D array.
+    /// ```typescript
+    /// let matrix = ones(3, 5);
+    /// let flat = flatten(matrix);
+    /// assert_eq(len(flat), 15);
+    /// ```
+    /// ```typescript
+    /// let matrix = [[1.0, 2.0, 3.0], [1.0]];
+    /// let flat = flatten(matrix);
+    /// assert_eq(len(flat), 4);
+    /// ```
+    #[rhai_fn(name = "flatten", pure)]
+    pub fn flatten(matrix: &mut Array) -> Array {
+        let mut flat: Array = Vec::with_capacity(super::count_elements(matrix));
+        super::flatten_into(matrix, &mut flat);
+        flat
+    }
+
+    /// Rearranges the elements of an array into a matrix with `rows` rows and `cols` columns,
+    /// reading and filling in row-major order. One of the sizes can be `-1`, in which case it is
+    /// inferred from the number of elements.
+    /// ```typescript
+    /// let m = reshape([1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq(m, [[1, 2, 3], [4, 5, 6]]);
+    /// ```
+    /// ```typescript
+    /// let m = reshape([[1, 2, 3], [4, 5, 6]], -1, 2);
+    /// assert_eq(m, [[1, 2], [3, 4], [5, 6]]);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try { reshape([1, 2, 3], 9223372036854775807, 2); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[rhai_fn(name = "reshape", return_raw, pure)]
+    pub fn reshape(matrix: &mut Array, rows: INT, cols: INT) -> Result<Array, Box<EvalAltResult>> {
+        reshape_with_shape(
+            matrix,
+            vec![Dynamic::from_int(rows), Dynamic::from_int(cols)],
+        )
+    }
+
+    /// Rearranges the elements of an array into any shape, given as an array with the size of each
+    /// dimension, reading and filling in row-major order. One of the sizes can be `-1`, in which
+    /// case it is inferred from the number of elements.
+    /// ```typescript
+    /// let a = reshape([1, 2, 3, 4, 5, 6, 7, 8], [2, 2, -1]);
+    /// assert_eq(a, [[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    /// ```
+    /// ```typescript
+    /// let v = reshape([[1, 2], [3, 4]], [4]);
+    /// assert_eq(v, [1, 2, 3, 4]);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try { reshape([1, 2, 3], [9223372036854775807, 4]); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "dimension_mismatch");
+    /// ```
+    #[rhai_fn(name = "reshape", return_raw, pure)]
+    pub fn reshape_with_shape(
+        matrix: &mut Array,
+        shape: Array,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let shape = shape
+            .into_iter()
+            .map(|d| {
+                d.as_int().map_err(|_| {
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "The size of each dimension must be an integer",
+                    )
+                    .in_function("reshape")
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<INT>, Box<EvalAltResult>>>()?;
+        let flat = flatten(matrix);
+        let shape = crate::resolve_shape(&shape, flat.len())?;
+        Ok(super::nest_elements(&mut flat.into_iter(), &shape))
     }
 
-    /// Returns the contents of an multidimensional array as a 1-D array.
+    /// Removes the dimensions of size 1 from an array, so that a row matrix (`[[1, 2, 3]]`) or a
+    /// column matrix (`[[1], [2], [3]]`) becomes a vector. The result always has at least one
+    /// dimension.
     /// ```typescript
-    /// let matrix = ones(3, 5);
-    /// let flat = flatten(matrix);
-    /// assert_eq(len(flat), 15);
+    /// assert_eq(squeeze([[1], [2], [3]]), [1, 2, 3]);
     /// ```
     /// ```typescript
-    /// let matrix = [[1.0, 2.0, 3.0], [1.0]];
-    /// let flat = flatten(matrix);
-    /// assert_eq(len(flat), 4);
+    /// let a = squeeze([[[1, 2]], [[3, 4]]]);
+    /// assert_eq(a, [[1, 2], [3, 4]]);
     /// ```
-    #[rhai_fn(name = "flatten", pure)]
-    pub fn flatten(matrix: &mut Array) -> Array {
-        let mut flat: Array = vec![];
-        for el in matrix {
-            if el.is_array() {
-                flat.extend(flatten(&mut el.clone().into_array().unwrap()))
-            } else {
-                flat.push(el.clone());
-            }
+    #[rhai_fn(name = "squeeze", pure)]
+    pub fn squeeze(matrix: &mut Array) -> Array {
+        let mut shape = matrix_size_by_reference(matrix)
+            .into_iter()
+            .map(|d| d.as_int().unwrap() as usize)
+            .filter(|d| *d != 1)
+            .collect::<Vec<_>>();
+        if shape.is_empty() {
+            shape.push(1);
         }
-        flat
+        super::nest_elements(&mut flatten(matrix).into_iter(), &shape)
     }
 
     /// Flip a matrix left-to-right
@@ -801,6 +4033,7 @@ pub mod matrix_functions {
             }
             Ok(out)
         })
+        .map_err(crate::in_function("fliplr", Some("matrix")))
     }
 
     /// Flip a matrix up-down
@@ -827,6 +4060,7 @@ pub mod matrix_functions {
             }
             Ok(out)
         })
+        .map_err(crate::in_function("flipud", Some("matrix")))
     }
 
     /// Rotate a matrix counterclockwise once
@@ -854,18 +4088,26 @@ pub mod matrix_functions {
             }
             Ok(out)
         })
+        .map_err(crate::in_function("rot90", Some("matrix")))
     }
 
-    /// Rotate a matrix counterclockwise `k` times
+    /// Rotate a matrix counterclockwise `k` times, or clockwise if `k` is negative
     /// ```typescript
     /// let matrix = rot90([[1.0, 0.0],
     ///                     [0.0, 2.0]], 2);
     /// assert_eq(matrix, [[2.0, 0.0],
     ///                    [0.0, 1.0]]);
     /// ```
+    /// ```typescript
+    /// let matrix = rot90([[1, 2],
+    ///                     [3, 4]], -1);
+    /// assert_eq(matrix, [[3, 1],
+    ///                    [4, 2]]);
+    /// ```
     #[rhai_fn(name = "rot90", return_raw)]
     pub fn rot90_ktimes(matrix: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        if k <= 0 {
+        let k = k.rem_euclid(4);
+        if k == 0 {
             return Ok(matrix.clone());
         }
 
@@ -880,6 +4122,98 @@ pub mod matrix_functions {
         Ok(result_base)
     }
 
+    /// Reverses the order of the elements of a vector, or of the rows of a matrix (along the first
+    /// dimension of any array).
+    /// ```typescript
+    /// assert_eq(flip([1, 2, 3]), [3, 2, 1]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(flip([[1, 2], [3, 4]]), [[3, 4], [1, 2]]);
+    /// ```
+    #[rhai_fn(name = "flip", pure)]
+    pub fn flip(arr: &mut Array) -> Array {
+        let mut flipped = arr.clone();
+        flipped.reverse();
+        flipped
+    }
+
+    /// Reverses the order of the elements of an array along dimension `dim`, counting from 1, so
+    /// `flip(m, 1)` flips a matrix up-down and `flip(m, 2)` flips it left-right.
+    /// ```typescript
+    /// assert_eq(flip([[1, 2], [3, 4]], 2), [[2, 1], [4, 3]]);
+    /// ```
+    #[rhai_fn(name = "flip", return_raw)]
+    pub fn flip_along_dim(arr: Array, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        Ok(super::map_along_dim(
+            arr,
+            super::check_dim(dim)?,
+            &|slice| slice.reverse(),
+        ))
+    }
+
+    /// Shifts the elements of a vector (or the rows of a matrix, along the first dimension of any
+    /// array) circularly by `k` places. Positive shifts move elements towards the end, and those
+    /// that fall off the end wrap around to the start.
+    /// ```typescript
+    /// assert_eq(circshift([1, 2, 3, 4, 5], 2), [4, 5, 1, 2, 3]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(circshift([1, 2, 3, 4, 5], -1), [2, 3, 4, 5, 1]);
+    /// ```
+    #[rhai_fn(name = "circshift", return_raw)]
+    pub fn circshift(arr: Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
+        circshift_along_dim(arr, k, 1)
+    }
+
+    /// Shifts the elements of an array circularly by `k` places along dimension `dim`, counting
+    /// from 1, so `circshift(m, 1, 2)` moves each column of a matrix one place to the right.
+    /// ```typescript
+    /// let m = [[1, 2, 3],
+    ///          [4, 5, 6]];
+    /// assert_eq(circshift(m, 1, 2), [[3, 1, 2],
+    ///                                [6, 4, 5]]);
+    /// ```
+    #[rhai_fn(name = "circshift", return_raw)]
+    pub fn circshift_along_dim(arr: Array, k: INT, dim: INT) -> Result<Array, Box<EvalAltResult>> {
+        Ok(super::map_along_dim(
+            arr,
+            super::check_dim(dim)?,
+            &|slice| {
+                if !slice.is_empty() {
+                    let shift = k.rem_euclid(slice.len() as INT) as usize;
+                    slice.rotate_right(shift);
+                }
+            },
+        ))
+    }
+
+    /// Shifts the elements of an array circularly along several dimensions at once, by the number
+    /// of places given for each dimension in `shifts`.
+    /// ```typescript
+    /// let m = [[1, 2, 3],
+    ///          [4, 5, 6]];
+    /// assert_eq(circshift(m, [1, -1]), [[5, 6, 4],
+    ///                                   [2, 3, 1]]);
+    /// ```
+    #[rhai_fn(name = "circshift", return_raw)]
+    pub fn circshift_multiple(arr: Array, shifts: Array) -> Result<Array, Box<EvalAltResult>> {
+        let mut result = arr;
+        for (idx, k) in shifts.into_iter().enumerate() {
+            let k = k.as_int().map_err(|_| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "The shifts must be integers",
+                    )
+                    .in_function("circshift")
+                    .with_argument("shifts"),
+                )
+            })?;
+            result = circshift_along_dim(result, k, idx as INT + 1)?;
+        }
+        Ok(result)
+    }
+
     /// Perform matrix multiplication.
     /// ```typescript
     /// let a = eye(3);
@@ -890,31 +4224,18 @@ pub mod matrix_functions {
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "mtimes", return_raw)]
     pub fn mtimes(matrix1: Array, matrix2: Array) -> Result<Array, Box<EvalAltResult>> {
+        let (mut matrix1, mut matrix2) = (matrix1, matrix2);
         if_matrices_and_compatible_convert_to_vec_array_and_do(
             FOIL::Inside,
-            &mut matrix1.clone(),
-            &mut matrix2.clone(),
+            &mut matrix1,
+            &mut matrix2,
             |matrix_as_vec1, matrix_as_vec2| {
-                let dm1 =
-                    DMatrix::from_fn(matrix_as_vec1.len(), matrix_as_vec1[0].len(), |i, j| {
-                        if matrix_as_vec1[0][0].is_float() {
-                            matrix_as_vec1[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec1[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
+                let dm1 = crate::vec_array_to_dmatrix(&matrix_as_vec1)?;
 
-                let dm2 =
-                    DMatrix::from_fn(matrix_as_vec2.len(), matrix_as_vec2[0].len(), |i, j| {
-                        if matrix_as_vec2[0][0].is_float() {
-                            matrix_as_vec2[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec2[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
+                let dm2 = crate::vec_array_to_dmatrix(&matrix_as_vec2)?;
 
                 // Try to multiply
-                let mat = dm1 * dm2;
+                let mat = crate::dense_matmul(&dm1, &dm2);
 
                 // Turn into Array
                 let mut out = vec![];
@@ -930,122 +4251,90 @@ pub mod matrix_functions {
         )
     }
 
-    /// Concatenate two arrays horizontally.
+    /// Concatenates matrices horizontally, so they must have the same number of rows. Numbers and
+    /// vectors are treated as row matrices, row vectors join into a longer row vector, and empty
+    /// arrays are skipped. Element types are preserved, so integers stay integers (earlier
+    /// versions returned every element as a FLOAT). At most four matrices can be passed directly;
+    /// use `cat(2, blocks)` for more.
+    /// ```typescript
+    /// assert_eq(horzcat([[1], [2]], [[3.5], [4.5]]), [[1, 3.5], [2, 4.5]]);
+    /// ```
     /// ```typescript
     /// let arr1 = eye(3);
     /// let arr2 = eye(3);
     /// let combined = horzcat(arr1, arr2);
     /// assert_eq(size(combined), [3, 6]);
     /// ```
-    #[cfg(feature = "nalgebra")]
+    /// ```typescript
+    /// let combined = horzcat([[1], [2]], [[3, 4], [5, 6]], [[7], [8]]);
+    /// assert_eq(combined, [[1, 3, 4, 7], [2, 5, 6, 8]]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(horzcat([1, 2], [3], 4), [1, 2, 3, 4]);
+    /// ```
     #[rhai_fn(name = "horzcat", return_raw)]
-    pub fn horzcat(matrix1: Array, matrix2: Array) -> Result<Array, Box<EvalAltResult>> {
-        if_matrices_and_compatible_convert_to_vec_array_and_do(
-            FOIL::First,
-            &mut matrix1.clone(),
-            &mut matrix2.clone(),
-            |matrix_as_vec1, matrix_as_vec2| {
-                let dm1 =
-                    DMatrix::from_fn(matrix_as_vec1.len(), matrix_as_vec1[0].len(), |i, j| {
-                        if matrix_as_vec1[0][0].is_float() {
-                            matrix_as_vec1[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec1[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
+    pub fn horzcat(matrix1: Dynamic, matrix2: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(2, vec![matrix1, matrix2])
+    }
 
-                let dm2 =
-                    DMatrix::from_fn(matrix_as_vec2.len(), matrix_as_vec2[0].len(), |i, j| {
-                        if matrix_as_vec2[0][0].is_float() {
-                            matrix_as_vec2[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec2[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
-
-                // Try to multiple
-                let w0 = dm1.shape().1;
-                let w = dm1.shape().1 + dm2.shape().1;
-                let h = dm1.shape().0;
-                let mat = DMatrix::from_fn(h, w, |i, j| {
-                    if j >= w0 {
-                        dm2[(i, j - w0)]
-                    } else {
-                        dm1[(i, j)]
-                    }
-                });
+    /// Concatenates three matrices horizontally, as `horzcat(a, b)` does.
+    #[rhai_fn(name = "horzcat", return_raw)]
+    pub fn horzcat3(a: Dynamic, b: Dynamic, c: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(2, vec![a, b, c])
+    }
 
-                // Turn into Array
-                let mut out = vec![];
-                for idx in 0..h {
-                    let mut new_row = vec![];
-                    for jdx in 0..w {
-                        new_row.push(Dynamic::from_float(mat[(idx, jdx)]));
-                    }
-                    out.push(Dynamic::from_array(new_row));
-                }
-                Ok(out)
-            },
-        )
+    /// Concatenates four matrices horizontally, as `horzcat(a, b)` does.
+    #[rhai_fn(name = "horzcat", return_raw)]
+    pub fn horzcat4(
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(2, vec![a, b, c, d])
     }
 
-    /// Concatenates two array vertically.
+    /// Concatenates matrices vertically, so they must have the same number of columns. Numbers
+    /// and vectors are treated as row matrices, and empty arrays are skipped, so a matrix can be
+    /// built up row by row starting from `[]`. Element types are preserved, so integers stay
+    /// integers (earlier versions returned every element as a FLOAT). At most four matrices can be
+    /// passed directly; use `cat(1, blocks)` for more.
+    /// ```typescript
+    /// assert_eq(vertcat([1, 2], [3.5, 4.5]), [[1, 2], [3.5, 4.5]]);
+    /// ```
     /// ```typescript
     /// let arr1 = eye(3);
     /// let arr2 = eye(3);
     /// let combined = vertcat(arr1, arr2);
     /// assert_eq(size(combined), [6, 3]);
     /// ```
-    #[cfg(feature = "nalgebra")]
+    /// ```typescript
+    /// let m = [];
+    /// for i in 0..3 {
+    ///     m = vertcat(m, [i, i * i]);
+    /// }
+    /// assert_eq(m, [[0, 0], [1, 1], [2, 4]]);
+    /// ```
     #[rhai_fn(name = "vertcat", return_raw)]
-    pub fn vertcat(matrix1: Array, matrix2: Array) -> Result<Array, Box<EvalAltResult>> {
-        if_matrices_and_compatible_convert_to_vec_array_and_do(
-            FOIL::Last,
-            &mut matrix1.clone(),
-            &mut matrix2.clone(),
-            |matrix_as_vec1, matrix_as_vec2| {
-                let dm1 =
-                    DMatrix::from_fn(matrix_as_vec1.len(), matrix_as_vec1[0].len(), |i, j| {
-                        if matrix_as_vec1[0][0].is_float() {
-                            matrix_as_vec1[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec1[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
+    pub fn vertcat(matrix1: Dynamic, matrix2: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(1, vec![matrix1, matrix2])
+    }
 
-                let dm2 =
-                    DMatrix::from_fn(matrix_as_vec2.len(), matrix_as_vec2[0].len(), |i, j| {
-                        if matrix_as_vec2[0][0].is_float() {
-                            matrix_as_vec2[i][j].as_float().unwrap()
-                        } else {
-                            matrix_as_vec2[i][j].as_int().unwrap() as FLOAT
-                        }
-                    });
-
-                // Try to multiple
-                let h0 = dm1.shape().0;
-                let w = dm1.shape().1;
-                let h = dm1.shape().0 + dm2.shape().0;
-                let mat = DMatrix::from_fn(h, w, |i, j| {
-                    if i >= h0 {
-                        dm2[(i - h0, j)]
-                    } else {
-                        dm1[(i, j)]
-                    }
-                });
+    /// Concatenates three matrices vertically, as `vertcat(a, b)` does.
+    #[rhai_fn(name = "vertcat", return_raw)]
+    pub fn vertcat3(a: Dynamic, b: Dynamic, c: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(1, vec![a, b, c])
+    }
 
-                // Turn into Array
-                let mut out = vec![];
-                for idx in 0..h {
-                    let mut new_row = vec![];
-                    for jdx in 0..w {
-                        new_row.push(Dynamic::from_float(mat[(idx, jdx)]));
-                    }
-                    out.push(Dynamic::from_array(new_row));
-                }
-                Ok(out)
-            },
-        )
+    /// Concatenates four matrices vertically, as `vertcat(a, b)` does.
+    #[rhai_fn(name = "vertcat", return_raw)]
+    pub fn vertcat4(
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(1, vec![a, b, c, d])
     }
 
     /// This function can be used in two distinct ways.
@@ -1067,7 +4356,38 @@ pub mod matrix_functions {
     ///                     [0.0, 0.0, 3.0]]);
     /// ```
     #[rhai_fn(name = "diag", return_raw)]
-    pub fn diag(matrix: Array) -> Result<Array, Box<EvalAltResult>> {
+    pub fn diag(ctx: NativeCallContext, matrix: Array) -> Result<Array, Box<EvalAltResult>> {
+        diag_with_offset(ctx, matrix, 0)
+    }
+
+    /// Works like `diag`, but for the `k`-th diagonal: `k > 0` is above the main diagonal and
+    /// `k < 0` is below it. Extracting a diagonal also works for non-square matrices, and building
+    /// a matrix from a 1-D array gives a square matrix just large enough to hold it.
+    /// ```typescript
+    ///  let matrix = [[1, 2, 3],
+    ///                [4, 5, 6]];
+    ///  assert_eq(diag(matrix, 1), [2, 6]);
+    ///  assert_eq(diag(matrix, -1), [4]);
+    /// ```
+    /// ```typescript
+    ///  let matrix = diag([1, 2], -1);
+    ///  assert_eq(matrix, [[0, 0, 0],
+    ///                     [1, 0, 0],
+    ///                     [0, 2, 0]]);
+    /// ```
+    #[rhai_fn(name = "diag", return_raw)]
+    pub fn diag_with_offset(
+        ctx: NativeCallContext,
+        matrix: Array,
+        k: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        // Offsets of the diagonal in rows and columns
+        let (row_offset, col_offset) = if k < 0 {
+            (k.unsigned_abs() as usize, 0)
+        } else {
+            (0, k as usize)
+        };
+
         if ndims_by_reference(&mut matrix.clone()) == 2 {
             // Turn into Vec<Array>
             let matrix_as_vec = matrix
@@ -1076,62 +4396,259 @@ pub mod matrix_functions {
                 .collect::<Vec<Array>>();
 
             let mut out = vec![];
-            for i in 0..matrix_as_vec.len() {
-                out.push(matrix_as_vec[i][i].clone());
+            let mut i = 0;
+            while i + row_offset < matrix_as_vec.len()
+                && i + col_offset < matrix_as_vec[i + row_offset].len()
+            {
+                out.push(matrix_as_vec[i + row_offset][i + col_offset].clone());
+                i += 1;
             }
 
             Ok(out)
         } else if ndims_by_reference(&mut matrix.clone()) == 1 {
+            let n = matrix
+                .len()
+                .saturating_add(row_offset)
+                .saturating_add(col_offset);
+            crate::check_array_shape(&ctx, &[n, n])?;
+            let zero = if matrix.iter().all(|x| x.is_int()) {
+                Dynamic::ZERO
+            } else {
+                Dynamic::FLOAT_ZERO
+            };
             let mut out = vec![];
-            for idx in 0..matrix.len() {
-                let mut new_row = vec![];
-                for jdx in 0..matrix.len() {
-                    if idx == jdx {
-                        new_row.push(matrix[idx].clone());
-                    } else {
-                        if matrix[idx].is_int() {
-                            new_row.push(Dynamic::ZERO);
-                        } else {
-                            new_row.push(Dynamic::FLOAT_ZERO);
-                        }
-                    }
+            for idx in 0..n {
+                let mut new_row = vec![zero.clone(); n];
+                if idx >= row_offset && idx - row_offset < matrix.len() {
+                    new_row[idx - row_offset + col_offset] = matrix[idx - row_offset].clone();
                 }
                 out.push(Dynamic::from_array(new_row));
             }
             Ok(out)
         } else {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "Argument must be a 2-D matrix (to extract the diagonal) or a 1-D array (to create a matrix with that diagonal".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "Argument must be a 2-D matrix (to extract the diagonal) or a 1-D array (to create a matrix with that diagonal)",
             )
-                .into());
+            .in_function("diag")
+            .with_argument("matrix")
+            .into())
         }
     }
 
-    /// Repeats copies of a matrix
+    /// Returns the trace of a square matrix: the sum of the elements on its main diagonal. The
+    /// result is an INT if every diagonal element is, and a FLOAT otherwise.
+    /// ```typescript
+    ///  let matrix = [[1, 2, 3],
+    ///                [4, 5, 6],
+    ///                [7, 8, 9]];
+    ///  assert_eq(trace(matrix), 15);
+    /// ```
+    /// ```typescript
+    ///  assert_eq(trace(eye(4)), 4.0);
+    /// ```
+    #[rhai_fn(name = "trace", return_raw, pure)]
+    pub fn trace(matrix: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
+            if matrix_as_vec.len() != matrix_as_vec[0].len() {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "The trace is only defined for square matrices, but this matrix is {} x {}",
+                        matrix_as_vec.len(),
+                        matrix_as_vec[0].len()
+                    ),
+                )
+                .in_function("trace")
+                .with_argument("matrix")
+                .into());
+            }
+            let mut diagonal = (0..matrix_as_vec.len())
+                .map(|i| matrix_as_vec[i][i].clone())
+                .collect::<Array>();
+            if diagonal.iter().all(|x| x.is_int()) {
+                diagonal
+                    .iter()
+                    .try_fold(0 as INT, |total, x| total.checked_add(x.as_int().unwrap()))
+                    .map(Dynamic::from_int)
+                    .ok_or_else(|| {
+                        crate::LabError::new(
+                            crate::LabErrorKind::Numerical,
+                            "The trace is too large to represent as an integer",
+                        )
+                        .in_function("trace")
+                        .into()
+                    })
+            } else {
+                crate::if_list_convert_to_vec_float_and_do(&mut diagonal, |d| {
+                    Ok(Dynamic::from_float(d.iter().sum()))
+                })
+            }
+        })
+        .map_err(crate::in_function("trace", Some("matrix")))
+    }
+
+    /// Concatenates an array of blocks along dimension `dim`, counting from 1: `cat(1, blocks)`
+    /// stacks them vertically like `vertcat`, `cat(2, blocks)` joins them horizontally like
+    /// `horzcat`, and higher dimensions stack matrices into N-dimensional arrays. All dimensions
+    /// except `dim` must match.
+    /// ```typescript
+    /// let a = [[1, 2], [3, 4]];
+    /// let b = [[5, 6], [7, 8]];
+    /// assert_eq(cat(1, [a, b]), [[1, 2], [3, 4], [5, 6], [7, 8]]);
+    /// assert_eq(cat(2, [a, b]), [[1, 2, 5, 6], [3, 4, 7, 8]]);
+    /// ```
+    /// ```typescript
+    /// let a = [[1, 2], [3, 4]];
+    /// let b = [[5, 6], [7, 8]];
+    /// let c = cat(3, [a, b]);
+    /// assert_eq(size(c), [2, 2, 2]);
+    /// assert_eq(c[0][1], [2, 6]);
+    /// ```
+    #[rhai_fn(name = "cat", return_raw)]
+    pub fn cat(dim: INT, blocks: Array) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(dim, blocks)
+    }
+
+    /// Concatenates two blocks along dimension `dim`, as `cat(dim, [a, b])` does.
+    /// ```typescript
+    /// let c = cat(2, [[1], [2]], [[3], [4]]);
+    /// assert_eq(c, [[1, 3], [2, 4]]);
+    /// ```
+    #[rhai_fn(name = "cat", return_raw)]
+    pub fn cat2(dim: INT, a: Dynamic, b: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(dim, vec![a, b])
+    }
+
+    /// Concatenates three blocks along dimension `dim`, as `cat(dim, [a, b, c])` does.
+    #[rhai_fn(name = "cat", return_raw)]
+    pub fn cat3(dim: INT, a: Dynamic, b: Dynamic, c: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        super::concatenate(dim, vec![a, b, c])
+    }
+
+    /// Tiles copies of a matrix `nx` times vertically and `ny` times horizontally. Numbers and
+    /// vectors are treated as row matrices. Element types are preserved.
     /// ```typescript
     /// let matrix = eye(3);
     /// let combined = repmat(matrix, 2, 2);
     /// assert_eq(size(combined), [6, 6]);
     /// ```
-    #[cfg(feature = "nalgebra")]
+    /// ```typescript
+    /// let tiled = repmat([[1, 2]], 2, 3);
+    /// assert_eq(tiled, [[1, 2, 1, 2, 1, 2],
+    ///                   [1, 2, 1, 2, 1, 2]]);
+    /// ```
     #[rhai_fn(name = "repmat", return_raw)]
-    pub fn repmat(matrix: &mut Array, nx: INT, ny: INT) -> Result<Array, Box<EvalAltResult>> {
-        if_matrix_do(matrix, |matrix| {
-            let mut row_matrix = matrix.clone();
-            for _ in 1..ny {
-                row_matrix = horzcat(row_matrix, matrix.clone())?;
-            }
-            let mut new_matrix = row_matrix.clone();
-            for _ in 1..nx {
-                new_matrix = vertcat(new_matrix, row_matrix.clone())?;
+    pub fn repmat(
+        ctx: NativeCallContext,
+        matrix: &mut Array,
+        nx: INT,
+        ny: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if nx < 0 || ny < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The number of copies must be non-negative, not {nx} and {ny}"),
+            )
+            .in_function("repmat")
+            .into());
+        }
+        let mut matrix = super::promote_block(Dynamic::from_array(matrix.clone()), 2)
+            .into_array()
+            .unwrap();
+        if_matrix_do(&mut matrix, |matrix| {
+            let rows = matrix.len().saturating_mul(nx as usize);
+            let columns = matrix
+                .first()
+                .map_or(0, |row| row.read_lock::<Array>().map_or(0, |row| row.len()))
+                .saturating_mul(ny as usize);
+            crate::check_array_shape(&ctx, &[rows, columns])?;
+            let row_block = matrix
+                .iter()
+                .map(|row| {
+                    let row = row.read_lock::<Array>().unwrap();
+                    let mut tiled = Vec::with_capacity(row.len() * ny as usize);
+                    for _ in 0..ny {
+                        tiled.extend(row.iter().cloned());
+                    }
+                    Dynamic::from_array(tiled)
+                })
+                .collect::<Array>();
+            let mut out = Vec::with_capacity(row_block.len() * nx as usize);
+            for _ in 0..nx {
+                out.extend(row_block.iter().cloned());
             }
-            Ok(new_matrix)
+            Ok(out)
         })
     }
 
+    /// Tiles copies of a matrix `n` times in each direction, as `repmat(matrix, n, n)` does.
+    /// ```typescript
+    /// let tiled = repmat([[1]], 2);
+    /// assert_eq(tiled, [[1, 1], [1, 1]]);
+    /// ```
+    #[rhai_fn(name = "repmat", return_raw)]
+    pub fn repmat_square(
+        ctx: NativeCallContext,
+        matrix: &mut Array,
+        n: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        repmat(ctx, matrix, n, n)
+    }
+
+    /// Computes the Kronecker product of two matrices: a block matrix in which block `(i, j)` is
+    /// `b` scaled by `a[i][j]`. Vectors are treated as row matrices, and the product of two vectors
+    /// is a vector. Integers stay integers.
+    /// ```typescript
+    /// let k = kron([[1, 2], [3, 4]], [[0, 1], [1, 0]]);
+    /// assert_eq(k, [[0, 1, 0, 2],
+    ///               [1, 0, 2, 0],
+    ///               [0, 3, 0, 4],
+    ///               [3, 0, 4, 0]]);
+    /// ```
+    /// ```typescript
+    /// let d = [[-1.0, 1.0, 0.0], [0.0, -1.0, 1.0]];
+    /// let block_diagonal = kron(eye(2), d);
+    /// assert_eq(size(block_diagonal), [4, 6]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(kron([1, 2], [1, 10]), [1, 10, 2, 20]);
+    /// ```
+    #[rhai_fn(name = "kron", return_raw)]
+    pub fn kron(a: Array, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        let vectors = super::block_rank(&Dynamic::from_array(a.clone())) <= 1
+            && super::block_rank(&Dynamic::from_array(b.clone())) <= 1;
+        let as_matrix = |m: Array| -> Result<Vec<Array>, Box<EvalAltResult>> {
+            let mut m = super::promote_block(Dynamic::from_array(m), 2)
+                .into_array()
+                .unwrap();
+            if_matrix_convert_to_vec_array_and_do(&mut m, Ok)
+        };
+        let (a, b) = (as_matrix(a)?, as_matrix(b)?);
+
+        let mut out = vec![];
+        for a_row in &a {
+            for b_row in &b {
+                let mut row = Vec::with_capacity(a_row.len() * b_row.len());
+                for x in a_row {
+                    for y in b_row {
+                        let product = crate::elementwise_functions::times(x.clone(), y.clone())?;
+                        row.push(product);
+                    }
+                }
+                out.push(Dynamic::from_array(row));
+            }
+        }
+        if vectors {
+            Ok(out.remove(0).into_array().unwrap())
+        } else {
+            Ok(out)
+        }
+    }
+
     /// Returns an object map containing 2-D grid coordinates based on the uni-axial coordinates
-    /// contained in arguments x and y.
+    /// contained in arguments x and y. Both grids have one row per element of `y` and one column
+    /// per element of `x`, so `g.x[i][j]` is `x[j]` and `g.y[i][j]` is `y[i]`.
     /// ```typescript
     /// let x = [1, 2];
     /// let y = [3, 4];
@@ -1141,62 +4658,306 @@ pub mod matrix_functions {
     ///                "y": [[3, 3],
     ///                      [4, 4]]});
     /// ```
+    /// ```typescript
+    /// let g = meshgrid([1, 2, 3], [10, 20]);
+    /// assert_eq(size(g.x), [2, 3]);
+    /// assert_eq(g.x .* g.y, [[10, 20, 30], [20, 40, 60]]);
+    /// ```
     #[rhai_fn(name = "meshgrid", return_raw)]
     pub fn meshgrid(x: Array, y: Array) -> Result<Map, Box<EvalAltResult>> {
         if_list_do(&mut x.clone(), |x| {
             if_list_do(&mut y.clone(), |y| {
-                let nx = x.len();
-                let ny = y.len();
-                let x_dyn: Array = vec![Dynamic::from_array(x.to_vec()); nx];
-                let mut y_dyn: Array = vec![Dynamic::from_array(y.to_vec()); ny];
-
-                let mut result = BTreeMap::new();
-                let mut xid = smartstring::SmartString::new();
-                xid.push_str("x");
-                let mut yid = smartstring::SmartString::new();
-                yid.push_str("y");
-                result.insert(xid, Dynamic::from_array(x_dyn));
-                result.insert(yid, Dynamic::from_array(transpose(&mut y_dyn).unwrap()));
+                let axes = [y.clone(), x.clone()];
+                let mut result = Map::new();
+                result.insert("x".into(), super::grid_along(&axes, 1, 0, None));
+                result.insert("y".into(), super::grid_along(&axes, 0, 0, None));
                 Ok(result)
             })
         })
+        .map_err(crate::in_function("meshgrid", None))
+    }
+
+    /// Returns an object map containing 3-D grid coordinates based on the uni-axial coordinates
+    /// contained in arguments x, y, and z. Each grid has size `[len(y), len(x), len(z)]`, so
+    /// `g.x[i][j][k]` is `x[j]`, `g.y[i][j][k]` is `y[i]`, and `g.z[i][j][k]` is `z[k]`.
+    /// ```typescript
+    /// let g = meshgrid([1, 2, 3], [4, 5], [6, 7, 8, 9]);
+    /// assert_eq(size(g.z), [2, 3, 4]);
+    /// assert_eq([g.x[1][2][3], g.y[1][2][3], g.z[1][2][3]], [3, 5, 9]);
+    /// ```
+    #[rhai_fn(name = "meshgrid", return_raw)]
+    pub fn meshgrid_3d(x: Array, y: Array, z: Array) -> Result<Map, Box<EvalAltResult>> {
+        let grids = ndgrid(vec![y.into(), x.into(), z.into()])?;
+        let mut result = Map::new();
+        for (name, idx) in [("x", 1), ("y", 0), ("z", 2)] {
+            result.insert(name.into(), grids[idx].clone());
+        }
+        Ok(result)
+    }
+
+    /// Returns N-dimensional grid coordinates for an array of N coordinate vectors. The result
+    /// holds one grid per vector, each with size `[len(axes[0]), len(axes[1]), ...]`, where grid
+    /// `k` varies along dimension `k`. Unlike `meshgrid`, the first vector runs down the rows.
+    /// ```typescript
+    /// let grids = ndgrid([[1, 2], [3, 4, 5]]);
+    /// assert_eq(grids[0], [[1, 1, 1], [2, 2, 2]]);
+    /// assert_eq(grids[1], [[3, 4, 5], [3, 4, 5]]);
+    /// ```
+    /// ```typescript
+    /// let grids = ndgrid([[0, 1], [0, 1], [0, 1]]);
+    /// assert_eq(size(grids[2]), [2, 2, 2]);
+    /// assert_eq(grids[2][0][1], [0, 1]);
+    /// ```
+    #[rhai_fn(name = "ndgrid", return_raw)]
+    pub fn ndgrid(axes: Array) -> Result<Array, Box<EvalAltResult>> {
+        // A single vector of numbers is a 1-D grid
+        let axes = if axes.iter().all(|x| !x.is_array()) {
+            vec![crate::promote_numbers(axes)]
+        } else {
+            axes.into_iter()
+                .map(|axis| {
+                    let mut axis = axis.into_array().map_err(|_| {
+                        rhai::EvalAltResult::from(
+                            crate::LabError::new(
+                                crate::LabErrorKind::TypeMismatch,
+                                "Each axis of a grid must be an array",
+                            )
+                            .in_function("ndgrid"),
+                        )
+                    })?;
+                    if_list_do(&mut axis, |axis| Ok(axis.clone()))
+                })
+                .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?
+        };
+        Ok((0..axes.len())
+            .map(|k| super::grid_along(&axes, k, 0, None))
+            .collect())
     }
 
-    /// Returns an array containing a number of elements linearly spaced between two bounds.
+    /// Returns an array containing a number of elements linearly spaced between two bounds. Both
+    /// bounds are included, a single element is just the upper bound, and zero elements give an
+    /// empty array.
     /// ```typescript
     /// let x = linspace(1, 2, 5);
     /// assert_eq(x, [1.0, 1.25, 1.5, 1.75, 2.0]);
     /// ```
+    /// ```typescript
+    /// let x = linspace(0, 10, 1);
+    /// assert_eq(x, [10.0]);
+    /// ```
+    /// ```typescript
+    /// let x = linspace(0.0, 1.0, 0);
+    /// assert_eq(x, []);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try { linspace(0, 1, 9223372036854775807); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
     #[rhai_fn(name = "linspace", return_raw)]
-    pub fn linspace(x1: Dynamic, x2: Dynamic, n: INT) -> Result<Array, Box<EvalAltResult>> {
-        if_int_convert_to_float_and_do(x1, |new_x1| {
-            if_int_convert_to_float_and_do(x2.clone(), |new_x2| {
-                let new_n = n as FLOAT;
-
-                let mut arr = vec![Dynamic::from_float(new_x1)];
-                let mut counter = new_x1;
-                let interval = (new_x2 - new_x1) / (new_n - 1.0);
-                for _ in 0..(n - 2) {
-                    counter += interval;
-                    arr.push(Dynamic::from_float(counter));
-                }
-                arr.push(Dynamic::from_float(new_x2));
-                Ok(arr)
-            })
-        })
+    pub fn linspace(
+        ctx: NativeCallContext,
+        x1: Dynamic,
+        x2: Dynamic,
+        n: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::evenly_spaced_array(&ctx, x1, x2, n)
     }
 
     /// Returns an array containing a number of elements logarithmically spaced between two bounds.
+    /// The bounds are exponents, so the elements run from `10^a` to `10^b`.
     /// ```typescript
     /// let x = logspace(1, 3, 3);
     /// assert_eq(x, [10.0, 100.0, 1000.0]);
     /// ```
     #[rhai_fn(name = "logspace", return_raw)]
-    pub fn logspace(a: Dynamic, b: Dynamic, n: INT) -> Result<Array, Box<EvalAltResult>> {
-        linspace(a, b, n).map(|arr| {
+    pub fn logspace(
+        ctx: NativeCallContext,
+        a: Dynamic,
+        b: Dynamic,
+        n: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        super::evenly_spaced_array(&ctx, a, b, n).map(|arr| {
             arr.iter()
                 .map(|e| Dynamic::from_float((10 as FLOAT).powf(e.as_float().unwrap())))
                 .collect::<Array>()
         })
     }
+
+    /// Returns an array containing a number of elements geometrically spaced between two bounds,
+    /// so that each element is a constant multiple of the one before it. Unlike `logspace`, the
+    /// bounds are the endpoints themselves. They must be non-zero and have the same sign.
+    /// ```typescript
+    /// let x = geomspace(1, 1000, 4);
+    /// assert_eq(x, [1.0, 10.0, 100.0, 1000.0]);
+    /// ```
+    /// ```typescript
+    /// let x = geomspace(-0.1, -100, 4);
+    /// assert_eq(x, [-0.1, -1.0, -10.0, -100.0]);
+    /// ```
+    #[rhai_fn(name = "geomspace", return_raw)]
+    pub fn geomspace(
+        ctx: NativeCallContext,
+        a: Dynamic,
+        b: Dynamic,
+        n: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if_int_convert_to_float_and_do(a, |a| {
+            if_int_convert_to_float_and_do(b.clone(), |b| {
+                if a == 0.0 || b == 0.0 || a.signum() != b.signum() {
+                    return Err(crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        format!("Bounds must be non-zero and have the same sign, not {a} and {b}"),
+                    )
+                    .in_function("geomspace")
+                    .into());
+                }
+                let exponents = super::evenly_spaced_array(
+                    &ctx,
+                    Dynamic::from_float(a.abs().log10()),
+                    Dynamic::from_float(b.abs().log10()),
+                    n,
+                )?;
+                Ok(exponents
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| {
+                        let value = match i {
+                            0 => a,
+                            i if i as INT == n - 1 => b,
+                            _ => a.signum() * (10 as FLOAT).powf(e.as_float().unwrap()),
+                        };
+                        Dynamic::from_float(value)
+                    })
+                    .collect())
+            })
+        })
+        .map_err(crate::in_function("geomspace", None))
+    }
+
+    /// Returns an array of values from `a` up to, but not including, `b`, spaced by `step`. The
+    /// result is made of integers if all the arguments are integers, and floats otherwise. A
+    /// negative step counts down from `a` to just above `b`.
+    /// ```typescript
+    /// let x = arange(0, 10, 3);
+    /// assert_eq(x, [0, 3, 6, 9]);
+    /// ```
+    /// ```typescript
+    /// let x = arange(0.0, 1.0, 0.25);
+    /// assert_eq(x, [0.0, 0.25, 0.5, 0.75]);
+    /// ```
+    /// ```typescript
+    /// let x = arange(5, 0, -2);
+    /// assert_eq(x, [5, 3, 1]);
+    /// ```
+    /// ```typescript
+    /// let kinds = [];
+    /// for range in [[0, 9223372036854775807, 1], [9223372036854775807, -9223372036854775807, -1],
+    ///               [0.0, 1e300, 1e-300]] {
+    ///     try { arange(range[0], range[1], range[2]); } catch (err) { kinds.push(err.kind); }
+    /// }
+    /// assert_eq(kinds, ["invalid_argument", "invalid_argument", "invalid_argument"]);
+    /// ```
+    #[rhai_fn(name = "arange", return_raw)]
+    pub fn arange(
+        ctx: NativeCallContext,
+        a: Dynamic,
+        b: Dynamic,
+        step: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let too_long = |n: FLOAT| -> Box<EvalAltResult> {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The range would have {n:e} elements, which is too many"),
+            )
+            .in_function("arange")
+            .into()
+        };
+        if a.is_int() && b.is_int() && step.is_int() {
+            let (a, b, step) = (a.as_int()?, b.as_int()?, step.as_int()?);
+            if step == 0 {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    "Step size cannot be zero",
+                )
+                .in_function("arange")
+                .into());
+            }
+            // Count in i128, which can't overflow for any bounds and step
+            let (wide_a, wide_b, wide_step) = (a as i128, b as i128, step as i128);
+            let n = (wide_b - wide_a + wide_step - wide_step.signum()) / wide_step;
+            let n = usize::try_from(n.max(0)).map_err(|_| too_long(n as FLOAT))?;
+            crate::check_array_shape(&ctx, &[n])?;
+            return Ok((0..n)
+                .map(|i| Dynamic::from_int(a + i as INT * step))
+                .collect());
+        }
+        let as_float = |x: &Dynamic| {
+            x.as_float()
+                .or_else(|_| x.as_int().map(|x| x as FLOAT))
+                .map_err(|_| {
+                    rhai::EvalAltResult::from(
+                        crate::LabError::new(
+                            crate::LabErrorKind::TypeMismatch,
+                            format!("Bounds and step must be numbers, not {}", x.type_name()),
+                        )
+                        .in_function("arange"),
+                    )
+                })
+        };
+        let (a, b, step) = (as_float(&a)?, as_float(&b)?, as_float(&step)?);
+        if step == 0.0 || !step.is_finite() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Step size must be finite and non-zero, not {step}"),
+            )
+            .in_function("arange")
+            .with_argument("step")
+            .into());
+        }
+        if !a.is_finite() || !b.is_finite() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The bounds must be finite, not {a} and {b}"),
+            )
+            .in_function("arange")
+            .into());
+        }
+        let n = ((b - a) / step).ceil().max(0.0);
+        if n >= usize::MAX as FLOAT {
+            return Err(too_long(n));
+        }
+        let n = n as usize;
+        crate::check_array_shape(&ctx, &[n])?;
+        Ok((0..n)
+            .map(|i| Dynamic::from_float(a + (i as FLOAT) * step))
+            .collect())
+    }
+
+    /// Returns an array of values from `a` up to, but not including, `b`, spaced by one.
+    /// ```typescript
+    /// let x = arange(2, 6);
+    /// assert_eq(x, [2, 3, 4, 5]);
+    /// ```
+    #[rhai_fn(name = "arange", return_raw)]
+    pub fn arange_unit_step(
+        ctx: NativeCallContext,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        arange(ctx, a, b, Dynamic::from_int(1))
+    }
+
+    /// Returns an array of values from zero up to, but not including, `n`.
+    /// ```typescript
+    /// let x = arange(4);
+    /// assert_eq(x, [0, 1, 2, 3]);
+    /// ```
+    #[rhai_fn(name = "arange", return_raw)]
+    pub fn arange_from_zero(
+        ctx: NativeCallContext,
+        n: Dynamic,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        arange(ctx, Dynamic::from_int(0), n, Dynamic::from_int(1))
+    }
 }
@@ -0,0 +1,926 @@
+use rhai::plugin::*;
+
+/// A dense matrix of floating point numbers, stored contiguously in row-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    /// Number of rows
+    rows: usize,
+    /// Number of columns
+    cols: usize,
+    /// Elements in row-major order
+    data: Vec<rhai::FLOAT>,
+}
+
+impl Matrix {
+    /// Creates a matrix from its shape and its elements in row-major order.
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        data: Vec<rhai::FLOAT>,
+    ) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if data.len() != rows * cols {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A {rows} x {cols} matrix needs {} elements, but {} were given",
+                    rows * cols,
+                    data.len()
+                ),
+            )
+            .into());
+        }
+        Ok(Matrix { rows, cols, data })
+    }
+
+    /// Creates a matrix with every element set to `value`.
+    pub fn filled(rows: usize, cols: usize, value: rhai::FLOAT) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![value; rows * cols],
+        }
+    }
+
+    /// Converts a nested Rhai array (a matrix) or a flat numeric array (a row vector) into a
+    /// matrix.
+    pub fn from_array(arr: &rhai::Array) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let to_float = |x: &rhai::Dynamic| {
+            x.as_float()
+                .or_else(|_| x.as_int().map(|i| i as rhai::FLOAT))
+                .map_err(|_| {
+                    rhai::EvalAltResult::from(crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        format!(
+                            "Matrix elements must be INT or FLOAT, not {}",
+                            x.type_name()
+                        ),
+                    ))
+                })
+        };
+
+        if arr.iter().all(|x| !x.is_array()) {
+            let data = arr.iter().map(to_float).collect::<Result<Vec<_>, _>>()?;
+            return Ok(Matrix {
+                rows: 1,
+                cols: data.len(),
+                data,
+            });
+        }
+
+        let mut data = vec![];
+        let mut cols = None;
+        for row in arr {
+            let row = row.read_lock::<rhai::Array>().ok_or_else(|| {
+                rhai::EvalAltResult::from(crate::LabError::new(
+                    crate::LabErrorKind::TypeMismatch,
+                    "Every row of a matrix must be an array",
+                ))
+            })?;
+            if *cols.get_or_insert(row.len()) != row.len() {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    "Every row of a matrix must have the same length",
+                )
+                .into());
+            }
+            for x in row.iter() {
+                data.push(to_float(x)?);
+            }
+        }
+        Ok(Matrix {
+            rows: arr.len(),
+            cols: cols.unwrap_or(0),
+            data,
+        })
+    }
+
+    /// Converts the matrix into a nested Rhai array of FLOATs, one array per row.
+    pub fn to_array(&self) -> rhai::Array {
+        (0..self.rows).map(|i| self.row(i).into()).collect()
+    }
+
+    /// Returns row `i` as a Rhai array.
+    pub fn row(&self, i: usize) -> rhai::Array {
+        self.data[i * self.cols..(i + 1) * self.cols]
+            .iter()
+            .map(|x| rhai::Dynamic::from_float(*x))
+            .collect()
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Elements in row-major order
+    pub fn data(&self) -> &[rhai::FLOAT] {
+        &self.data
+    }
+
+    /// Consumes the matrix, returning its elements in row-major order
+    pub fn into_data(self) -> Vec<rhai::FLOAT> {
+        self.data
+    }
+
+    /// Mutable access to the elements in row-major order
+    pub fn data_mut(&mut self) -> &mut [rhai::FLOAT] {
+        &mut self.data
+    }
+
+    /// Returns the element at row `i` and column `j`.
+    pub fn get(&self, i: usize, j: usize) -> rhai::FLOAT {
+        self.data[i * self.cols + j]
+    }
+
+    /// Sets the element at row `i` and column `j`.
+    pub fn set(&mut self, i: usize, j: usize, value: rhai::FLOAT) {
+        self.data[i * self.cols + j] = value;
+    }
+
+    /// Returns the transpose of the matrix.
+    pub fn transpose(&self) -> Self {
+        let mut data = Vec::with_capacity(self.data.len());
+        for j in 0..self.cols {
+            data.extend((0..self.rows).map(|i| self.get(i, j)));
+        }
+        Matrix {
+            rows: self.cols,
+            cols: self.rows,
+            data,
+        }
+    }
+
+    /// Returns the matrix product of this matrix and `other`.
+    pub fn product(&self, other: &Matrix) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if self.cols != other.rows {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A {} x {} matrix cannot be multiplied by a {} x {} matrix",
+                    self.rows, self.cols, other.rows, other.cols
+                ),
+            )
+            .into());
+        }
+        let mut data = vec![0.0; self.rows * other.cols];
+        for i in 0..self.rows {
+            let out = &mut data[i * other.cols..(i + 1) * other.cols];
+            for k in 0..self.cols {
+                let x = self.get(i, k);
+                for (o, y) in out.iter_mut().zip(other.row_slice(k)) {
+                    *o += x * y;
+                }
+            }
+        }
+        Ok(Matrix {
+            rows: self.rows,
+            cols: other.cols,
+            data,
+        })
+    }
+
+    /// Returns the elements of row `i`.
+    fn row_slice(&self, i: usize) -> &[rhai::FLOAT] {
+        &self.data[i * self.cols..(i + 1) * self.cols]
+    }
+
+    /// Resolves a (possibly negative, counted from the end) Rhai row index.
+    fn row_index(&self, i: rhai::INT) -> Result<usize, Box<rhai::EvalAltResult>> {
+        let idx = if i < 0 { i + self.rows as rhai::INT } else { i };
+        if idx < 0 || idx >= self.rows as rhai::INT {
+            Err(rhai::EvalAltResult::ErrorIndexNotFound(
+                rhai::Dynamic::from_int(i),
+                rhai::Position::NONE,
+            )
+            .into())
+        } else {
+            Ok(idx as usize)
+        }
+    }
+}
+
+impl std::fmt::Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for i in 0..self.rows {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", &self.data[i * self.cols..(i + 1) * self.cols])?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Rewrites MATLAB-style matrix literals such as `matrix![1, 2; 3, 4]` in a script into calls to
+/// `matrix` with one array per row, such as `matrix([[1, 2], [3, 4]])`. Rows are separated by `;`
+/// and elements by `,`, and elements can be any expressions, including other matrix literals.
+/// Every row must have the same length, which `matrix` checks as the matrix is built. String
+/// literals and comments are left as they are.
+/// ```
+/// use rhai_sci::translate_matrix_literals;
+///
+/// let script = translate_matrix_literals("let m = matrix![1, 2; 3, x + 1];");
+/// assert_eq!(script, "let m = matrix([[1, 2], [3, x + 1]]);");
+/// ```
+pub fn translate_matrix_literals(script: &str) -> String {
+    let chars = script.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(script.len());
+    let mut idx = 0;
+    while idx < chars.len() {
+        // Copy literals and comments through to their end
+        if let Some(end) = crate::literal_end(&chars, idx) {
+            result.extend(&chars[idx..end]);
+            idx = end;
+            continue;
+        }
+
+        if let Some((rows, end)) = matrix_literal_rows(&chars, idx) {
+            let rows = rows
+                .iter()
+                .map(|row| translate_matrix_literals(row.trim_matches([' ', '\t'])))
+                .collect::<Vec<_>>();
+            result.push_str("matrix([[");
+            result.push_str(&rows.join("], ["));
+            result.push_str("]])");
+            idx = end;
+        } else {
+            result.push(chars[idx]);
+            idx += 1;
+        }
+    }
+    result
+}
+
+/// Splits the matrix literal that starts at `idx` in a script, if there is one there, into the
+/// text of its rows, and finds where it ends. A trailing `;` doesn't start another row.
+fn matrix_literal_rows(chars: &[char], idx: usize) -> Option<(Vec<String>, usize)> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    if !chars[idx..].starts_with(&['m', 'a', 't', 'r', 'i', 'x'])
+        || (idx > 0 && is_name(chars[idx - 1]))
+    {
+        return None;
+    }
+    let skip_space = |k: usize| (k..chars.len()).find(|k| !chars[*k].is_whitespace());
+    let bang = skip_space(idx + 6).filter(|k| chars[*k] == '!')?;
+    let open = skip_space(bang + 1).filter(|k| chars[*k] == '[')?;
+
+    let mut rows = vec![];
+    let mut depth = 0_usize;
+    let mut start = open + 1;
+    let mut k = start;
+    while k < chars.len() {
+        if let Some(end) = crate::literal_end(chars, k) {
+            k = end;
+            continue;
+        }
+        match chars[k] {
+            '(' | '[' | '{' => depth += 1,
+            ']' if depth == 0 => {
+                let row = chars[start..k].iter().collect::<String>();
+                if !row.trim().is_empty() || rows.is_empty() {
+                    rows.push(row);
+                }
+                return Some((rows, k + 1));
+            }
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => {
+                rows.push(chars[start..k].iter().collect());
+                start = k + 1;
+            }
+            _ => {}
+        }
+        k += 1;
+    }
+    None
+}
+
+/// Applies one of the element-wise functions, such as `plus`, to two values of which at least one
+/// is a `Matrix`, giving a `Matrix`.
+fn matrix_elementwise_op(
+    op: fn(rhai::Dynamic, rhai::Dynamic) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>>,
+    a: rhai::Dynamic,
+    b: rhai::Dynamic,
+) -> Result<Matrix, Box<rhai::EvalAltResult>> {
+    op(a, b).map(|result| result.cast::<Matrix>())
+}
+
+/// Applies a linear algebra function written for nested arrays to a `Matrix`.
+#[cfg(feature = "nalgebra")]
+fn matrix_as_array_and_do<T>(
+    m: &Matrix,
+    f: fn(&mut rhai::Array) -> Result<T, Box<rhai::EvalAltResult>>,
+) -> Result<T, Box<rhai::EvalAltResult>> {
+    f(&mut m.to_array())
+}
+
+#[export_module]
+pub mod matrix_type_functions {
+    use crate::elementwise_functions::{minus, plus, rdivide, times};
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, FLOAT, INT};
+
+    /// A dense matrix of FLOATs with contiguous storage, created with `matrix`.
+    pub type Matrix = crate::Matrix;
+
+    /// Converts a nested array into a `Matrix`. A flat array becomes a row vector. Elements are
+    /// stored as FLOATs in one contiguous block, which is much more compact and faster to work
+    /// with than an array of arrays.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert_eq(m.shape, [2, 2]);
+    /// assert_eq(m[1][0], 3.0);
+    /// ```
+    /// ```typescript
+    /// let m = matrix([1, 2, 3]);
+    /// assert_eq(m.shape, [1, 3]);
+    /// assert(m == matrix([[1.0, 2.0, 3.0]]));
+    /// assert(m != matrix([[1], [2], [3]]));
+    /// ```
+    #[rhai_fn(name = "matrix", return_raw)]
+    pub fn matrix(arr: Array) -> Result<Matrix, Box<EvalAltResult>> {
+        Matrix::from_array(&arr)
+    }
+
+    /// Creates a `Matrix` of zeros with the given number of rows and columns.
+    /// ```typescript
+    /// let m = matrix(2, 3);
+    /// assert_eq(to_array(m), [[0.0, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+    /// ```
+    #[rhai_fn(name = "matrix", return_raw)]
+    pub fn matrix_zeros(rows: INT, cols: INT) -> Result<Matrix, Box<EvalAltResult>> {
+        matrix_filled(rows, cols, Dynamic::from_float(0.0))
+    }
+
+    /// Creates a `Matrix` with the given number of rows and columns, and every element set to
+    /// `value`.
+    /// ```typescript
+    /// let m = matrix(2, 2, 7);
+    /// assert_eq(to_array(m), [[7.0, 7.0], [7.0, 7.0]]);
+    /// ```
+    #[rhai_fn(name = "matrix", return_raw)]
+    pub fn matrix_filled(
+        rows: INT,
+        cols: INT,
+        value: Dynamic,
+    ) -> Result<Matrix, Box<EvalAltResult>> {
+        if rows < 0 || cols < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The number of rows and columns must be non-negative",
+            )
+            .in_function("matrix")
+            .into());
+        }
+        crate::if_int_convert_to_float_and_do(value, |value| {
+            Ok(Matrix::filled(rows as usize, cols as usize, value))
+        })
+    }
+
+    /// Reads a `Matrix` from text written MATLAB style: elements are separated by spaces or
+    /// commas, and rows by semicolons or new lines. Surrounding brackets are optional, and every
+    /// row must have the same number of elements.
+    /// ```typescript
+    /// let m = mat("1 2; 3 4");
+    /// assert(m == matrix([[1, 2], [3, 4]]));
+    /// ```
+    /// ```typescript
+    /// let m = mat("[1.5, -2e3, 0]");
+    /// assert_eq(m.shape, [1, 3]);
+    /// assert_eq(m[0][1], -2000.0);
+    /// ```
+    #[rhai_fn(name = "mat", return_raw)]
+    pub fn mat(text: ImmutableString) -> Result<Matrix, Box<EvalAltResult>> {
+        let text = text.trim();
+        let body = text
+            .strip_prefix('[')
+            .and_then(|body| body.strip_suffix(']'))
+            .unwrap_or(text);
+        let mut data = vec![];
+        let mut rows = 0;
+        let mut cols = None;
+        for line in body.split([';', '\n']) {
+            let row = line
+                .split([' ', '\t', '\r', ','])
+                .filter(|x| !x.is_empty())
+                .map(|x| {
+                    x.parse::<rhai::FLOAT>().map_err(|_| {
+                        crate::LabError::new(
+                            crate::LabErrorKind::InvalidArgument,
+                            format!("Cannot read '{x}' as a number"),
+                        )
+                        .in_function("mat")
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if row.is_empty() {
+                continue;
+            }
+            rows += 1;
+            let expected = *cols.get_or_insert(row.len());
+            if row.len() != expected {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "Row {rows} has {} elements, but the rows before it have {expected}",
+                        row.len()
+                    ),
+                )
+                .in_function("mat")
+                .into());
+            }
+            data.extend(row);
+        }
+        Matrix::new(rows, cols.unwrap_or(0), data)
+    }
+
+    /// Converts a `Matrix` into a nested array of FLOATs, one array per row.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert_eq(to_array(m), [[1.0, 2.0], [3.0, 4.0]]);
+    /// ```
+    #[rhai_fn(name = "to_array", pure)]
+    pub fn to_array(m: &mut Matrix) -> Array {
+        m.to_array()
+    }
+
+    /// Returns the number of rows of a `Matrix`.
+    /// ```typescript
+    /// let m = matrix(2, 3);
+    /// assert_eq(m.rows, 2);
+    /// ```
+    #[rhai_fn(get = "rows", pure)]
+    pub fn rows(m: &mut Matrix) -> INT {
+        m.rows() as INT
+    }
+
+    /// Returns the number of columns of a `Matrix`.
+    /// ```typescript
+    /// let m = matrix(2, 3);
+    /// assert_eq(m.cols, 3);
+    /// ```
+    #[rhai_fn(get = "cols", pure)]
+    pub fn cols(m: &mut Matrix) -> INT {
+        m.cols() as INT
+    }
+
+    /// Returns the shape of a `Matrix` as `[rows, columns]`.
+    /// ```typescript
+    /// let m = matrix(2, 3);
+    /// assert_eq(m.shape, [2, 3]);
+    /// ```
+    #[rhai_fn(get = "shape", pure)]
+    pub fn shape(m: &mut Matrix) -> Array {
+        vec![
+            Dynamic::from_int(m.rows() as INT),
+            Dynamic::from_int(m.cols() as INT),
+        ]
+    }
+
+    /// Returns the transpose of a `Matrix`. It can also be written as the method `m.t()`, which
+    /// keeps chains of products readable.
+    /// ```typescript
+    /// let m = matrix([[1, 2, 3], [4, 5, 6]]);
+    /// assert(transpose(m) == matrix([[1, 4], [2, 5], [3, 6]]));
+    /// assert(m.t().t() == m);
+    /// ```
+    /// ```typescript
+    /// let a = matrix([[1, 2], [3, 4], [5, 6]]);
+    /// let b = matrix([[1, 0], [0, 1], [1, 1]]);
+    /// assert(transpose(transpose(a) * b) == matrix([[6, 8], [8, 10]]));
+    /// assert((a.t() * b).t() == matrix([[6, 8], [8, 10]]));
+    /// ```
+    #[rhai_fn(name = "transpose", name = "t", pure)]
+    pub fn transpose(m: &mut Matrix) -> Matrix {
+        m.transpose()
+    }
+
+    /// Returns the size of a `Matrix` as `[rows, columns]`.
+    /// ```typescript
+    /// let m = matrix([[1, 2, 3]]);
+    /// assert_eq(size(m), [1, 3]);
+    /// ```
+    #[rhai_fn(name = "size", pure)]
+    pub fn size(m: &mut Matrix) -> Array {
+        shape(m)
+    }
+
+    /// Returns a row of a `Matrix` as an array, so that elements can be read with `m[i][j]`.
+    /// Negative indices count from the end.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert_eq(m[0], [1.0, 2.0]);
+    /// assert_eq(m[-1][1], 4.0);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get_row(m: &mut Matrix, i: INT) -> Result<Array, Box<EvalAltResult>> {
+        Ok(m.row(m.row_index(i)?))
+    }
+
+    /// Returns the rows of a `Matrix` in a range as a new `Matrix`. The range is clamped to the
+    /// matrix.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4], [5, 6]]);
+    /// assert_eq(to_array(m[1..3]), [[3.0, 4.0], [5.0, 6.0]]);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get_rows(
+        m: &mut Matrix,
+        rows: std::ops::Range<INT>,
+    ) -> Result<Matrix, Box<EvalAltResult>> {
+        slice(m, Dynamic::from(rows), Dynamic::from(0..m.cols() as INT))
+    }
+
+    /// Returns the rows of a `Matrix` in an inclusive range as a new `Matrix`.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4], [5, 6]]);
+    /// assert_eq(m[0..=1].shape, [2, 2]);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get_rows_inclusive(
+        m: &mut Matrix,
+        rows: std::ops::RangeInclusive<INT>,
+    ) -> Result<Matrix, Box<EvalAltResult>> {
+        slice(m, Dynamic::from(rows), Dynamic::from(0..m.cols() as INT))
+    }
+
+    /// Returns the block of a `Matrix` picked out by `rows` and `cols` as a new `Matrix`. Each
+    /// can be an integer, a range such as `1..3` or `1..=2`, or an array of indices.
+    /// ```typescript
+    /// let m = matrix([[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+    /// let block = slice(m, 1..3, 0..2);
+    /// assert_eq(to_array(block), [[4.0, 5.0], [7.0, 8.0]]);
+    /// ```
+    #[rhai_fn(name = "slice", return_raw, pure)]
+    pub fn slice(
+        m: &mut Matrix,
+        rows: Dynamic,
+        cols: Dynamic,
+    ) -> Result<Matrix, Box<EvalAltResult>> {
+        let rows = crate::selection_indices(&rows, m.rows())?;
+        let cols = crate::selection_indices(&cols, m.cols())?;
+        let data = rows
+            .iter()
+            .flat_map(|i| cols.iter().map(|j| m.get(*i, *j)))
+            .collect();
+        Matrix::new(rows.len(), cols.len(), data)
+    }
+
+    /// Replaces the block of a `Matrix` picked out by `rows` and `cols`, in place, with `block`:
+    /// a `Matrix` or nested array of the same size, or a single value to fill the block with.
+    /// ```typescript
+    /// let m = matrix(3, 3);
+    /// set_slice(m, 0..2, 1..3, [[1, 2], [3, 4]]);
+    /// set_slice(m, 2, 0..3, 9);
+    /// assert_eq(to_array(m), [[0.0, 1.0, 2.0], [0.0, 3.0, 4.0], [9.0, 9.0, 9.0]]);
+    /// ```
+    #[rhai_fn(name = "set_slice", return_raw)]
+    pub fn set_slice(
+        m: &mut Matrix,
+        rows: Dynamic,
+        cols: Dynamic,
+        block: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let rows = crate::selection_indices(&rows, m.rows())?;
+        let cols = crate::selection_indices(&cols, m.cols())?;
+        let block = if block.is::<Matrix>() {
+            block.cast::<Matrix>()
+        } else if block.is_array() {
+            Matrix::from_array(&block.into_array().unwrap())?
+        } else {
+            let value = crate::if_int_convert_to_float_and_do(block, Ok)?;
+            Matrix::filled(rows.len(), cols.len(), value)
+        };
+        if block.rows() != rows.len() || block.cols() != cols.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The block is {} x {}, but {} rows and {} columns are selected",
+                    block.rows(),
+                    block.cols(),
+                    rows.len(),
+                    cols.len()
+                ),
+            )
+            .in_function("set_slice")
+            .into());
+        }
+        for (bi, i) in rows.iter().enumerate() {
+            for (bj, j) in cols.iter().enumerate() {
+                m.set(*i, *j, block.get(bi, bj));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces a row of a `Matrix`, which also makes element assignment `m[i][j] = x` work.
+    /// ```typescript
+    /// let m = matrix(2, 2);
+    /// m[1][0] = 5;
+    /// m[0] = [1, 2];
+    /// assert_eq(to_array(m), [[1.0, 2.0], [5.0, 0.0]]);
+    /// ```
+    #[rhai_fn(index_set, return_raw)]
+    pub fn set_row(m: &mut Matrix, i: INT, row: Array) -> Result<(), Box<EvalAltResult>> {
+        let i = m.row_index(i)?;
+        if row.len() != m.cols() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The row has {} elements, but the matrix has {} columns",
+                    row.len(),
+                    m.cols()
+                ),
+            )
+            .in_function("set_row")
+            .with_argument("row")
+            .into());
+        }
+        for (j, x) in row.into_iter().enumerate() {
+            let x = crate::if_int_convert_to_float_and_do(x, Ok)?;
+            m.set(i, j, x);
+        }
+        Ok(())
+    }
+
+    /// Returns the matrix product of two matrices, which can also be written as `a * b`.
+    /// ```typescript
+    /// let a = matrix([[1, 2], [3, 4]]);
+    /// let b = matrix([[0, 1], [1, 0]]);
+    /// assert(mtimes(a, b) == matrix([[2, 1], [4, 3]]));
+    /// assert(a * b == matrix([[2, 1], [4, 3]]));
+    /// ```
+    #[rhai_fn(name = "mtimes", name = "*", return_raw, pure)]
+    pub fn mtimes(a: &mut Matrix, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        a.product(&b)
+    }
+
+    /// Adds two matrices, or a matrix and a number, element by element, broadcasting them to a
+    /// common shape as `plus` does.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert(m + m == matrix([[2, 4], [6, 8]]));
+    /// assert(m + 1 == matrix([[2, 3], [4, 5]]));
+    /// assert(0.5 + m == matrix([[1.5, 2.5], [3.5, 4.5]]));
+    /// m += 1;
+    /// assert(m == matrix([[2, 3], [4, 5]]));
+    /// assert(m + matrix([10, 20]) == matrix([[12, 23], [14, 25]]));
+    /// ```
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add(a: Matrix, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(plus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Adds a matrix and a number element by element, as `plus` does.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_int(a: Matrix, b: INT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(plus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Adds a matrix and a number element by element, as `plus` does.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add_float(a: Matrix, b: FLOAT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(plus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Adds a number and a matrix element by element, as `plus` does.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn int_add(a: INT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(plus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Adds a number and a matrix element by element, as `plus` does.
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn float_add(a: FLOAT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(plus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Subtracts two matrices, or a matrix and a number, element by element, broadcasting them
+    /// to a common shape as `minus` does. A matrix on its own can be negated with `-`.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert(m - m == matrix(2, 2));
+    /// assert(m - 1 == matrix([[0, 1], [2, 3]]));
+    /// assert(10 - m == matrix([[9, 8], [7, 6]]));
+    /// assert(-m == matrix([[-1, -2], [-3, -4]]));
+    /// ```
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract(a: Matrix, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(minus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Subtracts a number from a matrix element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_int(a: Matrix, b: INT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(minus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Subtracts a number from a matrix element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract_float(a: Matrix, b: FLOAT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(minus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Subtracts a matrix from a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn int_subtract(a: INT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(minus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Subtracts a matrix from a number element by element, as `minus` does.
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn float_subtract(a: FLOAT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(minus, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Negates every element of a matrix.
+    #[rhai_fn(name = "-")]
+    pub fn negate(a: Matrix) -> Matrix {
+        let mut a = a;
+        a.data_mut().iter_mut().for_each(|x| *x = -*x);
+        a
+    }
+
+    /// Multiplies every element of a matrix by a number, as `times` does.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert(m * 2 == matrix([[2, 4], [6, 8]]));
+    /// assert(0.5 * m == matrix([[0.5, 1], [1.5, 2]]));
+    /// assert(m .* m == matrix([[1, 4], [9, 16]]));
+    /// ```
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_int(a: Matrix, b: INT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(times, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Multiplies every element of a matrix by a number, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply_float(a: Matrix, b: FLOAT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(times, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Multiplies every element of a matrix by a number, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn int_multiply(a: INT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(times, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Multiplies every element of a matrix by a number, as `times` does.
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn float_multiply(a: FLOAT, b: Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(times, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Divides every element of a matrix by a number, as `rdivide` does. Two matrices are
+    /// divided element by element with `./` or `rdivide`.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert(m / 2 == matrix([[0.5, 1], [1.5, 2]]));
+    /// assert(m ./ m == matrix([[1, 1], [1, 1]]));
+    /// ```
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_int(a: Matrix, b: INT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(rdivide, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Divides every element of a matrix by a number, as `rdivide` does.
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_float(a: Matrix, b: FLOAT) -> Result<Matrix, Box<EvalAltResult>> {
+        super::matrix_elementwise_op(rdivide, Dynamic::from(a), Dynamic::from(b))
+    }
+
+    /// Returns the inverse of a square `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[2, 0], [0, 4]]);
+    /// assert(inv(a) == matrix([[0.5, 0], [0, 0.25]]));
+    /// assert(a * inv(a) == matrix([[1, 0], [0, 1]]));
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "inv", return_raw, pure)]
+    pub fn inv(m: &mut Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        Matrix::from_array(&super::matrix_as_array_and_do(
+            m,
+            crate::matrix_functions::invert_matrix,
+        )?)
+    }
+
+    /// Returns the Moore-Penrose pseudo-inverse of a `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[2, 0], [0, 4], [0, 0]]);
+    /// assert_eq(pinv(a).shape, [2, 3]);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "pinv", return_raw, pure)]
+    pub fn pinv(m: &mut Matrix) -> Result<Matrix, Box<EvalAltResult>> {
+        Matrix::from_array(&super::matrix_as_array_and_do(
+            m,
+            crate::matrix_functions::pseudo_inverse,
+        )?)
+    }
+
+    /// Returns the determinant of a square `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[2, 1], [1, 3]]);
+    /// assert(abs(det(a) - 5.0) < 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "det", return_raw, pure)]
+    pub fn det(m: &mut Matrix) -> Result<FLOAT, Box<EvalAltResult>> {
+        super::matrix_as_array_and_do(m, crate::matrix_functions::determinant)
+    }
+
+    /// Returns the 2-norm condition number of a `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[2, 0], [0, 1]]);
+    /// assert(abs(cond(a) - 2.0) < 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "cond", return_raw, pure)]
+    pub fn cond(m: &mut Matrix) -> Result<FLOAT, Box<EvalAltResult>> {
+        super::matrix_as_array_and_do(m, crate::matrix_functions::cond)
+    }
+
+    /// Returns the 2-norm of a `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[3, 0], [0, 4]]);
+    /// assert(abs(norm(a) - 4.0) < 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "norm", return_raw, pure)]
+    pub fn norm(m: &mut Matrix) -> Result<FLOAT, Box<EvalAltResult>> {
+        super::matrix_as_array_and_do(m, crate::matrix_functions::norm)
+    }
+
+    /// Returns the eigenvalues of a square `Matrix`, as `eig` does for nested arrays.
+    /// ```typescript
+    /// let a = matrix([[2, 1], [1, 2]]);
+    /// let values = eig(a);
+    /// assert(abs(values[0] - 1.0) < 1e-6 && abs(values[1] - 3.0) < 1e-6);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "eig", return_raw, pure)]
+    pub fn eig(m: &mut Matrix) -> Result<Array, Box<EvalAltResult>> {
+        super::matrix_as_array_and_do(m, crate::matrix_functions::eig)
+    }
+
+    /// Solves the square system `a * x = b` for `x`, where `b` is a vector or a matrix, as
+    /// `solve` does for nested arrays.
+    /// ```typescript
+    /// let a = matrix([[2, 0], [0, 4]]);
+    /// assert_eq(solve(a, [1, 1]), [0.5, 0.25]);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve(m: &mut Matrix, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        crate::matrix_functions::solve(&mut m.to_array(), b)
+    }
+
+    /// Returns the sum of the diagonal of a square `Matrix`.
+    /// ```typescript
+    /// let a = matrix([[1, 2], [3, 4]]);
+    /// assert_eq(trace(a), 5.0);
+    /// ```
+    #[rhai_fn(name = "trace", return_raw, pure)]
+    pub fn trace(m: &mut Matrix) -> Result<FLOAT, Box<EvalAltResult>> {
+        if m.rows() != m.cols() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The trace needs a square matrix, but this one is {} x {}",
+                    m.rows(),
+                    m.cols()
+                ),
+            )
+            .in_function("trace")
+            .with_argument("matrix")
+            .into());
+        }
+        Ok((0..m.rows()).map(|i| m.get(i, i)).sum())
+    }
+
+    /// Tests whether two matrices have the same shape and elements.
+    #[rhai_fn(name = "==", pure)]
+    pub fn eq(a: &mut Matrix, b: Matrix) -> bool {
+        *a == b
+    }
+
+    /// Tests whether two matrices differ in shape or elements.
+    #[rhai_fn(name = "!=", pure)]
+    pub fn ne(a: &mut Matrix, b: Matrix) -> bool {
+        *a != b
+    }
+
+    /// Formats a `Matrix` as a nested list of rows.
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert_eq(to_string(m), "[[1.0, 2.0], [3.0, 4.0]]");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(m: &mut Matrix) -> ImmutableString {
+        m.to_string().into()
+    }
+}
@@ -0,0 +1,836 @@
+use rhai::plugin::*;
+
+/// Checks that the true and predicted values for a metric are the same length and not empty.
+fn check_metric_inputs(
+    y_true: &rhai::Array,
+    y_pred: &rhai::Array,
+    function: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    if y_true.len() != y_pred.len() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!(
+                "There are {} true values but {} predicted values",
+                y_true.len(),
+                y_pred.len()
+            ),
+        )
+        .in_function(function)
+        .into());
+    }
+    if y_true.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "Cannot compute a metric of empty arrays",
+        )
+        .in_function(function)
+        .into());
+    }
+    Ok(())
+}
+
+/// Counts how often each true label was predicted as each label. Returns the sorted, distinct
+/// labels of both arrays and any `extra` labels, and the counts with a row for each true label
+/// and a column for each predicted label.
+fn confusion_counts(
+    y_true: &rhai::Array,
+    y_pred: &rhai::Array,
+    extra: &[rhai::Dynamic],
+    function: &str,
+) -> Result<(rhai::Array, Vec<Vec<usize>>), Box<rhai::EvalAltResult>> {
+    check_metric_inputs(y_true, y_pred, function)?;
+    let all = [y_true.as_slice(), y_pred.as_slice(), extra].concat();
+    let labels = crate::sorted_categories(&all)?;
+    let true_codes = crate::category_codes(y_true, &labels)?;
+    let pred_codes = crate::category_codes(y_pred, &labels)?;
+    let mut counts = vec![vec![0; labels.len()]; labels.len()];
+    for (t, p) in true_codes.into_iter().zip(pred_codes) {
+        counts[t][p] += 1;
+    }
+    Ok((labels, counts))
+}
+
+/// Returns the precision, recall, and F1 score of label `k` from confusion counts. Precision is
+/// `NaN` if the label was never predicted, and recall is `NaN` if it never occurs.
+fn label_scores(counts: &[Vec<usize>], k: usize) -> (rhai::FLOAT, rhai::FLOAT, rhai::FLOAT) {
+    let tp = counts[k][k] as rhai::FLOAT;
+    let predicted = counts.iter().map(|row| row[k]).sum::<usize>() as rhai::FLOAT;
+    let actual = counts[k].iter().sum::<usize>() as rhai::FLOAT;
+    let ratio = |a: rhai::FLOAT, b: rhai::FLOAT| if b == 0.0 { rhai::FLOAT::NAN } else { a / b };
+    let (precision, recall) = (ratio(tp, predicted), ratio(tp, actual));
+    let f1 = if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    };
+    (precision, recall, f1)
+}
+
+/// Reads the true classes for `roc` and `auc`, which are `true` or `1` for the positive class and
+/// `false` or `0` for the negative one.
+fn binary_classes(
+    y_true: &rhai::Array,
+    function: &str,
+) -> Result<Vec<bool>, Box<rhai::EvalAltResult>> {
+    y_true
+        .iter()
+        .map(|y| match (y.as_bool(), y.as_int(), y.as_float()) {
+            (Ok(b), _, _) => Ok(b),
+            (_, Ok(i), _) if i == 0 || i == 1 => Ok(i == 1),
+            (_, _, Ok(f)) if f == 0.0 || f == 1.0 => Ok(f == 1.0),
+            _ => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The true classes must be booleans or 0 and 1, not {y}"),
+            )
+            .in_function(function)
+            .with_argument("y_true")
+            .into()),
+        })
+        .collect()
+}
+
+/// The points of a receiver operating characteristic curve.
+struct RocCurve {
+    /// The false positive rate at each threshold
+    fpr: Vec<rhai::FLOAT>,
+    /// The true positive rate at each threshold
+    tpr: Vec<rhai::FLOAT>,
+    /// The scores at or above which a value is predicted to be positive, in descending order
+    thresholds: Vec<rhai::FLOAT>,
+}
+
+/// Computes the receiver operating characteristic curve of scores for binary classes, with one
+/// point per distinct score, after a first point for a threshold of infinity.
+fn roc_curve(
+    y_true: &rhai::Array,
+    scores: &mut rhai::Array,
+    function: &str,
+) -> Result<RocCurve, Box<rhai::EvalAltResult>> {
+    check_metric_inputs(y_true, scores, function)?;
+    let classes = binary_classes(y_true, function)?;
+    let scores = crate::if_list_convert_to_vec_float_and_do(scores, Ok)?;
+    if scores.iter().any(|s| s.is_nan()) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "The scores cannot be NaN",
+        )
+        .in_function(function)
+        .with_argument("scores")
+        .into());
+    }
+    let positives = classes.iter().filter(|c| **c).count() as rhai::FLOAT;
+    let negatives = classes.len() as rhai::FLOAT - positives;
+    if positives == 0.0 || negatives == 0.0 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "The true classes must include both positive and negative values",
+        )
+        .in_function(function)
+        .with_argument("y_true")
+        .into());
+    }
+
+    let mut order = crate::argsort_floats(&scores);
+    order.reverse();
+    let mut curve = RocCurve {
+        fpr: vec![0.0],
+        tpr: vec![0.0],
+        thresholds: vec![rhai::FLOAT::INFINITY],
+    };
+    let (mut tp, mut fp) = (0.0, 0.0);
+    for (n, &i) in order.iter().enumerate() {
+        if classes[i] {
+            tp += 1.0;
+        } else {
+            fp += 1.0;
+        }
+        // Values with the same score are all predicted the same way, so they share a point
+        if order.get(n + 1).is_some_and(|&j| scores[j] == scores[i]) {
+            continue;
+        }
+        curve.fpr.push(fp / negatives);
+        curve.tpr.push(tp / positives);
+        curve.thresholds.push(scores[i]);
+    }
+    Ok(curve)
+}
+
+/// Returns the area under a curve through the points `(x, y)` by the trapezoidal rule.
+fn trapezoid_area(x: &[rhai::FLOAT], y: &[rhai::FLOAT]) -> rhai::FLOAT {
+    crate::stable_sum(
+        x.windows(2)
+            .zip(y.windows(2))
+            .map(|(x, y)| (x[1] - x[0]) * (y[0] + y[1]) / 2.0),
+    )
+}
+
+/// The ways `rmse`, `mae`, `mape`, and `r2` can handle `NaN`s.
+const NAN_FLAGS: [&str; 2] = ["includenan", "omitnan"];
+
+/// Reads the true and predicted values of a regression metric as floats. With the `"omitnan"`
+/// flag, pairs where either value is `NaN` are dropped; with `"includenan"`, they are kept, so
+/// the metric is `NaN`.
+fn regression_values(
+    y_true: &mut rhai::Array,
+    y_pred: &mut rhai::Array,
+    nan_flag: &str,
+    function: &str,
+) -> Result<(Vec<rhai::FLOAT>, Vec<rhai::FLOAT>), Box<rhai::EvalAltResult>> {
+    if !NAN_FLAGS.contains(&nan_flag) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Unknown NaN flag '{nan_flag}', expected 'includenan' or 'omitnan'"),
+        )
+        .in_function(function)
+        .with_argument("nan_flag")
+        .into());
+    }
+    check_metric_inputs(y_true, y_pred, function)?;
+    let t = crate::if_list_convert_to_vec_float_and_do(y_true, Ok)?;
+    let p = crate::if_list_convert_to_vec_float_and_do(y_pred, Ok)?;
+    if nan_flag == "includenan" {
+        return Ok((t, p));
+    }
+    let (t, p): (Vec<_>, Vec<_>) = t
+        .into_iter()
+        .zip(p)
+        .filter(|(t, p)| !t.is_nan() && !p.is_nan())
+        .unzip();
+    if t.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "There are no pairs of values without NaN",
+        )
+        .in_function(function)
+        .into());
+    }
+    Ok((t, p))
+}
+
+/// Returns the mean of `f(true, predicted)` over the pairs of values.
+fn mean_of_pairs<F>(t: &[rhai::FLOAT], p: &[rhai::FLOAT], f: F) -> rhai::FLOAT
+where
+    F: Fn(rhai::FLOAT, rhai::FLOAT) -> rhai::FLOAT,
+{
+    crate::stable_sum(t.iter().zip(p).map(|(t, p)| f(*t, *p))) / t.len() as rhai::FLOAT
+}
+
+/// Returns the coefficient of determination of predictions `p` of values `t`, which is `NaN` if
+/// the values are all the same.
+fn r_squared(t: &[rhai::FLOAT], p: &[rhai::FLOAT]) -> rhai::FLOAT {
+    let mean = crate::stable_sum_slice(t) / t.len() as rhai::FLOAT;
+    let residual = crate::stable_sum(t.iter().zip(p).map(|(t, p)| (t - p).powi(2)));
+    let total = crate::stable_sum(t.iter().map(|t| (t - mean).powi(2)));
+    if total == 0.0 {
+        rhai::FLOAT::NAN
+    } else {
+        1.0 - residual / total
+    }
+}
+
+/// Checks the number of values and folds for `kfold` and `cross_validate`, returning them as
+/// `usize`s.
+fn check_folds(
+    n: rhai::INT,
+    k: rhai::INT,
+    function: &str,
+) -> Result<(usize, usize), Box<rhai::EvalAltResult>> {
+    if k < 2 || k > n {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!(
+                "The number of folds must be between 2 and the number of values ({n}), not {k}"
+            ),
+        )
+        .in_function(function)
+        .with_argument("k")
+        .into());
+    }
+    Ok((n as usize, k as usize))
+}
+
+/// Splits the indices in `order` into `k` folds of consecutive indices, the first `len % k` of
+/// them one longer than the rest. Returns the sorted training and test indices of each fold,
+/// where each fold is the test set once and the training set is everything else.
+fn fold_splits(order: &[usize], k: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let (size, extra) = (order.len() / k, order.len() % k);
+    let mut start = 0;
+    (0..k)
+        .map(|fold| {
+            let end = start + size + usize::from(fold < extra);
+            let mut test = order[start..end].to_vec();
+            let mut train = [&order[..start], &order[end..]].concat();
+            test.sort_unstable();
+            train.sort_unstable();
+            start = end;
+            (train, test)
+        })
+        .collect()
+}
+
+/// Converts indices to an array of INTs.
+fn index_array(idx: &[usize]) -> rhai::Array {
+    idx.iter()
+        .map(|i| rhai::Dynamic::from_int(*i as rhai::INT))
+        .collect()
+}
+
+/// Returns the elements (or, for a matrix, the rows) of an array at the given indices.
+fn select_elements(arr: &rhai::Array, idx: &[usize]) -> rhai::Array {
+    idx.iter().map(|i| arr[*i].clone()).collect()
+}
+
+/// Returns the folds of `fold_splits` as an array of maps with `train` and `test` indices.
+fn folds_to_array(folds: Vec<(Vec<usize>, Vec<usize>)>) -> rhai::Array {
+    folds
+        .into_iter()
+        .map(|(train, test)| {
+            let mut fold = rhai::Map::new();
+            fold.insert(
+                "train".into(),
+                rhai::Dynamic::from_array(index_array(&train)),
+            );
+            fold.insert("test".into(), rhai::Dynamic::from_array(index_array(&test)));
+            rhai::Dynamic::from_map(fold)
+        })
+        .collect()
+}
+
+/// Splits `x` and `y` into a training and a test set, given the indices of the test elements,
+/// returning a map of `x_train`, `x_test`, `y_train`, and `y_test`.
+#[cfg(feature = "rand")]
+fn split_by_test_indices(x: &rhai::Array, y: &rhai::Array, mut test: Vec<usize>) -> rhai::Map {
+    test.sort_unstable();
+    let mut is_test = vec![false; y.len()];
+    for i in &test {
+        is_test[*i] = true;
+    }
+    let train = (0..y.len()).filter(|i| !is_test[*i]).collect::<Vec<_>>();
+    let mut result = rhai::Map::new();
+    result.insert(
+        "x_train".into(),
+        rhai::Dynamic::from_array(select_elements(x, &train)),
+    );
+    result.insert(
+        "x_test".into(),
+        rhai::Dynamic::from_array(select_elements(x, &test)),
+    );
+    result.insert(
+        "y_train".into(),
+        rhai::Dynamic::from_array(select_elements(y, &train)),
+    );
+    result.insert(
+        "y_test".into(),
+        rhai::Dynamic::from_array(select_elements(y, &test)),
+    );
+    result
+}
+
+#[export_module]
+pub mod metrics_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, FnPtr, ImmutableString, Map, NativeCallContext, FLOAT, INT,
+    };
+
+    /// Counts how often each true class was predicted as each class, returning a map with the
+    /// sorted, distinct `labels` of both arrays and a `matrix` of counts with a row for each true
+    /// label and a column for each predicted label. The diagonal counts the correct predictions.
+    /// Classes can be numbers, strings, characters, or booleans, but not a mix of them.
+    /// ```typescript
+    /// let y_true = ["cat", "dog", "dog", "bird", "cat"];
+    /// let y_pred = ["cat", "dog", "cat", "bird", "cat"];
+    /// let c = confusion_matrix(y_true, y_pred);
+    /// assert_eq(c.labels, ["bird", "cat", "dog"]);
+    /// assert_eq(c.matrix, [[1, 0, 0],
+    ///                      [0, 2, 0],
+    ///                      [0, 1, 1]]);
+    /// ```
+    #[rhai_fn(name = "confusion_matrix", return_raw)]
+    pub fn confusion_matrix(y_true: Array, y_pred: Array) -> Result<Map, Box<EvalAltResult>> {
+        let (labels, counts) = super::confusion_counts(&y_true, &y_pred, &[], "confusion_matrix")?;
+        let matrix = counts
+            .into_iter()
+            .map(|row| {
+                Dynamic::from_array(
+                    row.into_iter()
+                        .map(|c| Dynamic::from_int(c as INT))
+                        .collect(),
+                )
+            })
+            .collect::<Array>();
+        let mut result = Map::new();
+        result.insert("labels".into(), Dynamic::from_array(labels));
+        result.insert("matrix".into(), Dynamic::from_array(matrix));
+        Ok(result)
+    }
+
+    /// Scores the predictions of each class, returning a map with the sorted, distinct `labels`
+    /// of both arrays and, for each label, its `precision` (the fraction of predictions of the
+    /// label that were right), `recall` (the fraction of true values of the label that were
+    /// predicted), `f1` score (the harmonic mean of precision and recall), and `support` (the
+    /// number of true values of the label). Precision is `NaN` for a label that was never
+    /// predicted.
+    /// ```typescript
+    /// let y_true = [0, 0, 1, 1, 1, 2];
+    /// let y_pred = [0, 1, 1, 1, 0, 1];
+    /// let s = precision_recall_f1(y_true, y_pred);
+    /// assert_eq(s.labels, [0, 1, 2]);
+    /// assert_eq(s.precision[0], 0.5);
+    /// assert_eq(s.recall[1], 2.0 / 3.0);
+    /// assert_eq(s.support, [2, 3, 1]);
+    /// assert(s.precision[2].is_nan());
+    /// ```
+    #[rhai_fn(name = "precision_recall_f1", return_raw)]
+    pub fn precision_recall_f1(y_true: Array, y_pred: Array) -> Result<Map, Box<EvalAltResult>> {
+        let (labels, counts) =
+            super::confusion_counts(&y_true, &y_pred, &[], "precision_recall_f1")?;
+        let mut columns = [Array::new(), Array::new(), Array::new()];
+        for k in 0..labels.len() {
+            let (precision, recall, f1) = super::label_scores(&counts, k);
+            columns[0].push(Dynamic::from_float(precision));
+            columns[1].push(Dynamic::from_float(recall));
+            columns[2].push(Dynamic::from_float(f1));
+        }
+        let support = counts
+            .iter()
+            .map(|row| Dynamic::from_int(row.iter().sum::<usize>() as INT))
+            .collect::<Array>();
+        let [precision, recall, f1] = columns;
+        let mut result = Map::new();
+        result.insert("labels".into(), Dynamic::from_array(labels));
+        result.insert("precision".into(), Dynamic::from_array(precision));
+        result.insert("recall".into(), Dynamic::from_array(recall));
+        result.insert("f1".into(), Dynamic::from_array(f1));
+        result.insert("support".into(), Dynamic::from_array(support));
+        Ok(result)
+    }
+
+    /// Scores the predictions of one `positive` class, as in a binary classification problem,
+    /// returning a map with its `precision`, `recall`, and `f1` score.
+    /// ```typescript
+    /// let y_true = [true, true, false, false, true];
+    /// let y_pred = [true, false, true, false, true];
+    /// let s = precision_recall_f1(y_true, y_pred, true);
+    /// assert_eq(s.precision, 2.0 / 3.0);
+    /// assert_eq(s.recall, 2.0 / 3.0);
+    /// assert_approx_eq(s.f1, 2.0 / 3.0);
+    /// ```
+    #[rhai_fn(name = "precision_recall_f1", return_raw)]
+    pub fn precision_recall_f1_positive(
+        y_true: Array,
+        y_pred: Array,
+        positive: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let (labels, counts) = super::confusion_counts(
+            &y_true,
+            &y_pred,
+            std::slice::from_ref(&positive),
+            "precision_recall_f1",
+        )?;
+        let k = crate::category_codes(&vec![positive], &labels)?[0];
+        let (precision, recall, f1) = super::label_scores(&counts, k);
+        let mut result = Map::new();
+        result.insert("precision".into(), Dynamic::from_float(precision));
+        result.insert("recall".into(), Dynamic::from_float(recall));
+        result.insert("f1".into(), Dynamic::from_float(f1));
+        Ok(result)
+    }
+
+    /// Computes the receiver operating characteristic (ROC) curve of a binary classifier from
+    /// the true classes (`true` or `1` for positive, `false` or `0` for negative) and the scores
+    /// it gave each value, where higher scores mean more likely positive. Returns a map of the
+    /// false positive rates `fpr`, true positive rates `tpr`, and the `thresholds` they are
+    /// reached at when every value scoring at least the threshold is predicted positive. The
+    /// thresholds are the distinct scores in descending order, after a first point at infinity
+    /// where nothing is predicted positive.
+    /// ```typescript
+    /// let curve = roc([0, 0, 1, 1], [0.1, 0.4, 0.35, 0.8]);
+    /// assert_eq(curve.fpr, [0.0, 0.0, 0.5, 0.5, 1.0]);
+    /// assert_eq(curve.tpr, [0.0, 0.5, 0.5, 1.0, 1.0]);
+    /// assert_eq(curve.thresholds, [1.0 / 0.0, 0.8, 0.4, 0.35, 0.1]);
+    /// ```
+    #[rhai_fn(name = "roc", return_raw)]
+    pub fn roc(y_true: Array, mut scores: Array) -> Result<Map, Box<EvalAltResult>> {
+        let curve = super::roc_curve(&y_true, &mut scores, "roc")?;
+        let to_array =
+            |v: Vec<FLOAT>| Dynamic::from_array(v.into_iter().map(Dynamic::from_float).collect());
+        let mut result = Map::new();
+        result.insert("fpr".into(), to_array(curve.fpr));
+        result.insert("tpr".into(), to_array(curve.tpr));
+        result.insert("thresholds".into(), to_array(curve.thresholds));
+        Ok(result)
+    }
+
+    /// Returns the area under the ROC curve of a binary classifier, as computed by `roc`, which
+    /// is the probability that a random positive value scores higher than a random negative
+    /// one. It is 1.0 for a perfect classifier and 0.5 for one that guesses.
+    /// ```typescript
+    /// assert_eq(auc([0, 0, 1, 1], [0.1, 0.4, 0.35, 0.8]), 0.75);
+    /// assert_eq(auc([false, true], [0.2, 0.9]), 1.0);
+    /// ```
+    #[rhai_fn(name = "auc", return_raw)]
+    pub fn auc(y_true: Array, mut scores: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        let curve = super::roc_curve(&y_true, &mut scores, "auc")?;
+        Ok(super::trapezoid_area(&curve.fpr, &curve.tpr))
+    }
+
+    /// Returns the area under a curve returned by `roc`.
+    /// ```typescript
+    /// let curve = roc([0, 0, 1, 1], [0.1, 0.4, 0.35, 0.8]);
+    /// assert_eq(auc(curve), 0.75);
+    /// ```
+    #[rhai_fn(name = "auc", return_raw)]
+    pub fn auc_of_curve(curve: Map) -> Result<FLOAT, Box<EvalAltResult>> {
+        let read = |key: &str| -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let mut values = curve
+                .get(key)
+                .and_then(|v| v.clone().into_array().ok())
+                .ok_or_else(|| {
+                    EvalAltResult::from(
+                        crate::LabError::new(
+                            crate::LabErrorKind::InvalidArgument,
+                            format!("The curve has no array '{key}'"),
+                        )
+                        .in_function("auc")
+                        .with_argument("curve"),
+                    )
+                })?;
+            crate::if_list_convert_to_vec_float_and_do(&mut values, Ok)
+        };
+        let (fpr, tpr) = (read("fpr")?, read("tpr")?);
+        if fpr.len() != tpr.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The curve has {} fpr values but {} tpr values",
+                    fpr.len(),
+                    tpr.len()
+                ),
+            )
+            .in_function("auc")
+            .with_argument("curve")
+            .into());
+        }
+        Ok(super::trapezoid_area(&fpr, &tpr))
+    }
+
+    /// Returns the root mean squared error of predictions of the true values. A `NaN` in either
+    /// array makes the result `NaN`; use `rmse(y_true, y_pred, "omitnan")` to skip those pairs.
+    /// ```typescript
+    /// let e = rmse([1.0, 2.0, 3.0, 4.0], [1.0, 2.0, 3.0, 6.0]);
+    /// assert_eq(e, 1.0);
+    /// ```
+    #[rhai_fn(name = "rmse", return_raw)]
+    pub fn rmse(y_true: Array, y_pred: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        rmse_with_flag(y_true, y_pred, "includenan".into())
+    }
+
+    /// Returns the root mean squared error of predictions of the true values, with `nan_flag`
+    /// saying how to handle `NaN`s: `"includenan"` gives a `NaN` result if there are any, and
+    /// `"omitnan"` skips the pairs of values where either is `NaN`.
+    /// ```typescript
+    /// let e = rmse([1.0, 2.0, 0.0/0.0, 4.0], [1.0, 2.0, 3.0, 7.0], "omitnan");
+    /// assert_eq(e, sqrt(3.0));
+    /// ```
+    #[rhai_fn(name = "rmse", return_raw)]
+    pub fn rmse_with_flag(
+        mut y_true: Array,
+        mut y_pred: Array,
+        nan_flag: ImmutableString,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (t, p) = super::regression_values(&mut y_true, &mut y_pred, &nan_flag, "rmse")?;
+        Ok(super::mean_of_pairs(&t, &p, |t, p| (t - p).powi(2)).sqrt())
+    }
+
+    /// Returns the mean absolute error of predictions of the true values. A `NaN` in either
+    /// array makes the result `NaN`; use `mae(y_true, y_pred, "omitnan")` to skip those pairs.
+    /// ```typescript
+    /// let e = mae([1.0, 2.0, 3.0, 4.0], [2.0, 2.0, 2.0, 6.0]);
+    /// assert_eq(e, 1.0);
+    /// ```
+    #[rhai_fn(name = "mae", return_raw)]
+    pub fn mae(y_true: Array, y_pred: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        mae_with_flag(y_true, y_pred, "includenan".into())
+    }
+
+    /// Returns the mean absolute error of predictions of the true values, with `nan_flag` saying
+    /// how to handle `NaN`s, as for `rmse`.
+    /// ```typescript
+    /// let e = mae([1.0, 0.0/0.0, 3.0], [2.0, 2.0, 5.0], "omitnan");
+    /// assert_eq(e, 1.5);
+    /// ```
+    #[rhai_fn(name = "mae", return_raw)]
+    pub fn mae_with_flag(
+        mut y_true: Array,
+        mut y_pred: Array,
+        nan_flag: ImmutableString,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (t, p) = super::regression_values(&mut y_true, &mut y_pred, &nan_flag, "mae")?;
+        Ok(super::mean_of_pairs(&t, &p, |t, p| (t - p).abs()))
+    }
+
+    /// Returns the mean absolute percentage error of predictions of the true values, as a
+    /// percentage. A true value of zero makes the result infinite, and a `NaN` in either array
+    /// makes it `NaN`; use `mape(y_true, y_pred, "omitnan")` to skip those pairs.
+    /// ```typescript
+    /// let e = mape([100.0, 200.0], [110.0, 150.0]);
+    /// assert_eq(e, 17.5);
+    /// ```
+    #[rhai_fn(name = "mape", return_raw)]
+    pub fn mape(y_true: Array, y_pred: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        mape_with_flag(y_true, y_pred, "includenan".into())
+    }
+
+    /// Returns the mean absolute percentage error of predictions of the true values, with
+    /// `nan_flag` saying how to handle `NaN`s, as for `rmse`.
+    /// ```typescript
+    /// let e = mape([100.0, 0.0/0.0, 200.0], [110.0, 1.0, 150.0], "omitnan");
+    /// assert_eq(e, 17.5);
+    /// ```
+    #[rhai_fn(name = "mape", return_raw)]
+    pub fn mape_with_flag(
+        mut y_true: Array,
+        mut y_pred: Array,
+        nan_flag: ImmutableString,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (t, p) = super::regression_values(&mut y_true, &mut y_pred, &nan_flag, "mape")?;
+        Ok(100.0 * super::mean_of_pairs(&t, &p, |t, p| ((t - p) / t).abs()))
+    }
+
+    /// Returns the coefficient of determination (R²) of predictions of the true values: one
+    /// minus the ratio of the squared error to the variation of the true values about their
+    /// mean. It is 1.0 for perfect predictions, 0.0 for always predicting the mean, and `NaN` if
+    /// the true values are all the same. A `NaN` in either array makes the result `NaN`; use
+    /// `r2(y_true, y_pred, "omitnan")` to skip those pairs.
+    /// ```typescript
+    /// assert_approx_eq(r2([1.0, 2.0, 3.0, 4.0], [1.5, 2.0, 3.0, 3.5]), 0.9);
+    /// assert_eq(r2([1.0, 2.0, 3.0], [2.0, 2.0, 2.0]), 0.0);
+    /// ```
+    #[rhai_fn(name = "r2", return_raw)]
+    pub fn r2(y_true: Array, y_pred: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        r2_with_flag(y_true, y_pred, "includenan".into())
+    }
+
+    /// Returns the coefficient of determination (R²) of predictions of the true values, with
+    /// `nan_flag` saying how to handle `NaN`s, as for `rmse`.
+    /// ```typescript
+    /// let e = r2([1.0, 2.0, 0.0/0.0, 3.0], [1.0, 2.0, 5.0, 3.0], "omitnan");
+    /// assert_eq(e, 1.0);
+    /// ```
+    #[rhai_fn(name = "r2", return_raw)]
+    pub fn r2_with_flag(
+        mut y_true: Array,
+        mut y_pred: Array,
+        nan_flag: ImmutableString,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let (t, p) = super::regression_values(&mut y_true, &mut y_pred, &nan_flag, "r2")?;
+        Ok(super::r_squared(&t, &p))
+    }
+
+    /// Splits the indices `0` to `n - 1` into `k` folds for k-fold cross-validation, returning an
+    /// array with a map for each fold of the `train` and `test` indices, where each index is in
+    /// the test set of exactly one fold. The folds are consecutive runs of indices, as equal in
+    /// size as possible, so the data should not be sorted in a way that matters.
+    /// ```typescript
+    /// let folds = kfold(5, 2);
+    /// assert_eq(folds[0], #{train: [3, 4], test: [0, 1, 2]});
+    /// assert_eq(folds[1], #{train: [0, 1, 2], test: [3, 4]});
+    /// ```
+    #[rhai_fn(name = "kfold", return_raw)]
+    pub fn kfold(n: INT, k: INT) -> Result<Array, Box<EvalAltResult>> {
+        let (n, k) = super::check_folds(n, k, "kfold")?;
+        let order = (0..n).collect::<Vec<_>>();
+        Ok(super::folds_to_array(super::fold_splits(&order, k)))
+    }
+
+    /// Splits the indices `0` to `n - 1` into `k` folds for k-fold cross-validation, as for
+    /// `kfold(n, k)`, but if `shuffle` is true, the indices are shuffled before they are split.
+    /// The shuffle is determined by `seed` and does not affect the generator selected with
+    /// `seed_rng`.
+    /// ```typescript
+    /// let folds = kfold(10, 3, true, 42);
+    /// assert_eq(folds.map(|f| f.test.len()), [4, 3, 3]);
+    /// assert_eq(folds, kfold(10, 3, true, 42));
+    /// let all = folds.reduce(|sum, f| sum + f.test, []);
+    /// all.sort();
+    /// assert_eq(all, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "kfold", return_raw)]
+    pub fn kfold_shuffled(
+        n: INT,
+        k: INT,
+        shuffle: bool,
+        seed: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        use randlib::{seq::SliceRandom, SeedableRng};
+        let (n, k) = super::check_folds(n, k, "kfold")?;
+        let mut order = (0..n).collect::<Vec<_>>();
+        if shuffle {
+            order.shuffle(&mut rand_pcg::Pcg64::seed_from_u64(seed as u64));
+        }
+        Ok(super::folds_to_array(super::fold_splits(&order, k)))
+    }
+
+    /// Runs k-fold cross-validation of a model, with the folds of `kfold(len(y), k)`. For each
+    /// fold, `fit_fn(x_train, y_train)` is called with the training elements (or rows) of `x`
+    /// and `y` and returns a model, then `predict_fn(model, x_test)` returns an array of
+    /// predictions for the test elements of `x`. Returns a map of the `predictions` for every
+    /// element of `y`, each made by the model that did not see it, and the `models` of each fold.
+    /// The predictions can be scored with functions like `rmse` or `confusion_matrix`.
+    /// ```typescript
+    /// let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    /// let y = [2.0, 4.0, 6.0, 8.0, 10.0, 12.0];
+    /// // A model that predicts y is proportional to x
+    /// let fit = |x, y| sum(y) / sum(x);
+    /// let predict = |slope, x| x.map(|v| slope * v);
+    /// let cv = cross_validate(fit, predict, x, y, 3);
+    /// assert_eq(cv.models, [2.0, 2.0, 2.0]);
+    /// assert_eq(rmse(y, cv.predictions), 0.0);
+    /// ```
+    #[rhai_fn(name = "cross_validate", return_raw)]
+    pub fn cross_validate(
+        context: NativeCallContext,
+        fit_fn: FnPtr,
+        predict_fn: FnPtr,
+        x: Array,
+        y: Array,
+        k: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if x.len() != y.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!("There are {} inputs but {} outputs", x.len(), y.len()),
+            )
+            .in_function("cross_validate")
+            .into());
+        }
+        let (n, k) = super::check_folds(y.len() as INT, k, "cross_validate")?;
+        let order = (0..n).collect::<Vec<_>>();
+        let mut predictions = vec![Dynamic::UNIT; n];
+        let mut models = Array::new();
+        for (train, test) in super::fold_splits(&order, k) {
+            let model = fit_fn.call_within_context::<Dynamic>(
+                &context,
+                (
+                    super::select_elements(&x, &train),
+                    super::select_elements(&y, &train),
+                ),
+            )?;
+            let predicted = predict_fn.call_within_context::<Dynamic>(
+                &context,
+                (model.clone(), super::select_elements(&x, &test)),
+            )?;
+            let predicted = match predicted.into_array() {
+                Ok(p) if p.len() == test.len() => p,
+                _ => {
+                    return Err(crate::LabError::new(
+                        crate::LabErrorKind::DimensionMismatch,
+                        format!(
+                            "The prediction function must return an array of {} predictions",
+                            test.len()
+                        ),
+                    )
+                    .in_function("cross_validate")
+                    .with_argument("predict_fn")
+                    .into())
+                }
+            };
+            for (i, p) in test.into_iter().zip(predicted) {
+                predictions[i] = p;
+            }
+            models.push(model);
+        }
+        let mut result = Map::new();
+        result.insert("predictions".into(), Dynamic::from_array(predictions));
+        result.insert("models".into(), Dynamic::from_array(models));
+        Ok(result)
+    }
+
+    /// Randomly splits the elements (or rows) of `x` and `y` into a training set and a test set
+    /// holding `test_fraction` of them (rounded to the nearest integer), returning a map of
+    /// `x_train`, `x_test`, `y_train`, and `y_test`. Both sets keep the elements in their
+    /// original order. The split is determined by `seed` and does not affect the generator
+    /// selected with `seed_rng`.
+    /// ```typescript
+    /// let x = [[1, 10], [2, 20], [3, 30], [4, 40], [5, 50]];
+    /// let y = [1, 2, 3, 4, 5];
+    /// let s = train_test_split(x, y, 0.4, 42);
+    /// assert_eq(s.y_test.len(), 2);
+    /// assert_eq(s.x_train.len(), 3);
+    /// assert_eq(s.x_test.map(|row| row[0]), s.y_test);
+    /// assert_eq(s, train_test_split(x, y, 0.4, 42));
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "train_test_split", return_raw)]
+    pub fn train_test_split(
+        x: Array,
+        y: Array,
+        test_fraction: FLOAT,
+        seed: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        train_test_split_stratified(x, y, test_fraction, seed, false)
+    }
+
+    /// Randomly splits the elements (or rows) of `x` and `y` into a training set and a test set,
+    /// as for `train_test_split(x, y, test_fraction, seed)`, but if `stratify` is true, each
+    /// distinct class in `y` contributes `test_fraction` of its elements to the test set, so the
+    /// proportions of the classes are about the same in both sets.
+    /// ```typescript
+    /// let x = [1, 2, 3, 4, 5, 6, 7, 8];
+    /// let y = ["a", "a", "a", "a", "a", "a", "b", "b"];
+    /// let s = train_test_split(x, y, 0.5, 7, true);
+    /// assert_eq(s.y_test.filter(|label| label == "b").len(), 1);
+    /// assert_eq(s.y_train.filter(|label| label == "b").len(), 1);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "train_test_split", return_raw)]
+    pub fn train_test_split_stratified(
+        x: Array,
+        y: Array,
+        test_fraction: FLOAT,
+        seed: INT,
+        stratify: bool,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        use randlib::SeedableRng;
+        if x.len() != y.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!("There are {} inputs but {} outputs", x.len(), y.len()),
+            )
+            .in_function("train_test_split")
+            .into());
+        }
+        if !(0.0..=1.0).contains(&test_fraction) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The test fraction must be between 0 and 1",
+            )
+            .in_function("train_test_split")
+            .with_argument("test_fraction")
+            .into());
+        }
+
+        // Group the indices by class, or put them all in one group
+        let groups = if stratify {
+            let labels = crate::sorted_categories(&y)?;
+            let mut groups = vec![vec![]; labels.len()];
+            for (idx, code) in crate::category_codes(&y, &labels)?.into_iter().enumerate() {
+                groups[code].push(idx);
+            }
+            groups
+        } else {
+            vec![(0..y.len()).collect::<Vec<_>>()]
+        };
+
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed as u64);
+        let mut test = vec![];
+        for members in groups {
+            let amount = (test_fraction * members.len() as FLOAT).round() as usize;
+            for pick in randlib::seq::index::sample(&mut rng, members.len(), amount) {
+                test.push(members[pick]);
+            }
+        }
+        Ok(super::split_by_test_indices(&x, &y, test))
+    }
+}
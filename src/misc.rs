@@ -1,19 +1,549 @@
 use rhai::plugin::*;
 
+/// Coefficients of the Lanczos approximation to the gamma function, with g = 7.
+const LANCZOS: [rhai::FLOAT; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+/// Returns the gamma function of `x`, which is exact for integers and infinite at the poles.
+fn gamma_function(x: rhai::FLOAT) -> rhai::FLOAT {
+    let pi = std::f64::consts::PI as rhai::FLOAT;
+    if x.fract() == 0.0 {
+        return if x <= 0.0 || x > 171.0 {
+            rhai::FLOAT::INFINITY
+        } else {
+            (2..x as i64).fold(1.0, |acc, k| acc * k as rhai::FLOAT)
+        };
+    }
+    if x < 0.5 {
+        return pi / ((pi * x).sin() * gamma_function(1.0 - x));
+    }
+    if x > 171.7 {
+        return rhai::FLOAT::INFINITY;
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let a = lanczos_sum(x);
+    // Split the power in two so it does not overflow before the exponential brings it down
+    let half_power = t.powf((x + 0.5) / 2.0);
+    (2.0 * pi).sqrt() * half_power * ((-t).exp() * half_power) * a
+}
+
+/// Returns the series in the Lanczos approximation for the gamma function of `x + 1`.
+fn lanczos_sum(x: rhai::FLOAT) -> rhai::FLOAT {
+    LANCZOS[1..]
+        .iter()
+        .enumerate()
+        .fold(LANCZOS[0], |acc, (i, p)| {
+            acc + p / (x + i as rhai::FLOAT + 1.0)
+        })
+}
+
+/// Returns the natural logarithm of the absolute value of the gamma function of `x`.
+fn ln_gamma(x: rhai::FLOAT) -> rhai::FLOAT {
+    let pi = std::f64::consts::PI as rhai::FLOAT;
+    if x.fract() == 0.0 && x <= 0.0 {
+        rhai::FLOAT::INFINITY
+    } else if x.fract() == 0.0 && x <= 171.0 {
+        gamma_function(x).ln()
+    } else if x < 0.5 {
+        (pi / (pi * x).sin().abs()).ln() - ln_gamma(1.0 - x)
+    } else {
+        let x = x - 1.0;
+        let t = x + 7.5;
+        0.5 * (2.0 * pi).ln() + (x + 0.5) * t.ln() - t + lanczos_sum(x).ln()
+    }
+}
+
+/// Returns the beta function of `a` and `b`.
+fn beta_function(a: rhai::FLOAT, b: rhai::FLOAT) -> rhai::FLOAT {
+    if a > 0.0 && b > 0.0 && a + b > 171.0 {
+        (ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)).exp()
+    } else {
+        gamma_function(a) * gamma_function(b) / gamma_function(a + b)
+    }
+}
+
+/// Returns the error function of `x`.
+fn error_function(x: rhai::FLOAT) -> rhai::FLOAT {
+    if x < 0.0 {
+        -error_function(-x)
+    } else if x < 2.0 {
+        // A series whose terms are all positive, so no precision is lost to cancellation
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 0.0;
+        while term > sum * rhai::FLOAT::EPSILON / 4.0 {
+            n += 1.0;
+            term *= 2.0 * x * x / (2.0 * n + 1.0);
+            sum += term;
+        }
+        2.0 / (std::f64::consts::PI as rhai::FLOAT).sqrt() * (-x * x).exp() * sum
+    } else {
+        1.0 - complementary_error_function(x)
+    }
+}
+
+/// Returns the complementary error function of `x`, keeping its precision for large `x`.
+pub fn complementary_error_function(x: rhai::FLOAT) -> rhai::FLOAT {
+    if x < 0.0 {
+        2.0 - complementary_error_function(-x)
+    } else if x < 2.0 {
+        1.0 - error_function(x)
+    } else {
+        // Continued fraction x + (1/2)/(x + 1/(x + (3/2)/(x + ...))), by Lentz's method
+        let tiny = rhai::FLOAT::MIN_POSITIVE;
+        let (mut f, mut c, mut d) = (x, x, 0.0);
+        for k in 1..500 {
+            let a = k as rhai::FLOAT / 2.0;
+            d = x + a * d;
+            d = if d == 0.0 { 1.0 / tiny } else { 1.0 / d };
+            c = x + a / c;
+            if c == 0.0 {
+                c = tiny;
+            }
+            let delta = c * d;
+            f *= delta;
+            if (delta - 1.0).abs() < rhai::FLOAT::EPSILON {
+                break;
+            }
+        }
+        (-x * x).exp() / ((std::f64::consts::PI as rhai::FLOAT).sqrt() * f)
+    }
+}
+
+/// The Euler-Mascheroni constant.
+const EULER_GAMMA: rhai::FLOAT = 0.577_215_664_901_532_9;
+
+/// Returns the Bessel functions of the first kind `J_0(x)` to `J_m(x)` of a positive `x`, for some
+/// `m` of at least `n`, so that enough terms are available to sum series of them.
+fn bessel_j_sequence(x: rhai::FLOAT, n: usize) -> Vec<rhai::FLOAT> {
+    let big = (n as rhai::FLOAT).max(x);
+    let m =
+        2 * ((big + 30.0 + 10.0 * big.cbrt() + (160.0 * n as rhai::FLOAT).sqrt()) / 2.0) as usize;
+    if x < 1.0 {
+        // The power series converges quickly and without cancellation
+        let half = x / 2.0;
+        return (0..=m)
+            .map(|k| {
+                let mut term = (1..=k).fold(1.0, |acc, i| acc * half / i as rhai::FLOAT);
+                let mut sum = term;
+                for i in 1..50 {
+                    term *= -half * half / (i * (i + k)) as rhai::FLOAT;
+                    sum += term;
+                    if term.abs() < sum.abs() * rhai::FLOAT::EPSILON {
+                        break;
+                    }
+                }
+                sum
+            })
+            .collect();
+    }
+
+    // Miller's algorithm: recur downwards from an arbitrary start, then normalize with the
+    // identity J_0 + 2 J_2 + 2 J_4 + ... = 1
+    let big = rhai::FLOAT::MAX.sqrt();
+    let mut j = vec![0.0; m + 2];
+    j[m] = 1.0;
+    for k in (1..=m).rev() {
+        j[k - 1] = 2.0 * k as rhai::FLOAT / x * j[k] - j[k + 1];
+        if j[k - 1].abs() > big {
+            j.iter_mut().for_each(|v| *v /= big);
+        }
+    }
+    let norm = j[0] + 2.0 * j.iter().skip(2).step_by(2).sum::<rhai::FLOAT>();
+    j.iter().map(|v| v / norm).collect()
+}
+
+/// The highest order of the Bessel functions, which keeps the work for each value bounded.
+const MAX_BESSEL_ORDER: rhai::INT = 1000;
+
+/// Beyond this (or `n^2`, if larger), the Bessel functions of the first and second kinds come
+/// from their asymptotic expansion instead of a sequence as long as `x`.
+const BESSEL_ASYMPTOTIC_THRESHOLD: rhai::FLOAT = 1e4;
+
+/// Checks that the order of a Bessel function is at most [`MAX_BESSEL_ORDER`].
+fn check_bessel_order(n: rhai::INT, function: &str) -> Result<(), Box<rhai::EvalAltResult>> {
+    if !(-MAX_BESSEL_ORDER..=MAX_BESSEL_ORDER).contains(&n) {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!(
+                "The order must be between -{MAX_BESSEL_ORDER} and {MAX_BESSEL_ORDER}, not {n}"
+            ),
+        )
+        .in_function(function)
+        .with_argument("n")
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns `J_n(x)` and `Y_n(x)` for a large positive `x` from Hankel's asymptotic expansion,
+/// summing its terms for as long as they decrease.
+fn bessel_asymptotic(n: usize, x: rhai::FLOAT) -> (rhai::FLOAT, rhai::FLOAT) {
+    let mu = 4.0 * (n * n) as rhai::FLOAT;
+    let (mut p, mut q): (rhai::FLOAT, rhai::FLOAT) = (1.0, 0.0);
+    let mut term: rhai::FLOAT = 1.0;
+    for k in 1..100 {
+        let odd = (2 * k - 1) as rhai::FLOAT;
+        let next = term * (mu - odd * odd) / (8.0 * k as rhai::FLOAT * x);
+        if next.abs() >= term.abs() {
+            break;
+        }
+        term = next;
+        match k % 4 {
+            1 => q += term,
+            2 => p -= term,
+            3 => q -= term,
+            _ => p += term,
+        }
+    }
+    let pi = std::f64::consts::PI as rhai::FLOAT;
+    let chi = x - (n as rhai::FLOAT / 2.0 + 0.25) * pi;
+    let scale = (2.0 / (pi * x)).sqrt();
+    (
+        scale * (p * chi.cos() - q * chi.sin()),
+        scale * (p * chi.sin() + q * chi.cos()),
+    )
+}
+
+/// Returns the Bessel function of the first kind of integer order `n`.
+fn bessel_j(n: rhai::INT, x: rhai::FLOAT) -> rhai::FLOAT {
+    let sign = if n.rem_euclid(2) == 1 && (n < 0) != (x < 0.0) {
+        -1.0
+    } else {
+        1.0
+    };
+    let n = n.unsigned_abs() as usize;
+    let x = x.abs();
+    if x == 0.0 {
+        return if n == 0 { 1.0 } else { 0.0 };
+    } else if x.is_nan() {
+        return rhai::FLOAT::NAN;
+    } else if x.is_infinite() {
+        return 0.0;
+    } else if x > BESSEL_ASYMPTOTIC_THRESHOLD.max((n * n) as rhai::FLOAT) {
+        return sign * bessel_asymptotic(n, x).0;
+    }
+    sign * bessel_j_sequence(x, n)[n]
+}
+
+/// Returns the Bessel function of the second kind of integer order `n`, which is only real for
+/// positive `x`.
+fn bessel_y(n: rhai::INT, x: rhai::FLOAT) -> rhai::FLOAT {
+    if x < 0.0 || x.is_nan() {
+        return rhai::FLOAT::NAN;
+    } else if x == 0.0 {
+        return rhai::FLOAT::NEG_INFINITY;
+    } else if x.is_infinite() {
+        return 0.0;
+    }
+    let sign = if n < 0 && n.rem_euclid(2) == 1 {
+        -1.0
+    } else {
+        1.0
+    };
+    let n = n.unsigned_abs() as usize;
+    if x > BESSEL_ASYMPTOTIC_THRESHOLD.max((n * n) as rhai::FLOAT) {
+        return sign * bessel_asymptotic(n, x).1;
+    }
+
+    // Neumann series for Y_0 and Y_1 in terms of J_k
+    let j = bessel_j_sequence(x, 1);
+    let log_term = (x / 2.0).ln() + EULER_GAMMA;
+    let mut y0 = log_term * j[0];
+    let mut y1 = log_term * j[1] - j[0] / x;
+    for k in 1..(j.len() - 1) / 2 {
+        let alternating = if k % 2 == 1 { -1.0 } else { 1.0 };
+        y0 -= 2.0 * alternating * j[2 * k] / k as rhai::FLOAT;
+        y1 += alternating * (j[2 * k - 1] - j[2 * k + 1]) / k as rhai::FLOAT;
+    }
+    let scale = 2.0 / std::f64::consts::PI as rhai::FLOAT;
+    let (mut previous, mut current) = (scale * y0, scale * y1);
+    if n == 0 {
+        return previous;
+    }
+
+    // Recurrence upwards is stable for Y_n
+    for k in 1..n {
+        let next = 2.0 * k as rhai::FLOAT / x * current - previous;
+        previous = current;
+        current = next;
+    }
+    sign * current
+}
+
+/// Returns the modified Bessel function of the first kind of integer order `n`.
+fn bessel_i(n: rhai::INT, x: rhai::FLOAT) -> rhai::FLOAT {
+    let sign = if n.rem_euclid(2) == 1 && x < 0.0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let n = n.unsigned_abs() as usize;
+    // The series takes about `x` terms, and overflows long before a million of them
+    if x.abs() > 1e6 {
+        return sign * rhai::FLOAT::INFINITY;
+    }
+    let half = x.abs() / 2.0;
+
+    // The power series has only positive terms
+    let mut term = (1..=n).fold(1.0, |acc, i| acc * half / i as rhai::FLOAT);
+    let mut sum = term;
+    let mut k = 1;
+    while term > sum * rhai::FLOAT::EPSILON / 4.0 {
+        term *= half * half / (k * (k + n)) as rhai::FLOAT;
+        sum += term;
+        k += 1;
+    }
+    sign * sum
+}
+
+/// Returns the modified Bessel function of the second kind of integer order `n`, which is only
+/// real for positive `x`.
+fn bessel_k(n: rhai::INT, x: rhai::FLOAT) -> rhai::FLOAT {
+    if x < 0.0 || x.is_nan() {
+        return rhai::FLOAT::NAN;
+    } else if x == 0.0 {
+        return rhai::FLOAT::INFINITY;
+    }
+    let n = n.unsigned_abs() as usize;
+
+    // K_v(x) is the integral of exp(-x cosh(t)) cosh(v t) over t > 0, which the trapezoidal rule
+    // approximates to machine precision because the integrand decays so quickly
+    let step = 0.05;
+    let (mut k0, mut k1) = (0.0, 0.0);
+    let mut t: rhai::FLOAT = 0.0;
+    loop {
+        let weight = if t == 0.0 { step / 2.0 } else { step };
+        let decay = (-x * t.cosh()).exp();
+        k0 += weight * decay;
+        k1 += weight * decay * t.cosh();
+        if x * (t.cosh() - 1.0) - t > 50.0 {
+            break;
+        }
+        t += step;
+    }
+    let (mut previous, mut current) = (k0, k1);
+    if n == 0 {
+        return previous;
+    }
+
+    // Recurrence upwards is stable for K_n
+    for k in 1..n {
+        let next = previous + 2.0 * k as rhai::FLOAT / x * current;
+        previous = current;
+        current = next;
+    }
+    current
+}
+
+/// Returns the complete elliptic integrals of the first and second kinds, `K(m)` and `E(m)`, of
+/// the parameter `m`, using the arithmetic-geometric mean.
+fn elliptic_integrals(m: rhai::FLOAT) -> (rhai::FLOAT, rhai::FLOAT) {
+    if m > 1.0 || m.is_nan() {
+        return (rhai::FLOAT::NAN, rhai::FLOAT::NAN);
+    } else if m == 1.0 {
+        return (rhai::FLOAT::INFINITY, 1.0);
+    }
+    let (mut a, mut b) = (1.0, (1.0 - m).sqrt());
+    let mut power = 0.5;
+    let mut total = power * m;
+    while (a - b).abs() > rhai::FLOAT::EPSILON * a {
+        let c = (a - b) / 2.0;
+        (a, b) = ((a + b) / 2.0, (a * b).sqrt());
+        power *= 2.0;
+        total += power * c * c;
+    }
+    let k = std::f64::consts::PI as rhai::FLOAT / (2.0 * a);
+    (k, k * (1.0 - total))
+}
+
+/// Returns the binomial coefficient "`n` choose `k`", or `None` if it overflows.
+fn binomial(n: rhai::INT, k: rhai::INT) -> Option<rhai::INT> {
+    if k < 0 || k > n {
+        return Some(0);
+    }
+    let k = k.min(n - k);
+    // Each partial product is itself a binomial coefficient, so it fits whenever the result does
+    let mut c: i128 = 1;
+    for i in 0..k as i128 {
+        c = c * (n as i128 - i) / (i + 1);
+        if c > rhai::INT::MAX as i128 {
+            return None;
+        }
+    }
+    Some(c as rhai::INT)
+}
+
+/// Builds the error for a combinatorial result too large for an integer.
+fn combinatorics_overflow(what: String) -> Box<rhai::EvalAltResult> {
+    crate::LabError::new(
+        crate::LabErrorKind::InvalidArgument,
+        format!("{what} is too large to represent as an integer"),
+    )
+    .into()
+}
+
+/// Converts an array of integers into a vector, failing with a message naming the function.
+fn integer_elements(
+    arr: rhai::Array,
+    name: &str,
+) -> Result<Vec<rhai::INT>, Box<rhai::EvalAltResult>> {
+    arr.into_iter()
+        .map(|x| {
+            x.as_int().map_err(|_| {
+                crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("{name} requires an array of integers"),
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Returns the greatest common divisor of two non-negative integers.
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns `a * b mod m` without overflowing.
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// Returns `base ^ exp mod m` by repeated squaring.
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Tests whether `n` is prime with the Miller-Rabin test, whose first twelve prime bases make it
+/// exact for every 64-bit integer.
+fn is_prime_u64(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(p) = BASES.iter().find(|p| n.is_multiple_of(**p)) {
+        return n == *p;
+    }
+    let (mut d, mut r) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        r += 1;
+    }
+    BASES.iter().all(|a| {
+        let mut x = pow_mod(*a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..r {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Finds a non-trivial divisor of a composite `n` with Pollard's rho method.
+fn pollard_rho(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+    (1..)
+        .find_map(|c| {
+            let f = |x: u64| (mul_mod(x, x, n) + c) % n;
+            let (mut x, mut y, mut d) = (2, 2, 1);
+            while d == 1 {
+                x = f(x);
+                y = f(f(y));
+                d = gcd_u64(x.abs_diff(y), n);
+            }
+            (d != n).then_some(d)
+        })
+        .unwrap()
+}
+
+/// Appends the prime factors of `n` to `factors`, in no particular order.
+fn prime_factors(n: u64, factors: &mut Vec<u64>) {
+    if n <= 1 {
+        return;
+    }
+    // Trial division removes small factors quickly, leaving Pollard's rho for large ones
+    let mut n = n;
+    for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+    }
+    if n == 1 {
+        return;
+    }
+    if is_prime_u64(n) {
+        factors.push(n);
+    } else {
+        let d = pollard_rho(n);
+        prime_factors(d, factors);
+        prime_factors(n / d, factors);
+    }
+}
+
+/// Rounds `x` to `digits` digits after the decimal point, or to a multiple of a power of ten if
+/// `digits` is negative. Halves round away from zero.
+fn round_to_digits(x: rhai::FLOAT, digits: rhai::INT) -> rhai::FLOAT {
+    let scale = (10.0 as rhai::FLOAT).powi(digits.unsigned_abs().min(400) as i32);
+    if digits >= 0 {
+        let scaled = x * scale;
+        if scaled.is_finite() {
+            scaled.round() / scale
+        } else {
+            x
+        }
+    } else {
+        (x / scale).round() * scale
+    }
+}
+
 #[export_module]
 pub mod misc_functions {
     use crate::{if_list_convert_to_vec_float_and_do, if_list_do_int_or_do_float};
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT};
+    use rhai::{Array, Dynamic, EvalAltResult, NativeCallContext, FLOAT, INT};
 
-    /// Returns a random number between zero and one.
+    /// Returns a random number between zero and one. The sequence can be made reproducible with
+    /// `seed_rng`.
     /// ```typescript
     /// let r = rand();
     /// assert(r >= 0.0 && r <= 1.0);
     /// ```
     #[cfg(feature = "rand")]
     #[rhai_fn(name = "rand")]
-    pub fn rand_float() -> FLOAT {
-        randlib::random()
+    pub fn rand_float(ctx: NativeCallContext) -> FLOAT {
+        crate::random_float(&ctx)
     }
 
     /// Returns an array of the unique elements in an array.
@@ -34,11 +564,12 @@ pub mod misc_functions {
             },
             |arr| {
                 let mut x = crate::array_to_vec_float(arr);
-                x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                x.sort_by(|a, b| a.total_cmp(b));
                 x.dedup();
                 Ok(x.iter().map(|el| Dynamic::from_float(*el)).collect())
             },
         )
+        .map_err(crate::in_function("unique", Some("arr")))
     }
 
     /// Given reference data, perform linear interpolation.
@@ -60,25 +591,32 @@ pub mod misc_functions {
         } else if xq.is_float() {
             xq.as_float().unwrap()
         } else {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "xq must be either INT or FLOAT".to_string(),
-                Position::NONE,
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "xq must be either INT or FLOAT",
             )
+            .in_function("interp1")
+            .with_argument("xq")
             .into());
         };
+        if new_xq.is_nan() {
+            return Ok(FLOAT::NAN);
+        }
 
         if x.len() < 2 {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have at least 2 elements".to_string(),
-                Position::NONE,
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The arrays must have at least 2 elements",
             )
+            .in_function("interp1")
             .into());
         }
         if x.len() != y.len() {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "The arrays must have the same length".to_string(),
-                Position::NONE,
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "The arrays must have the same length",
             )
+            .in_function("interp1")
             .into());
         }
 
@@ -104,4 +642,504 @@ pub mod misc_functions {
             })
         })
     }
+
+    /// Returns the gamma function of a number, or of each element of an array. It is exact for
+    /// positive integers, where `gamma(n)` is the factorial of `n - 1`, and infinite at zero and
+    /// the negative integers.
+    /// ```typescript
+    /// assert_eq(gamma(5), 24.0);
+    /// assert_eq(gamma([1, 2, 3, 4]), [1.0, 1.0, 2.0, 6.0]);
+    /// ```
+    /// ```typescript,only_f64
+    /// assert(abs(gamma(0.5) - sqrt(pi)) < 1e-14);
+    /// assert(abs(gamma(-0.5) + 2 * sqrt(pi)) < 1e-14);
+    /// ```
+    #[rhai_fn(name = "gamma", return_raw)]
+    pub fn gamma(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &super::gamma_function)
+    }
+
+    /// Returns the natural logarithm of the absolute value of the gamma function of a number, or
+    /// of each element of an array. Unlike `ln(gamma(x))`, it does not overflow for large `x`.
+    /// ```typescript,only_f64
+    /// assert(abs(lgamma(10) - ln(362880.0)) < 1e-12);
+    /// assert(abs(gammaln(200.5) - 860.5822035097825) < 1e-10);
+    /// ```
+    #[rhai_fn(name = "lgamma", name = "gammaln", return_raw)]
+    pub fn lgamma(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &super::ln_gamma)
+    }
+
+    /// Returns the beta function `gamma(a) * gamma(b) / gamma(a + b)` of two numbers, or element
+    /// by element for arrays of the same shape. A number is paired with every element of an array.
+    /// ```typescript
+    /// assert_eq(beta(2, 3), 1.0 / 12.0);
+    /// assert_eq(beta([1, 2], 1), [1.0, 0.5]);
+    /// ```
+    #[rhai_fn(name = "beta", return_raw)]
+    pub fn beta(a: Dynamic, b: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::zip_numbers(a, b, &super::beta_function)
+    }
+
+    /// Returns the error function of a number, or of each element of an array.
+    /// ```typescript,only_f64
+    /// assert_eq(erf(0), 0.0);
+    /// assert(abs(erf(1) - 0.8427007929497149) < 1e-15);
+    /// assert(abs(erf(-0.5) + 0.5204998778130465) < 1e-15);
+    /// ```
+    #[rhai_fn(name = "erf", return_raw)]
+    pub fn erf(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &super::error_function)
+    }
+
+    /// Returns the complementary error function `1 - erf(x)` of a number, or of each element of an
+    /// array, without losing precision when the result is small.
+    /// ```typescript,only_f64
+    /// assert_eq(erfc([0]), [1.0]);
+    /// assert(abs(erfc(5) / 1.5374597944280349e-12 - 1) < 1e-13);
+    /// ```
+    #[rhai_fn(name = "erfc", return_raw)]
+    pub fn erfc(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &super::complementary_error_function)
+    }
+
+    /// Returns the Bessel function of the first kind `J_n(x)` of integer order `n`, for a number
+    /// or each element of an array.
+    /// ```typescript,only_f64
+    /// assert_eq(besselj(0, 0), 1.0);
+    /// assert(abs(besselj(0, 1) - 0.7651976865579666) < 1e-15);
+    /// assert(abs(besselj(2, 10.0) - 0.2546303136851206) < 1e-15);
+    /// ```
+    /// ```typescript
+    /// let j = besselj(1, [1, -1]);
+    /// assert_eq(j[0], -j[1]);
+    /// ```
+    #[rhai_fn(name = "besselj", return_raw)]
+    pub fn besselj(n: INT, x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::check_bessel_order(n, "besselj")?;
+        crate::map_numbers(x, &|x| super::bessel_j(n, x))
+    }
+
+    /// Returns the Bessel function of the second kind `Y_n(x)` of integer order `n`, for a number
+    /// or each element of an array. It is `-inf` at zero and `NaN` for negative `x`.
+    /// ```typescript,only_f64
+    /// assert(abs(bessely(0, 1) - 0.08825696421567696) < 1e-15);
+    /// assert(abs(bessely(3, 5.5) - 0.2643700449646847) < 1e-14);
+    /// ```
+    #[rhai_fn(name = "bessely", return_raw)]
+    pub fn bessely(n: INT, x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::check_bessel_order(n, "bessely")?;
+        crate::map_numbers(x, &|x| super::bessel_y(n, x))
+    }
+
+    /// Returns the modified Bessel function of the first kind `I_n(x)` of integer order `n`, for
+    /// a number or each element of an array.
+    /// ```typescript,only_f64
+    /// assert_eq(besseli(0, 0), 1.0);
+    /// assert(abs(besseli(1, 2) - 1.5906368546373291) < 1e-15);
+    /// ```
+    #[rhai_fn(name = "besseli", return_raw)]
+    pub fn besseli(n: INT, x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::check_bessel_order(n, "besseli")?;
+        crate::map_numbers(x, &|x| super::bessel_i(n, x))
+    }
+
+    /// Returns the modified Bessel function of the second kind `K_n(x)` of integer order `n`, for
+    /// a number or each element of an array. It is `inf` at zero and `NaN` for negative `x`.
+    /// ```typescript,only_f64
+    /// assert(abs(besselk(0, 1) - 0.42102443824070834) < 1e-15);
+    /// assert(abs(besselk(2, 0.5) / 7.550183551240869 - 1) < 1e-14);
+    /// ```
+    #[rhai_fn(name = "besselk", return_raw)]
+    pub fn besselk(n: INT, x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        super::check_bessel_order(n, "besselk")?;
+        crate::map_numbers(x, &|x| super::bessel_k(n, x))
+    }
+
+    /// Returns the complete elliptic integral of the first kind `K(m)` of the parameter `m` (the
+    /// square of the modulus), for a number or each element of an array. It is `inf` at `m = 1`
+    /// and `NaN` above.
+    /// ```typescript,only_f64
+    /// assert_eq(ellipk(0), pi / 2);
+    /// assert(abs(ellipk(0.5) - 1.8540746773013719) < 1e-15);
+    /// ```
+    #[rhai_fn(name = "ellipk", return_raw)]
+    pub fn ellipk(m: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(m, &|m| super::elliptic_integrals(m).0)
+    }
+
+    /// Returns the complete elliptic integral of the second kind `E(m)` of the parameter `m` (the
+    /// square of the modulus), for a number or each element of an array.
+    /// ```typescript,only_f64
+    /// assert_eq(ellipe([0, 1]), [pi / 2, 1.0]);
+    /// assert(abs(ellipe(0.5) - 1.3506438810476755) < 1e-15);
+    /// ```
+    #[rhai_fn(name = "ellipe", return_raw)]
+    pub fn ellipe(m: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(m, &|m| super::elliptic_integrals(m).1)
+    }
+
+    /// Returns both complete elliptic integrals `[K(m), E(m)]` of the parameter `m`, as with
+    /// `ellipk` and `ellipe`.
+    /// ```typescript
+    /// let ke = ellipke(0.5);
+    /// assert_eq(ke, [ellipk(0.5), ellipe(0.5)]);
+    /// ```
+    #[rhai_fn(name = "ellipke", return_raw)]
+    pub fn ellipke(m: Dynamic) -> Result<Array, Box<EvalAltResult>> {
+        crate::if_int_convert_to_float_and_do(m, |m| {
+            let (k, e) = super::elliptic_integrals(m);
+            Ok(vec![Dynamic::from_float(k), Dynamic::from_float(e)])
+        })
+        .map_err(crate::in_function("ellipke", Some("m")))
+    }
+
+    /// Returns `n!`, the product of the integers from 1 to `n`. Fails for negative `n`, or if the
+    /// result is too large for an integer (use `factorial(bigint(n))` with the `bignum` feature
+    /// for larger values).
+    /// ```typescript,only_i64
+    /// assert_eq(factorial(0), 1);
+    /// assert_eq(factorial(10), 3628800);
+    /// assert_eq(factorial(20), 2432902008176640000);
+    /// ```
+    /// ```typescript
+    /// let result = "no error";
+    /// try { factorial(21); } catch { result = "error"; }
+    /// assert_eq(result, "error");
+    /// ```
+    #[rhai_fn(name = "factorial", return_raw)]
+    pub fn factorial(n: INT) -> Result<INT, Box<EvalAltResult>> {
+        if n < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot take the factorial of {n}, which is negative"),
+            )
+            .in_function("factorial")
+            .with_argument("n")
+            .into());
+        }
+        (2..=n)
+            .try_fold(1 as INT, |acc, k| acc.checked_mul(k))
+            .ok_or_else(|| super::combinatorics_overflow(format!("{n}!")))
+    }
+
+    /// Returns the binomial coefficient "`n` choose `k`", the number of ways to choose `k` items
+    /// from `n`. Intermediate results never exceed the final one, so this only fails if the
+    /// result itself is too large for an integer.
+    /// ```typescript,only_i64
+    /// assert_eq(nchoosek(5, 2), 10);
+    /// assert_eq(nchoosek(5, 6), 0);
+    /// assert_eq(nchoosek(66, 33), 7219428434016265740);
+    /// ```
+    #[rhai_fn(name = "nchoosek", return_raw)]
+    pub fn nchoosek(n: INT, k: INT) -> Result<INT, Box<EvalAltResult>> {
+        if n < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot choose from {n} items, which is negative"),
+            )
+            .in_function("nchoosek")
+            .with_argument("n")
+            .into());
+        }
+        super::binomial(n, k)
+            .ok_or_else(|| super::combinatorics_overflow(format!("{n} choose {k}")))
+    }
+
+    /// Returns every combination of `k` elements of an array, as an array of arrays. Elements
+    /// keep their order within each combination, and combinations are listed in order of the
+    /// positions they are taken from.
+    /// ```typescript
+    /// let c = nchoosek([1, 2, 3, 4], 2);
+    /// assert_eq(c, [[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(nchoosek(["a", "b"], 0), [[]]);
+    /// assert_eq(nchoosek(["a", "b"], 3), []);
+    /// ```
+    #[rhai_fn(name = "nchoosek", return_raw)]
+    pub fn nchoosek_array(arr: Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
+        let n = arr.len();
+        if k < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot choose {k} elements, which is negative"),
+            )
+            .in_function("nchoosek")
+            .with_argument("k")
+            .into());
+        }
+        nchoosek(n as INT, k)?;
+        let k = k as usize;
+        if k > n {
+            return Ok(vec![]);
+        }
+        let mut result = vec![];
+        let mut idx = (0..k).collect::<Vec<usize>>();
+        loop {
+            result.push(Dynamic::from_array(
+                idx.iter().map(|i| arr[*i].clone()).collect(),
+            ));
+            // Advance the last position that still has room to move right
+            match (0..k).rev().find(|&i| idx[i] < n - k + i) {
+                Some(i) => {
+                    idx[i] += 1;
+                    for j in i + 1..k {
+                        idx[j] = idx[j - 1] + 1;
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns every permutation of an array, as an array of arrays listed in lexicographic order
+    /// of the positions of their elements.
+    /// ```typescript
+    /// let p = perms([1, 2, 3]);
+    /// assert_eq(p, [[1, 2, 3], [1, 3, 2], [2, 1, 3], [2, 3, 1], [3, 1, 2], [3, 2, 1]]);
+    /// ```
+    /// ```typescript
+    /// assert_eq(len(perms(["a", "b", "c", "d"])), 24);
+    /// ```
+    #[rhai_fn(name = "perms", return_raw)]
+    pub fn perms(arr: Array) -> Result<Array, Box<EvalAltResult>> {
+        let n = arr.len();
+        factorial(n as INT)?;
+        let mut result = vec![];
+        let mut idx = (0..n).collect::<Vec<usize>>();
+        loop {
+            result.push(Dynamic::from_array(
+                idx.iter().map(|i| arr[*i].clone()).collect(),
+            ));
+            // Step to the next permutation in lexicographic order
+            let Some(i) = (1..n).rev().find(|&i| idx[i - 1] < idx[i]) else {
+                break;
+            };
+            let j = (i..n).rev().find(|&j| idx[j] > idx[i - 1]).unwrap();
+            idx.swap(i - 1, j);
+            idx[i..].reverse();
+        }
+        Ok(result)
+    }
+
+    /// Returns the greatest common divisor of two integers, which is always non-negative.
+    /// ```typescript
+    /// assert_eq(gcd(84, -36), 12);
+    /// assert_eq(gcd(0, 5), 5);
+    /// ```
+    #[rhai_fn(name = "gcd")]
+    #[allow(clippy::unnecessary_cast)] // INT may be i32
+    pub fn gcd(a: INT, b: INT) -> INT {
+        super::gcd_u64(a.unsigned_abs() as u64, b.unsigned_abs() as u64) as INT
+    }
+
+    /// Returns the greatest common divisor of all the integers in an array.
+    /// ```typescript
+    /// assert_eq(gcd([12, 18, 30]), 6);
+    /// ```
+    #[rhai_fn(name = "gcd", return_raw)]
+    pub fn gcd_array(arr: Array) -> Result<INT, Box<EvalAltResult>> {
+        super::integer_elements(arr, "gcd").map(|x| x.into_iter().fold(0, gcd))
+    }
+
+    /// Returns the least common multiple of two integers, which is always non-negative. Fails if
+    /// it is too large for an integer.
+    /// ```typescript
+    /// assert_eq(lcm(4, 6), 12);
+    /// assert_eq(lcm(-3, 5), 15);
+    /// assert_eq(lcm(0, 5), 0);
+    /// ```
+    #[rhai_fn(name = "lcm", return_raw)]
+    pub fn lcm(a: INT, b: INT) -> Result<INT, Box<EvalAltResult>> {
+        if a == 0 || b == 0 {
+            return Ok(0);
+        }
+        (a / gcd(a, b))
+            .checked_mul(b)
+            .and_then(|x| x.checked_abs())
+            .ok_or_else(|| super::combinatorics_overflow(format!("The lcm of {a} and {b}")))
+    }
+
+    /// Returns the least common multiple of all the integers in an array.
+    /// ```typescript
+    /// assert_eq(lcm([2, 3, 4, 5]), 60);
+    /// ```
+    #[rhai_fn(name = "lcm", return_raw)]
+    pub fn lcm_array(arr: Array) -> Result<INT, Box<EvalAltResult>> {
+        super::integer_elements(arr, "lcm")?
+            .into_iter()
+            .try_fold(1, lcm)
+    }
+
+    /// Tests whether an integer is prime. This is exact for all integers.
+    /// ```typescript,only_i64
+    /// assert(isprime(97));
+    /// assert(!isprime(1));
+    /// assert(isprime(9223372036854775783));
+    /// ```
+    #[rhai_fn(name = "isprime")]
+    pub fn isprime(n: INT) -> bool {
+        n > 0 && super::is_prime_u64(n as u64)
+    }
+
+    /// Tests whether each integer in an array is prime, returning an array of booleans.
+    /// ```typescript
+    /// assert_eq(isprime([2, 4, 7, 9]), [true, false, true, false]);
+    /// ```
+    #[rhai_fn(name = "isprime", return_raw)]
+    pub fn isprime_array(arr: Array) -> Result<Array, Box<EvalAltResult>> {
+        super::integer_elements(arr, "isprime").map(|x| {
+            x.into_iter()
+                .map(|n| Dynamic::from_bool(isprime(n)))
+                .collect()
+        })
+    }
+
+    /// Returns the prime numbers up to and including `n`, found with the sieve of Eratosthenes.
+    /// The sieve holds one entry per number up to `n`, so `n` is subject to the same limits as
+    /// the size of an array.
+    /// ```typescript
+    /// assert_eq(primes(20), [2, 3, 5, 7, 11, 13, 17, 19]);
+    /// assert_eq(primes(1), []);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try { primes(9223372036854775807); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[rhai_fn(name = "primes", return_raw)]
+    pub fn primes(ctx: NativeCallContext, n: INT) -> Result<Array, Box<EvalAltResult>> {
+        if n < 2 {
+            return Ok(vec![]);
+        }
+        let n = usize::try_from(n).unwrap_or(usize::MAX);
+        crate::check_array_shape(&ctx, &[n.saturating_add(1)])?;
+        let mut sieve = vec![true; n + 1];
+        let mut p = 2;
+        while p * p <= n {
+            if sieve[p] {
+                (p * p..=n).step_by(p).for_each(|k| sieve[k] = false);
+            }
+            p += 1;
+        }
+        Ok((2..=n)
+            .filter(|k| sieve[*k])
+            .map(|k| Dynamic::from_int(k as INT))
+            .collect())
+    }
+
+    /// Returns the prime factors of a positive integer in ascending order, repeated according to
+    /// their multiplicity, so that their product is `n`. Large factors are found with Pollard's
+    /// rho method.
+    /// ```typescript,only_i64
+    /// assert_eq(factor(360), [2, 2, 2, 3, 3, 5]);
+    /// assert_eq(factor(1), []);
+    /// assert_eq(factor(600851475143), [71, 839, 1471, 6857]);
+    /// ```
+    #[rhai_fn(name = "factor", return_raw)]
+    pub fn factor(n: INT) -> Result<Array, Box<EvalAltResult>> {
+        if n < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Only positive integers can be factored, not {n}"),
+            )
+            .in_function("factor")
+            .with_argument("n")
+            .into());
+        }
+        let mut factors = vec![];
+        super::prime_factors(n as u64, &mut factors);
+        factors.sort_unstable();
+        Ok(factors
+            .into_iter()
+            .map(|p| Dynamic::from_int(p as INT))
+            .collect())
+    }
+
+    /// Rounds each element of an array (or matrix) to the nearest integer, with halves rounded away
+    /// from zero.
+    /// ```typescript
+    /// assert_eq(round([1.4, 2.5, -2.5]), [1.0, 3.0, -3.0]);
+    /// assert_eq(round([[0.2, 1.7], [3, -0.6]]), [[0.0, 2.0], [3.0, -1.0]]);
+    /// ```
+    #[rhai_fn(name = "round", return_raw)]
+    pub fn round_array(x: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(Dynamic::from_array(x), &FLOAT::round)
+    }
+
+    /// Rounds a number, or each element of an array or matrix, to `n` digits after the decimal
+    /// point. A negative `n` rounds to the left of the decimal point.
+    /// ```typescript
+    /// assert_eq(round(pi, 2), 3.14);
+    /// assert_eq(round(1234.5, -2), 1200.0);
+    /// assert_eq(round([0.125, 2.675], 1), [0.1, 2.7]);
+    /// ```
+    #[rhai_fn(name = "round", return_raw)]
+    pub fn round_digits(x: Dynamic, n: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &|x| super::round_to_digits(x, n))
+    }
+
+    /// Rounds a number, or each element of an array or matrix, to `n` significant figures.
+    /// ```typescript
+    /// assert_eq(round_sig(123456, 2), 120000.0);
+    /// assert_eq(round_sig(0.00123456, 3), 0.00123);
+    /// assert_eq(round_sig([pi, -2 * pi, 0], 3), [3.14, -6.28, 0.0]);
+    /// ```
+    #[rhai_fn(name = "round_sig", return_raw)]
+    pub fn round_sig(x: Dynamic, n: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+        if n < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("At least one significant figure is needed, not {n}"),
+            )
+            .in_function("round_sig")
+            .with_argument("n")
+            .into());
+        }
+        crate::map_numbers(x, &|x| {
+            if x == 0.0 || !x.is_finite() {
+                x
+            } else {
+                super::round_to_digits(x, n - 1 - x.abs().log10().floor() as INT)
+            }
+        })
+    }
+
+    /// Rounds each element of an array (or matrix) down, towards negative infinity.
+    /// ```typescript
+    /// assert_eq(floor([1.5, -1.5, 2]), [1.0, -2.0, 2.0]);
+    /// ```
+    #[rhai_fn(name = "floor", return_raw)]
+    pub fn floor_array(x: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(Dynamic::from_array(x), &FLOAT::floor)
+    }
+
+    /// Rounds a number, or each element of an array or matrix, up, towards positive infinity.
+    /// ```typescript
+    /// assert_eq(ceil(1.2), 2.0);
+    /// assert_eq(ceil([1.5, -1.5, 2]), [2.0, -1.0, 2.0]);
+    /// assert_eq(ceiling([[0.1], [-1.1]]), [[1.0], [-1.0]]);
+    /// ```
+    #[rhai_fn(name = "ceil", return_raw)]
+    pub fn ceil(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &FLOAT::ceil)
+    }
+
+    /// Rounds each element of an array (or matrix) up, towards positive infinity, as `ceil` does.
+    #[rhai_fn(name = "ceiling", return_raw)]
+    pub fn ceiling_array(x: Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        ceil(Dynamic::from_array(x))
+    }
+
+    /// Rounds a number, or each element of an array or matrix, towards zero.
+    /// ```typescript
+    /// assert_eq(fix(-2.7), -2.0);
+    /// assert_eq(fix([2.7, -2.7, 3]), [2.0, -2.0, 3.0]);
+    /// ```
+    #[rhai_fn(name = "fix", return_raw)]
+    pub fn fix(x: Dynamic) -> Result<Dynamic, Box<EvalAltResult>> {
+        crate::map_numbers(x, &FLOAT::trunc)
+    }
 }
@@ -7,8 +7,16 @@ pub mod moving_functions {
 
     fn mov<G>(arr: &mut Array, k: INT, mut f: G) -> Result<Array, Box<EvalAltResult>>
     where
-        G: FnMut(&mut Array) -> Dynamic,
+        G: FnMut(&mut Array) -> Result<Dynamic, Box<EvalAltResult>>,
     {
+        if k < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The window width must be at least 1, not {k}"),
+            )
+            .with_argument("k")
+            .into());
+        }
         if_list_do(arr, |arr| {
             // First, validate the inputs
             let mut new_arr = vec![];
@@ -23,7 +31,7 @@ pub mod moving_functions {
                             ..=(std::cmp::min(i + k / 2 - 1, n - 1) as usize)
                     })
                     .unwrap()
-                    .to_vec()))
+                    .to_vec())?)
             }
             Ok(new_arr)
         })
@@ -37,7 +45,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movmin", return_raw, pure)]
     pub fn movmin(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::array_min(x).unwrap())
+        mov(arr, k, crate::stats::array_min)
     }
 
     /// Returns an array of the moving maximum (with a given width) across the input array.
@@ -48,7 +56,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movmax", return_raw, pure)]
     pub fn movmax(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::array_max(x).unwrap())
+        mov(arr, k, crate::stats::array_max)
     }
 
     /// Returns an array of the moving maximum absolute deviation (with a given width) across the input array.
@@ -59,7 +67,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movmad", return_raw, pure)]
     pub fn movmad(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::mad(x).unwrap())
+        mov(arr, k, crate::stats::mad)
     }
 
     /// Returns an array of the moving average (with a given width) across the input array.
@@ -68,9 +76,14 @@ pub mod moving_functions {
     /// let m = movmean(data, 3);
     /// assert_eq(m, [1.5, 2.0, 3.0, 4.0, 5.0, 5.5]);
     /// ```
+    /// ```typescript
+    /// let data = [1, 2.5, 4];
+    /// let m = movmean(data, 2);
+    /// assert_eq(m, [1.0, 1.75, 3.25]);
+    /// ```
     #[rhai_fn(name = "movmean", return_raw, pure)]
     pub fn movmean(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::mean(x).unwrap())
+        mov(arr, k, crate::stats::mean)
     }
 
     /// Returns an array of the moving median (with a given width) across the input array.
@@ -79,9 +92,14 @@ pub mod moving_functions {
     /// let m = movmedian(data, 3);
     /// assert_eq(m, [1.5, 2.0, 3.0, 4.0, 5.0, 5.5]);
     /// ```
+    /// ```typescript
+    /// let data = [1, 10.5, 4, 3];
+    /// let m = movmedian(data, 3);
+    /// assert_eq(m, [5.75, 4.0, 4.0, 3.5]);
+    /// ```
     #[rhai_fn(name = "movmedian", return_raw, pure)]
     pub fn movmedian(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::median(x).unwrap())
+        mov(arr, k, crate::stats::median)
     }
 
     /// Returns an array of the moving product (with a given width) across the input array.
@@ -92,7 +110,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movprod", return_raw, pure)]
     pub fn movprod(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::prod(x).unwrap())
+        mov(arr, k, crate::stats::prod)
     }
 
     /// Returns an array of the moving standard deviation (with a given width) across the input array.
@@ -103,7 +121,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movstd", return_raw, pure)]
     pub fn movstd(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::std(x).unwrap())
+        mov(arr, k, crate::stats::std)
     }
 
     /// Returns an array of the moving variance (with a given width) across the input array.
@@ -114,7 +132,7 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movvar", return_raw, pure)]
     pub fn movvar(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::variance(x).unwrap())
+        mov(arr, k, crate::stats::variance)
     }
 
     /// Returns an array of the moving sum (with a given width) across the input array.
@@ -125,6 +143,6 @@ pub mod moving_functions {
     /// ```
     #[rhai_fn(name = "movsum", return_raw, pure)]
     pub fn movsum(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        mov(arr, k, |x| crate::stats::sum(x).unwrap())
+        mov(arr, k, crate::stats::sum)
     }
 }
@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of elements (or, for matrix products, multiply-adds) above which work is split
+/// across threads when the `parallel` feature is enabled, unless changed with
+/// [`set_parallel_threshold`].
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 100_000;
+
+/// The number of elements handed to each task when an array is split into chunks. It does not
+/// depend on the number of threads, so that results are the same however many are used.
+pub const CHUNK_SIZE: usize = 8192;
+
+/// The current threshold for splitting work across threads.
+static THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_PARALLEL_THRESHOLD);
+
+/// The thread pool selected with `set_max_threads`, if any.
+#[cfg(feature = "parallel")]
+static POOL: std::sync::RwLock<Option<std::sync::Arc<rayon::ThreadPool>>> =
+    std::sync::RwLock::new(None);
+
+/// Sets the number of elements above which element-wise operations, reductions, distance
+/// matrices, and matrix products are split across threads. Smaller inputs are handled on the
+/// calling thread, where the cost of starting tasks would outweigh the gain. This has no effect
+/// unless the `parallel` feature is enabled.
+/// ```
+/// rhai_sci::set_parallel_threshold(1_000_000);
+/// assert_eq!(rhai_sci::eval::<rhai::FLOAT>("sum([1.0, 2.0])").unwrap(), 3.0);
+/// rhai_sci::set_parallel_threshold(rhai_sci::DEFAULT_PARALLEL_THRESHOLD);
+/// ```
+pub fn set_parallel_threshold(threshold: usize) {
+    THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+/// Limits the work split across threads to a dedicated pool of `threads` threads, so that a host
+/// application can keep scripts from using every core. Passing `0` goes back to rayon's global
+/// pool, which has one thread per core unless the host configures it otherwise.
+/// ```
+/// rhai_sci::set_max_threads(2).unwrap();
+/// let x = rhai_sci::eval::<rhai::FLOAT>("sum(ones(1, 200000)[0])").unwrap();
+/// assert_eq!(x, 200000.0);
+/// rhai_sci::set_max_threads(0).unwrap();
+/// ```
+#[cfg(feature = "parallel")]
+pub fn set_max_threads(threads: usize) -> Result<(), rayon::ThreadPoolBuildError> {
+    let pool = match threads {
+        0 => None,
+        n => Some(std::sync::Arc::new(
+            rayon::ThreadPoolBuilder::new().num_threads(n).build()?,
+        )),
+    };
+    *POOL.write().unwrap_or_else(|err| err.into_inner()) = pool;
+    Ok(())
+}
+
+/// Whether work on `n` elements should be split across threads.
+pub fn use_parallel(n: usize) -> bool {
+    cfg!(feature = "parallel") && n >= THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Runs `f` in the pool selected with `set_max_threads`, or in rayon's global pool.
+#[cfg(feature = "parallel")]
+fn in_pool<T, F>(f: F) -> T
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let pool = POOL.read().unwrap_or_else(|err| err.into_inner()).clone();
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+/// Computes `f(0), f(1), ..., f(n - 1)`, across threads if `work`, the number of elements (or
+/// multiply-adds) all of the calls handle together, is above the threshold.
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+pub fn parallel_map_indices<T, F>(n: usize, work: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if use_parallel(work) {
+        use rayon::prelude::*;
+        return in_pool(|| (0..n).into_par_iter().map(f).collect());
+    }
+    (0..n).map(f).collect()
+}
+
+/// Applies `f` to consecutive chunks of `x`, across threads if `x` is longer than the threshold,
+/// and returns the results in order. Below the threshold, `f` is applied once to all of `x`.
+pub fn parallel_map_chunks<T, F>(x: &[rhai::FLOAT], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&[rhai::FLOAT]) -> T + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    if use_parallel(x.len()) {
+        use rayon::prelude::*;
+        return in_pool(|| x.par_chunks(CHUNK_SIZE).map(f).collect());
+    }
+    vec![f(x)]
+}
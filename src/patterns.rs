@@ -31,54 +31,114 @@ pub fn int_and_float_totals(arr: &mut Array) -> (INT, INT, INT) {
         })
 }
 
+/// Promotes the integers in an array of numbers to floats if it also has floats, so that
+/// `[1, 2.5, 3]` is treated as `[1.0, 2.5, 3.0]`. Nested arrays (such as matrices) are promoted as
+/// a whole. Arrays that are all integers or all floats, or that have elements other than numbers,
+/// are returned unchanged.
+pub fn promote_numbers(mut arr: Array) -> Array {
+    let (int, float, total) = int_and_float_totals(&mut arr);
+    if int > 0 && float > 0 && int + float == total {
+        arr.into_iter().map(promote_number).collect()
+    } else {
+        arr
+    }
+}
+
+/// Converts an integer, or every integer in a (possibly nested) array, to a float.
+fn promote_number(x: Dynamic) -> Dynamic {
+    if x.is_int() {
+        Dynamic::from_float(x.as_int().unwrap() as FLOAT)
+    } else if x.is_array() {
+        Dynamic::from_array(
+            x.into_array()
+                .unwrap()
+                .into_iter()
+                .map(promote_number)
+                .collect(),
+        )
+    } else {
+        x
+    }
+}
+
+/// Calls `f` with an array, or with a copy of it promoted to floats by `promote_numbers` if it
+/// mixes integers and floats. The array itself is never modified.
+pub fn if_mixed_convert_to_float_and_do<F, T>(arr: &mut Array, f: F) -> T
+where
+    F: FnOnce(&mut Array) -> T,
+{
+    let (int, float, _) = int_and_float_totals(arr);
+    if int > 0 && float > 0 {
+        f(&mut promote_numbers(arr.clone()))
+    } else {
+        f(arr)
+    }
+}
+
+/// Returns a function that names `function`, and `argument` if given, in an error raised by one
+/// of the helpers below, for use with `map_err`. Errors that already name a function, and errors
+/// other than a `LabError`, are left as they are.
+pub fn in_function<'a>(
+    function: &'a str,
+    argument: Option<&'a str>,
+) -> impl Fn(Box<EvalAltResult>) -> Box<EvalAltResult> + 'a {
+    move |err| {
+        if !matches!(*err, EvalAltResult::ErrorRuntime(..)) {
+            return err;
+        }
+        match crate::LabError::from_eval(&err) {
+            Some(mut lab) if lab.function.is_none() => {
+                lab.function = Some(function.to_string());
+                if lab.argument.is_none() {
+                    lab.argument = argument.map(str::to_string);
+                }
+                lab.into()
+            }
+            _ => err,
+        }
+    }
+}
+
 pub fn if_list_do_int_or_do_float<FA, FB, T>(
     arr: &mut Array,
     mut f_int: FA,
-    mut f_float: FB,
+    f_float: FB,
 ) -> Result<T, Box<EvalAltResult>>
 where
     FA: FnMut(&mut Array) -> Result<T, Box<EvalAltResult>>,
     FB: FnMut(&mut Array) -> Result<T, Box<EvalAltResult>>,
 {
-    let (int, float, total) = int_and_float_totals(arr);
+    // Count the elements themselves, since nested arrays cannot be converted
+    let int = arr.iter().filter(|x| x.is_int()).count();
+    let float = arr.iter().filter(|x| x.is_float()).count();
+    let total = arr.len();
     if int == total {
         f_int(arr)
-    } else if float == total {
-        f_float(arr)
     } else if float + int == total {
-        let mut arr_of_float = arr
-            .iter()
-            .map(|el| {
-                Dynamic::from_float(if el.is_int() {
-                    el.as_int().unwrap() as FLOAT
-                } else if el.is_float() {
-                    el.as_float().unwrap()
-                } else {
-                    unreachable!("This should never happen!");
-                })
-            })
-            .collect::<Array>();
-        f_float(&mut arr_of_float)
+        if_mixed_convert_to_float_and_do(arr, f_float)
     } else {
-        Err(EvalAltResult::ErrorArithmetic(
-            "The elements of the input array must either be INT or FLOAT".to_string(),
-            Position::NONE,
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The elements of the input array must either be INT or FLOAT",
         )
         .into())
     }
 }
 
-pub fn if_list_do<F, T>(arr: &mut Array, mut f: F) -> Result<T, Box<EvalAltResult>>
+/// Calls `f` with a list of numbers, promoting it to floats if it mixes integers and floats.
+pub fn if_list_do<F, T>(arr: &mut Array, f: F) -> Result<T, Box<EvalAltResult>>
 where
-    F: FnMut(&mut Array) -> Result<T, Box<EvalAltResult>>,
+    F: FnOnce(&mut Array) -> Result<T, Box<EvalAltResult>>,
 {
-    crate::validation_functions::is_numeric_list(arr)
-        .then(|| f(arr))
-        .unwrap_or(Err(EvalAltResult::ErrorArithmetic(
-            format!("The elements of the input array must either be INT or FLOAT."),
-            Position::NONE,
+    if crate::validation_functions::is_numeric_list(arr) {
+        if_mixed_convert_to_float_and_do(arr, f)
+    } else {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The elements of the input array must either be INT or FLOAT.",
         )
-        .into()))
+        .into())
+    }
 }
 
 pub fn if_list_convert_to_vec_float_and_do<F, T>(
@@ -105,25 +165,24 @@ where
     } else if x.is_int() {
         x.as_int().unwrap() as FLOAT
     } else {
-        return Err(EvalAltResult::ErrorArithmetic(
-            "The input must either be INT or FLOAT".to_string(),
-            Position::NONE,
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The input must either be INT or FLOAT",
         )
         .into());
     };
     f(new_x)
 }
 
-#[cfg(feature = "nalgebra")]
 pub fn if_matrix_do<T, F>(matrix: &mut Array, mut f: F) -> Result<T, Box<EvalAltResult>>
 where
     F: FnMut(&mut Array) -> Result<T, Box<EvalAltResult>>,
 {
     crate::validation_functions::is_matrix(matrix)
         .then(|| f(matrix))
-        .unwrap_or(Err(EvalAltResult::ErrorArithmetic(
-            format!("The input must be a matrix."),
-            Position::NONE,
+        .unwrap_or(Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The input must be a matrix.",
         )
         .into()))
 }
@@ -160,23 +219,23 @@ where
                     .collect::<Vec<Array>>();
                 f(matrix_as_vec1, matrix_as_vec2)
             } else {
-                Err(EvalAltResult::ErrorArithmetic(
-                    "The input matrices are not compatible for this operation".to_string(),
-                    Position::NONE,
+                Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    "The input matrices are not compatible for this operation",
                 )
                 .into())
             }
         } else {
-            Err(EvalAltResult::ErrorArithmetic(
-                "The second input must be a matrix".to_string(),
-                Position::NONE,
+            Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "The second input must be a matrix",
             )
             .into())
         }
     } else {
-        Err(EvalAltResult::ErrorArithmetic(
-            "The first input must be a matrix".to_string(),
-            Position::NONE,
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The first input must be a matrix",
         )
         .into())
     }
@@ -189,21 +248,60 @@ pub fn if_matrix_convert_to_vec_array_and_do<F, T>(
 where
     F: FnMut(Vec<Array>) -> Result<T, Box<EvalAltResult>>,
 {
-    let matrix_as_vec = matrix
-        .into_iter()
-        .map(|x| x.clone().into_array().unwrap())
-        .collect::<Vec<Array>>();
     if crate::validation_functions::is_matrix(matrix) {
+        let matrix_as_vec = matrix
+            .iter()
+            .map(|x| x.clone().into_array().unwrap())
+            .collect::<Vec<Array>>();
         f(matrix_as_vec)
     } else {
-        Err(EvalAltResult::ErrorArithmetic(
-            "The input must be a matrix".to_string(),
-            Position::NONE,
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The input must be a matrix",
         )
         .into())
     }
 }
 
+/// Converts the rows of a non-empty matrix of INTs and FLOATs to a nalgebra matrix.
+#[cfg(feature = "nalgebra")]
+pub fn vec_array_to_dmatrix(
+    matrix_as_vec: &[Array],
+) -> Result<nalgebralib::DMatrix<FLOAT>, Box<EvalAltResult>> {
+    if matrix_as_vec.is_empty() || matrix_as_vec[0].is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "The matrix must not be empty",
+        )
+        .into());
+    }
+    let values = matrix_as_vec
+        .iter()
+        .flatten()
+        .map(|x| if_int_convert_to_float_and_do(x.clone(), Ok))
+        .collect::<Result<Vec<FLOAT>, _>>()?;
+    Ok(nalgebralib::DMatrix::from_row_slice(
+        matrix_as_vec.len(),
+        matrix_as_vec[0].len(),
+        &values,
+    ))
+}
+
+/// Converts a non-empty matrix of INTs and FLOATs to a nalgebra matrix and calls `f` with it,
+/// failing if the input is anything else.
+#[cfg(feature = "nalgebra")]
+pub fn if_matrix_convert_to_dmatrix_and_do<F, T>(
+    matrix: &mut Array,
+    mut f: F,
+) -> Result<T, Box<EvalAltResult>>
+where
+    F: FnMut(nalgebralib::DMatrix<FLOAT>) -> Result<T, Box<EvalAltResult>>,
+{
+    if_matrix_convert_to_vec_array_and_do(matrix, |matrix_as_vec| {
+        f(vec_array_to_dmatrix(&matrix_as_vec)?)
+    })
+}
+
 #[cfg(feature = "smartcore")]
 pub fn if_matrix_convert_to_dense_matrix_and_do<F, T>(
     matrix: &mut Array,
@@ -233,10 +331,11 @@ where
             .collect::<Vec<Vec<FLOAT>>>();
         f(DenseMatrix::from_2d_vec(&matrix_as_vec))
     } else {
-        Err(
-            EvalAltResult::ErrorArithmetic(format!("The input must be a matrix."), Position::NONE)
-                .into(),
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            "The input must be a matrix.",
         )
+        .into())
     }
 }
 
@@ -254,20 +353,161 @@ where
     } else if d.is_array() {
         if_list_do(&mut d.into_array().unwrap(), |arr| f_array(arr))
     } else {
-        Err(EvalAltResult::ErrorArithmetic(
-            "The input must be either an INT or an numeric array".to_string(),
-            Position::NONE,
+        Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "The input must be either an INT or an numeric array",
         )
         .into())
     }
 }
 
+/// What `sum`, `prod`, `cumsum`, and `cumprod` do with an integer result that does not fit in an
+/// INT, as chosen by their `overflow` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntOverflow {
+    /// Fail with an error, which is the default
+    Error,
+    /// Return the result as a FLOAT instead
+    Float,
+}
+
+impl IntOverflow {
+    /// Reads the `overflow` option, `"error"` or `"float"`, from a map of options.
+    pub fn from_options(options: &rhai::Map, function: &str) -> Result<Self, Box<EvalAltResult>> {
+        let error = |msg: String| -> Box<EvalAltResult> {
+            crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+                .in_function(function)
+                .into()
+        };
+        if let Some(key) = options.keys().find(|k| k.as_str() != "overflow") {
+            return Err(error(format!(
+                "Unknown option '{key}', expected \"overflow\""
+            )));
+        }
+        match options.get("overflow").map(|x| x.to_string()).as_deref() {
+            None | Some("error") => Ok(Self::Error),
+            Some("float") => Ok(Self::Float),
+            Some(other) => Err(error(format!(
+                "The overflow option must be \"error\" or \"float\", not {other}"
+            ))),
+        }
+    }
+}
+
+/// Adds integers exactly. An i128 cannot overflow on any array that fits in memory.
+pub fn wide_int_sum(arr: &[Dynamic]) -> i128 {
+    arr.iter().map(|x| x.as_int().unwrap() as i128).sum()
+}
+
+/// Multiplies integers exactly, or returns `None` if the product does not even fit in an i128.
+pub fn wide_int_prod(arr: &[Dynamic]) -> Option<i128> {
+    let x = arr
+        .iter()
+        .map(|x| x.as_int().unwrap() as i128)
+        .collect::<Vec<i128>>();
+    if x.contains(&0) {
+        Some(0)
+    } else {
+        x.into_iter().try_fold(1, i128::checked_mul)
+    }
+}
+
+/// Returns an exact integer result as an INT if it fits, and otherwise either fails or returns it
+/// as a FLOAT. A result too large even for an i128 is passed as `Err` with an approximation.
+pub fn fit_int(
+    result: Result<i128, FLOAT>,
+    overflow: IntOverflow,
+    function: &str,
+) -> Result<Dynamic, Box<EvalAltResult>> {
+    match result.map(INT::try_from) {
+        Ok(Ok(x)) => Ok(Dynamic::from_int(x)),
+        _ if overflow == IntOverflow::Float => Ok(Dynamic::from_float(
+            result.map_or_else(|approx| approx, |x| x as FLOAT),
+        )),
+        _ => Err(int_overflow_error(function)),
+    }
+}
+
+/// The error for an integer result that does not fit in an INT.
+pub fn int_overflow_error(function: &str) -> Box<EvalAltResult> {
+    crate::LabError::new(
+        crate::LabErrorKind::Numerical,
+        "The result is too large for an INT; use #{overflow: \"float\"} to get a FLOAT",
+    )
+    .in_function(function)
+    .into()
+}
+
 pub fn array_to_vec_int(arr: &mut Array) -> Vec<INT> {
     arr.iter()
         .map(|el| el.as_int().unwrap())
         .collect::<Vec<INT>>()
 }
 
+/// Adds floats with Kahan-Babuska (Neumaier) compensated summation, which tracks the low-order
+/// bits lost by each addition, so that long arrays and values of very different magnitudes do not
+/// lose precision.
+pub fn stable_sum<I>(x: I) -> FLOAT
+where
+    I: IntoIterator<Item = FLOAT>,
+{
+    let (mut sum, mut compensation) = (0.0 as FLOAT, 0.0 as FLOAT);
+    for v in x {
+        let t = sum + v;
+        compensation += if sum.abs() >= v.abs() {
+            (sum - t) + v
+        } else {
+            (v - t) + sum
+        };
+        sum = t;
+    }
+    sum + compensation
+}
+
+/// Adds a slice of floats as `stable_sum` does. Long slices are split into chunks that are summed
+/// across threads with the `parallel` feature, and the sums and their compensations are then added
+/// in turn, so no precision is lost.
+pub fn stable_sum_slice(x: &[FLOAT]) -> FLOAT {
+    let partial = crate::parallel_map_chunks(x, |chunk| stable_sum(chunk.iter().copied()));
+    stable_sum(partial)
+}
+
+/// Returns the mean of a slice of floats and the sum of squared deviations from it, updated one
+/// element at a time with Welford's algorithm to avoid the cancellation of the textbook formula.
+/// Long slices are split into chunks across threads with the `parallel` feature, and the results
+/// are merged with the pairwise update of Chan et al.
+pub fn welford(x: &[FLOAT]) -> (FLOAT, FLOAT) {
+    let partial = crate::parallel_map_chunks(x, |chunk| {
+        let (mut mean, mut m2) = (0.0 as FLOAT, 0.0 as FLOAT);
+        for (idx, v) in chunk.iter().enumerate() {
+            let delta = v - mean;
+            mean += delta / (idx + 1) as FLOAT;
+            m2 += delta * (v - mean);
+        }
+        (chunk.len() as FLOAT, mean, m2)
+    });
+    let (_, mean, m2) = partial
+        .into_iter()
+        .reduce(|(n_a, mean_a, m2_a), (n_b, mean_b, m2_b)| {
+            let n = n_a + n_b;
+            let delta = mean_b - mean_a;
+            (
+                n,
+                mean_a + delta * n_b / n,
+                m2_a + m2_b + delta * delta * n_a * n_b / n,
+            )
+        })
+        .unwrap_or((0.0, 0.0, 0.0));
+    (mean, m2)
+}
+
+/// Returns the indices that stably sort a slice of floats in ascending order.
+pub fn argsort_floats(x: &[FLOAT]) -> Vec<usize> {
+    let mut order = (0..x.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| x[*a].total_cmp(&x[*b]));
+    order
+}
+
 #[cfg(feature = "smartcore")]
 pub fn dense_matrix_to_vec_dynamic(dm: DenseMatrix<FLOAT>) -> Vec<Dynamic> {
     let mut output = vec![];
@@ -311,3 +551,552 @@ pub fn ovector_to_vec_dynamic(mat: nalgebralib::OVector<FLOAT, nalgebralib::Dyn>
     }
     out
 }
+
+/// Computes the numerical rank of a matrix, counting the singular values larger than
+/// `max(rows, columns) * epsilon` times the largest.
+#[cfg(feature = "nalgebra")]
+pub fn matrix_rank(matrix: &mut Array) -> Result<INT, Box<EvalAltResult>> {
+    if_matrix_convert_to_dmatrix_and_do(matrix, |dm| {
+        let singular_values = dm.singular_values();
+        let tol = dm.nrows().max(dm.ncols()) as FLOAT * FLOAT::EPSILON * singular_values.max();
+        Ok(singular_values.iter().filter(|s| **s > tol).count() as INT)
+    })
+}
+
+/// Copies a nalgebra matrix into a faer matrix.
+#[cfg(feature = "linalg-backend")]
+fn to_faer(m: &nalgebralib::DMatrix<FLOAT>) -> faer::Mat<FLOAT> {
+    faer::Mat::from_fn(m.nrows(), m.ncols(), |i, j| m[(i, j)])
+}
+
+/// Copies a faer matrix into a nalgebra matrix.
+#[cfg(feature = "linalg-backend")]
+fn from_faer(m: faer::MatRef<'_, FLOAT>) -> nalgebralib::DMatrix<FLOAT> {
+    nalgebralib::DMatrix::from_fn(m.nrows(), m.ncols(), |i, j| m[(i, j)])
+}
+
+/// Multiplies two matrices. With the `linalg-backend` feature, this uses faer's blocked and
+/// vectorized kernels, which are much faster for large matrices.
+#[cfg(feature = "nalgebra")]
+pub fn dense_matmul(
+    a: &nalgebralib::DMatrix<FLOAT>,
+    b: &nalgebralib::DMatrix<FLOAT>,
+) -> nalgebralib::DMatrix<FLOAT> {
+    #[cfg(feature = "linalg-backend")]
+    return from_faer((&to_faer(a) * &to_faer(b)).as_ref());
+    #[cfg(not(feature = "linalg-backend"))]
+    {
+        // Large products are computed a column at a time across threads with `parallel`
+        let work = a.nrows() * a.ncols() * b.ncols();
+        if b.ncols() > 1 && crate::use_parallel(work) {
+            let columns = crate::parallel_map_indices(b.ncols(), work, |j| a * b.column(j));
+            return nalgebralib::DMatrix::from_columns(&columns);
+        }
+        a * b
+    }
+}
+
+/// Inverts a square matrix, returning `None` if it is singular. With the `linalg-backend`
+/// feature, this uses faer.
+#[cfg(feature = "nalgebra")]
+pub fn dense_inverse(a: nalgebralib::DMatrix<FLOAT>) -> Option<nalgebralib::DMatrix<FLOAT>> {
+    #[cfg(feature = "linalg-backend")]
+    {
+        use faer::linalg::solvers::DenseSolveCore;
+        let inverse = from_faer(to_faer(&a).partial_piv_lu().inverse().as_ref());
+        inverse.iter().all(|x| x.is_finite()).then_some(inverse)
+    }
+    #[cfg(not(feature = "linalg-backend"))]
+    a.try_inverse()
+}
+
+/// Solves the square system `A X = B` with an LU decomposition, returning `None` if `A` is
+/// singular. With the `linalg-backend` feature, this uses faer.
+#[cfg(feature = "nalgebra")]
+pub fn dense_lu_solve(
+    a: nalgebralib::DMatrix<FLOAT>,
+    b: &nalgebralib::DMatrix<FLOAT>,
+) -> Option<nalgebralib::DMatrix<FLOAT>> {
+    #[cfg(feature = "linalg-backend")]
+    {
+        use faer::prelude::Solve;
+        let x = from_faer(to_faer(&a).partial_piv_lu().solve(to_faer(b)).as_ref());
+        x.iter().all(|x| x.is_finite()).then_some(x)
+    }
+    #[cfg(not(feature = "linalg-backend"))]
+    a.lu().solve(b)
+}
+
+/// Computes the thin singular value decomposition `A = U S V^T`, returning `U`, the singular
+/// values, and `V^T`. With the `linalg-backend` feature, this uses faer.
+#[cfg(feature = "nalgebra")]
+#[allow(clippy::type_complexity)]
+pub fn dense_svd(
+    a: nalgebralib::DMatrix<FLOAT>,
+) -> Option<(
+    nalgebralib::DMatrix<FLOAT>,
+    nalgebralib::DVector<FLOAT>,
+    nalgebralib::DMatrix<FLOAT>,
+)> {
+    #[cfg(feature = "linalg-backend")]
+    {
+        let svd = to_faer(&a).thin_svd().ok()?;
+        let s = svd.S().column_vector();
+        Some((
+            from_faer(svd.U()),
+            nalgebralib::DVector::from_fn(s.nrows(), |i, _| s[i]),
+            from_faer(svd.V().transpose()),
+        ))
+    }
+    #[cfg(not(feature = "linalg-backend"))]
+    {
+        let svd = nalgebralib::linalg::SVD::new(a, true, true);
+        Some((svd.u?, svd.singular_values, svd.v_t?))
+    }
+}
+
+/// Computes the eigenvalues and eigenvectors (as columns) of a symmetric matrix, in no particular
+/// order. With the `linalg-backend` feature, this uses faer.
+#[cfg(feature = "nalgebra")]
+pub fn dense_symmetric_eigen(
+    a: nalgebralib::DMatrix<FLOAT>,
+) -> (nalgebralib::DVector<FLOAT>, nalgebralib::DMatrix<FLOAT>) {
+    #[cfg(feature = "linalg-backend")]
+    if let Ok(eigen) = to_faer(&a).self_adjoint_eigen(faer::Side::Lower) {
+        let s = eigen.S().column_vector();
+        return (
+            nalgebralib::DVector::from_fn(s.nrows(), |i, _| s[i]),
+            from_faer(eigen.U()),
+        );
+    }
+    let se = a.symmetric_eigen();
+    (se.eigenvalues, se.eigenvectors)
+}
+
+/// Resolves a selection along a dimension of length `n` into indices. The selection can be an
+/// integer (negative counts from the end), a range such as `1..3` or `1..=2` (clamped to the
+/// dimension, like slicing in Python), or an array of integers.
+pub fn selection_indices(selection: &Dynamic, n: usize) -> Result<Vec<usize>, Box<EvalAltResult>> {
+    let resolve = |i: INT| -> Result<usize, Box<EvalAltResult>> {
+        let idx = if i < 0 { i + n as INT } else { i };
+        if idx < 0 || idx >= n as INT {
+            Err(EvalAltResult::ErrorArrayBounds(n, i, Position::NONE).into())
+        } else {
+            Ok(idx as usize)
+        }
+    };
+    let clamp = |i: INT| {
+        let idx = if i < 0 { i + n as INT } else { i };
+        idx.clamp(0, n as INT) as usize
+    };
+
+    if let Ok(i) = selection.as_int() {
+        Ok(vec![resolve(i)?])
+    } else if let Some(range) = selection.read_lock::<std::ops::Range<INT>>() {
+        Ok((clamp(range.start)..clamp(range.end)).collect())
+    } else if let Some(range) = selection.read_lock::<std::ops::RangeInclusive<INT>>() {
+        Ok((clamp(*range.start())..clamp(*range.end()).saturating_add(1).min(n)).collect())
+    } else if let Some(arr) = selection.read_lock::<Array>() {
+        arr.iter()
+            .map(|i| {
+                i.as_int()
+                    .map_err(|_| -> Box<EvalAltResult> {
+                        crate::LabError::new(
+                            crate::LabErrorKind::InvalidArgument,
+                            "An array of indices must only contain integers",
+                        )
+                        .into()
+                    })
+                    .and_then(resolve)
+            })
+            .collect()
+    } else {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::TypeMismatch,
+            format!(
+                "Indices must be an integer, a range, or an array of integers, not {}",
+                selection.type_name()
+            ),
+        )
+        .into())
+    }
+}
+
+/// Converts an array of `[x, y]` pairs into a vector of 2-D points.
+#[cfg(any(feature = "geometry", feature = "interpolation"))]
+pub fn array_to_points_2d(arr: Array) -> Result<Vec<[FLOAT; 2]>, Box<EvalAltResult>> {
+    let m = crate::Matrix::from_array(&arr)?;
+    if m.cols() != 2 && !arr.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "Points must be given as an array of [x, y] pairs",
+        )
+        .into());
+    }
+    Ok(m.data().chunks(2).map(|p| [p[0], p[1]]).collect())
+}
+
+/// Computes the Delaunay triangulation of a set of 2-D points with the Bowyer-Watson algorithm,
+/// returning triangles as counter-clockwise triples of point indices.
+#[cfg(any(feature = "geometry", feature = "interpolation"))]
+pub fn delaunay_triangles(points: &[[FLOAT; 2]]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return vec![];
+    }
+
+    // Enclose every point in a large super-triangle, whose vertices are removed at the end
+    let (mut xmin, mut ymin, mut xmax, mut ymax) = (FLOAT::MAX, FLOAT::MAX, FLOAT::MIN, FLOAT::MIN);
+    for [x, y] in points {
+        xmin = xmin.min(*x);
+        xmax = xmax.max(*x);
+        ymin = ymin.min(*y);
+        ymax = ymax.max(*y);
+    }
+    let d = (xmax - xmin).max(ymax - ymin).max(1.0);
+    let (xmid, ymid) = ((xmin + xmax) / 2.0, (ymin + ymax) / 2.0);
+    let mut pts = points.to_vec();
+    pts.push([xmid - 20.0 * d, ymid - d]);
+    pts.push([xmid + 20.0 * d, ymid - d]);
+    pts.push([xmid, ymid + 20.0 * d]);
+
+    let orient = |a: usize, b: usize, c: usize| {
+        (pts[b][0] - pts[a][0]) * (pts[c][1] - pts[a][1])
+            - (pts[b][1] - pts[a][1]) * (pts[c][0] - pts[a][0])
+    };
+    let in_circumcircle = |[a, b, c]: [usize; 3], p: usize| {
+        let [px, py] = pts[p];
+        let rows = [a, b, c].map(|v| {
+            let (dx, dy) = (pts[v][0] - px, pts[v][1] - py);
+            [dx, dy, dx * dx + dy * dy]
+        });
+        let det = rows[0][0] * (rows[1][1] * rows[2][2] - rows[2][1] * rows[1][2])
+            - rows[0][1] * (rows[1][0] * rows[2][2] - rows[2][0] * rows[1][2])
+            + rows[0][2] * (rows[1][0] * rows[2][1] - rows[2][0] * rows[1][1]);
+        det > 0.0
+    };
+
+    let mut triangles = vec![[n, n + 1, n + 2]];
+    for p in 0..n {
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) =
+            triangles.into_iter().partition(|t| in_circumcircle(*t, p));
+
+        // The boundary of the cavity consists of the edges that belong to only one bad triangle
+        let mut edges: Vec<[usize; 2]> = vec![];
+        for [a, b, c] in &bad {
+            for edge in [[*a, *b], [*b, *c], [*c, *a]] {
+                if let Some(pos) = edges
+                    .iter()
+                    .position(|e| e[0] == edge[1] && e[1] == edge[0])
+                {
+                    edges.swap_remove(pos);
+                } else {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        triangles = good;
+        for [a, b] in edges {
+            if orient(a, b, p) > 0.0 {
+                triangles.push([a, b, p]);
+            }
+        }
+    }
+
+    triangles.retain(|t| t.iter().all(|v| *v < n));
+    triangles
+}
+
+/// Resolves a requested shape for `n` elements, where at most one dimension can be `-1` to have
+/// its size inferred.
+pub fn resolve_shape(
+    shape: &[rhai::INT],
+    n: usize,
+) -> Result<Vec<usize>, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if shape.iter().filter(|d| **d == -1).count() > 1 || shape.iter().any(|d| *d < -1) {
+        return Err(error(format!(
+            "Sizes must be non-negative, with at most one -1 to infer, not {shape:?}"
+        )));
+    }
+    let overflow = || -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!("Cannot reshape {n} elements into size {shape:?}, which is too large"),
+        )
+        .into()
+    };
+    let known = shape
+        .iter()
+        .filter(|d| **d >= 0)
+        .try_fold(1_usize, |total, d| total.checked_mul(*d as usize))
+        .ok_or_else(overflow)?;
+    let inferred = match (shape.contains(&-1), known) {
+        (false, _) => 0,
+        (true, 0) => return Err(error(format!("Cannot infer a size for {shape:?}"))),
+        (true, known) => n / known,
+    };
+    let resolved = shape
+        .iter()
+        .map(|d| if *d == -1 { inferred } else { *d as usize })
+        .collect::<Vec<_>>();
+    let total = resolved
+        .iter()
+        .try_fold(1_usize, |total, d| total.checked_mul(*d))
+        .ok_or_else(overflow)?;
+    if total != n {
+        return Err(error(format!(
+            "Cannot reshape {n} elements into size {shape:?}"
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Applies `f` to a number, or to every number in a (possibly nested) array, returning floats.
+pub fn map_numbers<F>(x: Dynamic, f: &F) -> Result<Dynamic, Box<EvalAltResult>>
+where
+    F: Fn(FLOAT) -> FLOAT,
+{
+    if x.is_array() {
+        x.into_array()
+            .unwrap()
+            .into_iter()
+            .map(|el| map_numbers(el, f))
+            .collect::<Result<Array, _>>()
+            .map(Dynamic::from_array)
+    } else {
+        if_int_convert_to_float_and_do(x, |x| Ok(Dynamic::from_float(f(x))))
+    }
+}
+
+/// Applies `f` to pairs of numbers taken from two numbers or (possibly nested) arrays of the same
+/// shape. A number is paired with every element of an array.
+pub fn zip_numbers<F>(a: Dynamic, b: Dynamic, f: &F) -> Result<Dynamic, Box<EvalAltResult>>
+where
+    F: Fn(FLOAT, FLOAT) -> FLOAT,
+{
+    match (a.is_array(), b.is_array()) {
+        (false, false) => if_int_convert_to_float_and_do(a, |a| {
+            if_int_convert_to_float_and_do(b.clone(), |b| Ok(Dynamic::from_float(f(a, b))))
+        }),
+        (true, false) => map_numbers_with(a, |x| zip_numbers(x, b.clone(), f)),
+        (false, true) => map_numbers_with(b, |y| zip_numbers(a.clone(), y, f)),
+        (true, true) => {
+            let (a, b) = (a.into_array().unwrap(), b.into_array().unwrap());
+            if a.len() != b.len() {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::DimensionMismatch,
+                    format!(
+                        "The arrays must have the same length, not {} and {}",
+                        a.len(),
+                        b.len()
+                    ),
+                )
+                .into());
+            }
+            a.into_iter()
+                .zip(b)
+                .map(|(x, y)| zip_numbers(x, y, f))
+                .collect::<Result<Array, _>>()
+                .map(Dynamic::from_array)
+        }
+    }
+}
+
+/// Applies `f` to each element of an array, collecting the results into an array.
+fn map_numbers_with<F>(arr: Dynamic, f: F) -> Result<Dynamic, Box<EvalAltResult>>
+where
+    F: FnMut(Dynamic) -> Result<Dynamic, Box<EvalAltResult>>,
+{
+    arr.into_array()
+        .unwrap()
+        .into_iter()
+        .map(f)
+        .collect::<Result<Array, _>>()
+        .map(Dynamic::from_array)
+}
+
+/// Returns `n` floats evenly spaced from `x1` to `x2`, including both. The last is pinned to `x2`
+/// so that it does not collect round-off.
+pub fn evenly_spaced(x1: FLOAT, x2: FLOAT, n: usize) -> Vec<FLOAT> {
+    let interval = (x2 - x1) / (n as FLOAT - 1.0);
+    (0..n)
+        .map(|i| {
+            if i + 1 == n {
+                x2
+            } else {
+                x1 + (i as FLOAT) * interval
+            }
+        })
+        .collect()
+}
+
+/// Counts the elements of a nested array with the given shape the way Rhai does for
+/// `max_array_size`, which includes the inner arrays as well as the numbers in them.
+pub fn nested_array_size(shape: &[usize]) -> usize {
+    shape
+        .iter()
+        .scan(1_usize, |n, d| {
+            *n = n.saturating_mul(*d);
+            Some(*n)
+        })
+        .fold(0, usize::saturating_add)
+}
+
+/// Fails with the error Rhai itself raises for data that is too large if `arrays` array elements
+/// and `maps` map entries would exceed the `max_array_size` and `max_map_size` of the engine
+/// running the script. Functions that build large values call this before allocating them, so a
+/// sandboxed script cannot exhaust memory first. Limits of zero, and the `unchecked` feature, mean
+/// no limit, but even then an array too large to allocate at all is an error rather than a panic
+/// or an abort.
+#[cfg_attr(feature = "unchecked", allow(unused_variables))]
+pub fn check_data_size(
+    context: &rhai::NativeCallContext,
+    arrays: usize,
+    maps: usize,
+) -> Result<(), Box<EvalAltResult>> {
+    #[cfg(not(feature = "unchecked"))]
+    {
+        let engine = context.engine();
+        let exceeds = |limit: usize, size: usize| limit > 0 && size > limit;
+        if exceeds(engine.max_array_size(), arrays) {
+            return Err(EvalAltResult::ErrorDataTooLarge(
+                "Size of array".to_string(),
+                context.call_position(),
+            )
+            .into());
+        }
+        if exceeds(engine.max_map_size(), maps) {
+            return Err(EvalAltResult::ErrorDataTooLarge(
+                "Size of object map".to_string(),
+                context.call_position(),
+            )
+            .into());
+        }
+    }
+    // Reserving the memory (without touching it) is cheap, and fails for sizes that overflow or
+    // that the system can't provide
+    if arrays > SMALL_ALLOCATION && Vec::<Dynamic>::new().try_reserve_exact(arrays).is_err() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("An array of {arrays} elements is too large to allocate"),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The number of array elements below which `check_data_size` doesn't check that they can be
+/// allocated.
+const SMALL_ALLOCATION: usize = 1 << 16;
+
+/// Checks, as `check_data_size` does, that a nested array with the given shape is allowed.
+pub fn check_array_shape(
+    context: &rhai::NativeCallContext,
+    shape: &[usize],
+) -> Result<(), Box<EvalAltResult>> {
+    check_data_size(context, nested_array_size(shape), 0)
+}
+
+/// Orders two categories for encoding: numbers by value, then strings, characters, and booleans
+/// by their natural order. Values of different kinds cannot be compared.
+pub fn compare_categories(
+    a: &rhai::Dynamic,
+    b: &rhai::Dynamic,
+) -> Result<std::cmp::Ordering, Box<rhai::EvalAltResult>> {
+    let number = |x: &rhai::Dynamic| match (x.as_float(), x.as_int()) {
+        (Ok(f), _) => Some(f),
+        (_, Ok(i)) => Some(i as rhai::FLOAT),
+        _ => None,
+    };
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => return Ok(x.total_cmp(&y)),
+        (None, None) => (),
+        _ => return Err(mixed_categories(a, b)),
+    }
+    if let (Ok(x), Ok(y)) = (a.as_bool(), b.as_bool()) {
+        Ok(x.cmp(&y))
+    } else if let (Ok(x), Ok(y)) = (a.as_char(), b.as_char()) {
+        Ok(x.cmp(&y))
+    } else if let (Some(x), Some(y)) = (
+        a.read_lock::<rhai::ImmutableString>(),
+        b.read_lock::<rhai::ImmutableString>(),
+    ) {
+        Ok(x.as_str().cmp(y.as_str()))
+    } else {
+        Err(mixed_categories(a, b))
+    }
+}
+
+/// The error for categories that cannot be compared.
+fn mixed_categories(a: &rhai::Dynamic, b: &rhai::Dynamic) -> Box<rhai::EvalAltResult> {
+    crate::LabError::new(
+        crate::LabErrorKind::TypeMismatch,
+        format!(
+            "Categories must all be numbers, strings, characters, or booleans, not both {} and {}",
+            a.type_name(),
+            b.type_name()
+        ),
+    )
+    .into()
+}
+
+/// The distinct values of an array, sorted.
+pub fn sorted_categories(arr: &rhai::Array) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let mut labels = arr.clone();
+    let mut error = None;
+    labels.sort_by(|a, b| {
+        compare_categories(a, b).unwrap_or_else(|err| {
+            error.get_or_insert(err);
+            std::cmp::Ordering::Equal
+        })
+    });
+    if let Some(err) = error {
+        return Err(err);
+    }
+    labels.dedup_by(|a, b| compare_categories(a, b).is_ok_and(|o| o.is_eq()));
+    Ok(labels)
+}
+
+/// The position of each value of an array among sorted, distinct labels.
+pub fn category_codes(
+    arr: &rhai::Array,
+    labels: &rhai::Array,
+) -> Result<Vec<usize>, Box<rhai::EvalAltResult>> {
+    arr.iter()
+        .map(|x| {
+            let mut error = None;
+            let found = labels.binary_search_by(|label| {
+                compare_categories(label, x).unwrap_or_else(|err| {
+                    error.get_or_insert(err);
+                    std::cmp::Ordering::Equal
+                })
+            });
+            match (error, found) {
+                (Some(err), _) => Err(err),
+                (None, Ok(code)) => Ok(code),
+                (None, Err(_)) => Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("The value {x} is not one of the labels {labels:?}"),
+                )
+                .into()),
+            }
+        })
+        .collect()
+}
+
+/// Calls a script callback with the state of an iterative routine, such as a solver, and returns
+/// whether the routine should keep going. The callback stops the routine early by returning
+/// `false`, and any other result (including `()`) lets it continue.
+#[cfg(feature = "sparse")]
+pub fn keep_iterating(
+    context: &rhai::NativeCallContext,
+    callback: &rhai::FnPtr,
+    state: rhai::Map,
+) -> Result<bool, Box<EvalAltResult>> {
+    let result = callback.call_within_context::<Dynamic>(context, (state,))?;
+    Ok(result.as_bool().unwrap_or(true))
+}
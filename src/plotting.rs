@@ -0,0 +1,805 @@
+use rhai::plugin::*;
+
+/// The options accepted by the text charts `plot`, `scatter`, and `hist`.
+const CHART_OPTIONS: [&str; 3] = ["width", "height", "bins"];
+
+/// The size of a text chart and, for histograms, the number of bins.
+struct ChartOptions {
+    /// Columns in the plotting area
+    width: usize,
+    /// Rows in the plotting area
+    height: usize,
+    /// Bins of a histogram
+    bins: usize,
+}
+
+/// Reads the options described for `plot`, `scatter`, and `hist`.
+fn chart_options(options: &rhai::Map) -> Result<ChartOptions, Box<rhai::EvalAltResult>> {
+    if let Some(key) = options
+        .keys()
+        .find(|k| !CHART_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Unknown option '{key}', expected one of {CHART_OPTIONS:?}"),
+        )
+        .into());
+    }
+    let count = |key: &str, default: usize| match options.get(key) {
+        None => Ok(default),
+        Some(value) => match value.as_int() {
+            Ok(n) if n >= 1 => Ok(n as usize),
+            _ => Err(rhai::EvalAltResult::from(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The {key} option must be a positive INT"),
+            ))),
+        },
+    };
+    Ok(ChartOptions {
+        width: count("width", 60)?,
+        height: count("height", 15)?,
+        bins: count("bins", 10)?,
+    })
+}
+
+/// Formats an axis label compactly, using scientific notation for very large or small values.
+fn tick_label(value: rhai::FLOAT) -> String {
+    if value != 0.0 && (value.abs() >= 1e5 || value.abs() < 1e-3) {
+        format!("{value:.2e}")
+    } else {
+        let text = format!("{value:.3}");
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Reads the numbers to plot from an array of INTs and FLOATs.
+fn chart_values(arr: &rhai::Array) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    arr.iter()
+        .map(|x| match (x.as_float(), x.as_int()) {
+            (Ok(f), _) => Ok(f),
+            (_, Ok(i)) => Ok(i as rhai::FLOAT),
+            _ => Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!("Only INTs and FLOATs can be plotted, not {}", x.type_name()),
+            )
+            .into()),
+        })
+        .collect()
+}
+
+/// The range of the finite values, widened around a single value so it is never empty.
+fn axis_range(values: &[rhai::FLOAT]) -> Option<(rhai::FLOAT, rhai::FLOAT)> {
+    let (lo, hi) = values.iter().filter(|v| v.is_finite()).fold(
+        (rhai::FLOAT::INFINITY, rhai::FLOAT::NEG_INFINITY),
+        |(lo, hi), &v| (lo.min(v), hi.max(v)),
+    );
+    match (lo.is_finite(), lo == hi) {
+        (false, _) => None,
+        (true, true) => Some((lo - 0.5, hi + 0.5)),
+        (true, false) => Some((lo, hi)),
+    }
+}
+
+/// The cell along an axis of `cells` cells that a value within `range` falls in.
+fn chart_cell(value: rhai::FLOAT, (lo, hi): (rhai::FLOAT, rhai::FLOAT), cells: usize) -> usize {
+    (((value - lo) / (hi - lo)) * (cells - 1) as rhai::FLOAT).round() as usize
+}
+
+/// Surrounds a grid of characters, top row first, with a y axis labelled with `y_range` on the
+/// left and an x axis labelled with `x_range` below.
+fn frame_chart(grid: &[Vec<char>], x_range: (String, String), y_range: (String, String)) -> String {
+    let width = grid.first().map_or(0, |row| row.len());
+    let pad = y_range.0.chars().count().max(y_range.1.chars().count());
+    let mut lines = grid
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let (label, tick) = match r {
+                0 => (y_range.1.as_str(), '┤'),
+                r if r + 1 == grid.len() => (y_range.0.as_str(), '┤'),
+                _ => ("", '│'),
+            };
+            let row = row.iter().collect::<String>();
+            format!("{label:>pad$} {tick}{}", row.trim_end())
+        })
+        .collect::<Vec<_>>();
+    lines.push(format!("{:pad$} └{}", "", "─".repeat(width)));
+    let gap = width.saturating_sub(x_range.0.chars().count());
+    lines.push(format!("{:pad$}  {}{:>gap$}", "", x_range.0, x_range.1));
+    lines.join("\n")
+}
+
+/// Draws `y` against `x` as a text chart, marking each point with `mark` and, when `connect` is
+/// set, joining consecutive points with a line of dots.
+fn xy_chart(
+    x: &rhai::Array,
+    y: &rhai::Array,
+    options: &ChartOptions,
+    mark: char,
+    connect: bool,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let x = chart_values(x)?;
+    let y = chart_values(y)?;
+    if x.len() != y.len() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!(
+                "The x and y arrays must have the same length, not {} and {}",
+                x.len(),
+                y.len()
+            ),
+        )
+        .into());
+    }
+    let finite = x
+        .iter()
+        .zip(&y)
+        .filter(|(a, b)| a.is_finite() && b.is_finite())
+        .map(|(&a, &b)| (a, b))
+        .collect::<Vec<_>>();
+    let no_points = || -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "There are no finite points to plot",
+        )
+        .into()
+    };
+    let x_range =
+        axis_range(&finite.iter().map(|p| p.0).collect::<Vec<_>>()).ok_or_else(no_points)?;
+    let y_range =
+        axis_range(&finite.iter().map(|p| p.1).collect::<Vec<_>>()).ok_or_else(no_points)?;
+    let (width, height) = (options.width, options.height);
+    let cell = |a: rhai::FLOAT, b: rhai::FLOAT| {
+        (
+            chart_cell(a, x_range, width) as i64,
+            (height - 1 - chart_cell(b, y_range, height)) as i64,
+        )
+    };
+
+    let mut grid = vec![vec![' '; width]; height];
+    if connect {
+        // Missing values break the line rather than joining across the gap.
+        let points = x.iter().zip(&y);
+        for ((&x0, &y0), (&x1, &y1)) in points.clone().zip(points.skip(1)) {
+            if ![x0, y0, x1, y1].iter().all(|v| v.is_finite()) {
+                continue;
+            }
+            let (c0, r0) = cell(x0, y0);
+            let (c1, r1) = cell(x1, y1);
+            let steps = (c1 - c0).abs().max((r1 - r0).abs()).max(1);
+            for s in 0..=steps {
+                let t = s as rhai::FLOAT / steps as rhai::FLOAT;
+                let c = c0 + ((c1 - c0) as rhai::FLOAT * t).round() as i64;
+                let r = r0 + ((r1 - r0) as rhai::FLOAT * t).round() as i64;
+                grid[r as usize][c as usize] = '·';
+            }
+        }
+    }
+    for (a, b) in finite {
+        let (c, r) = cell(a, b);
+        grid[r as usize][c as usize] = mark;
+    }
+    Ok(frame_chart(
+        &grid,
+        (tick_label(x_range.0), tick_label(x_range.1)),
+        (tick_label(y_range.0), tick_label(y_range.1)),
+    ))
+}
+
+/// Counts the finite values in each of `bins` equally wide bins spanning `range`, with the last
+/// bin including its upper edge.
+fn histogram_counts(
+    values: &[rhai::FLOAT],
+    range: (rhai::FLOAT, rhai::FLOAT),
+    bins: usize,
+) -> Vec<usize> {
+    let mut counts = vec![0_usize; bins];
+    for v in values.iter().filter(|v| v.is_finite()) {
+        let bin = ((v - range.0) / (range.1 - range.0) * bins as rhai::FLOAT) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+/// Draws a histogram of the finite values of an array as a text chart of vertical bars.
+fn hist_chart(
+    arr: &rhai::Array,
+    options: &ChartOptions,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let values = chart_values(arr)?;
+    let range = axis_range(&values).ok_or_else(|| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "There are no finite values to plot",
+        )
+        .into()
+    })?;
+    let bins = options.bins;
+    let counts = histogram_counts(&values, range, bins);
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar = (options.width / bins).max(1);
+    let height = options.height;
+    let grid = (0..height)
+        .map(|r| {
+            let level = height - r;
+            counts
+                .iter()
+                .flat_map(|&count| {
+                    let filled = (count * height).div_ceil(most) >= level && count > 0;
+                    let fill = if filled { '█' } else { ' ' };
+                    // Wide bars leave a gap before the next one.
+                    let gap = usize::from(bar > 1);
+                    std::iter::repeat_n(fill, bar - gap).chain(std::iter::repeat_n(' ', gap))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    Ok(frame_chart(
+        &grid,
+        (tick_label(range.0), tick_label(range.1)),
+        ("0".to_string(), most.to_string()),
+    ))
+}
+
+/// Renders values as a sparkline of block characters scaled between the smallest and largest
+/// finite values, first averaging them into `width` equal buckets when there are more than that.
+fn sparkline_string(
+    arr: &rhai::Array,
+    width: Option<usize>,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let mut values = chart_values(arr)?;
+    if let Some(width) = width.filter(|&w| w < values.len()) {
+        // Missing values are left out of a bucket's average, which is missing only if all are.
+        let n = values.len();
+        values = (0..width)
+            .map(|b| {
+                let bucket = values[b * n / width..(b + 1) * n / width]
+                    .iter()
+                    .filter(|v| v.is_finite())
+                    .collect::<Vec<_>>();
+                if bucket.is_empty() {
+                    rhai::FLOAT::NAN
+                } else {
+                    bucket.iter().copied().sum::<rhai::FLOAT>() / bucket.len() as rhai::FLOAT
+                }
+            })
+            .collect();
+    }
+    let Some((lo, hi)) = axis_range(&values) else {
+        return Ok(" ".repeat(values.len()));
+    };
+    Ok(values
+        .iter()
+        .map(|v| {
+            if v.is_finite() {
+                let level = ((v - lo) / (hi - lo) * 7.0).round() as usize;
+                LEVELS[level.min(7)]
+            } else {
+                ' '
+            }
+        })
+        .collect())
+}
+
+/// The options accepted by `plot_to_file`.
+#[cfg(feature = "plotters")]
+const FIGURE_OPTIONS: [&str; 7] = [
+    "kind", "title", "x_label", "y_label", "width", "height", "bins",
+];
+
+/// The look and size of a figure saved with `plot_to_file`.
+#[cfg(feature = "plotters")]
+struct FigureOptions {
+    /// One of "line", "scatter", "histogram", or "heatmap", if given
+    kind: Option<String>,
+    /// Caption above the chart
+    title: String,
+    /// Description of the x axis
+    x_label: String,
+    /// Description of the y axis
+    y_label: String,
+    /// Width of the image in pixels
+    width: u32,
+    /// Height of the image in pixels
+    height: u32,
+    /// Bins of a histogram
+    bins: usize,
+}
+
+/// Reads the options described for `plot_to_file`.
+#[cfg(feature = "plotters")]
+fn figure_options(options: &rhai::Map) -> Result<FigureOptions, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options
+        .keys()
+        .find(|k| !FIGURE_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {FIGURE_OPTIONS:?}"
+        )));
+    }
+    let text = |key: &str| match options.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .clone()
+            .into_string()
+            .map(Some)
+            .map_err(|_| error(format!("The {key} option must be a string"))),
+    };
+    let count = |key: &str, default: usize| match options.get(key) {
+        None => Ok(default),
+        Some(value) => match value.as_int() {
+            Ok(n) if n >= 1 => Ok(n as usize),
+            _ => Err(error(format!("The {key} option must be a positive INT"))),
+        },
+    };
+    Ok(FigureOptions {
+        kind: text("kind")?,
+        title: text("title")?.unwrap_or_default(),
+        x_label: text("x_label")?.unwrap_or_default(),
+        y_label: text("y_label")?.unwrap_or_default(),
+        width: count("width", 800)? as u32,
+        height: count("height", 600)? as u32,
+        bins: count("bins", 10)?,
+    })
+}
+
+/// The data behind a figure saved with `plot_to_file`, ready to draw.
+#[cfg(feature = "plotters")]
+enum Figure {
+    /// Points of a line chart, with non-finite points breaking the line
+    Line(Vec<(rhai::FLOAT, rhai::FLOAT)>),
+    /// Finite points of a scatter chart
+    Scatter(Vec<(rhai::FLOAT, rhai::FLOAT)>),
+    /// Bin edges and counts of a histogram
+    Histogram(Vec<(rhai::FLOAT, rhai::FLOAT, usize)>),
+    /// Rows of a matrix shown as colored cells, with the range of its finite values
+    Heatmap(Vec<Vec<rhai::FLOAT>>, (rhai::FLOAT, rhai::FLOAT)),
+}
+
+#[cfg(feature = "plotters")]
+impl Figure {
+    /// The ranges of the x and y axes.
+    fn axes(&self) -> ((rhai::FLOAT, rhai::FLOAT), (rhai::FLOAT, rhai::FLOAT)) {
+        match self {
+            Figure::Line(points) | Figure::Scatter(points) => {
+                let finite = points
+                    .iter()
+                    .filter(|(x, y)| x.is_finite() && y.is_finite())
+                    .collect::<Vec<_>>();
+                let x = finite.iter().map(|p| p.0).collect::<Vec<_>>();
+                let y = finite.iter().map(|p| p.1).collect::<Vec<_>>();
+                (
+                    axis_range(&x).unwrap_or((0.0, 1.0)),
+                    axis_range(&y).unwrap_or((0.0, 1.0)),
+                )
+            }
+            Figure::Histogram(bins) => {
+                let most = bins.iter().map(|b| b.2).max().unwrap_or(0).max(1);
+                (
+                    (bins[0].0, bins[bins.len() - 1].1),
+                    (0.0, most as rhai::FLOAT * 1.05),
+                )
+            }
+            Figure::Heatmap(rows, _) => (
+                (0.0, rows[0].len() as rhai::FLOAT),
+                (0.0, rows.len() as rhai::FLOAT),
+            ),
+        }
+    }
+}
+
+/// Prepares the data for `plot_to_file` as a chart of the given kind: "line" or "scatter" for
+/// `y` against `x`, "histogram" for the values of `x`, or "heatmap" for the matrix `x`.
+#[cfg(feature = "plotters")]
+fn prepare_figure(
+    kind: &str,
+    x: &rhai::Array,
+    y: Option<&rhai::Array>,
+    bins: usize,
+) -> Result<Figure, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    let no_values = || error("There are no finite values to plot".to_string());
+    match (kind, y) {
+        ("line" | "scatter", _) => {
+            let (x, y) = match y {
+                Some(y) => (chart_values(x)?, chart_values(y)?),
+                None => {
+                    let y = chart_values(x)?;
+                    ((1..=y.len()).map(|i| i as rhai::FLOAT).collect(), y)
+                }
+            };
+            if x.len() != y.len() {
+                return Err(error(format!(
+                    "The x and y arrays must have the same length, not {} and {}",
+                    x.len(),
+                    y.len()
+                )));
+            }
+            let points = x.into_iter().zip(y).collect::<Vec<_>>();
+            if !points.iter().any(|(x, y)| x.is_finite() && y.is_finite()) {
+                return Err(no_values());
+            }
+            Ok(if kind == "line" {
+                Figure::Line(points)
+            } else {
+                Figure::Scatter(
+                    points
+                        .into_iter()
+                        .filter(|(x, y)| x.is_finite() && y.is_finite())
+                        .collect(),
+                )
+            })
+        }
+        ("histogram", None) => {
+            let values = chart_values(x)?;
+            let range = axis_range(&values).ok_or_else(no_values)?;
+            let width = (range.1 - range.0) / bins as rhai::FLOAT;
+            let counts = histogram_counts(&values, range, bins);
+            Ok(Figure::Histogram(
+                counts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, count)| {
+                        let lo = range.0 + width * i as rhai::FLOAT;
+                        (lo, lo + width, count)
+                    })
+                    .collect(),
+            ))
+        }
+        ("heatmap", None) => {
+            let rows = x
+                .iter()
+                .map(|row| {
+                    row.clone()
+                        .into_array()
+                        .map_err(|_| error("A heatmap needs a matrix".to_string()))
+                        .and_then(|row| chart_values(&row))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let ncols = rows.first().map_or(0, |row| row.len());
+            if ncols == 0 || rows.iter().any(|row| row.len() != ncols) {
+                return Err(error(
+                    "A heatmap needs a matrix with rows of the same length".to_string(),
+                ));
+            }
+            let range = axis_range(&rows.concat()).ok_or_else(no_values)?;
+            Ok(Figure::Heatmap(rows, range))
+        }
+        ("histogram" | "heatmap", Some(_)) => Err(error(format!(
+            "A {kind} is drawn from one array, so use plot_to_file(path, data, options)"
+        ))),
+        _ => Err(error(format!(
+            "Unknown kind of chart '{kind}', expected line, scatter, histogram, or heatmap"
+        ))),
+    }
+}
+
+/// Draws a figure on a plotters backend.
+#[cfg(feature = "plotters")]
+fn draw_figure<DB: plotterslib::prelude::DrawingBackend>(
+    backend: DB,
+    figure: &Figure,
+    options: &FigureOptions,
+) -> Result<(), String> {
+    use plotterslib::prelude::*;
+
+    let root = backend.into_drawing_area();
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let (x_range, y_range) = figure.axes();
+    let mut builder = ChartBuilder::on(&root);
+    builder
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60);
+    if !options.title.is_empty() {
+        builder.caption(&options.title, ("sans-serif", 24));
+    }
+    let mut chart = builder
+        .build_cartesian_2d(x_range.0..x_range.1, y_range.0..y_range.1)
+        .map_err(|e| e.to_string())?;
+    {
+        let mut mesh = chart.configure_mesh();
+        mesh.x_desc(options.x_label.as_str())
+            .y_desc(options.y_label.as_str());
+        if matches!(figure, Figure::Heatmap(..)) {
+            mesh.disable_mesh();
+        }
+        mesh.draw().map_err(|e| e.to_string())?;
+    }
+
+    match figure {
+        Figure::Line(points) => {
+            for segment in points.split(|(x, y)| !x.is_finite() || !y.is_finite()) {
+                chart
+                    .draw_series(LineSeries::new(segment.iter().copied(), &BLUE))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Figure::Scatter(points) => {
+            chart
+                .draw_series(points.iter().map(|&p| Circle::new(p, 3, BLUE.filled())))
+                .map_err(|e| e.to_string())?;
+        }
+        Figure::Histogram(bins) => {
+            chart
+                .draw_series(bins.iter().map(|&(lo, hi, count)| {
+                    Rectangle::new(
+                        [(lo, 0.0), (hi, count as rhai::FLOAT)],
+                        BLUE.mix(0.6).filled(),
+                    )
+                }))
+                .map_err(|e| e.to_string())?;
+        }
+        Figure::Heatmap(rows, (lo, hi)) => {
+            let nrows = rows.len();
+            // Cells run from blue for the smallest values to red for the largest, with the first
+            // row at the top.
+            let cells = rows.iter().enumerate().flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, v)| v.is_finite())
+                    .map(move |(j, &v)| {
+                        let t = ((v - lo) / (hi - lo)) as f64;
+                        let top = (nrows - i) as rhai::FLOAT;
+                        Rectangle::new(
+                            [(j as rhai::FLOAT, top - 1.0), (j as rhai::FLOAT + 1.0, top)],
+                            HSLColor(0.66 * (1.0 - t), 0.9, 0.5).filled(),
+                        )
+                    })
+            });
+            chart.draw_series(cells).map_err(|e| e.to_string())?;
+        }
+    }
+    root.present().map_err(|e| e.to_string())
+}
+
+/// Saves a figure to an SVG or bitmap image, choosing the format from the file extension.
+#[cfg(feature = "plotters")]
+fn save_figure(
+    path: &str,
+    figure: &Figure,
+    options: &FigureOptions,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let size = (options.width, options.height);
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let result = match extension.as_str() {
+        "svg" => draw_figure(
+            plotterslib::prelude::SVGBackend::new(path, size),
+            figure,
+            options,
+        ),
+        "png" | "jpg" | "jpeg" | "bmp" => draw_figure(
+            plotterslib::prelude::BitMapBackend::new(path, size),
+            figure,
+            options,
+        ),
+        _ => {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Figures can be saved as .svg, .png, .jpg, or .bmp files, not {path}"),
+            )
+            .into())
+        }
+    };
+    result.map_err(|msg| {
+        rhai::EvalAltResult::ErrorSystem(format!("Cannot save the figure to {path}"), msg.into())
+            .into()
+    })
+}
+
+#[export_module]
+pub mod plotting_functions {
+    use rhai::{Array, EvalAltResult, Map, INT};
+
+    /// Draws a line chart of `y` against `x` as a string of text, which can be printed to take a
+    /// quick look at data from a script or the REPL. Points are marked with `*` and joined by
+    /// dots. The chart is 60 columns by 15 rows, with the axes labelled by the ranges of the
+    /// data. Points that are not finite are skipped and break the line.
+    /// ```typescript
+    /// let chart = plot([0, 1, 2], [0, 2, 4], #{width: 5, height: 3});
+    /// assert_eq(chart, "4 ┤   ·*\n  │ ·*\n0 ┤*\n  └─────\n   0   2");
+    /// ```
+    /// ```typescript
+    /// let x = linspace(0, 6.28, 50);
+    /// print(plot(x, x.map(|v| v.sin())));
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "plot", return_raw)]
+    pub fn plot(x: Array, y: Array) -> Result<String, Box<EvalAltResult>> {
+        super::xy_chart(&x, &y, &super::chart_options(&Map::new())?, '*', true)
+    }
+
+    /// Draws a line chart like `plot(x, y)` with options given as a map: `width` and `height`
+    /// set the size of the plotting area in characters.
+    /// ```typescript
+    /// let chart = plot([1, 2, 3], [3, 1, 2], #{width: 20, height: 5});
+    /// assert_eq(chart.split("\n").len, 7);
+    /// ```
+    #[rhai_fn(name = "plot", return_raw)]
+    pub fn plot_with_options(
+        x: Array,
+        y: Array,
+        options: Map,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::xy_chart(&x, &y, &super::chart_options(&options)?, '*', true)
+    }
+
+    /// Draws a scatter chart of `y` against `x` as a string of text, marking each point with `o`.
+    /// The chart is 60 columns by 15 rows, with the axes labelled by the ranges of the data.
+    /// Points that are not finite are skipped.
+    /// ```typescript
+    /// let chart = scatter([0, 2, 1], [1, 3, 2], #{width: 3, height: 3});
+    /// assert_eq(chart, "3 ┤  o\n  │ o\n1 ┤o\n  └───\n   0 2");
+    /// ```
+    #[rhai_fn(name = "scatter", return_raw)]
+    pub fn scatter(x: Array, y: Array) -> Result<String, Box<EvalAltResult>> {
+        super::xy_chart(&x, &y, &super::chart_options(&Map::new())?, 'o', false)
+    }
+
+    /// Draws a scatter chart like `scatter(x, y)` with options given as a map: `width` and
+    /// `height` set the size of the plotting area in characters.
+    /// ```typescript
+    /// let chart = scatter(rand(1, 100)[0], rand(1, 100)[0], #{width: 30, height: 10});
+    /// assert_eq(chart.split("\n").len, 12);
+    /// ```
+    #[rhai_fn(name = "scatter", return_raw)]
+    pub fn scatter_with_options(
+        x: Array,
+        y: Array,
+        options: Map,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::xy_chart(&x, &y, &super::chart_options(&options)?, 'o', false)
+    }
+
+    /// Draws a histogram of an array as a string of text, with 10 equally wide bins spanning the
+    /// range of the data drawn as vertical bars. The y axis is labelled with the largest count
+    /// and the x axis with the range of the data. Values that are not finite are skipped.
+    /// ```typescript
+    /// let chart = hist([1, 2, 2, 3, 3, 3], #{bins: 3, width: 6, height: 3});
+    /// assert_eq(chart, "3 ┤    █\n  │  █ █\n0 ┤█ █ █\n  └──────\n   1    3");
+    /// ```
+    /// ```typescript
+    /// print(hist(rand(1, 1000)[0]));
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "hist", return_raw)]
+    pub fn hist(arr: Array) -> Result<String, Box<EvalAltResult>> {
+        super::hist_chart(&arr, &super::chart_options(&Map::new())?)
+    }
+
+    /// Draws a histogram like `hist(arr)` with options given as a map: `bins` sets the number of
+    /// bins, and `width` and `height` the size of the plotting area in characters.
+    /// ```typescript
+    /// let chart = hist(rand(1, 500)[0], #{bins: 20, width: 40, height: 8});
+    /// assert_eq(chart.split("\n").len, 10);
+    /// ```
+    #[rhai_fn(name = "hist", return_raw)]
+    pub fn hist_with_options(arr: Array, options: Map) -> Result<String, Box<EvalAltResult>> {
+        super::hist_chart(&arr, &super::chart_options(&options)?)
+    }
+
+    /// Draws an array as a one-line sparkline of block characters from `▁` for the smallest
+    /// value to `█` for the largest, which is handy for logging the shape of a signal. Values
+    /// that are not finite are shown as spaces, and an array of equal values as a flat line in
+    /// the middle.
+    /// ```typescript
+    /// assert_eq(sparkline([1, 2, 3, 4, 5, 6, 7, 8]), "▁▂▃▄▅▆▇█");
+    /// ```
+    /// ```typescript
+    /// let nan = 0.0 / 0.0;
+    /// assert_eq(sparkline([0.0, 1.0, nan, 1.0, 0.0]), "▁█ █▁");
+    /// ```
+    #[rhai_fn(name = "sparkline", return_raw)]
+    pub fn sparkline(arr: Array) -> Result<String, Box<EvalAltResult>> {
+        super::sparkline_string(&arr, None)
+    }
+
+    /// Draws an array as a sparkline like `sparkline(arr)`, but at most `width` characters long.
+    /// Longer arrays are split into `width` equal runs, each shown by its average.
+    /// ```typescript
+    /// let signal = linspace(0, 1, 1000);
+    /// assert_eq(sparkline(signal, 8), "▁▂▃▄▅▆▇█");
+    /// ```
+    #[rhai_fn(name = "sparkline", return_raw)]
+    pub fn sparkline_with_width(arr: Array, width: INT) -> Result<String, Box<EvalAltResult>> {
+        if width < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The width of a sparkline must be positive, not {width}"),
+            )
+            .in_function("sparkline")
+            .with_argument("width")
+            .into());
+        }
+        super::sparkline_string(&arr, Some(width as usize))
+    }
+
+    /// Saves a line chart of `y` against `x` to an image file, so that scripts can produce
+    /// figures to share. The format follows the file extension: `.svg` for a vector image, or
+    /// `.png`, `.jpg`, or `.bmp` for a bitmap. Requires the `plotters` feature.
+    /// ```typescript
+    /// let x = linspace(0, 6.28, 100);
+    /// plot_to_file(temp_path("sine.svg"), x, x.map(|v| v.sin()));
+    /// assert(true);
+    /// ```
+    #[cfg(feature = "plotters")]
+    #[rhai_fn(name = "plot_to_file", return_raw)]
+    pub fn plot_to_file(
+        path: rhai::ImmutableString,
+        x: Array,
+        y: Array,
+    ) -> Result<(), Box<EvalAltResult>> {
+        plot_to_file_with_options(path, x, y, Map::new())
+    }
+
+    /// Saves a chart of `y` against `x` to an image file like `plot_to_file(path, x, y)`, with
+    /// options given as a map:
+    /// - `kind`: "line" (the default) or "scatter"
+    /// - `title`: a caption above the chart
+    /// - `x_label` and `y_label`: descriptions of the axes
+    /// - `width` and `height`: the size of the image in pixels, 800 by 600 by default
+    ///
+    /// Requires the `plotters` feature.
+    /// ```typescript
+    /// let x = rand(1, 200)[0];
+    /// let y = x.map(|v| v * v + 0.1 * rand());
+    /// plot_to_file(temp_path("fit.png"), x, y, #{kind: "scatter", title: "Samples", x_label: "x"});
+    /// assert(true);
+    /// ```
+    #[cfg(feature = "plotters")]
+    #[rhai_fn(name = "plot_to_file", return_raw)]
+    pub fn plot_to_file_with_options(
+        path: rhai::ImmutableString,
+        x: Array,
+        y: Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = super::figure_options(&options)?;
+        let kind = options.kind.clone().unwrap_or_else(|| "line".to_string());
+        let figure = super::prepare_figure(&kind, &x, Some(&y), options.bins)?;
+        super::save_figure(&path, &figure, &options)
+    }
+
+    /// Saves a chart of one array to an image file, with options as for
+    /// `plot_to_file(path, x, y, options)`. The `kind` can be:
+    /// - "histogram" (the default for a vector): the values binned into `bins` equally wide bins,
+    ///   10 by default
+    /// - "heatmap" (the default for a matrix): each element as a cell colored from blue for the
+    ///   smallest values to red for the largest
+    /// - "line" or "scatter": the values against their positions, starting from 1
+    ///
+    /// Requires the `plotters` feature.
+    /// ```typescript
+    /// plot_to_file(temp_path("spread.svg"), rand(1, 1000)[0], #{bins: 20, title: "Spread"});
+    /// plot_to_file(temp_path("field.png"), rand(20, 30), #{});
+    /// assert(true);
+    /// ```
+    #[cfg(feature = "plotters")]
+    #[rhai_fn(name = "plot_to_file", return_raw)]
+    pub fn plot_data_to_file(
+        path: rhai::ImmutableString,
+        data: Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = super::figure_options(&options)?;
+        let kind = options.kind.clone().unwrap_or_else(|| {
+            if data.first().is_some_and(|row| row.is_array()) {
+                "heatmap".to_string()
+            } else {
+                "histogram".to_string()
+            }
+        });
+        let figure = super::prepare_figure(&kind, &data, None, options.bins)?;
+        super::save_figure(&path, &figure, &options)
+    }
+}
@@ -0,0 +1,922 @@
+use rhai::plugin::*;
+
+/// The options accepted by `to_pretty_string` and `pprint`.
+const PRETTY_OPTIONS: [&str; 5] = [
+    "precision",
+    "max_rows",
+    "max_cols",
+    "sci_above",
+    "sci_below",
+];
+
+/// How to lay out a vector or matrix as text.
+struct PrettyOptions {
+    /// Most digits after the decimal point
+    precision: usize,
+    /// Most rows to show before eliding the middle ones
+    max_rows: usize,
+    /// Most columns to show before eliding the middle ones
+    max_cols: usize,
+    /// Magnitude at or above which FLOATs switch to scientific notation
+    sci_above: rhai::FLOAT,
+    /// Magnitude below which nonzero FLOATs switch to scientific notation
+    sci_below: rhai::FLOAT,
+}
+
+/// Reads the options described for `to_pretty_string`.
+fn pretty_options(options: &rhai::Map) -> Result<PrettyOptions, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    if let Some(key) = options
+        .keys()
+        .find(|k| !PRETTY_OPTIONS.contains(&k.as_str()))
+    {
+        return Err(error(format!(
+            "Unknown option '{key}', expected one of {PRETTY_OPTIONS:?}"
+        )));
+    }
+    let count = |key: &str, default: usize, least: rhai::INT| match options.get(key) {
+        None => Ok(default),
+        Some(value) => match value.as_int() {
+            Ok(n) if n >= least => Ok(n as usize),
+            _ => Err(error(format!(
+                "The {key} option must be an INT of at least {least}"
+            ))),
+        },
+    };
+    let precision = count("precision", 4, 0)?;
+    if precision > MAX_FORMAT_COUNT {
+        return Err(error(format!(
+            "The precision option must be at most {MAX_FORMAT_COUNT}"
+        )));
+    }
+    let threshold = |key: &str, default: rhai::FLOAT| match options.get(key) {
+        None => Ok(default),
+        Some(value) => match (value.as_float(), value.as_int()) {
+            (Ok(f), _) if f > 0.0 => Ok(f),
+            (_, Ok(i)) if i > 0 => Ok(i as rhai::FLOAT),
+            _ => Err(error(format!("The {key} option must be a positive number"))),
+        },
+    };
+    Ok(PrettyOptions {
+        precision,
+        max_rows: count("max_rows", 10, 1)?,
+        max_cols: count("max_cols", 10, 1)?,
+        sci_above: threshold("sci_above", 1e5)?,
+        sci_below: threshold("sci_below", 1e-4)?,
+    })
+}
+
+/// The indices to show out of `n` when at most `max` fit, with `None` marking where the middle
+/// ones are elided.
+fn shown_indices(n: usize, max: usize) -> Vec<Option<usize>> {
+    if n <= max {
+        (0..n).map(Some).collect()
+    } else {
+        let head = max.div_ceil(2);
+        let tail = max / 2;
+        (0..head)
+            .map(Some)
+            .chain(std::iter::once(None))
+            .chain((n - tail..n).map(Some))
+            .collect()
+    }
+}
+
+/// Lays out a vector or matrix as text with right-aligned columns, following `to_pretty_string`.
+fn pretty_string(
+    arr: &rhai::Array,
+    options: &PrettyOptions,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let is_matrix = arr.first().is_some_and(|row| row.is_array());
+    let rows = if is_matrix {
+        arr.iter()
+            .map(|row| row.clone().into_array())
+            .collect::<Result<Vec<rhai::Array>, _>>()
+            .map_err(|_| {
+                rhai::EvalAltResult::from(crate::LabError::new(
+                    crate::LabErrorKind::TypeMismatch,
+                    "Every row of a matrix must be an array",
+                ))
+            })?
+    } else {
+        vec![arr.clone()]
+    };
+    let ncols = rows.first().map_or(0, |row| row.len());
+    if rows.iter().any(|row| row.len() != ncols) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            "Every row of a matrix must have the same length",
+        )
+        .into());
+    }
+    if ncols == 0 {
+        return Ok(if is_matrix { "[[]]" } else { "[]" }.to_string());
+    }
+
+    let row_indices = shown_indices(rows.len(), options.max_rows);
+    let col_indices = shown_indices(ncols, options.max_cols);
+    let shown = row_indices
+        .iter()
+        .flatten()
+        .flat_map(|&i| {
+            let row = &rows[i];
+            col_indices.iter().flatten().map(move |&j| &row[j])
+        })
+        .collect::<Vec<_>>();
+
+    // FLOATs share one notation and number of decimals, and INTs are shown as FLOATs when mixed
+    // with them, so that the decimal points line up.
+    let floats = shown
+        .iter()
+        .filter_map(|x| x.as_float().ok())
+        .collect::<Vec<_>>();
+    let any_float = !floats.is_empty();
+    let scientific = floats.iter().any(|f| {
+        f.is_finite() && *f != 0.0 && (f.abs() >= options.sci_above || f.abs() < options.sci_below)
+    });
+    let precision = options.precision;
+    let decimals = shown
+        .iter()
+        .filter_map(|x| {
+            x.as_float()
+                .or_else(|_| x.as_int().map(|i| i as rhai::FLOAT))
+                .ok()
+        })
+        .filter(|f| f.is_finite())
+        .map(|f| {
+            let text = if scientific {
+                format!("{f:.precision$e}")
+            } else {
+                format!("{f:.precision$}")
+            };
+            let mantissa = text.split('e').next().unwrap_or_default();
+            mantissa
+                .split_once('.')
+                .map_or(0, |(_, d)| d.trim_end_matches('0').len())
+        })
+        .max()
+        .unwrap_or(0)
+        .max(precision.min(1));
+    let format = |x: &rhai::Dynamic| -> String {
+        let value = match (x.as_float(), x.as_int()) {
+            (Ok(f), _) => f,
+            (_, Ok(i)) if any_float => i as rhai::FLOAT,
+            (_, Ok(i)) => return i.to_string(),
+            _ => return x.to_string(),
+        };
+        if !value.is_finite() {
+            value.to_string()
+        } else if scientific {
+            format!("{value:.decimals$e}")
+        } else {
+            format!("{value:.decimals$}")
+        }
+    };
+
+    let cells = row_indices
+        .iter()
+        .map(|i| {
+            i.map(|i| {
+                col_indices
+                    .iter()
+                    .map(|j| j.map_or("...".to_string(), |j| format(&rows[i][j])))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    let widths = (0..col_indices.len())
+        .map(|j| {
+            cells
+                .iter()
+                .flatten()
+                .map(|row| row[j].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let lines = cells
+        .iter()
+        .map(|row| match row {
+            None => "...".to_string(),
+            Some(row) => {
+                let padded = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:>width$}"))
+                    .collect::<Vec<_>>();
+                format!("[{}]", padded.join(", "))
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(if is_matrix {
+        format!("[{}]", lines.join(",\n "))
+    } else {
+        lines.join("")
+    })
+}
+
+/// One conversion in a `sprintf` format, such as `%-8.3f`.
+#[derive(Clone, Copy, Debug, Default)]
+struct FormatSpec {
+    /// Pad on the right instead of the left (`-`)
+    left: bool,
+    /// Show a `+` before positive numbers (`+`)
+    plus: bool,
+    /// Show a space before positive numbers (` `)
+    space: bool,
+    /// Pad numbers with zeros instead of spaces (`0`)
+    zero: bool,
+    /// Keep trailing zeros with `%g` and prefix `0x` or `0` with `%x` and `%o` (`#`)
+    alternate: bool,
+    /// The least number of characters
+    width: usize,
+    /// Digits after the decimal point, significant digits for `%g`, least digits for integers,
+    /// or most characters for `%s`
+    precision: Option<usize>,
+    /// The conversion character, such as `f`
+    conversion: char,
+}
+
+/// A piece of a `sprintf` format.
+enum FormatPiece {
+    /// Text that is copied as is
+    Text(String),
+    /// A conversion that formats the next value
+    Spec(FormatSpec),
+}
+
+/// The conversion characters `sprintf` accepts.
+const FORMAT_CONVERSIONS: &str = "diufFeEgGnNsxXoc";
+
+/// The largest width or precision a format or the pretty printer accepts, well beyond any useful
+/// value, so that a script can't ask for text too large to allocate.
+const MAX_FORMAT_COUNT: usize = 1000;
+
+/// Reads the digits at the start of `chars` as a count, if there are any.
+fn format_count(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+) -> Result<Option<usize>, Box<rhai::EvalAltResult>> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    match digits.parse::<usize>() {
+        Ok(count) if count <= MAX_FORMAT_COUNT => Ok(Some(count)),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The width or precision {digits} is too large, the most is {MAX_FORMAT_COUNT}"),
+        )
+        .in_function("sprintf")
+        .with_argument("format")
+        .into()),
+    }
+}
+
+/// Splits a `sprintf` format into text and conversions.
+fn parse_format(format: &str) -> Result<Vec<FormatPiece>, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+            .in_function("sprintf")
+            .with_argument("format")
+            .into()
+    };
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' || chars.next_if_eq(&'%').is_some() {
+            text.push(c);
+            continue;
+        }
+        let mut spec = FormatSpec::default();
+        while let Some(flag) = chars.next_if(|c| "-+ 0#".contains(*c)) {
+            match flag {
+                '-' => spec.left = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '0' => spec.zero = true,
+                _ => spec.alternate = true,
+            }
+        }
+        spec.width = format_count(&mut chars)?.unwrap_or(0);
+        if chars.next_if_eq(&'.').is_some() {
+            spec.precision = Some(format_count(&mut chars)?.unwrap_or(0));
+        }
+        spec.conversion = match chars.next() {
+            Some(c) if FORMAT_CONVERSIONS.contains(c) => c,
+            Some(c) => {
+                return Err(error(format!(
+                "Unknown conversion '%{c}', expected one of the characters '{FORMAT_CONVERSIONS}'"
+            )))
+            }
+            None => {
+                return Err(error(
+                    "The format ends in the middle of a conversion".into(),
+                ))
+            }
+        };
+        if !text.is_empty() {
+            pieces.push(FormatPiece::Text(std::mem::take(&mut text)));
+        }
+        pieces.push(FormatPiece::Spec(spec));
+    }
+    if !text.is_empty() {
+        pieces.push(FormatPiece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Formats a non-negative number in scientific notation as C does, with a sign and at least two
+/// digits in the exponent, as in `1.500000e+03`.
+fn c_exponential(x: rhai::FLOAT, precision: usize, upper: bool) -> String {
+    let text = format!("{x:.precision$e}");
+    let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+    let exponent = exponent.parse::<i32>().unwrap_or(0);
+    let e = if upper { 'E' } else { 'e' };
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa}{e}{sign}{:02}", exponent.abs())
+}
+
+/// Formats a non-negative number with `significant` digits in whichever of fixed or scientific
+/// notation C's `%g` would choose, dropping trailing zeros unless `keep_zeros`.
+fn c_general(x: rhai::FLOAT, significant: usize, keep_zeros: bool, upper: bool) -> String {
+    let p = significant.max(1);
+    let exponent = if x == 0.0 {
+        0
+    } else {
+        let text = format!("{x:.prec$e}", prec = p - 1);
+        text.split_once('e')
+            .and_then(|(_, e)| e.parse::<i64>().ok())
+            .unwrap_or(0)
+    };
+    let text = if exponent < -4 || exponent >= p as i64 {
+        c_exponential(x, p - 1, upper)
+    } else {
+        format!("{x:.prec$}", prec = (p as i64 - 1 - exponent) as usize)
+    };
+    if keep_zeros {
+        return text;
+    }
+    let split = text.find(['e', 'E']).unwrap_or(text.len());
+    let (mantissa, exponent) = text.split_at(split);
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{mantissa}{exponent}")
+}
+
+/// Formats a non-negative number in engineering notation, whose exponent is a multiple of three,
+/// with `precision` digits after the decimal point, as in `12.500e+03`.
+fn engineering(x: rhai::FLOAT, precision: usize, upper: bool) -> String {
+    let ten: rhai::FLOAT = 10.0;
+    let mut exponent = if x == 0.0 {
+        0
+    } else {
+        x.log10().floor() as i32
+    }
+    .div_euclid(3)
+        * 3;
+    let mut mantissa = format!("{:.precision$}", x / ten.powi(exponent));
+    if mantissa.parse::<rhai::FLOAT>().is_ok_and(|m| m >= 1000.0) {
+        exponent += 3;
+        mantissa = format!("{:.precision$}", x / ten.powi(exponent));
+    }
+    let e = if upper { 'E' } else { 'e' };
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa}{e}{sign}{:02}", exponent.abs())
+}
+
+/// Pads formatted text to the width of `spec`. Zeros go between the sign and the digits of
+/// finite numbers when the `0` flag is given.
+fn pad_formatted(spec: &FormatSpec, sign: &str, body: &str, zeros_allowed: bool) -> String {
+    let len = sign.chars().count() + body.chars().count();
+    let fill = spec.width.saturating_sub(len);
+    if spec.left {
+        format!("{sign}{body}{}", " ".repeat(fill))
+    } else if spec.zero && zeros_allowed {
+        format!("{sign}{}{body}", "0".repeat(fill))
+    } else {
+        format!("{}{sign}{body}", " ".repeat(fill))
+    }
+}
+
+/// Formats one value with one conversion of a `sprintf` format.
+fn format_value(
+    context: &NativeCallContext,
+    spec: &FormatSpec,
+    value: &rhai::Dynamic,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let error = |kind: crate::LabErrorKind, msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(kind, msg)
+            .in_function("sprintf")
+            .into()
+    };
+    match spec.conversion {
+        's' => {
+            let text = match value.read_lock::<rhai::ImmutableString>() {
+                Some(s) => s.to_string(),
+                None => context
+                    .call_native_fn::<rhai::ImmutableString>("to_string", (value.clone(),))?
+                    .to_string(),
+            };
+            let text = match spec.precision {
+                Some(p) => text.chars().take(p).collect(),
+                None => text,
+            };
+            return Ok(pad_formatted(spec, "", &text, false));
+        }
+        'c' => {
+            let text = match value.read_lock::<rhai::ImmutableString>() {
+                Some(s) => s.to_string(),
+                None => match (value.as_char(), value.as_int()) {
+                    (Ok(c), _) => Some(c),
+                    (_, Ok(i)) => u32::try_from(i).ok().and_then(char::from_u32),
+                    _ => None,
+                }
+                .map(String::from)
+                .ok_or_else(|| {
+                    error(
+                        crate::LabErrorKind::TypeMismatch,
+                        format!("Cannot format {} as a character", value.type_name()),
+                    )
+                })?,
+            };
+            return Ok(pad_formatted(spec, "", &text, false));
+        }
+        _ => {}
+    }
+
+    let (x, int) = match (value.as_float(), value.as_int()) {
+        (Ok(f), _) => (f, None),
+        (_, Ok(i)) => (i as rhai::FLOAT, Some(i)),
+        _ => {
+            return Err(error(
+                crate::LabErrorKind::TypeMismatch,
+                format!(
+                    "The '%{}' conversion needs a number, not {}",
+                    spec.conversion,
+                    value.type_name()
+                ),
+            ))
+        }
+    };
+    if !x.is_finite() {
+        let body = if x.is_nan() { "NaN" } else { "Inf" };
+        let sign = if x < 0.0 { "-" } else { "" };
+        return Ok(pad_formatted(spec, sign, body, false));
+    }
+    let sign = if x.is_sign_negative() {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    };
+    let a = x.abs();
+    let body = match spec.conversion {
+        'd' | 'i' | 'u' | 'x' | 'X' | 'o' if int.is_none() && a.fract() != 0.0 => {
+            // Like MATLAB, a number that is not a whole number is shown in scientific notation
+            c_exponential(a, spec.precision.unwrap_or(6), false)
+        }
+        'd' | 'i' | 'u' | 'x' | 'X' | 'o' => {
+            let digits = match (spec.conversion, int) {
+                ('x', Some(i)) => format!("{:x}", i.unsigned_abs()),
+                ('X', Some(i)) => format!("{:X}", i.unsigned_abs()),
+                ('o', Some(i)) => format!("{:o}", i.unsigned_abs()),
+                ('x' | 'X' | 'o', None) if a >= u64::MAX as rhai::FLOAT => {
+                    return Err(error(
+                        crate::LabErrorKind::InvalidArgument,
+                        format!("{x} is too large for the '%{}' conversion", spec.conversion),
+                    ))
+                }
+                ('x', None) => format!("{:x}", a as u64),
+                ('X', None) => format!("{:X}", a as u64),
+                ('o', None) => format!("{:o}", a as u64),
+                (_, Some(i)) => i.unsigned_abs().to_string(),
+                (_, None) => format!("{a:.0}"),
+            };
+            let digits = format!("{digits:0>width$}", width = spec.precision.unwrap_or(0));
+            match spec.conversion {
+                'x' if spec.alternate => format!("0x{digits}"),
+                'X' if spec.alternate => format!("0X{digits}"),
+                'o' if spec.alternate => format!("0{digits}"),
+                _ => digits,
+            }
+        }
+        'f' | 'F' => format!("{a:.prec$}", prec = spec.precision.unwrap_or(6)),
+        'e' | 'E' => c_exponential(a, spec.precision.unwrap_or(6), spec.conversion == 'E'),
+        'g' | 'G' => c_general(
+            a,
+            spec.precision.unwrap_or(6),
+            spec.alternate,
+            spec.conversion == 'G',
+        ),
+        _ => engineering(a, spec.precision.unwrap_or(6), spec.conversion == 'N'),
+    };
+    Ok(pad_formatted(spec, sign, &body, true))
+}
+
+/// Formats `values` with a `sprintf` format, flattening arrays into their elements and reusing
+/// the format until every value is used. Output stops at the first conversion that has no value
+/// left.
+fn sprintf_values(
+    context: &NativeCallContext,
+    format: &str,
+    values: rhai::Array,
+) -> Result<String, Box<rhai::EvalAltResult>> {
+    let pieces = parse_format(format)?;
+    let mut flat = vec![];
+    let mut stack = values;
+    stack.reverse();
+    while let Some(value) = stack.pop() {
+        if value.is_array() {
+            stack.extend(value.into_array().unwrap_or_default().into_iter().rev());
+        } else {
+            flat.push(value);
+        }
+    }
+
+    let mut text = String::new();
+    let mut next = 0;
+    loop {
+        let start = next;
+        for piece in &pieces {
+            match piece {
+                FormatPiece::Text(t) => text.push_str(t),
+                FormatPiece::Spec(spec) => {
+                    let Some(value) = flat.get(next) else {
+                        return Ok(text);
+                    };
+                    text.push_str(&format_value(context, spec, value)?);
+                    next += 1;
+                }
+            }
+        }
+        if next == start || next >= flat.len() {
+            return Ok(text);
+        }
+    }
+}
+
+#[export_module]
+pub mod printing_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, ImmutableString, Map, NativeCallContext, FLOAT, INT,
+    };
+
+    /// Formats a vector or matrix as text that is easy to read: the columns are right-aligned,
+    /// FLOATs are rounded to at most 4 decimals (trailing zeros are dropped, but every FLOAT gets
+    /// the same number of decimals), and very large or very small FLOATs switch the whole matrix
+    /// to scientific notation. Matrices with more than 10 rows or columns only show the rows and
+    /// columns at their corners, with `...` in place of the rest.
+    /// ```typescript
+    /// let text = to_pretty_string([[1, 2.5], [-10, 0.125]]);
+    /// assert_eq(text, "[[  1.000, 2.500],\n [-10.000, 0.125]]");
+    /// ```
+    /// ```typescript
+    /// let text = to_pretty_string([[1, 20], [300, 4]]);
+    /// assert_eq(text, "[[  1, 20],\n [300,  4]]");
+    /// ```
+    /// ```typescript
+    /// let text = to_pretty_string([[1.0, 250000.0]]);
+    /// assert_eq(text, "[[1.0e0, 2.5e5]]");
+    /// ```
+    /// ```typescript
+    /// let text = to_pretty_string(ones(100, 100));
+    /// let lines = text.split("\n");
+    /// assert_eq(lines.len, 11);
+    /// assert_eq(lines[5], " ...,");
+    /// assert_eq(lines[0], "[[1.0, 1.0, 1.0, 1.0, 1.0, ..., 1.0, 1.0, 1.0, 1.0, 1.0],");
+    /// ```
+    #[rhai_fn(name = "to_pretty_string", return_raw, pure)]
+    pub fn to_pretty_string(arr: &mut Array) -> Result<String, Box<EvalAltResult>> {
+        super::pretty_string(arr, &super::pretty_options(&Map::new())?)
+    }
+
+    /// Formats a vector or matrix as text like `to_pretty_string(arr)`, with options given as a
+    /// map:
+    /// - `precision`: the most digits after the decimal point, 4 by default and at most 1000
+    /// - `max_rows`: the most rows to show, 10 by default
+    /// - `max_cols`: the most columns to show, 10 by default
+    /// - `sci_above`: the magnitude at or above which FLOATs use scientific notation, `1e5` by
+    ///   default
+    /// - `sci_below`: the magnitude below which nonzero FLOATs use scientific notation, `1e-4` by
+    ///   default
+    /// ```typescript
+    /// let text = to_pretty_string([[3.14159, 2.71828]], #{precision: 2});
+    /// assert_eq(text, "[[3.14, 2.72]]");
+    /// ```
+    /// ```typescript
+    /// let text = to_pretty_string([1, 2, 3, 4, 5, 6], #{max_cols: 4});
+    /// assert_eq(text, "[1, 2, ..., 5, 6]");
+    /// ```
+    /// ```typescript
+    /// let text = to_pretty_string([0.5, 250000.0], #{sci_above: 1e6});
+    /// assert_eq(text, "[0.5, 250000.0]");
+    /// ```
+    /// ```typescript
+    /// let kind = "";
+    /// try { to_pretty_string([1.5], #{precision: 1000000000}); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[rhai_fn(name = "to_pretty_string", return_raw, pure)]
+    pub fn to_pretty_string_with_options(
+        arr: &mut Array,
+        options: Map,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::pretty_string(arr, &super::pretty_options(&options)?)
+    }
+
+    /// Formats a `Matrix` as text like `to_pretty_string(arr)`.
+    /// ```typescript
+    /// let text = to_pretty_string(matrix([[1, 2], [3, 4]]));
+    /// assert_eq(text, "[[1.0, 2.0],\n [3.0, 4.0]]");
+    /// ```
+    #[rhai_fn(name = "to_pretty_string", return_raw, pure)]
+    pub fn matrix_to_pretty_string(m: &mut crate::Matrix) -> Result<String, Box<EvalAltResult>> {
+        super::pretty_string(&m.to_array(), &super::pretty_options(&Map::new())?)
+    }
+
+    /// Formats a `Matrix` as text like `to_pretty_string(arr, options)`.
+    /// ```typescript
+    /// let text = to_pretty_string(matrix([[1, 2, 3]]), #{max_cols: 2});
+    /// assert_eq(text, "[[1.0, ..., 3.0]]");
+    /// ```
+    #[rhai_fn(name = "to_pretty_string", return_raw, pure)]
+    pub fn matrix_to_pretty_string_with_options(
+        m: &mut crate::Matrix,
+        options: Map,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::pretty_string(&m.to_array(), &super::pretty_options(&options)?)
+    }
+
+    /// Prints a vector or matrix as formatted by `to_pretty_string(arr)`. The text goes wherever
+    /// `print` sends it.
+    /// ```typescript
+    /// pprint([[1, 2], [3, 4]]);
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "pprint", return_raw, pure)]
+    pub fn pprint(ctx: NativeCallContext, arr: &mut Array) -> Result<(), Box<EvalAltResult>> {
+        let text = to_pretty_string(arr)?;
+        ctx.call_native_fn("print", (ImmutableString::from(text),))
+    }
+
+    /// Prints a vector or matrix as formatted by `to_pretty_string(arr, options)`.
+    /// ```typescript
+    /// pprint(rand(50, 50), #{precision: 2, max_rows: 6, max_cols: 6});
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "pprint", return_raw, pure)]
+    pub fn pprint_with_options(
+        ctx: NativeCallContext,
+        arr: &mut Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let text = to_pretty_string_with_options(arr, options)?;
+        ctx.call_native_fn("print", (ImmutableString::from(text),))
+    }
+
+    /// Prints a `Matrix` as formatted by `to_pretty_string(m)`.
+    /// ```typescript
+    /// pprint(matrix([[1, 2], [3, 4]]));
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "pprint", return_raw, pure)]
+    pub fn matrix_pprint(
+        ctx: NativeCallContext,
+        m: &mut crate::Matrix,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let text = matrix_to_pretty_string(m)?;
+        ctx.call_native_fn("print", (ImmutableString::from(text),))
+    }
+
+    /// Prints a `Matrix` as formatted by `to_pretty_string(m, options)`.
+    /// ```typescript
+    /// pprint(matrix([[1, 2], [3, 4]]), #{precision: 1});
+    /// assert(true);
+    /// ```
+    #[rhai_fn(name = "pprint", return_raw, pure)]
+    pub fn matrix_pprint_with_options(
+        ctx: NativeCallContext,
+        m: &mut crate::Matrix,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let text = matrix_to_pretty_string_with_options(m, options)?;
+        ctx.call_native_fn("print", (ImmutableString::from(text),))
+    }
+
+    /// Formats values into text with a C-style format, as MATLAB's `sprintf` does. Each `%`
+    /// conversion in `format` formats the next value, with optional flags (`-` to left-align,
+    /// `+` or a space to show a sign on positive numbers, `0` to pad with zeros, `#` to keep
+    /// trailing zeros with `%g`), a width, and a precision after a `.`. The conversions are:
+    /// - `%d`, `%i`, `%u`: a whole number (other numbers are shown as with `%e`)
+    /// - `%f`: fixed-point, with 6 decimals unless a precision is given
+    /// - `%e`, `%E`: scientific notation, as in `1.500000e+03`
+    /// - `%g`, `%G`: whichever of `%f` and `%e` is shorter, with the precision as the number of
+    ///   significant digits and trailing zeros dropped
+    /// - `%n`, `%N`: engineering notation, whose exponent is a multiple of three, as in
+    ///   `12.500e+03`
+    /// - `%x`, `%X`, `%o`: a whole number in hexadecimal or octal
+    /// - `%s`: a string, or any other value as `to_string` formats it
+    /// - `%c`: a character
+    /// - `%%`: a literal `%`
+    ///
+    /// Arrays are flattened into their elements, and the format is reused until every value has
+    /// been formatted. `NaN` and infinities are shown as `NaN`, `Inf`, and `-Inf`.
+    /// ```typescript
+    /// assert_eq(sprintf("%.3f", 3.14159), "3.142");
+    /// assert_eq(sprintf("[%6.2f]", 3.14159), "[  3.14]");
+    /// assert_eq(sprintf("[%-6d]", 42), "[42    ]");
+    /// assert_eq(sprintf("%05d", -42), "-0042");
+    /// ```
+    /// ```typescript
+    /// assert_eq(sprintf("%.3e", 12345.678), "1.235e+04");
+    /// assert_eq(sprintf("%g", 0.0001), "0.0001");
+    /// assert_eq(sprintf("%g", 1e-5), "1e-05");
+    /// assert_eq(sprintf("%.2n", 12345.678), "12.35e+03");
+    /// assert_eq(sprintf("%#x", 255), "0xff");
+    /// ```
+    /// Widths and precisions are limited to 1000.
+    /// ```typescript
+    /// let kinds = [];
+    /// for format in ["%.999999999999f", "%.1001g", "%999999999999d"] {
+    ///     try { sprintf(format, 1.0); } catch (err) { kinds.push(err.kind); }
+    /// }
+    /// assert_eq(kinds, ["invalid_argument", "invalid_argument", "invalid_argument"]);
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+        a: Dynamic,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![a])
+    }
+
+    /// Formats text with a format that has no conversions, other than `%%` for a literal `%`.
+    /// ```typescript
+    /// assert_eq(sprintf("100%%"), "100%");
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf_no_values(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![])
+    }
+
+    /// Formats two values as described for `sprintf(format, a)`.
+    /// ```typescript
+    /// assert_eq(sprintf("%s = %.2f", "x", 0.5), "x = 0.50");
+    /// ```
+    /// ```typescript
+    /// let t = sprintf("%d: %s\n", [1, "one", 2, "two"]);
+    /// assert_eq(t, "1: one\n2: two\n");
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf2(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![a, b])
+    }
+
+    /// Formats three values as described for `sprintf(format, a)`.
+    /// ```typescript
+    /// assert_eq(sprintf("%d/%02d/%02d", 2024, 1, 2), "2024/01/02");
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf3(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![a, b, c])
+    }
+
+    /// Formats four values as described for `sprintf(format, a)`.
+    /// ```typescript
+    /// let line = sprintf("%-5s|%5.1f|%5.1f|%5.1f", "min", 0.25, 1, 2.75);
+    /// assert_eq(line, "min  |  0.2|  1.0|  2.8");
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf4(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: Dynamic,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![a, b, c, d])
+    }
+
+    /// Formats five values as described for `sprintf(format, a)`. Pass an array to format more.
+    /// ```typescript
+    /// let t = sprintf("%d %d %d %d %d", 1, 2, 3, 4, 5);
+    /// assert_eq(t, "1 2 3 4 5");
+    /// ```
+    #[rhai_fn(name = "sprintf", return_raw)]
+    pub fn sprintf5(
+        ctx: NativeCallContext,
+        format: ImmutableString,
+        a: Dynamic,
+        b: Dynamic,
+        c: Dynamic,
+        d: Dynamic,
+        e: Dynamic,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![a, b, c, d, e])
+    }
+
+    /// Converts a number to text as MATLAB's `num2str` does: integers in full, and FLOATs with
+    /// four digits after the decimal point, or more for large numbers so that none of the
+    /// digits before the decimal point are lost, without trailing zeros.
+    /// ```typescript,only_f64
+    /// assert_eq(num2str(pi), "3.1416");
+    /// assert_eq(num2str(42), "42");
+    /// assert_eq(num2str(123456.789), "123456.789");
+    /// assert_eq(num2str(0.5), "0.5");
+    /// ```
+    #[rhai_fn(name = "num2str", return_raw)]
+    pub fn num2str(ctx: NativeCallContext, x: Dynamic) -> Result<String, Box<EvalAltResult>> {
+        if x.is_int() {
+            return super::sprintf_values(&ctx, "%d", vec![x]);
+        }
+        let f = x.as_float().map_err(|_| {
+            EvalAltResult::from(
+                crate::LabError::new(
+                    crate::LabErrorKind::TypeMismatch,
+                    format!("Expected a number, not {}", x.type_name()),
+                )
+                .in_function("num2str")
+                .with_argument("x"),
+            )
+        })?;
+        let magnitude = if f.is_finite() && f != 0.0 {
+            f.abs().log10().floor().max(0.0) as usize
+        } else {
+            0
+        };
+        super::sprintf_values(&ctx, &format!("%.{}g", magnitude + 5), vec![x])
+    }
+
+    /// Converts a number to text with `precision` significant digits.
+    /// ```typescript
+    /// assert_eq(num2str(pi, 8), "3.1415927");
+    /// assert_eq(num2str(123456, 2), "1.2e+05");
+    /// ```
+    /// ```typescript
+    /// let kind = "";
+    /// try { num2str(pi, 1000000000); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[rhai_fn(name = "num2str", return_raw)]
+    pub fn num2str_with_precision(
+        ctx: NativeCallContext,
+        x: Dynamic,
+        precision: INT,
+    ) -> Result<String, Box<EvalAltResult>> {
+        if !(1..=super::MAX_FORMAT_COUNT as INT).contains(&precision) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "The precision must be from 1 to {}, not {precision}",
+                    super::MAX_FORMAT_COUNT
+                ),
+            )
+            .in_function("num2str")
+            .with_argument("precision")
+            .into());
+        }
+        super::sprintf_values(&ctx, &format!("%.{precision}g"), vec![x])
+    }
+
+    /// Converts a number to text with a format as described for `sprintf`.
+    /// ```typescript
+    /// assert_eq(num2str(1234.5, "%.3e"), "1.234e+03");
+    /// assert_eq(num2str(0.000047, "%.1n"), "47.0e-06");
+    /// ```
+    #[rhai_fn(name = "num2str", return_raw)]
+    pub fn num2str_with_format(
+        ctx: NativeCallContext,
+        x: FLOAT,
+        format: ImmutableString,
+    ) -> Result<String, Box<EvalAltResult>> {
+        super::sprintf_values(&ctx, &format, vec![Dynamic::from_float(x)])
+    }
+}
@@ -0,0 +1,267 @@
+use rhai::plugin::*;
+
+/// The call counts and timings collected by one active call to `profile`.
+pub struct ProfileState {
+    /// Number of times each function was called
+    pub calls: std::collections::BTreeMap<rhai::ImmutableString, rhai::INT>,
+    /// Cumulative time (in seconds) attributed to each function
+    pub time: std::collections::BTreeMap<rhai::ImmutableString, rhai::FLOAT>,
+    /// The function that the time since the last sample is attributed to
+    current: Option<rhai::ImmutableString>,
+    /// The instant of the last sample
+    last_sample: std::time::Instant,
+}
+
+thread_local! {
+    /// Stack of active profiles, so that nested calls to `profile` each get their own report.
+    static PROFILES: std::cell::RefCell<Vec<ProfileState>> = const { std::cell::RefCell::new(vec![]) };
+    /// The instant of the latest call to `tic`.
+    static TIC: std::cell::Cell<Option<std::time::Instant>> = const { std::cell::Cell::new(None) };
+}
+
+/// Begins collecting a new profile on the current thread.
+pub fn start_profile() {
+    PROFILES.with(|profiles| {
+        profiles.borrow_mut().push(ProfileState {
+            calls: std::collections::BTreeMap::new(),
+            time: std::collections::BTreeMap::new(),
+            current: None,
+            last_sample: std::time::Instant::now(),
+        })
+    });
+}
+
+/// Stops the innermost profile on the current thread and returns what it collected.
+pub fn stop_profile() -> Option<ProfileState> {
+    PROFILES.with(|profiles| {
+        let mut state = profiles.borrow_mut().pop()?;
+        sample(&mut state, None, false);
+        Some(state)
+    })
+}
+
+/// Attributes the time since the previous sample to the current function, then optionally
+/// records a call to a new function.
+fn sample(state: &mut ProfileState, called: Option<&rhai::ImmutableString>, end_of_call: bool) {
+    let now = std::time::Instant::now();
+    if let Some(name) = &state.current {
+        *state.time.entry(name.clone()).or_insert(0.0) +=
+            (now - state.last_sample).as_secs_f64() as rhai::FLOAT;
+    }
+    state.last_sample = now;
+
+    if let Some(name) = called {
+        *state.calls.entry(name.clone()).or_insert(0) += 1;
+        state.current = Some(name.clone());
+    } else if end_of_call {
+        state.current = None;
+    }
+}
+
+/// Registers the profiling hook with an engine so that `profile` can report per-function call
+/// counts and timings. Without this hook, `profile` still runs the closure and reports the total
+/// elapsed time, but the per-function maps are empty.
+///
+/// Only calls to the functions of this package are counted, not calls to Rhai's built-in functions
+/// or to functions defined in the script. Time is attributed to the most recently called function
+/// of this package until the next statement or call to another function begins, so the time spent
+/// in nested calls is reported against the innermost one.
+/// ```
+/// use rhai::{packages::Package, Engine, Map};
+/// use rhai_sci::{register_profiler, SciPackage};
+///
+/// let mut engine = Engine::new();
+/// engine.register_global_module(SciPackage::new().as_shared_module());
+/// register_profiler(&mut engine);
+///
+/// let report = engine.eval::<Map>("profile(|| { let x = rand(50); sum(flatten(x)) })").unwrap();
+/// let calls = report["calls"].clone_cast::<Map>();
+/// assert_eq!(calls["sum"].as_int().unwrap(), 1);
+///
+/// let report = engine.eval::<Map>("fn double(x) { x * 2 } profile(|| double(len([1, 2])))").unwrap();
+/// let calls = report["calls"].clone_cast::<Map>();
+/// assert!(calls.is_empty());
+/// ```
+#[cfg(feature = "profiling")]
+#[allow(deprecated)]
+pub fn register_profiler(engine: &mut rhai::Engine) {
+    use rhai::debugger::{DebuggerCommand, DebuggerEvent};
+    use rhai::{ASTNode, Expr, Stmt};
+
+    let names = crate::FUNCTION_DOCS
+        .iter()
+        .map(|(name, ..)| *name)
+        .collect::<std::collections::HashSet<_>>();
+    engine.register_debugger(
+        |_, debugger| debugger,
+        move |_, event, node, _, _| {
+            if let DebuggerEvent::Step = event {
+                let call = match node {
+                    ASTNode::Expr(Expr::FnCall(x, ..))
+                    | ASTNode::Expr(Expr::MethodCall(x, ..))
+                    | ASTNode::Stmt(Stmt::FnCall(x, ..))
+                        if x.op_token.is_none() =>
+                    {
+                        Some(&x.name)
+                    }
+                    _ => None,
+                };
+                let called = call.filter(|name| names.contains(name.as_str()));
+                PROFILES.with(|profiles| {
+                    for state in profiles.borrow_mut().iter_mut() {
+                        sample(state, called, node.is_stmt() || call.is_some());
+                    }
+                });
+            }
+            Ok(DebuggerCommand::StepInto)
+        },
+    );
+}
+
+#[export_module]
+pub mod profiling_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext, FLOAT, INT};
+
+    /// Calls a closure and returns an object map describing where the time went. The map contains
+    /// the closure's `result`, the total `elapsed` time in seconds, and maps of per-function `calls`
+    /// and cumulative `time` (in seconds). The per-function maps are only populated if the host
+    /// application has registered the profiling hook with its engine.
+    /// ```typescript
+    /// let report = profile(|| sum([1, 2, 3]));
+    /// assert_eq(report.result, 6);
+    /// assert(report.elapsed >= 0.0);
+    /// ```
+    #[rhai_fn(name = "profile", return_raw)]
+    pub fn profile(context: NativeCallContext, f: FnPtr) -> Result<Map, Box<EvalAltResult>> {
+        crate::start_profile();
+        let start = std::time::Instant::now();
+        let result = f.call_within_context::<Dynamic>(&context, ());
+        let elapsed = start.elapsed().as_secs_f64() as FLOAT;
+        let state = crate::stop_profile().unwrap();
+
+        let mut report = Map::new();
+        report.insert("result".into(), result?);
+        report.insert("elapsed".into(), Dynamic::from_float(elapsed));
+        report.insert(
+            "calls".into(),
+            Dynamic::from_map(
+                state
+                    .calls
+                    .into_iter()
+                    .map(|(k, v)| (k.as_str().into(), Dynamic::from_int(v)))
+                    .collect::<Map>(),
+            ),
+        );
+        report.insert(
+            "time".into(),
+            Dynamic::from_map(
+                state
+                    .time
+                    .into_iter()
+                    .map(|(k, v)| (k.as_str().into(), Dynamic::from_float(v)))
+                    .collect::<Map>(),
+            ),
+        );
+        Ok(report)
+    }
+
+    /// Starts a stopwatch, which `toc` reads. Calling `tic` again restarts it.
+    /// ```typescript
+    /// tic();
+    /// let total = sum(rand(1, 1000)[0]);
+    /// assert(toc() >= 0.0);
+    /// ```
+    #[rhai_fn(name = "tic")]
+    pub fn tic() {
+        super::TIC.with(|tic| tic.set(Some(std::time::Instant::now())));
+    }
+
+    /// Returns the time in seconds since the latest call to `tic`. The stopwatch keeps running,
+    /// so `toc` can be called several times to time successive steps.
+    /// ```typescript
+    /// tic();
+    /// let first = toc();
+    /// let second = toc();
+    /// assert(second >= first);
+    /// ```
+    #[rhai_fn(name = "toc", return_raw)]
+    pub fn toc() -> Result<FLOAT, Box<EvalAltResult>> {
+        match super::TIC.with(|tic| tic.get()) {
+            Some(start) => Ok(start.elapsed().as_secs_f64() as FLOAT),
+            None => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "Call tic before toc to start the stopwatch",
+            )
+            .in_function("toc")
+            .into()),
+        }
+    }
+
+    /// Calls a closure `n_iters` times and returns an object map of statistics about how long each
+    /// call took, in seconds: the `mean`, `median`, `min`, `max`, and (sample) `std` of the
+    /// times, the `total` time, and the number of `iterations`. The raw `times` are included too.
+    /// ```typescript
+    /// let stats = benchmark(|| sum(rand(1, 100)[0]), 20);
+    /// assert_eq(stats.iterations, 20);
+    /// assert_eq(stats.times.len, 20);
+    /// assert(stats.min <= stats.median && stats.median <= stats.max);
+    /// ```
+    #[rhai_fn(name = "benchmark", return_raw)]
+    pub fn benchmark(
+        context: NativeCallContext,
+        f: FnPtr,
+        n_iters: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        if n_iters < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The number of iterations must be positive, not {n_iters}"),
+            )
+            .in_function("benchmark")
+            .with_argument("n_iters")
+            .into());
+        }
+        let times = (0..n_iters)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                let _ = f.call_within_context::<Dynamic>(&context, ())?;
+                Ok(start.elapsed().as_secs_f64() as FLOAT)
+            })
+            .collect::<Result<Vec<FLOAT>, Box<EvalAltResult>>>()?;
+
+        let n = times.len();
+        let total = times.iter().sum::<FLOAT>();
+        let mean = total / n as FLOAT;
+        let std = if n > 1 {
+            (times.iter().map(|t| (t - mean).powi(2)).sum::<FLOAT>() / (n - 1) as FLOAT).sqrt()
+        } else {
+            0.0
+        };
+        let mut sorted = times.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+
+        let mut stats = Map::new();
+        stats.insert("mean".into(), Dynamic::from_float(mean));
+        stats.insert("median".into(), Dynamic::from_float(median));
+        stats.insert("min".into(), Dynamic::from_float(sorted[0]));
+        stats.insert("max".into(), Dynamic::from_float(sorted[n - 1]));
+        stats.insert("std".into(), Dynamic::from_float(std));
+        stats.insert("total".into(), Dynamic::from_float(total));
+        stats.insert("iterations".into(), Dynamic::from_int(n_iters));
+        stats.insert(
+            "times".into(),
+            Dynamic::from_array(
+                times
+                    .into_iter()
+                    .map(Dynamic::from_float)
+                    .collect::<Array>(),
+            ),
+        );
+        Ok(stats)
+    }
+}
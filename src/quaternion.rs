@@ -0,0 +1,504 @@
+use rhai::plugin::*;
+
+/// A quaternion `w + xi + yj + zk`. Unit quaternions represent rotations in three dimensions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    /// Scalar part
+    pub w: rhai::FLOAT,
+    /// Coefficient of `i`
+    pub x: rhai::FLOAT,
+    /// Coefficient of `j`
+    pub y: rhai::FLOAT,
+    /// Coefficient of `k`
+    pub z: rhai::FLOAT,
+}
+
+impl Quaternion {
+    /// Creates a quaternion from its four components.
+    pub fn new(w: rhai::FLOAT, x: rhai::FLOAT, y: rhai::FLOAT, z: rhai::FLOAT) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    /// Creates the quaternion for a rotation by `angle` (in radians) about `axis`.
+    pub fn from_axis_angle(axis: [rhai::FLOAT; 3], angle: rhai::FLOAT) -> Option<Self> {
+        let n = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+        if n == 0.0 {
+            return None;
+        }
+        let s = (angle / 2.0).sin() / n;
+        Some(Quaternion::new(
+            (angle / 2.0).cos(),
+            axis[0] * s,
+            axis[1] * s,
+            axis[2] * s,
+        ))
+    }
+
+    /// Returns the Hamilton product `self * other`.
+    pub fn mul(&self, other: &Self) -> Self {
+        let (a, b) = (self, other);
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+
+    /// Returns the conjugate `w - xi - yj - zk`.
+    pub fn conj(&self) -> Self {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Returns the 4-D dot product of two quaternions.
+    pub fn dot(&self, other: &Self) -> rhai::FLOAT {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Returns the length of the quaternion.
+    pub fn norm(&self) -> rhai::FLOAT {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns the quaternion scaled by `s`.
+    pub fn scale(&self, s: rhai::FLOAT) -> Self {
+        Quaternion::new(self.w * s, self.x * s, self.y * s, self.z * s)
+    }
+
+    /// Returns the quaternion scaled to unit length, or `None` if it is zero.
+    pub fn normalize(&self) -> Option<Self> {
+        let n = self.norm();
+        (n > 0.0).then(|| self.scale(1.0 / n))
+    }
+
+    /// Rotates a 3-D vector by the rotation that this (unit) quaternion represents.
+    pub fn rotate(&self, v: [rhai::FLOAT; 3]) -> [rhai::FLOAT; 3] {
+        let p = Quaternion::new(0.0, v[0], v[1], v[2]);
+        let r = self.mul(&p).mul(&self.conj());
+        [r.x, r.y, r.z]
+    }
+
+    /// Returns the 3 × 3 rotation matrix for this (unit) quaternion, in row-major order.
+    pub fn to_rotation_matrix(&self) -> [[rhai::FLOAT; 3]; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Creates the unit quaternion for a 3 × 3 rotation matrix, using Shepperd's method to stay
+    /// accurate for every rotation angle.
+    pub fn from_rotation_matrix(m: [[rhai::FLOAT; 3]; 3]) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+            )
+        };
+        // Keep the scalar part non-negative so each rotation has one representation
+        if q.w < 0.0 {
+            q.scale(-1.0)
+        } else {
+            q
+        }
+    }
+
+    /// Creates the unit quaternion for intrinsic Z-Y-X Euler angles (yaw, then pitch, then roll),
+    /// in radians.
+    pub fn from_euler(yaw: rhai::FLOAT, pitch: rhai::FLOAT, roll: rhai::FLOAT) -> Self {
+        let (sy, cy) = (yaw / 2.0).sin_cos();
+        let (sp, cp) = (pitch / 2.0).sin_cos();
+        let (sr, cr) = (roll / 2.0).sin_cos();
+        Quaternion::new(
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        )
+    }
+
+    /// Returns the intrinsic Z-Y-X Euler angles `[yaw, pitch, roll]` of this (unit) quaternion,
+    /// in radians.
+    pub fn to_euler(&self) -> [rhai::FLOAT; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+        let pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+        let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+        [yaw, pitch, roll]
+    }
+
+    /// Spherically interpolates between two unit quaternions along the shorter arc, where `t`
+    /// runs from 0 (at `self`) to 1 (at `other`).
+    pub fn slerp(&self, other: &Self, t: rhai::FLOAT) -> Self {
+        let mut dot = self.dot(other);
+        let mut other = *other;
+        if dot < 0.0 {
+            other = other.scale(-1.0);
+            dot = -dot;
+        }
+        // Nearly parallel quaternions are interpolated linearly to avoid dividing by ~0
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = dot.acos();
+            let s = theta.sin();
+            (((1.0 - t) * theta).sin() / s, (t * theta).sin() / s)
+        };
+        let q = Quaternion::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        );
+        q.normalize().unwrap_or(q)
+    }
+}
+
+impl std::fmt::Display for Quaternion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Quaternion({:?}, {:?}, {:?}, {:?})",
+            self.w, self.x, self.y, self.z
+        )
+    }
+}
+
+/// Reads a 3-D vector of numbers.
+fn vector_3d(v: &rhai::Array) -> Result<[rhai::FLOAT; 3], Box<rhai::EvalAltResult>> {
+    let m = crate::Matrix::from_array(v)?;
+    if m.data().len() != 3 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Expected a vector of 3 numbers, not {}", m.data().len()),
+        )
+        .into());
+    }
+    Ok([m.data()[0], m.data()[1], m.data()[2]])
+}
+
+#[export_module]
+pub mod quaternion_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, FLOAT};
+
+    /// A quaternion `w + xi + yj + zk`, created with `quaternion`.
+    pub type Quaternion = crate::Quaternion;
+
+    /// Creates a `Quaternion` from its four components, scalar part first.
+    /// ```typescript
+    /// let q = quaternion(1, 2, 3, 4);
+    /// assert_eq([q.w, q.x, q.y, q.z], [1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    #[rhai_fn(name = "quaternion", return_raw)]
+    pub fn quaternion(
+        w: Dynamic,
+        x: Dynamic,
+        y: Dynamic,
+        z: Dynamic,
+    ) -> Result<Quaternion, Box<EvalAltResult>> {
+        let mut parts = [0.0; 4];
+        for (part, value) in parts.iter_mut().zip([w, x, y, z]) {
+            *part = crate::if_int_convert_to_float_and_do(value, Ok)?;
+        }
+        Ok(Quaternion::new(parts[0], parts[1], parts[2], parts[3]))
+    }
+
+    /// Creates the unit `Quaternion` for a rotation by `angle` (in radians) about `axis`, which
+    /// does not need to be normalized.
+    /// ```typescript,only_f64
+    /// let q = quaternion([0, 0, 2], pi / 2);
+    /// let v = rotate([1, 0, 0], q);
+    /// norm(v - [0, 1, 0]) < 1e-12
+    /// ```
+    #[rhai_fn(name = "quaternion", return_raw)]
+    pub fn quaternion_from_axis_angle(
+        axis: Array,
+        angle: Dynamic,
+    ) -> Result<Quaternion, Box<EvalAltResult>> {
+        let axis = super::vector_3d(&axis)?;
+        let angle = crate::if_int_convert_to_float_and_do(angle, Ok)?;
+        Quaternion::from_axis_angle(axis, angle).ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The axis of rotation cannot be zero",
+            )
+            .in_function("quaternion")
+            .with_argument("axis")
+            .into()
+        })
+    }
+
+    /// Returns the components of a `Quaternion` as `[w, x, y, z]`.
+    /// ```typescript
+    /// assert_eq(to_array(quaternion(1, 0, 0, 0)), [1.0, 0.0, 0.0, 0.0]);
+    /// ```
+    #[rhai_fn(name = "to_array", pure)]
+    pub fn to_array(q: &mut Quaternion) -> Array {
+        [q.w, q.x, q.y, q.z]
+            .into_iter()
+            .map(Dynamic::from_float)
+            .collect()
+    }
+
+    /// Returns the scalar part of a `Quaternion`.
+    #[rhai_fn(get = "w", pure)]
+    pub fn get_w(q: &mut Quaternion) -> FLOAT {
+        q.w
+    }
+
+    /// Returns the coefficient of `i` in a `Quaternion`.
+    #[rhai_fn(get = "x", pure)]
+    pub fn get_x(q: &mut Quaternion) -> FLOAT {
+        q.x
+    }
+
+    /// Returns the coefficient of `j` in a `Quaternion`.
+    #[rhai_fn(get = "y", pure)]
+    pub fn get_y(q: &mut Quaternion) -> FLOAT {
+        q.y
+    }
+
+    /// Returns the coefficient of `k` in a `Quaternion`.
+    #[rhai_fn(get = "z", pure)]
+    pub fn get_z(q: &mut Quaternion) -> FLOAT {
+        q.z
+    }
+
+    /// Multiplies two quaternions. For rotations, `a * b` applies `b` first and then `a`.
+    /// ```typescript
+    /// let i = quaternion(0, 1, 0, 0);
+    /// let j = quaternion(0, 0, 1, 0);
+    /// assert_eq(to_array(i * j), [0.0, 0.0, 0.0, 1.0]);
+    /// assert_eq(to_array(j * i), [0.0, 0.0, 0.0, -1.0]);
+    /// ```
+    #[rhai_fn(name = "*")]
+    pub fn multiply(a: Quaternion, b: Quaternion) -> Quaternion {
+        a.mul(&b)
+    }
+
+    /// Returns the conjugate of a `Quaternion`, which is the inverse rotation for a unit
+    /// quaternion.
+    /// ```typescript
+    /// assert_eq(to_array(conj(quaternion(1, 2, 3, 4))), [1.0, -2.0, -3.0, -4.0]);
+    /// ```
+    #[rhai_fn(name = "conj")]
+    pub fn conj(q: Quaternion) -> Quaternion {
+        q.conj()
+    }
+
+    /// Returns the inverse of a `Quaternion`, so that `q * inv(q)` is the identity.
+    /// ```typescript
+    /// let q = quaternion(0, 0, 2, 0);
+    /// assert(q * inv(q) == quaternion(1, 0, 0, 0));
+    /// ```
+    #[rhai_fn(name = "inv", return_raw)]
+    pub fn inv(q: Quaternion) -> Result<Quaternion, Box<EvalAltResult>> {
+        let n2 = q.dot(&q);
+        if n2 == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "A zero quaternion has no inverse",
+            )
+            .in_function("inv")
+            .into());
+        }
+        Ok(q.conj().scale(1.0 / n2))
+    }
+
+    /// Returns the length of a `Quaternion`.
+    /// ```typescript
+    /// assert_eq(norm(quaternion(1, 1, 1, 1)), 2.0);
+    /// ```
+    #[rhai_fn(name = "norm")]
+    pub fn norm(q: Quaternion) -> FLOAT {
+        q.norm()
+    }
+
+    /// Scales a `Quaternion` to unit length.
+    /// ```typescript
+    /// let q = normalize(quaternion(1, 1, -1, 1));
+    /// assert_eq(to_array(q), [0.5, 0.5, -0.5, 0.5]);
+    /// ```
+    #[rhai_fn(name = "normalize", return_raw)]
+    pub fn normalize(q: Quaternion) -> Result<Quaternion, Box<EvalAltResult>> {
+        q.normalize().ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "A zero quaternion cannot be normalized",
+            )
+            .in_function("normalize")
+            .into()
+        })
+    }
+
+    /// Spherically interpolates between two rotations, where `t` runs from 0 (at `a`) to 1 (at
+    /// `b`). The quaternions are normalized first, and the shorter arc between them is taken.
+    /// ```typescript,only_f64
+    /// let a = quaternion(1, 0, 0, 0);
+    /// let b = quaternion([0, 0, 1], pi / 2);
+    /// let half = slerp(a, b, 0.5);
+    /// let expected = quaternion([0, 0, 1], pi / 4);
+    /// norm(to_array(half) - to_array(expected)) < 1e-12
+    /// ```
+    #[rhai_fn(name = "slerp", return_raw)]
+    pub fn slerp(a: Quaternion, b: Quaternion, t: FLOAT) -> Result<Quaternion, Box<EvalAltResult>> {
+        Ok(normalize(a)?.slerp(&normalize(b)?, t))
+    }
+
+    /// Rotates a 3-D vector by the rotation that a `Quaternion` represents. The quaternion is
+    /// normalized first.
+    /// ```typescript,only_f64
+    /// let q = quaternion([1, 0, 0], pi);
+    /// let v = rotate([0, 1, 0], q);
+    /// norm(v - [0, -1, 0]) < 1e-12
+    /// ```
+    #[rhai_fn(name = "rotate", return_raw)]
+    pub fn rotate(v: Array, q: Quaternion) -> Result<Array, Box<EvalAltResult>> {
+        let v = super::vector_3d(&v)?;
+        Ok(normalize(q)?
+            .rotate(v)
+            .into_iter()
+            .map(Dynamic::from_float)
+            .collect())
+    }
+
+    /// Converts a `Quaternion` into a 3 × 3 rotation matrix. The quaternion is normalized first.
+    /// ```typescript,only_f64
+    /// let m = quat2rotm(quaternion([0, 0, 1], pi / 2));
+    /// norm(m - [[0, -1, 0], [1, 0, 0], [0, 0, 1]], "fro") < 1e-12
+    /// ```
+    #[rhai_fn(name = "quat2rotm", return_raw)]
+    pub fn quat2rotm(q: Quaternion) -> Result<Array, Box<EvalAltResult>> {
+        Ok(normalize(q)?
+            .to_rotation_matrix()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(Dynamic::from_float)
+                    .collect::<Array>()
+                    .into()
+            })
+            .collect())
+    }
+
+    /// Converts a 3 × 3 rotation matrix into a unit `Quaternion` with a non-negative scalar part.
+    /// ```typescript,only_f64
+    /// let q = rotm2quat([[1, 0, 0], [0, 0, -1], [0, 1, 0]]);
+    /// let expected = quaternion([1, 0, 0], pi / 2);
+    /// norm(to_array(q) - to_array(expected)) < 1e-12
+    /// ```
+    #[rhai_fn(name = "rotm2quat", return_raw)]
+    pub fn rotm2quat(m: Array) -> Result<Quaternion, Box<EvalAltResult>> {
+        let m = crate::Matrix::from_array(&m)?;
+        if m.rows() != 3 || m.cols() != 3 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A rotation matrix must be 3 x 3, not {} x {}",
+                    m.rows(),
+                    m.cols()
+                ),
+            )
+            .in_function("rotm2quat")
+            .into());
+        }
+        let mut rows = [[0.0; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = m.get(i, j);
+            }
+        }
+        Ok(Quaternion::from_rotation_matrix(rows))
+    }
+
+    /// Converts Euler angles `[yaw, pitch, roll]` (in radians, applied as intrinsic rotations
+    /// about Z, then Y, then X) into a unit `Quaternion`.
+    /// ```typescript,only_f64
+    /// let q = eul2quat([pi / 2, 0, 0]);
+    /// let expected = quaternion([0, 0, 1], pi / 2);
+    /// norm(to_array(q) - to_array(expected)) < 1e-12
+    /// ```
+    #[rhai_fn(name = "eul2quat", return_raw)]
+    pub fn eul2quat(angles: Array) -> Result<Quaternion, Box<EvalAltResult>> {
+        let [yaw, pitch, roll] = super::vector_3d(&angles)?;
+        Ok(Quaternion::from_euler(yaw, pitch, roll))
+    }
+
+    /// Converts a `Quaternion` into Euler angles `[yaw, pitch, roll]` (in radians, as intrinsic
+    /// rotations about Z, then Y, then X). The quaternion is normalized first.
+    /// ```typescript,only_f64
+    /// let angles = [0.3, -0.2, 1.1];
+    /// norm(quat2eul(eul2quat(angles)) - angles) < 1e-12
+    /// ```
+    #[rhai_fn(name = "quat2eul", return_raw)]
+    pub fn quat2eul(q: Quaternion) -> Result<Array, Box<EvalAltResult>> {
+        Ok(normalize(q)?
+            .to_euler()
+            .into_iter()
+            .map(Dynamic::from_float)
+            .collect())
+    }
+
+    /// Tests whether two quaternions have the same components.
+    #[rhai_fn(name = "==")]
+    pub fn eq(a: Quaternion, b: Quaternion) -> bool {
+        a == b
+    }
+
+    /// Tests whether two quaternions differ in any component.
+    #[rhai_fn(name = "!=")]
+    pub fn ne(a: Quaternion, b: Quaternion) -> bool {
+        a != b
+    }
+
+    /// Formats a `Quaternion` as its four components, scalar part first.
+    /// ```typescript
+    /// assert_eq(to_string(quaternion(1, 0, 0.5, 0)), "Quaternion(1.0, 0.0, 0.5, 0.0)");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(q: &mut Quaternion) -> ImmutableString {
+        q.to_string().into()
+    }
+}
@@ -0,0 +1,327 @@
+use rhai::plugin::*;
+
+/// A seeded random number generator of one of the algorithms `seed_rng` offers.
+#[cfg(feature = "rand")]
+type SeededRng = Box<dyn randlib::RngCore + Send>;
+
+#[cfg(feature = "rand")]
+thread_local! {
+    /// The seeded random number generator of engines without a [`LabRng`], if one has been
+    /// selected with `seed_rng`.
+    static RNG: std::cell::RefCell<Option<SeededRng>> = std::cell::RefCell::new(None);
+}
+
+/// The random number generator used by `rand`, `seed_rng`, and the other random functions of the
+/// scripts an engine runs. It is kept in the engine's default tag (see [`register_rng`]), so that
+/// each engine has its own: two engines in the same process can be seeded independently, and a
+/// seeded script gives the same results however many other scripts run alongside it. Clones share
+/// the same generator.
+/// ```
+/// use rhai::{packages::Package, Engine, FLOAT};
+/// use rhai_sci::{register_rng, LabPackage, LabRng};
+///
+/// let mut a = Engine::new();
+/// LabPackage::new().register_into_engine(&mut a);
+/// register_rng(&mut a, LabRng::seeded(42, "pcg64").unwrap());
+///
+/// let mut b = Engine::new();
+/// LabPackage::new().register_into_engine(&mut b);
+/// register_rng(&mut b, LabRng::seeded(42, "pcg64").unwrap());
+///
+/// // Drawing from one engine does not move the other along
+/// let first = a.eval::<FLOAT>("rand()").unwrap();
+/// a.eval::<FLOAT>("rand()").unwrap();
+/// assert_eq!(b.eval::<FLOAT>("rand()").unwrap(), first);
+/// ```
+#[cfg(feature = "rand")]
+#[derive(Clone, Default)]
+pub struct LabRng(std::sync::Arc<std::sync::Mutex<Option<SeededRng>>>);
+
+#[cfg(feature = "rand")]
+impl LabRng {
+    /// Creates a generator that draws from the thread-local, entropy-seeded generator until a
+    /// script calls `seed_rng`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a generator of the named algorithm (`"pcg64"`, `"xoshiro256++"`, or `"chacha12"`),
+    /// seeded with `seed`, as if a script had called `seed_rng(seed, algorithm)`.
+    pub fn seeded(seed: u64, algorithm: &str) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let rng = Self::new();
+        rng.seed(seed, algorithm)?;
+        Ok(rng)
+    }
+
+    /// Replaces the generator with one of the named algorithm, seeded with `seed`, for this and
+    /// every clone of it.
+    pub fn seed(&self, seed: u64, algorithm: &str) -> Result<(), Box<rhai::EvalAltResult>> {
+        let rng = seeded_rng(seed, algorithm)?;
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = Some(rng);
+        Ok(())
+    }
+}
+
+/// Gives an engine its own random number generator by making `rng` the engine's default tag,
+/// which replaces any tag it had. [`LabPackage`](crate::LabPackage) does this with a new
+/// [`LabRng`]. Engines without one share a thread-local generator instead.
+#[cfg(feature = "rand")]
+pub fn register_rng(engine: &mut rhai::Engine, rng: LabRng) {
+    engine.set_default_tag(rhai::Dynamic::from(rng));
+}
+
+/// Returns the random number generator of the engine running a script, if it has one.
+#[cfg(feature = "rand")]
+fn engine_rng(context: &rhai::NativeCallContext) -> Option<LabRng> {
+    context.tag()?.read_lock::<LabRng>().map(|rng| rng.clone())
+}
+
+/// Runs a function with the random number generator of the engine running a script. That is the
+/// engine's [`LabRng`] if it has one, and otherwise the generator selected by `seed_rng` on this
+/// thread. Either way, until `seed_rng` is called, it is the thread-local entropy-seeded generator.
+#[cfg(feature = "rand")]
+pub fn with_rng<T, F>(context: &rhai::NativeCallContext, f: F) -> T
+where
+    F: FnOnce(&mut dyn randlib::RngCore) -> T,
+{
+    match engine_rng(context) {
+        Some(LabRng(rng)) => match rng.lock().unwrap_or_else(|err| err.into_inner()).as_mut() {
+            Some(rng) => f(rng.as_mut()),
+            None => f(&mut randlib::thread_rng()),
+        },
+        None => RNG.with(|rng| match rng.borrow_mut().as_mut() {
+            Some(rng) => f(rng.as_mut()),
+            None => f(&mut randlib::thread_rng()),
+        }),
+    }
+}
+
+/// Returns a random float between zero and one from the generator of the engine running a script.
+#[cfg(feature = "rand")]
+pub fn random_float(context: &rhai::NativeCallContext) -> rhai::FLOAT {
+    use randlib::Rng;
+    with_rng(context, |rng| rng.gen())
+}
+
+/// Replaces the random number generator of the engine running a script with one of the named
+/// algorithm, seeded with `seed`.
+#[cfg(feature = "rand")]
+pub fn seed_rng_with_algorithm(
+    context: &rhai::NativeCallContext,
+    seed: u64,
+    algorithm: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    match engine_rng(context) {
+        Some(rng) => rng.seed(seed, algorithm),
+        None => {
+            let rng = seeded_rng(seed, algorithm)?;
+            RNG.with(|current| *current.borrow_mut() = Some(rng));
+            Ok(())
+        }
+    }
+}
+
+/// Creates a random number generator of the named algorithm, seeded with `seed`.
+#[cfg(feature = "rand")]
+fn seeded_rng(seed: u64, algorithm: &str) -> Result<SeededRng, Box<rhai::EvalAltResult>> {
+    use randlib::SeedableRng;
+    Ok(match algorithm {
+        "pcg64" => Box::new(rand_pcg::Pcg64::seed_from_u64(seed)),
+        "xoshiro256++" => Box::new(rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(seed)),
+        "chacha12" => Box::new(rand_chacha::ChaCha12Rng::seed_from_u64(seed)),
+        _ => {
+            return Err(crate::LabError::new(crate::LabErrorKind::InvalidArgument, format!(
+                    "Unknown random number generator '{algorithm}', expected 'pcg64', 'xoshiro256++', or 'chacha12'"
+                ))
+            .into())
+        }
+    })
+}
+
+#[export_module]
+pub mod random_functions {
+    #[cfg(feature = "rand")]
+    use crate::if_list_convert_to_vec_float_and_do;
+    #[cfg(feature = "rand")]
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, NativeCallContext, FLOAT, INT};
+
+    /// Seeds the random number generator used by `rand` and the other random functions, so that
+    /// subsequent results are reproducible. Uses the `"pcg64"` algorithm. Each engine set up with
+    /// `LabPackage` has its own generator, so seeding it does not affect scripts run by others.
+    /// ```typescript
+    /// seed_rng(42);
+    /// let a = rand(3);
+    /// seed_rng(42);
+    /// let b = rand(3);
+    /// assert_eq(a, b);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "seed_rng", return_raw)]
+    pub fn seed_rng(ctx: NativeCallContext, seed: INT) -> Result<(), Box<EvalAltResult>> {
+        crate::seed_rng_with_algorithm(&ctx, seed as u64, "pcg64")
+    }
+
+    /// Seeds the random number generator used by `rand` and the other random functions, selecting
+    /// the algorithm at the same time. The algorithm can be `"pcg64"` (fast, good statistical
+    /// quality), `"xoshiro256++"` (fastest), or `"chacha12"` (cryptographic quality).
+    /// ```typescript
+    /// seed_rng(42, "chacha12");
+    /// let a = rand();
+    /// seed_rng(42, "chacha12");
+    /// let b = rand();
+    /// assert_eq(a, b);
+    /// ```
+    /// ```typescript
+    /// seed_rng(42, "xoshiro256++");
+    /// let a = rand();
+    /// seed_rng(42, "pcg64");
+    /// let b = rand();
+    /// assert_ne(a, b);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "seed_rng", return_raw)]
+    pub fn seed_rng_algorithm(
+        ctx: NativeCallContext,
+        seed: INT,
+        algorithm: ImmutableString,
+    ) -> Result<(), Box<EvalAltResult>> {
+        crate::seed_rng_with_algorithm(&ctx, seed as u64, algorithm.as_str())
+    }
+
+    /// Draws a stratified random sample, returning the sorted indices of the selected elements of
+    /// `labels`. Each distinct label contributes `fraction` of its occurrences (rounded to the
+    /// nearest integer), drawn without replacement. The sample is determined by `seed` and does not
+    /// affect the generator selected with `seed_rng`.
+    /// ```typescript
+    /// let labels = ["a", "a", "b", "b", "b", "b"];
+    /// let idx = stratified_sample(labels, 0.5, 42);
+    /// assert_eq(len(idx), 3);
+    /// assert_eq(idx, stratified_sample(labels, 0.5, 42));
+    /// ```
+    /// ```typescript
+    /// let idx = stratified_sample([1, 1, 2, 2], 1.0, 7);
+    /// assert_eq(idx, [0, 1, 2, 3]);
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "stratified_sample", return_raw)]
+    pub fn stratified_sample(
+        labels: Array,
+        fraction: FLOAT,
+        seed: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        use randlib::SeedableRng;
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The sampling fraction must be between 0 and 1",
+            )
+            .in_function("stratified_sample")
+            .with_argument("fraction")
+            .into());
+        }
+
+        // Group the indices by label
+        let mut strata = std::collections::BTreeMap::<String, Vec<usize>>::new();
+        for (idx, label) in labels.iter().enumerate() {
+            strata.entry(format!("{:?}", label)).or_default().push(idx);
+        }
+
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(seed as u64);
+        let mut selected = vec![];
+        for members in strata.values() {
+            let amount = (fraction * members.len() as FLOAT).round() as usize;
+            for pick in randlib::seq::index::sample(&mut rng, members.len(), amount) {
+                selected.push(members[pick]);
+            }
+        }
+        selected.sort_unstable();
+        Ok(selected
+            .into_iter()
+            .map(|idx| Dynamic::from_int(idx as INT))
+            .collect())
+    }
+
+    /// Draws `k` elements from an array, with replacement, where each element is chosen with
+    /// probability proportional to its weight.
+    /// ```typescript
+    /// let x = weighted_choice(["a", "b", "c"], [0, 1, 0], 4);
+    /// assert_eq(x, ["b", "b", "b", "b"]);
+    /// ```
+    /// ```typescript
+    /// let x = weighted_choice([1, 2, 3], [0.2, 0.3, 0.5], 10);
+    /// assert_eq(len(x), 10);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try { weighted_choice([1, 2], [1, 1], 9223372036854775807); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "weighted_choice", return_raw)]
+    pub fn weighted_choice(
+        ctx: NativeCallContext,
+        arr: Array,
+        mut weights: Array,
+        k: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        use randlib::Rng;
+        if arr.len() != weights.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                "The array and the weights must have the same length",
+            )
+            .in_function("weighted_choice")
+            .with_argument("weights")
+            .into());
+        }
+        if k < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The number of samples must be non-negative",
+            )
+            .in_function("weighted_choice")
+            .with_argument("k")
+            .into());
+        }
+        crate::check_array_shape(&ctx, &[k as usize])?;
+        if_list_convert_to_vec_float_and_do(&mut weights, |w| {
+            if w.iter().any(|x| !(*x >= 0.0 && x.is_finite())) {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    "The weights must be finite and non-negative",
+                )
+                .in_function("weighted_choice")
+                .with_argument("weights")
+                .into());
+            }
+
+            // Build the cumulative distribution and invert it
+            let cdf = w
+                .iter()
+                .scan(0.0, |total, x| {
+                    *total += x;
+                    Some(*total)
+                })
+                .collect::<Vec<FLOAT>>();
+            let total = *cdf.last().unwrap_or(&0.0);
+            if total <= 0.0 {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    "The weights must not all be zero",
+                )
+                .in_function("weighted_choice")
+                .with_argument("weights")
+                .into());
+            }
+            Ok(crate::with_rng(&ctx, |rng| {
+                (0..k)
+                    .map(|_| {
+                        let u = rng.gen::<FLOAT>() * total;
+                        let idx = cdf.partition_point(|c| *c <= u).min(arr.len() - 1);
+                        arr[idx].clone()
+                    })
+                    .collect()
+            }))
+        })
+    }
+}
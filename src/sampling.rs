@@ -0,0 +1,338 @@
+use rhai::plugin::*;
+
+/// Sobol direction number initializers (degree `s`, coefficients `a`, initial `m`) for dimensions
+/// 2 through 10, from Joe and Kuo. The first dimension is the van der Corput sequence in base 2.
+const SOBOL_DIRECTIONS: [(u32, u32, &[u32]); 9] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+];
+
+/// Returns the first `n` prime numbers, used as the bases of the Halton sequence.
+fn first_primes(n: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = vec![];
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Mixes a 64-bit value into a well-distributed hash (the SplitMix64 finalizer).
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Computes the radical inverse of `index` in `base`. If `scramble` holds a seed, each digit is
+/// shifted by an amount that depends on the seed, the digit position and all preceding digits,
+/// which is a nested (Owen-style) scrambling.
+fn radical_inverse(mut index: u64, base: u64, scramble: Option<u64>) -> rhai::FLOAT {
+    let inv_base = 1.0 / base as rhai::FLOAT;
+    let mut factor = inv_base;
+    let mut result = 0.0;
+    let mut prefix: u64 = 0;
+    let mut position: u64 = 0;
+    while factor > rhai::FLOAT::EPSILON && (index > 0 || scramble.is_some()) {
+        let mut digit = index % base;
+        if let Some(seed) = scramble {
+            let shift = mix64(seed ^ mix64(prefix.wrapping_mul(0x9e3779b97f4a7c15) ^ position));
+            digit = (digit + shift % base) % base;
+            prefix = prefix.wrapping_mul(base).wrapping_add(index % base);
+        }
+        result += digit as rhai::FLOAT * factor;
+        factor *= inv_base;
+        index /= base;
+        position += 1;
+    }
+    result
+}
+
+/// The most dimensions the Halton sequence is generated in. Its bases are the first primes, and
+/// the sequence is of little use long before its bases get this large.
+const MAX_HALTON_DIMS: rhai::INT = 1000;
+
+/// Checks that `n` points of the Halton sequence in `dims` dimensions can be generated.
+fn check_halton_size(
+    context: &rhai::NativeCallContext,
+    n: rhai::INT,
+    dims: rhai::INT,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    check_qmc_size(n, dims)?;
+    if dims > MAX_HALTON_DIMS {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The Halton sequence is only available for up to {MAX_HALTON_DIMS} dimensions"),
+        )
+        .into());
+    }
+    crate::check_array_shape(context, &[n as usize, dims as usize])
+}
+
+/// Generates `n` points of the Halton sequence in `dims` dimensions, optionally scrambled with one
+/// seed per dimension.
+fn halton_points(
+    context: &rhai::NativeCallContext,
+    n: rhai::INT,
+    dims: rhai::INT,
+    seeds: Option<Vec<u64>>,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    check_halton_size(context, n, dims)?;
+    let bases = first_primes(dims as usize);
+    Ok((0..n as u64)
+        .map(|i| {
+            rhai::Dynamic::from_array(
+                bases
+                    .iter()
+                    .enumerate()
+                    .map(|(d, base)| {
+                        let seed = seeds.as_ref().map(|s| s[d]);
+                        rhai::Dynamic::from_float(radical_inverse(i, *base, seed))
+                    })
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+/// Generates `n` points of the Sobol sequence in `dims` dimensions.
+fn sobol_points(
+    context: &rhai::NativeCallContext,
+    n: rhai::INT,
+    dims: rhai::INT,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    check_qmc_size(n, dims)?;
+    if dims as usize > SOBOL_DIRECTIONS.len() + 1 {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!(
+                "The Sobol sequence is only available for up to {} dimensions",
+                SOBOL_DIRECTIONS.len() + 1
+            ),
+        )
+        .into());
+    }
+    crate::check_array_shape(context, &[n as usize, dims as usize])?;
+
+    // Direction numbers, scaled to 32 bits
+    let directions = (0..dims as usize)
+        .map(|d| {
+            let mut v = [0u32; 32];
+            if d == 0 {
+                for (j, vj) in v.iter_mut().enumerate() {
+                    *vj = 1 << (31 - j);
+                }
+            } else {
+                let (s, a, m) = SOBOL_DIRECTIONS[d - 1];
+                let s = s as usize;
+                for j in 0..32 {
+                    v[j] = if j < s {
+                        m[j] << (31 - j)
+                    } else {
+                        let mut vj = v[j - s] ^ (v[j - s] >> s);
+                        for l in 1..s {
+                            vj ^= ((a >> (s - 1 - l)) & 1) * v[j - l];
+                        }
+                        vj
+                    };
+                }
+            }
+            v
+        })
+        .collect::<Vec<[u32; 32]>>();
+
+    // Gray code construction, starting at the origin
+    let mut x = vec![0u32; dims as usize];
+    let mut points = vec![];
+    for i in 0..n as u64 {
+        if i > 0 {
+            let c = i.trailing_zeros() as usize;
+            for (xd, v) in x.iter_mut().zip(&directions) {
+                *xd ^= v[c];
+            }
+        }
+        points.push(rhai::Dynamic::from_array(
+            x.iter()
+                .map(|xd| rhai::Dynamic::from_float(*xd as rhai::FLOAT / 4294967296.0))
+                .collect(),
+        ));
+    }
+    Ok(points)
+}
+
+/// Checks that the number of points and dimensions requested are valid.
+fn check_qmc_size(n: rhai::INT, dims: rhai::INT) -> Result<(), Box<rhai::EvalAltResult>> {
+    if n < 0 || dims < 1 {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "The number of points must be non-negative and the number of dimensions positive"
+                .to_string(),
+        )
+        .into())
+    } else if n as u64 > 1 << 32 {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            "At most 2^32 quasi-random points can be generated",
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+#[export_module]
+pub mod sampling_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, FnPtr, ImmutableString, NativeCallContext, FLOAT, INT,
+    };
+
+    /// Generates `n` points of the Halton low-discrepancy sequence in `dims` dimensions, as an
+    /// `n x dims` matrix with values in [0, 1). Dimension `d` uses the `d`th prime as its base.
+    /// ```typescript,only_f64
+    /// let x = halton(4, 2);
+    /// assert_eq(x, [[0.0, 0.0], [0.5, 1.0/3.0], [0.25, 2.0/3.0], [0.75, 1.0/9.0]]);
+    /// ```
+    /// ```typescript
+    /// let kind = "";
+    /// try { halton(5, 2147483647); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "invalid_argument");
+    /// ```
+    #[rhai_fn(name = "halton", return_raw)]
+    pub fn halton(ctx: NativeCallContext, n: INT, dims: INT) -> Result<Array, Box<EvalAltResult>> {
+        super::halton_points(&ctx, n, dims, None)
+    }
+
+    /// Generates `n` points of the Halton sequence in `dims` dimensions, optionally with nested
+    /// (Owen-style) digit scrambling. Scrambling removes the correlations between dimensions that
+    /// the plain sequence shows in higher dimensions, and makes randomized error estimates possible.
+    /// The scrambling is drawn from the random number generator selected with `seed_rng`.
+    /// ```typescript
+    /// seed_rng(1);
+    /// let x = halton(100, 3, true);
+    /// assert_eq(size(x), [100, 3]);
+    /// assert(all(x.map(|row| all(row.map(|v| v >= 0.0 && v < 1.0)))));
+    /// ```
+    /// ```typescript
+    /// assert_eq(halton(3, 2, false), halton(3, 2));
+    /// ```
+    #[cfg(feature = "rand")]
+    #[rhai_fn(name = "halton", return_raw)]
+    pub fn halton_scrambled(
+        ctx: NativeCallContext,
+        n: INT,
+        dims: INT,
+        scramble: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        use randlib::Rng;
+        super::check_halton_size(&ctx, n, dims)?;
+        let seeds = if scramble {
+            Some(crate::with_rng(&ctx, |rng| {
+                (0..dims.max(0)).map(|_| rng.gen()).collect()
+            }))
+        } else {
+            None
+        };
+        super::halton_points(&ctx, n, dims, seeds)
+    }
+
+    /// Generates `n` points of the Sobol low-discrepancy sequence in `dims` dimensions (up to 10),
+    /// as an `n x dims` matrix with values in [0, 1). The balance properties of the sequence are
+    /// best when `n` is a power of two.
+    /// ```typescript
+    /// let x = sobol(4, 2);
+    /// assert_eq(x, [[0.0, 0.0], [0.5, 0.5], [0.75, 0.25], [0.25, 0.75]]);
+    /// ```
+    #[rhai_fn(name = "sobol", return_raw)]
+    pub fn sobol(ctx: NativeCallContext, n: INT, dims: INT) -> Result<Array, Box<EvalAltResult>> {
+        super::sobol_points(&ctx, n, dims)
+    }
+
+    /// Estimates the integral of a function over a box using quasi-Monte Carlo integration. The
+    /// function takes a point (an array) and returns a number, `bounds` is an array of `[lower,
+    /// upper]` pairs (one per dimension), `n` is the number of points, and `method` is either
+    /// `"sobol"` or `"halton"`.
+    /// ```typescript
+    /// let f = |x| x[0] * x[1];
+    /// let result = qmc_integrate(f, [[0, 1], [0, 2]], 1024, "sobol");
+    /// assert(abs(result - 1.0) < 0.01);
+    /// ```
+    /// ```typescript
+    /// let result = qmc_integrate(|x| x[0] * x[0], [[0, 3]], 1000, "halton");
+    /// assert(abs(result - 9.0) < 0.05);
+    /// ```
+    #[rhai_fn(name = "qmc_integrate", return_raw)]
+    pub fn qmc_integrate(
+        context: NativeCallContext,
+        f: FnPtr,
+        bounds: Array,
+        n: INT,
+        method: ImmutableString,
+    ) -> Result<FLOAT, Box<EvalAltResult>> {
+        let mut limits = vec![];
+        for bound in bounds {
+            let pair = bound.into_array().unwrap_or_default();
+            if pair.len() != 2 {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::TypeMismatch,
+                    "Each bound must be an array of the form [lower, upper]",
+                )
+                .in_function("qmc_integrate")
+                .into());
+            }
+            let lower = crate::if_int_convert_to_float_and_do(pair[0].clone(), Ok)?;
+            let upper = crate::if_int_convert_to_float_and_do(pair[1].clone(), Ok)?;
+            limits.push((lower, upper - lower));
+        }
+        if n < 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The number of points must be positive",
+            )
+            .in_function("qmc_integrate")
+            .into());
+        }
+
+        let points = match method.as_str() {
+            "sobol" => super::sobol_points(&context, n, limits.len() as INT)?,
+            "halton" => super::halton_points(&context, n, limits.len() as INT, None)?,
+            _ => {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("Unknown sequence '{method}', expected 'sobol' or 'halton'"),
+                )
+                .in_function("qmc_integrate")
+                .with_argument("method")
+                .into())
+            }
+        };
+
+        let mut total = 0.0;
+        for point in points {
+            let x = point
+                .into_array()
+                .unwrap()
+                .into_iter()
+                .zip(&limits)
+                .map(|(u, (lower, width))| {
+                    Dynamic::from_float(lower + width * u.as_float().unwrap())
+                })
+                .collect::<Array>();
+            total += crate::if_int_convert_to_float_and_do(
+                f.call_within_context::<Dynamic>(&context, (x,))?,
+                Ok,
+            )?;
+        }
+        let volume = limits.iter().map(|(_, width)| width).product::<FLOAT>();
+        Ok(volume * total / n as FLOAT)
+    }
+}
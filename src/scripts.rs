@@ -0,0 +1,74 @@
+use rhai::{Engine, EvalAltResult, Module, Position, Scope, Shared};
+
+/// The Rhai script libraries bundled with this package, by the path they are imported with. To
+/// add one, put it in the `scripts` directory and list it here.
+const BUNDLED_SCRIPTS: [(&str, &str); 2] = [
+    ("lab/report", include_str!("../scripts/report.rhai")),
+    ("lab/transforms", include_str!("../scripts/transforms.rhai")),
+];
+
+/// A module resolver for the libraries of higher-level routines, written in Rhai itself, that are
+/// bundled with this package. They are imported with paths that start with `lab/`:
+/// * `lab/transforms`: `center`, `zscore`, and `rescale` for arrays of numbers.
+/// * `lab/report`: `describe` and `summary` for the statistics of an array, and `markdown` for
+///   tables.
+///
+/// [`LabPackage`](crate::LabPackage) sets this up along with the usual resolver for script files,
+/// so it only needs to be used directly with engines that have their own module resolvers.
+/// ```
+/// use rhai::{packages::Package, Engine, FLOAT};
+/// use rhai_sci::{LabModuleResolver, SciPackage};
+///
+/// let mut engine = Engine::new();
+/// engine.register_global_module(SciPackage::new().as_shared_module());
+/// engine.set_module_resolver(LabModuleResolver::new());
+///
+/// let script = r#"
+///     import "lab/transforms" as transforms;
+///     transforms::rescale([2, 4, 6])
+/// "#;
+/// let scaled = engine.eval::<rhai::Array>(script).unwrap();
+/// assert_eq!(scaled[1].as_float().unwrap(), 0.5);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LabModuleResolver;
+
+impl LabModuleResolver {
+    /// Creates a resolver for the bundled script libraries.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The paths of the bundled script libraries.
+    pub fn paths() -> impl Iterator<Item = &'static str> {
+        BUNDLED_SCRIPTS.iter().map(|(path, _)| *path)
+    }
+
+    /// The source code of the bundled script library with the given path, if there is one.
+    pub fn source(path: &str) -> Option<&'static str> {
+        BUNDLED_SCRIPTS
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, script)| *script)
+    }
+}
+
+impl rhai::ModuleResolver for LabModuleResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        let script = Self::source(path)
+            .ok_or_else(|| EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))?;
+        let in_module = |err| EvalAltResult::ErrorInModule(path.to_string(), err, pos);
+        let mut ast = engine
+            .compile(script)
+            .map_err(|err| in_module(err.into()))?;
+        ast.set_source(path);
+        let module = Module::eval_ast_as_new(Scope::new(), &ast, engine).map_err(in_module)?;
+        Ok(module.into())
+    }
+}
@@ -0,0 +1,363 @@
+use rhai::plugin::*;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Writes `NaN` as `None`, since formats such as JSON cannot represent it.
+fn nan_to_none(x: rhai::FLOAT) -> Option<rhai::FLOAT> {
+    (!x.is_nan()).then_some(x)
+}
+
+/// Reads `None` back as `NaN`.
+fn none_to_nan(x: Option<rhai::FLOAT>) -> rhai::FLOAT {
+    x.unwrap_or(rhai::FLOAT::NAN)
+}
+
+/// The message of an error raised while rebuilding a value, for a deserialization error.
+fn rebuild_message(err: Box<rhai::EvalAltResult>) -> String {
+    crate::LabError::from_eval(&err).map_or_else(|| err.to_string(), |err| err.message)
+}
+
+/// A `Matrix` as it is serialized.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Matrix")]
+struct MatrixData {
+    /// Number of rows
+    rows: usize,
+    /// Number of columns
+    cols: usize,
+    /// Elements in row-major order, with `None` for `NaN`
+    data: Vec<Option<rhai::FLOAT>>,
+}
+
+impl Serialize for crate::Matrix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MatrixData {
+            rows: self.rows(),
+            cols: self.cols(),
+            data: self.data().iter().copied().map(nan_to_none).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for crate::Matrix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let m = MatrixData::deserialize(deserializer)?;
+        let data = m.data.into_iter().map(none_to_nan).collect();
+        crate::Matrix::new(m.rows, m.cols, data)
+            .map_err(|err| D::Error::custom(rebuild_message(err)))
+    }
+}
+
+/// A `Column` as it is serialized.
+#[cfg(feature = "data")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Column")]
+enum ColumnData {
+    /// Integers
+    Int(Vec<rhai::INT>),
+    /// Floats, with `None` for `NaN`
+    Float(Vec<Option<rhai::FLOAT>>),
+    /// Booleans
+    Bool(Vec<bool>),
+    /// Strings
+    Text(Vec<String>),
+}
+
+/// A `Table` as it is serialized.
+#[cfg(feature = "data")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Table")]
+struct TableData {
+    /// Column names, in order
+    names: Vec<String>,
+    /// Columns, in the same order as the names
+    columns: Vec<ColumnData>,
+}
+
+#[cfg(feature = "data")]
+impl Serialize for crate::Table {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let columns = self
+            .columns()
+            .iter()
+            .map(|column| match column {
+                crate::Column::Int(v) => ColumnData::Int(v.clone()),
+                crate::Column::Float(v) => {
+                    ColumnData::Float(v.iter().copied().map(nan_to_none).collect())
+                }
+                crate::Column::Bool(v) => ColumnData::Bool(v.clone()),
+                crate::Column::Text(v) => {
+                    ColumnData::Text(v.iter().map(|s| s.to_string()).collect())
+                }
+            })
+            .collect();
+        TableData {
+            names: self.names().to_vec(),
+            columns,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "data")]
+impl<'de> Deserialize<'de> for crate::Table {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let t = TableData::deserialize(deserializer)?;
+        let columns = t
+            .columns
+            .into_iter()
+            .map(|column| match column {
+                ColumnData::Int(v) => crate::Column::Int(v),
+                ColumnData::Float(v) => {
+                    crate::Column::Float(v.into_iter().map(none_to_nan).collect())
+                }
+                ColumnData::Bool(v) => crate::Column::Bool(v),
+                ColumnData::Text(v) => crate::Column::Text(v.into_iter().map(Into::into).collect()),
+            })
+            .collect();
+        crate::Table::new(t.names, columns).map_err(|err| D::Error::custom(rebuild_message(err)))
+    }
+}
+
+/// A `Pchip` as it is serialized. The slopes are not stored, since they are computed from the
+/// breakpoints and values.
+#[cfg(feature = "interpolation")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Pchip")]
+struct PchipData {
+    /// Breakpoints, strictly increasing
+    x: Vec<rhai::FLOAT>,
+    /// Values at the breakpoints
+    y: Vec<rhai::FLOAT>,
+}
+
+#[cfg(feature = "interpolation")]
+impl Serialize for crate::Pchip {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PchipData {
+            x: self.breakpoints().to_vec(),
+            y: self.values().to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "interpolation")]
+impl<'de> Deserialize<'de> for crate::Pchip {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let p = PchipData::deserialize(deserializer)?;
+        crate::Pchip::new(p.x, p.y).map_err(|err| D::Error::custom(rebuild_message(err)))
+    }
+}
+
+/// The key that marks a JSON object as one of the custom types of this package, holding the name
+/// of the type.
+const TYPE_KEY: &str = "$type";
+
+/// Converts a custom type into a JSON object tagged with its type name.
+fn tagged_json<T: Serialize>(name: &str, value: &T) -> serde_json::Value {
+    let mut json = serde_json::to_value(value).unwrap_or_default();
+    if let Some(object) = json.as_object_mut() {
+        object.insert(TYPE_KEY.to_string(), name.into());
+    }
+    json
+}
+
+/// Converts a Rhai value into JSON for `to_json`. Custom types become objects tagged with their
+/// type name so that `from_json` can restore them.
+fn value_to_json(value: &rhai::Dynamic) -> Result<serde_json::Value, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+            .in_function("to_json")
+            .into()
+    };
+    if value.is_unit() {
+        Ok(serde_json::Value::Null)
+    } else if let Ok(b) = value.as_bool() {
+        Ok(serde_json::Value::Bool(b))
+    } else if let Ok(i) = value.as_int() {
+        Ok(serde_json::Value::from(i))
+    } else if let Ok(f) = value.as_float() {
+        serde_json::Number::from_f64(f as f64)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| error(format!("JSON cannot represent the number {f}")))
+    } else if value.is_string() || value.is_char() {
+        Ok(serde_json::Value::String(value.to_string()))
+    } else if let Some(arr) = value.read_lock::<rhai::Array>() {
+        arr.iter()
+            .map(value_to_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map(serde_json::Value::Array)
+    } else if let Some(map) = value.read_lock::<rhai::Map>() {
+        map.iter()
+            .map(|(k, v)| value_to_json(v).map(|v| (k.to_string(), v)))
+            .collect::<Result<serde_json::Map<_, _>, _>>()
+            .map(serde_json::Value::Object)
+    } else if let Some(m) = value.read_lock::<crate::Matrix>() {
+        Ok(tagged_json("Matrix", &*m))
+    } else {
+        #[cfg(feature = "data")]
+        if let Some(t) = value.read_lock::<crate::Table>() {
+            return Ok(tagged_json("Table", &*t));
+        }
+        #[cfg(feature = "interpolation")]
+        if let Some(p) = value.read_lock::<crate::Pchip>() {
+            return Ok(tagged_json("Pchip", &*p));
+        }
+        Err(error(format!(
+            "Cannot convert a value of type {} to JSON",
+            value.type_name()
+        )))
+    }
+}
+
+/// Rebuilds a custom type from a JSON object tagged with its type name. The type is wrapped with
+/// `into_dynamic`, so that types that are not `Send + Sync` (such as `Table`) work without Rhai's
+/// `sync` feature.
+fn untagged_value<T>(
+    name: &str,
+    object: serde_json::Map<String, serde_json::Value>,
+    into_dynamic: fn(T) -> rhai::Dynamic,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    serde_json::from_value::<T>(serde_json::Value::Object(object))
+        .map(into_dynamic)
+        .map_err(|err| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot read a {name} from JSON: {err}"),
+            )
+            .in_function("from_json")
+            .into()
+        })
+}
+
+/// Converts JSON into a Rhai value for `from_json`, restoring the custom types that `to_json`
+/// tagged with their type name.
+fn json_to_value(value: serde_json::Value) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    Ok(match value {
+        serde_json::Value::Null => rhai::Dynamic::UNIT,
+        serde_json::Value::Bool(b) => rhai::Dynamic::from_bool(b),
+        serde_json::Value::Number(n) => {
+            match n.as_i64().and_then(|i| rhai::INT::try_from(i).ok()) {
+                Some(i) => rhai::Dynamic::from_int(i),
+                None => rhai::Dynamic::from_float(n.as_f64().unwrap_or(f64::NAN) as rhai::FLOAT),
+            }
+        }
+        serde_json::Value::String(s) => s.into(),
+        serde_json::Value::Array(arr) => rhai::Dynamic::from_array(
+            arr.into_iter()
+                .map(json_to_value)
+                .collect::<Result<_, _>>()?,
+        ),
+        serde_json::Value::Object(mut map) => {
+            let tag = match map.get(TYPE_KEY) {
+                Some(serde_json::Value::String(tag)) => Some(tag.clone()),
+                _ => None,
+            };
+            match tag.as_deref() {
+                Some("Matrix") => {
+                    map.remove(TYPE_KEY);
+                    return untagged_value("Matrix", map, rhai::Dynamic::from::<crate::Matrix>);
+                }
+                #[cfg(feature = "data")]
+                Some("Table") => {
+                    map.remove(TYPE_KEY);
+                    return untagged_value("Table", map, rhai::Dynamic::from::<crate::Table>);
+                }
+                #[cfg(feature = "interpolation")]
+                Some("Pchip") => {
+                    map.remove(TYPE_KEY);
+                    return untagged_value("Pchip", map, rhai::Dynamic::from::<crate::Pchip>);
+                }
+                _ => rhai::Dynamic::from_map(
+                    map.into_iter()
+                        .map(|(k, v)| Ok((k.into(), json_to_value(v)?)))
+                        .collect::<Result<_, Box<rhai::EvalAltResult>>>()?,
+                ),
+            }
+        }
+    })
+}
+
+/// Counts the array elements and map entries that JSON would become, the way Rhai does for
+/// `max_array_size` and `max_map_size`.
+fn json_size(value: &serde_json::Value) -> (usize, usize) {
+    let add = |(arrays, maps): (usize, usize), value| {
+        let (a, m) = json_size(value);
+        (arrays.saturating_add(a), maps.saturating_add(m))
+    };
+    match value {
+        serde_json::Value::Array(arr) => arr.iter().fold((arr.len(), 0), add),
+        serde_json::Value::Object(map) => map.values().fold((0, map.len()), add),
+        _ => (0, 0),
+    }
+}
+
+#[export_module]
+pub mod serialization_functions {
+    use rhai::{Dynamic, EvalAltResult, ImmutableString, Map, NativeCallContext};
+
+    /// Converts a value to a JSON string. Arrays become JSON arrays, maps become objects, and
+    /// `()` becomes `null`. A `Matrix`, `Table`, or `Pchip` becomes an object with a `"$type"`
+    /// key naming its type, so that `from_json` can restore it; any `NaN` in one is written as
+    /// `null`. Fails for other values JSON cannot hold, such as a `NaN` on its own.
+    /// ```typescript
+    /// let text = to_json(#{name: "run 1", x: [1, 2.5]});
+    /// assert_eq(text, "{\"name\":\"run 1\",\"x\":[1,2.5]}");
+    /// ```
+    /// ```typescript
+    /// let m = matrix([[1, 2], [3, 4]]);
+    /// assert(from_json(to_json(m)) == m);
+    /// ```
+    #[rhai_fn(name = "to_json", return_raw)]
+    pub fn to_json(value: Dynamic) -> Result<String, Box<EvalAltResult>> {
+        Ok(super::value_to_json(&value)?.to_string())
+    }
+
+    /// Converts a map to a JSON object, including any `Matrix`, `Table`, or `Pchip` values in
+    /// it, as `to_json` does for any value.
+    /// ```typescript
+    /// let saved = to_json(#{fit: pchip([0, 1, 2], [0, 1, 4])});
+    /// let p = from_json(saved).fit;
+    /// assert_eq(p.evaluate(1), 1.0);
+    /// ```
+    #[rhai_fn(name = "to_json", return_raw)]
+    pub fn map_to_json(map: Map) -> Result<String, Box<EvalAltResult>> {
+        to_json(Dynamic::from_map(map))
+    }
+
+    /// Parses a JSON string into a value. Arrays become arrays, objects become maps, and `null`
+    /// becomes `()`. Whole numbers become integers and other numbers become floats. Objects
+    /// written by `to_json` for a `Matrix`, `Table`, or `Pchip` become that type again.
+    /// ```typescript
+    /// let data = from_json("{\"name\": \"run 1\", \"x\": [[1, 2], [3, 4]], \"scale\": 0.5}");
+    /// assert_eq(data.name, "run 1");
+    /// assert_eq(data.x, [[1, 2], [3, 4]]);
+    /// assert_eq(data.scale, 0.5);
+    /// ```
+    /// ```typescript
+    /// let t = table(#{a: [1, 2], b: ["x", "y"]});
+    /// let restored = from_json(to_json(t));
+    /// assert_eq(restored.names, ["a", "b"]);
+    /// ```
+    #[rhai_fn(name = "from_json", return_raw)]
+    pub fn from_json(
+        ctx: NativeCallContext,
+        text: ImmutableString,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let value = serde_json::from_str(&text).map_err(|err| {
+            crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot parse the text as JSON: {err}"),
+            )
+            .in_function("from_json")
+            .with_argument("text")
+        })?;
+        let (arrays, maps) = super::json_size(&value);
+        crate::check_data_size(&ctx, arrays, maps)?;
+        super::json_to_value(value)
+    }
+}
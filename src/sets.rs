@@ -2,7 +2,8 @@ use rhai::plugin::*;
 
 #[export_module]
 pub mod set_functions {
-    use rhai::{Array, Dynamic, EvalAltResult};
+    use crate::{argsort_floats, if_list_convert_to_vec_float_and_do};
+    use rhai::{Array, Dynamic, EvalAltResult, FLOAT, INT};
 
     /// Returns the set union of two arrays.
     /// ```typescript
@@ -40,4 +41,292 @@ pub mod set_functions {
         }
         Ok(crate::misc_functions::unique(&mut new_arr).unwrap())
     }
+
+    /// Returns the elements of the first array that are not in the second array (the set
+    /// difference), sorted and without duplicates.
+    /// ```typescript
+    /// let set1 = [7, 1, 7, 7, 4];
+    /// let set2 = [7, 0, 4, 4, 0];
+    /// let x = setdiff(set1, set2);
+    /// assert_eq(x, [1]);
+    /// ```
+    #[rhai_fn(name = "setdiff", return_raw)]
+    pub fn setdiff(arr1: Array, arr2: Array) -> Result<Array, Box<EvalAltResult>> {
+        let array2 = arr2
+            .into_iter()
+            .map(|x| format!("{:?}", x))
+            .collect::<Vec<String>>();
+        let mut new_arr = arr1
+            .into_iter()
+            .filter(|el| !array2.contains(&format!("{:?}", el)))
+            .collect::<Array>();
+        crate::misc_functions::unique(&mut new_arr)
+    }
+
+    /// Sorts a numeric array and removes elements within `tol` of the element before them, keeping
+    /// the first (smallest) element of each cluster of nearly equal values.
+    fn unique_within(arr: Array, tol: FLOAT) -> Result<Vec<(FLOAT, Dynamic)>, Box<EvalAltResult>> {
+        if tol.is_nan() || tol < 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The tolerance must be non-negative",
+            )
+            .into());
+        }
+        let mut arr = arr;
+        let original = crate::promote_numbers(arr.clone());
+        if_list_convert_to_vec_float_and_do(&mut arr, |x| {
+            let mut result: Vec<(FLOAT, Dynamic)> = vec![];
+            for idx in argsort_floats(&x) {
+                match result.last() {
+                    Some((last, _)) if x[idx] - last <= tol => {}
+                    _ => result.push((x[idx], original[idx].clone())),
+                }
+            }
+            Ok(result)
+        })
+    }
+
+    /// Returns true if any of the sorted values is within `tol` of `x`.
+    fn contains_within(sorted: &mut [(FLOAT, Dynamic)], x: FLOAT, tol: FLOAT) -> bool {
+        let start = sorted.partition_point(|(v, _)| *v < x - tol);
+        sorted.get(start).is_some_and(|(v, _)| *v <= x + tol)
+    }
+
+    /// Returns the set union of two arrays, treating numbers within `tol` of each other as equal.
+    /// Of each cluster of nearly equal values, the smallest is kept.
+    /// ```typescript
+    /// let set1 = [0.1 + 0.2, 1.0];
+    /// let set2 = [0.3, 2.0];
+    /// let u = union(set1, set2, 1e-9);
+    /// assert_eq(u, [0.3, 1.0, 2.0]);
+    /// ```
+    #[rhai_fn(name = "union", return_raw)]
+    pub fn union_with_tolerance(
+        arr1: Array,
+        arr2: Array,
+        tol: FLOAT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let mut x = arr1;
+        x.extend(arr2);
+        Ok(unique_within(x, tol)?
+            .into_iter()
+            .map(|(_, el)| el)
+            .collect())
+    }
+
+    /// Performs set intersection of two arrays, treating numbers within `tol` of each other as
+    /// equal. The returned values are taken from the first array.
+    /// ```typescript
+    /// let set1 = [0.1 + 0.2, 0.5, 0.7];
+    /// let set2 = [0.3, 0.7];
+    /// let x = intersect(set1, set2, 1e-9);
+    /// assert_eq(x, [0.1 + 0.2, 0.7]);
+    /// ```
+    #[rhai_fn(name = "intersect", return_raw)]
+    pub fn intersect_with_tolerance(
+        arr1: Array,
+        arr2: Array,
+        tol: FLOAT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let mut other = unique_within(arr2, tol)?;
+        Ok(unique_within(arr1, tol)?
+            .into_iter()
+            .filter(|(x, _)| contains_within(&mut other, *x, tol))
+            .map(|(_, el)| el)
+            .collect())
+    }
+
+    /// Returns the elements of the first array that are not within `tol` of any element of the
+    /// second array, sorted and without (nearly) duplicate values.
+    /// ```typescript
+    /// let set1 = [0.1 + 0.2, 0.5, 0.7];
+    /// let set2 = [0.3, 0.7];
+    /// let x = setdiff(set1, set2, 1e-9);
+    /// assert_eq(x, [0.5]);
+    /// ```
+    #[rhai_fn(name = "setdiff", return_raw)]
+    pub fn setdiff_with_tolerance(
+        arr1: Array,
+        arr2: Array,
+        tol: FLOAT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let mut other = unique_within(arr2, tol)?;
+        Ok(unique_within(arr1, tol)?
+            .into_iter()
+            .filter(|(x, _)| !contains_within(&mut other, *x, tol))
+            .map(|(_, el)| el)
+            .collect())
+    }
+
+    /// Returns the unique elements of an array. If `with_counts` is true, also returns the number
+    /// of times each unique element occurs and the index of its first occurrence, as
+    /// `[values, counts, indices]`.
+    /// ```typescript
+    /// let data = [3, 1, 3, 2, 3, 1];
+    /// let result = unique(data, true);
+    /// assert_eq(result, [[1, 2, 3], [2, 1, 3], [1, 3, 0]]);
+    /// ```
+    /// ```typescript
+    /// let data = [1.5, 0.5, 1.5];
+    /// assert_eq(unique(data, false), [0.5, 1.5]);
+    /// ```
+    #[rhai_fn(name = "unique", return_raw, pure)]
+    pub fn unique_with_counts(
+        arr: &mut Array,
+        with_counts: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if !with_counts {
+            return crate::misc_functions::unique(arr);
+        }
+        let original = crate::promote_numbers(arr.clone());
+        if_list_convert_to_vec_float_and_do(arr, |x| {
+            // A stable sort keeps the first occurrence at the start of each run of equal values
+            let mut values = vec![];
+            let mut counts = vec![];
+            let mut indices = vec![];
+            let mut previous: Option<rhai::FLOAT> = None;
+            for idx in argsort_floats(&x) {
+                if previous == Some(x[idx]) {
+                    *counts.last_mut().unwrap() += 1;
+                } else {
+                    values.push(original[idx].clone());
+                    counts.push(1 as INT);
+                    indices.push(idx as INT);
+                    previous = Some(x[idx]);
+                }
+            }
+            Ok(vec![
+                Dynamic::from_array(values),
+                Dynamic::from_array(counts.into_iter().map(Dynamic::from_int).collect()),
+                Dynamic::from_array(indices.into_iter().map(Dynamic::from_int).collect()),
+            ])
+        })
+    }
+
+    /// Finds, for each element of `arr1`, the index of its first occurrence in `arr2`. Numbers are
+    /// compared within `tol` if it is given, and other values are compared exactly.
+    fn locate(
+        arr1: Array,
+        arr2: Array,
+        tol: Option<FLOAT>,
+    ) -> Result<Vec<Option<usize>>, Box<EvalAltResult>> {
+        match tol {
+            None => {
+                let array2 = arr2
+                    .into_iter()
+                    .map(|x| format!("{:?}", x))
+                    .collect::<Vec<String>>();
+                Ok(arr1
+                    .into_iter()
+                    .map(|el| {
+                        let key = format!("{:?}", el);
+                        array2.iter().position(|x| *x == key)
+                    })
+                    .collect())
+            }
+            Some(tol) if tol.is_nan() || tol < 0.0 => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The tolerance must be non-negative",
+            )
+            .into()),
+            Some(tol) => {
+                let (mut arr1, mut arr2) = (arr1, arr2);
+                let x = if_list_convert_to_vec_float_and_do(&mut arr1, Ok)?;
+                let y = if_list_convert_to_vec_float_and_do(&mut arr2, Ok)?;
+                Ok(x.iter()
+                    .map(|a| y.iter().position(|b| (a - b).abs() <= tol))
+                    .collect())
+            }
+        }
+    }
+
+    /// Converts the result of `locate` into an array of booleans.
+    fn membership(locations: Vec<Option<usize>>) -> Array {
+        locations
+            .into_iter()
+            .map(|idx| Dynamic::from_bool(idx.is_some()))
+            .collect()
+    }
+
+    /// Converts the result of `locate` into `[flags, indices]`, using -1 for missing elements.
+    fn membership_with_indices(locations: Vec<Option<usize>>) -> Array {
+        let indices = locations
+            .iter()
+            .map(|idx| Dynamic::from_int(idx.map_or(-1, |i| i as INT)))
+            .collect::<Array>();
+        vec![
+            Dynamic::from_array(membership(locations)),
+            Dynamic::from_array(indices),
+        ]
+    }
+
+    /// Returns an array of booleans indicating which elements of the first array are found in the
+    /// second array.
+    /// ```typescript
+    /// let x = ismember([1, 5, 2, 9], [2, 3, 1]);
+    /// assert_eq(x, [true, false, true, false]);
+    /// ```
+    #[rhai_fn(name = "ismember", return_raw)]
+    pub fn ismember(arr1: Array, arr2: Array) -> Result<Array, Box<EvalAltResult>> {
+        locate(arr1, arr2, None).map(membership)
+    }
+
+    /// Returns an array of booleans indicating which elements of the first array are within `tol`
+    /// of an element of the second array.
+    /// ```typescript
+    /// let x = ismember([0.1 + 0.2, 0.5], [0.3], 1e-9);
+    /// assert_eq(x, [true, false]);
+    /// ```
+    #[rhai_fn(name = "ismember", return_raw)]
+    pub fn ismember_with_tolerance(
+        arr1: Array,
+        arr2: Array,
+        tol: FLOAT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        locate(arr1, arr2, Some(tol)).map(membership)
+    }
+
+    /// Tests which elements of the first array are found in the second array. If `with_indices` is
+    /// true, returns `[flags, indices]`, where `indices` holds the index of the first matching
+    /// element of the second array, or -1 if there is none.
+    /// ```typescript
+    /// let x = ismember(["b", "z", "a"], ["a", "b", "b"], true);
+    /// assert_eq(x, [[true, false, true], [1, -1, 0]]);
+    /// ```
+    #[rhai_fn(name = "ismember", return_raw)]
+    pub fn ismember_indices(
+        arr1: Array,
+        arr2: Array,
+        with_indices: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let locations = locate(arr1, arr2, None)?;
+        Ok(if with_indices {
+            membership_with_indices(locations)
+        } else {
+            membership(locations)
+        })
+    }
+
+    /// Tests which elements of the first array are within `tol` of an element of the second
+    /// array. If `with_indices` is true, returns `[flags, indices]`, where `indices` holds the index
+    /// of the first matching element of the second array, or -1 if there is none.
+    /// ```typescript
+    /// let x = ismember([1.0, 2.5], [0.0, 1.0 + 1e-12], 1e-9, true);
+    /// assert_eq(x, [[true, false], [1, -1]]);
+    /// ```
+    #[rhai_fn(name = "ismember", return_raw)]
+    pub fn ismember_indices_with_tolerance(
+        arr1: Array,
+        arr2: Array,
+        tol: FLOAT,
+        with_indices: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let locations = locate(arr1, arr2, Some(tol))?;
+        Ok(if with_indices {
+            membership_with_indices(locations)
+        } else {
+            membership(locations)
+        })
+    }
 }
@@ -0,0 +1,198 @@
+use rhai::plugin::*;
+
+/// Fits numerator and denominator coefficients of the given order to an impulse response,
+/// returning them along with the norm of the residual of the fit.
+#[cfg(feature = "nalgebra")]
+fn prony(
+    h: &[rhai::FLOAT],
+    order: usize,
+) -> Result<(Vec<rhai::FLOAT>, Vec<rhai::FLOAT>, rhai::FLOAT), Box<rhai::EvalAltResult>> {
+    // Denominator from the linear prediction equations h[k] = -sum(a[i] * h[k - i])
+    let rows = h.len() - order - 1;
+    let m = nalgebralib::DMatrix::from_fn(rows, order, |r, c| h[order + r - c]);
+    let rhs = nalgebralib::DVector::from_fn(rows, |r, _| -h[order + r + 1]);
+    let tail = m.svd(true, true).solve(&rhs, 1e-12).map_err(|e| {
+        rhai::EvalAltResult::from(crate::LabError::new(
+            crate::LabErrorKind::Numerical,
+            e.to_string(),
+        ))
+    })?;
+    let mut a = vec![1.0];
+    a.extend(tail.iter());
+
+    // Numerator from the first order + 1 samples
+    let b = (0..=order)
+        .map(|k| (0..=k).map(|i| a[i] * h[k - i]).sum())
+        .collect::<Vec<rhai::FLOAT>>();
+
+    // Residual of the fit, by simulating the impulse response of the fitted filter
+    let mut y = vec![0.0; h.len()];
+    for k in 0..h.len() {
+        let input = b.get(k).copied().unwrap_or(0.0);
+        y[k] = input
+            - (1..=order.min(k))
+                .map(|i| a[i] * y[k - i])
+                .sum::<rhai::FLOAT>();
+    }
+    let residual = y
+        .iter()
+        .zip(h)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<rhai::FLOAT>()
+        .sqrt();
+    Ok((b, a, residual))
+}
+
+#[export_module]
+pub mod signal_functions {
+    use crate::if_list_convert_to_vec_float_and_do;
+    use rhai::{Array, Dynamic, EvalAltResult, Map, FLOAT, INT};
+
+    /// Evaluates the frequency response of a discrete-time filter with numerator coefficients `b`
+    /// and denominator coefficients `a` (both in increasing powers of `z^-1`) at the normalized
+    /// frequencies `w` (in radians per sample, from 0 to pi). Returns an object map with the
+    /// `magnitude` and `phase` (in radians) at each frequency.
+    /// ```typescript,only_f64
+    /// let h = freqz([0.5, 0.5], [1.0], [0.0, pi]);
+    /// assert_eq(h.magnitude[0], 1.0);
+    /// assert(h.magnitude[1] < 1e-12);
+    /// ```
+    /// ```typescript
+    /// let h = freqz([1.0], [1.0, -0.5], [0.0]);
+    /// assert_eq(h.magnitude, [2.0]);
+    /// assert_eq(h.phase, [0.0]);
+    /// ```
+    #[rhai_fn(name = "freqz", return_raw)]
+    pub fn freqz(b: Array, a: Array, w: Array) -> Result<Map, Box<EvalAltResult>> {
+        let (mut b, mut a, mut w) = (b, a, w);
+        let b = if_list_convert_to_vec_float_and_do(&mut b, Ok)?;
+        let a = if_list_convert_to_vec_float_and_do(&mut a, Ok)?;
+        let w = if_list_convert_to_vec_float_and_do(&mut w, Ok)?;
+        if a.is_empty() || a[0] == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The leading denominator coefficient must be nonzero",
+            )
+            .in_function("freqz")
+            .into());
+        }
+
+        // Evaluates a polynomial in z^-1 = exp(-i w) as (real, imaginary)
+        let evaluate = |coefficients: &[FLOAT], w: FLOAT| {
+            coefficients
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (k, c)| {
+                    let angle = -(k as FLOAT) * w;
+                    (re + c * angle.cos(), im + c * angle.sin())
+                })
+        };
+
+        let mut magnitude = vec![];
+        let mut phase = vec![];
+        for wk in w {
+            let (br, bi) = evaluate(&b, wk);
+            let (ar, ai) = evaluate(&a, wk);
+            let denominator = ar * ar + ai * ai;
+            let (hr, hi) = (
+                (br * ar + bi * ai) / denominator,
+                (bi * ar - br * ai) / denominator,
+            );
+            magnitude.push(Dynamic::from_float(hr.hypot(hi)));
+            phase.push(Dynamic::from_float(hi.atan2(hr)));
+        }
+
+        let mut result = Map::new();
+        result.insert("magnitude".into(), Dynamic::from_array(magnitude));
+        result.insert("phase".into(), Dynamic::from_array(phase));
+        Ok(result)
+    }
+
+    /// Fits a discrete-time transfer function `B(z)/A(z)` to an impulse response `h` sampled every
+    /// `dt` seconds, using Prony's method. The order is the smallest (up to 10) that reproduces the
+    /// response to within a relative error of 1e-8. Returns an object map with the numerator `b`,
+    /// the denominator `a` (with `a[0] = 1`), and the sample time `dt`, ready for use with `freqz`.
+    /// ```typescript,only_f64
+    /// let h = [1.0, 0.5, 0.25, 0.125, 0.0625, 0.03125];
+    /// let tf = impulse_to_tf(h, 0.1);
+    /// assert_eq(len(tf.a), 2);
+    /// assert(abs(tf.a[1] + 0.5) < 1e-9 && abs(tf.b[0] - 1.0) < 1e-9);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "impulse_to_tf", return_raw)]
+    pub fn impulse_to_tf(h: Array, dt: FLOAT) -> Result<Map, Box<EvalAltResult>> {
+        let mut h = h;
+        let samples = if_list_convert_to_vec_float_and_do(&mut h, Ok)?;
+        let max_order = ((samples.len().saturating_sub(1)) / 2).min(10);
+        let scale = samples.iter().map(|x| x * x).sum::<FLOAT>().sqrt();
+        let mut best = None;
+        for order in 1..=max_order {
+            let (b, a, residual) = super::prony(&samples, order)?;
+            let done = residual <= 1e-8 * scale;
+            best = Some((b, a));
+            if done {
+                break;
+            }
+        }
+        let (b, a) = best.ok_or_else(|| {
+            rhai::EvalAltResult::from(
+                crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    "At least 3 samples of the impulse response are needed",
+                )
+                .in_function("impulse_to_tf"),
+            )
+        })?;
+        transfer_function_map(b, a, dt)
+    }
+
+    /// Fits a discrete-time transfer function of the given order to an impulse response `h`
+    /// sampled every `dt` seconds, using Prony's method. The numerator and denominator both have
+    /// `order + 1` coefficients.
+    /// ```typescript,only_f64
+    /// let h = [0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    /// let tf = impulse_to_tf(h, 1.0, 1);
+    /// assert(abs(tf.b[1] - 1.0) < 1e-9 && abs(tf.a[1]) < 1e-9);
+    /// ```
+    #[cfg(feature = "nalgebra")]
+    #[rhai_fn(name = "impulse_to_tf", return_raw)]
+    pub fn impulse_to_tf_with_order(
+        h: Array,
+        dt: FLOAT,
+        order: INT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut h = h;
+        let samples = if_list_convert_to_vec_float_and_do(&mut h, Ok)?;
+        if order < 1 || samples.len() < 2 * order as usize + 1 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The order must be at least 1, with at least 2 * order + 1 samples",
+            )
+            .in_function("impulse_to_tf")
+            .with_argument("order")
+            .into());
+        }
+        let (b, a, _) = super::prony(&samples, order as usize)?;
+        transfer_function_map(b, a, dt)
+    }
+
+    /// Packages transfer function coefficients into an object map.
+    #[cfg(feature = "nalgebra")]
+    fn transfer_function_map(
+        b: Vec<FLOAT>,
+        a: Vec<FLOAT>,
+        dt: FLOAT,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut result = Map::new();
+        result.insert(
+            "b".into(),
+            Dynamic::from_array(b.into_iter().map(Dynamic::from_float).collect()),
+        );
+        result.insert(
+            "a".into(),
+            Dynamic::from_array(a.into_iter().map(Dynamic::from_float).collect()),
+        );
+        result.insert("dt".into(), Dynamic::from_float(dt));
+        Ok(result)
+    }
+}
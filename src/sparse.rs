@@ -0,0 +1,788 @@
+use rhai::plugin::*;
+
+/// A sparse matrix of floating point numbers in compressed sparse row (CSR) storage, so memory
+/// use grows with the number of non-zero elements rather than with rows × columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix {
+    /// Number of rows
+    rows: usize,
+    /// Number of columns
+    cols: usize,
+    /// Offsets into `col_idx` and `values` where each row starts, with one extra entry at the end
+    row_ptr: Vec<usize>,
+    /// Column of each stored element, sorted within each row
+    col_idx: Vec<usize>,
+    /// Value of each stored element
+    values: Vec<rhai::FLOAT>,
+}
+
+impl SparseMatrix {
+    /// Creates a sparse matrix from `(row, column, value)` triplets. Values at repeated positions
+    /// are summed, and positions that sum to zero are not stored.
+    pub fn from_triplets(
+        rows: usize,
+        cols: usize,
+        mut triplets: Vec<(usize, usize, rhai::FLOAT)>,
+    ) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if let Some((i, j, _)) = triplets.iter().find(|(i, j, _)| *i >= rows || *j >= cols) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Position ({i}, {j}) is outside of a {rows} x {cols} matrix"),
+            )
+            .into());
+        }
+        triplets.sort_by_key(|(i, j, _)| (*i, *j));
+
+        let mut row_ptr = Vec::new();
+        rows.checked_add(1)
+            .and_then(|n| row_ptr.try_reserve_exact(n).ok())
+            .ok_or_else(|| {
+                crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("A sparse matrix with {rows} rows is too large to allocate"),
+                )
+            })?;
+        row_ptr.resize(rows + 1, 0);
+        let mut col_idx: Vec<usize> = vec![];
+        let mut values: Vec<rhai::FLOAT> = vec![];
+        let mut last = None;
+        for (i, j, v) in triplets {
+            if last == Some((i, j)) {
+                *values.last_mut().unwrap() += v;
+            } else {
+                // Drop an entry that cancelled out before starting the next one
+                if values.last() == Some(&0.0) {
+                    values.pop();
+                    col_idx.pop();
+                    row_ptr[last.unwrap().0 + 1] -= 1;
+                }
+                col_idx.push(j);
+                values.push(v);
+                row_ptr[i + 1] += 1;
+                last = Some((i, j));
+            }
+        }
+        if values.last() == Some(&0.0) {
+            values.pop();
+            col_idx.pop();
+            row_ptr[last.unwrap().0 + 1] -= 1;
+        }
+        for i in 0..rows {
+            row_ptr[i + 1] += row_ptr[i];
+        }
+
+        Ok(SparseMatrix {
+            rows,
+            cols,
+            row_ptr,
+            col_idx,
+            values,
+        })
+    }
+
+    /// Creates a sparse matrix holding the non-zero elements of a dense matrix.
+    pub fn from_dense(m: &crate::Matrix) -> Self {
+        let mut triplets = vec![];
+        for i in 0..m.rows() {
+            for j in 0..m.cols() {
+                if m.get(i, j) != 0.0 {
+                    triplets.push((i, j, m.get(i, j)));
+                }
+            }
+        }
+        SparseMatrix::from_triplets(m.rows(), m.cols(), triplets).unwrap()
+    }
+
+    /// Converts the matrix into a dense `Matrix`.
+    pub fn to_dense(&self) -> crate::Matrix {
+        let mut m = crate::Matrix::filled(self.rows, self.cols, 0.0);
+        for (i, j, v) in self.triplets() {
+            m.set(i, j, v);
+        }
+        m
+    }
+
+    /// Returns the stored elements as `(row, column, value)` triplets in row-major order.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, rhai::FLOAT)> + '_ {
+        (0..self.rows).flat_map(move |i| {
+            (self.row_ptr[i]..self.row_ptr[i + 1])
+                .map(move |k| (i, self.col_idx[k], self.values[k]))
+        })
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Number of stored (non-zero) elements
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the element at row `i` and column `j`.
+    pub fn get(&self, i: usize, j: usize) -> rhai::FLOAT {
+        let row = &self.col_idx[self.row_ptr[i]..self.row_ptr[i + 1]];
+        match row.binary_search(&j) {
+            Ok(k) => self.values[self.row_ptr[i] + k],
+            Err(_) => 0.0,
+        }
+    }
+
+    /// Returns the transpose of the matrix.
+    pub fn transpose(&self) -> Self {
+        let mut row_ptr = vec![0; self.cols + 1];
+        for j in &self.col_idx {
+            row_ptr[j + 1] += 1;
+        }
+        for j in 0..self.cols {
+            row_ptr[j + 1] += row_ptr[j];
+        }
+        // Walking rows in order keeps the new column indices sorted
+        let mut next = row_ptr.clone();
+        let mut col_idx = vec![0; self.nnz()];
+        let mut values = vec![0.0; self.nnz()];
+        for (i, j, v) in self.triplets() {
+            col_idx[next[j]] = i;
+            values[next[j]] = v;
+            next[j] += 1;
+        }
+        SparseMatrix {
+            rows: self.cols,
+            cols: self.rows,
+            row_ptr,
+            col_idx,
+            values,
+        }
+    }
+
+    /// Multiplies the matrix by a dense vector.
+    pub fn matvec(&self, x: &[rhai::FLOAT]) -> Vec<rhai::FLOAT> {
+        (0..self.rows)
+            .map(|i| {
+                (self.row_ptr[i]..self.row_ptr[i + 1])
+                    .map(|k| self.values[k] * x[self.col_idx[k]])
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Tests whether the matrix is square and equal to its transpose.
+    pub fn is_symmetric(&self) -> bool {
+        self.rows == self.cols && *self == self.transpose()
+    }
+
+    /// Returns the diagonal of the matrix.
+    pub fn diagonal(&self) -> Vec<rhai::FLOAT> {
+        (0..self.rows.min(self.cols))
+            .map(|i| self.get(i, i))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for SparseMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SparseMatrix({} x {}, [", self.rows, self.cols)?;
+        for (k, (i, j, v)) in self.triplets().enumerate() {
+            if k > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "({i}, {j}): {v:?}")?;
+        }
+        write!(f, "])")
+    }
+}
+
+/// Reads an INT or FLOAT as a FLOAT.
+fn sparse_value(x: &rhai::Dynamic) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>> {
+    x.as_float()
+        .or_else(|_| x.as_int().map(|i| i as rhai::FLOAT))
+        .map_err(|_| {
+            crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!("Elements must be INT or FLOAT, not {}", x.type_name()),
+            )
+            .into()
+        })
+}
+
+/// Reads a non-negative INT as an index.
+fn sparse_index(x: &rhai::Dynamic) -> Result<usize, Box<rhai::EvalAltResult>> {
+    match x.as_int() {
+        Ok(i) if i >= 0 => Ok(i as usize),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Indices must be non-negative integers, not {x}"),
+        )
+        .into()),
+    }
+}
+
+/// Reads a dense vector with one element per column of `a`.
+fn sparse_operand(
+    a: &SparseMatrix,
+    x: &rhai::Array,
+) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    if x.len() != a.cols() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!(
+                "The vector has {} elements, but the matrix has {} columns",
+                x.len(),
+                a.cols()
+            ),
+        )
+        .into());
+    }
+    x.iter().map(sparse_value).collect()
+}
+
+/// Dot product of two dense vectors.
+fn dense_dot(a: &[rhai::FLOAT], b: &[rhai::FLOAT]) -> rhai::FLOAT {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The progress hook of an iterative solver, called after each iteration with its number, the
+/// current solution, and the relative residual. Returning `false` stops the solver early.
+type SolverProgress<'a> =
+    &'a mut dyn FnMut(usize, &[rhai::FLOAT], rhai::FLOAT) -> Result<bool, Box<rhai::EvalAltResult>>;
+
+/// Solves a symmetric positive definite system with the conjugate gradient method, using the
+/// diagonal of the matrix as a (Jacobi) preconditioner.
+fn conjugate_gradient(
+    a: &SparseMatrix,
+    b: &[rhai::FLOAT],
+    tol: rhai::FLOAT,
+    max_iter: usize,
+    progress: SolverProgress,
+) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    let inv_diag = a
+        .diagonal()
+        .iter()
+        .map(|d| if *d > 0.0 { 1.0 / d } else { 1.0 })
+        .collect::<Vec<_>>();
+    let b_norm = dense_dot(b, b).sqrt();
+    let mut x = vec![0.0; b.len()];
+    if b_norm == 0.0 {
+        return Ok(x);
+    }
+    let mut r = b.to_vec();
+    let mut z = r
+        .iter()
+        .zip(&inv_diag)
+        .map(|(r, d)| r * d)
+        .collect::<Vec<_>>();
+    let mut p = z.clone();
+    let mut rz = dense_dot(&r, &z);
+    for iteration in 1..=max_iter {
+        let ap = a.matvec(&p);
+        let alpha = rz / dense_dot(&p, &ap);
+        for i in 0..x.len() {
+            x[i] += alpha * p[i];
+            r[i] -= alpha * ap[i];
+        }
+        let residual = dense_dot(&r, &r).sqrt() / b_norm;
+        if !progress(iteration, &x, residual)? || residual <= tol {
+            return Ok(x);
+        }
+        z = r.iter().zip(&inv_diag).map(|(r, d)| r * d).collect();
+        let rz_next = dense_dot(&r, &z);
+        let beta = rz_next / rz;
+        rz = rz_next;
+        for i in 0..p.len() {
+            p[i] = z[i] + beta * p[i];
+        }
+    }
+    Err(crate::LabError::new(
+        crate::LabErrorKind::Numerical,
+        format!("Conjugate gradient did not converge in {max_iter} iterations"),
+    )
+    .into())
+}
+
+/// Solves a general square system with the stabilized biconjugate gradient method (BiCGSTAB).
+fn bicgstab(
+    a: &SparseMatrix,
+    b: &[rhai::FLOAT],
+    tol: rhai::FLOAT,
+    max_iter: usize,
+    progress: SolverProgress,
+) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    let b_norm = dense_dot(b, b).sqrt();
+    let mut x = vec![0.0; b.len()];
+    if b_norm == 0.0 {
+        return Ok(x);
+    }
+    let mut r = b.to_vec();
+    let r_hat = r.clone();
+    let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+    let mut v = vec![0.0; b.len()];
+    let mut p = vec![0.0; b.len()];
+    for iteration in 1..=max_iter {
+        let rho_next = dense_dot(&r_hat, &r);
+        if rho_next == 0.0 {
+            break;
+        }
+        let beta = (rho_next / rho) * (alpha / omega);
+        rho = rho_next;
+        for i in 0..p.len() {
+            p[i] = r[i] + beta * (p[i] - omega * v[i]);
+        }
+        v = a.matvec(&p);
+        alpha = rho / dense_dot(&r_hat, &v);
+        let s = r
+            .iter()
+            .zip(&v)
+            .map(|(r, v)| r - alpha * v)
+            .collect::<Vec<_>>();
+        let s_norm = dense_dot(&s, &s).sqrt() / b_norm;
+        if s_norm <= tol {
+            for i in 0..x.len() {
+                x[i] += alpha * p[i];
+            }
+            progress(iteration, &x, s_norm)?;
+            return Ok(x);
+        }
+        let t = a.matvec(&s);
+        omega = dense_dot(&t, &s) / dense_dot(&t, &t);
+        for i in 0..x.len() {
+            x[i] += alpha * p[i] + omega * s[i];
+            r[i] = s[i] - omega * t[i];
+        }
+        let residual = dense_dot(&r, &r).sqrt() / b_norm;
+        if !progress(iteration, &x, residual)? || residual <= tol {
+            return Ok(x);
+        }
+    }
+    Err(crate::LabError::new(
+        crate::LabErrorKind::Numerical,
+        format!("BiCGSTAB did not converge in {max_iter} iterations"),
+    )
+    .into())
+}
+
+#[export_module]
+pub mod sparse_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, FnPtr, ImmutableString, Map, NativeCallContext, Position,
+        FLOAT, INT,
+    };
+
+    /// A sparse matrix of FLOATs in compressed sparse row storage, created with `sparse`.
+    pub type SparseMatrix = crate::SparseMatrix;
+
+    /// Creates a `SparseMatrix` with the given number of rows and columns from arrays of row
+    /// indices, column indices, and values. Values at repeated positions are summed, which is
+    /// convenient when assembling finite element systems.
+    /// ```typescript
+    /// let a = sparse([0, 1, 2, 0], [0, 1, 2, 0], [1, 2, 3, 4], 3, 3);
+    /// assert_eq(to_array(a), [[5.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 3.0]]);
+    /// assert_eq(a.nnz, 3);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kinds = [];
+    /// for size in [[9223372036854775807, 1], [1, 9223372036854775807]] {
+    ///     try { sparse([0], [0], [1], size[0], size[1]); } catch (err) { kinds.push(err.kind); }
+    /// }
+    /// assert_eq(kinds, ["invalid_argument", "invalid_argument"]);
+    /// ```
+    #[rhai_fn(name = "sparse", return_raw)]
+    pub fn sparse(
+        ctx: NativeCallContext,
+        i: Array,
+        j: Array,
+        values: Array,
+        rows: INT,
+        cols: INT,
+    ) -> Result<SparseMatrix, Box<EvalAltResult>> {
+        if i.len() != j.len() || i.len() != values.len() {
+            return Err(crate::LabError::new(crate::LabErrorKind::DimensionMismatch, format!(
+                    "Row indices, column indices, and values must have the same length, not {}, {}, and {}",
+                    i.len(),
+                    j.len(),
+                    values.len()
+                )).in_function("sparse")
+            .into());
+        }
+        if rows < 0 || cols < 0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The number of rows and columns must be non-negative",
+            )
+            .in_function("sparse")
+            .into());
+        }
+        // The row pointers and the column pointers of the transpose are stored densely
+        for size in [rows, cols] {
+            let size = (size as usize).saturating_add(1);
+            crate::check_array_shape(&ctx, &[size])?;
+        }
+        let triplets = i
+            .iter()
+            .zip(&j)
+            .zip(&values)
+            .map(|((i, j), v)| {
+                Ok((
+                    super::sparse_index(i)?,
+                    super::sparse_index(j)?,
+                    super::sparse_value(v)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?;
+        SparseMatrix::from_triplets(rows as usize, cols as usize, triplets)
+    }
+
+    /// Creates a `SparseMatrix` from arrays of row indices, column indices, and values, sized to
+    /// fit the largest indices.
+    /// ```typescript
+    /// let a = sparse([0, 2], [1, 3], [1.5, -2.0]);
+    /// assert_eq(a.shape, [3, 4]);
+    /// ```
+    #[rhai_fn(name = "sparse", return_raw)]
+    pub fn sparse_fit(
+        ctx: NativeCallContext,
+        i: Array,
+        j: Array,
+        values: Array,
+    ) -> Result<SparseMatrix, Box<EvalAltResult>> {
+        let extent = |idx: &Array| -> Result<INT, Box<EvalAltResult>> {
+            Ok(idx
+                .iter()
+                .map(super::sparse_index)
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .max()
+                .map_or(0, |m| (m as INT).saturating_add(1)))
+        };
+        let (rows, cols) = (extent(&i)?, extent(&j)?);
+        sparse(ctx, i, j, values, rows, cols)
+    }
+
+    /// Converts a dense matrix, given as a nested array, into a `SparseMatrix` holding its
+    /// non-zero elements.
+    /// ```typescript
+    /// let a = sparse([[0, 1], [2, 0]]);
+    /// assert_eq(a.nnz, 2);
+    /// assert_eq(a[1], [2.0, 0.0]);
+    /// ```
+    #[rhai_fn(name = "sparse", return_raw)]
+    pub fn sparse_from_array(arr: Array) -> Result<SparseMatrix, Box<EvalAltResult>> {
+        Ok(SparseMatrix::from_dense(&crate::Matrix::from_array(&arr)?))
+    }
+
+    /// Converts a `Matrix` into a `SparseMatrix` holding its non-zero elements.
+    /// ```typescript
+    /// let a = sparse(matrix([[0, 1], [2, 0]]));
+    /// assert_eq(a.nnz, 2);
+    /// ```
+    #[rhai_fn(name = "sparse")]
+    pub fn sparse_from_matrix(m: crate::Matrix) -> SparseMatrix {
+        SparseMatrix::from_dense(&m)
+    }
+
+    /// Converts a `SparseMatrix` into a nested array of FLOATs, one array per row.
+    /// ```typescript
+    /// let a = sparse([1], [0], [7], 2, 2);
+    /// assert_eq(to_array(a), [[0.0, 0.0], [7.0, 0.0]]);
+    /// ```
+    #[rhai_fn(name = "to_array", pure)]
+    pub fn to_array(a: &mut SparseMatrix) -> Array {
+        a.to_dense().to_array()
+    }
+
+    /// Converts a `SparseMatrix` into a dense `Matrix`.
+    /// ```typescript
+    /// let a = sparse([0, 1], [0, 1], [1, 2]);
+    /// assert(full(a) == matrix([[1, 0], [0, 2]]));
+    /// ```
+    #[rhai_fn(name = "full", pure)]
+    pub fn full(a: &mut SparseMatrix) -> crate::Matrix {
+        a.to_dense()
+    }
+
+    /// Returns the stored elements of a `SparseMatrix` as a map of `i`, `j`, and `v` arrays,
+    /// which can be passed straight back to `sparse`.
+    /// ```typescript
+    /// let a = sparse([[0, 3], [4, 0]]);
+    /// let t = find(a);
+    /// assert_eq(t.i, [0, 1]);
+    /// assert_eq(t.j, [1, 0]);
+    /// assert_eq(t.v, [3.0, 4.0]);
+    /// ```
+    #[rhai_fn(name = "find", pure)]
+    pub fn find(a: &mut SparseMatrix) -> rhai::Map {
+        let (mut i, mut j, mut v) = (Array::new(), Array::new(), Array::new());
+        for (row, col, value) in a.triplets() {
+            i.push(Dynamic::from_int(row as INT));
+            j.push(Dynamic::from_int(col as INT));
+            v.push(Dynamic::from_float(value));
+        }
+        let mut result = rhai::Map::new();
+        result.insert("i".into(), Dynamic::from_array(i));
+        result.insert("j".into(), Dynamic::from_array(j));
+        result.insert("v".into(), Dynamic::from_array(v));
+        result
+    }
+
+    /// Returns the number of rows of a `SparseMatrix`.
+    /// ```typescript
+    /// let a = sparse([], [], [], 4, 5);
+    /// assert_eq(a.rows, 4);
+    /// ```
+    #[rhai_fn(get = "rows", pure)]
+    pub fn rows(a: &mut SparseMatrix) -> INT {
+        a.rows() as INT
+    }
+
+    /// Returns the number of columns of a `SparseMatrix`.
+    /// ```typescript
+    /// let a = sparse([], [], [], 4, 5);
+    /// assert_eq(a.cols, 5);
+    /// ```
+    #[rhai_fn(get = "cols", pure)]
+    pub fn cols(a: &mut SparseMatrix) -> INT {
+        a.cols() as INT
+    }
+
+    /// Returns the shape of a `SparseMatrix` as `[rows, columns]`.
+    /// ```typescript
+    /// let a = sparse([], [], [], 4, 5);
+    /// assert_eq(a.shape, [4, 5]);
+    /// ```
+    #[rhai_fn(get = "shape", pure)]
+    pub fn shape(a: &mut SparseMatrix) -> Array {
+        vec![
+            Dynamic::from_int(a.rows() as INT),
+            Dynamic::from_int(a.cols() as INT),
+        ]
+    }
+
+    /// Returns the size of a `SparseMatrix` as `[rows, columns]`.
+    /// ```typescript
+    /// let a = sparse([], [], [], 4, 5);
+    /// assert_eq(size(a), [4, 5]);
+    /// ```
+    #[rhai_fn(name = "size", pure)]
+    pub fn size(a: &mut SparseMatrix) -> Array {
+        shape(a)
+    }
+
+    /// Returns the number of stored (non-zero) elements of a `SparseMatrix`.
+    /// ```typescript
+    /// let a = sparse([0, 1], [1, 0], [1, 1], 1000, 1000);
+    /// assert_eq(a.nnz, 2);
+    /// ```
+    #[rhai_fn(get = "nnz", name = "nnz", pure)]
+    pub fn nnz(a: &mut SparseMatrix) -> INT {
+        a.nnz() as INT
+    }
+
+    /// Returns a row of a `SparseMatrix` as a dense array, so that elements can be read with
+    /// `a[i][j]`. Negative indices count from the end.
+    /// ```typescript
+    /// let a = sparse([0, 1], [1, 0], [3, 4]);
+    /// assert_eq(a[0], [0.0, 3.0]);
+    /// assert_eq(a[-1][0], 4.0);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get_row(a: &mut SparseMatrix, i: INT) -> Result<Array, Box<EvalAltResult>> {
+        let idx = if i < 0 { i + a.rows() as INT } else { i };
+        if idx < 0 || idx >= a.rows() as INT {
+            return Err(
+                EvalAltResult::ErrorIndexNotFound(Dynamic::from_int(i), Position::NONE).into(),
+            );
+        }
+        Ok((0..a.cols())
+            .map(|j| Dynamic::from_float(a.get(idx as usize, j)))
+            .collect())
+    }
+
+    /// Returns the transpose of a `SparseMatrix`, which can also be written as `a.t()`.
+    /// ```typescript
+    /// let a = sparse([0, 0], [1, 2], [5, 6], 2, 3);
+    /// let t = transpose(a);
+    /// assert_eq(t.shape, [3, 2]);
+    /// assert_eq(to_array(t), [[0.0, 0.0], [5.0, 0.0], [6.0, 0.0]]);
+    /// assert_eq(a.t().shape, [3, 2]);
+    /// ```
+    #[rhai_fn(name = "transpose", name = "t", pure)]
+    pub fn transpose(a: &mut SparseMatrix) -> SparseMatrix {
+        a.transpose()
+    }
+
+    /// Multiplies a `SparseMatrix` by a dense vector, returning a dense vector.
+    /// ```typescript
+    /// let a = sparse([0, 1, 1], [0, 0, 1], [2, 1, 3]);
+    /// assert_eq(mtimes(a, [1, 2]), [2.0, 7.0]);
+    /// assert_eq(a * [1, 2], [2.0, 7.0]);
+    /// ```
+    #[rhai_fn(name = "mtimes", name = "*", return_raw, pure)]
+    pub fn mtimes(a: &mut SparseMatrix, x: Array) -> Result<Array, Box<EvalAltResult>> {
+        let x = super::sparse_operand(a, &x)?;
+        Ok(a.matvec(&x).into_iter().map(Dynamic::from_float).collect())
+    }
+
+    /// Solves the square sparse system `a * x = b` for `x`. Symmetric matrices are solved with
+    /// the Jacobi-preconditioned conjugate gradient method, which assumes the matrix is also
+    /// positive definite, and other matrices with BiCGSTAB. Iteration stops once the residual is
+    /// within a relative tolerance of `1e-10`.
+    /// ```typescript,only_f64
+    /// let a = sparse([0, 0, 1, 1, 1, 2, 2], [0, 1, 0, 1, 2, 1, 2], [4, -1, -1, 4, -1, -1, 4]);
+    /// let x = solve(a, [3, 2, 3]);
+    /// norm(x - [1.0, 1.0, 1.0]) < 1e-9
+    /// ```
+    /// ```typescript,only_f64
+    /// let a = sparse([0, 0, 1, 2, 2], [0, 2, 1, 0, 2], [2, 1, 3, -1, 2]);
+    /// let x = solve(a, [3, 3, 1]);
+    /// norm(mtimes(a, x) - [3, 3, 1]) < 1e-9
+    /// ```
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve(a: &mut SparseMatrix, b: Array) -> Result<Array, Box<EvalAltResult>> {
+        solve_with_tolerance(a, b, 1e-10, (10 * a.rows()).max(100) as INT)
+    }
+
+    /// Solves the square sparse system `a * x = b` for `x`, as with `solve(a, b)`, but stops once
+    /// the residual is within the relative tolerance `tol` or after `max_iter` iterations.
+    /// ```typescript
+    /// let a = sparse([0, 1], [0, 1], [2, 4]);
+    /// let x = solve(a, [1, 1], 1e-12, 10);
+    /// assert_eq(x, [0.5, 0.25]);
+    /// ```
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve_with_tolerance(
+        a: &mut SparseMatrix,
+        b: Array,
+        tol: FLOAT,
+        max_iter: INT,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if a.rows() != a.cols() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "Sparse systems must be square, not {} x {}",
+                    a.rows(),
+                    a.cols()
+                ),
+            )
+            .in_function("solve")
+            .into());
+        }
+        let b = super::sparse_operand(a, &b)?;
+        let max_iter = max_iter.max(0) as usize;
+        let progress: super::SolverProgress = &mut |_, _, _| Ok(true);
+        let x = if a.is_symmetric() {
+            super::conjugate_gradient(a, &b, tol, max_iter, progress)?
+        } else {
+            super::bicgstab(a, &b, tol, max_iter, progress)?
+        };
+        Ok(x.into_iter().map(Dynamic::from_float).collect())
+    }
+
+    /// Solves the square sparse system `a * x = b` for `x`, as with `solve(a, b)`, calling
+    /// `callback` after each iteration with an object map of the `iteration` number (starting
+    /// from 1), the current solution `x`, and the relative `residual`. The callback can print
+    /// progress, and returning `false` from it stops the solver early with the current solution.
+    /// ```typescript,only_f64
+    /// let a = sparse([0, 0, 1, 1, 1, 2, 2], [0, 1, 0, 1, 2, 1, 2], [4, -1, -1, 4, -1, -1, 4]);
+    /// let residuals = [];
+    /// let x = solve(a, [3, 2, 3], |state| residuals.push(state.residual));
+    /// assert(residuals.len > 0);
+    /// assert(residuals[-1] < 1e-10);
+    /// ```
+    /// ```typescript
+    /// let a = sparse([0, 0, 1, 2, 2], [0, 2, 1, 0, 2], [2, 1, 3, -1, 2]);
+    /// let steps = 0;
+    /// let x = solve(a, [3, 3, 1], |state| {
+    ///     steps = state.iteration;
+    ///     false
+    /// });
+    /// assert_eq(steps, 1);
+    /// ```
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve_with_callback(
+        context: NativeCallContext,
+        a: &mut SparseMatrix,
+        b: Array,
+        callback: FnPtr,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        let max_iter = (10 * a.rows()).max(100) as INT;
+        solve_with_tolerance_and_callback(context, a, b, 1e-10, max_iter, callback)
+    }
+
+    /// Solves the square sparse system `a * x = b` for `x`, as with `solve(a, b, tol, max_iter)`,
+    /// calling `callback` after each iteration as with `solve(a, b, callback)`.
+    /// ```typescript
+    /// let a = sparse([0, 1], [0, 1], [2, 4]);
+    /// let calls = 0;
+    /// let x = solve(a, [1, 1], 1e-12, 10, |state| { calls += 1; });
+    /// assert_eq(x, [0.5, 0.25]);
+    /// assert_eq(calls, 1);
+    /// ```
+    #[rhai_fn(name = "solve", return_raw, pure)]
+    pub fn solve_with_tolerance_and_callback(
+        context: NativeCallContext,
+        a: &mut SparseMatrix,
+        b: Array,
+        tol: FLOAT,
+        max_iter: INT,
+        callback: FnPtr,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if a.rows() != a.cols() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "Sparse systems must be square, not {} x {}",
+                    a.rows(),
+                    a.cols()
+                ),
+            )
+            .in_function("solve")
+            .into());
+        }
+        let b = super::sparse_operand(a, &b)?;
+        let max_iter = max_iter.max(0) as usize;
+        let progress: super::SolverProgress = &mut |iteration, x, residual| {
+            let mut state = Map::new();
+            state.insert("iteration".into(), Dynamic::from_int(iteration as INT));
+            state.insert(
+                "x".into(),
+                Dynamic::from_array(x.iter().copied().map(Dynamic::from_float).collect()),
+            );
+            state.insert("residual".into(), Dynamic::from_float(residual));
+            crate::keep_iterating(&context, &callback, state)
+        };
+        let x = if a.is_symmetric() {
+            super::conjugate_gradient(a, &b, tol, max_iter, progress)?
+        } else {
+            super::bicgstab(a, &b, tol, max_iter, progress)?
+        };
+        Ok(x.into_iter().map(Dynamic::from_float).collect())
+    }
+
+    /// Tests whether two sparse matrices have the same shape and elements.
+    #[rhai_fn(name = "==", pure)]
+    pub fn eq(a: &mut SparseMatrix, b: SparseMatrix) -> bool {
+        *a == b
+    }
+
+    /// Tests whether two sparse matrices differ in shape or elements.
+    #[rhai_fn(name = "!=", pure)]
+    pub fn ne(a: &mut SparseMatrix, b: SparseMatrix) -> bool {
+        *a != b
+    }
+
+    /// Formats a `SparseMatrix` as its shape followed by its stored elements.
+    /// ```typescript
+    /// let a = sparse([0, 1], [1, 0], [3, 4]);
+    /// assert_eq(to_string(a), "SparseMatrix(2 x 2, [(0, 1): 3.0, (1, 0): 4.0])");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(a: &mut SparseMatrix) -> ImmutableString {
+        a.to_string().into()
+    }
+}
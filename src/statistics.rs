@@ -1,14 +1,221 @@
 use rhai::plugin::*;
 
+/// The distributions that `fitdist` can fit.
+const FIT_DISTRIBUTIONS: [&str; 4] = ["normal", "exponential", "lognormal", "weibull"];
+
+/// Finds the maximum likelihood shape and scale of a Weibull distribution for positive data, or
+/// `None` if the data are all the same. The shape solves the likelihood equation by Newton's
+/// method, falling back to bisection whenever a step leaves the bracket around the root.
+fn weibull_mle(x: &[rhai::FLOAT]) -> Option<(rhai::FLOAT, rhai::FLOAT)> {
+    type F = rhai::FLOAT;
+    let n = x.len() as F;
+    // Scaling by the largest value keeps the powers below from overflowing
+    let x_max = x.iter().copied().fold(0.0, F::max);
+    let logs = x.iter().map(|v| v.ln() - x_max.ln()).collect::<Vec<_>>();
+    let mean_log = crate::stable_sum_slice(&logs) / n;
+    let spread = (logs.iter().map(|l| (l - mean_log).powi(2)).sum::<F>() / n).sqrt();
+    if spread == 0.0 {
+        return None;
+    }
+    // The likelihood equation, g(k) = 0, and g'(k), which is positive
+    let g = |k: F| {
+        let (mut s0, mut s1, mut s2) = (0.0, 0.0, 0.0);
+        for l in &logs {
+            let w = (k * l).exp();
+            s0 += w;
+            s1 += w * l;
+            s2 += w * l * l;
+        }
+        let value = s1 / s0 - 1.0 / k - mean_log;
+        let slope = (s2 * s0 - s1 * s1) / (s0 * s0) + 1.0 / (k * k);
+        (value, slope)
+    };
+    let (mut lo, mut hi) = (0.0, F::INFINITY);
+    let mut k = 1.2825 / spread;
+    for _ in 0..200 {
+        let (value, slope) = g(k);
+        if value < 0.0 {
+            lo = k;
+        } else {
+            hi = k;
+        }
+        let mut next = k - value / slope;
+        if !(next > lo && next < hi) {
+            next = if hi.is_finite() {
+                (lo + hi) / 2.0
+            } else {
+                2.0 * k
+            };
+        }
+        let converged = (next - k).abs() <= 1e-12 * k;
+        k = next;
+        if converged {
+            break;
+        }
+    }
+    let mean_power = logs.iter().map(|l| (k * l).exp()).sum::<F>() / n;
+    Some((k, x_max * mean_power.powf(1.0 / k)))
+}
+
+/// Returns the quantile of the standard normal distribution at probability `p`, using Acklam's
+/// rational approximation refined by a step of Halley's method.
+fn normal_quantile(p: rhai::FLOAT) -> rhai::FLOAT {
+    type F = rhai::FLOAT;
+    const A: [F; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [F; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [F; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [F; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    if p <= 0.0 {
+        return F::NEG_INFINITY;
+    } else if p >= 1.0 {
+        return F::INFINITY;
+    }
+    let tail = |q: F| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    let x = if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    };
+    let sqrt_2: F = std::f64::consts::SQRT_2 as F;
+    let sqrt_2pi: F = (2.0 * std::f64::consts::PI).sqrt() as F;
+    let e = 0.5 * crate::complementary_error_function(-x / sqrt_2) - p;
+    let u = e * sqrt_2pi * (x * x / 2.0).exp();
+    x - u / (1.0 + x * u / 2.0)
+}
+
+/// Reads the distribution and parameters for `qqpoints`: either the name of a distribution, with
+/// standard parameters, or a map returned by `fitdist`. Returns the quantile function.
+fn quantile_function(
+    dist: &rhai::Dynamic,
+) -> Result<Box<dyn Fn(rhai::FLOAT) -> rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+            .in_function("qqpoints")
+            .with_argument("dist")
+            .into()
+    };
+    let (name, fit) = match (
+        dist.read_lock::<rhai::ImmutableString>(),
+        dist.read_lock::<rhai::Map>(),
+    ) {
+        (Some(name), _) => (name.to_string(), None),
+        (_, Some(fit)) => {
+            let name = fit
+                .get("distribution")
+                .and_then(|d| d.clone().into_string().ok())
+                .ok_or_else(|| error("The fit has no distribution".to_string()))?;
+            (name, Some(fit.clone()))
+        }
+        _ => {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!(
+                    "Expected the name of a distribution or a fit from fitdist, not {}",
+                    dist.type_name()
+                ),
+            )
+            .in_function("qqpoints")
+            .with_argument("dist")
+            .into())
+        }
+    };
+    let parameter = |key: &str, default: rhai::FLOAT| match &fit {
+        None => Ok(default),
+        Some(fit) => fit
+            .get(key)
+            .and_then(|v| {
+                v.as_float()
+                    .ok()
+                    .or_else(|| v.as_int().ok().map(|i| i as rhai::FLOAT))
+            })
+            .ok_or_else(|| error(format!("The {name} fit has no number '{key}'"))),
+    };
+    Ok(match name.as_str() {
+        "normal" => {
+            let (mu, sigma) = (parameter("mu", 0.0)?, parameter("sigma", 1.0)?);
+            Box::new(move |p| mu + sigma * normal_quantile(p))
+        }
+        "lognormal" => {
+            let (mu, sigma) = (parameter("mu", 0.0)?, parameter("sigma", 1.0)?);
+            Box::new(move |p| (mu + sigma * normal_quantile(p)).exp())
+        }
+        "exponential" => {
+            let mu = parameter("mu", 1.0)?;
+            Box::new(move |p| -mu * (-p).ln_1p())
+        }
+        "weibull" => {
+            let (scale, shape) = (parameter("scale", 1.0)?, parameter("shape", 1.0)?);
+            Box::new(move |p| scale * (-(-p).ln_1p()).powf(1.0 / shape))
+        }
+        _ => {
+            return Err(error(format!(
+                "Unknown distribution '{name}', expected one of {FIT_DISTRIBUTIONS:?}"
+            )))
+        }
+    })
+}
+
+/// Reads a 1-D array of numbers for `ecdf` and `qqpoints`, sorted and without `NaN`s.
+fn sorted_data(
+    arr: &mut rhai::Array,
+    function: &str,
+) -> Result<Vec<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+    let mut x = crate::if_list_convert_to_vec_float_and_do(arr, Ok)?;
+    x.retain(|v| !v.is_nan());
+    if x.is_empty() {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::EmptyInput,
+            "There are no values other than NaN",
+        )
+        .in_function(function)
+        .into());
+    }
+    x.sort_by(|a, b| a.total_cmp(b));
+    Ok(x)
+}
+
 #[export_module]
 pub mod stats {
     use crate::{
-        array_to_vec_float, array_to_vec_int, if_list_convert_to_vec_float_and_do, if_list_do,
-        if_list_do_int_or_do_float,
+        argsort_floats, array_to_vec_float, array_to_vec_int, fit_int,
+        if_int_convert_to_float_and_do, if_list_convert_to_vec_float_and_do, if_list_do,
+        if_list_do_int_or_do_float, stable_sum, stable_sum_slice, welford, wide_int_prod,
+        wide_int_sum, IntOverflow,
     };
-    #[cfg(feature = "nalgebra")]
-    use rhai::Map;
-    use rhai::{Array, Dynamic, EvalAltResult, Position, FLOAT, INT};
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT, INT};
 
     #[cfg(feature = "nalgebra")]
     use std::collections::BTreeMap;
@@ -29,8 +236,8 @@ pub mod stats {
         array_max(&mut vec![a, b])
     }
 
-    /// Return the highest value from an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// Return the highest value from an array. Fails if the array is empty, or if it has elements
+    /// other than INT or FLOAT. NaNs are skipped, unless every element is NaN.
     /// ```typescript
     /// let the_highest_number = max([2, 3, 4, 5]);
     /// assert_eq(the_highest_number, 5);
@@ -39,19 +246,33 @@ pub mod stats {
     /// let the_highest_number = max([2, 3.0, 4.12, 5]);
     /// assert_eq(the_highest_number, 5.0);
     /// ```
+    /// ```typescript
+    /// let kind = "";
+    /// try { max([]); } catch (err) { kind = err.kind; }
+    /// assert_eq(kind, "empty_input");
+    /// ```
     #[rhai_fn(name = "max", return_raw)]
     pub fn array_max(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Cannot take the maximum of an empty array",
+            )
+            .in_function("max")
+            .into());
+        }
         if_list_do_int_or_do_float(
             arr,
             |arr: &mut Array| {
-                let mut y = array_to_vec_int(arr);
-                y.sort();
-                Ok(Dynamic::from(y[y.len() - 1]))
+                let y = array_to_vec_int(arr);
+                Ok(Dynamic::from_int(y.into_iter().max().unwrap()))
             },
             |arr: &mut Array| {
-                let mut y = array_to_vec_float(arr);
-                y.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                Ok(Dynamic::from(y[y.len() - 1]))
+                // NaN is only the result if every element is NaN
+                let y = array_to_vec_float(arr);
+                Ok(Dynamic::from_float(
+                    y.into_iter().fold(FLOAT::NAN, FLOAT::max),
+                ))
             },
         )
     }
@@ -72,8 +293,8 @@ pub mod stats {
         array_min(&mut vec![a, b])
     }
 
-    /// Return the lowest value from an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// Return the lowest value from an array. Fails if the array is empty, or if it has elements
+    /// other than INT or FLOAT. NaNs are skipped, unless every element is NaN.
     ///
     /// ```typescript
     /// let the_lowest_number = min([2, 3, 4, 5]);
@@ -85,17 +306,26 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "min", return_raw, pure)]
     pub fn array_min(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Cannot take the minimum of an empty array",
+            )
+            .in_function("min")
+            .into());
+        }
         if_list_do_int_or_do_float(
             arr,
             |arr: &mut Array| {
-                let mut y = array_to_vec_int(arr);
-                y.sort();
-                Ok(Dynamic::from(y[0]))
+                let y = array_to_vec_int(arr);
+                Ok(Dynamic::from_int(y.into_iter().min().unwrap()))
             },
             |arr: &mut Array| {
-                let mut y = array_to_vec_float(arr);
-                y.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                Ok(Dynamic::from(y[0]))
+                // NaN is only the result if every element is NaN
+                let y = array_to_vec_float(arr);
+                Ok(Dynamic::from_float(
+                    y.into_iter().fold(FLOAT::NAN, FLOAT::min),
+                ))
             },
         )
     }
@@ -116,6 +346,78 @@ pub mod stats {
         }
     }
 
+    /// Selects the `k` highest (or lowest) values of a vector along with their indices, sorted in
+    /// ascending order of value. Uses an O(n) selection rather than a full sort.
+    fn select_k<T, F>(
+        y: Vec<T>,
+        k: INT,
+        highest: bool,
+        cmp: F,
+    ) -> Result<Vec<(T, usize)>, Box<EvalAltResult>>
+    where
+        F: Fn(&T, &T) -> std::cmp::Ordering,
+    {
+        if k < 0 || k as usize > y.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "k must be between 0 and the length of the array ({})",
+                    y.len()
+                ),
+            )
+            .into());
+        }
+
+        let k = k as usize;
+        let n = y.len();
+        let mut pairs = y
+            .into_iter()
+            .enumerate()
+            .map(|(idx, v)| (v, idx))
+            .collect::<Vec<(T, usize)>>();
+        let mut selected = if k == 0 {
+            vec![]
+        } else if highest {
+            pairs.select_nth_unstable_by(n - k, |a, b| cmp(&a.0, &b.0));
+            pairs.split_off(n - k)
+        } else {
+            pairs.select_nth_unstable_by(k - 1, |a, b| cmp(&a.0, &b.0));
+            pairs.truncate(k);
+            pairs
+        };
+        selected.sort_by(|a, b| cmp(&a.0, &b.0).then(a.1.cmp(&b.1)));
+        Ok(selected)
+    }
+
+    /// Returns the `k` highest or lowest values from an array, and their indices.
+    fn extreme_k(
+        arr: &mut Array,
+        k: INT,
+        highest: bool,
+    ) -> Result<(Array, Array), Box<EvalAltResult>> {
+        fn unzip<T, F>(selected: Vec<(T, usize)>, f: F) -> (Array, Array)
+        where
+            F: Fn(T) -> Dynamic,
+        {
+            selected
+                .into_iter()
+                .map(|(v, idx)| (f(v), Dynamic::from_int(idx as INT)))
+                .unzip()
+        }
+
+        if_list_do_int_or_do_float(
+            arr,
+            |arr: &mut Array| {
+                select_k(array_to_vec_int(arr), k, highest, |a, b| a.cmp(b))
+                    .map(|selected| unzip(selected, Dynamic::from_int))
+            },
+            |arr: &mut Array| {
+                select_k(array_to_vec_float(arr), k, highest, |a, b| a.total_cmp(b))
+                    .map(|selected| unzip(selected, Dynamic::from_float))
+            },
+        )
+    }
+
     /// Returns the `k` highest values from an array. Fails if the input is not an array, or if
     /// it is an array with elements other than INT or FLOAT.
     /// ```typescript
@@ -130,29 +432,35 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "maxk", return_raw, pure)]
     pub fn maxk(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        if_list_do_int_or_do_float(
-            arr,
-            |arr: &mut Array| {
-                let mut y = array_to_vec_int(arr);
-                y.sort();
-                let r = (y.len() - (k as usize))..(y.len());
-                let mut v = Array::new();
-                for idx in r {
-                    v.push(Dynamic::from(y[idx]));
-                }
-                Ok(v)
-            },
-            |arr: &mut Array| {
-                let mut y = array_to_vec_float(arr);
-                y.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                let r = (y.len() - (k as usize))..(y.len());
-                let mut v = Array::new();
-                for idx in r {
-                    v.push(Dynamic::from(y[idx]));
-                }
-                Ok(v)
-            },
-        )
+        extreme_k(arr, k, true).map(|(values, _)| values)
+    }
+
+    /// Returns the `k` highest values from an array. If `with_indices` is true, the result is a
+    /// pair of arrays `[values, indices]` where `indices` gives the position of each value in the
+    /// original array.
+    /// ```typescript
+    /// let data = [32, 15, -7, 10, 1000, 41, 42];
+    /// let mk = maxk(data, 3, true);
+    /// assert_eq(mk, [[41, 42, 1000], [5, 6, 4]]);
+    /// ```
+    /// ```typescript
+    /// let data = [32, 15, -7, 10, 1000, 41, 42];
+    /// let mk = maxk(data, 3, false);
+    /// assert_eq(mk, [41, 42, 1000]);
+    /// ```
+    #[rhai_fn(name = "maxk", return_raw, pure)]
+    pub fn maxk_with_indices(
+        arr: &mut Array,
+        k: INT,
+        with_indices: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        extreme_k(arr, k, true).map(|(values, indices)| {
+            if with_indices {
+                vec![Dynamic::from_array(values), Dynamic::from_array(indices)]
+            } else {
+                values
+            }
+        })
     }
 
     /// Return the `k` lowest values in an array. Fails if the input is not an array, or if
@@ -169,33 +477,37 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "mink", return_raw, pure)]
     pub fn mink(arr: &mut Array, k: INT) -> Result<Array, Box<EvalAltResult>> {
-        if_list_do_int_or_do_float(
-            arr,
-            |arr| {
-                let mut y = array_to_vec_int(arr);
-                y.sort();
-                let r = (0 as usize)..(k as usize);
-                let mut v = Array::new();
-                for idx in r {
-                    v.push(Dynamic::from(y[idx]));
-                }
-                Ok(v)
-            },
-            |arr| {
-                let mut y = array_to_vec_float(arr);
-                y.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                let r = (0 as usize)..(k as usize);
-                let mut v = Array::new();
-                for idx in r {
-                    v.push(Dynamic::from(y[idx]));
-                }
-                Ok(v)
-            },
-        )
+        extreme_k(arr, k, false).map(|(values, _)| values)
     }
 
-    /// Sum an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// Return the `k` lowest values in an array. If `with_indices` is true, the result is a
+    /// pair of arrays `[values, indices]` where `indices` gives the position of each value in the
+    /// original array.
+    /// ```typescript
+    /// let data = [32, 15, -7, 10, 1000, 41, 42];
+    /// let mk = mink(data, 3, true);
+    /// assert_eq(mk, [[-7, 10, 15], [2, 3, 1]]);
+    /// ```
+    #[rhai_fn(name = "mink", return_raw, pure)]
+    pub fn mink_with_indices(
+        arr: &mut Array,
+        k: INT,
+        with_indices: bool,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        extreme_k(arr, k, false).map(|(values, indices)| {
+            if with_indices {
+                vec![Dynamic::from_array(values), Dynamic::from_array(indices)]
+            } else {
+                values
+            }
+        })
+    }
+
+    /// Sum an array. Fails if the input is not an array, if it is an array with elements other
+    /// than INT or FLOAT, or if the sum of INTs is too large for an INT. Use
+    /// `sum(arr, #{overflow: "float"})` to get such a sum as a FLOAT instead. FLOATs are added
+    /// with compensated (Kahan-Babuska) summation, so long arrays and values of very different
+    /// magnitudes do not lose precision.
     /// ```typescript
     /// let data = [1, 2, 3];
     /// let m = sum(data);
@@ -206,37 +518,79 @@ pub mod stats {
     /// let m = sum(data);
     /// assert_eq(m, 6.0);
     /// ```
+    /// ```typescript,only_f64
+    /// let data = [1e100, 1.0, -1e100];
+    /// let m = sum(data);
+    /// assert_eq(m, 1.0);
+    /// ```
+    /// ```typescript,only_i64
+    /// let kind = "";
+    /// try {
+    ///     sum([9223372036854775807, 1]);
+    /// } catch (err) {
+    ///     kind = err.kind;
+    /// }
+    /// assert_eq(kind, "numerical");
+    /// ```
     #[rhai_fn(name = "sum", return_raw, pure)]
     pub fn sum(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        sum_with_options(arr, Map::new())
+    }
+
+    /// Sum an array like `sum(arr)`, with options given as a map:
+    /// - `overflow`: what to do if the sum of INTs is too large for an INT, either `"error"` (the
+    ///   default) or `"float"` to return it as a FLOAT
+    ///
+    /// INTs are added exactly, so the sum only fails if the final result is too large, even if a
+    /// partial sum along the way would be.
+    /// ```typescript
+    /// let data = [9223372036854775807, 1];
+    /// let m = sum(data, #{overflow: "float"});
+    /// assert_eq(m, 9223372036854775808.0);
+    /// ```
+    /// ```typescript
+    /// let data = [9223372036854775807, 1, -2];
+    /// let m = sum(data, #{overflow: "error"});
+    /// assert_eq(m, 9223372036854775806);
+    /// ```
+    #[rhai_fn(name = "sum", return_raw, pure)]
+    pub fn sum_with_options(arr: &mut Array, options: Map) -> Result<Dynamic, Box<EvalAltResult>> {
+        let overflow = IntOverflow::from_options(&options, "sum")?;
         if_list_do_int_or_do_float(
             arr,
+            |arr| fit_int(Ok(wide_int_sum(arr)), overflow, "sum"),
             |arr| {
-                let y = array_to_vec_int(arr);
-                Ok(Dynamic::from_int(y.iter().sum()))
-            },
-            |arr| {
-                let y = array_to_vec_float(arr);
-                Ok(Dynamic::from_float(y.iter().sum()))
+                Ok(Dynamic::from_float(stable_sum_slice(&array_to_vec_float(
+                    arr,
+                ))))
             },
         )
     }
 
     /// Return the average of an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// it is an array with elements other than INT or FLOAT. The elements are added as by `sum`,
+    /// without losing precision.
     /// ```typescript
     /// let data = [1, 2, 3];
     /// let m = mean(data);
     /// assert_eq(m, 2.0);
     /// ```
+    /// ```typescript
+    /// let data = [0.1, 0.1, 0.1, 0.1, 0.1, 0.1, 0.1, 0.1, 0.1, 0.1];
+    /// let m = mean(data);
+    /// assert_eq(m, 0.1);
+    /// ```
     #[rhai_fn(name = "mean", return_raw, pure)]
     pub fn mean(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
         let l = arr.len() as FLOAT;
         if_list_do_int_or_do_float(
             arr,
+            |arr: &mut Array| Ok(Dynamic::from_float(wide_int_sum(arr) as FLOAT / l)),
             |arr: &mut Array| {
-                sum(arr).map(|s| Dynamic::from_float(s.as_int().unwrap() as FLOAT / l))
+                Ok(Dynamic::from_float(
+                    stable_sum_slice(&array_to_vec_float(arr)) / l,
+                ))
             },
-            |arr: &mut Array| sum(arr).map(|s| Dynamic::from_float(s.as_float().unwrap() / l)),
         )
     }
 
@@ -247,6 +601,11 @@ pub mod stats {
     /// let m = argmax(data);
     /// assert_eq(m, 2);
     /// ```
+    /// ```typescript
+    /// let data = [1, 3.5, 3];
+    /// let m = argmax(data);
+    /// assert_eq(m, 1);
+    /// ```
     #[rhai_fn(name = "argmax", return_raw, pure)]
     pub fn argmax(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
         if_list_do(arr, |arr| {
@@ -258,6 +617,7 @@ pub mod stats {
                 )
             })
         })
+        .map_err(crate::in_function("argmax", Some("arr")))
     }
 
     /// Return the index of the smallest array element. Fails if the input is not an array, or if
@@ -278,10 +638,12 @@ pub mod stats {
                 )
             })
         })
+        .map_err(crate::in_function("argmin", Some("arr")))
     }
 
-    /// Compute the product of an array. Fails if the input is not an array, or if
-    /// it is an array with elements other than INT or FLOAT.
+    /// Compute the product of an array. Fails if the input is not an array, if it is an array
+    /// with elements other than INT or FLOAT, or if the product of INTs is too large for an INT.
+    /// Use `prod(arr, #{overflow: "float"})` to get such a product as a FLOAT instead.
     /// ```typescript
     /// let data = [1, 2, 3];
     /// let m = prod(data);
@@ -294,14 +656,30 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "prod", return_raw, pure)]
     pub fn prod(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        prod_with_options(arr, Map::new())
+    }
+
+    /// Compute the product of an array like `prod(arr)`, with options given as a map:
+    /// - `overflow`: what to do if the product of INTs is too large for an INT, either `"error"`
+    ///   (the default) or `"float"` to return it as a FLOAT
+    /// ```typescript
+    /// let data = [4294967296, 4294967296];
+    /// let m = prod(data, #{overflow: "float"});
+    /// assert_eq(m, 18446744073709551616.0);
+    /// ```
+    /// ```typescript,only_i64
+    /// let data = [4294967296, 4294967296, 0];
+    /// let m = prod(data);
+    /// assert_eq(m, 0);
+    /// ```
+    #[rhai_fn(name = "prod", return_raw, pure)]
+    pub fn prod_with_options(arr: &mut Array, options: Map) -> Result<Dynamic, Box<EvalAltResult>> {
+        let overflow = IntOverflow::from_options(&options, "prod")?;
         if_list_do_int_or_do_float(
             arr,
             |arr| {
-                let mut p = 1 as INT;
-                for el in arr {
-                    p *= el.as_int().unwrap()
-                }
-                Ok(Dynamic::from_int(p))
+                let approx = || arr.iter().map(|el| el.as_int().unwrap() as FLOAT).product();
+                fit_int(wide_int_prod(arr).ok_or_else(approx), overflow, "prod")
             },
             |arr| {
                 let mut p = 1.0 as FLOAT;
@@ -313,25 +691,25 @@ pub mod stats {
         )
     }
 
-    /// Returns the variance of a 1-D array.
+    /// Returns the variance of a 1-D array. This is computed in a single pass with Welford's
+    /// algorithm, which stays accurate when the mean is large compared to the spread.
     /// ```typescript
     /// let data = [1, 2, 3];
     /// let v = variance(data);
     /// assert_eq(v, 1.0);
     /// ```
+    /// ```typescript,only_f64
+    /// let data = [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0];
+    /// let v = variance(data);
+    /// assert_eq(v, 30.0);
+    /// ```
     #[rhai_fn(name = "variance", return_raw, pure)]
     pub fn variance(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
-        let m = mean(arr).map(|med| med.as_float().unwrap())?;
-
         if_list_convert_to_vec_float_and_do(arr, |x| {
-            let mut sum = 0.0 as FLOAT;
-
-            for v in &x {
-                sum += (v - m).powi(2)
-            }
-            let d = sum / (x.len() as FLOAT - 1.0);
-            Ok(Dynamic::from_float(d))
+            let (_, m2) = welford(&x);
+            Ok(Dynamic::from_float(m2 / (x.len() as FLOAT - 1.0)))
         })
+        .map_err(crate::in_function("variance", Some("arr")))
     }
 
     /// Returns the standard deviation of a 1-D array.
@@ -354,13 +732,90 @@ pub mod stats {
     #[rhai_fn(name = "rms", return_raw, pure)]
     pub fn rms(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
         if_list_convert_to_vec_float_and_do(arr, |arr| {
-            let mut sum = 0.0 as FLOAT;
-            for v in &arr {
-                sum += v.powi(2)
-            }
-            let d = sum / (arr.len() as FLOAT);
+            let squares = crate::parallel_map_chunks(&arr, |chunk| {
+                stable_sum(chunk.iter().map(|v| v.powi(2)))
+            });
+            let d = stable_sum(squares) / (arr.len() as FLOAT);
             Ok(Dynamic::from_float(d.sqrt()))
         })
+        .map_err(crate::in_function("rms", Some("arr")))
+    }
+
+    /// Returns the mean of the differences between successive elements of a 1-D array. This is
+    /// equivalent to `mean(diff(arr))`, but is computed in a single pass without allocating the
+    /// intermediate array of differences.
+    /// ```typescript
+    /// let data = [1, 3, 6, 10];
+    /// let m = mean_of_diff(data);
+    /// assert_eq(m, 3.0);
+    /// ```
+    #[rhai_fn(name = "mean_of_diff", return_raw, pure)]
+    pub fn mean_of_diff(arr: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        if arr.len() < 2 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "The array must have at least 2 elements",
+            )
+            .in_function("mean_of_diff")
+            .into());
+        }
+
+        let mut total = 0.0 as FLOAT;
+        let mut previous = if_int_convert_to_float_and_do(arr[0].clone(), Ok)?;
+        for el in arr.iter().skip(1) {
+            let current = if_int_convert_to_float_and_do(el.clone(), Ok)?;
+            total += current - previous;
+            previous = current;
+        }
+        Ok(total / (arr.len() - 1) as FLOAT)
+    }
+
+    /// Returns the root-mean-square of a 1-D array after removing its best-fit linear trend. This
+    /// is computed in a single pass, without allocating the detrended array.
+    /// ```typescript,only_f64
+    /// let data = [1, 2, 3, 4, 5];
+    /// let r = rms_of_detrended(data);
+    /// assert(r < 1e-10);
+    /// ```
+    /// ```typescript
+    /// let data = [0, 1, 0, 1];
+    /// let r = rms_of_detrended(data);
+    /// assert_eq(r, 0.4472135954999579);
+    /// ```
+    #[rhai_fn(name = "rms_of_detrended", return_raw, pure)]
+    pub fn rms_of_detrended(arr: &mut Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Array must not be empty",
+            )
+            .in_function("rms_of_detrended")
+            .into());
+        }
+
+        // Running means and co-moments of the sample index (t) and the data (y)
+        let (mut mean_t, mut mean_y) = (0.0 as FLOAT, 0.0 as FLOAT);
+        let (mut m2_t, mut m2_y, mut c_ty) = (0.0 as FLOAT, 0.0 as FLOAT, 0.0 as FLOAT);
+        for (idx, el) in arr.iter().enumerate() {
+            let y = if_int_convert_to_float_and_do(el.clone(), Ok)?;
+            let t = idx as FLOAT;
+            let n = (idx + 1) as FLOAT;
+            let dt = t - mean_t;
+            let dy = y - mean_y;
+            mean_t += dt / n;
+            mean_y += dy / n;
+            m2_t += dt * (t - mean_t);
+            m2_y += dy * (y - mean_y);
+            c_ty += dt * (y - mean_y);
+        }
+
+        // The residual sum of squares of the least-squares line
+        let ssr = if m2_t > 0.0 {
+            m2_y - c_ty * c_ty / m2_t
+        } else {
+            0.0
+        };
+        Ok((ssr.max(0.0) / arr.len() as FLOAT).sqrt())
     }
 
     /// Returns the variance of a 1-D array.
@@ -371,8 +826,16 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "median", return_raw, pure)]
     pub fn median(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Array must not be empty",
+            )
+            .in_function("median")
+            .into());
+        }
         if_list_convert_to_vec_float_and_do(arr, |mut x| {
-            x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            x.sort_by(|a, b| a.total_cmp(b));
 
             let med = if x.len() % 2 == 1 {
                 x[(x.len() - 1) / 2]
@@ -426,17 +889,19 @@ pub mod stats {
     #[rhai_fn(name = "prctile", return_raw, pure)]
     pub fn prctile(arr: &mut Array, p: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
         if arr.is_empty() {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "Array must not be empty".to_string(),
-                Position::NONE,
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Array must not be empty",
             )
+            .in_function("prctile")
             .into());
         }
         if !p.is_float() && !p.is_int() {
-            return Err(EvalAltResult::ErrorArithmetic(
-                "Percentile value must either be INT or FLOAT".to_string(),
-                Position::NONE,
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "Percentile value must either be INT or FLOAT",
             )
+            .in_function("prctile")
             .into());
         }
 
@@ -448,18 +913,17 @@ pub mod stats {
             }
 
             // Sort
-            float_array.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            float_array.sort_by(|a, b| a.total_cmp(b));
 
             let sorted_array = float_array
                 .iter()
                 .map(|el| Dynamic::from_float(*el))
                 .collect::<Array>();
 
-            let mut x = crate::matrix_functions::linspace(
-                Dynamic::from_int(0),
-                Dynamic::from_int(100),
-                float_array.len() as INT,
-            )?;
+            let mut x = crate::evenly_spaced(0.0, 100.0, float_array.len())
+                .into_iter()
+                .map(Dynamic::from_float)
+                .collect::<Array>();
             crate::misc_functions::interp1(&mut x, sorted_array, p.clone())
         })
     }
@@ -483,6 +947,153 @@ pub mod stats {
         }
     }
 
+    /// Returns the permutation of indices that sorts a 1-D array in ascending order. Ties keep
+    /// their original relative order.
+    /// ```typescript
+    /// let data = [3, 1, 2];
+    /// let idx = argsort(data);
+    /// assert_eq(idx, [1, 2, 0]);
+    /// ```
+    /// ```typescript
+    /// let data = [0.5, -1.0, 0.5, 2.0];
+    /// let idx = argsort(data);
+    /// assert_eq(idx, [1, 0, 2, 3]);
+    /// ```
+    #[rhai_fn(name = "argsort", return_raw, pure)]
+    pub fn argsort(arr: &mut Array) -> Result<Array, Box<EvalAltResult>> {
+        if_list_convert_to_vec_float_and_do(arr, |x| {
+            Ok(argsort_floats(&x)
+                .into_iter()
+                .map(|idx| Dynamic::from_int(idx as INT))
+                .collect())
+        })
+        .map_err(crate::in_function("argsort", Some("arr")))
+    }
+
+    /// Returns the 1-based rank of each element in a 1-D array, assigning tied elements the
+    /// average of the ranks they span. Given a matrix (an array of arrays) instead, returns its
+    /// numerical rank: the number of linearly independent rows or columns, judged from its
+    /// singular values.
+    /// ```typescript
+    /// let data = [10, 20, 20, 30];
+    /// let r = rank(data);
+    /// assert_eq(r, [1.0, 2.5, 2.5, 4.0]);
+    /// ```
+    /// ```typescript
+    /// let m = [[1, 2, 3],
+    ///          [2, 4, 6],
+    ///          [1, 0, 1]];
+    /// assert_eq(rank(m), 2);
+    /// ```
+    /// ```typescript
+    /// assert_eq(rank([[1, 2.0], [3, 4]]), 2);
+    /// ```
+    #[rhai_fn(name = "rank", return_raw, pure)]
+    pub fn rank(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        #[cfg(feature = "nalgebra")]
+        if arr.first().is_some_and(|x| x.is_array()) {
+            return crate::matrix_rank(arr).map(Dynamic::from_int);
+        }
+        rank_with_ties(arr, "average".into()).map(Dynamic::from_array)
+    }
+
+    /// Returns the 1-based rank of each element in a 1-D array, using the given method for ties.
+    /// The method can be `"average"` (FLOAT ranks), `"min"`, or `"max"` (INT ranks).
+    /// ```typescript
+    /// let data = [10, 20, 20, 30];
+    /// let r = rank(data, "min");
+    /// assert_eq(r, [1, 2, 2, 4]);
+    /// ```
+    /// ```typescript
+    /// let data = [10, 20, 20, 30];
+    /// let r = rank(data, "max");
+    /// assert_eq(r, [1, 3, 3, 4]);
+    /// ```
+    #[rhai_fn(name = "rank", return_raw, pure)]
+    pub fn rank_with_ties(
+        arr: &mut Array,
+        method: ImmutableString,
+    ) -> Result<Array, Box<EvalAltResult>> {
+        if !["average", "min", "max"].contains(&method.as_str()) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Unknown tie-handling method '{method}', expected 'average', 'min', or 'max'"
+                ),
+            )
+            .in_function("rank")
+            .with_argument("method")
+            .into());
+        }
+
+        if_list_convert_to_vec_float_and_do(arr, |x| {
+            let order = argsort_floats(&x);
+            let mut ranks = vec![Dynamic::UNIT; x.len()];
+            let mut start = 0;
+            while start < order.len() {
+                // Find the run of tied values beginning at this position
+                let mut end = start;
+                while end + 1 < order.len() && x[order[end + 1]] == x[order[start]] {
+                    end += 1;
+                }
+                let r = match method.as_str() {
+                    "average" => Dynamic::from_float((start + end + 2) as FLOAT / 2.0),
+                    "min" => Dynamic::from_int((start + 1) as INT),
+                    _ => Dynamic::from_int((end + 1) as INT),
+                };
+                for idx in &order[start..=end] {
+                    ranks[*idx] = r.clone();
+                }
+                start = end + 1;
+            }
+            Ok(ranks)
+        })
+    }
+
+    /// Returns the percentile rank of a value relative to a 1-D array of data, i.e. the percentage
+    /// of the data that falls below it, counting values equal to it as half.
+    /// ```typescript
+    /// let data = [1, 2, 3, 4];
+    /// let p = prctile_rank(data, 3);
+    /// assert_eq(p, 62.5);
+    /// ```
+    /// ```typescript
+    /// let data = [1, 2, 3, 4];
+    /// let p = prctile_rank(data, 10.0);
+    /// assert_eq(p, 100.0);
+    /// ```
+    /// ```typescript
+    /// let err = #{};
+    /// try {
+    ///     prctile_rank([1, 2, 3], "high");
+    /// } catch (error) {
+    ///     err = error;
+    /// }
+    /// assert_eq(err.kind, "type_mismatch");
+    /// assert_eq(err.function, "prctile_rank");
+    /// assert_eq(err.argument, "v");
+    /// ```
+    #[rhai_fn(name = "prctile_rank", return_raw, pure)]
+    pub fn prctile_rank(arr: &mut Array, v: Dynamic) -> Result<FLOAT, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Array must not be empty",
+            )
+            .in_function("prctile_rank")
+            .into());
+        }
+
+        let v = if_int_convert_to_float_and_do(v, Ok)
+            .map_err(crate::in_function("prctile_rank", Some("v")))?;
+        if_list_convert_to_vec_float_and_do(arr, |x| {
+            let below = x.iter().filter(|el| **el < v).count() as FLOAT;
+            let equal = x.iter().filter(|el| **el == v).count() as FLOAT;
+            Ok(100.0 * (below + 0.5 * equal) / x.len() as FLOAT)
+        })
+        .map_err(crate::in_function("prctile_rank", Some("arr")))
+    }
+
     /// Returns the mode of a 1-D array.
     /// ```typescript
     /// let data = [1, 2, 2, 2, 2, 3];
@@ -496,6 +1107,14 @@ pub mod stats {
     /// ```
     #[rhai_fn(name = "mode", return_raw, pure)]
     pub fn mode(arr: &mut Array) -> Result<Dynamic, Box<EvalAltResult>> {
+        if arr.is_empty() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::EmptyInput,
+                "Array must not be empty",
+            )
+            .in_function("mode")
+            .into());
+        }
         if_list_do_int_or_do_float(
             arr,
             |arr| {
@@ -542,12 +1161,13 @@ pub mod stats {
     ///          [0.8],
     ///          [2.1]];
     /// let b = regress(x, y);
-    /// assert_eq(b,  #{"parameters": [5.551115123125783e-16, 1.0000000000000002],
-    ///                 "pvalues": [1.0, 0.1091825535092476],
-    ///                 "standard_errors": [0.1118033988749896, 0.17320508075688787]});
+    /// assert_approx_eq(b.parameters, [0.0, 1.0], 0.0, 1e-6);
+    /// assert_approx_eq(b.pvalues, [1.0, 0.1091825535092476], 0.0, 1e-6);
+    /// assert_approx_eq(b.standard_errors, [0.1118033988749895, 0.1732050807568877], 0.0, 1e-6);
     /// ```
     #[cfg(feature = "nalgebra")]
     #[rhai_fn(name = "regress", return_raw, pure)]
+    #[allow(clippy::unnecessary_cast)] // FLOAT may be f32
     pub fn regress(x: &mut Array, y: Array) -> Result<Map, Box<EvalAltResult>> {
         use linregress::{FormulaRegressionBuilder, RegressionDataBuilder};
         let x_transposed = crate::matrix_functions::transpose(x)?;
@@ -558,38 +1178,57 @@ pub mod stats {
             vars.push(var_name.clone());
             data.push((
                 var_name,
-                array_to_vec_float(&mut column.clone().into_array().unwrap()),
+                if_list_convert_to_vec_float_and_do(&mut column.clone().into_array()?, Ok)?
+                    .into_iter()
+                    .map(|v| v as f64)
+                    .collect(),
             ));
         }
         data.push((
             "y".to_string(),
-            array_to_vec_float(&mut crate::matrix_functions::flatten(&mut y.clone())),
+            if_list_convert_to_vec_float_and_do(
+                &mut crate::matrix_functions::flatten(&mut y.clone()),
+                Ok,
+            )?
+            .into_iter()
+            .map(|v| v as f64)
+            .collect(),
         ));
 
-        let regress_data = RegressionDataBuilder::new().build_from(data).unwrap();
+        let regress_data = RegressionDataBuilder::new().build_from(data).map_err(|e| {
+            rhai::EvalAltResult::from(
+                crate::LabError::new(crate::LabErrorKind::DimensionMismatch, e.to_string())
+                    .in_function("regress"),
+            )
+        })?;
 
         let model = FormulaRegressionBuilder::new()
             .data(&regress_data)
             .data_columns("y", vars)
             .fit()
-            .map_err(|e| EvalAltResult::ErrorArithmetic(e.to_string(), Position::NONE))?;
+            .map_err(|e| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(crate::LabErrorKind::Numerical, e.to_string())
+                        .in_function("regress"),
+                )
+            })?;
 
         let parameters = Dynamic::from_array(
             model
                 .iter_parameter_pairs()
-                .map(|x| Dynamic::from_float(x.1))
+                .map(|x| Dynamic::from_float(x.1 as FLOAT))
                 .collect::<Array>(),
         );
         let pvalues = Dynamic::from_array(
             model
                 .iter_p_value_pairs()
-                .map(|x| Dynamic::from_float(x.1))
+                .map(|x| Dynamic::from_float(x.1 as FLOAT))
                 .collect::<Array>(),
         );
         let standard_errors = Dynamic::from_array(
             model
                 .iter_se_pairs()
-                .map(|x| Dynamic::from_float(x.1))
+                .map(|x| Dynamic::from_float(x.1 as FLOAT))
                 .collect::<Array>(),
         );
 
@@ -605,4 +1244,227 @@ pub mod stats {
         result.insert(se, standard_errors);
         Ok(result)
     }
+
+    /// Fits a distribution to a 1-D array of data by maximum likelihood, returning a map with
+    /// the `distribution`, its fitted parameters, the number of data points `n`, the
+    /// log-likelihood `loglik` of the data under the fit, and the Akaike information criterion
+    /// `aic` (lower is better when comparing fits to the same data). The distributions and their
+    /// parameters are:
+    /// - `"normal"`: the mean `mu` and standard deviation `sigma`, which is the maximum
+    ///   likelihood estimate that divides by `n` rather than `n - 1`
+    /// - `"exponential"`: the mean `mu`, which is the reciprocal of the rate
+    /// - `"lognormal"`: the mean `mu` and standard deviation `sigma` of the logarithm of the data
+    /// - `"weibull"`: the `scale` and `shape`
+    ///
+    /// The data must be finite, at least zero for `"exponential"`, and positive for
+    /// `"lognormal"` and `"weibull"`.
+    /// ```typescript
+    /// let fit = fitdist([1.0, 2.0, 3.0, 4.0, 5.0], "normal");
+    /// assert_eq(fit.mu, 3.0);
+    /// assert_eq(fit.sigma, sqrt(2.0));
+    /// assert_approx_eq(fit.loglik, -8.8275606, 1e-7, 0.0);
+    /// assert_approx_eq(fit.aic, 21.6551212, 1e-7, 0.0);
+    /// ```
+    /// ```typescript
+    /// let fit = fitdist([0.5, 1.5, 1.0], "exponential");
+    /// assert_eq(fit.mu, 1.0);
+    /// assert_eq(fit.loglik, -3.0);
+    /// ```
+    /// ```typescript
+    /// let fit = fitdist([1.0, e, e * e], "lognormal");
+    /// assert_approx_eq(fit.mu, 1.0);
+    /// assert_approx_eq(fit.sigma, sqrt(2.0 / 3.0));
+    /// ```
+    /// ```typescript
+    /// // Compare fits to data that follow a Weibull distribution
+    /// let data = [0.41, 0.73, 0.92, 1.05, 1.21, 1.33, 1.52, 1.68, 1.9, 2.37];
+    /// let weibull = fitdist(data, "weibull");
+    /// assert(weibull.shape > 2.0 && weibull.shape < 3.0);
+    /// assert(weibull.aic < fitdist(data, "exponential").aic);
+    /// ```
+    #[rhai_fn(name = "fitdist", return_raw, pure)]
+    pub fn fitdist(
+        arr: &mut Array,
+        distribution: ImmutableString,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let error = |kind: crate::LabErrorKind, msg: String| -> Box<EvalAltResult> {
+            crate::LabError::new(kind, msg)
+                .in_function("fitdist")
+                .into()
+        };
+        if !super::FIT_DISTRIBUTIONS.contains(&distribution.as_str()) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Unknown distribution '{distribution}', expected one of {:?}",
+                    super::FIT_DISTRIBUTIONS
+                ),
+            )
+            .in_function("fitdist")
+            .with_argument("distribution")
+            .into());
+        }
+        if arr.is_empty() {
+            return Err(error(
+                crate::LabErrorKind::EmptyInput,
+                "Cannot fit a distribution to an empty array".into(),
+            ));
+        }
+        let x = if_list_convert_to_vec_float_and_do(arr, Ok)?;
+        if x.iter().any(|v| !v.is_finite()) {
+            return Err(error(
+                crate::LabErrorKind::InvalidArgument,
+                "The data must be finite".into(),
+            ));
+        }
+        let least = x.iter().copied().fold(FLOAT::INFINITY, FLOAT::min);
+        match distribution.as_str() {
+            "exponential" if least < 0.0 => {
+                return Err(error(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!(
+                    "The data for an exponential fit cannot be negative, but one value is {least}"
+                ),
+                ))
+            }
+            "lognormal" | "weibull" if least <= 0.0 => {
+                return Err(error(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!(
+                    "The data for a {distribution} fit must be positive, but one value is {least}"
+                ),
+                ))
+            }
+            _ => {}
+        }
+
+        let n = x.len() as FLOAT;
+        let ln_2pi = (2.0 * std::f64::consts::PI).ln() as FLOAT;
+        let identical = || {
+            error(
+                crate::LabErrorKind::InvalidArgument,
+                format!("Cannot fit a {distribution} distribution to data that are all the same"),
+            )
+        };
+        let mut result = Map::new();
+        let (loglik, parameters) = match distribution.as_str() {
+            "normal" | "lognormal" => {
+                let is_log = distribution == "lognormal";
+                let y = if is_log {
+                    x.iter().map(|v| v.ln()).collect()
+                } else {
+                    x.clone()
+                };
+                let (mu, m2) = welford(&y);
+                let sigma = (m2 / n).sqrt();
+                if sigma == 0.0 {
+                    return Err(identical());
+                }
+                let log_sum = if is_log { stable_sum_slice(&y) } else { 0.0 };
+                let loglik = -n * sigma.ln() - n / 2.0 * ln_2pi - n / 2.0 - log_sum;
+                result.insert("mu".into(), Dynamic::from_float(mu));
+                result.insert("sigma".into(), Dynamic::from_float(sigma));
+                (loglik, 2.0)
+            }
+            "exponential" => {
+                let mu = stable_sum_slice(&x) / n;
+                if mu == 0.0 {
+                    return Err(identical());
+                }
+                result.insert("mu".into(), Dynamic::from_float(mu));
+                (-n * mu.ln() - n, 1.0)
+            }
+            _ => {
+                let (shape, scale) = super::weibull_mle(&x).ok_or_else(identical)?;
+                let loglik = stable_sum(x.iter().map(|v| {
+                    shape.ln() - shape * scale.ln() + (shape - 1.0) * v.ln()
+                        - (v / scale).powf(shape)
+                }));
+                result.insert("scale".into(), Dynamic::from_float(scale));
+                result.insert("shape".into(), Dynamic::from_float(shape));
+                (loglik, 2.0)
+            }
+        };
+        result.insert("distribution".into(), Dynamic::from(distribution));
+        result.insert("n".into(), Dynamic::from_int(x.len() as INT));
+        result.insert("loglik".into(), Dynamic::from_float(loglik));
+        result.insert(
+            "aic".into(),
+            Dynamic::from_float(2.0 * parameters - 2.0 * loglik),
+        );
+        Ok(result)
+    }
+
+    /// Returns the empirical cumulative distribution function of a 1-D array as a map of the
+    /// distinct values `x`, in ascending order, and the fraction `p` of the data that is at most
+    /// each of them. `NaN`s are ignored.
+    /// ```typescript
+    /// let f = ecdf([3, 1, 2, 2]);
+    /// assert_eq(f.x, [1.0, 2.0, 3.0]);
+    /// assert_eq(f.p, [0.25, 0.75, 1.0]);
+    /// ```
+    #[rhai_fn(name = "ecdf", return_raw, pure)]
+    pub fn ecdf(arr: &mut Array) -> Result<Map, Box<EvalAltResult>> {
+        let x = super::sorted_data(arr, "ecdf")?;
+        let n = x.len() as FLOAT;
+        let mut values = Array::new();
+        let mut probabilities = Array::new();
+        for (i, v) in x.iter().enumerate() {
+            if x.get(i + 1) != Some(v) {
+                values.push(Dynamic::from_float(*v));
+                probabilities.push(Dynamic::from_float((i + 1) as FLOAT / n));
+            }
+        }
+        let mut result = Map::new();
+        result.insert("x".into(), Dynamic::from_array(values));
+        result.insert("p".into(), Dynamic::from_array(probabilities));
+        Ok(result)
+    }
+
+    /// Returns the points of a quantile-quantile plot of a 1-D array against the standard normal
+    /// distribution, as described for `qqpoints(arr, dist)`.
+    /// ```typescript,only_f64
+    /// let q = qqpoints([-1.0, 0.0, 1.0]);
+    /// assert_eq(q.sample, [-1.0, 0.0, 1.0]);
+    /// assert_approx_eq(q.theoretical, [-0.9674215661017014, 0.0, 0.9674215661017014]);
+    /// ```
+    #[rhai_fn(name = "qqpoints", return_raw, pure)]
+    pub fn qqpoints_normal(arr: &mut Array) -> Result<Map, Box<EvalAltResult>> {
+        qqpoints(arr, Dynamic::from("normal"))
+    }
+
+    /// Returns the points of a quantile-quantile plot, for checking whether a 1-D array follows
+    /// a distribution, as a map of the `sample` values in ascending order and the `theoretical`
+    /// quantiles of the distribution at the same probabilities, `(i - 0.5) / n` for the `i`th
+    /// of `n` values. The points lie near a straight line when the distribution fits the data,
+    /// and near the line `y = x` when its parameters do too. `dist` is one of `"normal"`,
+    /// `"exponential"`, `"lognormal"`, or `"weibull"`, with standard parameters (a mean of 0 and
+    /// standard deviation of 1, a mean of 1, or a scale and shape of 1), or a map returned by
+    /// `fitdist`, with the fitted parameters. `NaN`s are ignored.
+    /// ```typescript
+    /// let q = qqpoints([1.0, 2.0, 3.0, 4.0], "exponential");
+    /// assert_eq(q.sample, [1.0, 2.0, 3.0, 4.0]);
+    /// assert_approx_eq(q.theoretical[0], -ln(1.0 - 0.125));
+    /// ```
+    /// ```typescript
+    /// let data = [4.1, 5.2, 3.9, 6.0, 5.1, 4.6, 5.5, 4.9];
+    /// let q = qqpoints(data, fitdist(data, "normal"));
+    /// assert_approx_eq(q.sample, q.theoretical, 0.0, 0.3);
+    /// ```
+    #[rhai_fn(name = "qqpoints", return_raw, pure)]
+    pub fn qqpoints(arr: &mut Array, dist: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        let quantile = super::quantile_function(&dist)?;
+        let x = super::sorted_data(arr, "qqpoints")?;
+        let n = x.len() as FLOAT;
+        let theoretical = (0..x.len())
+            .map(|i| Dynamic::from_float(quantile((i as FLOAT + 0.5) / n)))
+            .collect::<Array>();
+        let mut result = Map::new();
+        result.insert(
+            "sample".into(),
+            Dynamic::from_array(x.into_iter().map(Dynamic::from_float).collect()),
+        );
+        result.insert("theoretical".into(), Dynamic::from_array(theoretical));
+        Ok(result)
+    }
 }
@@ -0,0 +1,166 @@
+use rhai::plugin::*;
+
+/// Evaluates the deflection of a prismatic beam at position `x`, positive in the direction of the
+/// load.
+fn deflection(
+    kind: &str,
+    load: rhai::FLOAT,
+    l: rhai::FLOAT,
+    ei: rhai::FLOAT,
+    x: rhai::FLOAT,
+) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>> {
+    if !(0.0..=l).contains(&x) {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("The position {x} is not on a beam of length {l}"),
+        )
+        .into());
+    }
+    match kind {
+        "simply_supported" => {
+            Ok(load * x * (l.powi(3) - 2.0 * l * x * x + x.powi(3)) / (24.0 * ei))
+        }
+        "simply_supported_point" => {
+            let x = x.min(l - x);
+            Ok(load * x * (3.0 * l * l - 4.0 * x * x) / (48.0 * ei))
+        }
+        "cantilever" => Ok(load * x * x * (6.0 * l * l - 4.0 * l * x + x * x) / (24.0 * ei)),
+        "cantilever_point" => Ok(load * x * x * (3.0 * l - x) / (6.0 * ei)),
+        "fixed_fixed" => Ok(load * x * x * (l - x).powi(2) / (24.0 * ei)),
+        _ => Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!(
+                "Unknown beam case '{kind}'. Use 'simply_supported', 'simply_supported_point', \
+                 'cantilever', 'cantilever_point', or 'fixed_fixed'"
+            ),
+        )
+        .into()),
+    }
+}
+
+#[export_module]
+pub mod structures_functions {
+    use crate::{array_to_points_2d, if_int_convert_to_float_and_do};
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT};
+
+    /// Computes the section properties of a simple polygon, given as an array of `[x, y]`
+    /// vertices in either winding order. Returns an object map with the `area`, the `centroid` as
+    /// `[x, y]`, and the second moments of area `ixx`, `iyy`, and product `ixy`, all taken about
+    /// axes through the centroid.
+    /// ```typescript
+    /// let props = section_props([[0, 0], [3, 0], [3, 2], [0, 2]]);
+    /// assert_eq(props.area, 6.0);
+    /// assert_eq(props.centroid, [1.5, 1.0]);
+    /// assert_eq([props.ixx, props.iyy, props.ixy], [2.0, 4.5, 0.0]);
+    /// ```
+    #[rhai_fn(name = "section_props", return_raw)]
+    pub fn section_props(polygon: Array) -> Result<Map, Box<EvalAltResult>> {
+        let points = array_to_points_2d(polygon)?;
+        if points.len() < 3 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "A section must have at least 3 vertices",
+            )
+            .in_function("section_props")
+            .into());
+        }
+
+        // Green's theorem sums over the edges, about the origin
+        let (mut a, mut sx, mut sy, mut ix, mut iy, mut ixy) = (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        for (idx, [x0, y0]) in points.iter().enumerate() {
+            let [x1, y1] = points[(idx + 1) % points.len()];
+            let cross = x0 * y1 - x1 * y0;
+            a += cross;
+            sx += (x0 + x1) * cross;
+            sy += (y0 + y1) * cross;
+            ix += (y0 * y0 + y0 * y1 + y1 * y1) * cross;
+            iy += (x0 * x0 + x0 * x1 + x1 * x1) * cross;
+            ixy += (x0 * y1 + 2.0 * x0 * y0 + 2.0 * x1 * y1 + x1 * y0) * cross;
+        }
+        if a == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The section has zero area",
+            )
+            .in_function("section_props")
+            .into());
+        }
+
+        // Clockwise polygons give negative sums, so flip them
+        let sign = a.signum();
+        let area = sign * a / 2.0;
+        let (cx, cy) = (sign * sx / (6.0 * area), sign * sy / (6.0 * area));
+
+        // Shift the second moments to the centroid with the parallel axis theorem
+        let mut result = Map::new();
+        result.insert("area".into(), Dynamic::from_float(area));
+        result.insert(
+            "centroid".into(),
+            Dynamic::from_array(vec![Dynamic::from_float(cx), Dynamic::from_float(cy)]),
+        );
+        result.insert(
+            "ixx".into(),
+            Dynamic::from_float(sign * ix / 12.0 - area * cy * cy),
+        );
+        result.insert(
+            "iyy".into(),
+            Dynamic::from_float(sign * iy / 12.0 - area * cx * cx),
+        );
+        result.insert(
+            "ixy".into(),
+            Dynamic::from_float(sign * ixy / 24.0 - area * cx * cy),
+        );
+        Ok(result)
+    }
+
+    /// Evaluates the deflection of a prismatic beam with the given `length`, elastic `modulus`, and
+    /// second moment of area `inertia` at position `x` (a number or an array), measured from the
+    /// left end (or from the fixed end of a cantilever). Deflection is positive in the direction
+    /// of the load. The supported cases are:
+    /// - `"simply_supported"`: uniformly distributed load `load` per unit length
+    /// - `"simply_supported_point"`: point load `load` at midspan
+    /// - `"cantilever"`: uniformly distributed load `load` per unit length
+    /// - `"cantilever_point"`: point load `load` at the free end
+    /// - `"fixed_fixed"`: uniformly distributed load `load` per unit length
+    /// ```typescript
+    /// let d = beam_deflection("simply_supported", 384.0, 1.0, 5.0, 1.0, 0.5);
+    /// assert_eq(d, 1.0);
+    /// ```
+    /// ```typescript
+    /// let d = beam_deflection("cantilever_point", 3.0, 2.0, 1.0, 1.0, [0.0, 2.0]);
+    /// assert_eq(d, [0.0, 8.0]);
+    /// ```
+    #[rhai_fn(name = "beam_deflection", return_raw)]
+    pub fn beam_deflection(
+        kind: ImmutableString,
+        load: FLOAT,
+        length: FLOAT,
+        modulus: FLOAT,
+        inertia: FLOAT,
+        x: Dynamic,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        if length <= 0.0 || modulus * inertia <= 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The length and flexural rigidity of the beam must be positive",
+            )
+            .in_function("beam_deflection")
+            .into());
+        }
+        if x.is_array() {
+            x.into_array()
+                .unwrap()
+                .into_iter()
+                .map(|xi| {
+                    let xi = if_int_convert_to_float_and_do(xi, Ok)?;
+                    super::deflection(&kind, load, length, modulus * inertia, xi)
+                        .map(Dynamic::from_float)
+                })
+                .collect::<Result<Array, _>>()
+                .map(Dynamic::from_array)
+        } else {
+            let x = if_int_convert_to_float_and_do(x, Ok)?;
+            super::deflection(&kind, load, length, modulus * inertia, x).map(Dynamic::from_float)
+        }
+    }
+}
@@ -0,0 +1,1067 @@
+use rhai::plugin::*;
+
+/// A column of a `Table`, holding values of a single type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    /// Integers
+    Int(Vec<rhai::INT>),
+    /// Floats, with `NaN` for missing values
+    Float(Vec<rhai::FLOAT>),
+    /// Booleans
+    Bool(Vec<bool>),
+    /// Strings
+    Text(Vec<rhai::ImmutableString>),
+}
+
+impl Column {
+    /// Creates a column from values of a single type. Integers mixed with floats become floats,
+    /// and `()` becomes `NaN` in a column of numbers.
+    pub fn from_values(values: Vec<rhai::Dynamic>) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let present = values.iter().filter(|x| !x.is_unit());
+        let column = if values.iter().all(|x| x.is_int()) {
+            Column::Int(values.iter().map(|x| x.as_int().unwrap()).collect())
+        } else if present.clone().all(|x| x.is_int() || x.is_float()) {
+            Column::Float(
+                values
+                    .iter()
+                    .map(|x| match (x.as_float(), x.as_int()) {
+                        (Ok(f), _) => f,
+                        (_, Ok(i)) => i as rhai::FLOAT,
+                        _ => rhai::FLOAT::NAN,
+                    })
+                    .collect(),
+            )
+        } else if values.iter().all(|x| x.is_bool()) {
+            Column::Bool(values.iter().map(|x| x.as_bool().unwrap()).collect())
+        } else if values.iter().all(|x| x.is_string()) {
+            Column::Text(
+                values
+                    .into_iter()
+                    .map(|x| x.into_immutable_string().unwrap())
+                    .collect(),
+            )
+        } else {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "A column must hold only numbers, only booleans, or only strings",
+            )
+            .into());
+        };
+        Ok(column)
+    }
+
+    /// Creates a column from text fields, choosing integers, floats, booleans, or strings by what
+    /// all of the fields can be read as. Empty fields and markers such as `NA` are missing values,
+    /// which are `NaN` in a column of numbers.
+    pub fn from_text(fields: &[String]) -> Self {
+        let is_missing =
+            |field: &str| matches!(field.trim(), "" | "NA" | "N/A" | "null" | "NULL" | "None");
+        let present = fields.iter().map(|f| f.trim()).filter(|f| !is_missing(f));
+        if fields.iter().all(|f| f.trim().parse::<rhai::INT>().is_ok()) {
+            Column::Int(fields.iter().map(|f| f.trim().parse().unwrap()).collect())
+        } else if present.clone().all(|f| f.parse::<rhai::FLOAT>().is_ok()) {
+            Column::Float(
+                fields
+                    .iter()
+                    .map(|f| f.trim().parse().unwrap_or(rhai::FLOAT::NAN))
+                    .collect(),
+            )
+        } else if fields.iter().all(|f| {
+            matches!(
+                f.trim(),
+                "true" | "false" | "TRUE" | "FALSE" | "True" | "False"
+            )
+        }) {
+            Column::Bool(
+                fields
+                    .iter()
+                    .map(|f| f.trim().eq_ignore_ascii_case("true"))
+                    .collect(),
+            )
+        } else {
+            Column::Text(fields.iter().map(|f| f.as_str().into()).collect())
+        }
+    }
+
+    /// The number of values in the column.
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Int(v) => v.len(),
+            Column::Float(v) => v.len(),
+            Column::Bool(v) => v.len(),
+            Column::Text(v) => v.len(),
+        }
+    }
+
+    /// Whether the column has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The name of the type of the column's values.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Column::Int(_) => "int",
+            Column::Float(_) => "float",
+            Column::Bool(_) => "bool",
+            Column::Text(_) => "string",
+        }
+    }
+
+    /// The value at row `i`.
+    pub fn get(&self, i: usize) -> rhai::Dynamic {
+        match self {
+            Column::Int(v) => rhai::Dynamic::from_int(v[i]),
+            Column::Float(v) => rhai::Dynamic::from_float(v[i]),
+            Column::Bool(v) => rhai::Dynamic::from_bool(v[i]),
+            Column::Text(v) => v[i].clone().into(),
+        }
+    }
+
+    /// The values of the column as an array.
+    pub fn to_array(&self) -> rhai::Array {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    /// The values of a column of numbers as floats, or `None` for other columns.
+    pub fn to_floats(&self) -> Option<Vec<rhai::FLOAT>> {
+        match self {
+            Column::Int(v) => Some(v.iter().map(|x| *x as rhai::FLOAT).collect()),
+            Column::Float(v) => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    /// A column holding the values at the given rows, in order.
+    pub fn take(&self, rows: &[usize]) -> Self {
+        match self {
+            Column::Int(v) => Column::Int(rows.iter().map(|i| v[*i]).collect()),
+            Column::Float(v) => Column::Float(rows.iter().map(|i| v[*i]).collect()),
+            Column::Bool(v) => Column::Bool(rows.iter().map(|i| v[*i]).collect()),
+            Column::Text(v) => Column::Text(rows.iter().map(|i| v[*i].clone()).collect()),
+        }
+    }
+
+    /// Compares the values at rows `i` and `j`. `NaN` sorts after every other float.
+    pub fn compare(&self, i: usize, j: usize) -> std::cmp::Ordering {
+        match self {
+            Column::Int(v) => v[i].cmp(&v[j]),
+            Column::Float(v) => match (v[i].is_nan(), v[j].is_nan()) {
+                (false, false) => v[i].partial_cmp(&v[j]).unwrap(),
+                (a, b) => a.cmp(&b),
+            },
+            Column::Bool(v) => v[i].cmp(&v[j]),
+            Column::Text(v) => v[i].cmp(&v[j]),
+        }
+    }
+}
+
+/// A table of named columns, each holding values of a single type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    /// Column names, in order
+    names: Vec<String>,
+    /// Columns, in the same order as the names
+    columns: Vec<Column>,
+}
+
+impl Table {
+    /// Creates a table from column names and columns, which must have distinct names and the
+    /// same number of rows.
+    pub fn new(names: Vec<String>, columns: Vec<Column>) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let error = |msg: String| -> Box<rhai::EvalAltResult> {
+            crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+        };
+        if names.len() != columns.len() {
+            return Err(error(format!(
+                "There are {} column names but {} columns",
+                names.len(),
+                columns.len()
+            )));
+        }
+        if let Some((_, name)) = names
+            .iter()
+            .enumerate()
+            .find(|(i, name)| names[..*i].contains(name))
+        {
+            return Err(error(format!(
+                "The column name '{name}' is used more than once"
+            )));
+        }
+        if let Some(column) = columns.iter().find(|c| c.len() != columns[0].len()) {
+            return Err(error(format!(
+                "Every column must have the same length, but found lengths {} and {}",
+                columns[0].len(),
+                column.len()
+            )));
+        }
+        Ok(Table { names, columns })
+    }
+
+    /// Creates a table from column names and rows of values.
+    pub fn from_rows(
+        names: Vec<String>,
+        rows: Vec<rhai::Array>,
+    ) -> Result<Self, Box<rhai::EvalAltResult>> {
+        if let Some(row) = rows.iter().find(|row| row.len() != names.len()) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Every row must have {} values, one per column, not {}",
+                    names.len(),
+                    row.len()
+                ),
+            )
+            .into());
+        }
+        let columns = (0..names.len())
+            .map(|j| Column::from_values(rows.iter().map(|row| row[j].clone()).collect()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Table::new(names, columns)
+    }
+
+    /// The column names, in order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The columns, in order.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// The number of rows.
+    pub fn nrows(&self) -> usize {
+        self.columns.first().map_or(0, |c| c.len())
+    }
+
+    /// The number of columns.
+    pub fn ncols(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The position of the column with the given name.
+    pub fn position(&self, name: &str) -> Result<usize, Box<rhai::EvalAltResult>> {
+        self.names.iter().position(|n| n == name).ok_or_else(|| {
+            crate::LabError::new(
+                crate::LabErrorKind::NotFound,
+                format!(
+                    "There is no column named '{name}', expected one of {:?}",
+                    self.names
+                ),
+            )
+            .into()
+        })
+    }
+
+    /// The column with the given name.
+    pub fn column(&self, name: &str) -> Result<&Column, Box<rhai::EvalAltResult>> {
+        Ok(&self.columns[self.position(name)?])
+    }
+
+    /// Adds a column at the end, or replaces the column with the same name.
+    pub fn set_column(
+        &mut self,
+        name: &str,
+        column: Column,
+    ) -> Result<(), Box<rhai::EvalAltResult>> {
+        if !self.columns.is_empty() && column.len() != self.nrows() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "The column '{name}' must have {} values, not {}",
+                    self.nrows(),
+                    column.len()
+                ),
+            )
+            .into());
+        }
+        match self.names.iter().position(|n| n == name) {
+            Some(j) => self.columns[j] = column,
+            None => {
+                self.names.push(name.to_string());
+                self.columns.push(column);
+            }
+        }
+        Ok(())
+    }
+
+    /// Row `i` as a map from column names to values.
+    pub fn row(&self, i: usize) -> rhai::Map {
+        self.names
+            .iter()
+            .zip(&self.columns)
+            .map(|(name, column)| (name.into(), column.get(i)))
+            .collect()
+    }
+
+    /// A table holding the given rows, in order.
+    pub fn take_rows(&self, rows: &[usize]) -> Self {
+        Table {
+            names: self.names.clone(),
+            columns: self.columns.iter().map(|c| c.take(rows)).collect(),
+        }
+    }
+
+    /// The row indices sorted by the values of the given columns, in order of priority. Ties keep
+    /// their original order.
+    pub fn sorted_rows(&self, keys: &[usize], ascending: bool) -> Vec<usize> {
+        let mut rows = (0..self.nrows()).collect::<Vec<_>>();
+        rows.sort_by(|i, j| {
+            let ordering = keys
+                .iter()
+                .map(|k| self.columns[*k].compare(*i, *j))
+                .find(|o| o.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal);
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+        rows
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MAX_ROWS: usize = 10;
+        let shown = self.nrows().min(MAX_ROWS);
+        let cells = self
+            .columns
+            .iter()
+            .zip(&self.names)
+            .map(|(column, name)| {
+                let mut cells = vec![name.clone()];
+                cells.extend((0..shown).map(|i| match column {
+                    Column::Float(v) => format!("{:?}", v[i]),
+                    _ => column.get(i).to_string(),
+                }));
+                cells
+            })
+            .collect::<Vec<_>>();
+        let widths = cells
+            .iter()
+            .map(|c| c.iter().map(|s| s.chars().count()).max().unwrap_or(0))
+            .collect::<Vec<_>>();
+        for i in 0..=shown {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|(column, width)| format!("{:>width$}", column[i]))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{line}")?;
+        }
+        write!(f, "[{} rows x {} columns]", self.nrows(), self.ncols())
+    }
+}
+
+/// A `Table` split into groups of rows that share the values of the key columns, created with
+/// `group_by`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupedTable {
+    /// The table being grouped
+    table: Table,
+    /// Positions of the key columns
+    keys: Vec<usize>,
+    /// Row indices of each group, with groups sorted by their keys
+    groups: Vec<Vec<usize>>,
+}
+
+impl GroupedTable {
+    /// Groups the rows of a table by the values of the key columns.
+    pub fn new(table: Table, keys: Vec<usize>) -> Self {
+        let mut groups: Vec<Vec<usize>> = vec![];
+        for i in table.sorted_rows(&keys, true) {
+            let same = groups.last().is_some_and(|group| {
+                keys.iter()
+                    .all(|k| table.columns[*k].compare(group[0], i).is_eq())
+            });
+            match groups.last_mut() {
+                Some(group) if same => group.push(i),
+                _ => groups.push(vec![i]),
+            }
+        }
+        GroupedTable {
+            table,
+            keys,
+            groups,
+        }
+    }
+
+    /// The number of groups.
+    pub fn ngroups(&self) -> usize {
+        self.groups.len()
+    }
+}
+
+/// Resolves a column name, or an array of column names, to column positions.
+fn table_key_columns(
+    table: &Table,
+    keys: &rhai::Dynamic,
+) -> Result<Vec<usize>, Box<rhai::EvalAltResult>> {
+    let names = match (keys.clone().into_string(), keys.clone().into_array()) {
+        (Ok(name), _) => vec![name],
+        (_, Ok(names)) => names
+            .into_iter()
+            .map(|name| name.into_string().unwrap_or_default())
+            .collect(),
+        _ => {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                "Columns must be given as a name or an array of names",
+            )
+            .into())
+        }
+    };
+    names.iter().map(|name| table.position(name)).collect()
+}
+
+/// A value of a key column, compared across tables when joining. Numbers compare by value, so an
+/// integer key matches the same float key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum JoinKey {
+    /// The bits of a number as a float, with `-0.0` stored as `0.0`
+    Number(u64),
+    /// A boolean
+    Bool(bool),
+    /// A string
+    Text(rhai::ImmutableString),
+}
+
+/// The join key of row `i` of a table.
+#[allow(clippy::unnecessary_cast)] // FLOAT may be f32
+fn join_key(table: &Table, keys: &[usize], i: usize) -> Vec<JoinKey> {
+    keys.iter()
+        .map(|k| match &table.columns[*k] {
+            Column::Int(v) => JoinKey::Number((v[i] as f64).to_bits()),
+            Column::Float(v) => JoinKey::Number((v[i] as f64 + 0.0).to_bits()),
+            Column::Bool(v) => JoinKey::Bool(v[i]),
+            Column::Text(v) => JoinKey::Text(v[i].clone()),
+        })
+        .collect()
+}
+
+/// The values of a column at the given rows, with a missing value where the row is `None`:
+/// `()` (which becomes `NaN`) for numbers and an empty string for strings.
+fn join_column_values(
+    column: &Column,
+    name: &str,
+    rows: &[Option<usize>],
+) -> Result<Vec<rhai::Dynamic>, Box<rhai::EvalAltResult>> {
+    rows.iter()
+        .map(|row| match (row, column) {
+            (Some(i), _) => Ok(column.get(*i)),
+            (None, Column::Int(_) | Column::Float(_)) => Ok(rhai::Dynamic::UNIT),
+            (None, Column::Text(_)) => Ok("".into()),
+            (None, Column::Bool(_)) => Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "The bool column '{name}' cannot hold the missing values of unmatched rows"
+                ),
+            )
+            .into()),
+        })
+        .collect()
+}
+
+/// Joins two tables on key columns they share. `how` is `"inner"` (only rows whose keys match),
+/// `"left"` (every row of `left`), or `"outer"` (every row of both).
+fn join_tables(
+    left: &Table,
+    right: &Table,
+    on: &rhai::Dynamic,
+    how: &str,
+) -> Result<Table, Box<rhai::EvalAltResult>> {
+    if !matches!(how, "inner" | "left" | "outer") {
+        return Err(crate::LabError::new(
+            crate::LabErrorKind::InvalidArgument,
+            format!("Unknown join '{how}', expected \"inner\", \"left\", or \"outer\""),
+        )
+        .into());
+    }
+    let left_keys = table_key_columns(left, on)?;
+    let right_keys = table_key_columns(right, on)?;
+
+    let mut matches = std::collections::HashMap::<Vec<JoinKey>, Vec<usize>>::new();
+    for j in 0..right.nrows() {
+        matches
+            .entry(join_key(right, &right_keys, j))
+            .or_default()
+            .push(j);
+    }
+    let mut rows = vec![];
+    let mut matched = vec![false; right.nrows()];
+    for i in 0..left.nrows() {
+        match matches.get(&join_key(left, &left_keys, i)) {
+            Some(js) => {
+                for j in js {
+                    matched[*j] = true;
+                    rows.push((Some(i), Some(*j)));
+                }
+            }
+            None if how != "inner" => rows.push((Some(i), None)),
+            None => (),
+        }
+    }
+    if how == "outer" {
+        rows.extend(
+            (0..right.nrows())
+                .filter(|j| !matched[*j])
+                .map(|j| (None, Some(j))),
+        );
+    }
+    let left_rows = rows.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    let right_rows = rows.iter().map(|(_, j)| *j).collect::<Vec<_>>();
+
+    let mut names = vec![];
+    let mut columns = vec![];
+    for (l, r) in left_keys.iter().zip(&right_keys) {
+        let values = rows
+            .iter()
+            .map(|row| match row {
+                (Some(i), _) => left.columns[*l].get(*i),
+                (None, Some(j)) => right.columns[*r].get(*j),
+                (None, None) => rhai::Dynamic::UNIT,
+            })
+            .collect();
+        names.push(left.names[*l].clone());
+        columns.push(Column::from_values(values)?);
+    }
+    for (k, (name, column)) in left.names.iter().zip(&left.columns).enumerate() {
+        if !left_keys.contains(&k) {
+            names.push(name.clone());
+            columns.push(Column::from_values(join_column_values(
+                column, name, &left_rows,
+            )?)?);
+        }
+    }
+    for (k, (name, column)) in right.names.iter().zip(&right.columns).enumerate() {
+        if !right_keys.contains(&k) {
+            let values = join_column_values(column, name, &right_rows)?;
+            names.push(if left.names.contains(name) {
+                format!("{name}_right")
+            } else {
+                name.clone()
+            });
+            columns.push(Column::from_values(values)?);
+        }
+    }
+    Table::new(names, columns)
+}
+
+/// Aggregates the values of a column with a named function, such as `"mean"`.
+fn aggregate_column(
+    column: &Column,
+    name: &str,
+    how: &str,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    match how {
+        "count" => return Ok(rhai::Dynamic::from_int(column.len() as rhai::INT)),
+        "first" => return Ok(column.get(0)),
+        "last" => return Ok(column.get(column.len() - 1)),
+        _ => (),
+    }
+    let x = column.to_floats().ok_or_else(|| {
+        error(format!(
+            "Cannot take the {how} of the {} column '{name}'",
+            column.type_name()
+        ))
+    })?;
+    let n = x.len() as rhai::FLOAT;
+    let mean = x.iter().sum::<rhai::FLOAT>() / n;
+    let value = match how {
+        "sum" => match column {
+            Column::Int(v) => return Ok(rhai::Dynamic::from_int(v.iter().sum())),
+            _ => x.iter().sum(),
+        },
+        "min" => match column {
+            Column::Int(v) => return Ok(rhai::Dynamic::from_int(*v.iter().min().unwrap())),
+            _ => x
+                .iter()
+                .copied()
+                .fold(rhai::FLOAT::INFINITY, rhai::FLOAT::min),
+        },
+        "max" => match column {
+            Column::Int(v) => return Ok(rhai::Dynamic::from_int(*v.iter().max().unwrap())),
+            _ => x
+                .iter()
+                .copied()
+                .fold(rhai::FLOAT::NEG_INFINITY, rhai::FLOAT::max),
+        },
+        "mean" => mean,
+        "median" => {
+            let mut sorted = x.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            let mid = sorted.len() / 2;
+            if sorted.len().is_multiple_of(2) {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            } else {
+                sorted[mid]
+            }
+        }
+        "var" | "std" => {
+            let var = x.iter().map(|v| (v - mean).powi(2)).sum::<rhai::FLOAT>() / (n - 1.0);
+            if how == "std" {
+                var.sqrt()
+            } else {
+                var
+            }
+        }
+        _ => {
+            return Err(error(format!(
+                "Unknown aggregation '{how}', expected one of \"count\", \"sum\", \"mean\", \
+                 \"median\", \"min\", \"max\", \"std\", \"var\", \"first\", or \"last\""
+            )))
+        }
+    };
+    Ok(rhai::Dynamic::from_float(value))
+}
+
+#[export_module]
+pub mod table_functions {
+    use rhai::{
+        Array, Dynamic, EvalAltResult, FnPtr, ImmutableString, Map, NativeCallContext, INT,
+    };
+
+    /// A table of named, typed columns, created with `table` or `read_table`.
+    pub type Table = crate::Table;
+
+    /// A table split into groups of rows, created with `group_by`.
+    pub type GroupedTable = crate::GroupedTable;
+
+    /// Creates a `Table` from a map. A map with column `names` and `data` rows, such as the maps
+    /// returned by `read_matrix` with `names: true` and by `read_parquet`, keeps the column
+    /// order. Any other map is read as one column per key, in alphabetical order.
+    /// ```typescript
+    /// let t = table(#{names: ["id", "score"], data: [[1, 0.5], [2, 0.75]]});
+    /// assert_eq(t.names, ["id", "score"]);
+    /// assert_eq(t["score"], [0.5, 0.75]);
+    /// ```
+    /// ```typescript
+    /// let t = table(#{x: [1, 2, 3], label: ["a", "b", "c"]});
+    /// assert_eq(t.names, ["label", "x"]);
+    /// assert_eq(size(t), [3, 2]);
+    /// ```
+    #[rhai_fn(name = "table", return_raw)]
+    pub fn table_from_map(map: Map) -> Result<Table, Box<EvalAltResult>> {
+        match (map.get("names"), map.get("data")) {
+            (Some(names), Some(data)) if map.len() == 2 => {
+                match (names.clone().into_array(), data.clone().into_array()) {
+                    (Ok(names), Ok(data)) => table_from_rows(names, data),
+                    _ => Err(crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "The names and data of a table must be arrays",
+                    )
+                    .in_function("table")
+                    .into()),
+                }
+            }
+            _ => {
+                let names = map.keys().map(|k| k.to_string()).collect();
+                let columns = map
+                    .into_values()
+                    .map(|column| {
+                        crate::Column::from_values(column.into_array().map_err(|type_name| {
+                            EvalAltResult::ErrorMismatchDataType(
+                                "array".to_string(),
+                                type_name.to_string(),
+                                rhai::Position::NONE,
+                            )
+                        })?)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Table::new(names, columns)
+            }
+        }
+    }
+
+    /// Creates a `Table` from an array of column names and a matrix whose rows hold one value
+    /// per column. Each column must hold only numbers, only booleans, or only strings.
+    /// ```typescript
+    /// let t = table(["city", "temp"], [["Oslo", 4.5], ["Lima", 19.0]]);
+    /// assert_eq(t["city"], ["Oslo", "Lima"]);
+    /// ```
+    #[rhai_fn(name = "table", return_raw)]
+    pub fn table_from_rows(names: Array, data: Array) -> Result<Table, Box<EvalAltResult>> {
+        let names = names
+            .into_iter()
+            .map(|name| name.into_string())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "Column names must be strings",
+                    )
+                    .in_function("table")
+                    .with_argument("names"),
+                )
+            })?;
+        let rows = data
+            .into_iter()
+            .map(|row| row.into_array())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "Each row of the data must be an array",
+                    )
+                    .in_function("table")
+                    .with_argument("data"),
+                )
+            })?;
+        Table::from_rows(names, rows)
+    }
+
+    /// Returns the column names of a `Table`.
+    #[rhai_fn(get = "names", pure)]
+    pub fn get_names(t: &mut Table) -> Array {
+        t.names().iter().map(|n| n.clone().into()).collect()
+    }
+
+    /// Returns the number of rows and columns of a `Table`.
+    /// ```typescript
+    /// let t = table(["a", "b", "c"], [[1, 2, 3], [4, 5, 6]]);
+    /// assert_eq(size(t), [2, 3]);
+    /// ```
+    #[rhai_fn(name = "size", pure)]
+    pub fn size(t: &mut Table) -> Array {
+        vec![
+            Dynamic::from_int(t.nrows() as INT),
+            Dynamic::from_int(t.ncols() as INT),
+        ]
+    }
+
+    /// Returns the values of the column of a `Table` with the given name.
+    /// ```typescript
+    /// let t = table(["x", "y"], [[1, true], [2, false]]);
+    /// assert_eq(t["y"], [true, false]);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get_column(t: &mut Table, name: ImmutableString) -> Result<Array, Box<EvalAltResult>> {
+        Ok(t.column(&name)?.to_array())
+    }
+
+    /// Replaces the column of a `Table` with the given name, or adds it as a new last column.
+    /// The column must have one value per row.
+    /// ```typescript
+    /// let t = table(["x"], [[1.0], [2.0]]);
+    /// t["y"] = t["x"] * 2;
+    /// assert_eq(t.names, ["x", "y"]);
+    /// assert_eq(t["y"], [2.0, 4.0]);
+    /// ```
+    #[rhai_fn(index_set, return_raw)]
+    pub fn set_column(
+        t: &mut Table,
+        name: ImmutableString,
+        values: Array,
+    ) -> Result<(), Box<EvalAltResult>> {
+        t.set_column(&name, crate::Column::from_values(values)?)
+    }
+
+    /// Returns a `Table` with only the named columns, in the given order.
+    /// ```typescript
+    /// let t = table(["a", "b", "c"], [[1, 2, 3], [4, 5, 6]]);
+    /// let s = select(t, ["c", "a"]);
+    /// assert_eq(s.names, ["c", "a"]);
+    /// assert_eq(s["c"], [3, 6]);
+    /// ```
+    #[rhai_fn(name = "select", return_raw, pure)]
+    pub fn select(t: &mut Table, names: Array) -> Result<Table, Box<EvalAltResult>> {
+        let keys = super::table_key_columns(t, &Dynamic::from_array(names))?;
+        Table::new(
+            keys.iter().map(|k| t.names()[*k].clone()).collect(),
+            keys.iter().map(|k| t.columns()[*k].clone()).collect(),
+        )
+    }
+
+    /// Returns a `Table` with only the rows for which a function returns `true`. The function is
+    /// called with each row as a map from column names to values.
+    /// ```typescript
+    /// let t = table(["name", "age"], [["Ada", 36], ["Alan", 41], ["Grace", 85]]);
+    /// let older = t.filter(|row| row.age > 40);
+    /// assert_eq(older["name"], ["Alan", "Grace"]);
+    /// ```
+    #[rhai_fn(name = "filter", return_raw, pure)]
+    pub fn filter(
+        context: NativeCallContext,
+        t: &mut Table,
+        f: FnPtr,
+    ) -> Result<Table, Box<EvalAltResult>> {
+        let mut rows = vec![];
+        for i in 0..t.nrows() {
+            let keep = f.call_within_context::<Dynamic>(&context, (t.row(i),))?;
+            if keep.as_bool().map_err(|type_name| {
+                EvalAltResult::ErrorMismatchDataType(
+                    "bool".to_string(),
+                    type_name.to_string(),
+                    rhai::Position::NONE,
+                )
+            })? {
+                rows.push(i);
+            }
+        }
+        Ok(t.take_rows(&rows))
+    }
+
+    /// Returns a `Table` with its rows sorted in ascending order by the values of a column, or by
+    /// an array of columns in order of priority. Rows with equal values keep their order.
+    /// ```typescript
+    /// let t = table(["name", "age"], [["Grace", 85], ["Ada", 36], ["Alan", 41]]);
+    /// assert_eq(t.sort_by("age")["name"], ["Ada", "Alan", "Grace"]);
+    /// ```
+    #[rhai_fn(name = "sort_by", return_raw, pure)]
+    pub fn sort_by(t: &mut Table, columns: Dynamic) -> Result<Table, Box<EvalAltResult>> {
+        sort_by_with_order(t, columns, true)
+    }
+
+    /// Returns a `Table` with its rows sorted by the values of a column, or by an array of
+    /// columns in order of priority, in ascending order if `ascending` is `true` and descending
+    /// order otherwise.
+    /// ```typescript
+    /// let t = table(["team", "points"], [["a", 3], ["b", 7], ["a", 5]]);
+    /// let sorted = t.sort_by(["team", "points"], false);
+    /// assert_eq(sorted["points"], [7, 5, 3]);
+    /// ```
+    #[rhai_fn(name = "sort_by", return_raw, pure)]
+    pub fn sort_by_with_order(
+        t: &mut Table,
+        columns: Dynamic,
+        ascending: bool,
+    ) -> Result<Table, Box<EvalAltResult>> {
+        let keys = super::table_key_columns(t, &columns)?;
+        Ok(t.take_rows(&t.sorted_rows(&keys, ascending)))
+    }
+
+    /// Splits the rows of a `Table` into groups that share the values of a column, or of an array
+    /// of columns, for aggregating with `agg`.
+    /// ```typescript
+    /// let t = table(["team", "points"], [["a", 3], ["b", 7], ["a", 5]]);
+    /// let groups = t.group_by("team");
+    /// assert_eq(groups.len, 2);
+    /// ```
+    #[rhai_fn(name = "group_by", return_raw, pure)]
+    pub fn group_by(t: &mut Table, columns: Dynamic) -> Result<GroupedTable, Box<EvalAltResult>> {
+        let keys = super::table_key_columns(t, &columns)?;
+        Ok(GroupedTable::new(t.clone(), keys))
+    }
+
+    /// Returns the number of groups in a `GroupedTable`.
+    #[rhai_fn(get = "len", pure)]
+    pub fn group_count(g: &mut GroupedTable) -> INT {
+        g.ngroups() as INT
+    }
+
+    /// Aggregates each group of a `GroupedTable` into one row, returning a `Table` with the key
+    /// columns followed by one column per entry of `spec`, in alphabetical order. Each key of
+    /// `spec` names a column, and its value says how to aggregate it:
+    /// * a string naming an aggregation: `"count"`, `"sum"`, `"mean"`, `"median"`, `"min"`,
+    ///   `"max"`, `"std"`, `"var"`, `"first"`, or `"last"`. The result keeps the column's name.
+    /// * an array of such strings, giving one result column per aggregation, named like
+    ///   `points_mean`.
+    /// * a function, called with the column's values in the group.
+    ///
+    /// Groups are sorted by their keys.
+    /// ```typescript
+    /// let t = table(["team", "points"], [["b", 7], ["a", 3], ["a", 5]]);
+    /// let totals = t.group_by("team").agg(#{points: "sum"});
+    /// assert_eq(totals.names, ["team", "points"]);
+    /// assert_eq(totals["team"], ["a", "b"]);
+    /// assert_eq(totals["points"], [8, 7]);
+    /// ```
+    /// ```typescript
+    /// let t = table(["team", "points"], [["b", 7], ["a", 3], ["a", 5]]);
+    /// let summary = t.group_by("team").agg(#{points: ["mean", "count"]});
+    /// assert_eq(summary.names, ["team", "points_mean", "points_count"]);
+    /// assert_eq(summary["points_mean"], [4.0, 7.0]);
+    /// ```
+    /// ```typescript
+    /// let t = table(["team", "points"], [["b", 7], ["a", 3], ["a", 5]]);
+    /// let spread = t.group_by("team").agg(#{points: |x| max(x) - min(x)});
+    /// assert_eq(spread["points"], [2, 0]);
+    /// ```
+    #[rhai_fn(name = "agg", return_raw, pure)]
+    pub fn agg(
+        context: NativeCallContext,
+        g: &mut GroupedTable,
+        spec: Map,
+    ) -> Result<Table, Box<EvalAltResult>> {
+        let table = &g.table;
+        let mut names = g
+            .keys
+            .iter()
+            .map(|k| table.names()[*k].clone())
+            .collect::<Vec<_>>();
+        let mut columns = g
+            .keys
+            .iter()
+            .map(|k| {
+                let firsts = g.groups.iter().map(|group| group[0]).collect::<Vec<_>>();
+                table.columns()[*k].take(&firsts)
+            })
+            .collect::<Vec<_>>();
+        for (name, how) in spec {
+            let column = table.column(&name)?;
+            let groups = g.groups.iter().map(|group| column.take(group));
+            if how.is::<FnPtr>() {
+                let f = how.cast::<FnPtr>();
+                let values = groups
+                    .map(|group| f.call_within_context::<Dynamic>(&context, (group.to_array(),)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                names.push(name.to_string());
+                columns.push(crate::Column::from_values(values)?);
+            } else if let Ok(how) = how.clone().into_string() {
+                let values = groups
+                    .map(|group| super::aggregate_column(&group, &name, &how))
+                    .collect::<Result<Vec<_>, _>>()?;
+                names.push(name.to_string());
+                columns.push(crate::Column::from_values(values)?);
+            } else if let Ok(hows) = how.into_array() {
+                for how in hows {
+                    let how = how.into_string().unwrap_or_default();
+                    let values = g
+                        .groups
+                        .iter()
+                        .map(|group| super::aggregate_column(&column.take(group), &name, &how))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    names.push(format!("{name}_{how}"));
+                    columns.push(crate::Column::from_values(values)?);
+                }
+            } else {
+                return Err(crate::LabError::new(crate::LabErrorKind::TypeMismatch, format!(
+                        "The aggregation for '{name}' must be a string, an array of strings, or a function"
+                    )).in_function("agg")
+                .into());
+            }
+        }
+        Table::new(names, columns)
+    }
+
+    /// Joins two tables on a key column they share, or an array of key columns, keeping only the
+    /// rows whose keys appear in both. The result has the key columns, then the other columns of
+    /// `left`, then the other columns of `right`. Columns of `right` whose names are already used
+    /// get a `_right` suffix.
+    /// ```typescript
+    /// let people = table(["id", "name"], [[1, "Ada"], [2, "Alan"], [3, "Grace"]]);
+    /// let scores = table(["id", "score"], [[3, 9.5], [1, 8.0]]);
+    /// let joined = join(people, scores, "id");
+    /// assert_eq(joined.names, ["id", "name", "score"]);
+    /// assert_eq(joined["name"], ["Ada", "Grace"]);
+    /// assert_eq(joined["score"], [8.0, 9.5]);
+    /// ```
+    #[rhai_fn(name = "join", return_raw)]
+    pub fn join(left: Table, right: Table, on: Dynamic) -> Result<Table, Box<EvalAltResult>> {
+        super::join_tables(&left, &right, &on, "inner")
+    }
+
+    /// Joins two tables on a key column they share, or an array of key columns, in one of three
+    /// ways:
+    /// * `"inner"`: only the rows whose keys appear in both tables.
+    /// * `"left"`: every row of `left`, with missing values where `right` has no match.
+    /// * `"outer"`: every row of both tables, with missing values where either has no match.
+    ///
+    /// Missing values are `NaN` in columns of numbers (so integer columns become floats) and
+    /// empty strings in columns of strings. Columns of booleans cannot hold missing values. The
+    /// result has the key columns, then the other columns of `left`, then the other columns of
+    /// `right`, whose names get a `_right` suffix if they are already used.
+    /// ```typescript
+    /// let people = table(["id", "name"], [[1, "Ada"], [2, "Alan"]]);
+    /// let scores = table(["id", "score"], [[1, 8.0], [3, 9.5]]);
+    /// let joined = people.join(scores, "id", "outer");
+    /// assert_eq(joined["id"], [1, 2, 3]);
+    /// assert_eq(joined["name"], ["Ada", "Alan", ""]);
+    /// assert(is_nan(joined["score"][1]));
+    /// ```
+    #[rhai_fn(name = "join", return_raw)]
+    pub fn join_with_kind(
+        left: Table,
+        right: Table,
+        on: Dynamic,
+        how: ImmutableString,
+    ) -> Result<Table, Box<EvalAltResult>> {
+        super::join_tables(&left, &right, &on, &how)
+    }
+
+    /// Converts a `Table` of numbers into a matrix with one row per row of the table.
+    /// ```typescript
+    /// let t = table(["x", "y"], [[1, 2.5], [3, 4.0]]);
+    /// assert_eq(to_matrix(t), [[1.0, 2.5], [3.0, 4.0]]);
+    /// ```
+    #[rhai_fn(name = "to_matrix", return_raw, pure)]
+    pub fn to_matrix(t: &mut Table) -> Result<Array, Box<EvalAltResult>> {
+        let columns = t
+            .columns()
+            .iter()
+            .zip(t.names())
+            .map(|(column, name)| {
+                column.to_floats().ok_or_else(|| {
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        format!(
+                            "Cannot convert the {} column '{name}' to a matrix of numbers",
+                            column.type_name()
+                        ),
+                    )
+                    .in_function("to_matrix")
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<_>, Box<EvalAltResult>>>()?;
+        Ok((0..t.nrows())
+            .map(|i| {
+                Dynamic::from_array(
+                    columns
+                        .iter()
+                        .map(|column| Dynamic::from_float(column[i]))
+                        .collect(),
+                )
+            })
+            .collect())
+    }
+
+    /// Converts a `Table` into a map with the column `names` and the `data` as an array of rows,
+    /// the form used by `write_json` and `write_parquet`.
+    /// ```typescript
+    /// let t = table(["id", "ok"], [[1, true], [2, false]]);
+    /// assert_eq(to_map(t), #{names: ["id", "ok"], data: [[1, true], [2, false]]});
+    /// ```
+    #[rhai_fn(name = "to_map", pure)]
+    pub fn to_map(t: &mut Table) -> Map {
+        let rows = (0..t.nrows())
+            .map(|i| Dynamic::from_array(t.columns().iter().map(|c| c.get(i)).collect()))
+            .collect::<Array>();
+        let mut map = Map::new();
+        map.insert("names".into(), Dynamic::from_array(get_names(t)));
+        map.insert("data".into(), Dynamic::from_array(rows));
+        map
+    }
+
+    /// Tests whether two tables have the same column names and values.
+    #[rhai_fn(name = "==")]
+    pub fn eq(a: Table, b: Table) -> bool {
+        a == b
+    }
+
+    /// Tests whether two tables differ in their column names or values.
+    #[rhai_fn(name = "!=")]
+    pub fn ne(a: Table, b: Table) -> bool {
+        a != b
+    }
+
+    /// Formats a `Table` as aligned columns under their names, showing at most the first ten
+    /// rows.
+    /// ```typescript
+    /// let t = table(["name", "score"], [["Ada", 9.5], ["Al", 10.0]]);
+    /// assert_eq(to_string(t), "name  score\n Ada    9.5\n  Al   10.0\n[2 rows x 2 columns]");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(t: &mut Table) -> ImmutableString {
+        t.to_string().into()
+    }
+}
@@ -0,0 +1,664 @@
+use rhai::plugin::*;
+
+/// An N-dimensional array of floating point numbers, stored contiguously in row-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tensor {
+    /// Size of each dimension
+    shape: Vec<usize>,
+    /// Number of elements to step over to move one place along each dimension
+    strides: Vec<usize>,
+    /// Elements in row-major order
+    data: Vec<rhai::FLOAT>,
+}
+
+impl Tensor {
+    /// Creates a tensor from its shape and its elements in row-major order.
+    pub fn new(
+        shape: Vec<usize>,
+        data: Vec<rhai::FLOAT>,
+    ) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let n = shape.iter().product::<usize>();
+        if data.len() != n {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A tensor of shape {shape:?} needs {n} elements, but {} were given",
+                    data.len()
+                ),
+            )
+            .into());
+        }
+        let mut strides = vec![1; shape.len()];
+        for k in (0..shape.len().saturating_sub(1)).rev() {
+            strides[k] = strides[k + 1] * shape[k + 1];
+        }
+        Ok(Tensor {
+            shape,
+            strides,
+            data,
+        })
+    }
+
+    /// Converts a nested Rhai array of numbers into a tensor. Every array at the same depth must
+    /// have the same length.
+    pub fn from_array(arr: &rhai::Array) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let mut shape = vec![arr.len()];
+        let mut first = arr.first().cloned();
+        while let Some(inner) = first.and_then(|x| x.into_array().ok()) {
+            shape.push(inner.len());
+            first = inner.first().cloned();
+        }
+        let mut data = Vec::with_capacity(shape.iter().product());
+        tensor_flatten(arr, &shape, &mut data)?;
+        Tensor::new(shape, data)
+    }
+
+    /// Converts the tensor into a nested Rhai array of FLOATs.
+    pub fn to_array(&self) -> rhai::Array {
+        fn nest(data: &[rhai::FLOAT], shape: &[usize]) -> rhai::Array {
+            match shape {
+                [] | [_] => data.iter().map(|x| rhai::Dynamic::from_float(*x)).collect(),
+                [n, rest @ ..] => {
+                    let chunk = rest.iter().product::<usize>();
+                    (0..*n)
+                        .map(|k| nest(&data[k * chunk..(k + 1) * chunk], rest).into())
+                        .collect()
+                }
+            }
+        }
+        nest(&self.data, &self.shape)
+    }
+
+    /// Size of each dimension
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Number of elements to step over to move one place along each dimension
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    /// Elements in row-major order
+    pub fn data(&self) -> &[rhai::FLOAT] {
+        &self.data
+    }
+
+    /// Mutable access to the elements in row-major order
+    pub fn data_mut(&mut self) -> &mut [rhai::FLOAT] {
+        &mut self.data
+    }
+
+    /// Resolves a (possibly negative, counted from the end) index into each dimension into a
+    /// position in the data.
+    fn offset(&self, index: &rhai::Array) -> Result<usize, Box<rhai::EvalAltResult>> {
+        if index.len() != self.shape.len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A tensor with {} dimensions needs {} indices, but {} were given",
+                    self.shape.len(),
+                    self.shape.len(),
+                    index.len()
+                ),
+            )
+            .into());
+        }
+        let mut offset = 0;
+        for ((i, n), stride) in index.iter().zip(&self.shape).zip(&self.strides) {
+            let i = i.as_int().map_err(|_| -> Box<rhai::EvalAltResult> {
+                crate::LabError::new(
+                    crate::LabErrorKind::TypeMismatch,
+                    "Tensor indices must be integers",
+                )
+                .into()
+            })?;
+            let idx = if i < 0 { i + *n as rhai::INT } else { i };
+            if idx < 0 || idx >= *n as rhai::INT {
+                return Err(
+                    rhai::EvalAltResult::ErrorArrayBounds(*n, i, rhai::Position::NONE).into(),
+                );
+            }
+            offset += idx as usize * stride;
+        }
+        Ok(offset)
+    }
+
+    /// Resolves a dimension, counting from 1, into an axis.
+    fn axis(&self, dim: rhai::INT) -> Result<usize, Box<rhai::EvalAltResult>> {
+        if dim < 1 || dim as usize > self.shape.len() {
+            Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Dimension must be between 1 and {}, not {dim}",
+                    self.shape.len()
+                ),
+            )
+            .into())
+        } else {
+            Ok(dim as usize - 1)
+        }
+    }
+
+    /// Combines the elements along dimension `dim` (counting from 1) with `f`, removing that
+    /// dimension from the shape.
+    fn reduce(
+        &self,
+        dim: rhai::INT,
+        f: impl Fn(&[rhai::FLOAT]) -> rhai::FLOAT,
+    ) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let axis = self.axis(dim)?;
+        let n = self.shape[axis];
+        let inner = self.strides[axis];
+        let outer = self.shape[..axis].iter().product::<usize>();
+        let mut data = Vec::with_capacity(outer * inner);
+        let mut lane = vec![0.0; n];
+        for o in 0..outer {
+            for i in 0..inner {
+                for (k, x) in lane.iter_mut().enumerate() {
+                    *x = self.data[(o * n + k) * inner + i];
+                }
+                data.push(f(&lane));
+            }
+        }
+        let mut shape = self.shape.clone();
+        shape.remove(axis);
+        Tensor::new(shape, data)
+    }
+}
+
+impl std::fmt::Display for Tensor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", rhai::Dynamic::from_array(self.to_array()))
+    }
+}
+
+/// Copies the numbers of a nested array into `data`, checking that it has the given shape.
+fn tensor_flatten(
+    arr: &rhai::Array,
+    shape: &[usize],
+    data: &mut Vec<rhai::FLOAT>,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let ragged = || -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            "Every array at the same depth of a tensor must have the same length",
+        )
+        .into()
+    };
+    if arr.len() != shape[0] {
+        return Err(ragged());
+    }
+    for x in arr {
+        if shape.len() > 1 {
+            let inner = x.read_lock::<rhai::Array>().ok_or_else(ragged)?;
+            tensor_flatten(&inner, &shape[1..], data)?;
+        } else if let Ok(x) = x.as_float() {
+            data.push(x);
+        } else if let Ok(x) = x.as_int() {
+            data.push(x as rhai::FLOAT);
+        } else if x.is_array() {
+            return Err(ragged());
+        } else {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::TypeMismatch,
+                format!(
+                    "Tensor elements must be INT or FLOAT, not {}",
+                    x.type_name()
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Collects the elements at every combination of `indices` (one list per dimension), in
+/// row-major order, from data laid out with the given strides.
+fn tensor_gather(
+    data: &[rhai::FLOAT],
+    strides: &[usize],
+    indices: &[Vec<usize>],
+) -> Vec<rhai::FLOAT> {
+    let mut out = vec![];
+    if indices.iter().any(|idx| idx.is_empty()) {
+        return out;
+    }
+    let mut counter = vec![0; indices.len()];
+    'outer: loop {
+        let offset = counter
+            .iter()
+            .zip(indices)
+            .zip(strides)
+            .map(|((c, idx), stride)| idx[*c] * stride)
+            .sum::<usize>();
+        out.push(data[offset]);
+        for k in (0..counter.len()).rev() {
+            counter[k] += 1;
+            if counter[k] < indices[k].len() {
+                continue 'outer;
+            }
+            counter[k] = 0;
+        }
+        return out;
+    }
+}
+
+/// An argument combined with a tensor element by element.
+enum TensorOperand {
+    /// A tensor with the same shape
+    Tensor(Tensor),
+    /// A number, combined with every element
+    Scalar(rhai::FLOAT),
+}
+
+impl TensorOperand {
+    /// Returns the elements for the kernels.
+    fn as_operand(&self) -> crate::Operand<'_> {
+        match self {
+            TensorOperand::Tensor(t) => crate::Operand::Slice(t.data()),
+            TensorOperand::Scalar(x) => crate::Operand::Scalar(*x),
+        }
+    }
+}
+
+/// Checks that the argument `x` of `fma` is a tensor with the same shape as `t`, or a number.
+fn tensor_operand(
+    t: &Tensor,
+    x: &rhai::Dynamic,
+    argument: &str,
+) -> Result<TensorOperand, Box<rhai::EvalAltResult>> {
+    if let Some(x) = x.clone().try_cast::<Tensor>() {
+        if x.shape() != t.shape() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A tensor of shape {:?} cannot be combined with one of shape {:?}",
+                    x.shape(),
+                    t.shape()
+                ),
+            )
+            .in_function("fma")
+            .with_argument(argument)
+            .into());
+        }
+        return Ok(TensorOperand::Tensor(x));
+    }
+    crate::if_int_convert_to_float_and_do(x.clone(), |x| Ok(TensorOperand::Scalar(x)))
+}
+
+#[export_module]
+pub mod tensor_functions {
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, FLOAT, INT};
+
+    /// An N-dimensional array of FLOATs with contiguous storage, created with `tensor`.
+    pub type Tensor = crate::Tensor;
+
+    /// Converts a nested array into a `Tensor`, with one dimension per level of nesting.
+    /// ```typescript
+    /// let t = tensor([[[1, 2], [3, 4], [5, 6]]]);
+    /// assert_eq(t.shape, [1, 3, 2]);
+    /// assert_eq(t[[0, 2, 1]], 6.0);
+    /// ```
+    #[rhai_fn(name = "tensor", return_raw)]
+    pub fn tensor(arr: Array) -> Result<Tensor, Box<EvalAltResult>> {
+        Tensor::from_array(&arr)
+    }
+
+    /// Creates a `Tensor` of the given shape with every element set to `value`.
+    /// ```typescript
+    /// let t = tensor([2, 3, 4], 0);
+    /// assert_eq(t.shape, [2, 3, 4]);
+    /// assert_eq(numel(t), 24);
+    /// ```
+    #[rhai_fn(name = "tensor", return_raw)]
+    pub fn tensor_filled(shape: Array, value: Dynamic) -> Result<Tensor, Box<EvalAltResult>> {
+        let shape = shape
+            .iter()
+            .map(|d| match d.as_int() {
+                Ok(d) if d >= 0 => Ok(d as usize),
+                _ => Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("Dimensions must be non-negative integers, not {shape:?}"),
+                )
+                .in_function("tensor")
+                .with_argument("shape")
+                .into()),
+            })
+            .collect::<Result<Vec<usize>, Box<EvalAltResult>>>()?;
+        let value = crate::if_int_convert_to_float_and_do(value, Ok)?;
+        let n = shape.iter().product();
+        Tensor::new(shape, vec![value; n])
+    }
+
+    /// Converts a `Tensor` into a nested array of FLOATs.
+    /// ```typescript
+    /// let t = tensor([[1, 2], [3, 4]]);
+    /// assert_eq(to_array(t), [[1.0, 2.0], [3.0, 4.0]]);
+    /// ```
+    #[rhai_fn(name = "to_array", pure)]
+    pub fn to_array(t: &mut Tensor) -> Array {
+        t.to_array()
+    }
+
+    /// Returns the shape of a `Tensor`, with the size of each dimension.
+    /// ```typescript
+    /// let t = tensor([4, 3, 2], 1.0);
+    /// assert_eq(t.shape, [4, 3, 2]);
+    /// ```
+    #[rhai_fn(get = "shape", name = "size", pure)]
+    pub fn shape(t: &mut Tensor) -> Array {
+        t.shape()
+            .iter()
+            .map(|d| Dynamic::from_int(*d as INT))
+            .collect()
+    }
+
+    /// Returns the strides of a `Tensor`, which are the number of elements to step over to move
+    /// one place along each dimension.
+    /// ```typescript
+    /// let t = tensor([4, 3, 2], 1.0);
+    /// assert_eq(t.strides, [6, 2, 1]);
+    /// ```
+    #[rhai_fn(get = "strides", pure)]
+    pub fn strides(t: &mut Tensor) -> Array {
+        t.strides()
+            .iter()
+            .map(|d| Dynamic::from_int(*d as INT))
+            .collect()
+    }
+
+    /// Returns the number of dimensions of a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([4, 3, 2], 1.0);
+    /// assert_eq(ndims(t), 3);
+    /// ```
+    #[rhai_fn(name = "ndims", pure)]
+    pub fn ndims(t: &mut Tensor) -> INT {
+        t.shape().len() as INT
+    }
+
+    /// Returns the number of elements in a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([4, 3, 2], 1.0);
+    /// assert_eq(numel(t), 24);
+    /// ```
+    #[rhai_fn(name = "numel", pure)]
+    pub fn numel(t: &mut Tensor) -> INT {
+        t.data().len() as INT
+    }
+
+    /// Returns the element of a `Tensor` at an array of indices, one per dimension. Negative
+    /// indices count from the end.
+    /// ```typescript
+    /// let t = tensor([[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    /// assert_eq(t[[1, 0, 1]], 6.0);
+    /// assert_eq(t[[-1, -1, -1]], 8.0);
+    /// ```
+    #[rhai_fn(index_get, return_raw, pure)]
+    pub fn get(t: &mut Tensor, index: Array) -> Result<FLOAT, Box<EvalAltResult>> {
+        Ok(t.data()[t.offset(&index)?])
+    }
+
+    /// Sets the element of a `Tensor` at an array of indices, one per dimension.
+    /// ```typescript
+    /// let t = tensor([2, 2, 2], 0);
+    /// t[[1, 0, 1]] = 5;
+    /// assert_eq(to_array(t), [[[0.0, 0.0], [0.0, 0.0]], [[0.0, 5.0], [0.0, 0.0]]]);
+    /// ```
+    #[rhai_fn(index_set, return_raw)]
+    pub fn set(t: &mut Tensor, index: Array, value: Dynamic) -> Result<(), Box<EvalAltResult>> {
+        let offset = t.offset(&index)?;
+        t.data_mut()[offset] = crate::if_int_convert_to_float_and_do(value, Ok)?;
+        Ok(())
+    }
+
+    /// Returns the part of a `Tensor` picked out by an array with one selection per dimension.
+    /// Each selection can be an integer, which removes that dimension from the result, or a
+    /// range such as `1..3` or an array of indices, which keep it. Missing trailing selections
+    /// take the whole dimension.
+    /// ```typescript
+    /// let t = tensor([[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    /// assert_eq(to_array(slice(t, [0])), [[1.0, 2.0], [3.0, 4.0]]);
+    /// assert_eq(to_array(slice(t, [0..2, 1, 0])), [3.0, 7.0]);
+    /// assert_eq(slice(t, [[1], 0..2, [1]]).shape, [1, 2, 1]);
+    /// ```
+    #[rhai_fn(name = "slice", return_raw, pure)]
+    pub fn slice(t: &mut Tensor, selections: Array) -> Result<Tensor, Box<EvalAltResult>> {
+        if selections.len() > t.shape().len() {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!(
+                    "A tensor with {} dimensions cannot take {} selections",
+                    t.shape().len(),
+                    selections.len()
+                ),
+            )
+            .in_function("slice")
+            .with_argument("selections")
+            .into());
+        }
+        let mut indices = vec![];
+        let mut shape = vec![];
+        for (k, n) in t.shape().iter().enumerate() {
+            match selections.get(k) {
+                Some(s) => {
+                    let idx = crate::selection_indices(s, *n)?;
+                    if !s.is_int() {
+                        shape.push(idx.len());
+                    }
+                    indices.push(idx);
+                }
+                None => {
+                    shape.push(*n);
+                    indices.push((0..*n).collect());
+                }
+            }
+        }
+
+        Tensor::new(shape, super::tensor_gather(t.data(), t.strides(), &indices))
+    }
+
+    /// Returns a `Tensor` with the same elements in a new shape. One dimension can be `-1` to
+    /// have its size inferred.
+    /// ```typescript
+    /// let t = tensor([[1, 2, 3], [4, 5, 6]]);
+    /// let r = reshape(t, [3, -1]);
+    /// assert_eq(to_array(r), [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "reshape", return_raw, pure)]
+    pub fn reshape(t: &mut Tensor, shape: Array) -> Result<Tensor, Box<EvalAltResult>> {
+        let shape = shape
+            .iter()
+            .map(|d| {
+                d.as_int().map_err(|_| -> Box<EvalAltResult> {
+                    crate::LabError::new(
+                        crate::LabErrorKind::TypeMismatch,
+                        "Sizes must be integers",
+                    )
+                    .in_function("reshape")
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<INT>, Box<EvalAltResult>>>()?;
+        let shape = crate::resolve_shape(&shape, t.data().len())?;
+        Tensor::new(shape, t.data().to_vec())
+    }
+
+    /// Returns a `Tensor` with its dimensions rearranged, where `order` lists the dimensions of
+    /// the input (counting from 1) in their new positions.
+    /// ```typescript
+    /// let t = tensor([4, 3, 2], 0.0);
+    /// assert_eq(permute(t, [3, 1, 2]).shape, [2, 4, 3]);
+    /// ```
+    /// ```typescript
+    /// let t = tensor([[1, 2, 3], [4, 5, 6]]);
+    /// assert_eq(to_array(permute(t, [2, 1])), [[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "permute", return_raw, pure)]
+    pub fn permute(t: &mut Tensor, order: Array) -> Result<Tensor, Box<EvalAltResult>> {
+        let ndim = t.shape().len();
+        let axes = order
+            .iter()
+            .map(|d| t.axis(d.as_int().unwrap_or(0)))
+            .collect::<Result<Vec<usize>, Box<EvalAltResult>>>()?;
+        let mut seen = vec![false; ndim];
+        for a in &axes {
+            seen[*a] = true;
+        }
+        if axes.len() != ndim || seen.contains(&false) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::DimensionMismatch,
+                format!("The order must list each of the {ndim} dimensions once, not {order:?}"),
+            )
+            .in_function("permute")
+            .with_argument("order")
+            .into());
+        }
+        let strides = axes.iter().map(|a| t.strides()[*a]).collect::<Vec<_>>();
+        let indices = axes
+            .iter()
+            .map(|a| (0..t.shape()[*a]).collect())
+            .collect::<Vec<_>>();
+        Tensor::new(
+            axes.iter().map(|a| t.shape()[*a]).collect(),
+            super::tensor_gather(t.data(), &strides, &indices),
+        )
+    }
+
+    /// Returns the sum of all elements of a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([[1, 2], [3, 4]]);
+    /// assert_eq(sum(t), 10.0);
+    /// ```
+    #[rhai_fn(name = "sum", pure)]
+    pub fn sum(t: &mut Tensor) -> FLOAT {
+        crate::reduce_floats(t.data(), crate::Reduction::Sum)
+    }
+
+    /// Returns the sums along dimension `dim` (counting from 1) of a `Tensor`, which removes
+    /// that dimension.
+    /// ```typescript
+    /// let t = tensor([[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    /// assert_eq(to_array(sum(t, 1)), [[6.0, 8.0], [10.0, 12.0]]);
+    /// assert_eq(to_array(sum(t, 3)), [[3.0, 7.0], [11.0, 15.0]]);
+    /// ```
+    #[rhai_fn(name = "sum", return_raw, pure)]
+    pub fn sum_along(t: &mut Tensor, dim: INT) -> Result<Tensor, Box<EvalAltResult>> {
+        t.reduce(dim, |x| crate::reduce_floats(x, crate::Reduction::Sum))
+    }
+
+    /// Returns the mean of all elements of a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([[1, 2], [3, 4]]);
+    /// assert_eq(mean(t), 2.5);
+    /// ```
+    #[rhai_fn(name = "mean", pure)]
+    pub fn mean(t: &mut Tensor) -> FLOAT {
+        sum(t) / t.data().len() as FLOAT
+    }
+
+    /// Returns the means along dimension `dim` (counting from 1) of a `Tensor`, which removes
+    /// that dimension. For data laid out as time × latitude × longitude, `mean(t, 1)` is the
+    /// time-averaged field.
+    /// ```typescript
+    /// let t = tensor([[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    /// assert_eq(to_array(mean(t, 1)), [[3.0, 4.0], [5.0, 6.0]]);
+    /// ```
+    #[rhai_fn(name = "mean", return_raw, pure)]
+    pub fn mean_along(t: &mut Tensor, dim: INT) -> Result<Tensor, Box<EvalAltResult>> {
+        t.reduce(dim, |x| {
+            crate::reduce_floats(x, crate::Reduction::Sum) / x.len() as FLOAT
+        })
+    }
+
+    /// Returns the largest element of a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([[1, 5], [3, 4]]);
+    /// assert_eq(max(t), 5.0);
+    /// ```
+    #[rhai_fn(name = "max", pure)]
+    pub fn max(t: &mut Tensor) -> FLOAT {
+        crate::reduce_floats(t.data(), crate::Reduction::Max)
+    }
+
+    /// Returns the largest elements along dimension `dim` (counting from 1) of a `Tensor`, which
+    /// removes that dimension.
+    /// ```typescript
+    /// let t = tensor([[1, 5], [3, 4]]);
+    /// assert_eq(to_array(max(t, 2)), [5.0, 4.0]);
+    /// ```
+    #[rhai_fn(name = "max", return_raw, pure)]
+    pub fn max_along(t: &mut Tensor, dim: INT) -> Result<Tensor, Box<EvalAltResult>> {
+        t.reduce(dim, |x| crate::reduce_floats(x, crate::Reduction::Max))
+    }
+
+    /// Returns the smallest element of a `Tensor`.
+    /// ```typescript
+    /// let t = tensor([[1, 5], [3, 4]]);
+    /// assert_eq(min(t), 1.0);
+    /// ```
+    #[rhai_fn(name = "min", pure)]
+    pub fn min(t: &mut Tensor) -> FLOAT {
+        crate::reduce_floats(t.data(), crate::Reduction::Min)
+    }
+
+    /// Returns the smallest elements along dimension `dim` (counting from 1) of a `Tensor`,
+    /// which removes that dimension.
+    /// ```typescript
+    /// let t = tensor([[1, 5], [3, 4]]);
+    /// assert_eq(to_array(min(t, 1)), [1.0, 4.0]);
+    /// ```
+    #[rhai_fn(name = "min", return_raw, pure)]
+    pub fn min_along(t: &mut Tensor, dim: INT) -> Result<Tensor, Box<EvalAltResult>> {
+        t.reduce(dim, |x| crate::reduce_floats(x, crate::Reduction::Min))
+    }
+
+    /// Computes `a * b + c` element by element in one pass, where `b` and `c` are tensors with
+    /// the same shape as `a`, or numbers. Each element is rounded once where the hardware has a
+    /// fused multiply-add instruction, and several are handled at a time with the `simd` feature.
+    /// ```typescript
+    /// let x = tensor([[1, 2], [3, 4]]);
+    /// let y = tensor([[10, 20], [30, 40]]);
+    /// assert_eq(to_array(fma(x, 2.0, y)), [[12.0, 24.0], [36.0, 48.0]]);
+    /// ```
+    /// ```typescript
+    /// let x = tensor([1, 2, 3]);
+    /// assert_eq(fma(x, x, 1), tensor([2, 5, 10]));
+    /// ```
+    #[rhai_fn(name = "fma", return_raw)]
+    pub fn fma(a: Tensor, b: Dynamic, c: Dynamic) -> Result<Tensor, Box<EvalAltResult>> {
+        let b = super::tensor_operand(&a, &b, "b")?;
+        let c = super::tensor_operand(&a, &c, "c")?;
+        let n = a.data().len();
+        let data = crate::fma_floats(
+            crate::Operand::Slice(a.data()),
+            b.as_operand(),
+            c.as_operand(),
+            n,
+        );
+        Tensor::new(a.shape().to_vec(), data)
+    }
+
+    /// Tests whether two tensors have the same shape and elements.
+    #[rhai_fn(name = "==", pure)]
+    pub fn eq(a: &mut Tensor, b: Tensor) -> bool {
+        *a == b
+    }
+
+    /// Tests whether two tensors differ in shape or elements.
+    #[rhai_fn(name = "!=", pure)]
+    pub fn ne(a: &mut Tensor, b: Tensor) -> bool {
+        *a != b
+    }
+
+    /// Formats a `Tensor` as nested lists.
+    /// ```typescript
+    /// let t = tensor([[1, 2], [3, 4]]);
+    /// assert_eq(to_string(t), "[[1.0, 2.0], [3.0, 4.0]]");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(t: &mut Tensor) -> ImmutableString {
+        t.to_string().into()
+    }
+}
@@ -0,0 +1,470 @@
+use rhai::plugin::*;
+
+/// Saturation vapor pressure of water (Pa) at a dry-bulb temperature (°C), from the Hyland-Wexler
+/// correlations used by ASHRAE, over ice below freezing and over liquid water above.
+fn saturation_pressure(t: rhai::FLOAT) -> rhai::FLOAT {
+    let tk = t + 273.15;
+    if t < 0.0 {
+        (-5.674_535_9e3 / tk + 6.392_524_7 - 9.677_843e-3 * tk
+            + 6.221_570_1e-7 * tk.powi(2)
+            + 2.074_782_5e-9 * tk.powi(3)
+            - 9.484_024e-13 * tk.powi(4)
+            + 4.163_501_9 * tk.ln())
+        .exp()
+    } else {
+        (-5.800_220_6e3 / tk + 1.391_499_3 - 4.864_023_9e-2 * tk + 4.176_476_8e-5 * tk.powi(2)
+            - 1.445_209_3e-8 * tk.powi(3)
+            + 6.545_967_3 * tk.ln())
+        .exp()
+    }
+}
+
+/// Humidity ratio (kg water per kg dry air) for a vapor pressure and total pressure (Pa).
+fn humidity_ratio(vapor_pressure: rhai::FLOAT, pressure: rhai::FLOAT) -> rhai::FLOAT {
+    0.621_945 * vapor_pressure / (pressure - vapor_pressure)
+}
+
+/// Finds the root of a function that increases over `[lo, hi]` by bisection.
+fn bisect(
+    f: impl Fn(rhai::FLOAT) -> rhai::FLOAT,
+    mut lo: rhai::FLOAT,
+    mut hi: rhai::FLOAT,
+) -> rhai::FLOAT {
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        if f(mid) > 0.0 {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+        if hi - lo <= 1e-12 * hi.abs().max(1.0) {
+            break;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Built-in property tables, each a name and its columns. The first column is the key used for
+/// lookups, and is increasing.
+#[allow(clippy::type_complexity)]
+const PROPERTY_TABLES: &[(&str, &[(&str, &[rhai::FLOAT])])] = &[
+    (
+        // Saturated liquid water and steam, from the IAPWS-IF97 steam tables
+        "water_saturation",
+        &[
+            (
+                "temperature_c",
+                &[
+                    0.01, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0,
+                ],
+            ),
+            (
+                "pressure_kpa",
+                &[
+                    0.6117, 1.2282, 2.3392, 4.2469, 7.3851, 12.352, 19.946, 31.201, 47.414, 70.182,
+                    101.42,
+                ],
+            ),
+            (
+                "density_kg_m3",
+                &[
+                    999.8, 999.7, 998.2, 995.6, 992.2, 988.0, 983.2, 977.8, 971.8, 965.3, 958.4,
+                ],
+            ),
+            (
+                "enthalpy_vaporization_kj_kg",
+                &[
+                    2500.9, 2477.2, 2453.5, 2429.8, 2406.0, 2381.9, 2357.7, 2333.0, 2308.0, 2282.5,
+                    2256.4,
+                ],
+            ),
+            (
+                "viscosity_pa_s",
+                &[
+                    1.792e-3, 1.307e-3, 1.002e-3, 0.798e-3, 0.653e-3, 0.547e-3, 0.467e-3, 0.404e-3,
+                    0.355e-3, 0.315e-3, 0.282e-3,
+                ],
+            ),
+        ],
+    ),
+    (
+        // Dry air at atmospheric pressure, from Incropera's Fundamentals of Heat and Mass Transfer
+        "air",
+        &[
+            ("temperature_k", &[250.0, 300.0, 350.0, 400.0, 450.0, 500.0]),
+            (
+                "density_kg_m3",
+                &[1.3947, 1.1614, 0.9950, 0.8711, 0.7740, 0.6964],
+            ),
+            (
+                "specific_heat_kj_kg_k",
+                &[1.006, 1.007, 1.009, 1.014, 1.021, 1.030],
+            ),
+            (
+                "viscosity_pa_s",
+                &[15.96e-6, 18.46e-6, 20.82e-6, 23.01e-6, 25.07e-6, 27.01e-6],
+            ),
+            (
+                "conductivity_w_m_k",
+                &[0.0223, 0.0263, 0.0300, 0.0338, 0.0373, 0.0407],
+            ),
+            ("prandtl", &[0.720, 0.707, 0.700, 0.690, 0.686, 0.684]),
+        ],
+    ),
+];
+
+#[export_module]
+pub mod thermo_functions {
+    use crate::if_int_convert_to_float_and_do;
+    use rhai::{Array, Dynamic, EvalAltResult, ImmutableString, Map, FLOAT};
+
+    /// The molar gas constant, in J/(mol K).
+    const GAS_CONSTANT: FLOAT = 8.314_462_618;
+
+    /// Computes the psychrometric properties of moist air from the dry-bulb temperature `tdb`
+    /// (°C), the relative humidity `rh` (as a fraction from 0 to 1), and the total `pressure` (Pa),
+    /// following the ASHRAE Handbook formulation. Returns an object map with:
+    /// - `humidity_ratio`: kg of water vapor per kg of dry air
+    /// - `dew_point`: dew point temperature (°C)
+    /// - `wet_bulb`: thermodynamic wet-bulb temperature (°C)
+    /// - `enthalpy`: specific enthalpy (kJ per kg of dry air)
+    /// - `specific_volume`: m³ per kg of dry air
+    /// - `vapor_pressure` and `saturation_pressure`: partial pressures of water vapor (Pa)
+    /// ```typescript
+    /// let air = psychro(25, 0.5, 101325);
+    /// assert(abs(air.humidity_ratio - 0.00988) < 1e-5);
+    /// assert(abs(air.dew_point - 13.86) < 0.01);
+    /// assert(abs(air.enthalpy - 50.3) < 0.1);
+    /// ```
+    /// ```typescript
+    /// let air = psychro(30, 1.0, 101325);
+    /// assert(abs(air.dew_point - 30.0) < 1e-6);
+    /// assert(abs(air.wet_bulb - 30.0) < 1e-6);
+    /// ```
+    #[rhai_fn(name = "psychro", return_raw)]
+    pub fn psychro(
+        tdb: Dynamic,
+        rh: Dynamic,
+        pressure: Dynamic,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let tdb = if_int_convert_to_float_and_do(tdb, Ok)?;
+        let rh = if_int_convert_to_float_and_do(rh, Ok)?;
+        let pressure = if_int_convert_to_float_and_do(pressure, Ok)?;
+        if !(-100.0..=200.0).contains(&tdb) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The dry-bulb temperature must be between -100 and 200 °C, not {tdb}"),
+            )
+            .in_function("psychro")
+            .with_argument("tdb")
+            .into());
+        }
+        if !(0.0..=1.0).contains(&rh) || rh == 0.0 {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The relative humidity must be a fraction in (0, 1], not {rh}"),
+            )
+            .in_function("psychro")
+            .with_argument("rh")
+            .into());
+        }
+
+        let saturation_pressure = super::saturation_pressure(tdb);
+        let vapor_pressure = rh * saturation_pressure;
+        if pressure <= saturation_pressure {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The total pressure must exceed the saturation pressure of water",
+            )
+            .in_function("psychro")
+            .with_argument("pressure")
+            .into());
+        }
+        let w = super::humidity_ratio(vapor_pressure, pressure);
+
+        // Dew point, where the saturation pressure equals the vapor pressure
+        let dew_point = super::bisect(
+            |t| super::saturation_pressure(t) - vapor_pressure,
+            -100.0,
+            tdb,
+        );
+
+        // Wet bulb, from the adiabatic saturation energy balance
+        let wet_bulb = super::bisect(
+            |t| {
+                let ws = super::humidity_ratio(super::saturation_pressure(t), pressure);
+                let w_star = if t >= 0.0 {
+                    ((2501.0 - 2.326 * t) * ws - 1.006 * (tdb - t))
+                        / (2501.0 + 1.86 * tdb - 4.186 * t)
+                } else {
+                    ((2830.0 - 0.24 * t) * ws - 1.006 * (tdb - t)) / (2830.0 + 1.86 * tdb - 2.1 * t)
+                };
+                w_star - w
+            },
+            dew_point,
+            tdb,
+        );
+
+        let mut result = Map::new();
+        result.insert("humidity_ratio".into(), Dynamic::from_float(w));
+        result.insert("dew_point".into(), Dynamic::from_float(dew_point));
+        result.insert("wet_bulb".into(), Dynamic::from_float(wet_bulb));
+        result.insert(
+            "enthalpy".into(),
+            Dynamic::from_float(1.006 * tdb + w * (2501.0 + 1.86 * tdb)),
+        );
+        result.insert(
+            "specific_volume".into(),
+            Dynamic::from_float(
+                0.287_042 * (tdb + 273.15) * (1.0 + 1.607_858 * w) / (pressure / 1000.0),
+            ),
+        );
+        result.insert("vapor_pressure".into(), Dynamic::from_float(vapor_pressure));
+        result.insert(
+            "saturation_pressure".into(),
+            Dynamic::from_float(saturation_pressure),
+        );
+        Ok(result)
+    }
+
+    /// Solves the ideal gas law `p V = n R T` for whichever of pressure `p` (Pa), volume `v` (m³),
+    /// amount `n` (mol), or temperature `t` (K) is missing from the object map. Exactly three of
+    /// the four must be given. Returns an object map with all four.
+    /// ```typescript
+    /// let gas = pv_nrt(#{p: 101325, n: 1, t: 273.15});
+    /// assert(abs(gas.v - 0.022414) < 1e-6);
+    /// ```
+    /// ```typescript
+    /// let gas = pv_nrt(#{p: 8.314462618, v: 1, n: 1});
+    /// assert_eq(gas.t, 1.0);
+    /// ```
+    #[rhai_fn(name = "pv_nrt", return_raw)]
+    pub fn pv_nrt(state: Map) -> Result<Map, Box<EvalAltResult>> {
+        let mut values = [None; 4];
+        for (idx, key) in ["p", "v", "n", "t"].iter().enumerate() {
+            if let Some(value) = state.get(*key) {
+                values[idx] = Some(if_int_convert_to_float_and_do(value.clone(), Ok)?);
+            }
+        }
+
+        let solved = match values {
+            [None, Some(v), Some(n), Some(t)] => [n * GAS_CONSTANT * t / v, v, n, t],
+            [Some(p), None, Some(n), Some(t)] => [p, n * GAS_CONSTANT * t / p, n, t],
+            [Some(p), Some(v), None, Some(t)] => [p, v, p * v / (GAS_CONSTANT * t), t],
+            [Some(p), Some(v), Some(n), None] => [p, v, n, p * v / (n * GAS_CONSTANT)],
+            _ => {
+                return Err(crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    "Exactly three of 'p', 'v', 'n', and 't' must be given",
+                )
+                .in_function("pv_nrt")
+                .into())
+            }
+        };
+
+        let mut result = Map::new();
+        for (key, value) in ["p", "v", "n", "t"].iter().zip(solved) {
+            result.insert((*key).into(), Dynamic::from_float(value));
+        }
+        Ok(result)
+    }
+
+    /// Computes the properties of the 1976 U.S. Standard Atmosphere at a geopotential `altitude`
+    /// (m) from 0 to 86 km. Returns an object map with the `temperature` (K), `pressure` (Pa),
+    /// `density` (kg/m³), and `speed_of_sound` (m/s).
+    /// ```typescript
+    /// let sea_level = std_atmosphere(0);
+    /// assert_eq(sea_level.temperature, 288.15);
+    /// assert_eq(sea_level.pressure, 101325.0);
+    /// assert(abs(sea_level.density - 1.225) < 1e-3);
+    /// ```
+    /// ```typescript,only_f64
+    /// let tropopause = std_atmosphere(11000);
+    /// assert(abs(tropopause.temperature - 216.65) < 1e-9);
+    /// assert(abs(tropopause.pressure - 22632) < 1);
+    /// ```
+    #[rhai_fn(name = "std_atmosphere", return_raw)]
+    pub fn std_atmosphere(altitude: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        let altitude = if_int_convert_to_float_and_do(altitude, Ok)?;
+        if !(0.0..=84_852.0).contains(&altitude) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!("The altitude must be between 0 and 84852 m, not {altitude}"),
+            )
+            .in_function("std_atmosphere")
+            .with_argument("altitude")
+            .into());
+        }
+
+        // Base altitudes and lapse rates of the layers, integrated upwards from sea level
+        const BASES: [FLOAT; 7] = [0.0, 11e3, 20e3, 32e3, 47e3, 51e3, 71e3];
+        const LAPSE_RATES: [FLOAT; 7] = [-6.5e-3, 0.0, 1e-3, 2.8e-3, 0.0, -2.8e-3, -2e-3];
+        const G0: FLOAT = 9.806_65;
+        const R_AIR: FLOAT = 287.053;
+        let (mut temperature, mut pressure) = (288.15, 101_325.0);
+        for (idx, (base, lapse)) in BASES.iter().zip(LAPSE_RATES).enumerate() {
+            let top = BASES.get(idx + 1).copied().unwrap_or(84_852.0);
+            let dh = altitude.min(top) - base;
+            if lapse == 0.0 {
+                pressure *= (-G0 * dh / (R_AIR * temperature)).exp();
+            } else {
+                let next = temperature + lapse * dh;
+                pressure *= (next / temperature).powf(-G0 / (R_AIR * lapse));
+                temperature = next;
+            }
+            if altitude <= top {
+                break;
+            }
+        }
+
+        let mut result = Map::new();
+        result.insert("temperature".into(), Dynamic::from_float(temperature));
+        result.insert("pressure".into(), Dynamic::from_float(pressure));
+        result.insert(
+            "density".into(),
+            Dynamic::from_float(pressure / (R_AIR * temperature)),
+        );
+        result.insert(
+            "speed_of_sound".into(),
+            Dynamic::from_float((1.4 * R_AIR * temperature).sqrt()),
+        );
+        Ok(result)
+    }
+
+    /// Loads a built-in property table by name, returning an object map with the `name`, the
+    /// `key` column used for lookups, and one array per column. Column names include their units.
+    /// Use `property_lookup` to interpolate in the table. The available tables are:
+    /// - `"water_saturation"`: saturated water, keyed by `temperature_c` from 0.01 to 100 °C, with
+    ///   `pressure_kpa`, `density_kg_m3`, `enthalpy_vaporization_kj_kg`, and `viscosity_pa_s`
+    /// - `"air"`: dry air at atmospheric pressure, keyed by `temperature_k` from 250 to 500 K,
+    ///   with `density_kg_m3`, `specific_heat_kj_kg_k`, `viscosity_pa_s`, `conductivity_w_m_k`,
+    ///   and `prandtl`
+    /// ```typescript
+    /// let table = property_table("water_saturation");
+    /// assert_eq(table.key, "temperature_c");
+    /// assert_eq(table.pressure_kpa[10], 101.42);
+    /// ```
+    #[rhai_fn(name = "property_table", return_raw)]
+    pub fn property_table(name: ImmutableString) -> Result<Map, Box<EvalAltResult>> {
+        let (_, columns) = super::PROPERTY_TABLES
+            .iter()
+            .find(|(table, _)| *table == name.as_str())
+            .ok_or_else(|| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::NotFound,
+                        format!(
+                            "Unknown property table '{name}'. Available tables are: {}",
+                            super::PROPERTY_TABLES
+                                .iter()
+                                .map(|(table, _)| *table)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                    .in_function("property_table")
+                    .with_argument("name"),
+                )
+            })?;
+
+        let mut result = Map::new();
+        result.insert("name".into(), Dynamic::from(name));
+        result.insert("key".into(), Dynamic::from(columns[0].0.to_string()));
+        for (column, values) in columns.iter() {
+            result.insert(
+                (*column).into(),
+                Dynamic::from_array(values.iter().map(|x| Dynamic::from_float(*x)).collect()),
+            );
+        }
+        Ok(result)
+    }
+
+    /// Linearly interpolates every column of a property table (as returned by `property_table`,
+    /// or any object map with a `key` naming an increasing column of numbers) at the key value
+    /// `x`. Returns an object map with one value per numeric column.
+    /// ```typescript,only_f64
+    /// let water = property_lookup(property_table("water_saturation"), 25);
+    /// assert_eq(water.temperature_c, 25.0);
+    /// assert(abs(water.density_kg_m3 - 996.9) < 1e-9);
+    /// ```
+    /// ```typescript
+    /// let air = property_lookup(property_table("air"), 300);
+    /// assert_eq(air.prandtl, 0.707);
+    /// ```
+    #[rhai_fn(name = "property_lookup", return_raw)]
+    pub fn property_lookup(table: Map, x: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        let x = if_int_convert_to_float_and_do(x, Ok)?;
+        let column = |name: &str| -> Result<Vec<FLOAT>, Box<EvalAltResult>> {
+            let mut arr = table
+                .get(name)
+                .and_then(|c| c.clone().try_cast::<Array>())
+                .ok_or_else(|| {
+                    rhai::EvalAltResult::from(
+                        crate::LabError::new(
+                            crate::LabErrorKind::NotFound,
+                            format!("The table has no column named '{name}'"),
+                        )
+                        .in_function("property_lookup")
+                        .with_argument("table"),
+                    )
+                })?;
+            crate::if_list_convert_to_vec_float_and_do(&mut arr, Ok)
+        };
+        let key_name = table
+            .get("key")
+            .and_then(|k| k.clone().into_string().ok())
+            .ok_or_else(|| {
+                rhai::EvalAltResult::from(
+                    crate::LabError::new(
+                        crate::LabErrorKind::InvalidArgument,
+                        "The table must name its key column with 'key'",
+                    )
+                    .in_function("property_lookup")
+                    .with_argument("table"),
+                )
+            })?;
+        let keys = column(&key_name)?;
+        if keys.len() < 2 || keys.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                "The key column must have at least two strictly increasing values",
+            )
+            .in_function("property_lookup")
+            .into());
+        }
+        if x < keys[0] || x > keys[keys.len() - 1] {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "{x} is outside the range of the table, from {} to {}",
+                    keys[0],
+                    keys[keys.len() - 1]
+                ),
+            )
+            .in_function("property_lookup")
+            .into());
+        }
+
+        // Interval containing x, and the position within it
+        let idx = keys.partition_point(|k| *k <= x).clamp(1, keys.len() - 1) - 1;
+        let t = (x - keys[idx]) / (keys[idx + 1] - keys[idx]);
+
+        let mut result = Map::new();
+        for (name, value) in table.iter() {
+            if !value.is_array() {
+                continue;
+            }
+            if let Ok(values) = column(name) {
+                if values.len() == keys.len() {
+                    let y = if t == 0.0 {
+                        values[idx]
+                    } else {
+                        values[idx] + t * (values[idx + 1] - values[idx])
+                    };
+                    result.insert(name.clone(), Dynamic::from_float(y));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,671 @@
+use rhai::plugin::*;
+
+/// Exponents of the seven SI base dimensions: length, mass, time, current, temperature, amount
+/// of substance, and luminous intensity.
+pub type Dimensions = [i8; 7];
+
+/// Symbols of the SI base units, in the same order as [`Dimensions`].
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Recognized units as `(symbol, size in SI base units, dimensions, accepts SI prefixes)`.
+const UNITS: [(&str, rhai::FLOAT, Dimensions, bool); 44] = [
+    ("m", 1.0, [1, 0, 0, 0, 0, 0, 0], true),
+    ("g", 1e-3, [0, 1, 0, 0, 0, 0, 0], true),
+    ("s", 1.0, [0, 0, 1, 0, 0, 0, 0], true),
+    ("A", 1.0, [0, 0, 0, 1, 0, 0, 0], true),
+    ("K", 1.0, [0, 0, 0, 0, 1, 0, 0], true),
+    ("mol", 1.0, [0, 0, 0, 0, 0, 1, 0], true),
+    ("cd", 1.0, [0, 0, 0, 0, 0, 0, 1], true),
+    ("Hz", 1.0, [0, 0, -1, 0, 0, 0, 0], true),
+    ("N", 1.0, [1, 1, -2, 0, 0, 0, 0], true),
+    ("Pa", 1.0, [-1, 1, -2, 0, 0, 0, 0], true),
+    ("J", 1.0, [2, 1, -2, 0, 0, 0, 0], true),
+    ("W", 1.0, [2, 1, -3, 0, 0, 0, 0], true),
+    ("C", 1.0, [0, 0, 1, 1, 0, 0, 0], true),
+    ("V", 1.0, [2, 1, -3, -1, 0, 0, 0], true),
+    ("ohm", 1.0, [2, 1, -3, -2, 0, 0, 0], true),
+    ("F", 1.0, [-2, -1, 4, 2, 0, 0, 0], true),
+    ("T", 1.0, [0, 1, -2, -1, 0, 0, 0], true),
+    ("L", 1e-3, [3, 0, 0, 0, 0, 0, 0], true),
+    ("eV", 1.602176634e-19, [2, 1, -2, 0, 0, 0, 0], true),
+    ("bar", 1e5, [-1, 1, -2, 0, 0, 0, 0], true),
+    ("min", 60.0, [0, 0, 1, 0, 0, 0, 0], false),
+    ("h", 3600.0, [0, 0, 1, 0, 0, 0, 0], false),
+    ("day", 86400.0, [0, 0, 1, 0, 0, 0, 0], false),
+    ("atm", 101325.0, [-1, 1, -2, 0, 0, 0, 0], false),
+    ("psi", 6894.757293168361, [-1, 1, -2, 0, 0, 0, 0], false),
+    ("mmHg", 133.322387415, [-1, 1, -2, 0, 0, 0, 0], false),
+    ("Torr", 101325.0 / 760.0, [-1, 1, -2, 0, 0, 0, 0], false),
+    ("in", 0.0254, [1, 0, 0, 0, 0, 0, 0], false),
+    ("ft", 0.3048, [1, 0, 0, 0, 0, 0, 0], false),
+    ("yd", 0.9144, [1, 0, 0, 0, 0, 0, 0], false),
+    ("mi", 1609.344, [1, 0, 0, 0, 0, 0, 0], false),
+    ("nmi", 1852.0, [1, 0, 0, 0, 0, 0, 0], false),
+    ("t", 1000.0, [0, 1, 0, 0, 0, 0, 0], false),
+    ("lb", 0.45359237, [0, 1, 0, 0, 0, 0, 0], false),
+    ("oz", 0.028349523125, [0, 1, 0, 0, 0, 0, 0], false),
+    ("lbf", 4.4482216152605, [1, 1, -2, 0, 0, 0, 0], false),
+    ("cal", 4.184, [2, 1, -2, 0, 0, 0, 0], true),
+    ("Wh", 3600.0, [2, 1, -2, 0, 0, 0, 0], true),
+    ("BTU", 1055.05585262, [2, 1, -2, 0, 0, 0, 0], false),
+    ("degR", 5.0 / 9.0, [0, 0, 0, 0, 1, 0, 0], false),
+    ("rad", 1.0, [0, 0, 0, 0, 0, 0, 0], false),
+    (
+        "deg",
+        std::f64::consts::PI as rhai::FLOAT / 180.0,
+        [0, 0, 0, 0, 0, 0, 0],
+        false,
+    ),
+    (
+        "rev",
+        2.0 * std::f64::consts::PI as rhai::FLOAT,
+        [0, 0, 0, 0, 0, 0, 0],
+        false,
+    ),
+    ("rpm", 1.0 / 60.0, [0, 0, -1, 0, 0, 0, 0], false),
+];
+
+/// Temperature scales as `(symbol, shift, numerator, denominator)`, where a temperature `t` on
+/// the scale is `(t + shift) * numerator / denominator` degrees Celsius. Only `convert` accounts
+/// for the offset zero of these scales.
+const TEMPERATURE_SCALES: [(&str, rhai::FLOAT, rhai::FLOAT, rhai::FLOAT); 4] = [
+    ("degC", 0.0, 1.0, 1.0),
+    ("K", -273.15, 1.0, 1.0),
+    ("degF", -32.0, 5.0, 9.0),
+    ("degR", -491.67, 5.0, 9.0),
+];
+
+/// SI prefixes and the powers of ten they stand for.
+const PREFIXES: [(&str, i32); 14] = [
+    ("T", 12),
+    ("G", 9),
+    ("M", 6),
+    ("k", 3),
+    ("h", 2),
+    ("da", 1),
+    ("d", -1),
+    ("c", -2),
+    ("m", -3),
+    ("u", -6),
+    ("µ", -6),
+    ("n", -9),
+    ("p", -12),
+    ("f", -15),
+];
+
+/// A number with physical units. The value is stored in SI base units, alongside the unit it
+/// should be shown in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quantity {
+    /// Value in SI base units
+    si: rhai::FLOAT,
+    /// Exponents of the SI base dimensions
+    dims: Dimensions,
+    /// Unit to show the value in, and its size in SI base units
+    unit: Option<(String, rhai::FLOAT)>,
+}
+
+impl Quantity {
+    /// Creates a quantity of `value` in `unit`, such as `"km/h"` or `"kg*m^2/s^2"`.
+    pub fn new(value: rhai::FLOAT, unit: &str) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let (factor, dims) = parse_unit(unit)?;
+        Ok(Quantity {
+            si: value * factor,
+            dims,
+            unit: Some((unit.trim().to_string(), factor)),
+        })
+    }
+
+    /// Creates a quantity from a value in SI base units.
+    pub fn from_si(si: rhai::FLOAT, dims: Dimensions) -> Self {
+        Quantity {
+            si,
+            dims,
+            unit: None,
+        }
+    }
+
+    /// Value in SI base units
+    pub fn si(&self) -> rhai::FLOAT {
+        self.si
+    }
+
+    /// Exponents of the SI base dimensions
+    pub fn dims(&self) -> Dimensions {
+        self.dims
+    }
+
+    /// Value in the unit that the quantity is shown in
+    pub fn value(&self) -> rhai::FLOAT {
+        match &self.unit {
+            Some((_, factor)) => self.si / factor,
+            None => self.si,
+        }
+    }
+
+    /// Unit that the quantity is shown in
+    pub fn unit(&self) -> String {
+        match &self.unit {
+            Some((unit, _)) => unit.clone(),
+            None => format_dims(&self.dims),
+        }
+    }
+
+    /// Returns the same quantity shown in another unit, which must have the same dimensions.
+    pub fn to(&self, unit: &str) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let (factor, dims) = parse_unit(unit)?;
+        check_dims(&self.dims, &dims, "convert")?;
+        Ok(Quantity {
+            si: self.si,
+            dims,
+            unit: Some((unit.trim().to_string(), factor)),
+        })
+    }
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = self.unit();
+        if unit.is_empty() {
+            write!(f, "{:?}", self.value())
+        } else {
+            write!(f, "{:?} {unit}", self.value())
+        }
+    }
+}
+
+/// Formats dimensions as a product of SI base units, such as `kg*m/s^2`.
+fn format_dims(dims: &Dimensions) -> String {
+    let part = |symbol: &str, power: i8| {
+        if power == 1 {
+            symbol.to_string()
+        } else {
+            format!("{symbol}^{power}")
+        }
+    };
+    let numerator = BASE_SYMBOLS
+        .iter()
+        .zip(dims)
+        .filter(|(_, p)| **p > 0)
+        .map(|(s, p)| part(s, *p))
+        .collect::<Vec<_>>();
+    let denominator = BASE_SYMBOLS
+        .iter()
+        .zip(dims)
+        .filter(|(_, p)| **p < 0)
+        .map(|(s, p)| part(s, -p))
+        .collect::<Vec<_>>();
+    match (numerator.is_empty(), denominator.is_empty()) {
+        (_, true) => numerator.join("*"),
+        (true, false) => format!("1/{}", denominator.join("/")),
+        (false, false) => format!("{}/{}", numerator.join("*"), denominator.join("/")),
+    }
+}
+
+/// Fails unless two quantities have the same dimensions, naming the attempted operation.
+fn check_dims(
+    a: &Dimensions,
+    b: &Dimensions,
+    operation: &str,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    if a == b {
+        Ok(())
+    } else {
+        Err(crate::LabError::new(
+            crate::LabErrorKind::DimensionMismatch,
+            format!(
+                "Cannot {operation} quantities with units of {} and {}",
+                display_dims(a),
+                display_dims(b)
+            ),
+        )
+        .into())
+    }
+}
+
+/// Formats dimensions for an error message, spelling out dimensionless quantities.
+fn display_dims(dims: &Dimensions) -> String {
+    let s = format_dims(dims);
+    if s.is_empty() {
+        "a plain number".to_string()
+    } else {
+        s
+    }
+}
+
+/// Looks up a single unit symbol, possibly with an SI prefix, returning its size in SI base
+/// units and its dimensions.
+fn lookup_unit(symbol: &str) -> Option<(rhai::FLOAT, Dimensions)> {
+    if let Some((_, factor, dims, _)) = UNITS.iter().find(|(s, ..)| *s == symbol) {
+        return Some((*factor, *dims));
+    }
+    PREFIXES.iter().find_map(|(prefix, power)| {
+        let rest = symbol.strip_prefix(prefix)?;
+        UNITS
+            .iter()
+            .find(|(s, _, _, prefixable)| *s == rest && *prefixable)
+            .map(|(_, factor, dims, _)| (factor * (10.0 as rhai::FLOAT).powi(*power), *dims))
+    })
+}
+
+/// Parses a unit expression made of unit symbols joined by `*` and `/`, each with an optional
+/// integer power such as `^2` or `^-1`. A leading `1` allows units like `1/s`.
+fn parse_unit(unit: &str) -> Result<(rhai::FLOAT, Dimensions), Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg).into()
+    };
+    let mut factor = 1.0;
+    let mut dims = [0; 7];
+    let mut sign = 1;
+    let mut term = String::new();
+    let mut apply = |term: &str, sign: i32| -> Result<(), Box<rhai::EvalAltResult>> {
+        let term = term.trim();
+        if term.is_empty() || term == "1" {
+            return Ok(());
+        }
+        let (symbol, power) = match term.split_once('^') {
+            Some((symbol, power)) => (
+                symbol.trim(),
+                power
+                    .trim()
+                    .parse::<i32>()
+                    .map_err(|_| error(format!("Invalid power in unit '{unit}'")))?,
+            ),
+            None => (term, 1),
+        };
+        let (f, d) =
+            lookup_unit(symbol).ok_or_else(|| error(format!("Unknown unit '{symbol}'")))?;
+        let power = sign * power;
+        factor *= f.powi(power);
+        for (total, d) in dims.iter_mut().zip(d) {
+            *total += d * power as i8;
+        }
+        Ok(())
+    };
+    for c in unit.chars() {
+        match c {
+            '*' | '/' => {
+                apply(&term, sign)?;
+                term.clear();
+                sign = if c == '/' { -1 } else { 1 };
+            }
+            _ => term.push(c),
+        }
+    }
+    apply(&term, sign)?;
+    Ok((factor, dims))
+}
+
+/// Converts a value from one unit to another, allowing for the offset zero of the Celsius and
+/// Fahrenheit scales.
+fn convert_units(
+    value: rhai::FLOAT,
+    from: &str,
+    to: &str,
+) -> Result<rhai::FLOAT, Box<rhai::EvalAltResult>> {
+    let scale = |unit: &str| {
+        TEMPERATURE_SCALES
+            .iter()
+            .find(|(s, ..)| *s == unit.trim())
+            .copied()
+    };
+    if let (Some((_, s1, n1, d1)), Some((_, s2, n2, d2))) = (scale(from), scale(to)) {
+        return Ok((value + s1) * (n1 * d2) / (d1 * n2) - s2);
+    }
+    // Otherwise work in SI base units, as `size * t + zero` kelvin for temperature scales
+    let resolve = |unit: &str| match scale(unit) {
+        Some((_, shift, n, d)) => Ok((n / d, shift * n / d + 273.15, [0, 0, 0, 0, 1, 0, 0])),
+        None => parse_unit(unit).map(|(size, dims)| (size, 0.0, dims)),
+    };
+    let (from_size, from_zero, from_dims) = resolve(from)?;
+    let (to_size, to_zero, to_dims) = resolve(to)?;
+    check_dims(&from_dims, &to_dims, "convert")?;
+    Ok((value * from_size + from_zero - to_zero) / to_size)
+}
+
+#[export_module]
+pub mod units_functions {
+    use rhai::{Dynamic, EvalAltResult, ImmutableString, FLOAT, INT};
+
+    /// A number with physical units, created with `quantity`.
+    pub type Quantity = crate::Quantity;
+
+    /// Creates a `Quantity` of `value` in `unit`. Units are written as symbols joined by `*` and
+    /// `/`, with optional integer powers such as `m/s^2` or `kg*m^2/s^2`. SI base and derived
+    /// units (`m`, `g`, `s`, `A`, `K`, `mol`, `cd`, `Hz`, `N`, `Pa`, `J`, `W`, `C`, `V`, `ohm`, `F`,
+    /// `T`, `L`, `eV`, `bar`, `cal`, `Wh`) accept SI prefixes from `f` to `T`, with `u` for micro.
+    /// Other units (`min`, `h`, `day`, `atm`, `psi`, `mmHg`, `Torr`, `in`, `ft`, `yd`, `mi`, `nmi`,
+    /// `t`, `lb`, `oz`, `lbf`, `BTU`, `degR`, `rad`, `deg`, `rev`, `rpm`) do not.
+    /// ```typescript
+    /// let d = quantity(1.5, "km");
+    /// assert_eq(d.value, 1.5);
+    /// assert_eq(d.unit, "km");
+    /// assert_eq(to_string(d), "1.5 km");
+    /// ```
+    /// ```typescript
+    /// let g = quantity(9.81, "m/s^2");
+    /// let f = quantity(2, "kg") * g;
+    /// assert_eq(to_string(f.to("N")), "19.62 N");
+    /// ```
+    #[rhai_fn(name = "quantity", return_raw)]
+    pub fn quantity(value: Dynamic, unit: ImmutableString) -> Result<Quantity, Box<EvalAltResult>> {
+        let value = crate::if_int_convert_to_float_and_do(value, Ok)?;
+        Quantity::new(value, &unit)
+    }
+
+    /// Returns the same `Quantity` shown in another unit. Fails if the units have different
+    /// dimensions.
+    /// ```typescript
+    /// let v = quantity(36, "km/h");
+    /// assert_eq(v.to("m/s").value, 10.0);
+    /// ```
+    /// ```typescript
+    /// let t = quantity(90, "min");
+    /// assert_eq(to(t, "h").value, 1.5);
+    /// ```
+    #[rhai_fn(name = "to", return_raw, pure)]
+    pub fn to(q: &mut Quantity, unit: ImmutableString) -> Result<Quantity, Box<EvalAltResult>> {
+        q.to(&unit)
+    }
+
+    /// Converts a number, or each number in an array, from one unit to another. Units are written
+    /// as for `quantity`, and the temperature scales `degC` and `degF` may also be used on their
+    /// own, in which case temperatures rather than temperature differences are converted. Fails if
+    /// the units have different dimensions.
+    /// ```typescript
+    /// assert_eq(convert(5, "km", "m"), 5000.0);
+    /// assert_eq(convert(2, "h", "min"), 120.0);
+    /// assert_eq(convert(180, "deg", "rev"), 0.5);
+    /// ```
+    /// ```typescript
+    /// assert_eq(convert(100, "degC", "K"), 373.15);
+    /// assert_eq(convert(-40, "degF", "degC"), -40.0);
+    /// assert_eq(convert([0, 100], "degC", "degF"), [32.0, 212.0]);
+    /// ```
+    /// ```typescript
+    /// let result = "no error";
+    /// try { convert(1, "kg", "m"); } catch { result = "error"; }
+    /// assert_eq(result, "error");
+    /// ```
+    #[rhai_fn(name = "convert", return_raw)]
+    pub fn convert(
+        value: Dynamic,
+        from: ImmutableString,
+        to: ImmutableString,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        if value.is_array() {
+            let mut arr = value.into_array().unwrap();
+            crate::if_list_do(&mut arr, |arr| {
+                arr.iter()
+                    .map(|x| {
+                        crate::if_int_convert_to_float_and_do(x.clone(), |x| {
+                            super::convert_units(x, &from, &to).map(Dynamic::from_float)
+                        })
+                    })
+                    .collect::<Result<rhai::Array, _>>()
+                    .map(Dynamic::from_array)
+            })
+        } else {
+            crate::if_int_convert_to_float_and_do(value, |x| {
+                super::convert_units(x, &from, &to).map(Dynamic::from_float)
+            })
+        }
+    }
+
+    /// Returns the value of a `Quantity` in the unit it is shown in.
+    /// ```typescript
+    /// let p = quantity(2, "bar");
+    /// assert_eq(p.value, 2.0);
+    /// assert_eq(p.to("kPa").value, 200.0);
+    /// ```
+    #[rhai_fn(get = "value", pure)]
+    pub fn value(q: &mut Quantity) -> FLOAT {
+        q.value()
+    }
+
+    /// Returns the unit that a `Quantity` is shown in. The results of arithmetic are shown in SI
+    /// base units.
+    /// ```typescript
+    /// let a = quantity(3, "m") / quantity(2, "s") / quantity(1, "s");
+    /// assert_eq(a.unit, "m/s^2");
+    /// ```
+    #[rhai_fn(get = "unit", pure)]
+    pub fn unit(q: &mut Quantity) -> ImmutableString {
+        q.unit().into()
+    }
+
+    /// Adds two quantities with the same dimensions, showing the result in the unit of the first.
+    /// ```typescript
+    /// let d = quantity(1, "km") + quantity(500, "m");
+    /// assert_eq(to_string(d), "1.5 km");
+    /// ```
+    /// ```typescript
+    /// let d = quantity(1, "m");
+    /// let t = quantity(1, "s");
+    /// let result = "no error";
+    /// try { d + t; } catch { result = "error"; }
+    /// assert_eq(result, "error");
+    /// ```
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add(a: Quantity, b: Quantity) -> Result<Quantity, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "add")?;
+        Ok(Quantity {
+            si: a.si() + b.si(),
+            ..a
+        })
+    }
+
+    /// Subtracts one quantity from another with the same dimensions, showing the result in the
+    /// unit of the first.
+    /// ```typescript
+    /// let t = quantity(2, "h") - quantity(30, "min");
+    /// assert_eq(to_string(t), "1.5 h");
+    /// ```
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract(a: Quantity, b: Quantity) -> Result<Quantity, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "subtract")?;
+        Ok(Quantity {
+            si: a.si() - b.si(),
+            ..a
+        })
+    }
+
+    /// Negates a `Quantity`.
+    #[rhai_fn(name = "-")]
+    pub fn negate(a: Quantity) -> Quantity {
+        Quantity { si: -a.si(), ..a }
+    }
+
+    /// Multiplies two quantities, combining their units.
+    /// ```typescript
+    /// let area = quantity(2, "m") * quantity(300, "cm");
+    /// assert_eq(to_string(area), "6.0 m^2");
+    /// ```
+    #[rhai_fn(name = "*")]
+    pub fn multiply(a: Quantity, b: Quantity) -> Quantity {
+        let mut dims = a.dims();
+        for (d, e) in dims.iter_mut().zip(b.dims()) {
+            *d += e;
+        }
+        Quantity::from_si(a.si() * b.si(), dims)
+    }
+
+    /// Divides one quantity by another, combining their units.
+    /// ```typescript
+    /// let v = quantity(100, "m") / quantity(8, "s");
+    /// assert_eq(to_string(v), "12.5 m/s");
+    /// ```
+    #[rhai_fn(name = "/")]
+    pub fn divide(a: Quantity, b: Quantity) -> Quantity {
+        let mut dims = a.dims();
+        for (d, e) in dims.iter_mut().zip(b.dims()) {
+            *d -= e;
+        }
+        Quantity::from_si(a.si() / b.si(), dims)
+    }
+
+    /// Scales a `Quantity` by a number, keeping its unit.
+    /// ```typescript
+    /// let d = quantity(3, "ft") * 2;
+    /// assert_eq(to_string(d), "6.0 ft");
+    /// ```
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale(a: Quantity, b: Dynamic) -> Result<Quantity, Box<EvalAltResult>> {
+        let b = crate::if_int_convert_to_float_and_do(b, Ok)?;
+        Ok(Quantity {
+            si: a.si() * b,
+            ..a
+        })
+    }
+
+    /// Scales a `Quantity` by a number, keeping its unit.
+    /// ```typescript
+    /// let d = 2 * quantity(3, "ft");
+    /// assert_eq(to_string(d), "6.0 ft");
+    /// ```
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn scale_left(a: Dynamic, b: Quantity) -> Result<Quantity, Box<EvalAltResult>> {
+        scale(b, a)
+    }
+
+    /// Divides a `Quantity` by a number, keeping its unit.
+    /// ```typescript
+    /// let d = quantity(3, "km") / 2;
+    /// assert_eq(to_string(d), "1.5 km");
+    /// ```
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide_by_number(a: Quantity, b: Dynamic) -> Result<Quantity, Box<EvalAltResult>> {
+        let b = crate::if_int_convert_to_float_and_do(b, Ok)?;
+        Ok(Quantity {
+            si: a.si() / b,
+            ..a
+        })
+    }
+
+    /// Divides a number by a `Quantity`, inverting its unit.
+    /// ```typescript
+    /// let f = 1 / quantity(4, "s");
+    /// assert_eq(to_string(f.to("Hz")), "0.25 Hz");
+    /// ```
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn number_divide(a: Dynamic, b: Quantity) -> Result<Quantity, Box<EvalAltResult>> {
+        let a = crate::if_int_convert_to_float_and_do(a, Ok)?;
+        Ok(divide(Quantity::from_si(a, [0; 7]), b))
+    }
+
+    /// Raises a `Quantity` to an integer power.
+    /// ```typescript,only_f64
+    /// let v = quantity(2, "m") ** 3;
+    /// assert_eq(to_string(v.to("L")), "8000.0 L");
+    /// ```
+    #[rhai_fn(name = "**", name = "pow", return_raw)]
+    pub fn power(a: Quantity, n: INT) -> Result<Quantity, Box<EvalAltResult>> {
+        let mut dims = a.dims();
+        for d in dims.iter_mut() {
+            *d = i8::try_from(*d as INT * n).map_err(|_| -> Box<EvalAltResult> {
+                crate::LabError::new(
+                    crate::LabErrorKind::InvalidArgument,
+                    format!("The power {n} is too large for a quantity"),
+                )
+                .in_function("**")
+                .with_argument("n")
+                .into()
+            })?;
+        }
+        Ok(Quantity::from_si(a.si().powi(n as i32), dims))
+    }
+
+    /// Returns the square root of a `Quantity`, whose dimensions must all have even powers.
+    /// ```typescript
+    /// let side = sqrt(quantity(16, "m^2"));
+    /// assert_eq(to_string(side), "4.0 m");
+    /// ```
+    #[rhai_fn(name = "sqrt", return_raw)]
+    pub fn sqrt(a: Quantity) -> Result<Quantity, Box<EvalAltResult>> {
+        let mut dims = a.dims();
+        if dims.iter().any(|d| d % 2 != 0) {
+            return Err(crate::LabError::new(
+                crate::LabErrorKind::InvalidArgument,
+                format!(
+                    "Cannot take the square root of a quantity in {}",
+                    super::format_dims(&dims)
+                ),
+            )
+            .in_function("sqrt")
+            .into());
+        }
+        for d in dims.iter_mut() {
+            *d /= 2;
+        }
+        Ok(Quantity::from_si(a.si().sqrt(), dims))
+    }
+
+    /// Returns the absolute value of a `Quantity`.
+    /// ```typescript
+    /// assert_eq(to_string(abs(quantity(-3, "N"))), "3.0 N");
+    /// ```
+    #[rhai_fn(name = "abs")]
+    pub fn abs(a: Quantity) -> Quantity {
+        Quantity {
+            si: a.si().abs(),
+            ..a
+        }
+    }
+
+    /// Tests whether two quantities with the same dimensions are equal.
+    /// ```typescript
+    /// assert(quantity(1, "km") == quantity(1000, "m"));
+    /// ```
+    #[rhai_fn(name = "==", return_raw)]
+    pub fn eq(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "compare")?;
+        Ok(a.si() == b.si())
+    }
+
+    /// Tests whether two quantities with the same dimensions differ.
+    #[rhai_fn(name = "!=", return_raw)]
+    pub fn ne(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        eq(a, b).map(|x| !x)
+    }
+
+    /// Tests whether one quantity is less than another with the same dimensions.
+    /// ```typescript
+    /// assert(quantity(1, "mi") > quantity(1, "km"));
+    /// assert(quantity(59, "s") < quantity(1, "min"));
+    /// ```
+    #[rhai_fn(name = "<", return_raw)]
+    pub fn lt(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "compare")?;
+        Ok(a.si() < b.si())
+    }
+
+    /// Tests whether one quantity is at most another with the same dimensions.
+    #[rhai_fn(name = "<=", return_raw)]
+    pub fn le(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "compare")?;
+        Ok(a.si() <= b.si())
+    }
+
+    /// Tests whether one quantity is greater than another with the same dimensions.
+    #[rhai_fn(name = ">", return_raw)]
+    pub fn gt(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "compare")?;
+        Ok(a.si() > b.si())
+    }
+
+    /// Tests whether one quantity is at least another with the same dimensions.
+    #[rhai_fn(name = ">=", return_raw)]
+    pub fn ge(a: Quantity, b: Quantity) -> Result<bool, Box<EvalAltResult>> {
+        super::check_dims(&a.dims(), &b.dims(), "compare")?;
+        Ok(a.si() >= b.si())
+    }
+
+    /// Formats a `Quantity` as its value followed by its unit.
+    /// ```typescript
+    /// assert_eq(to_string(quantity(20, "kW")), "20.0 kW");
+    /// ```
+    #[rhai_fn(name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(q: &mut Quantity) -> ImmutableString {
+        q.to_string().into()
+    }
+}
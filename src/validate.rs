@@ -1,8 +1,199 @@
 use rhai::plugin::*;
 
+/// Returns the value of a number as a float, or `None` if it is not a number.
+fn number_as_float(x: &rhai::Dynamic) -> Option<rhai::FLOAT> {
+    x.as_float()
+        .ok()
+        .or_else(|| x.as_int().ok().map(|i| i as rhai::FLOAT))
+}
+
+/// Returns the rows of a square matrix of numbers as floats, or `None` if `arr` is anything else.
+fn square_float_rows(arr: &rhai::Array) -> Option<Vec<Vec<rhai::FLOAT>>> {
+    arr.iter()
+        .map(|row| {
+            let row = row.read_lock::<rhai::Array>()?;
+            (row.len() == arr.len())
+                .then(|| row.iter().map(number_as_float).collect::<Option<Vec<_>>>())?
+        })
+        .collect()
+}
+
+/// Returns whether every element of a (possibly nested) array is a finite number.
+fn all_finite(arr: &[rhai::Dynamic]) -> bool {
+    arr.iter().all(|x| match x.read_lock::<rhai::Array>() {
+        Some(inner) => all_finite(&inner),
+        None => number_as_float(x).is_some_and(rhai::FLOAT::is_finite),
+    })
+}
+
+/// The name `validate_schema` uses for the type of a value.
+fn schema_type_name(value: &rhai::Dynamic) -> &'static str {
+    if value.is_int() {
+        "int"
+    } else if value.is_float() {
+        "float"
+    } else if value.is_string() {
+        "string"
+    } else if value.is_bool() {
+        "bool"
+    } else if value.is_array() {
+        "array"
+    } else if value.is_map() {
+        "map"
+    } else if value.is_unit() {
+        "unit"
+    } else {
+        value.type_name()
+    }
+}
+
+/// Appends the ways `map` breaks `schema` to `violations`, naming keys after `prefix` so that
+/// nested maps are reported as `outer.inner`. Fails if the schema itself is malformed.
+fn schema_violations(
+    map: &rhai::Map,
+    schema: &rhai::Map,
+    prefix: &str,
+    violations: &mut rhai::Array,
+) -> Result<(), Box<rhai::EvalAltResult>> {
+    let error = |msg: String| -> Box<rhai::EvalAltResult> {
+        crate::LabError::new(crate::LabErrorKind::InvalidArgument, msg)
+            .in_function("validate_schema")
+            .with_argument("schema")
+            .into()
+    };
+    let violation = |key: &str, kind: &str, message: String| {
+        let mut v = rhai::Map::new();
+        v.insert("key".into(), key.into());
+        v.insert("kind".into(), kind.into());
+        v.insert("message".into(), message.into());
+        rhai::Dynamic::from_map(v)
+    };
+    for (name, spec) in schema {
+        let key = format!("{prefix}{name}");
+        let spec = spec
+            .read_lock::<rhai::Map>()
+            .ok_or_else(|| error(format!("The schema for '{key}' must be a map")))?;
+        if let Some(field) = spec
+            .keys()
+            .find(|k| !["type", "required", "min", "max", "values", "fields"].contains(&k.as_str()))
+        {
+            return Err(error(format!(
+                "Unknown schema property '{field}' for '{key}'"
+            )));
+        }
+        let required = match spec.get("required") {
+            Some(r) => r
+                .as_bool()
+                .map_err(|_| error(format!("'required' for '{key}' must be true or false")))?,
+            None => true,
+        };
+        let Some(value) = map.get(name.as_str()) else {
+            if required {
+                violations.push(violation(
+                    &key,
+                    "missing",
+                    format!("Missing required key '{key}'"),
+                ));
+            }
+            continue;
+        };
+        let actual = schema_type_name(value);
+
+        if let Some(ty) = spec.get("type") {
+            let ty = ty
+                .clone()
+                .into_string()
+                .map_err(|_| error(format!("'type' for '{key}' must be a string")))?;
+            let matches = match ty.as_str() {
+                "number" => value.is_int() || value.is_float(),
+                "int" | "float" | "string" | "bool" | "array" | "map" | "unit" => actual == ty,
+                _ => return Err(error(format!("Unknown type '{ty}' for '{key}'"))),
+            };
+            if !matches {
+                violations.push(violation(
+                    &key,
+                    "type",
+                    format!("'{key}' must be {ty}, not {actual}"),
+                ));
+                continue;
+            }
+        }
+
+        let bound = |name: &str| -> Result<Option<rhai::FLOAT>, Box<rhai::EvalAltResult>> {
+            spec.get(name)
+                .map(|b| {
+                    number_as_float(b)
+                        .ok_or_else(|| error(format!("'{name}' for '{key}' must be a number")))
+                })
+                .transpose()
+        };
+        let (min, max) = (bound("min")?, bound("max")?);
+        if min.is_some() || max.is_some() {
+            match number_as_float(value) {
+                None => violations.push(violation(
+                    &key,
+                    "type",
+                    format!("'{key}' must be a number, not {actual}"),
+                )),
+                Some(x) if min.is_some_and(|min| x.is_nan() || x < min) => {
+                    violations.push(violation(
+                        &key,
+                        "range",
+                        format!("'{key}' must be at least {}, not {value}", min.unwrap()),
+                    ))
+                }
+                Some(x) if max.is_some_and(|max| x.is_nan() || x > max) => {
+                    violations.push(violation(
+                        &key,
+                        "range",
+                        format!("'{key}' must be at most {}, not {value}", max.unwrap()),
+                    ))
+                }
+                Some(_) => (),
+            }
+        }
+
+        if let Some(values) = spec.get("values") {
+            let values = values
+                .read_lock::<rhai::Array>()
+                .ok_or_else(|| error(format!("'values' for '{key}' must be an array")))?;
+            if !values
+                .iter()
+                .any(|v| format!("{v:?}") == format!("{value:?}"))
+            {
+                violations.push(violation(
+                    &key,
+                    "value",
+                    format!("'{key}' must be one of {:?}, not {value:?}", *values),
+                ));
+            }
+        }
+
+        if let Some(fields) = spec.get("fields") {
+            let fields = fields
+                .read_lock::<rhai::Map>()
+                .ok_or_else(|| error(format!("'fields' for '{key}' must be a map")))?;
+            match value.read_lock::<rhai::Map>() {
+                Some(inner) => schema_violations(&inner, &fields, &format!("{key}."), violations)?,
+                None => violations.push(violation(
+                    &key,
+                    "type",
+                    format!("'{key}' must be map, not {actual}"),
+                )),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the numbers in a list as floats, or `None` if it holds anything else.
+fn list_as_floats(arr: &rhai::Array) -> Option<Vec<rhai::FLOAT>> {
+    arr.iter().map(number_as_float).collect()
+}
+
 #[export_module]
 pub mod validation_functions {
-    use rhai::{Array, Dynamic};
+    use rhai::{Array, EvalAltResult, Map, FLOAT, INT};
 
     /// Tests whether the input in a simple list array
     /// ```typescript
@@ -79,12 +270,17 @@ pub mod validation_functions {
         };
     }
 
-    /// Tests whether the input in a simple list array composed of either floating point or integer values.
+    /// Tests whether the input in a simple list array composed of floating point values, integer
+    /// values, or a mix of the two. Functions that take lists of numbers promote a mix to floats.
     /// ```typescript
     /// let x = [1.0, 2.0, 3.0, 4.0];
     /// assert_eq(is_numeric_list(x), true)
     /// ```
     /// ```typescript
+    /// let x = [1, 2.5, 3];
+    /// assert_eq(is_numeric_list(x), true)
+    /// ```
+    /// ```typescript
     /// let x = [1, 2, 3, 4];
     /// assert_eq(is_numeric_list(x), true)
     /// ```
@@ -95,11 +291,7 @@ pub mod validation_functions {
     #[rhai_fn(name = "is_numeric_list", pure)]
     pub fn is_numeric_list(arr: &mut Array) -> bool {
         let (int, float, total) = crate::int_and_float_totals(arr);
-        if (int == total || float == total) && is_list(arr) {
-            true
-        } else {
-            false
-        }
+        int + float == total && is_list(arr)
     }
 
     /// Tests whether the input is a row vector
@@ -151,19 +343,164 @@ pub mod validation_functions {
     /// ```
     #[rhai_fn(name = "is_matrix", pure)]
     pub fn is_matrix(arr: &mut Array) -> bool {
-        if crate::matrix_functions::matrix_size_by_reference(arr).len() != 2 {
-            false
-        } else {
-            if crate::stats::prod(&mut crate::matrix_functions::matrix_size_by_reference(arr))
-                .unwrap()
-                .as_int()
-                .unwrap()
-                == crate::matrix_functions::numel_by_reference(arr)
-            {
-                true
-            } else {
-                false
+        let shape = crate::matrix_functions::matrix_size_by_reference(arr);
+        shape.len() == 2
+            && arr.iter().all(|row| {
+                row.read_lock::<Array>().is_some_and(|row| {
+                    row.len() as INT == shape[1].as_int().unwrap()
+                        && !row.iter().any(|x| x.is_array())
+                })
+            })
+    }
+
+    /// Tests whether the input is a square matrix.
+    /// ```typescript
+    /// assert_eq(is_square(eye(3)), true);
+    /// assert_eq(is_square(ones(2, 3)), false);
+    /// ```
+    #[rhai_fn(name = "is_square", pure)]
+    pub fn is_square(arr: &mut Array) -> bool {
+        is_matrix(arr)
+            && arr.iter().all(|row| {
+                row.read_lock::<Array>()
+                    .is_some_and(|row| row.len() == arr.len())
+            })
+    }
+
+    /// Tests whether the input is a square matrix of numbers that is equal to its transpose.
+    /// ```typescript
+    /// assert_eq(is_symmetric([[2, 1], [1, 3]]), true);
+    /// assert_eq(is_symmetric([[2, 1], [0, 3]]), false);
+    /// ```
+    #[rhai_fn(name = "is_symmetric", pure)]
+    pub fn is_symmetric(arr: &mut Array) -> bool {
+        is_symmetric_with_tolerance(arr, 0.0)
+    }
+
+    /// Tests whether the input is a square matrix of numbers that is equal to its transpose to
+    /// within an absolute tolerance, so that `abs(m[i][j] - m[j][i]) <= tol` for every element.
+    /// ```typescript,only_f64
+    /// let m = [[2.0, 1.0], [1.0 + 1e-12, 3.0]];
+    /// assert_eq(is_symmetric(m), false);
+    /// assert_eq(is_symmetric(m, 1e-9), true);
+    /// ```
+    #[rhai_fn(name = "is_symmetric", pure)]
+    pub fn is_symmetric_with_tolerance(arr: &mut Array, tol: FLOAT) -> bool {
+        super::square_float_rows(arr)
+            .is_some_and(|m| (0..m.len()).all(|i| (0..i).all(|j| (m[i][j] - m[j][i]).abs() <= tol)))
+    }
+
+    /// Tests whether the input is a symmetric positive definite matrix, the condition for a
+    /// Cholesky decomposition, by attempting one. Symmetry is checked to within `1e-10` times
+    /// the largest element, to allow for round-off in computed matrices.
+    /// ```typescript
+    /// assert_eq(is_positive_definite([[4, 2], [2, 3]]), true);
+    /// assert_eq(is_positive_definite([[1, 2], [2, 1]]), false);
+    /// ```
+    /// ```typescript
+    /// let x = [[1.0, 2.0], [3.0, 4.0], [5.0, 7.0]];
+    /// assert_eq(is_positive_definite(mtimes(transpose(x), x)), true);
+    /// ```
+    #[rhai_fn(name = "is_positive_definite", pure)]
+    pub fn is_positive_definite(arr: &mut Array) -> bool {
+        let Some(m) = super::square_float_rows(arr) else {
+            return false;
+        };
+        let n = m.len();
+        let scale = m
+            .iter()
+            .flatten()
+            .fold(0.0 as FLOAT, |acc, x| acc.max(x.abs()));
+        if n == 0 || !is_symmetric_with_tolerance(arr, 1e-10 * scale) {
+            return false;
+        }
+        let mut l = vec![vec![0.0 as FLOAT; n]; n];
+        for j in 0..n {
+            let d = m[j][j] - (0..j).map(|k| l[j][k] * l[j][k]).sum::<FLOAT>();
+            if !(d > 0.0 && d.is_finite()) {
+                return false;
+            }
+            l[j][j] = d.sqrt();
+            for i in j + 1..n {
+                let s = m[i][j] - (0..j).map(|k| l[i][k] * l[j][k]).sum::<FLOAT>();
+                l[i][j] = s / l[j][j];
             }
         }
+        true
+    }
+
+    /// Tests whether a list of numbers is sorted in ascending order, allowing repeated values.
+    /// A list containing `NaN` is not sorted.
+    /// ```typescript
+    /// assert_eq(is_sorted([1, 2, 2, 5.5]), true);
+    /// assert_eq(is_sorted([3, 1, 2]), false);
+    /// ```
+    #[rhai_fn(name = "is_sorted", pure)]
+    pub fn is_sorted(arr: &mut Array) -> bool {
+        super::list_as_floats(arr).is_some_and(|x| x.windows(2).all(|w| w[0] <= w[1]))
+    }
+
+    /// Tests whether a list of numbers never decreases or never increases, as the sample points
+    /// of interpolation and lookup tables must.
+    /// ```typescript
+    /// assert_eq(is_monotonic([1, 2, 2, 3]), true);
+    /// assert_eq(is_monotonic([3.0, 2.5, 0.0]), true);
+    /// assert_eq(is_monotonic([1, 3, 2]), false);
+    /// ```
+    #[rhai_fn(name = "is_monotonic", pure)]
+    pub fn is_monotonic(arr: &mut Array) -> bool {
+        super::list_as_floats(arr).is_some_and(|x| {
+            x.windows(2).all(|w| w[0] <= w[1]) || x.windows(2).all(|w| w[0] >= w[1])
+        })
+    }
+
+    /// Tests whether every element of an array (or matrix) is a finite number, so neither `NaN`
+    /// nor infinite. Integers are always finite, and any element that is not a number fails.
+    /// ```typescript
+    /// assert_eq(is_finite_all([[1, 2.5], [3, 4]]), true);
+    /// assert_eq(is_finite_all([1.0, 0.0 / 0.0]), false);
+    /// assert_eq(is_finite_all([1.0, 1.0 / 0.0]), false);
+    /// ```
+    #[rhai_fn(name = "is_finite_all", pure)]
+    pub fn is_finite_all(arr: &mut Array) -> bool {
+        super::all_finite(arr)
+    }
+
+    /// Checks a map, such as a configuration or a record read with `read_json`, against a schema
+    /// and returns an array of the problems found, which is empty if there are none. The schema
+    /// maps each expected key to a map of rules:
+    /// * `type`: one of `"int"`, `"float"`, `"number"` (either of those), `"string"`, `"bool"`,
+    ///   `"array"`, `"map"`, or `"unit"`.
+    /// * `required`: whether the key must be present, `true` by default.
+    /// * `min` and `max`: inclusive bounds for a number.
+    /// * `values`: an array of the values allowed.
+    /// * `fields`: a schema for a nested map, whose keys are reported as `outer.inner`.
+    ///
+    /// Each problem is a map with the `key` it concerns, its `kind` (`"missing"`, `"type"`,
+    /// `"range"`, or `"value"`), and a `message`. Keys that the schema does not mention are
+    /// allowed. A malformed schema is an error.
+    /// ```typescript
+    /// let schema = #{
+    ///     name: #{type: "string"},
+    ///     mode: #{type: "string", values: ["fast", "accurate"]},
+    ///     tolerance: #{type: "number", min: 0.0, max: 1.0},
+    ///     steps: #{type: "int", min: 1, required: false},
+    /// };
+    /// let problems = validate_schema(#{mode: "quick", tolerance: 2.0}, schema);
+    /// assert_eq(problems.map(|p| p.key), ["mode", "name", "tolerance"]);
+    /// assert_eq(problems.map(|p| p.kind), ["value", "missing", "range"]);
+    /// assert_eq(problems[2].message, "'tolerance' must be at most 1, not 2.0");
+    /// ```
+    /// ```typescript
+    /// let schema = #{solver: #{type: "map", fields: #{iterations: #{type: "int", min: 1}}}};
+    /// assert_eq(validate_schema(#{solver: #{iterations: 100}}, schema), []);
+    /// let problems = validate_schema(#{solver: #{iterations: 2.5}}, schema);
+    /// assert_eq(problems[0].message, "'solver.iterations' must be int, not float");
+    /// ```
+    #[rhai_fn(name = "validate_schema", return_raw)]
+    pub fn validate_schema(map: Map, schema: Map) -> Result<Array, Box<EvalAltResult>> {
+        let mut violations = Array::new();
+        super::schema_violations(&map, &schema, "", &mut violations)?;
+        Ok(violations)
     }
 }
@@ -0,0 +1,15 @@
+//! Runs every example in the documentation of the functions in this package. The tests are
+//! generated by build.rs from the function metadata, so they are only available with the
+//! `metadata` feature.
+#![cfg(feature = "metadata")]
+
+/// Evaluates a doc example, which must end with an expression that evaluates to `true`.
+fn run_example(signature: &str, code: &str) {
+    match rhai_sci::eval::<bool>(code) {
+        Ok(true) => {}
+        Ok(false) => panic!("The example for `{signature}` evaluated to false:\n{code}"),
+        Err(e) => panic!("The example for `{signature}` failed with \"{e}\":\n{code}"),
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/doc-examples.rs"));
@@ -1,2 +0,0 @@
-#[cfg(feature = "metadata")]
-include!(concat!(env!("OUT_DIR"), "/rhai-sci-tests.rs"));
\ No newline at end of file